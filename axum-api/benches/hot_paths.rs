@@ -0,0 +1,143 @@
+//! Benchmarks of the paths every client hits: logging in, listing tickets and
+//! broadcasting over WebSockets, against the in-memory backend.
+//!
+//! Requests go through the whole router, middleware included, without a socket.
+//! Run with `cargo bench`; `cargo bench -- tickets` runs a subset.
+
+use std::sync::Arc;
+
+use axum_api::{
+    api::v1::ws::{
+        connections::WsConnections,
+        protocol::{ServerMessage, project_channel},
+    },
+    create_app, create_mock_shared_state,
+    models::{Ticket, TicketEventKind},
+    schema::{ApiResponse, ListResponse, LoginRequest, LoginResponse},
+    seed::{self, Fixtures},
+    state::AppState,
+};
+use axum_test::TestServer;
+use chrono::Utc;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use tokio::runtime::Runtime;
+
+const USERNAME: &str = "bench";
+const PASSWORD: &str = "benchpassword123";
+const PROJECT: &str = "0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b";
+const TICKETS: usize = 50;
+
+/// A user who may read a project of `TICKETS` tickets.
+fn fixtures() -> Fixtures {
+    let tickets: String = (1..=TICKETS)
+        .map(|i| format!("  - {{ created_by: {USERNAME}, title: Ticket {i}, severity: 3, project: {PROJECT} }}\n"))
+        .collect();
+    Fixtures::parse(&format!(
+        "users:\n  - {{ username: {USERNAME}, password: {PASSWORD} }}\n\
+         projects:\n  - {{ id: {PROJECT}, owner: {USERNAME}, acl: [{{ permissions: READ, principals: [{USERNAME}] }}] }}\n\
+         tickets:\n{tickets}"
+    ))
+    .expect("fixtures parse")
+}
+
+fn setup(runtime: &Runtime) -> (Arc<AppState>, TestServer, String) {
+    runtime.block_on(async {
+        let state = create_mock_shared_state().expect("config loads");
+        state.startup.ready();
+        seed::load(&state, fixtures()).await.expect("fixtures load");
+        let state = Arc::new(state);
+        let server = TestServer::new(create_app(state.clone())).expect("server starts");
+        let login = server.post("/api/login").json(&login_request()).await;
+        let token = login.json::<ApiResponse<LoginResponse>>().data.token;
+        (state, server, token)
+    })
+}
+
+fn login_request() -> LoginRequest {
+    LoginRequest {
+        user: USERNAME.to_string(),
+        password: PASSWORD.to_string(),
+        remember_me: false,
+    }
+}
+
+fn login(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let (_state, server, _) = setup(&runtime);
+
+    // Dominated by bcrypt, a few samples are enough to see the rest move
+    let mut group = c.benchmark_group("login");
+    group.sample_size(10);
+    group.bench_function("password", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { server.post("/api/login").json(&login_request()).await.assert_status_ok() })
+    });
+    group.finish();
+}
+
+fn tickets(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let (state, server, token) = setup(&runtime);
+
+    let mut group = c.benchmark_group("tickets");
+    group.throughput(Throughput::Elements(TICKETS as u64));
+    group.bench_function("list", |b| {
+        b.to_async(&runtime).iter(|| async {
+            server.get("/api/v1/tickets").authorization_bearer(&token).await.assert_status_ok()
+        })
+    });
+    group.bench_function("list_fields", |b| {
+        b.to_async(&runtime).iter(|| async {
+            server
+                .get("/api/v1/tickets?fields=id,title,status")
+                .authorization_bearer(&token)
+                .await
+                .assert_status_ok()
+        })
+    });
+
+    let tickets: Vec<Ticket> = runtime.block_on(state.db.tickets().list_tickets()).unwrap();
+    let page = ListResponse::complete(tickets);
+    group.bench_function("serialize", |b| b.iter(|| serde_json::to_vec(&page).unwrap()));
+    group.finish();
+}
+
+fn ws_broadcast(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let (state, _server, _) = setup(&runtime);
+    let channel = project_channel(PROJECT);
+    let message = ServerMessage::TicketUpdated {
+        channel: channel.clone(),
+        ticket: 1,
+        kind: TicketEventKind::Moved,
+        actor: USERNAME.to_string(),
+        at: Utc::now(),
+    };
+
+    let mut group = c.benchmark_group("ws_broadcast");
+    for subscribers in [1, 10, 100] {
+        let connections = Arc::new(WsConnections::new(state.controller.clone()));
+        let mut sockets: Vec<_> = (0..subscribers)
+            .map(|i| {
+                let socket = connections.try_open(&format!("user{}", i), 1).unwrap();
+                connections.subscribe(socket.id, &channel);
+                socket
+            })
+            .collect();
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(subscribers), &subscribers, |b, _| {
+            b.iter(|| {
+                connections.broadcast(&channel, &message);
+                // What each socket's handler then writes out
+                for socket in &mut sockets {
+                    let received = socket.receiver.try_recv().unwrap();
+                    std::hint::black_box(received.to_text());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, login, tickets, ws_broadcast);
+criterion_main!(benches);
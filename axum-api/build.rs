@@ -0,0 +1,46 @@
+use std::{
+    env,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    tonic_prost_build::compile_protos("proto/tracker.proto")?;
+    build_metadata()?;
+    Ok(())
+}
+
+/// Build metadata served by `/version`.
+fn build_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    // Builds without a checkout (e.g. in a container) can pass GIT_COMMIT_HASH instead
+    let commit = env::var("GIT_COMMIT_HASH").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| String::from_utf8(out.stdout).ok())
+            .map(|hash| hash.trim().to_string())
+    });
+    println!(
+        "cargo:rustc-env=GIT_COMMIT_HASH={}",
+        commit.as_deref().unwrap_or("unknown")
+    );
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT_HASH");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", timestamp);
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=ENABLED_FEATURES={}", features.join(","));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Error response of the API (`{"error": {type, code, message, status}}`). `code`
+    /// stays the same whatever the language of `message`.
+    #[error("API error {status} ({kind}): {message}")]
+    Api {
+        status: StatusCode,
+        kind: String,
+        code: String,
+        message: String,
+    },
+
+    #[error("Not logged in")]
+    NotLoggedIn,
+
+    #[error("Two-factor code required")]
+    TwoFactorRequired,
+}
+
+impl ClientError {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Http(e) => e.status(),
+            ClientError::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
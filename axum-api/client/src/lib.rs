@@ -0,0 +1,381 @@
+//! Typed client for the axum-api HTTP API.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), axum_api_client::ClientError> {
+//! use axum_api_client::{Client, types::CreateTicket};
+//!
+//! let client = Client::new("http://localhost:3069");
+//! client.login("alice", "password", false).await?;
+//! let ticket = client
+//!     .tickets()
+//!     .create(&CreateTicket {
+//!         title: "Printer on fire".to_string(),
+//!         severity: 1,
+//!         severity_label: "critical".to_string(),
+//!         assigned_to: "facilities".to_string(),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::RwLock;
+
+use chrono::Utc;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Serialize, de::DeserializeOwned};
+
+mod error;
+pub mod me;
+mod retry;
+pub mod tickets;
+pub mod types;
+
+pub use error::ClientError;
+pub use retry::RetryPolicy;
+
+use types::{
+    ApiResponse, ErrorEnvelope, HealthStatus, LoginRequest, LoginResponse, RefreshRequest,
+    RegisterRequest, TwoFactorChallenge, TwoFactorLoginRequest,
+};
+
+/// Access tokens this close to expiry are refreshed before use.
+const REFRESH_MARGIN_SECS: usize = 30;
+
+#[derive(Debug, Clone)]
+struct Tokens {
+    access: String,
+    expires_at: usize,
+    refresh: String,
+}
+
+/// Result of the first login step.
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    LoggedIn,
+    /// The user has 2FA enabled: finish with `Client::login_two_factor`.
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    retry: RetryPolicy,
+    tokens: RwLock<Option<Tokens>>,
+}
+
+pub struct ClientBuilder {
+    base_url: String,
+    retry: RetryPolicy,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Uses a preconfigured reqwest client (timeouts, proxies, TLS).
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client {
+            http: self.http.unwrap_or_default(),
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            retry: self.retry,
+            tokens: RwLock::new(None),
+        }
+    }
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Self {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: &str) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.to_string(),
+            retry: RetryPolicy::default(),
+            http: None,
+        }
+    }
+
+    pub fn tickets(&self) -> tickets::Tickets<'_> {
+        tickets::Tickets { client: self }
+    }
+
+    pub fn me(&self) -> me::Me<'_> {
+        me::Me { client: self }
+    }
+
+    /// Current access token, if logged in.
+    pub fn access_token(&self) -> Option<String> {
+        self.tokens.read().unwrap().as_ref().map(|t| t.access.clone())
+    }
+
+    pub async fn health(&self) -> Result<HealthStatus, ClientError> {
+        let url = format!("{}/health", self.base_url);
+        let response = self
+            .execute(|| self.http.get(&url), true, false)
+            .await?;
+        parse(response).await
+    }
+
+    pub async fn register(&self, request: &RegisterRequest) -> Result<(), ClientError> {
+        let response = self
+            .execute(|| self.http.post(self.url("/register")).json(request), false, false)
+            .await?;
+        check(response).await
+    }
+
+    pub async fn login(
+        &self,
+        user: &str,
+        password: &str,
+        remember_me: bool,
+    ) -> Result<LoginOutcome, ClientError> {
+        let request = LoginRequest {
+            user,
+            password,
+            remember_me,
+        };
+        let response = self
+            .execute(|| self.http.post(self.url("/login")).json(&request), false, false)
+            .await?;
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(LoginOutcome::TwoFactorRequired(parse(response).await?));
+        }
+        self.store_tokens(parse(response).await?);
+        Ok(LoginOutcome::LoggedIn)
+    }
+
+    pub async fn login_two_factor(
+        &self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+        remember_me: bool,
+    ) -> Result<(), ClientError> {
+        let request = TwoFactorLoginRequest {
+            challenge_token: &challenge.challenge_token,
+            code,
+            remember_me,
+        };
+        let response = self
+            .execute(|| self.http.post(self.url("/login/2fa")).json(&request), false, false)
+            .await?;
+        self.store_tokens(parse(response).await?);
+        Ok(())
+    }
+
+    /// Exchanges the refresh token for a new access token.
+    pub async fn refresh(&self) -> Result<(), ClientError> {
+        let refresh_token = self
+            .tokens
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|t| t.refresh.clone())
+            .ok_or(ClientError::NotLoggedIn)?;
+        let request = RefreshRequest {
+            refresh_token: &refresh_token,
+        };
+        // Sent directly: `execute` itself refreshes, so it can't be used here
+        let response = self
+            .http
+            .post(self.url("/refresh"))
+            .json(&request)
+            .send()
+            .await?;
+        match parse(response).await {
+            Ok(tokens) => {
+                self.store_tokens(tokens);
+                Ok(())
+            }
+            Err(e) => {
+                if e.status() == Some(StatusCode::UNAUTHORIZED) {
+                    // The session is gone, the tokens are useless
+                    *self.tokens.write().unwrap() = None;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Forgets the tokens locally.
+    pub fn logout(&self) {
+        *self.tokens.write().unwrap() = None;
+    }
+
+    fn store_tokens(&self, response: LoginResponse) {
+        *self.tokens.write().unwrap() = Some(Tokens {
+            access: response.token,
+            expires_at: response.expires_at,
+            refresh: response.refresh_token,
+        });
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api{}", self.base_url, path)
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        self.send(Method::GET, path, None::<&()>, None).await
+    }
+
+    pub(crate) async fn get_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, ClientError> {
+        let url = self.url(path);
+        let response = self
+            .execute(|| self.http.get(&url).query(query), true, true)
+            .await?;
+        parse(response).await
+    }
+
+    /// Sends an authenticated request. POSTs with an `idempotency_key` are retried
+    /// like GET and DELETE, since the server replays their first response.
+    pub(crate) async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+        idempotency_key: Option<&str>,
+    ) -> Result<T, ClientError> {
+        let url = self.url(path);
+        let retryable = method == Method::GET || method == Method::DELETE || idempotency_key.is_some();
+        let response = self
+            .execute(
+                || {
+                    let mut request = self.http.request(method.clone(), &url);
+                    if let Some(body) = body {
+                        request = request.json(body);
+                    }
+                    if let Some(key) = idempotency_key {
+                        request = request.header("Idempotency-Key", key);
+                    }
+                    request
+                },
+                retryable,
+                true,
+            )
+            .await?;
+        parse(response).await
+    }
+
+    pub(crate) async fn send_empty(&self, method: Method, path: &str) -> Result<(), ClientError> {
+        let url = self.url(path);
+        let retryable = method == Method::DELETE;
+        let response = self
+            .execute(|| self.http.request(method.clone(), &url), retryable, true)
+            .await?;
+        check(response).await
+    }
+
+    /// Runs a request with the retry policy. Authenticated requests carry the access
+    /// token, refreshed ahead of expiry or once after a 401.
+    async fn execute(
+        &self,
+        build: impl Fn() -> RequestBuilder,
+        retryable: bool,
+        authenticated: bool,
+    ) -> Result<Response, ClientError> {
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            let mut request = build();
+            if authenticated {
+                if self.token_expiring() && !refreshed {
+                    self.refresh().await?;
+                    refreshed = true;
+                }
+                let token = self.access_token().ok_or(ClientError::NotLoggedIn)?;
+                request = request.bearer_auth(token);
+            }
+
+            match request.send().await {
+                Ok(response)
+                    if response.status() == StatusCode::UNAUTHORIZED
+                        && authenticated
+                        && !refreshed =>
+                {
+                    self.refresh().await?;
+                    refreshed = true;
+                    continue;
+                }
+                Ok(response)
+                    if retryable
+                        && RetryPolicy::is_transient(response.status())
+                        && attempt < self.retry.max_retries =>
+                {
+                    log::debug!("Retrying after {}", response.status());
+                }
+                Ok(response) => return Ok(response),
+                Err(e)
+                    if retryable
+                        && (e.is_connect() || e.is_timeout())
+                        && attempt < self.retry.max_retries =>
+                {
+                    log::debug!("Retrying after {}", e);
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            tokio::time::sleep(self.retry.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    fn token_expiring(&self) -> bool {
+        let now = Utc::now().timestamp().max(0) as usize;
+        self.tokens
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|t| t.expires_at <= now + REFRESH_MARGIN_SECS)
+    }
+}
+
+/// Fresh `Idempotency-Key` value.
+pub(crate) fn idempotency_key() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+async fn error(response: Response) -> ClientError {
+    let status = response.status();
+    match response.json::<ErrorEnvelope>().await {
+        Ok(envelope) => ClientError::Api {
+            status,
+            code: envelope.error.code.unwrap_or_else(|| envelope.error.r#type.clone()),
+            kind: envelope.error.r#type,
+            message: envelope.error.message,
+        },
+        Err(_) => ClientError::Api {
+            status,
+            kind: "unknown".to_string(),
+            code: "unknown".to_string(),
+            message: status.to_string(),
+        },
+    }
+}
+
+async fn parse<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    if !response.status().is_success() {
+        return Err(error(response).await);
+    }
+    Ok(response.json::<ApiResponse<T>>().await?.data)
+}
+
+async fn check(response: Response) -> Result<(), ClientError> {
+    if !response.status().is_success() {
+        return Err(error(response).await);
+    }
+    Ok(())
+}
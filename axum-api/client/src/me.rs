@@ -0,0 +1,42 @@
+use reqwest::Method;
+
+use crate::{
+    Client, ClientError,
+    types::{ListResponse, RevokedSessions, SessionInfo},
+};
+
+/// `/api/v2/me`
+pub struct Me<'a> {
+    pub(crate) client: &'a Client,
+}
+
+impl Me<'_> {
+    pub async fn sessions(&self) -> Result<Vec<SessionInfo>, ClientError> {
+        let page: ListResponse<SessionInfo> = self.client.get("/v2/me/sessions").await?;
+        Ok(page.items)
+    }
+
+    pub async fn revoke_session(&self, id: &str) -> Result<(), ClientError> {
+        self.client
+            .send_empty(Method::DELETE, &format!("/v2/me/sessions/{}", id))
+            .await
+    }
+
+    /// Revokes every session except the current one.
+    pub async fn revoke_other_sessions(&self) -> Result<usize, ClientError> {
+        let revoked: RevokedSessions = self
+            .client
+            .send(Method::DELETE, "/v2/me/sessions", None::<&()>, None)
+            .await?;
+        Ok(revoked.revoked)
+    }
+
+    /// Invalidates every token of the user, this client's included.
+    pub async fn logout_all(&self) -> Result<(), ClientError> {
+        self.client
+            .send_empty(Method::POST, "/v2/me/logout-all")
+            .await?;
+        self.client.logout();
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+/// How failed requests are retried: connection errors, timeouts and 429/502/503/504
+/// responses, with exponential backoff. Only requests that are safe to repeat are
+/// retried (GET, DELETE, and POSTs carrying an `Idempotency-Key`).
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries at all.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+
+    pub(crate) fn is_transient(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(800));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(RetryPolicy::is_transient(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!RetryPolicy::is_transient(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!RetryPolicy::is_transient(StatusCode::CONFLICT));
+    }
+}
@@ -0,0 +1,48 @@
+use reqwest::Method;
+use serde_json::Value;
+
+use crate::{
+    Client, ClientError, idempotency_key,
+    types::{CreateTicket, ListResponse, Ticket},
+};
+
+/// `/api/v1/tickets`
+pub struct Tickets<'a> {
+    pub(crate) client: &'a Client,
+}
+
+impl Tickets<'_> {
+    pub async fn list(&self) -> Result<Vec<Ticket>, ClientError> {
+        let page: ListResponse<Ticket> = self.client.get("/v1/tickets").await?;
+        Ok(page.items)
+    }
+
+    /// Tickets reduced to the given fields (`?fields=`).
+    pub async fn list_fields(&self, fields: &[&str]) -> Result<Vec<Value>, ClientError> {
+        let page: ListResponse<Value> = self
+            .client
+            .get_query("/v1/tickets", &[("fields", &fields.join(","))])
+            .await?;
+        Ok(page.items)
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Ticket, ClientError> {
+        self.client.get(&format!("/v1/tickets/{}", id)).await
+    }
+
+    /// Creates a ticket under a fresh `Idempotency-Key`, so retries never duplicate it.
+    pub async fn create(&self, ticket: &CreateTicket) -> Result<Ticket, ClientError> {
+        self.create_with_key(ticket, &idempotency_key()).await
+    }
+
+    /// Creates a ticket under a caller-chosen `Idempotency-Key`.
+    pub async fn create_with_key(
+        &self,
+        ticket: &CreateTicket,
+        key: &str,
+    ) -> Result<Ticket, ClientError> {
+        self.client
+            .send(Method::POST, "/v1/tickets", Some(ticket), Some(key))
+            .await
+    }
+}
@@ -0,0 +1,114 @@
+//! Request and response bodies, mirroring the server's `schema` module.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApiResponse<T> {
+    pub data: T,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ErrorBody {
+    pub r#type: String,
+    #[serde(default)]
+    pub code: Option<String>, // not sent by older servers
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterRequest {
+    pub user: String,
+    pub password: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LoginRequest<'a> {
+    pub user: &'a str,
+    pub password: &'a str,
+    pub remember_me: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TwoFactorLoginRequest<'a> {
+    pub challenge_token: &'a str,
+    pub code: &'a str,
+    pub remember_me: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RefreshRequest<'a> {
+    pub refresh_token: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub expires_at: usize,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwoFactorChallenge {
+    pub challenge_token: String,
+    pub expires_at: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthStatus {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RevokedSessions {
+    pub revoked: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct CreateTicket {
+    pub title: String,
+    pub severity: u8,
+    pub severity_label: String,
+    pub description: String,
+    pub assigned_to: String,
+    pub mentioned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ticket {
+    pub id: i64,
+    pub title: String,
+    pub severity: u8,
+    pub severity_label: String,
+    pub description: String,
+    pub created_by: String,
+    pub assigned_to: String,
+    pub mentioned: Vec<String>,
+    pub last_modification: DateTime<Utc>,
+    pub creation_date: DateTime<Utc>,
+}
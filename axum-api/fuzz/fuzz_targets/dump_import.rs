@@ -0,0 +1,36 @@
+#![no_main]
+
+use std::sync::LazyLock;
+
+use axum_api::{
+    db::{
+        DatabaseInterface,
+        dump::{Importer, Record},
+        inmemory::InMemoryDatabase,
+    },
+    error::AppError,
+};
+use libfuzzer_sys::fuzz_target;
+use tokio::runtime::Runtime;
+
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| Runtime::new().unwrap());
+
+/// What `/api/mgmt/import` does with a request body, into a fresh database.
+async fn import(dump: &[u8]) -> Result<usize, AppError> {
+    let db = InMemoryDatabase::new();
+    let mut importer = Importer::new(&db as &dyn DatabaseInterface).await?;
+    for (i, line) in dump.split(|b| *b == b'\n').enumerate() {
+        if let Some(record) = Record::from_line(line, i + 1)? {
+            importer.import(record).await?;
+        }
+    }
+    importer.finish()
+}
+
+fuzz_target!(|dump: &[u8]| {
+    RUNTIME.block_on(async {
+        if let Err(e) = import(dump).await {
+            assert!(!e.status_code().is_server_error(), "{} for a client's dump: {}", e.status_code(), e);
+        }
+    });
+});
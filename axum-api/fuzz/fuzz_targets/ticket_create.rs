@@ -0,0 +1,17 @@
+#![no_main]
+
+use axum_api_fuzz::{APP, assert_handled};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|body: &[u8]| {
+    APP.runtime.block_on(async {
+        let response = APP
+            .server
+            .post("/api/v1/tickets")
+            .authorization_bearer(&APP.token)
+            .content_type("application/json")
+            .bytes(body.to_vec().into())
+            .await;
+        assert_handled(&response);
+    });
+});
@@ -0,0 +1,45 @@
+#![no_main]
+
+use std::{sync::LazyLock, time::Duration};
+
+use axum_api_fuzz::APP;
+use axum_test::{TestWebSocket, WsMessage};
+use libfuzzer_sys::fuzz_target;
+use tokio::sync::Mutex;
+
+// One socket for the run, as a client would keep it open
+static SOCKET: LazyLock<Mutex<TestWebSocket>> = LazyLock::new(|| {
+    APP.runtime.block_on(async {
+        let socket = APP
+            .server
+            .get_websocket("/api/v1/ws")
+            .authorization_bearer(&APP.token)
+            .await
+            .into_websocket()
+            .await;
+        Mutex::new(socket)
+    })
+});
+
+fuzz_target!(|message: &[u8]| {
+    // Text frames are UTF-8, tungstenite won't send anything else as one
+    let Ok(text) = std::str::from_utf8(message) else {
+        return;
+    };
+    // Opened outside of the runtime, it blocks on it
+    let socket = &*SOCKET;
+    APP.runtime.block_on(async {
+        let mut socket = socket.lock().await;
+        socket.send_text(text).await;
+        // Every message is answered, an error or an echo at worst. Replies owed to
+        // earlier messages, such as a DM to oneself, may come first.
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), socket.receive_message()).await {
+                Ok(WsMessage::Text(_)) => break,
+                Ok(WsMessage::Ping(_)) => continue,
+                Ok(other) => panic!("Socket answered {:?} to {:?}", other, text),
+                Err(_) => panic!("No answer to {:?}", text),
+            }
+        }
+    });
+});
@@ -0,0 +1,68 @@
+//! Fuzz targets feeding arbitrary bytes to what the server parses from clients: JSON
+//! bodies, WebSocket messages and dump lines. Whatever comes in, the answer has to be
+//! a client error, never a panic or a 5xx.
+//!
+//! ```text
+//! cargo +nightly fuzz run register
+//! cargo +nightly fuzz run ticket_create
+//! cargo +nightly fuzz run ws_message
+//! cargo +nightly fuzz run dump_import
+//! ```
+//!
+//! Targets sending requests share one app for the whole run, backed by an in-memory
+//! database and served on a local port.
+
+use std::sync::{Arc, LazyLock};
+
+use axum_api::{
+    create_app, create_mock_shared_state,
+    schema::{ApiResponse, LoginRequest, LoginResponse},
+    seed::{self, Fixtures},
+};
+use axum_test::{TestResponse, TestServer};
+use tokio::runtime::Runtime;
+
+pub const USERNAME: &str = "fuzz";
+const PASSWORD: &str = "fuzzpassword123";
+
+const FIXTURES: &str = r#"
+users:
+  - username: fuzz
+    password: fuzzpassword123
+projects:
+  - id: 0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b
+    owner: fuzz
+"#;
+
+pub struct App {
+    pub runtime: Runtime,
+    pub server: TestServer,
+    pub token: String, // of `USERNAME`
+}
+
+pub static APP: LazyLock<App> = LazyLock::new(|| {
+    let runtime = Runtime::new().unwrap();
+    let (server, token) = runtime.block_on(async {
+        let state = create_mock_shared_state().expect("config loads");
+        state.startup.ready();
+        seed::load(&state, Fixtures::parse(FIXTURES).unwrap()).await.expect("fixtures load");
+        let server = TestServer::builder()
+            .http_transport()
+            .build(create_app(Arc::new(state)))
+            .expect("server starts");
+        let login = LoginRequest {
+            user: USERNAME.to_string(),
+            password: PASSWORD.to_string(),
+            remember_me: false,
+        };
+        let token = server.post("/api/login").json(&login).await.json::<ApiResponse<LoginResponse>>().data.token;
+        (server, token)
+    });
+    App { runtime, server, token }
+});
+
+/// Panics unless the server answered, and not with a server error.
+pub fn assert_handled(response: &TestResponse) {
+    let status = response.status_code();
+    assert!(!status.is_server_error(), "{} for a client's input: {}", status, response.text());
+}
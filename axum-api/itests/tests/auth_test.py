@@ -66,7 +66,7 @@ def auth_token(test_user):
     resp = requests.post(URL_LOGIN, json=payload, headers=headers)
     assert resp.status_code == 200, f"Login failed: {resp.text}"
 
-    data = resp.json()
+    data = resp.json()["data"]
     assert "token" in data, "No token in response"
     
     return data["token"]
@@ -101,7 +101,7 @@ def test_2_login_success(test_user):
     resp = requests.post(URL_LOGIN, json=payload, headers=headers)
     assert resp.status_code == 200, resp.text
 
-    data = resp.json()
+    data = resp.json()["data"]
     assert "token" in data
 
     main = Main(token=data["token"])
@@ -0,0 +1,524 @@
+//! Effective permissions of principals on projects, ticket groups and tickets.
+//!
+//! A project's ACL applies to everything in it. A ticket group's ACL combines with
+//! it as the group's `inheritance` says: `Extend` adds what the group grants, `Narrow`
+//! keeps only what both grant. A ticket has the permissions of its ticket group, or
+//! of its project when it is in none.
+//!
+//! Denies take precedence over grants: a permission denied to any of the principals,
+//! by the project or by the ticket group, is not held whatever grants it. A group
+//! can't lift a deny of its project.
+//!
+//! The project's owner has `ROOT` on it and everything in it, whatever the ACLs say.
+//!
+//! A ticket of no project has no ACL to go by: whoever created it and whoever it is
+//! assigned to, a user or one of their groups, have `ROOT` on it, no one else anything.
+//!
+//! A sub-project inherits from the projects above it, its ancestors: their grants and
+//! denies apply to it as if they were in its own ACL, and their owners own it too.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::{AclInheritance, Group, Permissions, Project, Ticket, TicketGroup},
+    schema::AclCacheTotals,
+};
+
+/// How long resolved memberships and permissions are served before being resolved again,
+/// should a change bypass the controllers.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How many levels of parents are followed, projects nested deeper inherit from the
+/// closest ones only.
+pub const MAX_DEPTH: usize = 100;
+
+/// Whether the walk up from the project stops before the parent `id`: it was already
+/// seen, so parents loop, or the walk is deep enough.
+fn walked(project: &Project, ancestors: &[Project], id: &str) -> bool {
+    id == project.id.to_string()
+        || ancestors.iter().any(|a| a.id.to_string() == id)
+        || ancestors.len() == MAX_DEPTH
+}
+
+/// The projects above the project, its parent first, loaded from the database. Stops
+/// at a parent that is missing.
+pub async fn ancestors(db: &dyn DatabaseInterface, project: &Project) -> Result<Vec<Project>, AppError> {
+    let mut ancestors: Vec<Project> = Vec::new();
+    let mut next = project.parent_id.clone();
+    while let Some(id) = next.take() {
+        if walked(project, &ancestors, &id) {
+            break;
+        }
+        match db.projects().get_project(&id).await {
+            Ok(parent) => {
+                next = parent.parent_id.clone();
+                ancestors.push(parent);
+            }
+            Err(AppError::NotFound(_)) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(ancestors)
+}
+
+/// The project's ancestors among already loaded projects.
+pub fn ancestors_among(projects: &[Project], project: &Project) -> Vec<Project> {
+    let mut ancestors: Vec<Project> = Vec::new();
+    let mut next = project.parent_id.clone();
+    while let Some(id) = next.take() {
+        if walked(project, &ancestors, &id) {
+            break;
+        }
+        if let Some(parent) = projects.iter().find(|p| p.id.to_string() == id) {
+            next = parent.parent_id.clone();
+            ancestors.push(parent.clone());
+        }
+    }
+    ancestors
+}
+
+/// Whether the principals are the user owning the project.
+pub fn owns(project: &Project, principals: &[String]) -> bool {
+    project.owner.as_ref().is_some_and(|owner| principals.first() == Some(owner))
+}
+
+/// Grants and denies of the principals on the project, its ancestors' included, or
+/// `None` when they own it or one of them.
+fn inherited(project: &Project, ancestors: &[Project], principals: &[String]) -> Option<(Permissions, Permissions)> {
+    let lineage = || std::iter::once(project).chain(ancestors);
+    if lineage().any(|p| owns(p, principals)) {
+        return None;
+    }
+    let grants = lineage().fold(Permissions::NONE, |acc, p| acc | p.acl.granted(principals));
+    let denies = lineage().fold(Permissions::NONE, |acc, p| acc | p.acl.denied(principals));
+    Some((grants, denies))
+}
+
+/// Permissions the principals (a user and their groups) have on the project, whose
+/// ancestors are given closest first.
+pub fn project_permissions(project: &Project, ancestors: &[Project], principals: &[String]) -> Permissions {
+    match inherited(project, ancestors, principals) {
+        Some((grants, denies)) => Permissions::effective(grants, denies),
+        None => Permissions::ROOT,
+    }
+}
+
+pub fn project_allows(
+    project: &Project,
+    ancestors: &[Project],
+    principals: &[String],
+    permission: Permissions,
+) -> bool {
+    project_permissions(project, ancestors, principals).contains(permission)
+}
+
+/// Permissions the principals have on a ticket group of the project.
+pub fn group_permissions(
+    project: &Project,
+    ancestors: &[Project],
+    group: &TicketGroup,
+    principals: &[String],
+) -> Permissions {
+    let Some((inherited, denies)) = inherited(project, ancestors, principals) else {
+        return Permissions::ROOT;
+    };
+    let own = group.acl.granted(principals);
+    let grants = match group.inheritance {
+        AclInheritance::Extend => inherited | own,
+        AclInheritance::Narrow => inherited & own,
+    };
+    Permissions::effective(grants, denies | group.acl.denied(principals))
+}
+
+/// Permissions the principals have on a ticket of the project. Tickets of a ticket
+/// group that is gone fall back to the project's.
+pub fn ticket_permissions(
+    project: &Project,
+    ancestors: &[Project],
+    ticket: &Ticket,
+    principals: &[String],
+) -> Permissions {
+    ticket_permissions_in(project, ancestors, ticket.ticket_group.as_deref(), principals)
+}
+
+/// Permissions the principals have on tickets of the project in the ticket group with
+/// the prefix, if any.
+pub fn ticket_permissions_in(
+    project: &Project,
+    ancestors: &[Project],
+    prefix: Option<&str>,
+    principals: &[String],
+) -> Permissions {
+    let group = prefix.and_then(|prefix| project.tickets.iter().find(|group| group.prefix == prefix));
+    match group {
+        Some(group) => group_permissions(project, ancestors, group, principals),
+        None => project_permissions(project, ancestors, principals),
+    }
+}
+
+/// Permissions the principals have on a ticket of no project created by and assigned
+/// to the given principals.
+pub fn unfiled_permissions(created_by: &str, assigned_to: &str, principals: &[String]) -> Permissions {
+    let creator = principals.first().is_some_and(|user| user == created_by);
+    if creator || principals.iter().any(|p| p == assigned_to) {
+        Permissions::ROOT
+    } else {
+        Permissions::NONE
+    }
+}
+
+/// Permissions the principals have on a ticket of the project with the id and of the
+/// ticket group with the prefix, going by already loaded projects. Tickets of no
+/// project go by `unfiled_permissions`, those of a deleted project are open to no one.
+pub fn ticket_permissions_among(
+    projects: &[Project],
+    project: Option<&str>,
+    prefix: Option<&str>,
+    (created_by, assigned_to): (&str, &str),
+    principals: &[String],
+) -> Permissions {
+    let Some(id) = project else {
+        return unfiled_permissions(created_by, assigned_to, principals);
+    };
+    match projects.iter().find(|p| p.id.to_string() == id) {
+        Some(project) => ticket_permissions_in(project, &ancestors_among(projects, project), prefix, principals),
+        None => Permissions::NONE,
+    }
+}
+
+pub fn ticket_allows(
+    project: &Project,
+    ancestors: &[Project],
+    ticket: &Ticket,
+    principals: &[String],
+    permission: Permissions,
+) -> bool {
+    ticket_permissions(project, ancestors, ticket, principals).contains(permission)
+}
+
+/// Users and groups the principals can see: themselves, their groups and fellow
+/// members, and whoever is named in the ACL of a project they can fetch. Groups stand
+/// for their members too.
+pub fn visible_principals(projects: &[Project], groups: &[Group], principals: &[String]) -> BTreeSet<String> {
+    let mut visible: BTreeSet<String> = principals.iter().cloned().collect();
+    for project in projects {
+        let ancestors = ancestors_among(projects, project);
+        if project_allows(project, &ancestors, principals, Permissions::FETCH) {
+            visible.extend(project.acl.list.iter().flat_map(|acl| acl.principals.iter().cloned()));
+        }
+    }
+    let members: Vec<String> = groups
+        .iter()
+        .filter(|g| visible.contains(&g.gid))
+        .flat_map(|g| g.principals.iter().cloned())
+        .collect();
+    visible.extend(members);
+    visible
+}
+
+type PermissionCache = HashMap<(Vec<String>, String), (Instant, Permissions)>;
+
+/// Per-process cache of the principals of users and of their permissions on projects.
+/// Controllers changing ACLs or group memberships invalidate what they affect.
+#[derive(Default)]
+pub struct AclCache {
+    memberships: Mutex<HashMap<String, (Instant, Vec<String>)>>, // principals by username
+    permissions: Mutex<PermissionCache>,                         // by principals and project id
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AclCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn count(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The principals of the user, as `GroupController::principals_of` resolved them.
+    pub fn principals_of(&self, username: &str) -> Option<Vec<String>> {
+        let cached = self
+            .memberships
+            .lock()
+            .unwrap()
+            .get(username)
+            .filter(|(resolved, _)| resolved.elapsed() < CACHE_TTL)
+            .map(|(_, principals)| principals.clone());
+        self.count(cached.is_some());
+        cached
+    }
+
+    pub fn store_principals(&self, username: &str, principals: &[String]) {
+        let mut memberships = self.memberships.lock().unwrap();
+        memberships.retain(|_, (resolved, _)| resolved.elapsed() < CACHE_TTL);
+        memberships.insert(username.to_string(), (Instant::now(), principals.to_vec()));
+    }
+
+    /// What the principals may do on the project.
+    pub fn permissions(&self, principals: &[String], project: &str) -> Option<Permissions> {
+        let key = (principals.to_vec(), project.to_string());
+        let cached = self
+            .permissions
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|(resolved, _)| resolved.elapsed() < CACHE_TTL)
+            .map(|(_, permissions)| *permissions);
+        self.count(cached.is_some());
+        cached
+    }
+
+    pub fn store_permissions(&self, principals: &[String], project: &str, permissions: Permissions) {
+        let mut cache = self.permissions.lock().unwrap();
+        cache.retain(|_, (resolved, _)| resolved.elapsed() < CACHE_TTL);
+        cache.insert((principals.to_vec(), project.to_string()), (Instant::now(), permissions));
+    }
+
+    /// Forgets permissions on the project, after its ACL changed.
+    pub fn invalidate_project(&self, project: &str) {
+        self.permissions.lock().unwrap().retain(|(_, id), _| id != project);
+    }
+
+    /// Forgets the user's principals and what they could do, after they joined or left a group.
+    pub fn invalidate_user(&self, username: &str) {
+        self.memberships.lock().unwrap().remove(username);
+        self.permissions
+            .lock()
+            .unwrap()
+            .retain(|(principals, _), _| !principals.iter().any(|p| p == username));
+    }
+
+    pub fn totals(&self) -> AclCacheTotals {
+        AclCacheTotals {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.memberships.lock().unwrap().len() + self.permissions.lock().unwrap().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        models::{AccessControlList, AccessControlStore},
+        test::app::{sample_project, sample_ticket},
+    };
+
+    fn acl(entries: &[(Permissions, &str)]) -> AccessControlStore {
+        AccessControlStore {
+            list: entries
+                .iter()
+                .map(|(permissions, principal)| AccessControlList {
+                    permissions: *permissions,
+                    principals: vec![principal.to_string()],
+                })
+                .collect(),
+            deny: vec![],
+            last_mod_date: Utc::now(),
+        }
+    }
+
+    fn project(inheritance: AclInheritance) -> Project {
+        let mut project = sample_project(&["reader"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["writer".to_string()],
+        });
+        project.tickets.push(TicketGroup {
+            prefix: "OPS".to_string(),
+            acl: acl(&[(Permissions::WRITE, "reader"), (Permissions::READ, "writer"), (Permissions::READ, "oncall")]),
+            inheritance,
+        });
+        project
+    }
+
+    fn principals(name: &str) -> Vec<String> {
+        vec![name.to_string()]
+    }
+
+    #[test]
+    fn groups_extend_the_project() {
+        let project = project(AclInheritance::Extend);
+        let group = &project.tickets[0];
+        assert_eq!(group_permissions(&project, &[], group, &principals("reader")), Permissions::WRITE);
+        assert_eq!(group_permissions(&project, &[], group, &principals("writer")), Permissions::WRITE);
+        assert_eq!(group_permissions(&project, &[], group, &principals("oncall")), Permissions::READ);
+    }
+
+    #[test]
+    fn groups_narrow_the_project() {
+        let project = project(AclInheritance::Narrow);
+        let group = &project.tickets[0];
+        assert_eq!(group_permissions(&project, &[], group, &principals("reader")), Permissions::READ);
+        assert_eq!(group_permissions(&project, &[], group, &principals("writer")), Permissions::READ);
+        assert_eq!(group_permissions(&project, &[], group, &principals("oncall")), Permissions::NONE);
+    }
+
+    #[test]
+    fn tickets_take_their_group_permissions() {
+        let project = project(AclInheritance::Narrow);
+        let mut ticket = sample_ticket(1, "Disk full");
+        assert!(ticket_allows(&project, &[], &ticket, &principals("writer"), Permissions::MODIFY));
+
+        ticket.ticket_group = Some("OPS".to_string());
+        assert!(!ticket_allows(&project, &[], &ticket, &principals("writer"), Permissions::MODIFY));
+
+        // The group is gone
+        ticket.ticket_group = Some("DEV".to_string());
+        assert!(ticket_allows(&project, &[], &ticket, &principals("writer"), Permissions::MODIFY));
+    }
+
+    #[test]
+    fn denies_override_grants() {
+        assert_eq!(
+            Permissions::effective(Permissions::WRITE, Permissions::MODIFY),
+            Permissions::READ | Permissions::CREATE
+        );
+        assert_eq!(Permissions::effective(Permissions::READ, Permissions::NONE), Permissions::READ);
+        assert_eq!(Permissions::effective(Permissions::READ, Permissions::ROOT), Permissions::NONE);
+        // Denying what isn't granted changes nothing
+        assert_eq!(Permissions::effective(Permissions::FETCH, Permissions::CUSTOM1), Permissions::FETCH);
+    }
+
+    #[test]
+    fn denies_to_groups_apply_to_members() {
+        let mut store = acl(&[(Permissions::WRITE, "alice")]);
+        store.deny = acl(&[(Permissions::MODIFY, "contractors")]).list;
+        let alice = principals("alice");
+        let contractor = vec!["alice".to_string(), "contractors".to_string()];
+        assert!(store.allows(&alice, Permissions::MODIFY));
+        assert!(!store.allows(&contractor, Permissions::MODIFY));
+        assert!(store.allows(&contractor, Permissions::CREATE));
+        // What a grant would give, denied or not
+        assert_eq!(store.granted(&contractor), Permissions::WRITE);
+        assert_eq!(store.effective(&contractor), Permissions::WRITE - Permissions::MODIFY);
+    }
+
+    #[test]
+    fn project_denies_reach_ticket_groups() {
+        for inheritance in [AclInheritance::Extend, AclInheritance::Narrow] {
+            let mut project = project(inheritance);
+            project.acl.deny = acl(&[(Permissions::MODIFY, "reader")]).list;
+            let group = &project.tickets[0];
+            // The group grants reader WRITE, the project's deny still wins
+            assert!(!group_permissions(&project, &[], group, &principals("reader")).contains(Permissions::MODIFY));
+            assert!(!project_allows(&project, &[], &principals("reader"), Permissions::MODIFY));
+        }
+    }
+
+    #[test]
+    fn group_denies_stay_in_the_group() {
+        let mut project = project(AclInheritance::Extend);
+        project.tickets[0].acl.deny = acl(&[(Permissions::MODIFY, "writer")]).list;
+        let mut ticket = sample_ticket(1, "Disk full");
+        ticket.ticket_group = Some("OPS".to_string());
+        assert!(!ticket_allows(&project, &[], &ticket, &principals("writer"), Permissions::MODIFY));
+        assert!(ticket_allows(&project, &[], &ticket, &principals("writer"), Permissions::CREATE));
+        assert!(project_allows(&project, &[], &principals("writer"), Permissions::MODIFY));
+    }
+
+    #[test]
+    fn cache_counts_hits_and_misses() {
+        let cache = AclCache::new();
+        let alice = principals("alice");
+        assert_eq!(cache.permissions(&alice, "p1"), None);
+        cache.store_permissions(&alice, "p1", Permissions::READ);
+        assert_eq!(cache.permissions(&alice, "p1"), Some(Permissions::READ));
+        assert_eq!(cache.principals_of("alice"), None);
+        cache.store_principals("alice", &["alice".to_string(), "ops".to_string()]);
+        assert_eq!(cache.principals_of("alice").unwrap().len(), 2);
+
+        let totals = cache.totals();
+        assert_eq!((totals.hits, totals.misses, totals.entries), (2, 2, 2));
+    }
+
+    #[test]
+    fn cache_invalidation() {
+        let cache = AclCache::new();
+        let alice = vec!["alice".to_string(), "ops".to_string()];
+        let bob = principals("bob");
+        cache.store_principals("alice", &alice);
+        cache.store_permissions(&alice, "p1", Permissions::READ);
+        cache.store_permissions(&alice, "p2", Permissions::READ);
+        cache.store_permissions(&bob, "p1", Permissions::WRITE);
+
+        cache.invalidate_project("p1");
+        assert_eq!(cache.permissions(&alice, "p1"), None);
+        assert_eq!(cache.permissions(&bob, "p1"), None);
+        assert_eq!(cache.permissions(&alice, "p2"), Some(Permissions::READ));
+
+        cache.invalidate_user("alice");
+        assert_eq!(cache.principals_of("alice"), None);
+        assert_eq!(cache.permissions(&alice, "p2"), None);
+    }
+
+    #[test]
+    fn owners_have_root() {
+        let mut project = project(AclInheritance::Narrow);
+        project.owner = Some("owner".to_string());
+        project.acl.deny = acl(&[(Permissions::ROOT, "owner")]).list;
+        let mut ticket = sample_ticket(1, "Disk full");
+        ticket.ticket_group = Some("OPS".to_string());
+        assert_eq!(project_permissions(&project, &[], &principals("owner")), Permissions::ROOT);
+        assert!(ticket_allows(&project, &[], &ticket, &principals("owner"), Permissions::ROOT));
+        // Only the owner itself, not whoever shares its name as a group
+        assert!(!owns(&project, &["alice".to_string(), "owner".to_string()]));
+    }
+
+    #[test]
+    fn sub_projects_inherit_from_ancestors() {
+        let mut root = sample_project(&["reader"]);
+        root.owner = Some("owner".to_string());
+        root.acl.deny = acl(&[(Permissions::MODIFY, "contractors")]).list;
+        let mut team = sample_project(&[]);
+        team.parent_id = Some(root.id.to_string());
+        team.acl.list = acl(&[(Permissions::WRITE, "contractors")]).list;
+        let mut sub = sample_project(&[]);
+        sub.parent_id = Some(team.id.to_string());
+
+        let projects = vec![root.clone(), team.clone(), sub.clone()];
+        let ancestors = ancestors_among(&projects, &sub);
+        assert_eq!(ancestors.iter().map(|a| a.id).collect::<Vec<_>>(), vec![team.id, root.id]);
+
+        assert_eq!(project_permissions(&sub, &ancestors, &principals("reader")), Permissions::READ);
+        assert_eq!(project_permissions(&sub, &ancestors, &principals("owner")), Permissions::ROOT);
+        // The root's deny reaches what the team grants
+        let contractor = vec!["carol".to_string(), "contractors".to_string()];
+        assert_eq!(
+            project_permissions(&sub, &ancestors, &contractor),
+            Permissions::WRITE - Permissions::MODIFY
+        );
+        // Nothing flows up
+        assert_eq!(project_permissions(&root, &[], &contractor), Permissions::NONE);
+    }
+
+    #[test]
+    fn looping_parents_end_the_walk() {
+        let mut a = sample_project(&[]);
+        let mut b = sample_project(&[]);
+        a.parent_id = Some(b.id.to_string());
+        b.parent_id = Some(a.id.to_string());
+        let ancestors = ancestors_among(&[a.clone(), b.clone()], &a);
+        assert_eq!(ancestors.len(), 1);
+        assert_eq!(ancestors[0].id, b.id);
+
+        // A missing parent ends it too
+        a.parent_id = Some("gone".to_string());
+        assert!(ancestors_among(&[a.clone(), b], &a).is_empty());
+    }
+}
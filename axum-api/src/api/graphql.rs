@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, EmptySubscription, Object, Result, Schema};
+use axum::{Json, extract::State};
+
+use crate::{
+    middleware::auth::{AuthenticatedUser, CurrentSession},
+    models::Permissions,
+    schema::{
+        CreateTicketRequest, GroupResponse, ProjectResponse, RevokedSessions, SessionInfo,
+        TicketResponse, UserResponse,
+    },
+    state::AppState,
+};
+
+pub type ApiSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Identity of the caller, taken from the JWT by the auth middleware.
+struct Caller {
+    username: String,
+    session_id: String,
+}
+
+pub fn build_schema(app_state: Arc<AppState>) -> ApiSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(app_state)
+        .finish()
+}
+
+fn request_context<'a>(ctx: &'a Context<'_>) -> (&'a AppState, &'a Caller) {
+    (
+        ctx.data_unchecked::<Arc<AppState>>(),
+        ctx.data_unchecked::<Caller>(),
+    )
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn me(&self, ctx: &Context<'_>) -> Result<UserResponse> {
+        let (app_state, caller) = request_context(ctx);
+        Ok(app_state.controller.user.get_user(&caller.username).await?.into())
+    }
+
+    async fn sessions(&self, ctx: &Context<'_>) -> Result<Vec<SessionInfo>> {
+        let (app_state, caller) = request_context(ctx);
+        let sessions = app_state
+            .controller
+            .session
+            .list_sessions(&caller.username)
+            .await?;
+        Ok(sessions
+            .into_iter()
+            .map(|s| SessionInfo::new(s, &caller.session_id))
+            .collect())
+    }
+
+    async fn groups(&self, ctx: &Context<'_>) -> Result<Vec<GroupResponse>> {
+        let (app_state, _) = request_context(ctx);
+        let groups = app_state.controller.group.list_groups().await?;
+        Ok(groups.into_iter().map(Into::into).collect())
+    }
+
+    async fn group(&self, ctx: &Context<'_>, gid: String) -> Result<GroupResponse> {
+        let (app_state, _) = request_context(ctx);
+        Ok(app_state.controller.group.get_group(&gid).await?.into())
+    }
+
+    async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<ProjectResponse>> {
+        let (app_state, caller) = request_context(ctx);
+        let principals = app_state
+            .controller
+            .group
+            .principals_of(&caller.username)
+            .await?;
+        let projects = app_state.controller.project.list_projects(&principals).await?;
+        Ok(projects.into_iter().map(Into::into).collect())
+    }
+
+    async fn project(&self, ctx: &Context<'_>, id: String) -> Result<ProjectResponse> {
+        let (app_state, caller) = request_context(ctx);
+        let principals = app_state
+            .controller
+            .group
+            .principals_of(&caller.username)
+            .await?;
+        Ok(app_state
+            .controller
+            .project
+            .get_project(&id, &principals)
+            .await?
+            .into())
+    }
+
+    async fn tickets(&self, ctx: &Context<'_>) -> Result<Vec<TicketResponse>> {
+        let (app_state, caller) = request_context(ctx);
+        let principals = app_state
+            .controller
+            .group
+            .principals_of(&caller.username)
+            .await?;
+        let tickets = app_state
+            .controller
+            .ticket
+            .tickets_for(&principals, Permissions::LIST)
+            .await?;
+        Ok(tickets.into_iter().map(Into::into).collect())
+    }
+
+    async fn ticket(&self, ctx: &Context<'_>, id: String) -> Result<TicketResponse> {
+        let (app_state, caller) = request_context(ctx);
+        let principals = app_state
+            .controller
+            .group
+            .principals_of(&caller.username)
+            .await?;
+        Ok(app_state
+            .controller
+            .ticket
+            .ticket_for(&id, &principals, Permissions::FETCH)
+            .await?
+            .into())
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Same as `POST /api/v1/tickets`.
+    async fn create_ticket(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateTicketRequest,
+    ) -> Result<TicketResponse> {
+        let (app_state, caller) = request_context(ctx);
+        let principals = app_state
+            .controller
+            .group
+            .principals_of(&caller.username)
+            .await?;
+        let ticket = app_state
+            .controller
+            .ticket
+            .create_ticket(&caller.username, &principals, input)
+            .await?;
+
+        log::info!(
+            "Ticket event -> Ticket {} created by {}",
+            ticket.id,
+            &caller.username
+        );
+
+        Ok(ticket.into())
+    }
+
+    /// Same as `DELETE /api/v1/me/sessions/{id}`.
+    async fn revoke_session(&self, ctx: &Context<'_>, id: String) -> Result<bool> {
+        let (app_state, caller) = request_context(ctx);
+        app_state
+            .controller
+            .session
+            .revoke_session(&caller.username, &id)
+            .await?;
+
+        log::info!(
+            "Session event -> User {} revoked session {}",
+            &caller.username,
+            &id
+        );
+
+        Ok(true)
+    }
+
+    /// Same as `DELETE /api/v2/me/sessions`.
+    async fn revoke_other_sessions(&self, ctx: &Context<'_>) -> Result<RevokedSessions> {
+        let (app_state, caller) = request_context(ctx);
+        let revoked = app_state
+            .controller
+            .session
+            .revoke_all_sessions(&caller.username, Some(&caller.session_id))
+            .await?;
+
+        log::info!(
+            "Session event -> User {} revoked {} other sessions",
+            &caller.username,
+            revoked
+        );
+
+        Ok(RevokedSessions { revoked })
+    }
+
+    /// Same as `POST /api/v1/me/logout-all`.
+    async fn logout_all(&self, ctx: &Context<'_>) -> Result<bool> {
+        let (app_state, caller) = request_context(ctx);
+        app_state
+            .controller
+            .user
+            .bump_token_generation(&caller.username)
+            .await?;
+
+        log::info!(
+            "Session event -> User {} logged out everywhere",
+            &caller.username
+        );
+
+        Ok(true)
+    }
+}
+
+/// `POST /api/graphql`, authenticated like the REST user routes.
+pub async fn graphql_handler(
+    State(schema): State<ApiSchema>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    CurrentSession(session_id): CurrentSession,
+    Json(req): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(
+        schema
+            .execute(req.data(Caller {
+                username,
+                session_id,
+            }))
+            .await,
+    )
+}
@@ -0,0 +1,129 @@
+use crate::{
+    controllers::{idempotency_controller::IdempotencyController, ticket_controller::EmailSender},
+    error::AppError,
+    schema::{IncomingEmail, InboundEmailResponse, JsonOk, MailgunInboundEmail},
+    state::AppState,
+    utils::{constant_time_eq, hmac_sha256_hex},
+};
+use axum::extract::{Form, State};
+use chrono::Utc;
+use std::sync::Arc;
+
+// How old a signed request may be, so captured ones can't be replayed later
+const MAX_SIGNATURE_AGE_SECS: i64 = 15 * 60;
+
+/// `<id@host>` to `id@host`, as message ids are stored.
+fn message_ids(header: Option<&str>) -> Vec<String> {
+    header
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|id| id.trim_start_matches('<').trim_end_matches('>').to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+fn verify_signature(key: &str, email: &MailgunInboundEmail) -> Result<(), AppError> {
+    let invalid = || AppError::Authorization("Invalid webhook signature".to_string());
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    let timestamp: i64 = email.timestamp.parse().map_err(|_| invalid())?;
+    if (Utc::now().timestamp() - timestamp).abs() > MAX_SIGNATURE_AGE_SECS {
+        return Err(invalid());
+    }
+    let expected = hmac_sha256_hex(key.as_bytes(), format!("{}{}", email.timestamp, email.token).as_bytes());
+    if !constant_time_eq(&expected, &email.signature) {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Whether the provider authenticated the sender's domain, by SPF or DKIM.
+fn sender_authenticated(email: &MailgunInboundEmail) -> bool {
+    [&email.spf, &email.dkim]
+        .iter()
+        .any(|result| result.as_deref().is_some_and(|r| r.trim().eq_ignore_ascii_case("pass")))
+}
+
+async fn sender_of(app_state: &AppState, email: &MailgunInboundEmail) -> Result<EmailSender, AppError> {
+    let address = email.sender.trim().to_string();
+    if !sender_authenticated(email) {
+        return Ok(EmailSender::External(address));
+    }
+    Ok(match app_state.controller.user.find_by_email(&address).await? {
+        Some(user) => EmailSender::User {
+            principals: app_state.controller.group.principals_of(&user.username).await?,
+            username: user.username,
+        },
+        None => EmailSender::External(address),
+    })
+}
+
+/// Mailgun inbound route target. A reply to an email already filed (by `In-Reply-To`
+/// or `References`) is added as a comment on its ticket, any other email is filed as a
+/// ticket in the configured project. The sender is the author when no user has their
+/// address, or when the provider couldn't authenticate it (`X-Mailgun-Spf` and
+/// `X-Mailgun-Dkim-Check-Result`). Each signature is accepted once, so a captured
+/// request can't be replayed while its timestamp is recent. Not found unless
+/// `INBOUND_EMAIL_PROJECT` is set.
+#[utoipa::path(
+    post,
+    path = "/api/inbound/email",
+    tag = "inbound",
+    request_body(content = MailgunInboundEmail, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, body = InboundEmailResponse)),
+)]
+pub async fn inbound_email(
+    State(app_state): State<Arc<AppState>>,
+    Form(email): Form<MailgunInboundEmail>,
+) -> Result<JsonOk<InboundEmailResponse>, AppError> {
+    let Some(project) = app_state.config.inbound_email_project.as_deref() else {
+        return Err(AppError::NotFound("Inbound email is not enabled".to_string()));
+    };
+    verify_signature(&app_state.config.inbound_email_signing_key, &email)?;
+    // Kept past the age a signature is accepted at, either side of our clock
+    let nonce = IdempotencyController::record_id("mailgun", &email.token);
+    let idempotency = &app_state.controller.idempotency;
+    if !idempotency.claim_once(&nonce, 2 * MAX_SIGNATURE_AGE_SECS as usize).await? {
+        log::warn!(target: "audit", "Replayed inbound email webhook rejected");
+        return Err(AppError::Authorization("Invalid webhook signature".to_string()));
+    }
+    let sender = match sender_of(&app_state, &email).await {
+        Ok(sender) => sender,
+        Err(e) => {
+            idempotency.abandon(&nonce).await?;
+            return Err(e);
+        }
+    };
+
+    let mut replied_to = message_ids(email.in_reply_to.as_deref());
+    for id in message_ids(email.references.as_deref()) {
+        if !replied_to.contains(&id) {
+            replied_to.push(id);
+        }
+    }
+    let incoming = IncomingEmail {
+        sender: email.sender.trim().to_string(),
+        subject: email.subject,
+        body: email.stripped_text.filter(|s| !s.trim().is_empty()).unwrap_or(email.body_plain),
+        message_id: message_ids(email.message_id.as_deref()).into_iter().next(),
+        replied_to,
+    };
+    let (ticket, comment, filed) = match app_state.controller.ticket.ingest_email(incoming, sender, project).await {
+        Ok(filed) => filed,
+        Err(e) => {
+            // Retried deliveries come with the same signature
+            idempotency.abandon(&nonce).await?;
+            return Err(e);
+        }
+    };
+    if filed && comment.is_none() {
+        log::info!("Ticket event -> Ticket {} created from email by {}", ticket.id, ticket.created_by);
+    }
+
+    Ok(JsonOk(InboundEmailResponse {
+        ticket: ticket.id,
+        comment: comment.map(|c| c.id),
+        filed,
+    }))
+}
@@ -0,0 +1,64 @@
+use crate::{
+    error::AppError,
+    models::ChatChannel,
+    schema::{ChatChannelRequest, JsonOk, NoContent},
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use std::sync::Arc;
+
+/// Every project's chat channel.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/chat-channels",
+    tag = "mgmt",
+    responses((status = 200, body = Vec<ChatChannel>)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn list_chat_channels(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<Vec<ChatChannel>>, AppError> {
+    let channels = app_state.controller.chat.channels().await?;
+    Ok(JsonOk(channels))
+}
+
+/// Posts the project's selected events to a Slack or Teams incoming webhook,
+/// replacing its previous channel.
+#[utoipa::path(
+    put,
+    path = "/api/mgmt/chat-channels/{project}",
+    tag = "mgmt",
+    params(("project" = String, Path, description = "Project id")),
+    request_body = ChatChannelRequest,
+    responses((status = 200, body = ChatChannel)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn set_chat_channel(
+    State(app_state): State<Arc<AppState>>,
+    Path(project): Path<String>,
+    Json(req): Json<ChatChannelRequest>,
+) -> Result<JsonOk<ChatChannel>, AppError> {
+    let channel = app_state.controller.chat.set_channel(&project, req).await?;
+
+    log::info!("Mgmt event -> Chat channel of project {} set to {:?}", &project, channel.platform);
+
+    Ok(JsonOk(channel))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/chat-channels/{project}",
+    tag = "mgmt",
+    params(("project" = String, Path, description = "Project id")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn remove_chat_channel(
+    State(app_state): State<Arc<AppState>>,
+    Path(project): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.chat.remove_channel(&project).await?;
+
+    log::info!("Mgmt event -> Chat channel of project {} removed", &project);
+
+    Ok(NoContent)
+}
@@ -0,0 +1,69 @@
+use crate::{
+    db::dump::{self, Importer, Record},
+    error::AppError,
+    schema::{ImportedRecords, JsonOk, Ndjson},
+    state::AppState,
+};
+use axum::{body::Body, extract::State};
+use std::sync::Arc;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+
+/// Streams the whole database as NDJSON, one record per line, ending with an `end`
+/// record. The dump holds password hashes and other secrets, keep it safe.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/export",
+    tag = "mgmt",
+    responses(Ndjson),
+    security(("mgmt_token" = [])),
+)]
+pub async fn export(State(app_state): State<Arc<AppState>>) -> Ndjson {
+    let lines = ReceiverStream::new(dump::export(app_state.db.clone())).map(|record| {
+        let mut line = serde_json::to_vec(&record?)?;
+        line.push(b'\n');
+        Ok::<_, AppError>(line)
+    });
+
+    log::warn!(target: "audit", "Mgmt event -> Database export started");
+
+    Ndjson(Body::from_stream(lines))
+}
+
+/// Restores a dump made by `/api/mgmt/export` into an empty database. A dump cut
+/// short is rejected once its end is reached, what it held up to there stays.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/import",
+    tag = "mgmt",
+    request_body(content = String, content_type = "application/x-ndjson"),
+    responses((status = 200, body = ImportedRecords)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn import(State(app_state): State<Arc<AppState>>, body: Body) -> Result<JsonOk<ImportedRecords>, AppError> {
+    let mut importer = Importer::new(app_state.db.as_ref()).await?;
+    let mut chunks = body.into_data_stream();
+    let mut pending = Vec::new();
+    let mut line = 0;
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| AppError::Validation(format!("Dump could not be read: {}", e)))?;
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+            line += 1;
+            let text: Vec<u8> = pending.drain(..=end).collect();
+            import_line(&mut importer, &text, line).await?;
+        }
+    }
+    import_line(&mut importer, &pending, line + 1).await?;
+    let imported = importer.finish()?;
+
+    log::warn!(target: "audit", "Mgmt event -> Database restored from a dump of {} records", imported);
+
+    Ok(JsonOk(ImportedRecords { imported }))
+}
+
+async fn import_line(importer: &mut Importer<'_>, text: &[u8], line: usize) -> Result<(), AppError> {
+    match Record::from_line(text, line)? {
+        Some(record) => importer.import(record).await,
+        None => Ok(()),
+    }
+}
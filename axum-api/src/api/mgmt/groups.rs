@@ -0,0 +1,32 @@
+use crate::{
+    error::AppError,
+    schema::{JsonOk, RenamePrincipalRequest, RenamePrincipalResponse},
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use std::sync::Arc;
+
+/// Renames a group. Its members, tickets and ACL entries follow; the old id stays
+/// taken by an alias without members, so that invites made before still join it.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/groups/{id}/rename",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Group id")),
+    request_body = RenamePrincipalRequest,
+    responses((status = 200, body = RenamePrincipalResponse)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn rename_group(
+    State(app_state): State<Arc<AppState>>,
+    Path(gid): Path<String>,
+    Json(req): Json<RenamePrincipalRequest>,
+) -> Result<JsonOk<RenamePrincipalResponse>, AppError> {
+    let renamed = app_state.controller.principal.rename_group(&gid, &req.to).await?;
+    log::warn!(
+        target: "audit",
+        "Rename -> Group {} renamed to {}: {} tickets, {} groups, {} projects",
+        renamed.from, renamed.to, renamed.tickets, renamed.groups, renamed.projects
+    );
+    Ok(JsonOk(renamed))
+}
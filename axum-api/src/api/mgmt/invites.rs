@@ -0,0 +1,48 @@
+use crate::{
+    error::AppError,
+    schema::{CreateInviteRequest, CreateInviteResponse, JsonCreated},
+    state::AppState,
+};
+use axum::extract::{Json, State};
+use chrono::Duration;
+use std::sync::Arc;
+
+const DEFAULT_INVITE_LIFETIME_HOURS: i64 = 72;
+
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/invites",
+    tag = "mgmt",
+    request_body = CreateInviteRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replays the first response on retries")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn create_invite(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<CreateInviteRequest>,
+) -> Result<JsonCreated<CreateInviteResponse>, AppError> {
+    let hours = req
+        .expires_in_hours
+        .unwrap_or(DEFAULT_INVITE_LIFETIME_HOURS);
+    if hours <= 0 {
+        return Err(AppError::Validation(
+            "expires_in_hours must be positive".to_string(),
+        ));
+    }
+
+    let (token, invite) = app_state
+        .controller
+        .invite
+        .create_invite(req.groups, Duration::hours(hours))
+        .await?;
+
+    log::info!(
+        "Mgmt event -> Invite created, expires at {}",
+        invite.expires_at
+    );
+
+    Ok(JsonCreated(CreateInviteResponse {
+        token,
+        expires_at: invite.expires_at,
+    }))
+}
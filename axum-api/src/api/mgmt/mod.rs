@@ -0,0 +1,11 @@
+pub mod chat_channels;
+pub mod dump;
+pub mod groups;
+pub mod invites;
+pub mod outbox;
+pub mod security_events;
+pub mod seed;
+pub mod service_accounts;
+pub mod stats;
+pub mod trash;
+pub mod users;
\ No newline at end of file
@@ -0,0 +1,71 @@
+use crate::{
+    db::OutboxFilter,
+    error::AppError,
+    models::OutboxEntry,
+    schema::{JsonOk, ReplayOutboxRequest, ReplayedEntries},
+    state::AppState,
+};
+use axum::extract::{Json, Path, Query, State};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Lists stored domain events, oldest first. Only filled with `OUTBOX=true`.
+/// Filters: `status`, `since` (RFC 3339), plus `limit`.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/outbox",
+    tag = "mgmt",
+    params(OutboxFilter),
+    responses((status = 200, body = Vec<OutboxEntry>)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn list_outbox(
+    State(app_state): State<Arc<AppState>>,
+    Query(mut filter): Query<OutboxFilter>,
+) -> Result<JsonOk<Vec<OutboxEntry>>, AppError> {
+    filter.limit = Some(filter.limit.unwrap_or(DEFAULT_LIMIT));
+    let entries = app_state.controller.outbox.entries(&filter).await?;
+    Ok(JsonOk(entries))
+}
+
+/// Delivers an entry again on the next dispatch, also if it was delivered or given up.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/outbox/{id}/replay",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Outbox entry id")),
+    responses((status = 200, body = OutboxEntry)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn replay_entry(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<JsonOk<OutboxEntry>, AppError> {
+    let entry = app_state.controller.outbox.replay(&id).await?;
+
+    log::info!("Mgmt event -> Outbox entry {} queued for replay", &id);
+
+    Ok(JsonOk(entry))
+}
+
+/// Delivers every entry created since the given time again, for subscribers that
+/// missed or lost them.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/outbox/replay",
+    tag = "mgmt",
+    request_body = ReplayOutboxRequest,
+    responses((status = 200, body = ReplayedEntries)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn replay_since(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<ReplayOutboxRequest>,
+) -> Result<JsonOk<ReplayedEntries>, AppError> {
+    let replayed = app_state.controller.outbox.replay_since(req.since).await?;
+
+    log::info!("Mgmt event -> {} outbox entries since {} queued for replay", replayed, req.since);
+
+    Ok(JsonOk(ReplayedEntries { replayed }))
+}
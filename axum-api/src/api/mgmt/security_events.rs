@@ -0,0 +1,43 @@
+use crate::{
+    db::SecurityEventFilter,
+    error::AppError,
+    middleware::conditional::Preconditions,
+    models::SecurityEvent,
+    schema::{Conditional, ListResponse, PageParams},
+    state::AppState,
+};
+use axum::extract::{Query, State};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// Lists recorded security events, newest first.
+/// Filters: `kind`, `username`, `ip`, `since` (RFC 3339), plus `limit` and `cursor` for paging.
+/// Dashboards can poll with `If-None-Match` and get a 304 while nothing new was recorded.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/security-events",
+    tag = "mgmt",
+    params(SecurityEventFilter, PageParams),
+    security(("mgmt_token" = [])),
+)]
+pub async fn list_security_events(
+    State(app_state): State<Arc<AppState>>,
+    preconditions: Preconditions,
+    Query(mut filter): Query<SecurityEventFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Conditional<ListResponse<SecurityEvent>>, AppError> {
+    filter.limit = Some(page.limit_or(DEFAULT_LIMIT, MAX_LIMIT));
+    filter.cursor = page.cursor;
+    let (items, total, next_cursor) = app_state.controller.security.list_events(&filter).await?;
+    let last_modified = items.first().map(|e| e.created_at);
+    preconditions.evaluate(
+        ListResponse {
+            items,
+            total,
+            next_cursor,
+        },
+        last_modified,
+    )
+}
@@ -0,0 +1,29 @@
+use crate::{
+    error::AppError,
+    schema::JsonOk,
+    seed::{self, Fixtures, SeedReport},
+    state::AppState,
+};
+use axum::extract::{Json, State};
+use std::sync::Arc;
+
+/// Loads users, groups, projects and tickets, skipping those already there. See
+/// `seed` for the checks applied.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/seed",
+    tag = "mgmt",
+    request_body = Fixtures,
+    responses((status = 200, body = SeedReport)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn load_fixtures(
+    State(app_state): State<Arc<AppState>>,
+    Json(fixtures): Json<Fixtures>,
+) -> Result<JsonOk<SeedReport>, AppError> {
+    let report = seed::load(&app_state, fixtures).await?;
+
+    log::info!("Mgmt event -> Fixtures loaded: {:?}", report);
+
+    Ok(JsonOk(report))
+}
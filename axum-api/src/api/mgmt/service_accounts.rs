@@ -0,0 +1,126 @@
+use crate::{
+    error::AppError,
+    schema::{
+        CreateApiTokenRequest, CreateApiTokenResponse, CreateServiceAccountRequest, JsonCreated, JsonOk, NoContent,
+        ServiceAccountResponse,
+    },
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use std::sync::Arc;
+
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/service-accounts",
+    tag = "mgmt",
+    security(("mgmt_token" = [])),
+)]
+pub async fn list_service_accounts(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<Vec<ServiceAccountResponse>>, AppError> {
+    let accounts = app_state.controller.service_account.list().await?;
+    Ok(JsonOk(accounts.into_iter().map(ServiceAccountResponse::from).collect()))
+}
+
+/// Creates an account for CI and other automation. It can't log in with a password,
+/// it acts through the API tokens issued to it and is granted access like any user.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/service-accounts",
+    tag = "mgmt",
+    request_body = CreateServiceAccountRequest,
+    security(("mgmt_token" = [])),
+)]
+pub async fn create_service_account(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<CreateServiceAccountRequest>,
+) -> Result<JsonCreated<ServiceAccountResponse>, AppError> {
+    let account = app_state
+        .controller
+        .service_account
+        .create(&req.username, req.name)
+        .await?;
+
+    log::info!("Mgmt event -> Service account {} created", &account.username);
+
+    Ok(JsonCreated(account.into()))
+}
+
+/// Deactivates a service account and revokes all of its tokens.
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/service-accounts/{id}",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username of the service account")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn deactivate_service_account(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.service_account.deactivate(&user_id).await?;
+
+    log::warn!(target: "audit", "Mgmt event -> Service account {} deactivated", &user_id);
+
+    Ok(NoContent)
+}
+
+/// Issues a non-expiring token limited to the given scopes, such as `tickets:write`
+/// or `*:read`. The token is only shown in this response.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/service-accounts/{id}/tokens",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username of the service account")),
+    request_body = CreateApiTokenRequest,
+    security(("mgmt_token" = [])),
+)]
+pub async fn issue_api_token(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(req): Json<CreateApiTokenRequest>,
+) -> Result<JsonCreated<CreateApiTokenResponse>, AppError> {
+    let (token, api_token) = app_state
+        .controller
+        .service_account
+        .issue_token(&user_id, &req.name, req.scopes)
+        .await?;
+
+    log::warn!(
+        target: "audit",
+        "Mgmt event -> Token '{}' issued to service account {}",
+        api_token.name,
+        &user_id
+    );
+
+    Ok(JsonCreated(CreateApiTokenResponse {
+        id: api_token.id,
+        token,
+        scopes: api_token.scopes.iter().map(ToString::to_string).collect(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/service-accounts/{id}/tokens/{token_id}",
+    tag = "mgmt",
+    params(
+        ("id" = String, Path, description = "Username of the service account"),
+        ("token_id" = String, Path, description = "Token id"),
+    ),
+    security(("mgmt_token" = [])),
+)]
+pub async fn revoke_api_token(
+    State(app_state): State<Arc<AppState>>,
+    Path((user_id, token_id)): Path<(String, String)>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .service_account
+        .revoke_token(&user_id, &token_id)
+        .await?;
+
+    log::warn!(target: "audit", "Mgmt event -> Token {} of service account {} revoked", &token_id, &user_id);
+
+    Ok(NoContent)
+}
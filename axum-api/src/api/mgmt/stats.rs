@@ -0,0 +1,30 @@
+use crate::{
+    error::AppError,
+    schema::{AdminStatsResponse, JsonOk},
+    state::AppState,
+};
+use axum::extract::State;
+use std::sync::Arc;
+
+/// Totals of users, groups, projects and tickets, open WebSockets and the database backend,
+/// how busy the database concurrency limit is and the state of its circuit breaker.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/stats",
+    tag = "mgmt",
+    security(("mgmt_token" = [])),
+)]
+pub async fn admin_stats(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<AdminStatsResponse>, AppError> {
+    let ws = &app_state.ws_connections;
+    let acl_cache = app_state.controller.acl_cache.totals();
+    let database_limit = app_state.db_limit.as_ref().map(|limit| limit.stats());
+    let database_breaker = app_state.db_breaker.as_ref().map(|breaker| breaker.stats());
+    let stats = app_state
+        .controller
+        .stats
+        .overview(ws.total(), ws.totals(), acl_cache, database_limit, database_breaker)
+        .await?;
+    Ok(JsonOk(stats))
+}
@@ -0,0 +1,70 @@
+use crate::{
+    error::AppError,
+    schema::{DeletedEntity, DeletedKind, JsonOk, NoContent},
+    state::AppState,
+};
+use axum::extract::{Path, State};
+use chrono::TimeDelta;
+use std::sync::Arc;
+
+fn retention(app_state: &AppState) -> TimeDelta {
+    TimeDelta::days(i64::from(app_state.config.soft_delete_retention))
+}
+
+/// Deleted users, groups, projects and tickets, oldest deletion first. Each can be
+/// restored until its `purge_at`, `SOFT_DELETE_RETENTION_DAYS` after its deletion.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/trash",
+    tag = "mgmt",
+    responses((status = 200, body = Vec<DeletedEntity>)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn list_deleted(State(app_state): State<Arc<AppState>>) -> Result<JsonOk<Vec<DeletedEntity>>, AppError> {
+    let entities = app_state.controller.trash.entities(retention(&app_state)).await?;
+    Ok(JsonOk(entities))
+}
+
+/// Takes a deleted entity out of the trash. Fails with 404 once its retention is over.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/trash/{kind}/{id}/restore",
+    tag = "mgmt",
+    params(
+        ("kind" = DeletedKind, Path, description = "user, group, project or ticket"),
+        ("id" = String, Path, description = "Username, group, project or ticket id"),
+    ),
+    security(("mgmt_token" = [])),
+)]
+pub async fn restore_deleted(
+    State(app_state): State<Arc<AppState>>,
+    Path((kind, id)): Path<(DeletedKind, String)>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.trash.restore(kind, &id, retention(&app_state)).await?;
+
+    log::info!("Mgmt event -> Deleted {:?} {} restored", kind, &id);
+
+    Ok(NoContent)
+}
+
+/// Removes a deleted entity for good, before its retention is over.
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/trash/{kind}/{id}",
+    tag = "mgmt",
+    params(
+        ("kind" = DeletedKind, Path, description = "user, group, project or ticket"),
+        ("id" = String, Path, description = "Username, group, project or ticket id"),
+    ),
+    security(("mgmt_token" = [])),
+)]
+pub async fn purge_deleted(
+    State(app_state): State<Arc<AppState>>,
+    Path((kind, id)): Path<(DeletedKind, String)>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.trash.remove(kind, &id, retention(&app_state)).await?;
+
+    log::info!("Mgmt event -> Deleted {:?} {} removed for good", kind, &id);
+
+    Ok(NoContent)
+}
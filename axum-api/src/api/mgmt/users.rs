@@ -0,0 +1,229 @@
+use crate::{
+    error::AppError,
+    schema::{
+        JsonOk, MergeUsersRequest, MergeUsersResponse, MetadataQuery, MetadataValueRequest, NoContent,
+        ReencryptedValues, RenamePrincipalRequest, RenamePrincipalResponse, UserMetadataResponse,
+    },
+    state::AppState,
+};
+use axum::extract::{Json, Path, Query, State};
+use std::sync::Arc;
+
+/// Users with a metadata key, or with the key set to a value, by username. Handy to
+/// find beta users or those imported from elsewhere. Service accounts only with
+/// `service_accounts=true`.
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/users",
+    tag = "mgmt",
+    params(MetadataQuery),
+    security(("mgmt_token" = [])),
+)]
+pub async fn find_users(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<MetadataQuery>,
+) -> Result<JsonOk<Vec<UserMetadataResponse>>, AppError> {
+    let users = app_state
+        .controller
+        .user
+        .find_by_metadata(&query.key, query.value.as_deref(), query.service_accounts)
+        .await?;
+    Ok(JsonOk(
+        users
+            .into_iter()
+            .map(|u| UserMetadataResponse {
+                username: u.username,
+                metadata: u.metadata,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/mgmt/users/{id}/metadata",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn user_metadata(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> Result<JsonOk<UserMetadataResponse>, AppError> {
+    let metadata = app_state.controller.user.metadata(&user_id).await?;
+    Ok(JsonOk(UserMetadataResponse {
+        username: user_id,
+        metadata,
+    }))
+}
+
+/// Sets a metadata key of a user. Keys are lowercased, values are limited in size.
+#[utoipa::path(
+    put,
+    path = "/api/mgmt/users/{id}/metadata/{key}",
+    tag = "mgmt",
+    params(
+        ("id" = String, Path, description = "Username"),
+        ("key" = String, Path, description = "Metadata key"),
+    ),
+    request_body = MetadataValueRequest,
+    security(("mgmt_token" = [])),
+)]
+pub async fn set_user_metadata(
+    State(app_state): State<Arc<AppState>>,
+    Path((user_id, key)): Path<(String, String)>,
+    Json(req): Json<MetadataValueRequest>,
+) -> Result<JsonOk<UserMetadataResponse>, AppError> {
+    let metadata = app_state
+        .controller
+        .user
+        .set_metadata(&user_id, &key, req.value)
+        .await?;
+
+    log::info!("Mgmt event -> Metadata '{}' of user {} set", key, &user_id);
+
+    Ok(JsonOk(UserMetadataResponse {
+        username: user_id,
+        metadata,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/users/{id}/metadata/{key}",
+    tag = "mgmt",
+    params(
+        ("id" = String, Path, description = "Username"),
+        ("key" = String, Path, description = "Metadata key"),
+    ),
+    security(("mgmt_token" = [])),
+)]
+pub async fn remove_user_metadata(
+    State(app_state): State<Arc<AppState>>,
+    Path((user_id, key)): Path<(String, String)>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.user.remove_metadata(&user_id, &key).await?;
+
+    log::info!("Mgmt event -> Metadata '{}' of user {} removed", key, &user_id);
+
+    Ok(NoContent)
+}
+
+/// Encrypts the metadata of every user under the current encryption key, plain
+/// values of encrypted keys included. Run after rotating the key, before the
+/// previous one is dropped from `ENCRYPTION_KEYS`.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/users/reencrypt-metadata",
+    tag = "mgmt",
+    responses((status = 200, body = ReencryptedValues)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn reencrypt_metadata(State(app_state): State<Arc<AppState>>) -> Result<JsonOk<ReencryptedValues>, AppError> {
+    let reencrypted = app_state.controller.user.reencrypt_metadata().await?;
+
+    log::warn!(target: "audit", "Mgmt event -> {} user metadata values re-encrypted", reencrypted);
+
+    Ok(JsonOk(ReencryptedValues { reencrypted }))
+}
+
+/// Disables 2FA for a user who lost both their authenticator and recovery codes.
+#[utoipa::path(
+    delete,
+    path = "/api/mgmt/users/{id}/2fa",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn reset_two_factor(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.two_factor.reset(&user_id).await?;
+
+    log::info!("Mgmt event -> 2FA reset for user {}", &user_id);
+
+    Ok(NoContent)
+}
+
+/// Merges a duplicate account into the one kept, e.g. after a user registered before
+/// being imported: their tickets, comments, group memberships, ACL entries and projects
+/// move to the target, and the source is deactivated and logged out.
+///
+/// The merge is not atomic: merges and renames run one at a time, but while one runs
+/// other requests may see it half done. A failed merge puts back what it changed.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/users/merge",
+    tag = "mgmt",
+    request_body = MergeUsersRequest,
+    responses((status = 200, body = MergeUsersResponse)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn merge_users(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<MergeUsersRequest>,
+) -> Result<JsonOk<MergeUsersResponse>, AppError> {
+    let merged = app_state
+        .controller
+        .principal
+        .merge_users(&req.source, &req.target)
+        .await?;
+    app_state.controller.user.bump_token_generation(&req.source).await?;
+    log::warn!(
+        target: "audit",
+        "Merge -> User {} merged into {}: {} tickets, {} comments, {} groups, {} projects",
+        merged.source, merged.target, merged.tickets, merged.comments, merged.groups, merged.projects
+    );
+    Ok(JsonOk(merged))
+}
+
+/// Renames a user. Their tickets, comments, memberships, ACL entries and sessions
+/// follow; the old username stays taken by an alias, so that the user's tokens from
+/// before keep working until they expire.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/users/{id}/rename",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username")),
+    request_body = RenamePrincipalRequest,
+    responses((status = 200, body = RenamePrincipalResponse)),
+    security(("mgmt_token" = [])),
+)]
+pub async fn rename_user(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Json(req): Json<RenamePrincipalRequest>,
+) -> Result<JsonOk<RenamePrincipalResponse>, AppError> {
+    let renamed = app_state.controller.principal.rename_user(&user_id, &req.to).await?;
+    app_state.controller.session.validated().forget_user(&user_id, None);
+    log::warn!(
+        target: "audit",
+        "Rename -> User {} renamed to {}: {} tickets, {} comments, {} groups, {} projects, {} sessions",
+        renamed.from, renamed.to, renamed.tickets, renamed.comments, renamed.groups, renamed.projects, renamed.sessions
+    );
+    Ok(JsonOk(renamed))
+}
+
+/// Invalidates every outstanding token of a user.
+#[utoipa::path(
+    post,
+    path = "/api/mgmt/users/{id}/logout-all",
+    tag = "mgmt",
+    params(("id" = String, Path, description = "Username")),
+    security(("mgmt_token" = [])),
+)]
+pub async fn logout_all(
+    State(app_state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .user
+        .bump_token_generation(&user_id)
+        .await?;
+
+    log::info!("Mgmt event -> All tokens of user {} invalidated", &user_id);
+
+    Ok(NoContent)
+}
@@ -1 +1,8 @@
-pub mod v1;
\ No newline at end of file
+pub mod mgmt;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod inbound;
+pub mod public;
+pub mod v1;
+pub mod v2;
+pub mod versions;
\ No newline at end of file
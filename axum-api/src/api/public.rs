@@ -0,0 +1,98 @@
+use crate::{
+    error::AppError,
+    schema::{JsonCreated, JsonOk, PublicTicketRequest, PublicTicketResponse, SharedTicketQuery, TicketResponse},
+    state::AppState,
+    utils::{client_ip, constant_time_eq, sha256_hex},
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::HeaderMap,
+};
+use std::sync::Arc;
+
+/// The submission route, as rate limit rules see it.
+pub const PORTAL_SUBMISSION_PATH: &str = "/api/public/projects/{slug}/tickets";
+
+/// Files a ticket in the project whose public portal has the slug, without an account.
+/// The ticket is created by the portal's reporter, `portal:<slug>`. Depending on the
+/// portal, the submission must carry its token and a captcha solution. Submissions
+/// are limited per IP by `PUBLIC_PORTAL_RATE_LIMIT`, 5 an hour unless set.
+#[utoipa::path(
+    post,
+    path = "/api/public/projects/{slug}/tickets",
+    tag = "public",
+    params(("slug" = String, Path, description = "Slug of the project's portal")),
+    request_body = PublicTicketRequest,
+    responses((status = 201, body = PublicTicketResponse)),
+)]
+pub async fn submit_ticket(
+    State(app_state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<PublicTicketRequest>,
+) -> Result<JsonCreated<PublicTicketResponse>, AppError> {
+    let (project, portal) = app_state.controller.project.portal_project(&slug).await?;
+    let ip = client_ip(&headers);
+
+    if let Some(hash) = &portal.token_hash {
+        let given = req.token.as_deref().map(sha256_hex).unwrap_or_default();
+        if !constant_time_eq(&given, hash) {
+            log::warn!("Public event -> Portal {} refused a submission from {:?}: bad token", slug, ip);
+            return Err(AppError::Authorization("Invalid portal token".to_string()));
+        }
+    }
+    if portal.captcha {
+        let solution = req.captcha.as_deref().unwrap_or_default();
+        if solution.is_empty() || !app_state.captcha.verify(solution, ip.as_deref()).await? {
+            return Err(AppError::BadRequest("Captcha not solved".to_string()));
+        }
+    }
+
+    let ticket = app_state.controller.ticket.file_public(&project, &portal, req).await?;
+
+    log::info!(
+        "Public event -> Ticket {} filed through portal {} from {:?}",
+        ticket.id,
+        slug,
+        ip
+    );
+
+    Ok(JsonCreated(PublicTicketResponse { ticket: ticket.id }))
+}
+
+/// The ticket a share link grants, read-only and without an account. The link is
+/// authenticated by its token, from `POST /api/v1/tickets/{id}/share`, and stops
+/// working once it expires or is revoked. Every read is recorded in the audit log.
+#[utoipa::path(
+    get,
+    path = "/api/public/tickets/shared",
+    tag = "public",
+    params(SharedTicketQuery),
+    responses((status = 200, body = TicketResponse)),
+)]
+pub async fn shared_ticket(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<SharedTicketQuery>,
+    headers: HeaderMap,
+) -> Result<JsonOk<TicketResponse>, AppError> {
+    let ip = client_ip(&headers);
+    let share = match app_state
+        .controller
+        .share
+        .resolve(&query.token, app_state.config.jwt_secret.as_bytes())
+        .await
+    {
+        Ok(share) => share,
+        Err(e) => {
+            log::warn!(target: "audit", "Share -> Refused a shared ticket read from {:?}: {}", ip, e);
+            return Err(e);
+        }
+    };
+    let ticket = app_state.controller.ticket.ticket(&share.ticket.to_string()).await?;
+    log::warn!(
+        target: "audit",
+        "Share -> Ticket {} read through share {} from {:?}",
+        ticket.id, share.id, ip
+    );
+    Ok(JsonOk(ticket.into()))
+}
@@ -1,19 +1,78 @@
 use crate::{
+    controllers::{
+        two_factor_controller::TwoFactorController,
+        user_controller::{Authentication, RegistrationRules},
+    },
     error::AppError,
-    schema::{Created, LoginRequest, LoginResponse, RegisterRequest, User},
+    middleware::auth::{EMAIL_VERIFICATION_LIFETIME, TWO_FACTOR_CHALLENGE_LIFETIME},
+    models::{self, SecurityEventKind},
+    notifier::Notification,
+    schema::{
+        Created, JsonOk, LoginOutcome, LoginRequest, LoginResponse, NoContent, RefreshRequest,
+        RegisterRequest, TwoFactorChallenge, TwoFactorLoginRequest, VerifyEmailRequest,
+    },
     state::AppState,
-    validation::naming::validate_username,
+    utils::{client_ip, user_agent},
 };
 use axum::{
-    extract::{Json, State},
-    response::IntoResponse,
+    extract::{Json, Path, State},
+    http::HeaderMap,
 };
+use chrono::{Duration, Utc};
 use std::sync::Arc;
 
+const TWO_FACTOR_PURPOSE: &str = "2fa";
+const EMAIL_VERIFICATION_PURPOSE: &str = "email_verification";
+const REFRESH_PURPOSE: &str = "refresh";
+
+/// Builds a user from a registration request, under the configured email rules.
+fn new_user(app_state: &AppState, req: &RegisterRequest) -> Result<models::User, AppError> {
+    let config = &app_state.config;
+    app_state.controller.user.new_user(
+        &req.user,
+        &req.password,
+        req.email.as_deref(),
+        RegistrationRules {
+            allowed_email_domains: &config.allowed_email_domains,
+            require_email: config.require_email_verification,
+        },
+    )
+}
+
+/// Sends an email verification token to the user, if they registered with an email.
+async fn send_email_verification(app_state: &AppState, username: &str, email: Option<String>) {
+    let Some(email) = email else {
+        return;
+    };
+
+    let result = match app_state.auth.create_scoped_token(
+        username,
+        EMAIL_VERIFICATION_PURPOSE,
+        EMAIL_VERIFICATION_LIFETIME,
+    ) {
+        Ok((token, _)) => {
+            app_state
+                .notifier
+                .send(Notification {
+                    recipient: email,
+                    subject: "Verify your email".to_string(),
+                    body: format!("Your email verification token: {}", token),
+                })
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        log::warn!("Failed to send email verification to {}: {}", username, e);
+    }
+}
+
 #[utoipa::path(
-    get,
+    post,
     path = "/api/register",
-    responses((status = 201, description = "Create user successfully"))
+    tag = "auth",
+    request_body = RegisterRequest,
 )]
 pub async fn register(
     State(app_state): State<Arc<AppState>>,
@@ -25,49 +84,267 @@ pub async fn register(
         ));
     }
 
-    let hashed_password = app_state.auth.hash_password(&req.password)?;
-
-    let user = User {
-        username: validate_username(&req.user).map_err(AppError::Validation)?,
-        password_hash: hashed_password,
-    };
+    let user = new_user(&app_state, &req)?;
 
     let uid = user.username.clone();
+    let email = user.email.clone();
 
-    app_state.db.users().create_user(user.into()).await?;
+    app_state.controller.user.register(user).await?;
 
-    log::info!(
-        "Register event -> {}",
-        format!("User with ID {:?} created: {}", &uid, &req.user)
-    );
+    log::info!("Register event -> User with ID {:?} created: {}", &uid, &req.user);
+
+    send_email_verification(&app_state, &uid, email).await;
 
     Ok(Created{})
 }
 
+/// Registration through a single-use invite, allowed even when open registration is off.
+#[utoipa::path(
+    post,
+    path = "/api/register/invite/{token}",
+    tag = "auth",
+    params(("token" = String, Path, description = "Invite token")),
+    request_body = RegisterRequest,
+)]
+pub async fn register_with_invite(
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Json(req): Json<RegisterRequest>,
+) -> Result<Created, AppError> {
+    let user = new_user(&app_state, &req)?;
+
+    let uid = user.username.clone();
+    let email = user.email.clone();
+
+    app_state.controller.invite.redeem(&token, user).await?;
+
+    log::info!("Register event -> User {} created from invite", &uid);
+
+    send_email_verification(&app_state, &uid, email).await;
+
+    Ok(Created {})
+}
+
+/// Marks the user's email as verified using the token sent on registration.
+#[utoipa::path(
+    post,
+    path = "/api/verify-email",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+)]
+pub async fn verify_email(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<NoContent, AppError> {
+    let claims = app_state
+        .auth
+        .decode_scoped_token(&req.token, EMAIL_VERIFICATION_PURPOSE)
+        .map_err(|_e| AppError::Authorization("Invalid verification token".to_string()))?;
+
+    app_state.controller.user.mark_email_verified(&claims.sub).await?;
+
+    log::info!("Register event -> User {} verified email", &claims.sub);
+
+    Ok(NoContent)
+}
+
+/// Opens a session for the user and issues an access token bound to it,
+/// plus a refresh token that lives as long as the session.
+async fn issue_token(
+    app_state: &AppState,
+    username: &str,
+    headers: &HeaderMap,
+    remember_me: bool,
+) -> Result<LoginResponse, AppError> {
+    let config = &app_state.config;
+    let session_lifetime = if remember_me {
+        config.remember_me_lifetime
+    } else {
+        config.refresh_token_lifetime
+    };
+    let expires_at = Utc::now() + Duration::seconds(session_lifetime as i64);
+    let generation = app_state.controller.user.token_generation(username).await?;
+    let session_id = app_state
+        .controller
+        .session
+        .start_session(
+            username,
+            user_agent(headers),
+            client_ip(headers),
+            expires_at,
+            generation,
+        )
+        .await?;
+
+    let (token, token_expires_at) = app_state.auth.create_token(
+        username,
+        &session_id,
+        generation,
+        config.access_token_lifetime.min(session_lifetime),
+    )?;
+    let (refresh_token, _) =
+        app_state
+            .auth
+            .create_scoped_token(&session_id, REFRESH_PURPOSE, session_lifetime)?;
+
+    log::info!("Auth event -> User logged in: {}", username);
+
+    Ok(LoginResponse {
+        token,
+        expires_at: token_expires_at,
+        refresh_token,
+    })
+}
+
+/// Records a failed login attempt and returns the generic error for the client.
+async fn login_failed(
+    app_state: &AppState,
+    username: &str,
+    headers: &HeaderMap,
+    detail: &str,
+) -> AppError {
+    app_state
+        .controller
+        .security
+        .record(
+            SecurityEventKind::LoginFailure,
+            Some(username),
+            client_ip(headers),
+            detail,
+            &app_state.config.security_alert,
+        )
+        .await;
+    AppError::Authorization("Unauthorized".to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    request_body = LoginRequest,
+)]
 pub async fn login(
     State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    let user = app_state
-        .db
-        .users()
-        .get_user(&req.user)
+) -> Result<LoginOutcome, AppError> {
+    let user = match app_state
+        .controller
+        .user
+        .authenticate(&req.user, &req.password, app_state.config.require_email_verification)
+        .await?
+    {
+        Authentication::Authenticated(user) => user,
+        Authentication::Rejected(detail) => {
+            return Err(login_failed(&app_state, &req.user, &headers, detail).await);
+        }
+    };
+
+    if TwoFactorController::is_enabled(&user) {
+        let (challenge_token, expires_at) = app_state.auth.create_scoped_token(
+            &user.username,
+            TWO_FACTOR_PURPOSE,
+            TWO_FACTOR_CHALLENGE_LIFETIME,
+        )?;
+        return Ok(LoginOutcome::Challenge(TwoFactorChallenge {
+            challenge_token,
+            expires_at,
+        }));
+    }
+
+    Ok(LoginOutcome::Token(
+        issue_token(&app_state, &user.username, &headers, req.remember_me).await?,
+    ))
+}
+
+/// Second login step for users with 2FA: exchanges a challenge token and a TOTP
+/// (or recovery) code for an access token.
+#[utoipa::path(
+    post,
+    path = "/api/login/2fa",
+    tag = "auth",
+    request_body = TwoFactorLoginRequest,
+)]
+pub async fn login_two_factor(
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<TwoFactorLoginRequest>,
+) -> Result<JsonOk<LoginResponse>, AppError> {
+    let claims = app_state
+        .auth
+        .decode_scoped_token(&req.challenge_token, TWO_FACTOR_PURPOSE)
+        .map_err(|_e| AppError::Authorization("Unauthorized".to_string()))?;
+
+    match app_state
+        .controller
+        .two_factor
+        .verify_challenge(&claims.jti, claims.exp, &claims.sub, &req.code)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            log::warn!("Invalid 2FA code for {}", &claims.sub);
+            return Err(login_failed(&app_state, &claims.sub, &headers, "Invalid 2FA code").await);
+        }
+        Err(AppError::Authorization(_)) => {
+            return Err(login_failed(&app_state, &claims.sub, &headers, "2FA challenge spent").await);
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(JsonOk(
+        issue_token(&app_state, &claims.sub, &headers, req.remember_me).await?,
+    ))
+}
+
+/// Exchanges a refresh token for a new access token within the same session.
+#[utoipa::path(
+    post,
+    path = "/api/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+)]
+pub async fn refresh(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<JsonOk<LoginResponse>, AppError> {
+    let claims = app_state
+        .auth
+        .decode_scoped_token(&req.refresh_token, REFRESH_PURPOSE)
+        .map_err(|_e| AppError::Authorization("Unauthorized".to_string()))?;
+
+    let session = app_state
+        .controller
+        .session
+        .get_session(&claims.sub)
         .await
         .map_err(|_e| AppError::Authorization("Unauthorized".to_string()))?;
 
     if !app_state
-        .auth
-        .verify_password(&req.password, &user.password_hash)?
+        .controller
+        .session
+        .validate_session(&session.id, &session.username)
+        .await?
+        || !app_state
+            .controller
+            .user
+            .validate_user(&session.username, session.token_generation)
+            .await?
     {
         return Err(AppError::Authorization("Unauthorized".to_string()));
     }
 
-    let token = app_state.auth.create_token(&user.username)?;
-
-    log::info!(
-        "Auth event -> {}",
-        format!("User logged in: {}", &user.username)
-    );
+    let remaining = (session.expires_at - Utc::now()).num_seconds().max(0) as usize;
+    let (token, expires_at) = app_state.auth.create_token(
+        &session.username,
+        &session.id,
+        session.token_generation,
+        app_state.config.access_token_lifetime.min(remaining),
+    )?;
 
-    Ok(Json(LoginResponse { token: token.0 }))
+    Ok(JsonOk(LoginResponse {
+        token,
+        expires_at,
+        refresh_token: req.refresh_token,
+    }))
 }
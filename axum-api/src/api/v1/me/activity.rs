@@ -0,0 +1,33 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::Activity,
+    schema::{ActivityQuery, JsonOk, ListResponse, PageParams},
+    state::AppState,
+};
+use axum::extract::{Query, State};
+use std::sync::Arc;
+
+/// Tickets the current user created, changed the status of and commented on,
+/// newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/activity",
+    tag = "me",
+    params(ActivityQuery, PageParams),
+    security(("bearer_auth" = [])),
+)]
+pub async fn my_activity(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ActivityQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<JsonOk<ListResponse<Activity>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&user_id).await?;
+    let feed = app_state
+        .controller
+        .activity
+        .user_feed(&user_id, &principals, &query, &page)
+        .await?;
+    Ok(JsonOk(feed))
+}
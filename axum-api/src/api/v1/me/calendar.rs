@@ -0,0 +1,112 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::{MilestoneState, TicketStatus},
+    schema::{CalendarQuery, CalendarTokenResponse, ICalendar, JsonCreated, NoContent},
+    state::AppState,
+    utils::{
+        ical::{Event, calendar},
+        time::{local_instant, user_timezone},
+    },
+};
+use axum::extract::{Query, State};
+use chrono::{NaiveTime, Utc};
+use std::sync::Arc;
+
+const CALENDAR_PATH: &str = "/api/v1/me/calendar.ics";
+
+// Calendars remind of due tickets at this hour of the due date, in the user's timezone
+const REMINDER_HOUR: u32 = 9;
+
+/// Issues the token of the user's calendar feed, the previous one stops working.
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/calendar-token",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn issue_calendar_token(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonCreated<CalendarTokenResponse>, AppError> {
+    let token = app_state.controller.user.issue_calendar_token(&user_id).await?;
+    log::info!("Calendar event -> Feed token issued for {}", &user_id);
+    Ok(JsonCreated(CalendarTokenResponse {
+        url: format!("{}?token={}", CALENDAR_PATH, token),
+        token,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/calendar-token",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_calendar_token(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.user.revoke_calendar_token(&user_id).await?;
+    log::info!("Calendar event -> Feed token revoked for {}", &user_id);
+    Ok(NoContent)
+}
+
+/// iCalendar feed of the due dates of the user's unresolved tickets (assigned to
+/// them or their groups) and the end dates of open milestones of their projects.
+/// Calendar apps can't send headers, so the feed is authenticated by the token
+/// from `POST /api/v1/me/calendar-token` in the URL. Tickets come with a reminder on
+/// the morning of their due date in the user's timezone.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/calendar.ics",
+    tag = "me",
+    params(CalendarQuery),
+)]
+pub async fn calendar_feed(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<ICalendar, AppError> {
+    let user = app_state.controller.user.calendar_owner(&query.token).await?;
+    let principals = app_state.controller.group.principals_of(&user.username).await?;
+    let timezone = user_timezone(&user);
+    let reminder_time = NaiveTime::from_hms_opt(REMINDER_HOUR, 0, 0).unwrap_or_default();
+
+    let mut events = Vec::new();
+    for ticket in app_state.controller.ticket.tickets().await? {
+        let Some(date) = ticket.due_date else {
+            continue;
+        };
+        if !principals.contains(&ticket.assigned_to)
+            || matches!(ticket.status, TicketStatus::Resolved | TicketStatus::Closed)
+        {
+            continue;
+        }
+        events.push(Event {
+            uid: format!("ticket-{}@startemplates", ticket.id),
+            date,
+            summary: format!("Due: #{} {}", ticket.id, ticket.title),
+            description: format!("Severity: {}", ticket.severity.label),
+            reminder: Some(local_instant(date, reminder_time, timezone)),
+        });
+    }
+    for project in app_state.controller.project.list_projects(&principals).await? {
+        let milestones = app_state
+            .controller
+            .milestone
+            .list(&project.id.to_string(), &principals)
+            .await?;
+        for milestone in milestones.into_iter().filter(|m| m.state == MilestoneState::Open) {
+            events.push(Event {
+                uid: format!("milestone-{}@startemplates", milestone.id),
+                date: milestone.end,
+                summary: format!("Milestone ends: {}", milestone.name),
+                description: format!("From {} to {}", milestone.start, milestone.end),
+                reminder: None,
+            });
+        }
+    }
+    events.sort_by(|a, b| (a.date, &a.uid).cmp(&(b.date, &b.uid)));
+
+    Ok(ICalendar(calendar(&format!("Deadlines of {}", user.username), timezone, &events, Utc::now())))
+}
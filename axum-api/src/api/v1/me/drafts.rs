@@ -0,0 +1,70 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::Draft,
+    schema::{JsonOk, NoContent},
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Saves the ticket being written, any JSON object, under a key of the client's
+/// choosing. Drafts expire `DRAFT_TTL` seconds after their last save; name one in
+/// `draft` when creating the ticket and it is discarded.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/drafts/{key}",
+    tag = "me",
+    params(("key" = String, Path, description = "Draft key")),
+    request_body = Value,
+    responses((status = 200, body = Draft)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn save_draft(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Json(payload): Json<Value>,
+) -> Result<JsonOk<Draft>, AppError> {
+    let config = &app_state.config;
+    let draft = app_state
+        .controller
+        .draft
+        .save(&user_id, &key, payload, config.draft_ttl, config.draft_max_bytes)
+        .await?;
+    Ok(JsonOk(draft))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/drafts/{key}",
+    tag = "me",
+    params(("key" = String, Path, description = "Draft key")),
+    responses((status = 200, body = Draft)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_draft(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Result<JsonOk<Draft>, AppError> {
+    let draft = app_state.controller.draft.get(&user_id, &key).await?;
+    Ok(JsonOk(draft))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/drafts/{key}",
+    tag = "me",
+    params(("key" = String, Path, description = "Draft key")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn discard_draft(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.draft.discard(&user_id, &key).await?;
+    Ok(NoContent)
+}
@@ -0,0 +1,26 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    schema::{JsonOk, UserMetadataResponse},
+    state::AppState,
+};
+use axum::extract::State;
+use std::sync::Arc;
+
+/// The user's metadata, set on registration and by operators.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/metadata",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn my_metadata(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<UserMetadataResponse>, AppError> {
+    let metadata = app_state.controller.user.metadata(&user_id).await?;
+    Ok(JsonOk(UserMetadataResponse {
+        username: user_id,
+        metadata,
+    }))
+}
@@ -0,0 +1,9 @@
+pub mod activity;
+pub mod calendar;
+pub mod drafts;
+pub mod metadata;
+pub mod notifications;
+pub mod preferences;
+pub mod sessions;
+pub mod timezone;
+pub mod two_factor;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use crate::{
+    db::NotificationFilter,
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::Notification,
+    schema::{JsonOk, ListResponse, MarkedRead, NoContent, PageParams, UnreadCount},
+    state::AppState,
+};
+use axum::extract::{Path, Query, State};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+/// Notifications of the current user, newest first. New ones are also pushed
+/// to open WebSockets.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/notifications",
+    tag = "me",
+    params(NotificationFilter, PageParams),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_notifications(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Query(mut filter): Query<NotificationFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<JsonOk<ListResponse<Notification>>, AppError> {
+    filter.limit = Some(page.limit_or(DEFAULT_LIMIT, MAX_LIMIT));
+    filter.cursor = page.cursor;
+    let (items, total, next_cursor) = app_state
+        .controller
+        .notification
+        .list(&user_id, &filter)
+        .await?;
+
+    Ok(JsonOk(ListResponse {
+        items,
+        total,
+        next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/notifications/unread-count",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn unread_count(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<UnreadCount>, AppError> {
+    let unread = app_state.controller.notification.unread_count(&user_id).await?;
+
+    Ok(JsonOk(UnreadCount { unread }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/notifications/{id}/read",
+    tag = "me",
+    params(("id" = String, Path, description = "Notification id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn mark_read(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .notification
+        .mark_read(&user_id, &id)
+        .await?;
+
+    Ok(NoContent)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/notifications/read-all",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn mark_all_read(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<MarkedRead>, AppError> {
+    let marked = app_state
+        .controller
+        .notification
+        .mark_all_read(&user_id)
+        .await?;
+
+    Ok(JsonOk(MarkedRead { marked }))
+}
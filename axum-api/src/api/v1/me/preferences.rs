@@ -0,0 +1,93 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::Preference,
+    schema::{JsonOk, NoContent, SavePreferenceRequest},
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use std::sync::Arc;
+
+/// The user's preference namespaces, by name.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/preferences",
+    tag = "me",
+    responses((status = 200, body = Vec<Preference>)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_preferences(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<Vec<Preference>>, AppError> {
+    let preferences = app_state.controller.preference.list(&user_id).await?;
+    Ok(JsonOk(preferences))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/preferences/{namespace}",
+    tag = "me",
+    params(("namespace" = String, Path, description = "Preference namespace")),
+    responses((status = 200, body = Preference)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_preferences(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+) -> Result<JsonOk<Preference>, AppError> {
+    let preference = app_state.controller.preference.get(&user_id, &namespace).await?;
+    Ok(JsonOk(preference))
+}
+
+/// Saves any JSON value under the namespace, up to `PREFERENCE_MAX_BYTES`, in at
+/// most `PREFERENCE_MAX_NAMESPACES` namespaces per user. `version` is the version the
+/// value replaces, 0 to create the namespace; saves over a newer version fail with
+/// 409, the client then reads the namespace again and merges its change.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/preferences/{namespace}",
+    tag = "me",
+    params(("namespace" = String, Path, description = "Preference namespace")),
+    request_body = SavePreferenceRequest,
+    responses((status = 200, body = Preference)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn save_preferences(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+    Json(req): Json<SavePreferenceRequest>,
+) -> Result<JsonOk<Preference>, AppError> {
+    let config = &app_state.config;
+    let preference = app_state
+        .controller
+        .preference
+        .save(
+            &user_id,
+            &namespace,
+            req.value,
+            req.version,
+            config.preference_max_bytes,
+            config.preference_max_namespaces,
+        )
+        .await?;
+    Ok(JsonOk(preference))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/preferences/{namespace}",
+    tag = "me",
+    params(("namespace" = String, Path, description = "Preference namespace")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_preferences(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state.controller.preference.delete(&user_id, &namespace).await?;
+    Ok(NoContent)
+}
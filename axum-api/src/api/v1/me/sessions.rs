@@ -0,0 +1,100 @@
+use crate::{
+    error::AppError,
+    middleware::auth::{AuthenticatedUser, CurrentSession},
+    schema::{JsonOk, ListResponse, NoContent, RevokedSessions, SessionInfo},
+    state::AppState,
+};
+use axum::extract::{Path, State};
+use std::sync::Arc;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/sessions",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_sessions(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    CurrentSession(session_id): CurrentSession,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<ListResponse<SessionInfo>>, AppError> {
+    let sessions = app_state.controller.session.list_sessions(&user_id).await?;
+
+    Ok(JsonOk(ListResponse::complete(
+        sessions
+            .into_iter()
+            .map(|s| SessionInfo::new(s, &session_id))
+            .collect(),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/sessions/{id}",
+    tag = "me",
+    params(("id" = String, Path, description = "Session id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_session(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .session
+        .revoke_session(&user_id, &id)
+        .await?;
+
+    log::info!("Session event -> User {} revoked session {}", &user_id, &id);
+
+    Ok(NoContent)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/sessions/revoke-all",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_all_sessions(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    CurrentSession(session_id): CurrentSession,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<RevokedSessions>, AppError> {
+    let revoked = app_state
+        .controller
+        .session
+        .revoke_all_sessions(&user_id, Some(&session_id))
+        .await?;
+
+    log::info!(
+        "Session event -> User {} revoked {} other sessions",
+        &user_id,
+        revoked
+    );
+
+    Ok(JsonOk(RevokedSessions { revoked }))
+}
+
+/// Invalidates every token of the current user, including the one used for this request.
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/logout-all",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn logout_all(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .user
+        .bump_token_generation(&user_id)
+        .await?;
+
+    log::info!("Session event -> User {} logged out everywhere", &user_id);
+
+    Ok(NoContent)
+}
@@ -0,0 +1,58 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::User,
+    schema::{JsonOk, SetTimezoneRequest, TimezoneResponse},
+    state::AppState,
+    utils::time::{today, user_timezone},
+};
+use axum::extract::{Json, State};
+use chrono::Utc;
+use std::sync::Arc;
+
+fn timezone_response(user: User) -> TimezoneResponse {
+    TimezoneResponse {
+        today: today(user_timezone(&user), Utc::now()),
+        timezone: user.personal.timezone,
+    }
+}
+
+/// The user's timezone, due dates end and reminders go off by it. Unset means UTC.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/timezone",
+    tag = "me",
+    responses((status = 200, body = TimezoneResponse)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn my_timezone(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<TimezoneResponse>, AppError> {
+    let user = app_state.controller.user.get_user(&user_id).await?;
+    Ok(JsonOk(timezone_response(user)))
+}
+
+/// Sets the user's timezone by IANA name, e.g. `Europe/Kyiv`, or back to UTC with
+/// `null`.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/timezone",
+    tag = "me",
+    request_body = SetTimezoneRequest,
+    responses((status = 200, body = TimezoneResponse)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_timezone(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<SetTimezoneRequest>,
+) -> Result<JsonOk<TimezoneResponse>, AppError> {
+    let user = app_state
+        .controller
+        .user
+        .set_timezone(&user_id, req.timezone.as_deref())
+        .await?;
+    log::info!("Profile event -> {} set timezone {:?}", &user_id, user.personal.timezone);
+    Ok(JsonOk(timezone_response(user)))
+}
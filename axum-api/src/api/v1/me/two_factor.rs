@@ -0,0 +1,55 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    schema::{JsonOk, NoContent, TwoFactorCodeRequest, TwoFactorEnrollResponse},
+    state::AppState,
+};
+use axum::extract::{Json, State};
+use std::sync::Arc;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/2fa/enroll",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn enroll(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<JsonOk<TwoFactorEnrollResponse>, AppError> {
+    let (otpauth_uri, recovery_codes) = app_state
+        .controller
+        .two_factor
+        .enroll(&user_id, &app_state.config.totp_issuer)
+        .await?;
+
+    log::info!("2FA event -> User {} started enrollment", &user_id);
+
+    Ok(JsonOk(TwoFactorEnrollResponse {
+        otpauth_uri,
+        recovery_codes,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/2fa/confirm",
+    tag = "me",
+    request_body = TwoFactorCodeRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn confirm(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<NoContent, AppError> {
+    app_state
+        .controller
+        .two_factor
+        .confirm(&user_id, &req.code)
+        .await?;
+
+    log::info!("2FA event -> User {} enabled two-factor authentication", &user_id);
+
+    Ok(NoContent)
+}
@@ -0,0 +1,134 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::Milestone,
+    schema::{
+        AssignMilestoneRequest, BurndownResponse, CloseMilestoneRequest, ClosedMilestoneResponse,
+        CreateMilestoneRequest, JsonCreated, JsonOk, TicketResponse,
+    },
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use std::sync::Arc;
+
+/// Milestones of a project, by start date.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/milestones",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_milestones(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<Milestone>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let milestones = app_state.controller.milestone.list(&id, &principals).await?;
+    Ok(JsonOk(milestones))
+}
+
+/// Adds an open milestone, requires `MODIFY` on the project.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/milestones",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = CreateMilestoneRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_milestone(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<CreateMilestoneRequest>,
+) -> Result<JsonCreated<Milestone>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let milestone = app_state.controller.milestone.create(&id, &principals, req).await?;
+    Ok(JsonCreated(milestone))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/milestones/{id}",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Milestone id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_milestone(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Milestone>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let milestone = app_state.controller.milestone.get(&id, &principals).await?;
+    Ok(JsonOk(milestone))
+}
+
+/// Open tickets of the milestone at the end of each day so far.
+#[utoipa::path(
+    get,
+    path = "/api/v1/milestones/{id}/burndown",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Milestone id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn milestone_burndown(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<BurndownResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let days = app_state.controller.milestone.burndown(&id, &principals).await?;
+    Ok(JsonOk(BurndownResponse { milestone: id, days }))
+}
+
+/// Closes the milestone, its unfinished tickets roll over to another one.
+/// Requires `MODIFY` on the project.
+#[utoipa::path(
+    post,
+    path = "/api/v1/milestones/{id}/close",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Milestone id")),
+    request_body = CloseMilestoneRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn close_milestone(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<CloseMilestoneRequest>,
+) -> Result<JsonOk<ClosedMilestoneResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let closed = app_state
+        .controller
+        .milestone
+        .close(&id, &principals, req.rollover_to)
+        .await?;
+    Ok(JsonOk(closed))
+}
+
+/// Plans the ticket into a milestone of its project, requires `MODIFY` on the project.
+#[utoipa::path(
+    put,
+    path = "/api/v1/tickets/{id}/milestone",
+    tag = "milestones",
+    params(("id" = String, Path, description = "Ticket id")),
+    request_body = AssignMilestoneRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn assign_milestone(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<AssignMilestoneRequest>,
+) -> Result<JsonOk<TicketResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let ticket = app_state
+        .controller
+        .milestone
+        .assign(&id, &principals, req.milestone)
+        .await?;
+    Ok(JsonOk(ticket.into()))
+}
@@ -1,2 +1,10 @@
 pub mod authentication;
+pub mod me;
+pub mod milestones;
+pub mod principals;
+pub mod projects;
+pub mod render;
+pub mod search;
+pub mod shares;
+pub mod tickets;
 pub mod ws;
\ No newline at end of file
@@ -0,0 +1,34 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    schema::{JsonOk, PrincipalSuggestion, SuggestQuery},
+    state::AppState,
+};
+use axum::extract::{Query, State};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+/// Users and groups to autocomplete `@mentions` and ACL entries with, limited to
+/// those visible to the caller.
+#[utoipa::path(
+    get,
+    path = "/api/v1/principals/suggest",
+    tag = "principals",
+    params(SuggestQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn suggest_principals(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Query(query): Query<SuggestQuery>,
+) -> Result<JsonOk<Vec<PrincipalSuggestion>>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let suggestions = app_state
+        .controller
+        .group
+        .suggest(&username, &query.q, limit)
+        .await?;
+    Ok(JsonOk(suggestions))
+}
@@ -0,0 +1,594 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::{AccessControlList, AccessControlStore, Activity, AssignmentRule, CustomFieldDefinition, EscalationPolicy, Severity},
+    schema::{
+        AclChangeRequest, AclQuery, ActivityQuery, AssignmentDryRunRequest, AssignmentDryRunResponse, BoardColumn, BoardResponse,
+        CloneProjectRequest, CloneProjectResponse, JsonCreated, JsonOk, ListResponse, NoContent, PageParams, ProjectOnlineResponse,
+        ProjectOwnershipResponse, ProjectStatsResponse, ProjectTreeResponse, PublicPortalRequest, PublicPortalResponse,
+        SetParentRequest, StatsQuery, TransferOwnershipRequest,
+    },
+    state::AppState,
+};
+use axum::extract::{Json, Path, Query, State};
+use std::sync::Arc;
+
+/// Ticket counts, trend, resolution time and top assignees of a project.
+/// Cached for a few seconds.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/stats",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), StatsQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_stats(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<StatsQuery>,
+) -> Result<JsonOk<ProjectStatsResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let stats = app_state
+        .controller
+        .project
+        .stats(&id, &principals, query.days)
+        .await?;
+    Ok(JsonOk(stats))
+}
+
+/// Members of the project with an open WebSocket.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/online",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_online(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<ProjectOnlineResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    let users = app_state
+        .controller
+        .project
+        .members_among(&project, &app_state.ws_connections.online_users())
+        .await?;
+    Ok(JsonOk(ProjectOnlineResponse { project: id, users }))
+}
+
+/// Tickets created, status changes and comments in the project, newest first.
+/// Activity on tickets the caller can't see is left out of the page.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/activity",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), ActivityQuery, PageParams),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_activity(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<ActivityQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<JsonOk<ListResponse<Activity>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let feed = app_state
+        .controller
+        .activity
+        .project_feed(&id, &principals, &query, &page)
+        .await?;
+    Ok(JsonOk(feed))
+}
+
+/// The severity scale tickets of the project are created with.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/severities",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_severities(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<Severity>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    Ok(JsonOk(project.severity_scale()))
+}
+
+/// Extra fields tickets of the project carry in `custom_fields`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/custom-fields",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn custom_fields(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<CustomFieldDefinition>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    Ok(JsonOk(project.custom_fields))
+}
+
+/// Replaces the custom field definitions, requires `MODIFY` on the project.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/custom-fields",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = Vec<CustomFieldDefinition>,
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_custom_fields(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(definitions): Json<Vec<CustomFieldDefinition>>,
+) -> Result<JsonOk<Vec<CustomFieldDefinition>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let definitions = app_state
+        .controller
+        .project
+        .set_custom_fields(&id, &principals, definitions)
+        .await?;
+    Ok(JsonOk(definitions))
+}
+
+/// Rules assigning new tickets of the project that are created without an assignee.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/assignment-rules",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn assignment_rules(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<AssignmentRule>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    Ok(JsonOk(project.assignment_rules))
+}
+
+/// Replaces the assignment rules, in order of precedence. Requires `MODIFY` on the project.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/assignment-rules",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = Vec<AssignmentRule>,
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_assignment_rules(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(rules): Json<Vec<AssignmentRule>>,
+) -> Result<JsonOk<Vec<AssignmentRule>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let rules = app_state
+        .controller
+        .project
+        .set_assignment_rules(&id, &principals, rules)
+        .await?;
+    Ok(JsonOk(rules))
+}
+
+/// Previews which rule a new ticket would meet and who it would be assigned to,
+/// without creating it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/assignment-rules/dry-run",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = AssignmentDryRunRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn assignment_dry_run(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<AssignmentDryRunRequest>,
+) -> Result<JsonOk<AssignmentDryRunResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    let matched = app_state
+        .controller
+        .ticket
+        .match_assignment_rule(&project, req.severity, &req.custom_fields)
+        .await?;
+    let (rule, assigned_to) = matched.unzip();
+    Ok(JsonOk(AssignmentDryRunResponse { rule, assigned_to }))
+}
+
+/// The project's tickets in board order, one column per status.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/board",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_board(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<BoardResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    app_state.controller.project.authorize(&id, &principals).await?;
+    let columns = app_state
+        .controller
+        .ticket
+        .board(&id, &principals)
+        .await?
+        .into_iter()
+        .map(|(status, tickets)| BoardColumn {
+            status,
+            tickets: tickets.into_iter().map(Into::into).collect(),
+        })
+        .collect();
+    Ok(JsonOk(BoardResponse { project: id, columns }))
+}
+
+/// Policies escalating the project's tickets that nothing happened to for a while.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/escalation-policies",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn escalation_policies(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<EscalationPolicy>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let project = app_state.controller.project.get_project(&id, &principals).await?;
+    Ok(JsonOk(project.escalation_policies))
+}
+
+/// Replaces the escalation policies, in order of precedence. Requires `MODIFY` on the project.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/escalation-policies",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = Vec<EscalationPolicy>,
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_escalation_policies(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(policies): Json<Vec<EscalationPolicy>>,
+) -> Result<JsonOk<Vec<EscalationPolicy>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let policies = app_state
+        .controller
+        .project
+        .set_escalation_policies(&id, &principals, policies)
+        .await?;
+    Ok(JsonOk(policies))
+}
+
+/// The project's public portal, if it has one. Requires `MODIFY` on the project.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/portal",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    responses((status = 200, body = Option<PublicPortalResponse>)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn public_portal(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Option<PublicPortalResponse>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let portal = app_state.controller.project.portal(&id, &principals).await?;
+    Ok(JsonOk(portal.map(|portal| PublicPortalResponse::new(&portal, None))))
+}
+
+/// Lets people without accounts file tickets in the project at
+/// `/api/public/projects/{slug}/tickets`, or changes how. A token issued here is only
+/// shown in this response. Requires `MODIFY` on the project.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/portal",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = PublicPortalRequest,
+    responses((status = 200, body = PublicPortalResponse)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_public_portal(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<PublicPortalRequest>,
+) -> Result<JsonOk<PublicPortalResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let (portal, token) = app_state.controller.project.set_portal(&id, &principals, req).await?;
+
+    log::info!("Project event -> Public portal {} of project {} set by {}", portal.slug, id, username);
+
+    Ok(JsonOk(PublicPortalResponse::new(&portal, token)))
+}
+
+/// Stops public submissions to the project. Requires `MODIFY` on the project.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/projects/{id}/portal",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_public_portal(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<NoContent, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    app_state.controller.project.remove_portal(&id, &principals).await?;
+
+    log::info!("Project event -> Public portal of project {} removed by {}", id, username);
+
+    Ok(NoContent)
+}
+
+/// What an ACL change applies to, for the audit log.
+fn acl_scope(id: &str, query: &AclQuery) -> String {
+    match &query.ticket_group {
+        Some(prefix) => format!("ticket group {} of project {}", prefix, id),
+        None => format!("project {}", id),
+    }
+}
+
+/// The ACL of the project, or of one of its ticket groups. Requires `MODIFY` on the project.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/acl",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), AclQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_acl(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<AclQuery>,
+) -> Result<JsonOk<AccessControlStore>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let acl = app_state
+        .controller
+        .project
+        .acl(&id, &principals, query.ticket_group.as_deref())
+        .await?;
+    Ok(JsonOk(acl))
+}
+
+/// Replaces the ACL of the project, or of one of its ticket groups. Requires `ROOT`
+/// on the project, which must keep a user or group with `ROOT`.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/acl",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), AclQuery),
+    request_body = Vec<AccessControlList>,
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_project_acl(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<AclQuery>,
+    Json(list): Json<Vec<AccessControlList>>,
+) -> Result<JsonOk<AccessControlStore>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let acl = app_state
+        .controller
+        .project
+        .set_acl(&id, &principals, query.ticket_group.as_deref(), list)
+        .await?;
+    log::warn!(
+        target: "audit",
+        "ACL -> {} replaced the ACL of {}: {:?}",
+        username, acl_scope(&id, &query), acl.list
+    );
+    Ok(JsonOk(acl))
+}
+
+/// Adds permissions to those a user or group is granted on the project, or on one of
+/// its ticket groups. Requires `ROOT` on the project.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/acl/grant",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), AclQuery),
+    request_body = AclChangeRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn grant_permissions(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<AclQuery>,
+    Json(req): Json<AclChangeRequest>,
+) -> Result<JsonOk<AccessControlStore>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let acl = app_state
+        .controller
+        .project
+        .grant(&id, &principals, query.ticket_group.as_deref(), &req.principal, req.permissions)
+        .await?;
+    log::warn!(
+        target: "audit",
+        "ACL -> {} granted {:?} to {} on {}",
+        username, req.permissions, req.principal, acl_scope(&id, &query)
+    );
+    Ok(JsonOk(acl))
+}
+
+/// Takes permissions from those a user or group is granted itself on the project, or
+/// on one of its ticket groups. Requires `ROOT` on the project, which must keep a user
+/// or group with `ROOT`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/acl/revoke",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id"), AclQuery),
+    request_body = AclChangeRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_permissions(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Query(query): Query<AclQuery>,
+    Json(req): Json<AclChangeRequest>,
+) -> Result<JsonOk<AccessControlStore>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let acl = app_state
+        .controller
+        .project
+        .revoke(&id, &principals, query.ticket_group.as_deref(), &req.principal, req.permissions)
+        .await?;
+    log::warn!(
+        target: "audit",
+        "ACL -> {} revoked {:?} from {} on {}",
+        username, req.permissions, req.principal, acl_scope(&id, &query)
+    );
+    Ok(JsonOk(acl))
+}
+
+/// Makes another user the owner of the project, who has `ROOT` on it whatever its ACL
+/// says. Only the current owner can, `confirm` must repeat the project id.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/transfer-ownership",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = TransferOwnershipRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn transfer_ownership(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<TransferOwnershipRequest>,
+) -> Result<JsonOk<ProjectOwnershipResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let (project, previous_owner) = app_state
+        .controller
+        .project
+        .transfer_ownership(&id, &principals, &req.new_owner, &req.confirm)
+        .await?;
+    log::warn!(
+        target: "audit",
+        "Ownership -> {} transferred project {} from {} to {}",
+        username,
+        id,
+        previous_owner.as_deref().unwrap_or("nobody"),
+        req.new_owner
+    );
+    Ok(JsonOk(ProjectOwnershipResponse {
+        project: project.id.to_string(),
+        owner: req.new_owner,
+        previous_owner,
+    }))
+}
+
+/// Starts a project from another: copies its settings, ticket groups, ACLs and chat
+/// channel, and with `include_open_tickets` its open and in progress tickets. The caller
+/// needs `MODIFY` on the source and owns the new project.
+#[utoipa::path(
+    post,
+    path = "/api/v1/projects/{id}/clone",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = CloneProjectRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn clone_project(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<CloneProjectRequest>,
+) -> Result<JsonCreated<CloneProjectResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let (project, tickets) = app_state
+        .controller
+        .project
+        .clone_project(&id, &principals, &username, req.include_open_tickets)
+        .await?;
+    log::info!(
+        "Project event -> Project {} cloned from {} by {} with {} tickets",
+        project.id, id, username, tickets
+    );
+    Ok(JsonCreated(CloneProjectResponse {
+        project: project.id.to_string(),
+        source: id,
+        tickets,
+    }))
+}
+
+/// The projects above the project and below it, those the caller can't fetch left out.
+#[utoipa::path(
+    get,
+    path = "/api/v1/projects/{id}/tree",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn project_tree(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<ProjectTreeResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let tree = app_state.controller.project.tree(&id, &principals).await?;
+    Ok(JsonOk(tree))
+}
+
+/// Makes the project a sub-project of another, which its ACL then inherits from, or a
+/// top-level one. The caller needs `ROOT` on the project and `MODIFY` on the new parent.
+#[utoipa::path(
+    put,
+    path = "/api/v1/projects/{id}/parent",
+    tag = "projects",
+    params(("id" = String, Path, description = "Project id")),
+    request_body = SetParentRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_project_parent(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<SetParentRequest>,
+) -> Result<JsonOk<ProjectTreeResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let controller = &app_state.controller.project;
+    controller.set_parent(&id, &principals, req.parent_id.as_deref()).await?;
+    log::info!(
+        "Project event -> Project {} moved {} by {}",
+        id,
+        req.parent_id.as_ref().map_or("to the top level".to_string(), |p| format!("below {}", p)),
+        username
+    );
+    Ok(JsonOk(controller.tree(&id, &principals).await?))
+}
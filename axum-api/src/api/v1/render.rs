@@ -0,0 +1,26 @@
+use crate::{
+    error::AppError,
+    schema::{JsonOk, RenderMarkdownRequest, RenderedMarkdown},
+    state::AppState,
+};
+use axum::extract::{Json, State};
+use std::sync::Arc;
+
+/// Renders markdown as ticket descriptions and comments are: to HTML stripped of
+/// scripts and other dangerous markup, `@mentions` and ticket references such as
+/// `#12` or `OPS-12` linked when they exist.
+#[utoipa::path(
+    post,
+    path = "/api/v1/render/markdown",
+    tag = "render",
+    request_body = RenderMarkdownRequest,
+    responses((status = 200, body = RenderedMarkdown)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn render_markdown(
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<RenderMarkdownRequest>,
+) -> Result<JsonOk<RenderedMarkdown>, AppError> {
+    let html = app_state.controller.render.markdown(&req.markdown).await?;
+    Ok(JsonOk(RenderedMarkdown { html }))
+}
@@ -0,0 +1,36 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    schema::{JsonOk, SearchQuery, SearchResults},
+    state::AppState,
+};
+use axum::extract::{Query, State};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+/// Tickets, projects, users and groups matching the words of `q`, grouped by kind and
+/// ranked within each, among those the caller can see.
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "search",
+    params(SearchQuery),
+    responses((status = 200, body = SearchResults)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn search(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Query(query): Query<SearchQuery>,
+) -> Result<JsonOk<SearchResults>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let results = app_state
+        .controller
+        .search
+        .search(&principals, &query.q, limit)
+        .await?;
+    Ok(JsonOk(results))
+}
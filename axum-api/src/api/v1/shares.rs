@@ -0,0 +1,121 @@
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    models::{Permissions, TicketShare},
+    schema::{JsonCreated, JsonOk, NoContent, ShareTicketRequest, TicketShareResponse},
+    state::AppState,
+};
+use axum::extract::{Json, Path, State};
+use chrono::Duration;
+use std::sync::Arc;
+
+/// Where shared tickets are read, outside the login.
+pub const SHARED_TICKET_PATH: &str = "/api/public/tickets/shared";
+
+const DEFAULT_SHARE_LIFETIME_HOURS: i64 = 72;
+const MAX_SHARE_LIFETIME_HOURS: i64 = 24 * 30;
+
+/// Creates a link letting anyone read the ticket without logging in, for 72 hours
+/// unless `expires_in_hours` says otherwise, 30 days at most. Requires `MODIFY` on
+/// the ticket.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets/{id}/share",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    request_body = ShareTicketRequest,
+    responses((status = 201, body = TicketShareResponse)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn share_ticket(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<ShareTicketRequest>,
+) -> Result<JsonCreated<TicketShareResponse>, AppError> {
+    let hours = req.expires_in_hours.unwrap_or(DEFAULT_SHARE_LIFETIME_HOURS);
+    if !(1..=MAX_SHARE_LIFETIME_HOURS).contains(&hours) {
+        return Err(AppError::Validation(format!(
+            "expires_in_hours must be between 1 and {}",
+            MAX_SHARE_LIFETIME_HOURS
+        )));
+    }
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let ticket = app_state
+        .controller
+        .ticket
+        .ticket_for(&id, &principals, Permissions::MODIFY)
+        .await?;
+    let (token, share) = app_state
+        .controller
+        .share
+        .create(ticket.id, &username, Duration::hours(hours), app_state.config.jwt_secret.as_bytes())
+        .await?;
+    log::warn!(
+        target: "audit",
+        "Share -> {} shared ticket {} as {} until {}",
+        username, ticket.id, share.id, share.expires_at
+    );
+    Ok(JsonCreated(TicketShareResponse {
+        id: share.id,
+        url: format!("{}?token={}", SHARED_TICKET_PATH, token),
+        token,
+        expires_at: share.expires_at,
+    }))
+}
+
+/// The ticket's links that haven't expired or been revoked. Requires `MODIFY` on the
+/// ticket.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tickets/{id}/shares",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    responses((status = 200, body = Vec<TicketShare>)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_shares(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<TicketShare>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let ticket = app_state
+        .controller
+        .ticket
+        .ticket_for(&id, &principals, Permissions::MODIFY)
+        .await?;
+    let shares = app_state.controller.share.list(ticket.id).await?;
+    Ok(JsonOk(shares))
+}
+
+/// Revokes a link to the ticket. Requires `MODIFY` on the ticket.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tickets/{id}/shares/{share_id}",
+    tag = "tickets",
+    params(
+        ("id" = String, Path, description = "Ticket id"),
+        ("share_id" = String, Path, description = "Share id"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_share(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path((id, share_id)): Path<(String, String)>,
+) -> Result<NoContent, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let ticket = app_state
+        .controller
+        .ticket
+        .ticket_for(&id, &principals, Permissions::MODIFY)
+        .await?;
+    let share = app_state.controller.share.revoke(ticket.id, &share_id).await?;
+    log::warn!(
+        target: "audit",
+        "Share -> {} revoked share {} of ticket {}",
+        username, share.id, ticket.id
+    );
+    Ok(NoContent)
+}
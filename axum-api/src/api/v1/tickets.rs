@@ -0,0 +1,320 @@
+use crate::{
+    controllers::ticket_controller::{TICKET_FIELDS, TicketFilter, UNREAD_FIELD},
+    error::AppError,
+    middleware::{auth::AuthenticatedUser, conditional::Preconditions},
+    models::{Comment, ReadReceipt, TicketStatus},
+    schema::{
+        Conditional, CreateCommentRequest, CreateTicketRequest, DuplicateCandidate, DuplicateCheckRequest, FieldsQuery,
+        JsonCreated, JsonOk, ListResponse, MoveTicketRequest, SortOrder, SortParams, TicketListQuery, TicketResponse,
+    },
+    state::AppState,
+    validation::{custom_fields::parse_filter, fields::validate_fields},
+};
+use axum::extract::{Json, Path, Query, State};
+use chrono::DateTime;
+use serde_json::Value;
+use std::sync::Arc;
+
+const SORT_FIELDS: &[&str] = &["id", "title", "severity", "status", "creation_date", "last_modification", "due_date"];
+
+fn selected_fields(fields: Option<&str>) -> Result<Option<Vec<String>>, AppError> {
+    fields
+        .map(|raw| validate_fields(raw, TICKET_FIELDS).map_err(AppError::Validation))
+        .transpose()
+}
+
+/// Orders listed tickets by one of `SORT_FIELDS`, which must be selected. Statuses
+/// sort in workflow order; tickets without a due date come last either way.
+fn sort_tickets(tickets: &mut [Value], sort: &SortParams, fields: Option<&[String]>) -> Result<(), AppError> {
+    let Some(field) = sort.sort.as_deref() else {
+        return Ok(());
+    };
+    if !SORT_FIELDS.contains(&field) {
+        return Err(AppError::Validation(format!("Tickets can be sorted by {}", SORT_FIELDS.join(", "))));
+    }
+    if fields.is_some_and(|fields| !fields.iter().any(|f| f == field)) {
+        return Err(AppError::Validation(format!("Field '{}' must be selected to sort by it", field)));
+    }
+    let key = |ticket: &Value| -> Option<(i64, String)> {
+        match &ticket[field] {
+            Value::String(status) if field == "status" => {
+                let status: TicketStatus = serde_json::from_value(status.as_str().into()).ok()?;
+                TicketStatus::ALL.iter().position(|s| *s == status).map(|i| (i as i64, String::new()))
+            }
+            // Timestamps have as many decimals as they need, their text doesn't sort
+            Value::String(at) if field != "title" && field != "due_date" => {
+                DateTime::parse_from_rfc3339(at).ok().map(|at| (at.timestamp_micros(), String::new()))
+            }
+            Value::String(text) => Some((0, text.clone())),
+            Value::Number(number) => number.as_i64().map(|n| (n, String::new())),
+            _ => None,
+        }
+    };
+    tickets.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(a), Some(b)) if sort.order == SortOrder::Desc => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    });
+    Ok(())
+}
+
+fn selected_list_fields(fields: Option<&str>) -> Result<Option<Vec<String>>, AppError> {
+    let allowed: Vec<&str> = TICKET_FIELDS.iter().copied().chain([UNREAD_FIELD]).collect();
+    fields
+        .map(|raw| validate_fields(raw, &allowed).map_err(AppError::Validation))
+        .transpose()
+}
+
+/// Safe to retry with an `Idempotency-Key` header.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets",
+    tag = "tickets",
+    request_body = CreateTicketRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replays the first response on retries")),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_ticket(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Json(req): Json<CreateTicketRequest>,
+) -> Result<JsonCreated<TicketResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let ticket = app_state
+        .controller
+        .ticket
+        .create_ticket(&username, &principals, req)
+        .await?;
+
+    log::info!("Ticket event -> Ticket {} created by {}", ticket.id, &username);
+
+    Ok(JsonCreated(ticket.into()))
+}
+
+/// Recent tickets of the project that look like the one about to be filed, most
+/// similar first, so it can be linked to instead of filed twice.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets/check-duplicates",
+    tag = "tickets",
+    request_body = DuplicateCheckRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn check_duplicates(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Json(req): Json<DuplicateCheckRequest>,
+) -> Result<JsonOk<Vec<DuplicateCandidate>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    if let Some(project) = &req.project {
+        app_state.controller.project.authorize(project, &principals).await?;
+    }
+    let candidates = app_state
+        .controller
+        .ticket
+        .duplicate_candidates(&req.title, req.project.as_deref(), &principals)
+        .await?;
+    Ok(JsonOk(
+        candidates
+            .into_iter()
+            .map(|(ticket, score)| DuplicateCandidate {
+                ticket: ticket.into(),
+                score,
+            })
+            .collect(),
+    ))
+}
+
+/// Supports conditional requests through `ETag` / `If-None-Match`.
+/// Tickets carry `unread`, whether they changed since the caller last saw them.
+/// Filter by custom fields with `?custom=environment:staging,customer:acme`, sort
+/// by `id`, `title`, `severity`, `status`, `creation_date`, `last_modification`
+/// or `due_date`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tickets",
+    tag = "tickets",
+    params(TicketListQuery, SortParams),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_tickets(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    preconditions: Preconditions,
+    Query(query): Query<TicketListQuery>,
+    Query(sort): Query<SortParams>,
+) -> Result<Conditional<ListResponse<Value>>, AppError> {
+    let fields = selected_list_fields(query.fields.as_deref())?;
+    let filter = TicketFilter {
+        project: query.project,
+        custom_fields: query
+            .custom
+            .as_deref()
+            .map(parse_filter)
+            .transpose()
+            .map_err(AppError::Validation)?
+            .unwrap_or_default(),
+    };
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let mut tickets = app_state
+        .controller
+        .ticket
+        .list_tickets(&username, &principals, fields.as_deref(), &filter)
+        .await?;
+    sort_tickets(&mut tickets, &sort, fields.as_deref())?;
+    // ETag only: the newest last_modification would not reflect deleted tickets
+    preconditions.evaluate(ListResponse::complete(tickets), None)
+}
+
+/// Supports conditional requests through `ETag` / `If-None-Match`
+/// and `Last-Modified` / `If-Modified-Since`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tickets/{id}",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id"), FieldsQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_ticket(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    preconditions: Preconditions,
+    Query(query): Query<FieldsQuery>,
+) -> Result<Conditional<Value>, AppError> {
+    let fields = selected_fields(query.fields.as_deref())?;
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let (ticket, last_modified) = app_state
+        .controller
+        .ticket
+        .get_ticket(&id, &principals, fields.as_deref())
+        .await?;
+    preconditions.evaluate(ticket, Some(last_modified))
+}
+
+/// Moves a ticket on its project's board, to another position or status column, or to
+/// another project or ticket group. Moving it elsewhere needs `CREATE` there, and is
+/// recorded with a comment.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets/{id}/move",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    request_body = MoveTicketRequest,
+    security(("bearer_auth" = [])),
+)]
+pub async fn move_ticket(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<MoveTicketRequest>,
+) -> Result<JsonOk<TicketResponse>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let relocated = req.project.is_some() || req.ticket_group.is_some();
+    let ticket = app_state
+        .controller
+        .ticket
+        .move_ticket(&id, &username, &principals, req)
+        .await?;
+    if relocated {
+        log::info!(
+            "Ticket event -> Ticket {} moved to project {} by {}",
+            ticket.id,
+            ticket.project.as_deref().unwrap_or_default(),
+            username
+        );
+    }
+    Ok(JsonOk(ticket.into()))
+}
+
+/// Comments on the ticket, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tickets/{id}/comments",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    responses((status = 200, body = Vec<Comment>)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_comments(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<Comment>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let comments = app_state.controller.ticket.comments(&id, &principals).await?;
+    Ok(JsonOk(comments))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets/{id}/comments",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    request_body = CreateCommentRequest,
+    responses((status = 201, body = Comment)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_comment(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+    Json(req): Json<CreateCommentRequest>,
+) -> Result<JsonCreated<Comment>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let comment = app_state
+        .controller
+        .ticket
+        .add_comment(&id, &username, &principals, &req.body)
+        .await?;
+    log::info!("Ticket event -> Comment added to ticket {} by {}", comment.ticket, &username);
+    Ok(JsonCreated(comment))
+}
+
+/// Records that the caller saw the ticket as it is now: it stops being unread for
+/// them until it changes again, and their notifications about it are marked read.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tickets/{id}/seen",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    responses((status = 200, body = ReadReceipt)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn mark_ticket_seen(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<ReadReceipt>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let receipt = app_state
+        .controller
+        .ticket
+        .mark_seen(&id, &username, &principals)
+        .await?;
+    app_state
+        .controller
+        .notification
+        .mark_ticket_read(&username, receipt.ticket)
+        .await?;
+    Ok(JsonOk(receipt))
+}
+
+/// Who saw the ticket and when, most recent first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tickets/{id}/seen",
+    tag = "tickets",
+    params(("id" = String, Path, description = "Ticket id")),
+    responses((status = 200, body = Vec<ReadReceipt>)),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_ticket_receipts(
+    State(app_state): State<Arc<AppState>>,
+    AuthenticatedUser(username): AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<JsonOk<Vec<ReadReceipt>>, AppError> {
+    let principals = app_state.controller.group.principals_of(&username).await?;
+    let receipts = app_state.controller.ticket.receipts(&id, &principals).await?;
+    Ok(JsonOk(receipts))
+}
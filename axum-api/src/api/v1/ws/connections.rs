@@ -0,0 +1,251 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    api::v1::ws::protocol::{ServerMessage, project_channel},
+    controllers::Controller,
+    error::AppError,
+    events::{DomainEvent, Subscriber},
+    models::{Permissions, Ticket},
+    schema::WsTotals,
+    utils::BoxFuture,
+};
+
+/// A live socket: messages sent to it are forwarded by its handler.
+struct Socket {
+    username: String,
+    sender: mpsc::UnboundedSender<ServerMessage>,
+    channels: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Registry {
+    open: HashMap<String, usize>, // by user
+    sockets: HashMap<u64, Socket>,
+}
+
+/// Open WebSockets of this instance, per user, and the channels they subscribed to.
+pub struct WsConnections {
+    registry: Mutex<Registry>,
+    controller: Arc<Controller>, // to check who may see relayed tickets
+    next_id: AtomicU64,
+    opened: AtomicU64,
+    closed: AtomicU64,
+    rejected: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl WsConnections {
+    pub fn new(controller: Arc<Controller>) -> Self {
+        Self {
+            registry: Mutex::default(),
+            controller,
+            next_id: AtomicU64::default(),
+            opened: AtomicU64::default(),
+            closed: AtomicU64::default(),
+            rejected: AtomicU64::default(),
+            timed_out: AtomicU64::default(),
+        }
+    }
+
+    /// Registers a connection unless the user already has `max` open ones.
+    /// It counts as open until the guard is dropped.
+    pub fn try_open(self: &Arc<Self>, username: &str, max: usize) -> Option<ConnectionGuard> {
+        let mut registry = self.registry.lock().unwrap();
+        let count = registry.open.entry(username.to_string()).or_default();
+        if *count >= max {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        *count += 1;
+        let first = *count == 1;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        registry.sockets.insert(
+            id,
+            Socket {
+                username: username.to_string(),
+                sender,
+                channels: HashSet::new(),
+            },
+        );
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        Some(ConnectionGuard {
+            connections: self.clone(),
+            id,
+            username: username.to_string(),
+            first,
+            receiver,
+        })
+    }
+
+    pub fn total(&self) -> usize {
+        self.registry.lock().unwrap().open.values().sum()
+    }
+
+    pub fn user_count(&self, username: &str) -> usize {
+        self.registry.lock().unwrap().open.get(username).copied().unwrap_or(0)
+    }
+
+    /// Users with at least one open connection, sorted.
+    pub fn online_users(&self) -> Vec<String> {
+        let registry = self.registry.lock().unwrap();
+        let mut users: Vec<String> = registry.open.keys().cloned().collect();
+        users.sort();
+        users
+    }
+
+    /// Channels with at least one subscriber, sorted.
+    pub fn channels(&self) -> Vec<String> {
+        let registry = self.registry.lock().unwrap();
+        let channels: BTreeSet<&String> = registry.sockets.values().flat_map(|s| &s.channels).collect();
+        channels.into_iter().cloned().collect()
+    }
+
+    pub fn subscribe(&self, id: u64, channel: &str) {
+        if let Some(socket) = self.registry.lock().unwrap().sockets.get_mut(&id) {
+            socket.channels.insert(channel.to_string());
+        }
+    }
+
+    pub fn unsubscribe(&self, id: u64, channel: &str) {
+        if let Some(socket) = self.registry.lock().unwrap().sockets.get_mut(&id) {
+            socket.channels.remove(channel);
+        }
+    }
+
+    /// Sends a message to every socket subscribed to the channel.
+    pub fn broadcast(&self, channel: &str, message: &ServerMessage) {
+        let registry = self.registry.lock().unwrap();
+        for socket in registry.sockets.values().filter(|s| s.channels.contains(channel)) {
+            let _ = socket.sender.send(message.clone());
+        }
+    }
+
+    /// Users with a socket subscribed to the channel, sorted.
+    fn subscribers(&self, channel: &str) -> BTreeSet<String> {
+        let registry = self.registry.lock().unwrap();
+        registry
+            .sockets
+            .values()
+            .filter(|s| s.channels.contains(channel))
+            .map(|s| s.username.clone())
+            .collect()
+    }
+
+    /// Sends a message to the sockets of the user subscribed to the channel.
+    fn send_on(&self, channel: &str, username: &str, message: &ServerMessage) {
+        let registry = self.registry.lock().unwrap();
+        for socket in registry
+            .sockets
+            .values()
+            .filter(|s| s.username == username && s.channels.contains(channel))
+        {
+            let _ = socket.sender.send(message.clone());
+        }
+    }
+
+    /// Relays the message to the subscribers of the channel allowed to fetch the ticket.
+    /// Subscribing takes access to the project, but ticket groups may narrow it.
+    async fn relay(&self, ticket: &Ticket, channel: &str, message: &ServerMessage) -> Result<(), AppError> {
+        for username in self.subscribers(channel) {
+            let principals = self.controller.group.principals_of(&username).await?;
+            let permissions = self.controller.ticket.permissions(ticket, &principals).await?;
+            if permissions.contains(Permissions::FETCH) {
+                self.send_on(channel, &username, message);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends a message to every socket of the user, returns how many there are.
+    pub fn send_to_user(&self, username: &str, message: &ServerMessage) -> usize {
+        let registry = self.registry.lock().unwrap();
+        registry
+            .sockets
+            .values()
+            .filter(|s| s.username == username)
+            .filter(|s| s.sender.send(message.clone()).is_ok())
+            .count()
+    }
+
+    pub fn record_timeout(&self) {
+        self.timed_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn totals(&self) -> WsTotals {
+        WsTotals {
+            opened: self.opened.load(Ordering::Relaxed),
+            closed: self.closed.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+        }
+    }
+
+    fn close(&self, id: u64, username: &str) {
+        let mut registry = self.registry.lock().unwrap();
+        registry.sockets.remove(&id);
+        if let Some(count) = registry.open.get_mut(username) {
+            *count -= 1;
+            if *count == 0 {
+                registry.open.remove(username);
+            }
+        }
+        self.closed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub struct ConnectionGuard {
+    connections: Arc<WsConnections>,
+    pub id: u64,
+    username: String,
+    pub first: bool,                                      // the user's only open connection
+    pub receiver: mpsc::UnboundedReceiver<ServerMessage>, // messages routed to this socket
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.connections.close(self.id, &self.username);
+    }
+}
+
+/// Relays ticket changes to the channel of the ticket's project, to those who may see the ticket.
+impl Subscriber for WsConnections {
+    fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        let message = match event {
+            DomainEvent::TicketUpdated(event) => event.ticket.project.as_deref().map(|project| {
+                let channel = project_channel(project);
+                let message = ServerMessage::TicketUpdated {
+                    channel: channel.clone(),
+                    ticket: event.ticket.id,
+                    kind: event.kind,
+                    actor: event.actor.clone(),
+                    at: event.at,
+                };
+                (&event.ticket, channel, message)
+            }),
+            DomainEvent::CommentAdded { comment, ticket } => ticket.project.as_deref().map(|project| {
+                let channel = project_channel(project);
+                let message = ServerMessage::CommentAdded {
+                    channel: channel.clone(),
+                    comment: comment.clone(),
+                };
+                (ticket, channel, message)
+            }),
+            DomainEvent::UserRegistered { .. } => None,
+        };
+        Box::pin(async move {
+            match message {
+                Some((ticket, channel, message)) => self.relay(ticket, &channel, &message).await,
+                None => Ok(()),
+            }
+        })
+    }
+}
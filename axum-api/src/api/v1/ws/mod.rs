@@ -1,42 +1,339 @@
-use std::sync::Arc;
+pub mod connections;
+pub mod protocol;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
     extract::{
         State,
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
     },
+    http::HeaderMap,
     response::IntoResponse,
 };
+use chrono::Utc;
 use log::info;
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
 
-use crate::{middleware::auth::AuthenticatedUser, state::AppState};
+use crate::{
+    api::v1::ws::{
+        connections::ConnectionGuard,
+        protocol::{ClientMessage, MAX_DM_LENGTH, PROJECT_CHANNEL, ServerMessage},
+    },
+    error::AppError,
+    middleware::{
+        auth::{Claims, now_secs},
+        revalidate_claims, verify_access_token,
+    },
+    models::{Notification, NotificationKind},
+    state::AppState,
+    utils::client_ip,
+};
 
+/// Rejected with 429 when the user already has the maximum number of sockets open.
+/// The socket is closed once its token expires, unless the client sends a fresh one
+/// (`{"action": "auth", "token": ...}`), or when its session is revoked. See `protocol`
+/// for the messages exchanged.
+#[utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    tag = "me",
+    responses((status = 101, description = "Switching to the WebSocket protocol")),
+    security(("bearer_auth" = [])),
+)]
 pub async fn ws_handler(
-    AuthenticatedUser(user_id): AuthenticatedUser,
+    claims: Claims,
     State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, user_id, app_state))
+    let max = app_state.config.ws_max_connections_per_user;
+    let Some(connection) = app_state.ws_connections.try_open(&claims.sub, max) else {
+        log::warn!("Websocket rejected: {} already has {} open", claims.sub, max);
+        return AppError::TooManyRequests(format!("At most {} WebSocket connections per user", max))
+            .into_response();
+    };
+    let ip = client_ip(&headers);
+    // Subscribed before the upgrade completes, so nothing created after it is missed
+    let notifications = app_state.controller.notification.subscribe();
+    ws.on_upgrade(move |socket| {
+        handle_socket(socket, claims, ip, app_state, connection, notifications)
+    })
+    .into_response()
+}
+
+/// When the token stops being accepted.
+fn expiry(claims: &Claims) -> tokio::time::Instant {
+    let remaining = claims.exp.saturating_sub(now_secs());
+    tokio::time::Instant::now() + Duration::from_secs(remaining as u64)
+}
+
+/// Verifies a token sent to replace the current one, it must be for the same user.
+async fn refresh_token(
+    app_state: &AppState,
+    user_id: &str,
+    token: &str,
+    ip: Option<String>,
+) -> Result<Claims, String> {
+    let claims = verify_access_token(app_state, token, ip)
+        .await
+        .map_err(|_| "Invalid token".to_string())?;
+    if claims.sub != user_id {
+        return Err("Token belongs to another user".to_string());
+    }
+    Ok(claims)
+}
+
+/// Subscribes the connection to a project channel, if the user can fetch the project.
+async fn subscribe(
+    app_state: &AppState,
+    connection: u64,
+    user_id: &str,
+    channel: &str,
+) -> Result<ServerMessage, AppError> {
+    let id = channel
+        .strip_prefix(PROJECT_CHANNEL)
+        .ok_or_else(|| AppError::NotFound(format!("Channel {} not found", channel)))?;
+    let principals = app_state.controller.group.principals_of(user_id).await?;
+    let project = app_state.controller.project.get_project(id, &principals).await?;
+    let online = app_state
+        .controller
+        .project
+        .members_among(&project, &app_state.ws_connections.online_users())
+        .await?;
+    app_state.ws_connections.subscribe(connection, channel);
+    Ok(ServerMessage::Subscribed {
+        channel: channel.to_string(),
+        online,
+    })
 }
 
-async fn handle_socket(mut socket: WebSocket, user_id: String, _app_state: Arc<AppState>) {
+/// Routes a direct message to the recipient's open sockets, or stores it as a
+/// notification when there is none.
+async fn direct_message(
+    app_state: &AppState,
+    from: &str,
+    to: &str,
+    body: String,
+) -> Result<ServerMessage, AppError> {
+    if body.chars().count() > MAX_DM_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Messages are limited to {} characters",
+            MAX_DM_LENGTH
+        )));
+    }
+    app_state.controller.user.get_user(to).await?;
+
+    let message = ServerMessage::Dm {
+        from: from.to_string(),
+        body: body.clone(),
+        sent_at: Utc::now(),
+    };
+    let delivered = app_state.ws_connections.send_to_user(to, &message) > 0;
+    if !delivered {
+        app_state
+            .controller
+            .notification
+            .notify(to, NotificationKind::DirectMessage, json!({ "from": from, "body": body }), None)
+            .await?;
+    }
+    Ok(ServerMessage::DmSent {
+        to: to.to_string(),
+        delivered,
+    })
+}
+
+/// Tells subscribers of the projects the user is a member of that they came online
+/// or went offline.
+async fn announce_presence(app_state: &AppState, user_id: &str, online: bool) {
+    let principals = match app_state.controller.group.principals_of(user_id).await {
+        Ok(principals) => principals,
+        Err(e) => {
+            log::error!("Presence of {} not announced: {}", user_id, e);
+            return;
+        }
+    };
+    for channel in app_state.ws_connections.channels() {
+        let Some(id) = channel.strip_prefix(PROJECT_CHANNEL) else {
+            continue;
+        };
+        if app_state.controller.project.get_project(id, &principals).await.is_ok() {
+            let presence = ServerMessage::Presence {
+                channel: channel.clone(),
+                user: user_id.to_string(),
+                online,
+            };
+            app_state.ws_connections.broadcast(&channel, &presence);
+        }
+    }
+}
+
+// How long a closing socket waits for the client to acknowledge
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends a close frame and waits for the client's, so it still reads ours when it is
+/// not reading at the moment.
+async fn close(socket: &mut WebSocket, code: u16, reason: &'static str) {
+    if socket
+        .send(Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })))
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let _ = tokio::time::timeout(CLOSE_TIMEOUT, async {
+        while let Some(Ok(msg)) = socket.recv().await {
+            if matches!(msg, Message::Close(_)) {
+                break;
+            }
+        }
+    })
+    .await;
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    mut claims: Claims,
+    ip: Option<String>,
+    app_state: Arc<AppState>,
+    mut connection: ConnectionGuard,
+    mut notifications: broadcast::Receiver<Notification>,
+) {
     // now you have:
     // - authenticated user email
     // - entire application state
 
-    info!("Websocket connected: {}", user_id);
+    let user_id = claims.sub.clone();
+    let ping_interval = Duration::from_secs(app_state.config.ws_ping_interval);
+    let idle_timeout = Duration::from_secs(app_state.config.ws_idle_timeout);
+    let connected = Instant::now();
+    info!(
+        "Websocket connected: {} ({} open)",
+        user_id,
+        app_state.ws_connections.total()
+    );
+    if connection.first {
+        announce_presence(&app_state, &user_id, true).await;
+    }
+
+    // Liveness is checked on every ping, so timeouts are only as precise as the interval
+    let mut ping = tokio::time::interval(ping_interval);
+    ping.tick().await;
+    let mut last_pong = Instant::now();
+    let mut last_message = Instant::now();
 
-    while let Some(Ok(msg)) = socket.recv().await {
-        match msg {
-            Message::Text(t) => {
-                let reply = format!("{} said: {}", user_id, t);
-                let _ = socket.send(Message::Text(reply.into())).await;
+    // The token was checked on upgrade only, revocations are noticed on the next check
+    let mut revalidate =
+        tokio::time::interval(Duration::from_secs(app_state.config.ws_revalidate_interval));
+    revalidate.tick().await;
+    let token_expiry = tokio::time::sleep_until(expiry(&claims));
+    tokio::pin!(token_expiry);
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(t))) => {
+                    last_message = Instant::now();
+                    let reply = match serde_json::from_str::<ClientMessage>(&t) {
+                        Ok(ClientMessage::Auth { token }) => {
+                            match refresh_token(&app_state, &user_id, &token, ip.clone()).await {
+                                Ok(fresh) => {
+                                    token_expiry.as_mut().reset(expiry(&fresh));
+                                    claims = fresh;
+                                    ServerMessage::AuthOk { expires_at: claims.exp }
+                                }
+                                Err(message) => ServerMessage::AuthError { message },
+                            }
+                            .to_text()
+                        }
+                        Ok(ClientMessage::Subscribe { channel }) => {
+                            subscribe(&app_state, connection.id, &user_id, &channel)
+                                .await
+                                .unwrap_or_else(|e| ServerMessage::Error { message: e.to_string() })
+                                .to_text()
+                        }
+                        Ok(ClientMessage::Unsubscribe { channel }) => {
+                            app_state.ws_connections.unsubscribe(connection.id, &channel);
+                            ServerMessage::Unsubscribed { channel }.to_text()
+                        }
+                        Ok(ClientMessage::Dm { to, body }) => {
+                            direct_message(&app_state, &user_id, &to, body)
+                                .await
+                                .unwrap_or_else(|e| ServerMessage::Error { message: e.to_string() })
+                                .to_text()
+                        }
+                        Err(_) => format!("{} said: {}", user_id, t),
+                    };
+                    let _ = socket.send(Message::Text(reply.into())).await;
+                }
+                Some(Ok(Message::Binary(_))) => last_message = Instant::now(),
+                Some(Ok(Message::Pong(_))) => last_pong = Instant::now(),
+                Some(Ok(Message::Ping(_))) => {}
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+            },
+            _ = ping.tick() => {
+                if last_pong.elapsed() > ping_interval * 2 {
+                    app_state.ws_connections.record_timeout();
+                    close(&mut socket, close_code::AWAY, "Heartbeat timeout").await;
+                    break;
+                }
+                if last_message.elapsed() >= idle_timeout {
+                    app_state.ws_connections.record_timeout();
+                    close(&mut socket, close_code::AWAY, "Idle timeout").await;
+                    break;
+                }
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+            }
+            _ = revalidate.tick() => match revalidate_claims(&app_state, &claims, ip.clone()).await {
+                Ok(_) => {}
+                Err(AppError::Authorization(_)) => {
+                    close(&mut socket, close_code::POLICY, "Session revoked").await;
+                    break;
+                }
+                // Not the client's fault, checked again on the next tick
+                Err(e) => log::error!("Websocket revalidation failed for {}: {}", user_id, e),
+            },
+            notification = notifications.recv() => match notification {
+                Ok(notification) if notification.recipient == user_id => {
+                    let push = ServerMessage::Notification { notification };
+                    if socket.send(Message::Text(push.to_text().into())).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("Websocket of {} missed {} notifications", user_id, missed)
+                }
+                Err(RecvError::Closed) => break,
+            },
+            Some(message) = connection.receiver.recv() => {
+                if socket.send(Message::Text(message.to_text().into())).await.is_err() {
+                    break;
+                }
             }
-            Message::Close(_) => {
-                info!("Websocket disconnected: {}", user_id);
+            _ = &mut token_expiry => {
+                close(&mut socket, close_code::POLICY, "Token expired").await;
                 break;
             }
-            _ => {}
         }
     }
+
+    drop(connection);
+    if app_state.ws_connections.user_count(&user_id) == 0 {
+        announce_presence(&app_state, &user_id, false).await;
+    }
+    info!(
+        "Websocket disconnected: {} after {}s",
+        user_id,
+        connected.elapsed().as_secs()
+    );
 }
@@ -0,0 +1,63 @@
+//! JSON messages of the `/api/v1/ws` protocol: client messages are tagged by `action`,
+//! server messages by `type`. Text that is not a client message is echoed back as before.
+//!
+//! Channels are named `project:<id>` and require `FETCH` on the project. They carry
+//! presence, and changes to the project's tickets.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Comment, Notification, TicketEventKind};
+
+pub const PROJECT_CHANNEL: &str = "project:";
+
+pub fn project_channel(project: &str) -> String {
+    format!("{}{}", PROJECT_CHANNEL, project)
+}
+
+pub const MAX_DM_LENGTH: usize = 4000; // characters
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Replaces the token the socket was opened with, so it can outlive its expiry.
+    Auth { token: String },
+    Subscribe { channel: String },
+    Unsubscribe { channel: String },
+    /// Delivered to every open socket of `to`, or stored as a notification if there is none.
+    Dm { to: String, body: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    AuthOk { expires_at: usize },
+    /// The socket keeps its previous token.
+    AuthError { message: String },
+    /// Lists the members of the channel's project that are online.
+    Subscribed { channel: String, online: Vec<String> },
+    Unsubscribed { channel: String },
+    /// A member of the channel's project connected for the first time or closed their last socket.
+    Presence { channel: String, user: String, online: bool },
+    Dm { from: String, body: String, sent_at: DateTime<Utc> },
+    /// Acknowledges a `dm`, `delivered` is false if it was stored for later.
+    DmSent { to: String, delivered: bool },
+    /// Pushed as soon as a notification for the user is created.
+    Notification { notification: Notification },
+    /// A ticket of the channel's project was created or moved, by `actor`.
+    TicketUpdated {
+        channel: String,
+        ticket: i64,
+        kind: TicketEventKind,
+        actor: String,
+        at: DateTime<Utc>,
+    },
+    CommentAdded { channel: String, comment: Comment },
+    Error { message: String },
+}
+
+impl ServerMessage {
+    pub fn to_text(&self) -> String {
+        serde_json::to_string(self).expect("server messages always serialize")
+    }
+}
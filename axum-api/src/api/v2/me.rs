@@ -0,0 +1,25 @@
+use crate::{
+    api::v1,
+    error::AppError,
+    middleware::auth::{AuthenticatedUser, CurrentSession},
+    schema::{JsonOk, RevokedSessions},
+    state::AppState,
+};
+use axum::extract::State;
+use std::sync::Arc;
+
+/// Revokes every session of the user except the current one.
+/// Replaces `POST /api/v1/me/sessions/revoke-all`.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/me/sessions",
+    tag = "me",
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_other_sessions(
+    user: AuthenticatedUser,
+    session: CurrentSession,
+    state: State<Arc<AppState>>,
+) -> Result<JsonOk<RevokedSessions>, AppError> {
+    v1::me::sessions::revoke_all_sessions(user, session, state).await
+}
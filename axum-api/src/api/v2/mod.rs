@@ -0,0 +1 @@
+pub mod me;
\ No newline at end of file
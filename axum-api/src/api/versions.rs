@@ -0,0 +1,66 @@
+use utoipa::openapi::{Deprecated, OpenApi, PathItem, PathsBuilder, path::Paths};
+
+use crate::middleware::deprecation::Deprecation;
+
+pub const V1_PREFIX: &str = "/api/v1";
+pub const V2_PREFIX: &str = "/api/v2";
+
+pub const REVOKE_ALL_SESSIONS_V1: Deprecation = Deprecation {
+    since: 1792108800,        // 2026-10-16
+    sunset: Some(1807833600), // 2027-04-16
+    successor: Some("/api/v2/me/sessions"),
+};
+
+/// Deprecated v1 routes by full path. v2 does not carry them over.
+pub const DEPRECATED_V1: &[(&str, Deprecation)] =
+    &[("/api/v1/me/sessions/revoke-all", REVOKE_ALL_SESSIONS_V1)];
+
+fn is_deprecated(path: &str) -> bool {
+    DEPRECATED_V1.iter().any(|(p, _)| *p == path)
+}
+
+fn mark_deprecated(item: &mut PathItem) {
+    for operation in [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+        &mut item.patch,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        operation.deprecated = Some(Deprecated::True);
+    }
+}
+
+/// The v1 document: unversioned routes plus v1, deprecated routes flagged.
+pub fn v1_doc(api: &OpenApi) -> OpenApi {
+    let mut doc = api.clone();
+    doc.info.version = "v1".to_string();
+    doc.paths.paths.retain(|path, _| !path.starts_with(V2_PREFIX));
+    for (path, item) in doc.paths.paths.iter_mut() {
+        if is_deprecated(path) {
+            mark_deprecated(item);
+        }
+    }
+    doc
+}
+
+/// The v2 document: unversioned routes, v2-only routes, and the v1 routes that v2
+/// reuses (every non-deprecated one) moved under the v2 prefix.
+pub fn v2_doc(api: &OpenApi) -> OpenApi {
+    let mut doc = api.clone();
+    doc.info.version = "v2".to_string();
+    let mut paths = Paths::new();
+    for (path, item) in api.paths.paths.iter() {
+        let path = match path.strip_prefix(V1_PREFIX) {
+            Some(_) if is_deprecated(path) => continue,
+            Some(rest) => format!("{}{}", V2_PREFIX, rest),
+            None => path.clone(),
+        };
+        paths.merge(PathsBuilder::new().path(path, item.clone()).build());
+    }
+    doc.paths = paths;
+    doc
+}
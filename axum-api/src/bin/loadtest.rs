@@ -0,0 +1,273 @@
+//! Load test of the hot paths: logging in, listing tickets and broadcasting over
+//! WebSockets, against an in-memory backend served over HTTP on a local port.
+//!
+//! Each scenario runs for the given time with concurrent clients and reports its
+//! throughput and latencies. Built with the `loadtest` feature:
+//!
+//! ```text
+//! cargo run --release --features loadtest --bin loadtest -- --duration 10 --clients 16 --sockets 100
+//! ```
+
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum_api::{
+    api::v1::ws::protocol::{ClientMessage, ServerMessage, project_channel},
+    config::AppConfig,
+    create_app,
+    db::inmemory::InMemoryDatabase,
+    middleware::auth::Auth,
+    models::TicketEventKind,
+    schema::{ApiResponse, LoginRequest, LoginResponse},
+    seed::{self, Fixtures},
+    state::AppState,
+};
+use axum_test::{TestServer, WsMessage};
+use chrono::Utc;
+use tokio::sync::mpsc;
+
+const USERNAME: &str = "load";
+const PASSWORD: &str = "loadpassword123";
+const PROJECT: &str = "0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b";
+const TICKETS: usize = 50;
+
+fn login_request() -> LoginRequest {
+    LoginRequest {
+        user: USERNAME.to_string(),
+        password: PASSWORD.to_string(),
+        remember_me: false,
+    }
+}
+
+struct Options {
+    duration: Duration,
+    clients: usize,
+    sockets: usize,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Options {
+            duration: Duration::from_secs(10),
+            clients: 16,
+            sockets: 100,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(name) = args.next() {
+            let value = args.next().ok_or_else(|| format!("{} needs a value", name))?;
+            match name.as_str() {
+                "--duration" => options.duration = Duration::from_secs(value.parse()?),
+                "--clients" => options.clients = value.parse()?,
+                "--sockets" => options.sockets = value.parse()?,
+                _ => return Err(format!("Unknown option {}, expected --duration, --clients or --sockets", name).into()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// What a scenario measured: one latency per request, or per broadcast.
+struct Report {
+    name: &'static str,
+    elapsed: Duration,
+    latencies: Vec<Duration>,
+    errors: usize,
+}
+
+impl Report {
+    fn percentile(&self, p: f64) -> Duration {
+        let index = ((self.latencies.len() as f64 * p).ceil() as usize).saturating_sub(1);
+        self.latencies.get(index).copied().unwrap_or_default()
+    }
+
+    fn print(mut self) {
+        self.latencies.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        println!(
+            "{:<14} {:>9} {:>10.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>7}",
+            self.name,
+            self.latencies.len(),
+            self.latencies.len() as f64 / self.elapsed.as_secs_f64(),
+            ms(self.percentile(0.5)),
+            ms(self.percentile(0.9)),
+            ms(self.percentile(0.99)),
+            ms(self.percentile(1.0)),
+            self.errors
+        );
+    }
+}
+
+/// Sends requests from `clients` tasks, each waiting for its response before the
+/// next, until the time is up. `request` tells whether the response was a success.
+async fn hammer<F, Fut>(name: &'static str, options: &Options, request: F) -> Report
+where
+    F: Fn() -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = bool> + Send,
+{
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..options.clients)
+        .map(|_| {
+            let request = request.clone();
+            let deadline = start + options.duration;
+            tokio::spawn(async move {
+                let (mut latencies, mut errors) = (vec![], 0);
+                while Instant::now() < deadline {
+                    let sent = Instant::now();
+                    if !request().await {
+                        errors += 1;
+                    }
+                    latencies.push(sent.elapsed());
+                }
+                (latencies, errors)
+            })
+        })
+        .collect();
+
+    let mut report = Report {
+        name,
+        elapsed: Duration::ZERO,
+        latencies: vec![],
+        errors: 0,
+    };
+    for task in tasks {
+        let (latencies, errors) = task.await.expect("client task panicked");
+        report.latencies.extend(latencies);
+        report.errors += errors;
+    }
+    report.elapsed = start.elapsed();
+    report
+}
+
+/// Opens `sockets` WebSockets subscribed to the project's channel, then broadcasts
+/// to it one message at a time, each once every socket got the previous one.
+async fn broadcast(server: &TestServer, state: &AppState, token: &str, options: &Options) -> Report {
+    let channel = project_channel(PROJECT);
+    let (delivered, mut deliveries) = mpsc::unbounded_channel();
+    for _ in 0..options.sockets {
+        let mut socket = server
+            .get_websocket("/api/v1/ws")
+            .authorization_bearer(token)
+            .await
+            .into_websocket()
+            .await;
+        let subscribe = ClientMessage::Subscribe { channel: channel.clone() };
+        socket.send_text(serde_json::to_string(&subscribe).unwrap()).await;
+        let delivered = delivered.clone();
+        tokio::spawn(async move {
+            let mut subscribed = false;
+            while let WsMessage::Text(text) = socket.receive_message().await {
+                match serde_json::from_str(&text) {
+                    Ok(ServerMessage::Subscribed { .. }) if !subscribed => {
+                        subscribed = true;
+                        let _ = delivered.send(None);
+                    }
+                    Ok(ServerMessage::TicketUpdated { ticket, .. }) => {
+                        let _ = delivered.send(Some(ticket));
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+    for _ in 0..options.sockets {
+        deliveries.recv().await;
+    }
+
+    let start = Instant::now();
+    let mut report = Report {
+        name: "ws broadcast",
+        elapsed: Duration::ZERO,
+        latencies: vec![],
+        errors: 0,
+    };
+    for round in 1.. {
+        if start.elapsed() >= options.duration {
+            break;
+        }
+        let message = ServerMessage::TicketUpdated {
+            channel: channel.clone(),
+            ticket: round,
+            kind: TicketEventKind::Moved,
+            actor: USERNAME.to_string(),
+            at: Utc::now(),
+        };
+        let sent = Instant::now();
+        state.ws_connections.broadcast(&channel, &message);
+        let mut received = 0;
+        while received < options.sockets {
+            match tokio::time::timeout(Duration::from_secs(5), deliveries.recv()).await {
+                Ok(Some(Some(ticket))) if ticket == round => received += 1,
+                Ok(Some(_)) => {}
+                _ => {
+                    report.errors += options.sockets - received;
+                    break;
+                }
+            }
+        }
+        report.latencies.push(sent.elapsed());
+    }
+    report.elapsed = start.elapsed();
+    report
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_args()?;
+
+    let mut config = AppConfig::from_env()?;
+    config.ws_max_connections_per_user = options.sockets;
+    let auth = Auth::new(config.jwt_secret.as_bytes());
+    let state = AppState::new(config, auth, Arc::new(InMemoryDatabase::new()));
+    state.startup.ready();
+    let tickets: String = (1..=TICKETS)
+        .map(|i| format!("  - {{ created_by: {USERNAME}, title: Ticket {i}, severity: 3, project: {PROJECT} }}\n"))
+        .collect();
+    let fixtures = Fixtures::parse(&format!(
+        "users:\n  - {{ username: {USERNAME}, password: {PASSWORD} }}\n\
+         projects:\n  - {{ id: {PROJECT}, owner: {USERNAME}, acl: [{{ permissions: READ, principals: [{USERNAME}] }}] }}\n\
+         tickets:\n{tickets}"
+    ))?;
+    seed::load(&state, fixtures).await?;
+    let state = Arc::new(state);
+    let server = Arc::new(TestServer::builder().http_transport().build(create_app(state.clone()))?);
+
+    let response = server.post("/api/login").json(&login_request()).await;
+    let token = response.json::<ApiResponse<LoginResponse>>().data.token;
+
+    println!(
+        "{} clients for {}s each, {} sockets, {} tickets",
+        options.clients,
+        options.duration.as_secs(),
+        options.sockets,
+        TICKETS
+    );
+    println!(
+        "{:<14} {:>9} {:>10} {:>9} {:>9} {:>9} {:>9} {:>7}",
+        "scenario", "requests", "per sec", "p50 ms", "p90 ms", "p99 ms", "max ms", "errors"
+    );
+
+    let client = server.clone();
+    hammer("login", &options, move || {
+        let server = client.clone();
+        async move { server.post("/api/login").json(&login_request()).await.status_code().is_success() }
+    })
+    .await
+    .print();
+
+    let (client, bearer) = (server.clone(), token.clone());
+    hammer("ticket list", &options, move || {
+        let (server, bearer) = (client.clone(), bearer.clone());
+        async move {
+            let response = server.get("/api/v1/tickets").authorization_bearer(bearer).await;
+            response.status_code().is_success()
+        }
+    })
+    .await
+    .print();
+
+    broadcast(&server, &state, &token, &options).await.print();
+    Ok(())
+}
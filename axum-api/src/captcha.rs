@@ -0,0 +1,95 @@
+//! Captcha checks of anonymous submissions, see `api::public`.
+//!
+//! hCaptcha, reCAPTCHA and Cloudflare Turnstile share one verification API: the
+//! solution the widget produced is posted with the site's secret to the provider's
+//! `siteverify` URL, which answers whether it is valid. Set `CAPTCHA_VERIFY_URL` to
+//! the provider's and `CAPTCHA_SECRET` to the site's secret.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::{error::AppError, utils::BoxFuture};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks captcha solutions with the provider.
+pub trait CaptchaVerifier: Send + Sync {
+    /// Whether the solution is valid, `ip` being the address of whoever solved it.
+    fn verify<'a>(&'a self, solution: &'a str, ip: Option<&'a str>) -> BoxFuture<'a, Result<bool, AppError>>;
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+}
+
+/// Verifies solutions with a `siteverify` endpoint.
+pub struct HttpCaptchaVerifier {
+    client: reqwest::Client,
+    url: String,
+    secret: String,
+}
+
+impl HttpCaptchaVerifier {
+    /// Without a secret, every solution fails rather than every one passing.
+    pub fn new(url: String, secret: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("HTTP client builds with a timeout only");
+        Self { client, url, secret }
+    }
+}
+
+impl CaptchaVerifier for HttpCaptchaVerifier {
+    fn verify<'a>(&'a self, solution: &'a str, ip: Option<&'a str>) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            if self.secret.is_empty() {
+                return Err(AppError::Unavailable("Captchas can't be checked, CAPTCHA_SECRET is not set".to_string()));
+            }
+            let mut form = vec![("secret", self.secret.as_str()), ("response", solution)];
+            if let Some(ip) = ip {
+                form.push(("remoteip", ip));
+            }
+            let response = self
+                .client
+                .post(&self.url)
+                .form(&form)
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Captcha provider unreachable: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::Internal(anyhow!(
+                    "Captcha provider answered {}",
+                    response.status()
+                )));
+            }
+            let verdict: SiteVerifyResponse = response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Captcha provider answer unreadable: {}", e)))?;
+            Ok(verdict.success)
+        })
+    }
+}
+
+/// Accepts one solution only, for tests and local development.
+pub struct FixedCaptchaVerifier {
+    solution: String,
+}
+
+impl FixedCaptchaVerifier {
+    pub fn new(solution: &str) -> Self {
+        Self {
+            solution: solution.to_string(),
+        }
+    }
+}
+
+impl CaptchaVerifier for FixedCaptchaVerifier {
+    fn verify<'a>(&'a self, solution: &'a str, _ip: Option<&'a str>) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { Ok(solution == self.solution) })
+    }
+}
@@ -1,15 +1,74 @@
-use std::env;
+use std::{env, net::IpAddr};
 
 use dotenvy::dotenv;
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 
-use crate::error::AppError;
+use crate::{
+    api::public::PORTAL_SUBMISSION_PATH,
+    error::AppError,
+    middleware::{
+        auth::ONE_WEEK,
+        rate_limit::{RateLimitRule, parse_rules},
+    },
+    utils::encryption::EncryptionKey,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct RuntimeConfig {
     pub user_login_allowed: bool,
 }
 
+/// Number of security events from one source within the window that triggers an audit warning.
+#[derive(Clone, Debug)]
+pub struct AlertThreshold {
+    pub count: usize,
+    pub window_secs: i64,
+}
+
+/// Who can see `/swagger-ui` and `/api-docs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwaggerAccess {
+    Public,
+    Management, // requires the management token
+    Disabled,
+}
+
+impl std::str::FromStr for SwaggerAccess {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "public" => Ok(Self::Public),
+            "mgmt" | "management" => Ok(Self::Management),
+            "disabled" | "off" => Ok(Self::Disabled),
+            other => Err(format!("Invalid SWAGGER_ACCESS value: {}", other)),
+        }
+    }
+}
+
+/// What serves `/api/v1/search`: the database's own search, or an index kept next to
+/// it, see `search`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchBackend {
+    Database,
+    Embedded,    // in the server's memory, rebuilt at startup
+    Meilisearch, // at MEILISEARCH_URL
+}
+
+impl std::str::FromStr for SearchBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "database" | "db" => Ok(Self::Database),
+            "embedded" => Ok(Self::Embedded),
+            "meilisearch" => Ok(Self::Meilisearch),
+            other => Err(format!("Invalid SEARCH_BACKEND value: {}", other)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub jwt_secret: String,
@@ -19,6 +78,55 @@ pub struct AppConfig {
     pub management_token: String,
     pub host: String,
     pub port: u16,
+    pub grpc_port: u16, // only used with the grpc feature
+    pub totp_issuer: String,
+    pub allowed_email_domains: Vec<String>, // empty means any domain
+    pub require_email_verification: bool,
+    pub access_token_lifetime: usize,  // seconds
+    pub refresh_token_lifetime: usize, // seconds, also the session lifetime
+    pub remember_me_lifetime: usize,   // seconds, session lifetime with remember_me
+    pub auth_cache_ttl: u64, // seconds a validated session is trusted without database reads, 0 checks every request
+    pub security_alert: AlertThreshold,
+    pub swagger_access: SwaggerAccess,
+    pub idempotency_ttl: usize, // seconds an Idempotency-Key response is replayed
+    pub draft_ttl: usize,       // seconds a ticket draft is kept after it was last saved
+    pub draft_max_bytes: usize, // size of a draft's payload, as JSON
+    pub preference_max_bytes: usize, // size of a preference namespace's value, as JSON
+    pub preference_max_namespaces: usize, // preference namespaces per user
+    pub inmemory_max_entities: Option<usize>, // per collection, in-memory backend only
+    pub inmemory_ttl: Option<u64>, // seconds, in-memory backend only
+    pub db_cache_size: Option<usize>, // users and projects cached each, none disables the cache
+    pub db_cache_ttl: u64, // seconds a cached read is served, how stale it can get
+    pub db_startup_wait: u64, // seconds to keep retrying an unreachable database at startup
+    pub db_max_concurrency: Option<usize>, // database calls running at once, unlimited if unset
+    pub db_max_queue: usize,  // calls waiting for their turn before new ones get a 503
+    pub db_breaker_threshold: Option<u32>, // consecutive database failures opening the circuit
+    pub db_breaker_cooldown: u64, // seconds the circuit stays open before a probe
+    pub request_timeout: Option<u64>, // seconds a request, database calls included, may take
+    pub log_bodies: Option<usize>, // bytes of each JSON body to log, for development only
+    pub trusted_proxies: Vec<IpNet>, // peers whose X-Forwarded-For and X-Real-IP are believed
+    pub rate_limits: Vec<RateLimitRule>,
+    pub portal_rate_limit: RateLimitRule, // of public ticket submissions, by IP, see `api::public`
+    pub captcha_verify_url: String,       // `siteverify` endpoint of the captcha provider
+    pub captcha_secret: String,
+    pub ws_ping_interval: u64,               // seconds between server pings
+    pub ws_idle_timeout: u64,                // seconds without client messages before closing
+    pub ws_max_connections_per_user: usize,
+    pub ws_revalidate_interval: u64, // seconds between checks that a socket's session is still valid
+    pub escalation_interval: u64,    // seconds between checks for stale tickets, 0 disables them
+    pub outbox: bool,                // store domain events and deliver them in the background
+    pub outbox_interval: u64,        // seconds between deliveries of stored events
+    pub soft_delete_retention: u32,  // days deleted entities can be restored before they are purged
+    pub search_backend: SearchBackend,
+    pub meilisearch_url: Option<String>, // required by the meilisearch backend
+    pub meilisearch_api_key: String,
+    pub inbound_email_project: Option<String>, // project emails are filed in, none disables them
+    pub inbound_email_signing_key: String,     // Mailgun webhook signing key
+    pub seed_file: Option<String>,             // fixtures loaded on startup, see `seed`
+    pub encryption_keys: Vec<EncryptionKey>,   // the first encrypts, the others only decrypt
+    pub encrypted_metadata_keys: Vec<String>,  // user metadata stored encrypted, needs a key
+    pub vault_address: Option<String>,         // resolves `vault:` secret references, see `secrets`
+    pub vault_token: String,
 }
 
 impl AppConfig {
@@ -39,39 +147,329 @@ impl AppConfig {
         // Load .env file if it exists
         dotenv().ok();
 
-        let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "default_jwt_secret_change_in_production".to_string());
+        let jwt_secret = secret_var("JWT_SECRET")?
+            .unwrap_or_else(|| "default_jwt_secret_change_in_production".to_string());
 
-        let management_token = env::var("MGMT_TOKEN")
-            .unwrap_or_else(|_| "default_mgmt_token_change_in_production".to_string());
+        let management_token = secret_var("MGMT_TOKEN")?
+            .unwrap_or_else(|| "default_mgmt_token_change_in_production".to_string());
 
         let database_connection_string =
-            env::var("DB_CONNECTION_STRING").unwrap_or_else(|_| "./data".to_string());
+            secret_var("DB_CONNECTION_STRING")?.unwrap_or_else(|| "./data".to_string());
         
         let database_name =
             env::var("DB_NAME").unwrap_or_else(|_| "unnamed".to_string());
 
-        let client_api_keys = env::var("CLIENT_API_KEYS")
-            .unwrap_or_else(|_| String::new())
+        let client_api_keys = secret_var("CLIENT_API_KEYS")?
+            .unwrap_or_default()
             .split(':')
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string())
             .collect();
 
+        let totp_issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "axum-api".to_string());
+
+        let allowed_email_domains = env::var("ALLOWED_EMAIL_DOMAINS")
+            .unwrap_or_else(|_| String::new())
+            .split(':')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase())
+            .collect();
+
+        let require_email_verification = env::var("REQUIRE_EMAIL_VERIFICATION")
+            .map(|s| s.to_lowercase().contains("true"))
+            .unwrap_or(false);
+
+        let access_token_lifetime = env::var("ACCESS_TOKEN_LIFETIME")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(ONE_WEEK))?;
+
+        let refresh_token_lifetime = env::var("REFRESH_TOKEN_LIFETIME")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(ONE_WEEK))?;
+
+        let remember_me_lifetime = env::var("REMEMBER_ME_LIFETIME")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(ONE_WEEK * 4))?;
+
+        let auth_cache_ttl = env::var("AUTH_CACHE_TTL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(0))?;
+
+        let security_alert = AlertThreshold {
+            count: env::var("SECURITY_ALERT_THRESHOLD")
+                .map(|s| s.parse::<usize>())
+                .unwrap_or(Ok(50))?,
+            window_secs: env::var("SECURITY_ALERT_WINDOW")
+                .map(|s| s.parse::<i64>())
+                .unwrap_or(Ok(300))?,
+        };
+
+        let swagger_access = env::var("SWAGGER_ACCESS")
+            .unwrap_or_else(|_| "mgmt".to_string())
+            .parse::<SwaggerAccess>()?;
+
+        let idempotency_ttl = env::var("IDEMPOTENCY_TTL")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(60 * 60 * 24))?;
+
+        let draft_ttl = env::var("DRAFT_TTL")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(60 * 60 * 24 * 7))?;
+
+        let draft_max_bytes = env::var("DRAFT_MAX_BYTES")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(64 * 1024))?;
+
+        let preference_max_bytes = env::var("PREFERENCE_MAX_BYTES")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(16 * 1024))?;
+
+        let preference_max_namespaces = env::var("PREFERENCE_MAX_NAMESPACES")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(32))?;
+
+        let inmemory_max_entities = env::var("INMEMORY_MAX_ENTITIES")
+            .ok()
+            .map(|s| s.parse::<usize>())
+            .transpose()?;
+
+        let inmemory_ttl = env::var("INMEMORY_TTL")
+            .ok()
+            .map(|s| s.parse::<u64>())
+            .transpose()?;
+
+        let db_startup_wait = env::var("DB_STARTUP_WAIT")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(30))?;
+
+        let db_max_concurrency = env::var("DB_MAX_CONCURRENCY")
+            .ok()
+            .map(|s| s.parse::<usize>())
+            .transpose()?;
+        if db_max_concurrency == Some(0) {
+            return Err("DB_MAX_CONCURRENCY must be at least 1".into());
+        }
+
+        let db_max_queue = env::var("DB_MAX_QUEUE")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(100))?;
+
+        let db_breaker_threshold = env::var("DB_BREAKER_THRESHOLD")
+            .ok()
+            .map(|s| s.parse::<u32>())
+            .transpose()?;
+        if db_breaker_threshold == Some(0) {
+            return Err("DB_BREAKER_THRESHOLD must be at least 1".into());
+        }
+
+        let db_breaker_cooldown = env::var("DB_BREAKER_COOLDOWN")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(30))?;
+
+        let request_timeout = env::var("REQUEST_TIMEOUT")
+            .ok()
+            .map(|s| s.parse::<u64>())
+            .transpose()?;
+
+        let db_cache_size = env::var("DB_CACHE_SIZE")
+            .ok()
+            .map(|s| s.parse::<usize>())
+            .transpose()?;
+
+        let db_cache_ttl = env::var("DB_CACHE_TTL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(10))?;
+
+        let log_bodies = env::var("LOG_BODIES")
+            .ok()
+            .map(|s| s.parse::<usize>())
+            .transpose()?;
+
+        // Comma separated addresses or CIDR ranges, e.g. `10.0.0.0/8,127.0.0.1`
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<IpNet>()
+                    .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("Invalid TRUSTED_PROXIES entry: {}", s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Rules inline, or a file with one rule per line
+        let rate_limits = match (env::var("RATE_LIMITS"), env::var("RATE_LIMITS_FILE")) {
+            (Ok(rules), _) => parse_rules(&rules)?,
+            (Err(_), Ok(path)) => parse_rules(&std::fs::read_to_string(path)?)?,
+            _ => Vec::new(),
+        };
+
+        // Count and window only, e.g. `5/hour`: submissions are always counted by IP
+        let portal_rate_limit = format!(
+            "POST {}: {}/ip",
+            PORTAL_SUBMISSION_PATH,
+            env::var("PUBLIC_PORTAL_RATE_LIMIT").unwrap_or_else(|_| "5/hour".to_string())
+        )
+        .parse::<RateLimitRule>()?;
+
+        let captcha_verify_url = env::var("CAPTCHA_VERIFY_URL")
+            .unwrap_or_else(|_| "https://api.hcaptcha.com/siteverify".to_string());
+        let captcha_secret = secret_var("CAPTCHA_SECRET")?.unwrap_or_default();
+
+        let ws_ping_interval = env::var("WS_PING_INTERVAL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(30))?;
+
+        let ws_idle_timeout = env::var("WS_IDLE_TIMEOUT")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(60 * 10))?;
+
+        let ws_max_connections_per_user = env::var("WS_MAX_CONNECTIONS_PER_USER")
+            .map(|s| s.parse::<usize>())
+            .unwrap_or(Ok(5))?;
+
+        let ws_revalidate_interval = env::var("WS_REVALIDATE_INTERVAL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(60))?;
+
+        let escalation_interval = env::var("ESCALATION_INTERVAL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(60 * 5))?;
+
+        let outbox = env::var("OUTBOX")
+            .map(|s| s.to_lowercase().contains("true"))
+            .unwrap_or(false);
+
+        let outbox_interval = env::var("OUTBOX_INTERVAL")
+            .map(|s| s.parse::<u64>())
+            .unwrap_or(Ok(1))?;
+
+        let soft_delete_retention = env::var("SOFT_DELETE_RETENTION_DAYS")
+            .map(|s| s.parse::<u32>())
+            .unwrap_or(Ok(30))?;
+
+        let search_backend = env::var("SEARCH_BACKEND")
+            .unwrap_or_else(|_| "database".to_string())
+            .parse::<SearchBackend>()?;
+        let meilisearch_url = env::var("MEILISEARCH_URL").ok().filter(|s| !s.is_empty());
+        let meilisearch_api_key = secret_var("MEILISEARCH_API_KEY")?.unwrap_or_default();
+        if search_backend == SearchBackend::Meilisearch && meilisearch_url.is_none() {
+            return Err("SEARCH_BACKEND=meilisearch needs MEILISEARCH_URL".into());
+        }
+
+        let inbound_email_project = env::var("INBOUND_EMAIL_PROJECT").ok().filter(|s| !s.is_empty());
+        let inbound_email_signing_key = secret_var("INBOUND_EMAIL_SIGNING_KEY")?.unwrap_or_default();
+
+        let seed_file = env::var("SEED_FILE").ok().filter(|s| !s.is_empty());
+
+        // Comma separated `id:hex`, newest first: rotating a key means prepending one
+        let encryption_keys = secret_var("ENCRYPTION_KEYS")?
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| EncryptionKey::parse(s.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let encrypted_metadata_keys: Vec<String> = env::var("ENCRYPTED_METADATA_KEYS")
+            .unwrap_or_default()
+            .split(':')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase())
+            .collect();
+        if !encrypted_metadata_keys.is_empty() && encryption_keys.is_empty() {
+            return Err("ENCRYPTED_METADATA_KEYS needs ENCRYPTION_KEYS".into());
+        }
+
+        let vault_address = env::var("VAULT_ADDR").ok().filter(|s| !s.is_empty());
+        let vault_token = secret_var("VAULT_TOKEN")?.unwrap_or_default();
+
         let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
 
         let port = env::var("PORT")
             .unwrap_or_else(|_| "3069".to_string())
             .parse::<u16>()?;
 
+        let grpc_port = env::var("GRPC_PORT")
+            .unwrap_or_else(|_| "50051".to_string())
+            .parse::<u16>()?;
+
         Ok(Self {
             jwt_secret,
             database_connection_string,
             client_api_keys,
             host,
             port,
+            grpc_port,
             management_token,
-            database_name
+            database_name,
+            totp_issuer,
+            allowed_email_domains,
+            require_email_verification,
+            access_token_lifetime,
+            refresh_token_lifetime,
+            remember_me_lifetime,
+            auth_cache_ttl,
+            security_alert,
+            swagger_access,
+            idempotency_ttl,
+            draft_ttl,
+            draft_max_bytes,
+            preference_max_bytes,
+            preference_max_namespaces,
+            inmemory_max_entities,
+            inmemory_ttl,
+            db_cache_size,
+            db_cache_ttl,
+            db_startup_wait,
+            db_max_concurrency,
+            db_max_queue,
+            db_breaker_threshold,
+            db_breaker_cooldown,
+            request_timeout,
+            log_bodies,
+            trusted_proxies,
+            rate_limits,
+            portal_rate_limit,
+            captcha_verify_url,
+            captcha_secret,
+            ws_ping_interval,
+            ws_idle_timeout,
+            ws_max_connections_per_user,
+            ws_revalidate_interval,
+            escalation_interval,
+            outbox,
+            outbox_interval,
+            soft_delete_retention,
+            search_backend,
+            meilisearch_url,
+            meilisearch_api_key,
+            inbound_email_project,
+            inbound_email_signing_key,
+            seed_file,
+            encryption_keys,
+            encrypted_metadata_keys,
+            vault_address,
+            vault_token,
         })
     }
 }
+
+/// A secret given inline as `NAME`, or as `NAME_FILE`, the path of a file holding it
+/// as Docker and Kubernetes mount secrets. The file's trailing newline isn't part of it.
+fn secret_var(name: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let inline = env::var(name).ok();
+    let file = env::var(format!("{}_FILE", name)).ok().filter(|s| !s.is_empty());
+    match (inline, file) {
+        (Some(_), Some(_)) => Err(format!("Set either {} or {}_FILE, not both", name, name).into()),
+        (Some(value), None) => Ok(Some(value)),
+        (None, Some(path)) => Ok(Some(read_secret_file(&path)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+pub fn read_secret_file(path: &str) -> Result<String, AppError> {
+    std::fs::read_to_string(path)
+        .map(|content| content.trim_end_matches(['\n', '\r']).to_string())
+        .map_err(|e| AppError::Validation(format!("Secret file {} can't be read: {}", path, e)))
+}
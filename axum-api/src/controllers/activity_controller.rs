@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    acl,
+    db::{ActivityFilter, DatabaseInterface},
+    error::AppError,
+    events::{DomainEvent, Subscriber},
+    models::{Activity, ActivityKind, Permissions, Project, Ticket, TicketEventKind},
+    schema::{ActivityQuery, ListResponse, PageParams},
+    utils::BoxFuture,
+};
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+/// The filter a feed query asks for, its kinds given comma-separated.
+fn filter(query: &ActivityQuery, page: &PageParams) -> Result<ActivityFilter, AppError> {
+    let kinds = query
+        .kind
+        .iter()
+        .flat_map(|kinds| kinds.split(','))
+        .map(str::trim)
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| {
+            serde_json::from_value(kind.into())
+                .map_err(|_| AppError::Validation(format!("Unknown activity kind: {}", kind)))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(ActivityFilter {
+        kinds,
+        cursor: page.cursor.clone(),
+        limit: Some(page.limit_or(DEFAULT_LIMIT, MAX_LIMIT)),
+        ..Default::default()
+    })
+}
+
+fn activity(kind: ActivityKind, ticket: &Ticket, actor: &str, at: DateTime<Utc>) -> Activity {
+    Activity {
+        id: uuid::Uuid::now_v7().to_string(),
+        kind,
+        actor: actor.to_string(),
+        ticket: ticket.id,
+        title: ticket.title.clone(),
+        project: ticket.project.clone(),
+        from_status: None,
+        to_status: None,
+        comment: None,
+        at,
+    }
+}
+
+pub struct ActivityController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl ActivityController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Activity on the tickets of a project, newest first. Projects the principals
+    /// can't fetch are reported as missing.
+    pub async fn project_feed(
+        &self,
+        id: &str,
+        principals: &[String],
+        query: &ActivityQuery,
+        page: &PageParams,
+    ) -> Result<ListResponse<Activity>, AppError> {
+        let project = self.db.projects().get_project(id).await?;
+        let ancestors = acl::ancestors(self.db.as_ref(), &project).await?;
+        if !acl::project_allows(&project, &ancestors, principals, Permissions::FETCH) {
+            return Err(AppError::NotFound(format!("Project {} not found", id)));
+        }
+        let filter = ActivityFilter {
+            project: Some(id.to_string()),
+            ..filter(query, page)?
+        };
+        self.feed(principals, &filter).await
+    }
+
+    /// What the user did, newest first.
+    pub async fn user_feed(
+        &self,
+        username: &str,
+        principals: &[String],
+        query: &ActivityQuery,
+        page: &PageParams,
+    ) -> Result<ListResponse<Activity>, AppError> {
+        let filter = ActivityFilter {
+            actor: Some(username.to_string()),
+            ..filter(query, page)?
+        };
+        self.feed(principals, &filter).await
+    }
+
+    /// A page of the matching activities, less those on tickets the principals can't
+    /// fetch now or that were deleted. Pages are cut before that, so one may hold fewer
+    /// than the limit and still be followed by others.
+    async fn feed(&self, principals: &[String], filter: &ActivityFilter) -> Result<ListResponse<Activity>, AppError> {
+        let repo = self.db.activity();
+        let activities = repo.list_activity(filter).await?;
+        let total = repo.count_activity(filter).await?;
+        let next_cursor = match filter.limit {
+            Some(limit) if activities.len() == limit => activities.last().map(|a| a.id.clone()),
+            _ => None,
+        };
+
+        let ids: Vec<String> = activities.iter().map(|a| a.ticket.to_string()).collect();
+        let tickets = self.db.tickets().get_tickets(&ids).await?.found;
+        let projects = self.db.projects().list_projects().await?;
+        let fetches = |project: &Project, ticket| {
+            let ancestors = acl::ancestors_among(&projects, project);
+            acl::ticket_allows(project, &ancestors, ticket, principals, Permissions::FETCH)
+        };
+        let items = activities
+            .into_iter()
+            .filter(|activity| {
+                tickets.iter().find(|t| t.id == activity.ticket).is_some_and(|ticket| match &ticket.project {
+                    None => true,
+                    Some(id) => projects
+                        .iter()
+                        .find(|p| p.id.to_string() == *id)
+                        .is_some_and(|project| fetches(project, ticket)),
+                })
+            })
+            .collect();
+        Ok(ListResponse {
+            items,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+/// Records created tickets, status changes and comments.
+impl Subscriber for ActivityController {
+    fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let activity = match event {
+                DomainEvent::TicketUpdated(event) => match (event.kind, event.previous_status) {
+                    (TicketEventKind::Created, _) => {
+                        activity(ActivityKind::TicketCreated, &event.ticket, &event.actor, event.at)
+                    }
+                    (TicketEventKind::Moved, Some(previous)) if previous != event.ticket.status => Activity {
+                        from_status: Some(previous),
+                        to_status: Some(event.ticket.status),
+                        ..activity(ActivityKind::StatusChanged, &event.ticket, &event.actor, event.at)
+                    },
+                    // Moved within its column, or to another board
+                    (TicketEventKind::Moved, _) => return Ok(()),
+                },
+                DomainEvent::CommentAdded { comment, ticket } => Activity {
+                    comment: Some(comment.id.clone()),
+                    ..activity(ActivityKind::CommentAdded, ticket, &comment.author, comment.created_at)
+                },
+                DomainEvent::UserRegistered { .. } => return Ok(()),
+            };
+            self.db.activity().create_activity(activity).await
+        })
+    }
+}
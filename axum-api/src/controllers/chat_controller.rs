@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::{ChatChannel, ChatEvent, Project, Ticket},
+    schema::ChatChannelRequest,
+};
+
+// Tickets at this level or more severe count as high severity unless configured
+const DEFAULT_HIGH_SEVERITY: u8 = 2;
+
+pub struct ChatController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl ChatController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    pub async fn channels(&self) -> Result<Vec<ChatChannel>, AppError> {
+        self.db.chat_channels().list_chat_channels().await
+    }
+
+    /// Posts the project's events to a channel, in place of the one it had.
+    pub async fn set_channel(&self, project: &str, req: ChatChannelRequest) -> Result<ChatChannel, AppError> {
+        let project: Project = self.db.projects().get_project(project).await?;
+        let webhook_url = req.webhook_url.trim();
+        let valid_url = matches!(webhook_url.split_once("://"), Some(("https" | "http", host)) if !host.is_empty());
+        if !valid_url {
+            return Err(AppError::Validation("Webhook URL must be an http(s) URL".to_string()));
+        }
+        let mut events: Vec<ChatEvent> = Vec::new();
+        for event in req.events {
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+        if events.is_empty() {
+            return Err(AppError::Validation("At least one event is required".to_string()));
+        }
+        let high_severity = req.high_severity.unwrap_or(DEFAULT_HIGH_SEVERITY);
+        if !project.severity_scale().iter().any(|s| s.level == high_severity) {
+            return Err(AppError::Validation(format!(
+                "Severity level {} is not on the project's scale",
+                high_severity
+            )));
+        }
+
+        let id = project.id.to_string();
+        let channel = ChatChannel {
+            project: id.clone(),
+            platform: req.platform,
+            webhook_url: webhook_url.to_string(),
+            events,
+            high_severity,
+            updated_at: Utc::now(),
+        };
+        let repo = self.db.chat_channels();
+        match repo.get_chat_channel(&id).await {
+            Ok(_) => repo.update_chat_channel(&id, channel.clone()).await?,
+            Err(AppError::NotFound(_)) => repo.create_chat_channel(channel.clone()).await?,
+            Err(e) => return Err(e),
+        }
+        Ok(channel)
+    }
+
+    pub async fn remove_channel(&self, project: &str) -> Result<(), AppError> {
+        self.db.chat_channels().delete_chat_channel(project).await
+    }
+
+    /// The channel of the ticket's project with the event it is posted as, if the
+    /// channel takes it. A new ticket is posted once, as `HighSeverity` if it is and
+    /// the channel takes those.
+    pub async fn route(&self, ticket: &Ticket, event: ChatEvent) -> Result<Option<(ChatChannel, ChatEvent)>, AppError> {
+        let Some(project) = &ticket.project else {
+            return Ok(None);
+        };
+        let channel = match self.db.chat_channels().get_chat_channel(project).await {
+            Ok(channel) => channel,
+            Err(AppError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let candidates: &[ChatEvent] = match event {
+            ChatEvent::TicketCreated | ChatEvent::HighSeverity if ticket.severity.level <= channel.high_severity => {
+                &[ChatEvent::HighSeverity, ChatEvent::TicketCreated]
+            }
+            ChatEvent::TicketCreated | ChatEvent::HighSeverity => &[ChatEvent::TicketCreated],
+            ChatEvent::SlaBreach => &[ChatEvent::SlaBreach],
+        };
+        let event = candidates.iter().copied().find(|e| channel.events.contains(e));
+        Ok(event.map(|event| (channel, event)))
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use serde_json::Value;
+
+use crate::{db::DatabaseInterface, error::AppError, models::Draft, utils::sha256_hex};
+
+const MAX_KEY_LENGTH: usize = 64;
+
+/// Drafts are scoped to their owner, so two users can use the same keys.
+pub fn draft_id(owner: &str, key: &str) -> String {
+    sha256_hex(&format!("{}:{}", owner, key))
+}
+
+pub struct DraftController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl DraftController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Saves the payload under the key, replacing what was saved there before. The
+    /// draft is kept for `ttl` seconds, counted again from every save. Payloads are
+    /// JSON objects of at most `max_bytes`.
+    pub async fn save(
+        &self,
+        owner: &str,
+        key: &str,
+        payload: Value,
+        ttl: usize,
+        max_bytes: usize,
+    ) -> Result<Draft, AppError> {
+        if key.is_empty() || key.chars().count() > MAX_KEY_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Draft keys are 1 to {} characters long",
+                MAX_KEY_LENGTH
+            )));
+        }
+        if !payload.is_object() {
+            return Err(AppError::Validation("A draft is a JSON object".to_string()));
+        }
+        let size = serde_json::to_vec(&payload)?.len();
+        if size > max_bytes {
+            return Err(AppError::Validation(format!(
+                "Draft is {} bytes, at most {} are kept",
+                size, max_bytes
+            )));
+        }
+
+        let now = Utc::now();
+        let draft = Draft {
+            id: draft_id(owner, key),
+            owner: owner.to_string(),
+            key: key.to_string(),
+            payload,
+            updated_at: now,
+            expires_at: now + Duration::seconds(ttl as i64),
+        };
+        self.db.drafts().put_draft(draft.clone()).await?;
+        Ok(draft)
+    }
+
+    /// The owner's draft under the key. Expired drafts are missing, even before
+    /// they are purged.
+    pub async fn get(&self, owner: &str, key: &str) -> Result<Draft, AppError> {
+        let draft = self.db.drafts().get_draft(&draft_id(owner, key)).await?;
+        if draft.expires_at <= Utc::now() {
+            return Err(AppError::NotFound(format!("Draft {} not found", key)));
+        }
+        Ok(draft)
+    }
+
+    /// Deletes the owner's draft under the key, if there is one.
+    pub async fn discard(&self, owner: &str, key: &str) -> Result<(), AppError> {
+        discard(self.db.as_ref(), owner, key).await
+    }
+}
+
+/// Deletes a draft, for the controllers creating what it was a draft of.
+pub async fn discard(db: &dyn DatabaseInterface, owner: &str, key: &str) -> Result<(), AppError> {
+    match db.drafts().delete_draft(&draft_id(owner, key)).await {
+        Ok(()) | Err(AppError::NotFound(_)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -1,13 +1,101 @@
 use std::sync::Arc;
 
-use crate::db::DatabaseInterface;
+use crate::{
+    acl::{self, AclCache},
+    db::DatabaseInterface,
+    error::AppError,
+    models::Group,
+    schema::{PrincipalKind, PrincipalSuggestion},
+};
 
 pub struct GroupController {
     pub db: Arc<dyn DatabaseInterface>,
+    acl_cache: Arc<AclCache>,
 }
 
 impl GroupController {
-    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<dyn DatabaseInterface>, acl_cache: Arc<AclCache>) -> Self {
+        Self { db, acl_cache }
+    }
+
+    /// The groups, without the aliases left by renames.
+    pub async fn list_groups(&self) -> Result<Vec<Group>, AppError> {
+        let mut groups = self.db.groups().list_groups().await?;
+        groups.retain(|g| g.renamed_to.is_none());
+        Ok(groups)
+    }
+
+    pub async fn get_group(&self, gid: &str) -> Result<Group, AppError> {
+        self.db.groups().get_group(gid).await
+    }
+
+    /// Principals an ACL entry can name for the user: the username and the ids of their
+    /// groups. Cached until the user joins or leaves a group.
+    pub async fn principals_of(&self, username: &str) -> Result<Vec<String>, AppError> {
+        if let Some(principals) = self.acl_cache.principals_of(username) {
+            return Ok(principals);
+        }
+        let mut principals = vec![username.to_string()];
+        principals.extend(
+            self.db
+                .groups()
+                .list_groups()
+                .await?
+                .into_iter()
+                .filter(|g| g.principals.iter().any(|p| p == username))
+                .map(|g| g.gid),
+        );
+        self.acl_cache.store_principals(username, &principals);
+        Ok(principals)
+    }
+
+    /// Users and groups whose id or name starts with `query` (case-insensitive), among
+    /// those the user can see, see `acl::visible_principals`.
+    pub async fn suggest(
+        &self,
+        username: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<PrincipalSuggestion>, AppError> {
+        let groups = self.db.groups().list_groups().await?;
+        let principals = self.principals_of(username).await?;
+        let projects = self.db.projects().list_projects().await?;
+        let visible = acl::visible_principals(&projects, &groups, &principals);
+
+        let query = query.trim().to_lowercase();
+        let matches = |id: &str, name: &str| {
+            id.to_lowercase().starts_with(&query) || name.to_lowercase().starts_with(&query)
+        };
+        // ACLs may name principals that no longer exist, those aren't found
+        let user_ids: Vec<String> = visible
+            .iter()
+            .filter(|id| !groups.iter().any(|g| g.gid == **id))
+            .cloned()
+            .collect();
+        let users = self.db.users().get_users(&user_ids).await?.found;
+        let mut suggestions = Vec::new();
+        for id in visible {
+            if let Some(group) = groups.iter().find(|g| g.gid == id) {
+                if matches(&group.gid, &group.name) {
+                    suggestions.push(PrincipalSuggestion {
+                        id,
+                        kind: PrincipalKind::Group,
+                        name: group.name.clone(),
+                    });
+                }
+            } else if let Some(user) = users.iter().find(|u| u.username == id)
+                && matches(&user.username, &user.personal.name)
+            {
+                suggestions.push(PrincipalSuggestion {
+                    id,
+                    kind: PrincipalKind::User,
+                    name: user.personal.name.clone(),
+                });
+            }
+            if suggestions.len() == limit {
+                break;
+            }
+        }
+        Ok(suggestions)
     }
 }
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::IdempotencyRecord,
+    utils::sha256_hex,
+};
+
+pub struct IdempotencyController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+pub enum IdempotencyStatus {
+    /// First request with this key: run it, then `complete` or `abandon` the record.
+    Started,
+    /// The key was used before for the same request: replay the stored response.
+    Replay(IdempotencyRecord),
+}
+
+impl IdempotencyController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Keys are scoped to the principal, so two users can't see each other's responses.
+    pub fn record_id(principal: &str, key: &str) -> String {
+        sha256_hex(&format!("{}:{}", principal, key))
+    }
+
+    /// Claims the key for a request, or returns the response recorded for it.
+    /// Expired records are replaced.
+    pub async fn begin(
+        &self,
+        id: &str,
+        fingerprint: String,
+        ttl: usize,
+    ) -> Result<IdempotencyStatus, AppError> {
+        let now = Utc::now();
+        let record = IdempotencyRecord {
+            id: id.to_string(),
+            fingerprint,
+            status: None,
+            content_type: None,
+            body: String::new(),
+            created_at: now,
+            expires_at: now + Duration::seconds(ttl as i64),
+        };
+
+        match self.db.idempotency().create_record(record.clone()).await {
+            Ok(()) => return Ok(IdempotencyStatus::Started),
+            Err(AppError::Conflict(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let existing = self.db.idempotency().get_record(id).await?;
+        if existing.expires_at <= now {
+            self.db.idempotency().delete_record(id).await?;
+            self.db.idempotency().create_record(record).await?;
+            return Ok(IdempotencyStatus::Started);
+        }
+        if existing.fingerprint != record.fingerprint {
+            return Err(AppError::BadRequest(
+                "Idempotency-Key was already used for a different request".to_string(),
+            ));
+        }
+        match existing.status {
+            Some(_) => Ok(IdempotencyStatus::Replay(existing)),
+            None => Err(AppError::Conflict(
+                "A request with this Idempotency-Key is still in progress".to_string(),
+            )),
+        }
+    }
+
+    /// Stores the response of the first request for replay.
+    pub async fn complete(
+        &self,
+        id: &str,
+        status: u16,
+        content_type: Option<String>,
+        body: String,
+    ) -> Result<(), AppError> {
+        let mut record = self.db.idempotency().get_record(id).await?;
+        record.status = Some(status);
+        record.content_type = content_type;
+        record.body = body;
+        self.db.idempotency().update_record(id, record).await
+    }
+
+    /// Records a value meant to be used once, such as a webhook's nonce, for `ttl`
+    /// seconds. False if it was recorded before: the request is a replay.
+    pub async fn claim_once(&self, id: &str, ttl: usize) -> Result<bool, AppError> {
+        match self.begin(id, String::new(), ttl).await {
+            Ok(IdempotencyStatus::Started) => Ok(true),
+            Ok(IdempotencyStatus::Replay(_)) | Err(AppError::Conflict(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Releases the key so the request can be retried (e.g. after a server error).
+    pub async fn abandon(&self, id: &str) -> Result<(), AppError> {
+        match self.db.idempotency().delete_record(id).await {
+            Ok(()) | Err(AppError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
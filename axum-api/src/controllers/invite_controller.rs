@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    acl::AclCache,
+    controllers::principal_controller::current_group,
+    db::DatabaseInterface,
+    error::AppError,
+    events::{DomainEvent, EventBus},
+    models::{Invite, User},
+    utils::{random_token, sha256_hex},
+};
+
+const INVITE_TOKEN_LENGTH: usize = 32;
+
+pub struct InviteController {
+    pub db: Arc<dyn DatabaseInterface>,
+    acl_cache: Arc<AclCache>,
+    events: Arc<EventBus>,
+}
+
+impl InviteController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, acl_cache: Arc<AclCache>, events: Arc<EventBus>) -> Self {
+        Self { db, acl_cache, events }
+    }
+
+    /// Creates a single-use invite, returns the plain token (only its hash is stored).
+    pub async fn create_invite(
+        &self,
+        groups: Vec<String>,
+        expires_in: Duration,
+    ) -> Result<(String, Invite), AppError> {
+        for gid in &groups {
+            self.db
+                .groups()
+                .get_group(gid)
+                .await
+                .map_err(|_| AppError::Validation(format!("Group {} does not exist", gid)))?;
+        }
+
+        let token = random_token(INVITE_TOKEN_LENGTH);
+        let now = Utc::now();
+        let invite = Invite {
+            id: sha256_hex(&token),
+            groups,
+            created_at: now,
+            expires_at: now + expires_in,
+            used_by: None,
+            used_at: None,
+        };
+        self.db.invites().create_invite(invite.clone()).await?;
+
+        Ok((token, invite))
+    }
+
+    /// Creates the user from an invite token, consumes the invite and
+    /// adds the user to the invite's groups. The invite is claimed before the user is
+    /// created, so that concurrent redemptions can't both succeed, and released again
+    /// if the user can't be created.
+    pub async fn redeem(&self, token: &str, user: User) -> Result<(), AppError> {
+        let id = sha256_hex(token);
+        let invite = self
+            .db
+            .invites()
+            .get_invite(&id)
+            .await
+            .map_err(|_| AppError::Authorization("Invalid invite".to_string()))?;
+
+        if invite.used_by.is_some() {
+            return Err(AppError::Authorization("Invite already used".to_string()));
+        }
+        if invite.expires_at < Utc::now() {
+            return Err(AppError::Authorization("Invite expired".to_string()));
+        }
+
+        let username = user.username.clone();
+        let claimed = match self.db.invites().claim_invite(&id, &username, Utc::now()).await {
+            Ok(claimed) => claimed,
+            Err(AppError::Conflict(_)) => return Err(AppError::Authorization("Invite already used".to_string())),
+            Err(e) => return Err(e),
+        };
+        if let Err(e) = self.db.users().create_user(user).await {
+            if let Err(release) = self.db.invites().update_invite(&id, invite).await {
+                log::error!("Invite {} stays claimed by {} who wasn't created: {}", id, username, release);
+            }
+            return Err(e);
+        }
+
+        // Groups renamed since the invite was made are joined under their new id
+        for gid in &claimed.groups {
+            match current_group(self.db.as_ref(), gid).await {
+                Ok(mut group) => {
+                    if !group.principals.contains(&username) {
+                        group.principals.push(username.clone());
+                        self.db.groups().update_group(&group.gid.clone(), group).await?;
+                        self.acl_cache.invalidate_user(&username);
+                    }
+                }
+                Err(e) => log::warn!("Invite group {} unavailable: {}", gid, e),
+            }
+        }
+
+        self.events
+            .publish(DomainEvent::UserRegistered {
+                username,
+                at: Utc::now(),
+            })
+            .await
+    }
+}
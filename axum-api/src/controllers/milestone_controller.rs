@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    acl,
+    db::DatabaseInterface,
+    error::AppError,
+    models::{Milestone, MilestoneState, Permissions, Project, Ticket, TicketStatus},
+    schema::{BurndownDay, ClosedMilestoneResponse, CreateMilestoneRequest},
+};
+
+pub struct MilestoneController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl MilestoneController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// The project if the principals hold `permission` on it. Projects they can't
+    /// fetch are reported as missing.
+    async fn project(&self, id: &str, principals: &[String], permission: Permissions) -> Result<Project, AppError> {
+        let project = self.db.projects().get_project(id).await?;
+        let ancestors = acl::ancestors(self.db.as_ref(), &project).await?;
+        let permissions = acl::project_permissions(&project, &ancestors, principals);
+        if !permissions.contains(Permissions::FETCH) {
+            return Err(AppError::NotFound(format!("Project {} not found", id)));
+        }
+        if !permissions.contains(permission) {
+            return Err(AppError::Authorization(format!("Not allowed to modify project {}", id)));
+        }
+        Ok(project)
+    }
+
+    /// Fetches a milestone of a project the principals can fetch.
+    async fn milestone(&self, id: &str, principals: &[String], permission: Permissions) -> Result<Milestone, AppError> {
+        let milestone = self.db.milestones().get_milestone(id).await?;
+        match self.project(&milestone.project, principals, permission).await {
+            Err(AppError::NotFound(_)) => Err(AppError::NotFound(format!("Milestone {} not found", id))),
+            Err(e) => Err(e),
+            Ok(_) => Ok(milestone),
+        }
+    }
+
+    async fn tickets_of(&self, milestone: &str) -> Result<Vec<Ticket>, AppError> {
+        Ok(self
+            .db
+            .tickets()
+            .list_tickets()
+            .await?
+            .into_iter()
+            .filter(|t| t.milestone.as_deref() == Some(milestone))
+            .collect())
+    }
+
+    /// Adds an open milestone to the project, the principals need `MODIFY`.
+    pub async fn create(
+        &self,
+        project: &str,
+        principals: &[String],
+        req: CreateMilestoneRequest,
+    ) -> Result<Milestone, AppError> {
+        self.project(project, principals, Permissions::MODIFY).await?;
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("Name is required".to_string()));
+        }
+        if req.end < req.start {
+            return Err(AppError::Validation("A milestone can't end before it starts".to_string()));
+        }
+        let milestone = Milestone {
+            id: uuid::Uuid::now_v7().to_string(),
+            project: project.to_string(),
+            name: name.to_string(),
+            start: req.start,
+            end: req.end,
+            state: MilestoneState::Open,
+            created_at: Utc::now(),
+            closed_at: None,
+        };
+        self.db.milestones().create_milestone(milestone.clone()).await?;
+        Ok(milestone)
+    }
+
+    /// Milestones of the project, by start date.
+    pub async fn list(&self, project: &str, principals: &[String]) -> Result<Vec<Milestone>, AppError> {
+        self.project(project, principals, Permissions::FETCH).await?;
+        self.db.milestones().list_milestones(project).await
+    }
+
+    pub async fn get(&self, id: &str, principals: &[String]) -> Result<Milestone, AppError> {
+        self.milestone(id, principals, Permissions::FETCH).await
+    }
+
+    /// Plans a ticket into an open milestone of its project, or out of any with `None`.
+    /// The principals need `MODIFY` on the project.
+    pub async fn assign(
+        &self,
+        ticket: &str,
+        principals: &[String],
+        milestone: Option<String>,
+    ) -> Result<Ticket, AppError> {
+        let mut ticket = self.db.tickets().get_ticket(ticket).await?;
+        let Some(project) = ticket.project.clone() else {
+            return Err(AppError::Validation(format!(
+                "Ticket {} has no project to plan it in",
+                ticket.id
+            )));
+        };
+        self.project(&project, principals, Permissions::MODIFY).await?;
+        if let Some(id) = &milestone {
+            let milestone = self.db.milestones().get_milestone(id).await?;
+            if milestone.project != project {
+                return Err(AppError::Validation(format!(
+                    "Milestone {} belongs to another project",
+                    id
+                )));
+            }
+            if milestone.state == MilestoneState::Closed {
+                return Err(AppError::Conflict(format!("Milestone {} is closed", id)));
+            }
+        }
+        ticket.milestone = milestone;
+        ticket.last_modification = Utc::now();
+        self.db
+            .tickets()
+            .update_ticket(&ticket.id.to_string(), ticket.clone())
+            .await?;
+        Ok(ticket)
+    }
+
+    /// Open tickets of the milestone at the end of each day, from its start to its end
+    /// or today, whichever comes first. Derived from when tickets were created and
+    /// resolved, so tickets count from their creation even if planned in later.
+    pub async fn burndown(&self, id: &str, principals: &[String]) -> Result<Vec<BurndownDay>, AppError> {
+        let milestone = self.get(id, principals).await?;
+        let tickets = self.tickets_of(&milestone.id).await?;
+        let last = milestone.end.min(Utc::now().date_naive());
+        Ok(milestone
+            .start
+            .iter_days()
+            .take_while(|day| *day <= last)
+            .map(|day| BurndownDay {
+                day,
+                open: tickets
+                    .iter()
+                    .filter(|t| t.creation_date.date_naive() <= day)
+                    .filter(|t| t.resolved_at.is_none_or(|r| r.date_naive() > day))
+                    .count(),
+            })
+            .collect())
+    }
+
+    /// Closes the milestone and moves its unfinished tickets to `rollover_to`, by default
+    /// the project's next open milestone, or out of any milestone if there is none.
+    /// The principals need `MODIFY` on the project.
+    pub async fn close(
+        &self,
+        id: &str,
+        principals: &[String],
+        rollover_to: Option<String>,
+    ) -> Result<ClosedMilestoneResponse, AppError> {
+        let mut milestone = self.milestone(id, principals, Permissions::MODIFY).await?;
+        if milestone.state == MilestoneState::Closed {
+            return Err(AppError::Conflict(format!("Milestone {} is already closed", id)));
+        }
+        let others: Vec<Milestone> = self
+            .db
+            .milestones()
+            .list_milestones(&milestone.project)
+            .await?
+            .into_iter()
+            .filter(|m| m.id != milestone.id)
+            .collect();
+        let target = match rollover_to {
+            Some(target) => {
+                let Some(next) = others.iter().find(|m| m.id == target) else {
+                    return Err(AppError::Validation(format!(
+                        "Milestone {} is not another milestone of the project",
+                        target
+                    )));
+                };
+                if next.state == MilestoneState::Closed {
+                    return Err(AppError::Conflict(format!("Milestone {} is closed", target)));
+                }
+                Some(target)
+            }
+            None => others
+                .iter()
+                .find(|m| m.state == MilestoneState::Open && m.start >= milestone.start)
+                .map(|m| m.id.clone()),
+        };
+
+        let mut rolled_over = Vec::new();
+        for mut ticket in self.tickets_of(&milestone.id).await? {
+            if matches!(ticket.status, TicketStatus::Open | TicketStatus::InProgress) {
+                ticket.milestone = target.clone();
+                ticket.last_modification = Utc::now();
+                self.db
+                    .tickets()
+                    .update_ticket(&ticket.id.to_string(), ticket.clone())
+                    .await?;
+                rolled_over.push(ticket.id);
+            }
+        }
+        rolled_over.sort();
+
+        milestone.state = MilestoneState::Closed;
+        milestone.closed_at = Some(Utc::now());
+        self.db.milestones().update_milestone(id, milestone.clone()).await?;
+        Ok(ClosedMilestoneResponse {
+            milestone,
+            rolled_over,
+            rollover_to: target,
+        })
+    }
+}
@@ -1,26 +1,103 @@
 use std::sync::Arc;
 
-use crate::{controllers::{group_controller::GroupController, project_controller::ProjectController, ticket_controller::TicketController, user_controller::UserController}, db::DatabaseInterface};
+use crate::{acl::AclCache, events::EventBus, controllers::{activity_controller::ActivityController, chat_controller::ChatController, draft_controller::DraftController, group_controller::GroupController, idempotency_controller::IdempotencyController, invite_controller::InviteController, milestone_controller::MilestoneController, notification_controller::NotificationController, outbox_controller::OutboxController, preference_controller::PreferenceController, principal_controller::PrincipalController, project_controller::ProjectController, render_controller::RenderController, search_controller::SearchController, security_controller::SecurityController, service_account_controller::ServiceAccountController, session_controller::{SessionController, ValidatedSessions}, share_controller::ShareController, stats_controller::StatsController, ticket_controller::TicketController, trash_controller::TrashController, two_factor_controller::TwoFactorController, user_controller::{MetadataEncryption, UserController}}, db::DatabaseInterface, search::{SearchIndex, SearchIndexer}, utils::encryption::FieldCipher};
 pub mod user_controller;
 pub mod project_controller;
 pub mod group_controller;
 pub mod ticket_controller;
+pub mod session_controller;
+pub mod two_factor_controller;
+pub mod invite_controller;
+pub mod security_controller;
+pub mod idempotency_controller;
+pub mod stats_controller;
+pub mod notification_controller;
+pub mod milestone_controller;
+pub mod chat_controller;
+pub mod service_account_controller;
+pub mod outbox_controller;
+pub mod trash_controller;
+pub mod search_controller;
+pub mod activity_controller;
+pub mod draft_controller;
+pub mod render_controller;
+pub mod share_controller;
+pub mod preference_controller;
+pub mod principal_controller;
 
 pub struct Controller {
     pub user: UserController,
     pub project: ProjectController,
     pub group: GroupController,
     pub ticket: TicketController,
+    pub session: SessionController,
+    pub two_factor: TwoFactorController,
+    pub invite: InviteController,
+    pub security: SecurityController,
+    pub idempotency: IdempotencyController,
+    pub stats: StatsController,
+    pub notification: Arc<NotificationController>, // also subscribed to the event bus
+    pub activity: Arc<ActivityController>,         // same
+    pub milestone: MilestoneController,
+    pub chat: ChatController,
+    pub service_account: ServiceAccountController,
+    pub outbox: OutboxController,
+    pub trash: TrashController,
+    pub search: SearchController,
+    pub draft: DraftController,
+    pub share: ShareController,
+    pub preference: PreferenceController,
+    pub principal: PrincipalController,
+    pub render: RenderController,
+    pub acl_cache: Arc<AclCache>, // shared by the controllers resolving or changing access
 }
 
 
 impl Controller {
-    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+    /// Builds the controllers around the database, publishing to `events`. Searches go
+    /// to `index` when there is one, kept current from the events. 2FA secrets are
+    /// sealed with `cipher` when there is one.
+    pub fn new(
+        db: Arc<dyn DatabaseInterface>,
+        events: Arc<EventBus>,
+        encryption: Option<MetadataEncryption>,
+        cipher: Option<FieldCipher>,
+        index: Option<Arc<dyn SearchIndex>>,
+    ) -> Self {
+        let acl_cache = Arc::new(AclCache::new());
+        let validated_sessions = Arc::new(ValidatedSessions::default());
+        let notification = Arc::new(NotificationController::new(db.clone()));
+        events.subscribe(notification.clone());
+        let activity = Arc::new(ActivityController::new(db.clone()));
+        events.subscribe(activity.clone());
+        if let Some(index) = &index {
+            events.subscribe(Arc::new(SearchIndexer::new(db.clone(), index.clone())));
+        }
         Self {
-            user: UserController::new(db.clone()),
-            project: ProjectController::new(db.clone()),
-            group: GroupController::new(db.clone()),
-            ticket: TicketController::new(db.clone()),
+            user: UserController::new(db.clone(), validated_sessions.clone(), events.clone(), encryption),
+            project: ProjectController::new(db.clone(), acl_cache.clone()),
+            group: GroupController::new(db.clone(), acl_cache.clone()),
+            ticket: TicketController::new(db.clone(), events.clone()),
+            session: SessionController::new(db.clone(), validated_sessions),
+            two_factor: TwoFactorController::new(db.clone(), cipher),
+            invite: InviteController::new(db.clone(), acl_cache.clone(), events.clone()),
+            security: SecurityController::new(db.clone()),
+            idempotency: IdempotencyController::new(db.clone()),
+            stats: StatsController::new(db.clone()),
+            notification,
+            activity,
+            milestone: MilestoneController::new(db.clone()),
+            chat: ChatController::new(db.clone()),
+            service_account: ServiceAccountController::new(db.clone()),
+            outbox: OutboxController::new(db.clone(), events),
+            trash: TrashController::new(db.clone(), acl_cache.clone()),
+            draft: DraftController::new(db.clone()),
+            share: ShareController::new(db.clone()),
+            preference: PreferenceController::new(db.clone()),
+            principal: PrincipalController::new(db.clone(), acl_cache.clone()),
+            render: RenderController::new(db.clone()),
+            search: SearchController::new(db, index),
+            acl_cache,
         }
     }
 }
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::{Value, json};
+use tokio::sync::broadcast;
+
+use crate::{
+    db::{DatabaseInterface, NotificationFilter},
+    error::AppError,
+    events::{DomainEvent, Subscriber},
+    models::{EscalationPolicy, Notification, NotificationKind, Ticket, TicketEventKind},
+    utils::BoxFuture,
+};
+
+// Notifications buffered per subscriber before a slow one starts missing them
+const EVENT_BUFFER: usize = 256;
+
+pub struct NotificationController {
+    pub db: Arc<dyn DatabaseInterface>,
+    events: broadcast::Sender<Notification>,
+}
+
+impl NotificationController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self { db, events }
+    }
+
+    /// Live feed of notifications created through this instance, for every recipient.
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.events.subscribe()
+    }
+
+    /// Stores an unread notification for the recipient.
+    pub async fn notify(
+        &self,
+        recipient: &str,
+        kind: NotificationKind,
+        payload: Value,
+        link: Option<String>,
+    ) -> Result<Notification, AppError> {
+        let notification = Notification {
+            id: uuid::Uuid::now_v7().to_string(),
+            recipient: recipient.to_string(),
+            kind,
+            payload,
+            link,
+            read: false,
+            created_at: Utc::now(),
+        };
+        self.db
+            .notifications()
+            .create_notification(notification.clone())
+            .await?;
+        // Fails only when nobody is listening
+        let _ = self.events.send(notification.clone());
+        Ok(notification)
+    }
+
+    /// Users behind a principal: a group's members, or the user itself.
+    async fn users_of(&self, principal: &str) -> Result<Vec<String>, AppError> {
+        match self.db.groups().get_group(principal).await {
+            Ok(group) => Ok(group.principals),
+            Err(AppError::NotFound(_)) if self.db.users().exists_user(principal).await? => {
+                Ok(vec![principal.to_string()])
+            }
+            Err(AppError::NotFound(_)) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Notifies the assignee and the mentioned principals of a new ticket, groups
+    /// expanded to their members, except whoever created it. Someone both assigned
+    /// and mentioned only hears about the assignment.
+    pub async fn notify_ticket(&self, ticket: &Ticket, actor: &str) -> Result<(), AppError> {
+        let mut notified = vec![actor.to_string()];
+        let targets = std::iter::once((NotificationKind::Assignment, &ticket.assigned_to))
+            .chain(ticket.mentioned.iter().map(|m| (NotificationKind::Mention, m)));
+        for (kind, principal) in targets {
+            for user in self.users_of(principal).await? {
+                if notified.contains(&user) {
+                    continue;
+                }
+                let payload = json!({ "ticket": ticket.id, "title": ticket.title, "by": actor });
+                let link = format!("/api/v1/tickets/{}", ticket.id);
+                self.notify(&user, kind, payload, Some(link)).await?;
+                notified.push(user);
+            }
+        }
+        Ok(())
+    }
+
+    /// Notifies the members of the policy's group that the ticket was escalated to them.
+    /// Never fails: the escalation stands even if nobody hears about it.
+    pub async fn ticket_escalated(&self, ticket: &Ticket, policy: &EscalationPolicy) {
+        if let Err(e) = self.notify_escalation(ticket, policy).await {
+            log::error!("Failed to notify about escalated ticket {}: {}", ticket.id, e);
+        }
+    }
+
+    async fn notify_escalation(&self, ticket: &Ticket, policy: &EscalationPolicy) -> Result<(), AppError> {
+        for user in self.users_of(&policy.group).await? {
+            let payload = json!({ "ticket": ticket.id, "title": ticket.title, "policy": policy.name });
+            let link = format!("/api/v1/tickets/{}", ticket.id);
+            self.notify(&user, NotificationKind::Escalation, payload, Some(link)).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns a page of the user's notifications, the total number of matches and
+    /// the cursor of the next page.
+    pub async fn list(
+        &self,
+        recipient: &str,
+        filter: &NotificationFilter,
+    ) -> Result<(Vec<Notification>, usize, Option<String>), AppError> {
+        let repo = self.db.notifications();
+        let notifications = repo.list_notifications(recipient, filter).await?;
+        let total = repo.count_notifications(recipient, filter).await?;
+        let next_cursor = match filter.limit {
+            Some(limit) if notifications.len() == limit => notifications.last().map(|n| n.id.clone()),
+            _ => None,
+        };
+        Ok((notifications, total, next_cursor))
+    }
+
+    pub async fn unread_count(&self, recipient: &str) -> Result<usize, AppError> {
+        let unread = NotificationFilter {
+            unread: Some(true),
+            ..Default::default()
+        };
+        self.db.notifications().count_notifications(recipient, &unread).await
+    }
+
+    /// Marks one of the user's notifications as read, other users' are reported as missing.
+    pub async fn mark_read(&self, recipient: &str, id: &str) -> Result<(), AppError> {
+        let notification = self.db.notifications().get_notification(id).await?;
+        if notification.recipient != recipient {
+            return Err(AppError::NotFound(format!("Notification {} not found", id)));
+        }
+        if !notification.read {
+            self.db
+                .notifications()
+                .update_notification(id, Notification { read: true, ..notification })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Marks every unread notification of the user as read, returns how many there were.
+    pub async fn mark_all_read(&self, recipient: &str) -> Result<usize, AppError> {
+        self.mark_read_where(recipient, |_| true).await
+    }
+
+    /// Marks the user's unread notifications about the ticket as read, once they saw
+    /// it for themselves. Returns how many there were.
+    pub async fn mark_ticket_read(&self, recipient: &str, ticket: i64) -> Result<usize, AppError> {
+        let link = format!("/api/v1/tickets/{}", ticket);
+        self.mark_read_where(recipient, |n| n.link.as_ref() == Some(&link)).await
+    }
+
+    async fn mark_read_where(
+        &self,
+        recipient: &str,
+        matches: impl Fn(&Notification) -> bool,
+    ) -> Result<usize, AppError> {
+        let unread = NotificationFilter {
+            unread: Some(true),
+            ..Default::default()
+        };
+        let notifications: Vec<Notification> = self
+            .db
+            .notifications()
+            .list_notifications(recipient, &unread)
+            .await?
+            .into_iter()
+            .filter(|n| matches(n))
+            .collect();
+        for notification in &notifications {
+            self.db
+                .notifications()
+                .update_notification(
+                    &notification.id,
+                    Notification {
+                        read: true,
+                        ..notification.clone()
+                    },
+                )
+                .await?;
+        }
+        Ok(notifications.len())
+    }
+}
+
+/// Notifies about new tickets, see `notify_ticket`.
+impl Subscriber for NotificationController {
+    fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            match event {
+                DomainEvent::TicketUpdated(event) if event.kind == TicketEventKind::Created => {
+                    self.notify_ticket(&event.ticket, &event.actor).await
+                }
+                _ => Ok(()),
+            }
+        })
+    }
+}
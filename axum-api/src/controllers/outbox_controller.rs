@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    db::{DatabaseInterface, OutboxFilter},
+    error::AppError,
+    events::EventBus,
+    models::{OutboxEntry, OutboxStatus},
+};
+
+// Entries delivered per dispatch, the rest wait for the next one
+const DISPATCH_BATCH: usize = 100;
+// Failed deliveries of an entry before it is given up until replayed
+pub const MAX_ATTEMPTS: u32 = 10;
+
+pub struct OutboxController {
+    pub db: Arc<dyn DatabaseInterface>,
+    events: Arc<EventBus>,
+}
+
+impl OutboxController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, events: Arc<EventBus>) -> Self {
+        Self { db, events }
+    }
+
+    pub async fn entries(&self, filter: &OutboxFilter) -> Result<Vec<OutboxEntry>, AppError> {
+        self.db.outbox().list_outbox_entries(filter).await
+    }
+
+    /// Delivers the oldest pending entries, in order. An entry whose delivery fails
+    /// stays pending for the next dispatch, until it has failed `MAX_ATTEMPTS` times.
+    /// Returns how many entries were delivered.
+    pub async fn dispatch_pending(&self) -> Result<usize, AppError> {
+        let filter = OutboxFilter {
+            status: Some(OutboxStatus::Pending),
+            limit: Some(DISPATCH_BATCH),
+            ..OutboxFilter::default()
+        };
+        let mut delivered = 0;
+        for mut entry in self.entries(&filter).await? {
+            entry.attempts += 1;
+            match self.events.deliver(&entry.event).await {
+                Ok(()) => {
+                    entry.status = OutboxStatus::Delivered;
+                    entry.delivered_at = Some(Utc::now());
+                    entry.last_error = None;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    log::warn!("Delivery of outbox entry {} failed (attempt {}): {}", entry.id, entry.attempts, e);
+                    if entry.attempts >= MAX_ATTEMPTS {
+                        entry.status = OutboxStatus::Failed;
+                    }
+                    entry.last_error = Some(e.to_string());
+                }
+            }
+            self.db.outbox().update_outbox_entry(&entry.id, entry.clone()).await?;
+            if entry.status == OutboxStatus::Delivered {
+                self.events.broadcast(entry.event);
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Queues an entry for delivery again, whatever became of it.
+    pub async fn replay(&self, id: &str) -> Result<OutboxEntry, AppError> {
+        let mut entry = self.db.outbox().get_outbox_entry(id).await?;
+        entry.status = OutboxStatus::Pending;
+        entry.attempts = 0;
+        entry.last_error = None;
+        entry.delivered_at = None;
+        self.db.outbox().update_outbox_entry(id, entry.clone()).await?;
+        Ok(entry)
+    }
+
+    /// Queues every entry created since then for delivery again. Returns how many
+    /// were queued.
+    pub async fn replay_since(&self, since: DateTime<Utc>) -> Result<usize, AppError> {
+        let filter = OutboxFilter {
+            since: Some(since),
+            ..OutboxFilter::default()
+        };
+        let mut replayed = 0;
+        for entry in self.entries(&filter).await? {
+            if entry.status != OutboxStatus::Pending {
+                self.replay(&entry.id).await?;
+                replayed += 1;
+            }
+        }
+        Ok(replayed)
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::Value;
+
+use crate::{db::DatabaseInterface, error::AppError, models::Preference, utils::sha256_hex};
+
+const MAX_NAMESPACE_LENGTH: usize = 64;
+
+/// Preferences are scoped to their owner, so two users can use the same namespaces.
+fn preference_id(owner: &str, namespace: &str) -> String {
+    sha256_hex(&format!("{}:{}", owner, namespace))
+}
+
+/// Namespaces appear in URLs, they are 1 to 64 lowercase letters, digits, `-`, `_` or `.`.
+fn validate_namespace(namespace: &str) -> Result<(), AppError> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.');
+    if namespace.is_empty() || namespace.len() > MAX_NAMESPACE_LENGTH || !namespace.chars().all(allowed) {
+        return Err(AppError::Validation(format!(
+            "Preference namespaces are 1 to {} lowercase letters, digits, '-', '_' or '.'",
+            MAX_NAMESPACE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub struct PreferenceController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl PreferenceController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// The owner's namespaces, by name.
+    pub async fn list(&self, owner: &str) -> Result<Vec<Preference>, AppError> {
+        self.db.preferences().list_preferences(owner).await
+    }
+
+    pub async fn get(&self, owner: &str, namespace: &str) -> Result<Preference, AppError> {
+        match self.db.preferences().get_preference(&preference_id(owner, namespace)).await {
+            Err(AppError::NotFound(_)) => Err(AppError::NotFound(format!("Preferences {} not found", namespace))),
+            result => result,
+        }
+    }
+
+    /// Replaces the value of the namespace at `version`, the one the client read, or
+    /// creates the namespace when `version` is 0. Fails with `Conflict` when it was
+    /// saved again since. Values are at most `max_bytes` as JSON, and a user has at
+    /// most `max_namespaces`.
+    pub async fn save(
+        &self,
+        owner: &str,
+        namespace: &str,
+        value: Value,
+        version: u64,
+        max_bytes: usize,
+        max_namespaces: usize,
+    ) -> Result<Preference, AppError> {
+        validate_namespace(namespace)?;
+        let size = serde_json::to_vec(&value)?.len();
+        if size > max_bytes {
+            return Err(AppError::Validation(format!(
+                "Preferences are {} bytes, at most {} are kept",
+                size, max_bytes
+            )));
+        }
+        if version == 0 && self.list(owner).await?.len() >= max_namespaces {
+            return Err(AppError::Validation(format!(
+                "At most {} preference namespaces are kept per user",
+                max_namespaces
+            )));
+        }
+
+        let preference = Preference {
+            id: preference_id(owner, namespace),
+            owner: owner.to_string(),
+            namespace: namespace.to_string(),
+            value,
+            version: version + 1,
+            updated_at: Utc::now(),
+        };
+        match self.db.preferences().put_preference(preference.clone()).await {
+            Ok(()) => Ok(preference),
+            Err(AppError::Conflict(_) | AppError::NotFound(_)) if version > 0 => Err(AppError::Conflict(format!(
+                "Preferences {} changed since version {}, read them again",
+                namespace, version
+            ))),
+            Err(AppError::Conflict(_)) => Err(AppError::Conflict(format!(
+                "Preferences {} already exist, save them with their version",
+                namespace
+            ))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes the owner's namespace, if there is one.
+    pub async fn delete(&self, owner: &str, namespace: &str) -> Result<(), AppError> {
+        match self.db.preferences().delete_preference(&preference_id(owner, namespace)).await {
+            Ok(()) | Err(AppError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
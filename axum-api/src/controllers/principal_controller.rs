@@ -0,0 +1,368 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    acl::AclCache,
+    db::DatabaseInterface,
+    error::AppError,
+    models::{AccessControlStore, Comment, Group, Project, Session, Ticket, User},
+    schema::{MergeUsersResponse, RenamePrincipalResponse},
+    validation::naming::validate_username,
+};
+
+// Aliases followed at most, renames of renames are rare
+const MAX_ALIAS_HOPS: usize = 8;
+
+/// Replaces `from` with `to` among the principals, keeping the first of them if both
+/// are there. Whether `from` was there.
+fn repoint(principals: &mut Vec<String>, from: &str, to: &str) -> bool {
+    if !principals.iter().any(|p| p == from) {
+        return false;
+    }
+    let mut seen = false;
+    principals.retain_mut(|p| {
+        if p == from {
+            *p = to.to_string();
+        }
+        let keep = !(p == to && seen);
+        seen |= p == to;
+        keep
+    });
+    true
+}
+
+/// Grants and denials of `from` go to `to`, which ends up with both its own and them.
+fn repoint_acl(store: &mut AccessControlStore, from: &str, to: &str) -> bool {
+    let mut changed = false;
+    for acl in store.list.iter_mut().chain(store.deny.iter_mut()) {
+        changed |= repoint(&mut acl.principals, from, to);
+    }
+    if changed {
+        store.last_mod_date = Utc::now();
+    }
+    changed
+}
+
+fn repoint_ticket(ticket: &mut Ticket, from: &str, to: &str) -> bool {
+    let mut changed = repoint(&mut ticket.mentioned, from, to);
+    for field in [&mut ticket.created_by, &mut ticket.assigned_to] {
+        if field == from {
+            *field = to.to_string();
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn repoint_project(project: &mut Project, from: &str, to: &str) -> bool {
+    let mut changed = repoint_acl(&mut project.acl, from, to);
+    for group in project.tickets.iter_mut() {
+        changed |= repoint_acl(&mut group.acl, from, to);
+    }
+    if project.owner.as_deref() == Some(from) {
+        project.owner = Some(to.to_string());
+        changed = true;
+    }
+    changed
+}
+
+/// The user a username names, following the aliases renames left behind. Tokens and
+/// links from before a rename carry the old name.
+pub async fn current_user(db: &dyn DatabaseInterface, username: &str) -> Result<User, AppError> {
+    let mut user = db.users().get_user(username).await?;
+    for _ in 0..MAX_ALIAS_HOPS {
+        let Some(renamed_to) = user.renamed_to.clone() else {
+            return Ok(user);
+        };
+        user = db.users().get_user(&renamed_to).await?;
+    }
+    Err(AppError::NotFound(format!("User {} not found", username)))
+}
+
+/// The group a group id names, following the aliases renames left behind. Invites
+/// from before a rename carry the old id.
+pub async fn current_group(db: &dyn DatabaseInterface, gid: &str) -> Result<Group, AppError> {
+    let mut group = db.groups().get_group(gid).await?;
+    for _ in 0..MAX_ALIAS_HOPS {
+        let Some(renamed_to) = group.renamed_to.clone() else {
+            return Ok(group);
+        };
+        group = db.groups().get_group(&renamed_to).await?;
+    }
+    Err(AppError::NotFound(format!("Group {} not found", gid)))
+}
+
+/// An entity to write, or as it was before, to put back if a later write fails.
+enum Entity {
+    Ticket(Ticket),
+    Comment(Comment),
+    Group(Group),
+    Project(Project),
+    Session(Session),
+    User(User),
+}
+
+/// How many entities of each kind named a principal.
+#[derive(Default)]
+struct References {
+    tickets: usize,
+    comments: usize,
+    groups: usize,
+    projects: usize,
+}
+
+/// Changes that go through every place naming a user or group.
+pub struct PrincipalController {
+    pub db: Arc<dyn DatabaseInterface>,
+    acl_cache: Arc<AclCache>,
+    changes: tokio::sync::Mutex<()>, // held for the whole of a merge or rename
+}
+
+impl PrincipalController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, acl_cache: Arc<AclCache>) -> Self {
+        Self {
+            db,
+            acl_cache,
+            changes: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    async fn update(&self, entity: Entity) -> Result<(), AppError> {
+        match entity {
+            Entity::Ticket(ticket) => self.db.tickets().update_ticket(&ticket.id.to_string(), ticket).await,
+            Entity::Comment(comment) => self.db.comments().update_comment(&comment.id.clone(), comment).await,
+            Entity::Group(group) => self.db.groups().update_group(&group.gid.clone(), group).await,
+            Entity::Project(project) => self.db.projects().update_project(&project.id.to_string(), project).await,
+            Entity::Session(session) => self.db.sessions().update_session(&session.id.clone(), session).await,
+            Entity::User(user) => self.db.users().update_user(&user.username.clone(), user).await,
+        }
+    }
+
+    /// Makes the updates, each given with the entity as it was, in order. If one
+    /// fails, those made before it are undone.
+    async fn update_all(&self, updates: Vec<(Entity, Entity)>) -> Result<(), AppError> {
+        let mut done: Vec<Entity> = Vec::new();
+        for (original, updated) in updates {
+            if let Err(e) = self.update(updated).await {
+                for original in done.into_iter().rev() {
+                    if let Err(cleanup) = self.update(original).await {
+                        log::error!("Update not undone after a failed one: {}", cleanup);
+                    }
+                }
+                return Err(e);
+            }
+            done.push(original);
+        }
+        Ok(())
+    }
+
+    /// The updates making tickets, comments, group memberships, ACL entries and project
+    /// ownerships naming `from` name `to` instead.
+    async fn repoint_references(&self, from: &str, to: &str) -> Result<(Vec<(Entity, Entity)>, References), AppError> {
+        let mut updates: Vec<(Entity, Entity)> = Vec::new();
+        let mut references = References::default();
+        for ticket in self.db.tickets().list_tickets().await? {
+            let mut changed = ticket.clone();
+            if repoint_ticket(&mut changed, from, to) {
+                references.tickets += 1;
+                updates.push((Entity::Ticket(ticket), Entity::Ticket(changed)));
+            }
+        }
+        for comment in self.db.comments().list_comments_by(from).await? {
+            references.comments += 1;
+            let changed = Comment {
+                author: to.to_string(),
+                ..comment.clone()
+            };
+            updates.push((Entity::Comment(comment), Entity::Comment(changed)));
+        }
+        for group in self.db.groups().list_groups().await? {
+            let mut changed = group.clone();
+            if repoint(&mut changed.principals, from, to) {
+                references.groups += 1;
+                updates.push((Entity::Group(group), Entity::Group(changed)));
+            }
+        }
+        for project in self.db.projects().list_projects().await? {
+            let mut changed = project.clone();
+            if repoint_project(&mut changed, from, to) {
+                references.projects += 1;
+                updates.push((Entity::Project(project), Entity::Project(changed)));
+            }
+        }
+        Ok((updates, references))
+    }
+
+    /// Users and groups share ACL entries, a new name must be neither.
+    async fn check_name_free(&self, name: &str) -> Result<String, AppError> {
+        let name = validate_username(name).map_err(AppError::Validation)?;
+        if self.db.users().exists_user(&name).await? || self.db.groups().exists_group(&name).await? {
+            return Err(AppError::Conflict(format!("The name {} is taken", name)));
+        }
+        Ok(name)
+    }
+
+    /// Merges the duplicate account `source` into `target`: tickets, comments, group
+    /// memberships, ACL entries and project ownerships naming the source name the
+    /// target instead, and the source is deactivated. The target must be an active
+    /// user, neither may be a service account.
+    ///
+    /// Not atomic, the backends have no transactions. Merges and renames run one at a
+    /// time, each reading what it changes after the previous one is done. Readers may
+    /// see a merge half done, and an edit made meanwhile to an entity it rewrites is
+    /// lost. If a write fails, what was written before it is put back as it was.
+    pub async fn merge_users(&self, source: &str, target: &str) -> Result<MergeUsersResponse, AppError> {
+        if source == target {
+            return Err(AppError::Validation("Can't merge a user into itself".to_string()));
+        }
+        let _merging = self.changes.lock().await;
+        let mut source_user = self.db.users().get_user(source).await?;
+        let target_user = self.db.users().get_user(target).await?;
+        if source_user.service_account || target_user.service_account {
+            return Err(AppError::Validation("Service accounts can't be merged".to_string()));
+        }
+        if target_user.deactivated {
+            return Err(AppError::Validation(format!("User {} is deactivated", target)));
+        }
+
+        let (mut updates, references) = self.repoint_references(source, target).await?;
+        let original = source_user.clone();
+        source_user.deactivated = true;
+        source_user.api_tokens.clear();
+        source_user.calendar_token = None;
+        updates.push((Entity::User(original), Entity::User(source_user)));
+
+        self.update_all(updates).await?;
+        self.acl_cache.invalidate_user(source);
+        self.acl_cache.invalidate_user(target);
+        Ok(MergeUsersResponse {
+            source: source.to_string(),
+            target: target.to_string(),
+            tickets: references.tickets,
+            comments: references.comments,
+            groups: references.groups,
+            projects: references.projects,
+        })
+    }
+
+    /// Renames a user: the account moves to the new username with its sessions, and
+    /// tickets, comments, group memberships, ACL entries and project ownerships follow.
+    /// A deactivated alias stays at the old username, so that tokens issued before
+    /// keep working until they expire; the name can't be taken again.
+    ///
+    /// Runs alone and is undone on failure as `merge_users`, neither atomic.
+    pub async fn rename_user(&self, username: &str, new_username: &str) -> Result<RenamePrincipalResponse, AppError> {
+        let _renaming = self.changes.lock().await;
+        let user = self.db.users().get_user(username).await?;
+        if user.renamed_to.is_some() {
+            return Err(AppError::NotFound(format!("User {} not found", username)));
+        }
+        let new_username = self.check_name_free(new_username).await?;
+
+        let (mut updates, references) = self.repoint_references(username, &new_username).await?;
+        let sessions = self.db.sessions().list_user_sessions(username).await?;
+        let moved_sessions = sessions.len();
+        for session in sessions {
+            let moved = Session {
+                username: new_username.clone(),
+                ..session.clone()
+            };
+            updates.push((Entity::Session(session), Entity::Session(moved)));
+        }
+        let renamed = User {
+            username: new_username.clone(),
+            ..user.clone()
+        };
+        // Emails and external ids are unique, the alias gives them up first
+        let alias = User {
+            username: username.to_string(),
+            created_at: user.created_at,
+            created_by: user.created_by.clone(),
+            deactivated: true,
+            renamed_to: Some(new_username.clone()),
+            ..User::default()
+        };
+
+        self.db.users().update_user(username, alias).await?;
+        let written = match self.db.users().create_user(renamed).await {
+            Ok(()) => self.update_all(updates).await.map_err(|e| (e, true)),
+            Err(e) => Err((e, false)),
+        };
+        if let Err((e, created)) = written {
+            if created && let Err(cleanup) = self.db.users().delete_user(&new_username, true).await {
+                log::error!("User {} of failed rename of {} not removed: {}", new_username, username, cleanup);
+            }
+            if let Err(cleanup) = self.db.users().update_user(username, user).await {
+                log::error!("User {} not restored after a failed rename: {}", username, cleanup);
+            }
+            return Err(e);
+        }
+
+        self.acl_cache.invalidate_user(username);
+        self.acl_cache.invalidate_user(&new_username);
+        Ok(RenamePrincipalResponse {
+            from: username.to_string(),
+            to: new_username,
+            tickets: references.tickets,
+            comments: references.comments,
+            groups: references.groups,
+            projects: references.projects,
+            sessions: moved_sessions,
+        })
+    }
+
+    /// Renames a group: its members move to the new id, and tickets and ACL entries
+    /// follow. An alias without members stays at the old id, so that invites issued
+    /// before still join the group; the id can't be taken again.
+    ///
+    /// Runs alone and is undone on failure as `merge_users`, neither atomic.
+    pub async fn rename_group(&self, gid: &str, new_gid: &str) -> Result<RenamePrincipalResponse, AppError> {
+        let _renaming = self.changes.lock().await;
+        let group = self.db.groups().get_group(gid).await?;
+        if group.renamed_to.is_some() {
+            return Err(AppError::NotFound(format!("Group {} not found", gid)));
+        }
+        let new_gid = self.check_name_free(new_gid).await?;
+
+        let (updates, references) = self.repoint_references(gid, &new_gid).await?;
+        let renamed = Group {
+            gid: new_gid.clone(),
+            ..group.clone()
+        };
+        let alias = Group {
+            gid: gid.to_string(),
+            name: group.name.clone(),
+            principals: vec![],
+            renamed_to: Some(new_gid.clone()),
+        };
+
+        self.db.groups().update_group(gid, alias).await?;
+        let written = match self.db.groups().create_group(renamed).await {
+            Ok(()) => self.update_all(updates).await.map_err(|e| (e, true)),
+            Err(e) => Err((e, false)),
+        };
+        if let Err((e, created)) = written {
+            if created && let Err(cleanup) = self.db.groups().delete_group(&new_gid, true).await {
+                log::error!("Group {} of failed rename of {} not removed: {}", new_gid, gid, cleanup);
+            }
+            if let Err(cleanup) = self.db.groups().update_group(gid, group).await {
+                log::error!("Group {} not restored after a failed rename: {}", gid, cleanup);
+            }
+            return Err(e);
+        }
+
+        for member in &group.principals {
+            self.acl_cache.invalidate_user(member);
+        }
+        Ok(RenamePrincipalResponse {
+            from: gid.to_string(),
+            to: new_gid,
+            tickets: references.tickets,
+            comments: references.comments,
+            groups: references.groups,
+            projects: references.projects,
+            sessions: 0,
+        })
+    }
+}
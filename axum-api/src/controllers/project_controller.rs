@@ -1,13 +1,777 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use crate::db::DatabaseInterface;
+use chrono::{Days, Utc};
+
+use crate::{
+    acl::{self, AclCache},
+    db::{DatabaseInterface, TicketDayCount},
+    error::AppError,
+    models::{
+        AccessControlList, AccessControlStore, AssignmentRule, AssignmentTarget, ChatChannel, CustomFieldDefinition,
+        EscalationPolicy, Permissions, Project, PublicPortal, Ticket, TicketStatus,
+    },
+    schema::{
+        ProjectStatsResponse, ProjectTreeEntry, ProjectTreeResponse, PublicPortalRequest, SeverityCount, StatusCount,
+        SubprojectCount,
+    },
+    utils::{random_token, sha256_hex},
+    validation::{assignment_rules::validate_rules, custom_fields::validate_definitions, naming::validate_slug},
+};
+
+/// How long computed project stats are served before being recomputed.
+const STATS_TTL: Duration = Duration::from_secs(30);
+const DEFAULT_STATS_DAYS: u32 = 30;
+const MAX_STATS_DAYS: u32 = 365;
+const TOP_ASSIGNEES: usize = 5;
+const PORTAL_TOKEN_LEN: usize = 32;
+
+type StatsCache = HashMap<(String, u32), (Instant, ProjectStatsResponse)>;
 
 pub struct ProjectController {
     pub db: Arc<dyn DatabaseInterface>,
+    stats_cache: Mutex<StatsCache>, // by project id and trend window
+    acl_cache: Arc<AclCache>,
 }
 
 impl ProjectController {
-    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<dyn DatabaseInterface>, acl_cache: Arc<AclCache>) -> Self {
+        Self {
+            db,
+            stats_cache: Mutex::new(HashMap::new()),
+            acl_cache,
+        }
+    }
+
+    /// Resolves what the principals may do on the project and caches it.
+    fn resolve(&self, project: &Project, ancestors: &[Project], principals: &[String]) -> Permissions {
+        let permissions = acl::project_permissions(project, ancestors, principals);
+        self.acl_cache.store_permissions(principals, &project.id.to_string(), permissions);
+        permissions
+    }
+
+    /// What the principals may do on the project, cached until its ACL or the ACL of
+    /// one of its ancestors changes.
+    async fn permissions(&self, project: &Project, principals: &[String]) -> Result<Permissions, AppError> {
+        if let Some(permissions) = self.acl_cache.permissions(principals, &project.id.to_string()) {
+            return Ok(permissions);
+        }
+        let ancestors = acl::ancestors(self.db.as_ref(), project).await?;
+        Ok(self.resolve(project, &ancestors, principals))
+    }
+
+    /// Forgets the cached permissions on the project and on the projects below it.
+    async fn invalidate_tree(&self, id: &str) -> Result<(), AppError> {
+        self.acl_cache.invalidate_project(id);
+        for sub in self.db.projects().list_subprojects(id).await? {
+            self.acl_cache.invalidate_project(&sub.id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Projects whose ACL grants `LIST` to any of the principals.
+    pub async fn list_projects(&self, principals: &[String]) -> Result<Vec<Project>, AppError> {
+        let projects = self.db.projects().list_projects().await?;
+        Ok(projects
+            .iter()
+            .filter(|p| {
+                self.acl_cache
+                    .permissions(principals, &p.id.to_string())
+                    .unwrap_or_else(|| self.resolve(p, &acl::ancestors_among(&projects, p), principals))
+                    .contains(Permissions::LIST)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Fetches a project if its ACL grants `FETCH` to any of the principals.
+    /// Hidden projects are reported as missing.
+    pub async fn get_project(&self, id: &str, principals: &[String]) -> Result<Project, AppError> {
+        let project = self.db.projects().get_project(id).await?;
+        if !self.permissions(&project, principals).await?.contains(Permissions::FETCH) {
+            return Err(AppError::NotFound(format!("Project {} not found", id)));
+        }
+        Ok(project)
+    }
+
+    /// Checks that the principals can fetch the project, without loading it when their
+    /// permissions are cached.
+    pub async fn authorize(&self, id: &str, principals: &[String]) -> Result<(), AppError> {
+        match self.acl_cache.permissions(principals, id) {
+            Some(permissions) if permissions.contains(Permissions::FETCH) => Ok(()),
+            Some(_) => Err(AppError::NotFound(format!("Project {} not found", id))),
+            None => self.get_project(id, principals).await.map(|_| ()),
+        }
+    }
+
+    /// Fetches a project the principals may change, its ACL must grant them `MODIFY`.
+    pub async fn modifiable_project(&self, id: &str, principals: &[String]) -> Result<Project, AppError> {
+        let project = self.get_project(id, principals).await?;
+        if !self.permissions(&project, principals).await?.contains(Permissions::MODIFY) {
+            return Err(AppError::Authorization(format!("Not allowed to modify project {}", id)));
+        }
+        Ok(project)
+    }
+
+    /// Replaces the project's custom field definitions, the principals need `MODIFY`.
+    /// Tickets created before keep their values, the new definitions apply to new ones.
+    pub async fn set_custom_fields(
+        &self,
+        id: &str,
+        principals: &[String],
+        definitions: Vec<CustomFieldDefinition>,
+    ) -> Result<Vec<CustomFieldDefinition>, AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        validate_definitions(&definitions).map_err(AppError::Validation)?;
+        project.custom_fields = definitions;
+        self.db.projects().update_project(id, project.clone()).await?;
+        Ok(project.custom_fields)
+    }
+
+    /// Replaces the project's assignment rules, the principals need `MODIFY`. Every
+    /// principal assigned must exist and round-robin groups need members.
+    pub async fn set_assignment_rules(
+        &self,
+        id: &str,
+        principals: &[String],
+        rules: Vec<AssignmentRule>,
+    ) -> Result<Vec<AssignmentRule>, AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        let rules: Vec<AssignmentRule> = rules
+            .into_iter()
+            .map(|rule| AssignmentRule {
+                name: rule.name.trim().to_string(),
+                ..rule
+            })
+            .collect();
+        validate_rules(&project, &rules).map_err(AppError::Validation)?;
+        for rule in &rules {
+            match &rule.assign {
+                AssignmentTarget::Principal { principal } => {
+                    if !self.db.users().exists_user(principal).await?
+                        && !self.db.groups().exists_group(principal).await?
+                    {
+                        return Err(AppError::Validation(format!(
+                            "Rule '{}': no user or group '{}'",
+                            rule.name, principal
+                        )));
+                    }
+                }
+                AssignmentTarget::RoundRobin { group } => {
+                    let members = match self.db.groups().get_group(group).await {
+                        Ok(group) => group.principals,
+                        Err(AppError::NotFound(_)) => vec![],
+                        Err(e) => return Err(e),
+                    };
+                    if members.is_empty() {
+                        return Err(AppError::Validation(format!(
+                            "Rule '{}': group '{}' has no members to take turns",
+                            rule.name, group
+                        )));
+                    }
+                }
+            }
+        }
+        project.assignment_rules = rules;
+        self.db.projects().update_project(id, project.clone()).await?;
+        Ok(project.assignment_rules)
+    }
+
+    /// Replaces the project's escalation policies, the principals need `MODIFY`.
+    /// Severities must be on the project's scale and groups must exist.
+    pub async fn set_escalation_policies(
+        &self,
+        id: &str,
+        principals: &[String],
+        policies: Vec<EscalationPolicy>,
+    ) -> Result<Vec<EscalationPolicy>, AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        let scale = project.severity_scale();
+        let mut names = Vec::new();
+        for policy in &policies {
+            let invalid = |reason: String| AppError::Validation(format!("Policy '{}': {}", policy.name, reason));
+            if policy.name.trim().is_empty() {
+                return Err(AppError::Validation("Policy names can't be empty".to_string()));
+            }
+            if names.contains(&policy.name) {
+                return Err(invalid("defined twice".to_string()));
+            }
+            names.push(policy.name.clone());
+            if !scale.iter().any(|s| s.level == policy.severity) {
+                return Err(invalid(format!("severity {} is not on the project's scale", policy.severity)));
+            }
+            if policy.idle_hours == 0 {
+                return Err(invalid("idle_hours must be at least 1".to_string()));
+            }
+            if !self.db.groups().exists_group(&policy.group).await? {
+                return Err(invalid(format!("no group '{}'", policy.group)));
+            }
+        }
+        project.escalation_policies = policies;
+        self.db.projects().update_project(id, project.clone()).await?;
+        Ok(project.escalation_policies)
+    }
+
+    /// The project's public portal, if it has one. The principals need `MODIFY`.
+    pub async fn portal(&self, id: &str, principals: &[String]) -> Result<Option<PublicPortal>, AppError> {
+        Ok(self.modifiable_project(id, principals).await?.portal)
+    }
+
+    /// Turns on the project's public portal or changes it, the principals need `MODIFY`.
+    /// The slug must not be another project's. Returns the portal and the token issued
+    /// with it, if one was: when a token becomes required or is rotated.
+    pub async fn set_portal(
+        &self,
+        id: &str,
+        principals: &[String],
+        req: PublicPortalRequest,
+    ) -> Result<(PublicPortal, Option<String>), AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        let slug = validate_slug(&req.slug).map_err(AppError::Validation)?;
+        let taken = self.db.projects().list_projects().await?.into_iter().any(|p| {
+            p.id != project.id && p.portal.is_some_and(|portal| portal.slug == slug)
+        });
+        if taken {
+            return Err(AppError::Conflict(format!("Portal {} is another project's", slug)));
+        }
+        let previous = project.portal.and_then(|portal| portal.token_hash);
+        let (token_hash, token) = match (req.require_token, previous) {
+            (false, _) => (None, None),
+            (true, Some(hash)) if !req.rotate_token => (Some(hash), None),
+            (true, _) => {
+                let token = random_token(PORTAL_TOKEN_LEN);
+                (Some(sha256_hex(&token)), Some(token))
+            }
+        };
+        let portal = PublicPortal {
+            slug,
+            token_hash,
+            captcha: req.captcha,
+        };
+        project.portal = Some(portal.clone());
+        self.db.projects().update_project(id, project).await?;
+        Ok((portal, token))
+    }
+
+    /// Turns off the project's public portal, the principals need `MODIFY`.
+    pub async fn remove_portal(&self, id: &str, principals: &[String]) -> Result<(), AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        if project.portal.take().is_none() {
+            return Err(AppError::NotFound(format!("Project {} has no public portal", id)));
+        }
+        self.db.projects().update_project(id, project).await
+    }
+
+    /// The project whose public portal has the slug.
+    pub async fn portal_project(&self, slug: &str) -> Result<(Project, PublicPortal), AppError> {
+        self.db
+            .projects()
+            .list_projects()
+            .await?
+            .into_iter()
+            .find_map(|p| {
+                let portal = p.portal.clone().filter(|portal| portal.slug == slug)?;
+                Some((p, portal))
+            })
+            .ok_or_else(|| AppError::NotFound(format!("Portal {} not found", slug)))
+    }
+
+    /// The project's ACL, or the ACL of its ticket group with `prefix`.
+    fn acl_mut<'a>(project: &'a mut Project, prefix: Option<&str>) -> Result<&'a mut AccessControlStore, AppError> {
+        match prefix {
+            None => Ok(&mut project.acl),
+            Some(prefix) => project
+                .tickets
+                .iter_mut()
+                .find(|group| group.prefix == prefix)
+                .map(|group| &mut group.acl)
+                .ok_or_else(|| AppError::NotFound(format!("Ticket group {} not found", prefix))),
+        }
+    }
+
+    /// The ACL of the project or of one of its ticket groups, the principals need
+    /// `MODIFY` on the project.
+    pub async fn acl(
+        &self,
+        id: &str,
+        principals: &[String],
+        prefix: Option<&str>,
+    ) -> Result<AccessControlStore, AppError> {
+        let mut project = self.modifiable_project(id, principals).await?;
+        Ok(Self::acl_mut(&mut project, prefix)?.clone())
+    }
+
+    /// Applies `change` to the ACL of the project or of one of its ticket groups, the
+    /// principals need `ROOT` on the project. Unless it has an owner, the project's ACL
+    /// must leave some user or group with `ROOT`, so it can't be locked out of.
+    async fn change_acl(
+        &self,
+        id: &str,
+        principals: &[String],
+        prefix: Option<&str>,
+        change: impl FnOnce(&mut AccessControlStore),
+    ) -> Result<AccessControlStore, AppError> {
+        let mut project = self.get_project(id, principals).await?;
+        if !self.permissions(&project, principals).await?.contains(Permissions::ROOT) {
+            return Err(AppError::Authorization(format!("Not allowed to change the ACL of project {}", id)));
+        }
+        let acl = Self::acl_mut(&mut project, prefix)?;
+        change(acl);
+        acl.last_mod_date = Utc::now();
+        let acl = acl.clone();
+        let rooted = project.owner.is_some()
+            || project
+                .acl
+                .list
+                .iter()
+                .flat_map(|list| &list.principals)
+                .any(|p| project.acl.allows(std::slice::from_ref(p), Permissions::ROOT));
+        if !rooted {
+            return Err(AppError::Validation("Some user or group must keep ROOT on the project".to_string()));
+        }
+        self.db.projects().update_project(id, project).await?;
+        self.invalidate_tree(id).await?;
+        Ok(acl)
+    }
+
+    async fn check_principal(&self, principal: &str) -> Result<(), AppError> {
+        if !self.db.users().exists_user(principal).await? && !self.db.groups().exists_group(principal).await? {
+            return Err(AppError::Validation(format!("No user or group '{}'", principal)));
+        }
+        Ok(())
+    }
+
+    /// Replaces the ACL of the project or of one of its ticket groups, the principals
+    /// need `ROOT` on the project. Every principal must exist, lists without any are dropped.
+    pub async fn set_acl(
+        &self,
+        id: &str,
+        principals: &[String],
+        prefix: Option<&str>,
+        list: Vec<AccessControlList>,
+    ) -> Result<AccessControlStore, AppError> {
+        let mut checked: Vec<&String> = Vec::new();
+        for principal in list.iter().flat_map(|acl| &acl.principals) {
+            if !checked.contains(&principal) {
+                self.check_principal(principal).await?;
+                checked.push(principal);
+            }
+        }
+        let list: Vec<AccessControlList> = list.into_iter().filter(|acl| !acl.principals.is_empty()).collect();
+        self.change_acl(id, principals, prefix, |acl| acl.list = list).await
+    }
+
+    /// Adds `permissions` to those the principal is granted itself.
+    pub async fn grant(
+        &self,
+        id: &str,
+        principals: &[String],
+        prefix: Option<&str>,
+        principal: &str,
+        permissions: Permissions,
+    ) -> Result<AccessControlStore, AppError> {
+        self.check_principal(principal).await?;
+        self.change_acl(id, principals, prefix, |acl| {
+            let granted = acl.granted_to(principal) | permissions;
+            acl.set_permissions(principal, granted);
+        })
+        .await
+    }
+
+    /// Takes `permissions` from those the principal is granted itself. It may still
+    /// have them through its groups.
+    pub async fn revoke(
+        &self,
+        id: &str,
+        principals: &[String],
+        prefix: Option<&str>,
+        principal: &str,
+        permissions: Permissions,
+    ) -> Result<AccessControlStore, AppError> {
+        self.change_acl(id, principals, prefix, |acl| {
+            let granted = acl.granted_to(principal) - permissions;
+            acl.set_permissions(principal, granted);
+        })
+        .await
+    }
+
+    /// Makes another user the project's owner. Only the owner can, or a principal with
+    /// `ROOT` when the project has none yet. `confirm` must repeat the project id. The
+    /// previous owner keeps what the ACL grants them. Returns the previous owner.
+    pub async fn transfer_ownership(
+        &self,
+        id: &str,
+        principals: &[String],
+        new_owner: &str,
+        confirm: &str,
+    ) -> Result<(Project, Option<String>), AppError> {
+        let mut project = self.get_project(id, principals).await?;
+        let allowed = match &project.owner {
+            Some(_) => acl::owns(&project, principals),
+            None => self.permissions(&project, principals).await?.contains(Permissions::ROOT),
+        };
+        if !allowed {
+            return Err(AppError::Authorization(format!("Only the owner can transfer project {}", id)));
+        }
+        if confirm != project.id.to_string() {
+            return Err(AppError::Validation("Confirm the transfer by repeating the project id".to_string()));
+        }
+        if project.owner.as_deref() == Some(new_owner) {
+            return Err(AppError::Validation(format!("{} already owns the project", new_owner)));
+        }
+        match self.db.users().get_user(new_owner).await {
+            Ok(user) if !user.deactivated => {}
+            Ok(_) | Err(AppError::NotFound(_)) => {
+                return Err(AppError::Validation(format!("No active user '{}'", new_owner)));
+            }
+            Err(e) => return Err(e),
+        }
+
+        let previous = project.owner.replace(new_owner.to_string());
+        self.db.projects().update_project(id, project.clone()).await?;
+        self.invalidate_tree(id).await?;
+        Ok((project, previous))
+    }
+
+    /// Creates a project with the settings, ticket groups, ACLs and chat channel of
+    /// another, owned by `owner`. With `open_tickets`, copies of its open and in progress
+    /// tickets are filed in it too. The principals need `MODIFY` on the source. Returns
+    /// the new project and how many tickets were copied.
+    ///
+    /// The backends have no transactions: if a write fails, what was written before it
+    /// is removed again.
+    pub async fn clone_project(
+        &self,
+        id: &str,
+        principals: &[String],
+        owner: &str,
+        open_tickets: bool,
+    ) -> Result<(Project, usize), AppError> {
+        let source = self.modifiable_project(id, principals).await?;
+        let now = Utc::now();
+        let mut project = Project {
+            id: uuid::Uuid::now_v7(),
+            owner: Some(owner.to_string()),
+            portal: None, // slugs are unique, the portal stays with the source
+            ..source.clone()
+        };
+        project.acl.last_mod_date = now;
+        for group in project.tickets.iter_mut() {
+            group.acl.last_mod_date = now;
+        }
+        let clone_id = project.id.to_string();
+
+        let mut copies: Vec<Ticket> = Vec::new();
+        if open_tickets {
+            let tickets = self.db.tickets().list_tickets().await?;
+            let mut open: Vec<&Ticket> = tickets
+                .iter()
+                .filter(|t| t.project.as_deref() == Some(id))
+                .filter(|t| matches!(t.status, TicketStatus::Open | TicketStatus::InProgress))
+                .collect();
+            open.sort_by_key(|t| t.id);
+            let first_id = match open.len() {
+                0 => 0,
+                count => self.db.tickets().reserve_ticket_ids(count as i64).await?,
+            };
+            for (new_id, ticket) in (first_id..).zip(open) {
+                copies.push(Ticket {
+                    id: new_id,
+                    project: Some(clone_id.clone()),
+                    last_modification: now,
+                    creation_date: now,
+                    milestone: None, // milestones stay with the source project
+                    escalated_at: None,
+                    message_id: None, // email replies keep going to the original
+                    ..ticket.clone()
+                });
+            }
+        }
+        let channel = match self.db.chat_channels().get_chat_channel(id).await {
+            Ok(channel) => Some(channel),
+            Err(AppError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+
+        let mut written: Vec<i64> = Vec::new();
+        let result: Result<(), AppError> = async {
+            self.db.projects().create_project(project.clone()).await?;
+            for ticket in &copies {
+                self.db.tickets().create_ticket(ticket.clone()).await?;
+                written.push(ticket.id);
+            }
+            if let Some(channel) = channel {
+                let channel = ChatChannel {
+                    project: clone_id.clone(),
+                    updated_at: now,
+                    ..channel
+                };
+                self.db.chat_channels().create_chat_channel(channel).await?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            for ticket in &written {
+                if let Err(cleanup) = self.db.tickets().delete_ticket(&ticket.to_string(), true).await {
+                    log::error!("Ticket {} of failed clone {} not removed: {}", ticket, clone_id, cleanup);
+                }
+            }
+            match self.db.projects().delete_project(&clone_id, true).await {
+                Ok(()) | Err(AppError::NotFound(_)) => {}
+                Err(cleanup) => log::error!("Failed clone {} of project {} not removed: {}", clone_id, id, cleanup),
+            }
+            return Err(e);
+        }
+        Ok((project, copies.len()))
+    }
+
+    /// Moves the project below `parent_id`, or to the top level. The principals need
+    /// `ROOT` on the project, and keep it once moved, and `MODIFY` on the new parent,
+    /// which can't be the project or a project below it.
+    pub async fn set_parent(
+        &self,
+        id: &str,
+        principals: &[String],
+        parent_id: Option<&str>,
+    ) -> Result<Project, AppError> {
+        let mut project = self.get_project(id, principals).await?;
+        if !self.permissions(&project, principals).await?.contains(Permissions::ROOT) {
+            return Err(AppError::Authorization(format!("Not allowed to move project {}", id)));
+        }
+        if let Some(parent) = parent_id {
+            let below = self.db.projects().list_subprojects(id).await?;
+            if parent == id || below.iter().any(|p| p.id.to_string() == parent) {
+                return Err(AppError::Validation("A project can't be moved below itself".to_string()));
+            }
+            self.modifiable_project(parent, principals).await?;
+        }
+
+        project.parent_id = parent_id.map(str::to_string);
+        let ancestors = acl::ancestors(self.db.as_ref(), &project).await?;
+        if ancestors.len() == acl::MAX_DEPTH {
+            return Err(AppError::Validation(format!(
+                "Projects can be nested {} levels deep at most",
+                acl::MAX_DEPTH
+            )));
+        }
+        if !acl::project_allows(&project, &ancestors, principals, Permissions::ROOT) {
+            return Err(AppError::Validation("The move would take away your ROOT on the project".to_string()));
+        }
+        self.db.projects().update_project(id, project.clone()).await?;
+        self.invalidate_tree(id).await?;
+        Ok(project)
+    }
+
+    /// The projects below the project the principals can fetch, closest first, with
+    /// how deep below it they are.
+    async fn visible_subprojects(
+        &self,
+        project: &Project,
+        principals: &[String],
+    ) -> Result<Vec<(Project, usize)>, AppError> {
+        let id = project.id.to_string();
+        let below = self.db.projects().list_subprojects(&id).await?;
+        let mut known = acl::ancestors(self.db.as_ref(), project).await?;
+        known.push(project.clone());
+        known.extend(below.iter().cloned());
+
+        let mut depths = HashMap::from([(id, 0)]);
+        let mut visible = Vec::new();
+        for sub in below {
+            let depth = sub.parent_id.as_ref().and_then(|p| depths.get(p)).map_or(1, |d| d + 1);
+            depths.insert(sub.id.to_string(), depth);
+            let permissions = self
+                .acl_cache
+                .permissions(principals, &sub.id.to_string())
+                .unwrap_or_else(|| self.resolve(&sub, &acl::ancestors_among(&known, &sub), principals));
+            if permissions.contains(Permissions::FETCH) {
+                visible.push((sub, depth));
+            }
+        }
+        Ok(visible)
+    }
+
+    /// The ancestors of a project the principals can fetch and the projects below it,
+    /// leaving out those they can't.
+    pub async fn tree(&self, id: &str, principals: &[String]) -> Result<ProjectTreeResponse, AppError> {
+        let project = self.get_project(id, principals).await?;
+        let ancestors = acl::ancestors(self.db.as_ref(), &project).await?;
+        // The ancestors of an ancestor are the ones after it, no need to walk up again
+        let visible_ancestors = ancestors
+            .iter()
+            .enumerate()
+            .filter(|(i, ancestor)| {
+                self.acl_cache
+                    .permissions(principals, &ancestor.id.to_string())
+                    .unwrap_or_else(|| self.resolve(ancestor, &ancestors[i + 1..], principals))
+                    .contains(Permissions::FETCH)
+            })
+            .map(|(_, ancestor)| ancestor.id.to_string())
+            .collect();
+        let subprojects = self
+            .visible_subprojects(&project, principals)
+            .await?
+            .into_iter()
+            .map(|(sub, depth)| ProjectTreeEntry {
+                project: sub.id.to_string(),
+                parent: sub.parent_id.unwrap_or_default(),
+                depth,
+            })
+            .collect();
+        Ok(ProjectTreeResponse {
+            project: id.to_string(),
+            ancestors: visible_ancestors,
+            subprojects,
+        })
+    }
+
+    /// The users among `usernames` the project's ACL grants `FETCH` to, directly or
+    /// through a group.
+    pub async fn members_among(
+        &self,
+        project: &Project,
+        usernames: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let groups = self.db.groups().list_groups().await?;
+        let ancestors = acl::ancestors(self.db.as_ref(), project).await?;
+        Ok(usernames
+            .iter()
+            .filter(|username| {
+                let mut principals = vec![username.to_string()];
+                principals.extend(
+                    groups
+                        .iter()
+                        .filter(|g| g.principals.contains(username))
+                        .map(|g| g.gid.clone()),
+                );
+                acl::project_allows(project, &ancestors, &principals, Permissions::FETCH)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Ticket statistics of a project the principals can fetch, with a trend over
+    /// the last `days` days (today included, 30 if unset).
+    pub async fn stats(
+        &self,
+        id: &str,
+        principals: &[String],
+        days: Option<u32>,
+    ) -> Result<ProjectStatsResponse, AppError> {
+        let days = days.unwrap_or(DEFAULT_STATS_DAYS);
+        if days == 0 || days > MAX_STATS_DAYS {
+            return Err(AppError::Validation(format!(
+                "days must be between 1 and {}",
+                MAX_STATS_DAYS
+            )));
+        }
+        // Access is checked on every call, only the numbers are cached
+        self.authorize(id, principals).await?;
+
+        let key = (id.to_string(), days);
+        let cached = self
+            .stats_cache
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|(computed, _)| computed.elapsed() < STATS_TTL)
+            .map(|(_, stats)| stats.clone());
+        let mut stats = match cached {
+            Some(stats) => stats,
+            None => {
+                let stats = self.compute_stats(id, days).await?;
+                let mut cache = self.stats_cache.lock().unwrap();
+                cache.retain(|_, (computed, _)| computed.elapsed() < STATS_TTL);
+                cache.insert(key, (Instant::now(), stats.clone()));
+                stats
+            }
+        };
+
+        // Sub-projects the principals can't fetch are left out of the roll-up
+        if !stats.subprojects.is_empty() {
+            let project = self.db.projects().get_project(id).await?;
+            let visible: Vec<String> = self
+                .visible_subprojects(&project, principals)
+                .await?
+                .into_iter()
+                .map(|(sub, _)| sub.id.to_string())
+                .collect();
+            stats.subprojects.retain(|sub| visible.contains(&sub.project));
+            stats.rollup_total = stats.total + stats.subprojects.iter().map(|sub| sub.total).sum::<usize>();
+        }
+        Ok(stats)
+    }
+
+    async fn compute_stats(&self, id: &str, days: u32) -> Result<ProjectStatsResponse, AppError> {
+        let tickets = self.db.tickets();
+        let counts = tickets.ticket_counts(Some(id)).await?;
+
+        let mut by_status = BTreeMap::new();
+        let mut by_severity = BTreeMap::new();
+        for count in &counts {
+            *by_status.entry(count.status).or_default() += count.count;
+            *by_severity.entry(count.severity).or_default() += count.count;
+        }
+
+        let today = Utc::now().date_naive();
+        let since = today - Days::new(u64::from(days) - 1);
+        let mut activity: HashMap<_, _> = tickets
+            .ticket_activity(id, since)
+            .await?
+            .into_iter()
+            .map(|d| (d.day, d))
+            .collect();
+        let trend = since
+            .iter_days()
+            .take(days as usize)
+            .map(|day| {
+                activity.remove(&day).unwrap_or(TicketDayCount {
+                    day,
+                    opened: 0,
+                    resolved: 0,
+                })
+            })
+            .collect();
+
+        let mut subprojects = Vec::new();
+        for sub in self.db.projects().list_subprojects(id).await? {
+            let mut by_status = BTreeMap::new();
+            for count in tickets.ticket_counts(Some(&sub.id.to_string())).await? {
+                *by_status.entry(count.status).or_default() += count.count;
+            }
+            subprojects.push(SubprojectCount {
+                project: sub.id.to_string(),
+                parent: sub.parent_id.unwrap_or_default(),
+                total: by_status.values().sum(),
+                by_status: by_status
+                    .into_iter()
+                    .map(|(status, count)| StatusCount { status, count })
+                    .collect(),
+            });
+        }
+
+        let total = counts.iter().map(|c| c.count).sum();
+        Ok(ProjectStatsResponse {
+            project: id.to_string(),
+            total,
+            by_status: by_status
+                .into_iter()
+                .map(|(status, count)| StatusCount { status, count })
+                .collect(),
+            by_severity: by_severity
+                .into_iter()
+                .map(|(severity, count)| SeverityCount { severity, count })
+                .collect(),
+            trend,
+            average_resolution_secs: tickets.average_resolution_secs(id).await?,
+            top_assignees: tickets.assignee_counts(id, TOP_ASSIGNEES).await?,
+            rollup_total: total + subprojects.iter().map(|sub| sub.total).sum::<usize>(),
+            subprojects,
+            generated_at: Utc::now(),
+        })
     }
 }
@@ -0,0 +1,46 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    utils::markdown::{self, Autolinks},
+};
+
+// Longer markdown is refused rather than rendered
+const MAX_MARKDOWN_BYTES: usize = 256 * 1024;
+
+pub struct RenderController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl RenderController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Renders markdown to sanitized HTML, see `utils::markdown`. Mentions of users
+    /// and groups and references to live tickets are linked.
+    pub async fn markdown(&self, text: &str) -> Result<String, AppError> {
+        if text.len() > MAX_MARKDOWN_BYTES {
+            return Err(AppError::Validation(format!(
+                "Markdown is {} bytes, at most {} are rendered",
+                text.len(),
+                MAX_MARKDOWN_BYTES
+            )));
+        }
+        let references = markdown::scan(text);
+        let mut principals = HashSet::new();
+        for name in references.mentions {
+            if self.db.users().exists_user(&name).await? || self.db.groups().exists_group(&name).await? {
+                principals.insert(name);
+            }
+        }
+        let ids: Vec<String> = references.tickets.iter().map(i64::to_string).collect();
+        let tickets = self.db.tickets().get_tickets(&ids).await?.found;
+        let links = Autolinks {
+            principals,
+            tickets: tickets.into_iter().map(|t| (t.id, t.ticket_group)).collect(),
+        };
+        Ok(markdown::render(text, &links))
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    acl,
+    db::{DatabaseInterface, Scored, SearchHits},
+    error::AppError,
+    models::{Permissions, Project, Ticket},
+    schema::{SearchResult, SearchResults},
+    search::{self, IndexHit, SearchIndex, SearchKind},
+};
+
+/// How many hits of each kind are asked of the search backend, before those the caller
+/// can't see are left out.
+const CANDIDATES: usize = 200;
+
+/// The first `limit` hits that are kept, as results.
+fn results<T>(
+    hits: Vec<Scored<T>>,
+    limit: usize,
+    keep: impl Fn(&T) -> bool,
+    describe: impl Fn(T) -> (String, String),
+) -> Vec<SearchResult> {
+    hits.into_iter()
+        .filter(|hit| keep(&hit.entity))
+        .take(limit)
+        .map(|hit| {
+            let (id, title) = describe(hit.entity);
+            SearchResult { id, title, score: hit.score }
+        })
+        .collect()
+}
+
+/// The entities the index hits of `kind` are about, in the order of the hits. Hits on
+/// entities not among `found`, deleted since they were indexed, are left out.
+fn scored<T>(hits: &[IndexHit], kind: SearchKind, found: Vec<T>, id: impl Fn(&T) -> String) -> Vec<Scored<T>> {
+    let mut found: HashMap<String, T> = found.into_iter().map(|entity| (id(&entity), entity)).collect();
+    hits.iter()
+        .filter(|hit| hit.kind == kind)
+        .filter_map(|hit| Some(Scored { score: hit.score, entity: found.remove(&hit.id)? }))
+        .collect()
+}
+
+fn ids(hits: &[IndexHit], kind: SearchKind) -> Vec<String> {
+    hits.iter().filter(|hit| hit.kind == kind).map(|hit| hit.id.clone()).collect()
+}
+
+pub struct SearchController {
+    pub db: Arc<dyn DatabaseInterface>,
+    pub index: Option<Arc<dyn SearchIndex>>,
+}
+
+impl SearchController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, index: Option<Arc<dyn SearchIndex>>) -> Self {
+        Self { db, index }
+    }
+
+    /// Refills the index from the database, if there is one. Returns how many entities
+    /// it now holds.
+    pub async fn rebuild_index(&self) -> Result<usize, AppError> {
+        let Some(index) = &self.index else {
+            return Ok(0);
+        };
+        let documents = search::documents(self.db.as_ref()).await?;
+        let count = documents.len();
+        index.replace_all(documents).await?;
+        Ok(count)
+    }
+
+    /// Hits of the index, with the entities loaded from the database.
+    async fn index_hits(
+        &self,
+        index: &dyn SearchIndex,
+        query: &str,
+        limit: usize,
+        projects: &[Project],
+    ) -> Result<SearchHits, AppError> {
+        let hits = index.query(query, limit).await?;
+        let tickets = self.db.tickets().get_tickets(&ids(&hits, SearchKind::Ticket)).await?.found;
+        let users = self.db.users().get_users(&ids(&hits, SearchKind::User)).await?.found;
+        let groups = self.db.groups().get_groups(&ids(&hits, SearchKind::Group)).await?.found;
+        Ok(SearchHits {
+            tickets: scored(&hits, SearchKind::Ticket, tickets, |t| t.id.to_string()),
+            projects: scored(&hits, SearchKind::Project, projects.to_vec(), |p| p.id.to_string()),
+            users: scored(&hits, SearchKind::User, users, |u| u.username.clone()),
+            groups: scored(&hits, SearchKind::Group, groups, |g| g.gid.clone()),
+        })
+    }
+
+    /// Tickets, projects, users and groups matching `query`, found by the index if there
+    /// is one and the database otherwise, up to `limit` of each, among what the principals
+    /// can see: projects and tickets they can list, and the principals
+    /// `acl::visible_principals` gives.
+    pub async fn search(&self, principals: &[String], query: &str, limit: usize) -> Result<SearchResults, AppError> {
+        if query.trim().is_empty() {
+            return Err(AppError::Validation("Search query is required".to_string()));
+        }
+        let projects = self.db.projects().list_projects().await?;
+        let hits = match &self.index {
+            Some(index) => self.index_hits(index.as_ref(), query, CANDIDATES.max(limit), &projects).await?,
+            None => self.db.search().find(query, CANDIDATES.max(limit)).await?,
+        };
+        let groups = self.db.groups().list_groups().await?;
+
+        let lists = |project: &Project| {
+            acl::project_allows(project, &acl::ancestors_among(&projects, project), principals, Permissions::LIST)
+        };
+        // Tickets of a deleted project are hidden with it
+        let lists_ticket = |ticket: &Ticket| match &ticket.project {
+            None => acl::unfiled_permissions(&ticket.created_by, &ticket.assigned_to, principals)
+                .contains(Permissions::LIST),
+            Some(id) => projects.iter().find(|p| p.id.to_string() == *id).is_some_and(|project| {
+                let ancestors = acl::ancestors_among(&projects, project);
+                acl::ticket_allows(project, &ancestors, ticket, principals, Permissions::LIST)
+            }),
+        };
+        let visible = acl::visible_principals(&projects, &groups, principals);
+
+        Ok(SearchResults {
+            tickets: results(hits.tickets, limit, lists_ticket, |t| (t.id.to_string(), t.title)),
+            projects: results(hits.projects, limit, lists, |p| (p.id.to_string(), p.id.to_string())),
+            users: results(hits.users, limit, |u| visible.contains(&u.username), |u| (u.username, u.personal.name)),
+            groups: results(hits.groups, limit, |g| visible.contains(&g.gid), |g| (g.gid, g.name)),
+        })
+    }
+}
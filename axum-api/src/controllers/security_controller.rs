@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    config::AlertThreshold,
+    db::{DatabaseInterface, SecurityEventFilter},
+    error::AppError,
+    models::{SecurityEvent, SecurityEventKind},
+};
+
+pub struct SecurityController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl SecurityController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Stores a security event and warns to the audit log once the same source
+    /// (IP, or username if the IP is unknown) reaches the alert threshold.
+    /// Never fails: a broken security log must not break authentication.
+    pub async fn record(
+        &self,
+        kind: SecurityEventKind,
+        username: Option<&str>,
+        ip: Option<String>,
+        detail: &str,
+        alert: &AlertThreshold,
+    ) {
+        let event = SecurityEvent {
+            id: uuid::Uuid::now_v7().to_string(),
+            kind,
+            username: username.map(|u| u.to_string()),
+            ip,
+            detail: detail.to_string(),
+            created_at: Utc::now(),
+        };
+
+        let mut filter = SecurityEventFilter {
+            kind: Some(kind),
+            since: Some(event.created_at - Duration::seconds(alert.window_secs)),
+            limit: Some(alert.count + 1),
+            ..Default::default()
+        };
+        if event.ip.is_some() {
+            filter.ip = event.ip.clone();
+        } else {
+            filter.username = event.username.clone();
+        }
+
+        if let Err(e) = self.db.security_events().create_event(event).await {
+            log::error!("Failed to record security event: {}", e);
+            return;
+        }
+
+        match self.db.security_events().list_events(&filter).await {
+            // Fire once when the threshold is crossed, not on every event after it
+            Ok(events) if events.len() == alert.count => {
+                log::warn!(
+                    target: "audit",
+                    "Security event -> {} {:?} events from {} in the last {}s",
+                    alert.count,
+                    kind,
+                    filter.ip.or(filter.username).unwrap_or_default(),
+                    alert.window_secs
+                );
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("Failed to check security event threshold: {}", e),
+        }
+    }
+
+    /// Returns a page of events, the total number of matches and the cursor of the next page.
+    pub async fn list_events(
+        &self,
+        filter: &SecurityEventFilter,
+    ) -> Result<(Vec<SecurityEvent>, usize, Option<String>), AppError> {
+        let events = self.db.security_events().list_events(filter).await?;
+        let total = self.db.security_events().count_events(filter).await?;
+        let next_cursor = match filter.limit {
+            Some(limit) if events.len() == limit => events.last().map(|e| e.id.clone()),
+            _ => None,
+        };
+        Ok((events, total, next_cursor))
+    }
+}
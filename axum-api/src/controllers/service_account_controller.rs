@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::{ApiToken, TokenScope, User},
+    schema,
+    utils::{random_token, sha256_hex},
+    validation::naming::validate_username,
+};
+
+// Tells API tokens apart from JWTs in the `Authorization` header
+pub const API_TOKEN_PREFIX: &str = "sat_";
+const API_TOKEN_LENGTH: usize = 40;
+const MAX_TOKENS: usize = 20; // per service account
+const MAX_TOKEN_NAME_LENGTH: usize = 64;
+
+pub struct ServiceAccountController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl ServiceAccountController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    pub fn is_api_token(token: &str) -> bool {
+        token.starts_with(API_TOKEN_PREFIX)
+    }
+
+    /// Creates a service account. It has no password, so it can only act through
+    /// the API tokens issued to it.
+    pub async fn create(&self, username: &str, name: String) -> Result<User, AppError> {
+        let username = validate_username(username).map_err(AppError::Validation)?;
+        let mut user: User = schema::User {
+            username,
+            password_hash: String::new(),
+        }
+        .into();
+        user.created_at = Utc::now();
+        user.service_account = true;
+        user.personal.name = name.trim().to_string();
+        self.db.users().create_user(user.clone()).await?;
+        Ok(user)
+    }
+
+    /// Service accounts, by username.
+    pub async fn list(&self) -> Result<Vec<User>, AppError> {
+        let mut accounts: Vec<User> = self
+            .db
+            .users()
+            .list_users()
+            .await?
+            .into_iter()
+            .filter(|u| u.service_account)
+            .collect();
+        accounts.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(accounts)
+    }
+
+    pub async fn get(&self, username: &str) -> Result<User, AppError> {
+        match self.db.users().get_user(username).await {
+            Ok(user) if user.service_account => Ok(user),
+            Ok(_) | Err(AppError::NotFound(_)) => {
+                Err(AppError::NotFound(format!("Service account {} not found", username)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deactivates the account and drops its tokens. The account stays, so ACLs and
+    /// tickets naming it keep making sense.
+    pub async fn deactivate(&self, username: &str) -> Result<(), AppError> {
+        let mut user = self.get(username).await?;
+        user.deactivated = true;
+        user.api_tokens.clear();
+        self.db.users().update_user(username, user).await
+    }
+
+    /// Issues a token to the account, returns the plain token (only its hash is stored).
+    pub async fn issue_token(
+        &self,
+        username: &str,
+        name: &str,
+        scopes: Vec<String>,
+    ) -> Result<(String, ApiToken), AppError> {
+        let mut user = self.get(username).await?;
+        if user.deactivated {
+            return Err(AppError::Validation(format!("Service account {} is deactivated", username)));
+        }
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_TOKEN_NAME_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Token name must be 1 to {} characters",
+                MAX_TOKEN_NAME_LENGTH
+            )));
+        }
+        if user.api_tokens.iter().any(|t| t.name == name) {
+            return Err(AppError::Conflict(format!("{} already has a token named '{}'", username, name)));
+        }
+        if user.api_tokens.len() >= MAX_TOKENS {
+            return Err(AppError::Validation(format!("Service accounts have at most {} tokens", MAX_TOKENS)));
+        }
+        let mut unique: Vec<TokenScope> = Vec::new();
+        for scope in scopes {
+            let scope: TokenScope = scope.parse().map_err(AppError::Validation)?;
+            if !unique.contains(&scope) {
+                unique.push(scope);
+            }
+        }
+        if unique.is_empty() {
+            return Err(AppError::Validation("At least one scope is required".to_string()));
+        }
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, random_token(API_TOKEN_LENGTH));
+        let api_token = ApiToken {
+            id: uuid::Uuid::now_v7().to_string(),
+            name: name.to_string(),
+            hash: sha256_hex(&token),
+            scopes: unique,
+            created_at: Utc::now(),
+        };
+        user.api_tokens.push(api_token.clone());
+        self.db.users().update_user(username, user).await?;
+        Ok((token, api_token))
+    }
+
+    pub async fn revoke_token(&self, username: &str, id: &str) -> Result<(), AppError> {
+        let mut user = self.get(username).await?;
+        let before = user.api_tokens.len();
+        user.api_tokens.retain(|t| t.id != id);
+        if user.api_tokens.len() == before {
+            return Err(AppError::NotFound(format!("Token {} of {} not found", id, username)));
+        }
+        self.db.users().update_user(username, user).await
+    }
+
+    /// The active service account a token belongs to, with the token's scopes.
+    pub async fn account_of(&self, token: &str) -> Result<(User, Vec<TokenScope>), AppError> {
+        let hash = sha256_hex(token);
+        let user = match self.db.users().find_user_by_api_token(&hash).await {
+            Ok(user) if user.service_account && !user.deactivated => user,
+            Ok(_) | Err(AppError::NotFound(_)) => {
+                return Err(AppError::Authorization("Unauthorized".to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+        let scopes = user
+            .api_tokens
+            .iter()
+            .find(|t| t.hash == hash)
+            .map(|t| t.scopes.clone())
+            .unwrap_or_default();
+        Ok((user, scopes))
+    }
+}
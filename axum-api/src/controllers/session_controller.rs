@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration as StdDuration, Instant},
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{db::DatabaseInterface, error::AppError, models::Session};
+
+// Don't write the session back on every request, only when it gets stale
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// Per-process record of sessions recently validated against the database, so that
+/// requests shortly after skip the reads. Revoking sessions or tokens drops the
+/// affected entries; changes made by another instance are noticed once an entry is
+/// older than the allowed age.
+#[derive(Default)]
+pub struct ValidatedSessions {
+    sessions: Mutex<HashMap<String, (String, u64, Instant)>>, // username, token generation and when, by session id
+}
+
+impl ValidatedSessions {
+    /// Whether the session was validated for the user and token generation within `max_age`.
+    pub fn contains(&self, session_id: &str, username: &str, generation: u64, max_age: StdDuration) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(session_id).is_some_and(|(user, validated_generation, validated)| {
+            user == username && *validated_generation == generation && validated.elapsed() < max_age
+        })
+    }
+
+    pub fn store(&self, session_id: &str, username: &str, generation: u64, max_age: StdDuration) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, (_, _, validated)| validated.elapsed() < max_age);
+        sessions.insert(session_id.to_string(), (username.to_string(), generation, Instant::now()));
+    }
+
+    /// Drops the sessions of the user, except `keep`.
+    pub fn forget_user(&self, username: &str, keep: Option<&str>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|id, (user, _, _)| user != username || Some(id.as_str()) == keep);
+    }
+
+    pub fn forget_session(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+}
+
+pub struct SessionController {
+    pub db: Arc<dyn DatabaseInterface>,
+    validated: Arc<ValidatedSessions>,
+}
+
+impl SessionController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, validated: Arc<ValidatedSessions>) -> Self {
+        Self { db, validated }
+    }
+
+    /// Sessions validated recently, see `ValidatedSessions`.
+    pub fn validated(&self) -> &ValidatedSessions {
+        &self.validated
+    }
+
+    /// Registers a new session for the user and returns its id.
+    pub async fn start_session(
+        &self,
+        username: &str,
+        user_agent: Option<String>,
+        ip: Option<String>,
+        expires_at: DateTime<Utc>,
+        token_generation: u64,
+    ) -> Result<String, AppError> {
+        let now = Utc::now();
+        let session = Session {
+            id: uuid::Uuid::now_v7().to_string(),
+            username: username.to_string(),
+            user_agent,
+            ip,
+            created_at: now,
+            last_used_at: now,
+            expires_at,
+            token_generation,
+        };
+        let id = session.id.clone();
+        self.db.sessions().create_session(session).await?;
+        Ok(id)
+    }
+
+    pub async fn get_session(&self, session_id: &str) -> Result<Session, AppError> {
+        self.db.sessions().get_session(session_id).await
+    }
+
+    /// Checks that the session exists, belongs to the user and is not expired.
+    /// Refreshes `last_used_at` on success. Database failures are errors, not `false`.
+    pub async fn validate_session(
+        &self,
+        session_id: &str,
+        username: &str,
+    ) -> Result<bool, AppError> {
+        let mut session = match self.db.sessions().get_session(session_id).await {
+            Ok(session) => session,
+            Err(AppError::NotFound(_)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let now = Utc::now();
+        if session.username != username || session.expires_at < now {
+            return Ok(false);
+        }
+        if now - session.last_used_at > Duration::seconds(TOUCH_INTERVAL_SECS) {
+            session.last_used_at = now;
+            if let Err(e) = self.db.sessions().update_session(session_id, session).await {
+                log::warn!("Failed to touch session {}: {}", session_id, e);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Lists active (non-expired) sessions of the user, newest first.
+    pub async fn list_sessions(&self, username: &str) -> Result<Vec<Session>, AppError> {
+        let now = Utc::now();
+        let mut sessions: Vec<Session> = self
+            .db
+            .sessions()
+            .list_user_sessions(username)
+            .await?
+            .into_iter()
+            .filter(|s| s.expires_at >= now)
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(sessions)
+    }
+
+    /// Revokes a single session of the user.
+    pub async fn revoke_session(&self, username: &str, session_id: &str) -> Result<(), AppError> {
+        let session = self.db.sessions().get_session(session_id).await?;
+        if session.username != username {
+            return Err(AppError::NotFound(format!(
+                "Session {} not found",
+                session_id
+            )));
+        }
+        self.db.sessions().delete_session(session_id).await?;
+        self.validated.forget_session(session_id);
+        Ok(())
+    }
+
+    /// Revokes every session of the user except `keep`, returns how many were removed.
+    pub async fn revoke_all_sessions(
+        &self,
+        username: &str,
+        keep: Option<&str>,
+    ) -> Result<usize, AppError> {
+        let sessions = self.db.sessions().list_user_sessions(username).await?;
+        self.validated.forget_user(username, keep);
+        let mut revoked = 0;
+        for session in sessions {
+            if Some(session.id.as_str()) == keep {
+                continue;
+            }
+            self.db.sessions().delete_session(&session.id).await?;
+            revoked += 1;
+        }
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::TicketShare,
+    utils::{constant_time_eq, hmac_sha256_hex, random_token},
+};
+
+const SHARE_ID_LENGTH: usize = 16;
+
+/// Signs what a share link grants, so that a token can't be made up from a share id
+/// or stretched past the share's expiry.
+fn signature(key: &[u8], share: &TicketShare) -> String {
+    let grant = format!("{}:{}:{}", share.id, share.ticket, share.expires_at.timestamp());
+    hmac_sha256_hex(key, grant.as_bytes())
+}
+
+pub struct ShareController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl ShareController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Shares the ticket for `lifetime`, returns the token of the link, signed with
+    /// `key`, along with the share. The token is not stored and shown only once.
+    pub async fn create(
+        &self,
+        ticket: i64,
+        created_by: &str,
+        lifetime: Duration,
+        key: &[u8],
+    ) -> Result<(String, TicketShare), AppError> {
+        let now = Utc::now();
+        let share = TicketShare {
+            id: random_token(SHARE_ID_LENGTH),
+            ticket,
+            created_by: created_by.to_string(),
+            created_at: now,
+            expires_at: now + lifetime,
+        };
+        self.db.shares().create_share(share.clone()).await?;
+        Ok((format!("{}.{}", share.id, signature(key, &share)), share))
+    }
+
+    /// The ticket's shares that haven't expired, oldest first.
+    pub async fn list(&self, ticket: i64) -> Result<Vec<TicketShare>, AppError> {
+        let now = Utc::now();
+        let shares = self.db.shares().list_shares(ticket).await?;
+        Ok(shares.into_iter().filter(|s| s.expires_at > now).collect())
+    }
+
+    /// Revokes a share of the ticket, its link stops working.
+    pub async fn revoke(&self, ticket: i64, id: &str) -> Result<TicketShare, AppError> {
+        let share = match self.db.shares().get_share(id).await {
+            Ok(share) if share.ticket == ticket => share,
+            Ok(_) | Err(AppError::NotFound(_)) => {
+                return Err(AppError::NotFound(format!("Share {} not found", id)));
+            }
+            Err(e) => return Err(e),
+        };
+        self.db.shares().delete_share(id).await?;
+        Ok(share)
+    }
+
+    /// The share a link's token grants, if it was signed with `key` and is neither
+    /// revoked nor expired.
+    pub async fn resolve(&self, token: &str, key: &[u8]) -> Result<TicketShare, AppError> {
+        let invalid = || AppError::Authorization("Invalid share link".to_string());
+        let (id, given) = token.split_once('.').ok_or_else(invalid)?;
+        let share = match self.db.shares().get_share(id).await {
+            Ok(share) => share,
+            Err(AppError::NotFound(_)) => return Err(invalid()),
+            Err(e) => return Err(e),
+        };
+        if !constant_time_eq(given, &signature(key, &share)) {
+            return Err(invalid());
+        }
+        if share.expires_at <= Utc::now() {
+            return Err(AppError::Authorization("Share link expired".to_string()));
+        }
+        Ok(share)
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use crate::{
+    db::{DatabaseInterface, breaker::BreakerStats, limited::PoolStats},
+    error::AppError,
+    schema::{AclCacheTotals, AdminStatsResponse, UserTotals, WsTotals},
+};
+
+pub struct StatsController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl StatsController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+
+    /// Entity totals and backend details. WebSockets, the ACL cache and the guards in
+    /// front of the database are tracked outside of it, so the caller passes their
+    /// numbers in.
+    pub async fn overview(
+        &self,
+        ws_connections: usize,
+        ws_totals: WsTotals,
+        acl_cache: AclCacheTotals,
+        database_limit: Option<PoolStats>,
+        database_breaker: Option<BreakerStats>,
+    ) -> Result<AdminStatsResponse, AppError> {
+        let (users, deactivated, groups, projects, tickets, database) = tokio::try_join!(
+            self.db.users().count_users(),
+            self.db.users().count_deactivated_users(),
+            self.db.groups().count_groups(),
+            self.db.projects().count_projects(),
+            self.db.tickets().count_tickets(),
+            self.db.backend_info(),
+        )?;
+        Ok(AdminStatsResponse {
+            users: UserTotals {
+                total: users,
+                active: users.saturating_sub(deactivated),
+                deactivated,
+            },
+            groups,
+            projects,
+            tickets,
+            ws_connections,
+            ws_totals,
+            acl_cache,
+            database,
+            database_limit,
+            database_breaker,
+        })
+    }
+}
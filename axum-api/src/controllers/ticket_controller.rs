@@ -1,13 +1,955 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use crate::db::DatabaseInterface;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::Value;
+
+use crate::{
+    acl,
+    controllers::draft_controller,
+    db::DatabaseInterface,
+    error::AppError,
+    events::{DomainEvent, EventBus},
+    models::{
+        AssignmentTarget, Comment, EscalationPolicy, Permissions, Project, PublicPortal, ReadReceipt, Severity,
+        Ticket, TicketEvent, TicketEventKind, TicketStatus,
+    },
+    schema::{CreateTicketRequest, IncomingEmail, MoveTicketRequest, PublicTicketRequest, TicketResponse},
+    utils::{rank, similarity::similarity},
+    validation::{
+        assignment_rules::condition_matches,
+        custom_fields::{validate_values, value_matches},
+        email::validate_email_address,
+        mentions::parse_mentions,
+    },
+};
+
+/// Listed tickets can also select `unread`, see `TicketController::list_tickets`.
+pub const UNREAD_FIELD: &str = "unread";
+
+/// Fields of `TicketResponse` that can be selected with `?fields=`.
+pub const TICKET_FIELDS: &[&str] = &[
+    "id",
+    "title",
+    "severity",
+    "severity_label",
+    "description",
+    "created_by",
+    "assigned_to",
+    "mentioned",
+    "last_modification",
+    "creation_date",
+    "status",
+    "project",
+    "resolved_at",
+    "custom_fields",
+    "rank",
+    "milestone",
+    "due_date",
+    "ticket_group",
+];
+
+// Tickets created longer ago aren't offered as duplicates
+const DUPLICATE_WINDOW_DAYS: i64 = 90;
+// Share of title words two tickets need in common to be likely duplicates
+const DUPLICATE_THRESHOLD: f64 = 0.3;
+const MAX_DUPLICATES: usize = 5;
+
+// Anonymous submissions are held to tighter limits than tickets of signed in users
+const MAX_PUBLIC_TITLE: usize = 200;
+const MAX_PUBLIC_DESCRIPTION: usize = 20 * 1024;
+
+/// Narrows a ticket listing down.
+#[derive(Debug, Default)]
+pub struct TicketFilter {
+    pub project: Option<String>,
+    pub custom_fields: Vec<(String, String)>, // name and value, all must match
+}
+
+impl TicketFilter {
+    pub fn is_empty(&self) -> bool {
+        self.project.is_none() && self.custom_fields.is_empty()
+    }
+
+    pub fn matches(&self, ticket: &Ticket) -> bool {
+        self.project.as_ref().is_none_or(|p| ticket.project.as_ref() == Some(p))
+            && self.custom_fields.iter().all(|(name, expected)| {
+                ticket
+                    .custom_fields
+                    .get(name)
+                    .is_some_and(|value| value_matches(value, expected))
+            })
+    }
+}
+
+/// Who an inbound email is from. Only an address the mail provider authenticated is
+/// matched to its user: anyone can write any address in `From`.
+pub enum EmailSender {
+    User { username: String, principals: Vec<String> },
+    External(String), // the address as given
+}
 
 pub struct TicketController {
     pub db: Arc<dyn DatabaseInterface>,
+    events: Arc<EventBus>,
+    ranking: tokio::sync::Mutex<()>, // held while a move or a create reads its column and writes the rank
+}
+
+/// Stored attributes needed to build the selected `TicketResponse` fields.
+fn model_fields(fields: &[String]) -> Vec<String> {
+    let mut model: Vec<String> = Vec::new();
+    for field in fields {
+        let attr = match field.as_str() {
+            "severity_label" => "severity",
+            UNREAD_FIELD => continue,
+            other => other,
+        };
+        if !model.iter().any(|m| m == attr) {
+            model.push(attr.to_string());
+        }
+    }
+    model
+}
+
+/// Adds whether the viewer has missed changes to a listed ticket, if selected.
+fn with_unread(mut value: Value, unread: Option<bool>) -> Value {
+    if let (Value::Object(map), Some(unread)) = (&mut value, unread) {
+        map.insert(UNREAD_FIELD.to_string(), Value::Bool(unread));
+    }
+    value
+}
+
+/// Turns a partial stored ticket into the `TicketResponse` shape, keeping only `fields`.
+fn project_ticket(value: Value, fields: &[String]) -> Value {
+    let Value::Object(mut map) = value else {
+        return value;
+    };
+    match map.remove("severity") {
+        Some(Value::Object(mut severity)) => {
+            if let Some(level) = severity.remove("level") {
+                map.insert("severity".to_string(), level);
+            }
+            if let Some(label) = severity.remove("label") {
+                map.insert("severity_label".to_string(), label);
+            }
+        }
+        // Stored as a `[level, label]` pair by older versions
+        Some(Value::Array(severity)) => {
+            let mut parts = severity.into_iter();
+            if let Some(level) = parts.next() {
+                map.insert("severity".to_string(), level);
+            }
+            if let Some(label) = parts.next() {
+                map.insert("severity_label".to_string(), label);
+            }
+        }
+        _ => {}
+    }
+    map.retain(|k, _| fields.contains(k));
+    Value::Object(map)
+}
+
+/// The tickets of a board column, top first. Unranked tickets come last, by id.
+fn column<'a>(
+    tickets: &'a [Ticket],
+    project: &Option<String>,
+    status: TicketStatus,
+    except: Option<i64>,
+) -> Vec<&'a Ticket> {
+    let mut column: Vec<&Ticket> = tickets
+        .iter()
+        .filter(|t| &t.project == project && t.status == status && Some(t.id) != except)
+        .collect();
+    column.sort_by(|a, b| {
+        (a.rank.is_empty(), &a.rank, a.id).cmp(&(b.rank.is_empty(), &b.rank, b.id))
+    });
+    column
 }
 
 impl TicketController {
-    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<dyn DatabaseInterface>, events: Arc<EventBus>) -> Self {
+        Self {
+            db,
+            events,
+            ranking: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    async fn publish(
+        &self,
+        kind: TicketEventKind,
+        ticket: &Ticket,
+        actor: &str,
+        previous_status: Option<TicketStatus>,
+    ) -> Result<(), AppError> {
+        self.events
+            .publish(DomainEvent::TicketUpdated(TicketEvent {
+                kind,
+                ticket: ticket.clone(),
+                actor: actor.to_string(),
+                at: Utc::now(),
+                previous_status,
+            }))
+            .await
+    }
+
+    /// The project a ticket is created in, if any.
+    async fn ticket_project(&self, project: Option<&str>) -> Result<Option<Project>, AppError> {
+        let Some(id) = project else {
+            return Ok(None);
+        };
+        match self.db.projects().get_project(id).await {
+            Ok(project) => Ok(Some(project)),
+            Err(AppError::NotFound(_)) => Err(AppError::Validation(format!("Project {} not found", id))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The first of the project's assignment rules a new ticket with this severity level
+    /// and custom field values meets, with who it assigns the ticket to. Round-robin
+    /// rules whose group is gone or empty are passed over.
+    pub async fn match_assignment_rule(
+        &self,
+        project: &Project,
+        severity: u8,
+        fields: &HashMap<String, Value>,
+    ) -> Result<Option<(String, String)>, AppError> {
+        for rule in &project.assignment_rules {
+            if !condition_matches(&rule.when, severity, fields) {
+                continue;
+            }
+            let assignee = match &rule.assign {
+                AssignmentTarget::Principal { principal } => principal.clone(),
+                AssignmentTarget::RoundRobin { group } => {
+                    let members = match self.db.groups().get_group(group).await {
+                        Ok(group) => group.principals,
+                        Err(AppError::NotFound(_)) => continue,
+                        Err(e) => return Err(e),
+                    };
+                    // Whoever comes after the member the project's newest ticket went to
+                    let last = self.db.tickets().last_assignee(&project.id.to_string(), &members).await?;
+                    let next = match last.and_then(|last| members.iter().position(|m| *m == last)) {
+                        Some(i) => members.get(i + 1).or(members.first()),
+                        None => members.first(),
+                    };
+                    let Some(next) = next else {
+                        continue;
+                    };
+                    next.clone()
+                }
+            };
+            return Ok(Some((rule.name.clone(), assignee)));
+        }
+        Ok(None)
+    }
+
+    /// Adds the users and groups `@mentioned` in the text to `mentioned`.
+    /// Names that are neither are left alone, as they may be plain text.
+    async fn with_mentions(&self, mut mentioned: Vec<String>, text: &str) -> Result<Vec<String>, AppError> {
+        let names: Vec<String> = parse_mentions(text)
+            .into_iter()
+            .filter(|name| !mentioned.contains(name))
+            .collect();
+        if names.is_empty() {
+            return Ok(mentioned);
+        }
+        let users = self.db.users().get_users(&names).await?;
+        let groups = self.db.groups().get_groups(&users.missing).await?;
+        for name in names {
+            let known = users.found.iter().any(|u| u.username == name) || groups.found.iter().any(|g| g.gid == name);
+            if known && !mentioned.contains(&name) {
+                mentioned.push(name);
+            }
+        }
+        Ok(mentioned)
+    }
+
+    /// Creates a ticket with a newly reserved id at the bottom of its project's open
+    /// column, ranked under the same lock as moves so that concurrent creates stack up
+    /// rather than share a rank. `@mentions` in the description are added to `mentioned`. In a project the principals need `CREATE`
+    /// on it, or on the ticket group. The draft it was written in, if named, is
+    /// discarded.
+    pub async fn create_ticket(
+        &self,
+        created_by: &str,
+        principals: &[String],
+        req: CreateTicketRequest,
+    ) -> Result<Ticket, AppError> {
+        let draft = req.draft.clone();
+        let ticket = self.insert_ticket(created_by, Some(principals), req, None).await?;
+        if let Some(key) = draft
+            && let Err(e) = draft_controller::discard(self.db.as_ref(), created_by, &key).await
+        {
+            // The ticket stands, the draft expires on its own
+            log::error!("Failed to discard draft of ticket {}: {}", ticket.id, e);
+        }
+        Ok(ticket)
+    }
+
+    /// Creates a ticket without checking the author's access, for fixtures loaded by
+    /// operators.
+    pub async fn import_ticket(&self, created_by: &str, req: CreateTicketRequest) -> Result<Ticket, AppError> {
+        self.insert_ticket(created_by, None, req, None).await
+    }
+
+    /// Without principals the caller vouches for the author, as the public portal and
+    /// the inbound email webhook do.
+    async fn insert_ticket(
+        &self,
+        created_by: &str,
+        principals: Option<&[String]>,
+        req: CreateTicketRequest,
+        message_id: Option<String>,
+    ) -> Result<Ticket, AppError> {
+        let title = req.title.trim();
+        if title.is_empty() {
+            return Err(AppError::Validation("Title is required".to_string()));
+        }
+        let project = self.ticket_project(req.project.as_deref()).await?;
+        if let Some(prefix) = &req.ticket_group {
+            let known = project.as_ref().is_some_and(|p| p.tickets.iter().any(|group| group.prefix == *prefix));
+            if !known {
+                return Err(AppError::Validation(format!("Ticket group {} not found in the project", prefix)));
+            }
+        }
+        if let (Some(project), Some(principals)) = (&project, principals) {
+            let ancestors = acl::ancestors(self.db.as_ref(), project).await?;
+            if !acl::project_allows(project, &ancestors, principals, Permissions::FETCH) {
+                return Err(AppError::Validation(format!("Project {} not found", project.id)));
+            }
+            let permissions =
+                acl::ticket_permissions_in(project, &ancestors, req.ticket_group.as_deref(), principals);
+            if !permissions.contains(Permissions::CREATE) {
+                return Err(AppError::Authorization(format!(
+                    "Not allowed to create tickets in project {}",
+                    project.id
+                )));
+            }
+        }
+        let scale = project.as_ref().map_or_else(Severity::default_scale, Project::severity_scale);
+        let severity =
+            Severity::on_scale(&scale, req.severity, &req.severity_label).map_err(AppError::Validation)?;
+        // Tickets outside projects have no custom fields defined
+        let definitions = project.as_ref().map(|p| p.custom_fields.as_slice()).unwrap_or_default();
+        validate_values(definitions, &req.custom_fields).map_err(AppError::Validation)?;
+
+        let id = self.db.tickets().reserve_ticket_ids(1).await?;
+        let mut assigned_to = req.assigned_to;
+        if assigned_to.trim().is_empty()
+            && let Some(project) = &project
+            && let Some((_, assignee)) =
+                self.match_assignment_rule(project, severity.level, &req.custom_fields).await?
+        {
+            assigned_to = assignee;
+        }
+        let mentioned = self.with_mentions(req.mentioned, &req.description).await?;
+        // New tickets go to the bottom of the open column, until stored no other may
+        // take the rank after the last
+        let ranking = self.ranking.lock().await;
+        let last = self.db.tickets().last_rank(req.project.as_deref(), TicketStatus::Open).await?;
+        let now = Utc::now();
+        let ticket = Ticket {
+            id,
+            title: title.to_string(),
+            severity,
+            description: req.description,
+            created_by: created_by.to_string(),
+            assigned_to,
+            mentioned,
+            last_modification: now,
+            creation_date: now,
+            status: TicketStatus::Open,
+            project: req.project,
+            resolved_at: None,
+            custom_fields: req.custom_fields.into_iter().filter(|(_, v)| !v.is_null()).collect(),
+            rank: rank::between(last.as_deref(), None),
+            milestone: None,
+            escalated_at: None,
+            message_id,
+            due_date: req.due_date,
+            ticket_group: req.ticket_group,
+        };
+        self.db.tickets().create_ticket(ticket.clone()).await?;
+        drop(ranking);
+        if let Err(e) = self.publish(TicketEventKind::Created, &ticket, created_by, None).await {
+            // Nobody would be notified of it
+            if let Err(cleanup) = self.db.tickets().delete_ticket(&ticket.id.to_string(), true).await {
+                log::error!("Ticket {} stays without its creation event: {}", ticket.id, cleanup);
+            }
+            return Err(e);
+        }
+        Ok(ticket)
+    }
+
+    pub async fn tickets(&self) -> Result<Vec<Ticket>, AppError> {
+        self.db.tickets().list_tickets().await
+    }
+
+    pub async fn ticket(&self, id: &str) -> Result<Ticket, AppError> {
+        self.db.tickets().get_ticket(id).await
+    }
+
+    /// Permissions the principals have on the ticket. Tickets of no project go by
+    /// `acl::unfiled_permissions`, those of a deleted project are open to no one.
+    pub async fn permissions(&self, ticket: &Ticket, principals: &[String]) -> Result<Permissions, AppError> {
+        let Some(id) = &ticket.project else {
+            return Ok(acl::unfiled_permissions(&ticket.created_by, &ticket.assigned_to, principals));
+        };
+        let project = match self.db.projects().get_project(id).await {
+            Ok(project) => project,
+            Err(AppError::NotFound(_)) => return Ok(Permissions::NONE),
+            Err(e) => return Err(e),
+        };
+        let ancestors = acl::ancestors(self.db.as_ref(), &project).await?;
+        Ok(acl::ticket_permissions(&project, &ancestors, ticket, principals))
+    }
+
+    /// The ticket, if the principals hold `permission` on it. Tickets they can't fetch
+    /// are not found, as if they didn't exist.
+    pub async fn ticket_for(
+        &self,
+        id: &str,
+        principals: &[String],
+        permission: Permissions,
+    ) -> Result<Ticket, AppError> {
+        let ticket = self.ticket(id).await?;
+        let permissions = self.permissions(&ticket, principals).await?;
+        if !permissions.contains(Permissions::FETCH) {
+            return Err(AppError::NotFound(format!("Ticket {} not found", id)));
+        }
+        if !permissions.contains(permission) {
+            return Err(AppError::Authorization(format!("Not allowed to change ticket {}", id)));
+        }
+        Ok(ticket)
+    }
+
+    /// The tickets the principals hold `permission` on.
+    pub async fn tickets_for(&self, principals: &[String], permission: Permissions) -> Result<Vec<Ticket>, AppError> {
+        let projects = self.db.projects().list_projects().await?;
+        Ok(self
+            .tickets()
+            .await?
+            .into_iter()
+            .filter(|t| {
+                let people = (t.created_by.as_str(), t.assigned_to.as_str());
+                let (project, prefix) = (t.project.as_deref(), t.ticket_group.as_deref());
+                acl::ticket_permissions_among(&projects, project, prefix, people, principals).contains(permission)
+            })
+            .collect())
+    }
+
+    /// Comments on a ticket the principals can fetch, oldest first.
+    pub async fn comments(&self, ticket: &str, principals: &[String]) -> Result<Vec<Comment>, AppError> {
+        let ticket = self.ticket_for(ticket, principals, Permissions::FETCH).await?;
+        self.db.comments().list_comments(ticket.id).await
+    }
+
+    /// Adds a comment to a ticket, which counts as activity on it. The principals
+    /// need `CREATE` on the ticket.
+    pub async fn add_comment(
+        &self,
+        ticket: &str,
+        author: &str,
+        principals: &[String],
+        body: &str,
+    ) -> Result<Comment, AppError> {
+        let ticket = self.ticket_for(ticket, principals, Permissions::CREATE).await?;
+        self.comment_on(ticket, author, body, None).await
+    }
+
+    async fn comment_on(
+        &self,
+        mut ticket: Ticket,
+        author: &str,
+        body: &str,
+        message_id: Option<String>,
+    ) -> Result<Comment, AppError> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(AppError::Validation("Comments can't be empty".to_string()));
+        }
+        let comment = Comment {
+            id: uuid::Uuid::now_v7().to_string(),
+            ticket: ticket.id,
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+            message_id,
+        };
+        self.db.comments().create_comment(comment.clone()).await?;
+        ticket.last_modification = comment.created_at;
+        self.db
+            .tickets()
+            .update_ticket(&ticket.id.to_string(), ticket.clone())
+            .await?;
+        self.events
+            .publish(DomainEvent::CommentAdded {
+                comment: comment.clone(),
+                ticket,
+            })
+            .await?;
+        Ok(comment)
+    }
+
+    /// The ticket an email with one of these `Message-ID`s was filed to, as the
+    /// ticket itself or a comment on it.
+    async fn ticket_of_message(&self, message_ids: &[String]) -> Result<Option<i64>, AppError> {
+        if message_ids.is_empty() {
+            return Ok(None);
+        }
+        for id in message_ids {
+            if let Some(comment) = self.db.comments().find_comment_by_message_id(id).await? {
+                return Ok(Some(comment.ticket));
+            }
+        }
+        Ok(self
+            .tickets()
+            .await?
+            .into_iter()
+            .find(|t| t.message_id.as_ref().is_some_and(|m| message_ids.contains(m)))
+            .map(|t| t.id))
+    }
+
+    /// Files an email: a reply to one already filed, going by `In-Reply-To` and
+    /// `References` (`replied_to`), becomes a comment on its ticket, anything else a
+    /// new ticket of the project at its least severe level, assigned by its rules.
+    /// Emails already filed are not filed again. Users need `CREATE` as they would
+    /// through the API; external senders may file tickets in the project and reply
+    /// on its tickets only.
+    pub async fn ingest_email(
+        &self,
+        email: IncomingEmail,
+        sender: EmailSender,
+        project: &str,
+    ) -> Result<(Ticket, Option<Comment>, bool), AppError> {
+        if let Some(id) = &email.message_id
+            && let Some(ticket) = self.ticket_of_message(std::slice::from_ref(id)).await?
+        {
+            return Ok((self.ticket(&ticket.to_string()).await?, None, false));
+        }
+        let (author, principals) = match &sender {
+            EmailSender::User { username, principals } => (username.as_str(), Some(principals.as_slice())),
+            EmailSender::External(address) => (address.as_str(), None),
+        };
+
+        if let Some(ticket) = self.ticket_of_message(&email.replied_to).await? {
+            let id = ticket.to_string();
+            let ticket = match principals {
+                Some(principals) => Some(self.ticket_for(&id, principals, Permissions::CREATE).await?),
+                None => Some(self.ticket(&id).await?).filter(|t| t.project.as_deref() == Some(project)),
+            };
+            if let Some(ticket) = ticket {
+                let comment = self.comment_on(ticket, author, &email.body, email.message_id).await?;
+                return Ok((self.ticket(&id).await?, Some(comment), true));
+            }
+        }
+
+        let scale = self
+            .ticket_project(Some(project))
+            .await?
+            .as_ref()
+            .map_or_else(Severity::default_scale, Project::severity_scale);
+        let subject = email.subject.trim();
+        let req = CreateTicketRequest {
+            title: if subject.is_empty() { "(no subject)".to_string() } else { subject.to_string() },
+            severity: scale.iter().map(|s| s.level).max().unwrap_or_default(),
+            severity_label: String::new(),
+            description: email.body,
+            assigned_to: String::new(),
+            mentioned: Vec::new(),
+            project: Some(project.to_string()),
+            custom_fields: HashMap::new(),
+            due_date: None,
+            ticket_group: None,
+            draft: None,
+        };
+        let ticket = self.insert_ticket(author, principals, req, email.message_id).await?;
+        Ok((ticket, None, true))
+    }
+
+    /// Files a ticket submitted through the project's public portal, created by the
+    /// portal's reporter at the lowest severity. The reporter's address, if given, is
+    /// added to the description.
+    pub async fn file_public(
+        &self,
+        project: &Project,
+        portal: &PublicPortal,
+        req: PublicTicketRequest,
+    ) -> Result<Ticket, AppError> {
+        if req.title.chars().count() > MAX_PUBLIC_TITLE {
+            return Err(AppError::Validation(format!("Title is longer than {} characters", MAX_PUBLIC_TITLE)));
+        }
+        if req.description.len() > MAX_PUBLIC_DESCRIPTION {
+            return Err(AppError::Validation(format!(
+                "Description is longer than {} bytes",
+                MAX_PUBLIC_DESCRIPTION
+            )));
+        }
+        let mut description = req.description.trim().to_string();
+        if let Some(email) = req.email.as_deref().filter(|e| !e.trim().is_empty()) {
+            let email = validate_email_address(email, &[]).map_err(AppError::Validation)?;
+            description = format!("{}\n\nReported through the public portal by {}", description, email)
+                .trim_start()
+                .to_string();
+        }
+        let scale = project.severity_scale();
+        let req = CreateTicketRequest {
+            title: req.title,
+            severity: scale.iter().map(|s| s.level).max().unwrap_or_default(),
+            severity_label: String::new(),
+            description,
+            assigned_to: String::new(),
+            mentioned: Vec::new(),
+            project: Some(project.id.to_string()),
+            custom_fields: HashMap::new(),
+            due_date: None,
+            ticket_group: None,
+            draft: None,
+        };
+        self.insert_ticket(&portal.reporter(), None, req, None).await
+    }
+
+    /// Lists the tickets the principals can list, reduced to `fields` if given.
+    /// Unfiltered listings are projected by the database, filtered ones after matching.
+    /// Unless left out of `fields`, `unread` tells whether the ticket changed since the
+    /// viewer last saw it.
+    pub async fn list_tickets(
+        &self,
+        viewer: &str,
+        principals: &[String],
+        fields: Option<&[String]>,
+        filter: &TicketFilter,
+    ) -> Result<Vec<Value>, AppError> {
+        let selected = fields.is_none_or(|fields| fields.iter().any(|f| f == UNREAD_FIELD));
+        let seen: HashMap<i64, DateTime<Utc>> = if selected {
+            let receipts = self.db.receipts().list_receipts_of(viewer).await?;
+            receipts.into_iter().map(|r| (r.ticket, r.seen_at)).collect()
+        } else {
+            HashMap::new()
+        };
+        let unread = |id: i64, changed: DateTime<Utc>| {
+            selected.then(|| seen.get(&id).is_none_or(|seen| *seen < changed))
+        };
+        match fields {
+            Some(fields) if filter.is_empty() => {
+                let mut model = model_fields(fields);
+                for attr in ["id", "last_modification", "project", "ticket_group", "created_by", "assigned_to"] {
+                    if !model.iter().any(|m| m == attr) {
+                        model.push(attr.to_string());
+                    }
+                }
+                let projects = self.db.projects().list_projects().await?;
+                self.db
+                    .tickets()
+                    .list_tickets_fields(&model)
+                    .await?
+                    .into_iter()
+                    .filter(|t| {
+                        acl::ticket_permissions_among(
+                            &projects,
+                            t["project"].as_str(),
+                            t["ticket_group"].as_str(),
+                            (
+                                t["created_by"].as_str().unwrap_or_default(),
+                                t["assigned_to"].as_str().unwrap_or_default(),
+                            ),
+                            principals,
+                        )
+                        .contains(Permissions::LIST)
+                    })
+                    .map(|t| {
+                        let changed = serde_json::from_value(t["last_modification"].clone())?;
+                        let unread = unread(t["id"].as_i64().unwrap_or_default(), changed);
+                        Ok(with_unread(project_ticket(t, fields), unread))
+                    })
+                    .collect()
+            }
+            Some(fields) => self
+                .tickets_for(principals, Permissions::LIST)
+                .await?
+                .into_iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| {
+                    let unread = unread(t.id, t.last_modification);
+                    Ok(with_unread(project_ticket(serde_json::to_value(t)?, fields), unread))
+                })
+                .collect(),
+            None => self
+                .tickets_for(principals, Permissions::LIST)
+                .await?
+                .into_iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| {
+                    let unread = unread(t.id, t.last_modification);
+                    Ok(with_unread(serde_json::to_value(TicketResponse::from(t))?, unread))
+                })
+                .collect(),
+        }
+    }
+
+    /// Records that the user saw the ticket as it is now.
+    pub async fn mark_seen(&self, id: &str, username: &str, principals: &[String]) -> Result<ReadReceipt, AppError> {
+        let ticket = self.ticket_for(id, principals, Permissions::FETCH).await?;
+        let receipt = ReadReceipt {
+            ticket: ticket.id,
+            username: username.to_string(),
+            seen_at: Utc::now(),
+        };
+        self.db.receipts().set_receipt(receipt.clone()).await?;
+        Ok(receipt)
+    }
+
+    /// Who saw the ticket and when, most recent first.
+    pub async fn receipts(&self, id: &str, principals: &[String]) -> Result<Vec<ReadReceipt>, AppError> {
+        let ticket = self.ticket_for(id, principals, Permissions::FETCH).await?;
+        self.db.receipts().list_receipts(ticket.id).await
+    }
+
+    /// Escalates unresolved tickets that have been idle for longer than their project's
+    /// first matching policy allows, at most once per period of inactivity. Returns the
+    /// escalated tickets with the policy applied.
+    pub async fn escalate_stale(&self, now: DateTime<Utc>) -> Result<Vec<(Ticket, EscalationPolicy)>, AppError> {
+        let projects: HashMap<String, Project> = self
+            .db
+            .projects()
+            .list_projects()
+            .await?
+            .into_iter()
+            .filter(|p| !p.escalation_policies.is_empty())
+            .map(|p| (p.id.to_string(), p))
+            .collect();
+        let mut escalated = Vec::new();
+        for mut ticket in self.tickets().await? {
+            let Some(project) = ticket.project.as_ref().and_then(|id| projects.get(id)) else {
+                continue;
+            };
+            let unresolved = matches!(ticket.status, TicketStatus::Open | TicketStatus::InProgress);
+            let escalated_since = ticket.escalated_at.is_some_and(|at| at >= ticket.last_modification);
+            if !unresolved || escalated_since {
+                continue;
+            }
+            let idle = now - ticket.last_modification;
+            let Some(policy) = project.escalation_policies.iter().find(|policy| {
+                ticket.severity.level <= policy.severity && idle >= TimeDelta::hours(policy.idle_hours.into())
+            }) else {
+                continue;
+            };
+            if policy.reassign {
+                ticket.assigned_to = policy.group.clone();
+            }
+            ticket.escalated_at = Some(now);
+            ticket.last_modification = now;
+            self.db
+                .tickets()
+                .update_ticket(&ticket.id.to_string(), ticket.clone())
+                .await?;
+            escalated.push((ticket, policy.clone()));
+        }
+        Ok(escalated)
+    }
+
+    /// Recent tickets the principals can list of the same project, or also outside any,
+    /// whose title shares enough words with `title` to be the same issue. Most similar
+    /// first.
+    pub async fn duplicate_candidates(
+        &self,
+        title: &str,
+        project: Option<&str>,
+        principals: &[String],
+    ) -> Result<Vec<(Ticket, f64)>, AppError> {
+        let since = Utc::now() - TimeDelta::days(DUPLICATE_WINDOW_DAYS);
+        let mut candidates: Vec<(Ticket, f64)> = self
+            .tickets_for(principals, Permissions::LIST)
+            .await?
+            .into_iter()
+            .filter(|t| t.project.as_deref() == project && t.creation_date >= since)
+            .map(|t| {
+                let score = similarity(title, &t.title);
+                (t, score)
+            })
+            .filter(|(_, score)| *score >= DUPLICATE_THRESHOLD)
+            .collect();
+        // Newer tickets first among equally similar ones
+        candidates.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then(b.id.cmp(&a.id)));
+        candidates.truncate(MAX_DUPLICATES);
+        Ok(candidates)
+    }
+
+    /// The project's tickets the principals can list in board order, by status.
+    pub async fn board(
+        &self,
+        project: &str,
+        principals: &[String],
+    ) -> Result<Vec<(TicketStatus, Vec<Ticket>)>, AppError> {
+        let tickets = self.tickets_for(principals, Permissions::LIST).await?;
+        let project = Some(project.to_string());
+        Ok(TicketStatus::ALL
+            .into_iter()
+            .map(|status| {
+                let tickets = column(&tickets, &project, status, None).into_iter().cloned().collect();
+                (status, tickets)
+            })
+            .collect())
+    }
+
+    /// Moves the ticket to another project or ticket group of its project, the principals
+    /// need `CREATE` there. It takes the destination's label for its severity level,
+    /// keeps the custom fields defined there and the mentioned principals who can fetch
+    /// it there, and leaves the milestone of its old project behind. Ticket numbers are
+    /// shared by all projects, so it keeps its id and its comments.
+    async fn relocate(
+        &self,
+        ticket: &mut Ticket,
+        principals: &[String],
+        project: Option<String>,
+        group: Option<String>,
+    ) -> Result<(), AppError> {
+        if project == ticket.project && group == ticket.ticket_group {
+            return Err(AppError::Validation(format!("Ticket {} is already there", ticket.id)));
+        }
+        let Some(destination) = self.ticket_project(project.as_deref()).await? else {
+            return Err(AppError::Validation("Only tickets of a project can join a ticket group".to_string()));
+        };
+        let destination_id = destination.id.to_string();
+        let ancestors = acl::ancestors(self.db.as_ref(), &destination).await?;
+        if !acl::project_allows(&destination, &ancestors, principals, Permissions::FETCH) {
+            return Err(AppError::NotFound(format!("Project {} not found", destination_id)));
+        }
+        let target = match &group {
+            Some(prefix) => Some(
+                destination
+                    .tickets
+                    .iter()
+                    .find(|g| g.prefix == *prefix)
+                    .ok_or_else(|| AppError::Validation(format!("Ticket group {} not found in the project", prefix)))?,
+            ),
+            None => None,
+        };
+        let permissions = |principals: &[String]| match target {
+            Some(target) => acl::group_permissions(&destination, &ancestors, target, principals),
+            None => acl::project_permissions(&destination, &ancestors, principals),
+        };
+        if !permissions(principals).contains(Permissions::CREATE) {
+            return Err(AppError::Authorization(format!(
+                "Not allowed to create tickets in project {}",
+                destination_id
+            )));
+        }
+
+        ticket.severity = Severity::on_scale(&destination.severity_scale(), ticket.severity.level, "")
+            .map_err(AppError::Validation)?;
+        if ticket.project.as_deref() != Some(destination_id.as_str()) {
+            ticket
+                .custom_fields
+                .retain(|name, _| destination.custom_fields.iter().any(|d| d.name == *name));
+            ticket.milestone = None;
+            ticket.escalated_at = None;
+        }
+        validate_values(&destination.custom_fields, &ticket.custom_fields).map_err(AppError::Validation)?;
+        let groups = self.db.groups().list_groups().await?;
+        ticket.mentioned.retain(|name| {
+            let mut principals = vec![name.clone()];
+            principals.extend(groups.iter().filter(|g| g.principals.contains(name)).map(|g| g.gid.clone()));
+            permissions(&principals).contains(Permissions::FETCH)
+        });
+        ticket.project = Some(destination_id);
+        ticket.ticket_group = group;
+        Ok(())
+    }
+
+    /// Moves a ticket to `position` in the column of `status`, changing its status if
+    /// needed. With `project` or `ticket_group` it moves to that project's board first,
+    /// see `relocate`, and a comment records where it came from. The principals need
+    /// `MODIFY` on the ticket where it is, if it is in a project.
+    ///
+    /// Only the moved ticket is written, between the ranks of its new neighbours, so
+    /// concurrent moves of other tickets never overwrite each other. Moves are also
+    /// serialized on this instance, so two tickets dropped into the same spot at once
+    /// both land there, one after the other.
+    pub async fn move_ticket(
+        &self,
+        id: &str,
+        actor: &str,
+        principals: &[String],
+        req: MoveTicketRequest,
+    ) -> Result<Ticket, AppError> {
+        let _guard = self.ranking.lock().await;
+        let mut ticket = self.ticket_for(id, principals, Permissions::MODIFY).await?;
+        let (status, position) = (req.status, req.position);
+        let from = (ticket.project.clone(), ticket.ticket_group.clone());
+        let relocated = req.project.is_some() || req.ticket_group.is_some();
+        if relocated {
+            let project = req.project.or_else(|| ticket.project.clone());
+            self.relocate(&mut ticket, principals, project, req.ticket_group).await?;
+        }
+
+        let tickets = self.tickets().await?;
+        let mut neighbours: Vec<Ticket> = column(&tickets, &ticket.project, status, Some(ticket.id))
+            .into_iter()
+            .cloned()
+            .collect();
+        let unranked = neighbours.iter().any(|t| t.rank.is_empty())
+            || neighbours.windows(2).any(|pair| pair[0].rank == pair[1].rank);
+        if unranked {
+            // Tickets from before ranks existed are ranked in their current order
+            let mut previous: Option<String> = None;
+            for t in neighbours.iter_mut() {
+                t.rank = rank::between(previous.as_deref(), None);
+                previous = Some(t.rank.clone());
+                self.db.tickets().update_ticket(&t.id.to_string(), t.clone()).await?;
+            }
+        }
+
+        let position = position.min(neighbours.len());
+        let before = position.checked_sub(1).map(|i| neighbours[i].rank.as_str());
+        let after = neighbours.get(position).map(|t| t.rank.as_str());
+        ticket.rank = rank::between(before, after);
+        let previous_status = ticket.status;
+        if ticket.status != status {
+            ticket.resolved_at = match status {
+                TicketStatus::Resolved | TicketStatus::Closed => ticket.resolved_at.or(Some(Utc::now())),
+                TicketStatus::Open | TicketStatus::InProgress => None,
+            };
+            ticket.status = status;
+        }
+        ticket.last_modification = Utc::now();
+        self.db.tickets().update_ticket(id, ticket.clone()).await?;
+        if relocated {
+            let place = |project: &Option<String>, group: &Option<String>| match (project, group) {
+                (Some(project), Some(group)) => format!("ticket group {} of project {}", group, project),
+                (Some(project), None) => format!("project {}", project),
+                (None, _) => "no project".to_string(),
+            };
+            let comment = Comment {
+                id: uuid::Uuid::now_v7().to_string(),
+                ticket: ticket.id,
+                author: actor.to_string(),
+                body: format!(
+                    "Moved from {} to {}",
+                    place(&from.0, &from.1),
+                    place(&ticket.project, &ticket.ticket_group)
+                ),
+                created_at: ticket.last_modification,
+                message_id: None,
+            };
+            self.db.comments().create_comment(comment).await?;
+        }
+        self.publish(TicketEventKind::Moved, &ticket, actor, Some(previous_status)).await?;
+        Ok(ticket)
+    }
+
+    /// Fetches a ticket the principals can fetch, reduced to `fields` if given, along
+    /// with its last modification time.
+    pub async fn get_ticket(
+        &self,
+        id: &str,
+        principals: &[String],
+        fields: Option<&[String]>,
+    ) -> Result<(Value, DateTime<Utc>), AppError> {
+        let ticket = self.ticket_for(id, principals, Permissions::FETCH).await?;
+        let last_modified = ticket.last_modification;
+        match fields {
+            Some(fields) => Ok((project_ticket(serde_json::to_value(ticket)?, fields), last_modified)),
+            None => Ok((serde_json::to_value(TicketResponse::from(ticket))?, last_modified)),
+        }
     }
 }
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    acl::AclCache,
+    db::DatabaseInterface,
+    error::AppError,
+    schema::{DeletedEntity, DeletedKind},
+};
+
+fn noun(kind: DeletedKind) -> &'static str {
+    match kind {
+        DeletedKind::User => "user",
+        DeletedKind::Group => "group",
+        DeletedKind::Project => "project",
+        DeletedKind::Ticket => "ticket",
+    }
+}
+
+/// Soft-deleted users, groups, projects and tickets, restorable for `retention` after
+/// their deletion and removed for good by `purge_expired` once it is over.
+pub struct TrashController {
+    pub db: Arc<dyn DatabaseInterface>,
+    acl_cache: Arc<AclCache>,
+}
+
+impl TrashController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, acl_cache: Arc<AclCache>) -> Self {
+        Self { db, acl_cache }
+    }
+
+    /// Everything in the trash, oldest deletion first, with when it is removed for good.
+    pub async fn entities(&self, retention: TimeDelta) -> Result<Vec<DeletedEntity>, AppError> {
+        let entity = |kind, id: String, deleted_at: DateTime<Utc>| DeletedEntity {
+            kind,
+            id,
+            deleted_at,
+            purge_at: deleted_at + retention,
+        };
+        let mut entities: Vec<DeletedEntity> = Vec::new();
+        for d in self.db.users().list_deleted_users().await? {
+            entities.push(entity(DeletedKind::User, d.entity.username, d.deleted_at));
+        }
+        for d in self.db.groups().list_deleted_groups().await? {
+            entities.push(entity(DeletedKind::Group, d.entity.gid, d.deleted_at));
+        }
+        for d in self.db.projects().list_deleted_projects().await? {
+            entities.push(entity(DeletedKind::Project, d.entity.id.to_string(), d.deleted_at));
+        }
+        for d in self.db.tickets().list_deleted_tickets().await? {
+            entities.push(entity(DeletedKind::Ticket, d.entity.id.to_string(), d.deleted_at));
+        }
+        entities.sort_by_key(|e| e.deleted_at);
+        Ok(entities)
+    }
+
+    async fn find(&self, kind: DeletedKind, id: &str, retention: TimeDelta) -> Result<DeletedEntity, AppError> {
+        self.entities(retention)
+            .await?
+            .into_iter()
+            .find(|e| e.kind == kind && e.id == id && e.purge_at > Utc::now())
+            .ok_or_else(|| AppError::NotFound(format!("Deleted {} {} not found", noun(kind), id)))
+    }
+
+    /// Takes an entity out of the trash, unless its retention is over.
+    pub async fn restore(&self, kind: DeletedKind, id: &str, retention: TimeDelta) -> Result<(), AppError> {
+        self.find(kind, id, retention).await?;
+        match kind {
+            DeletedKind::User => {
+                self.db.users().restore_user(id).await?;
+                self.acl_cache.invalidate_user(id);
+            }
+            DeletedKind::Group => {
+                self.db.groups().restore_group(id).await?;
+                // Its members are in it again
+                for member in self.db.groups().get_group(id).await?.principals {
+                    self.acl_cache.invalidate_user(&member);
+                }
+            }
+            DeletedKind::Project => {
+                self.db.projects().restore_project(id).await?;
+                self.acl_cache.invalidate_project(id);
+            }
+            DeletedKind::Ticket => self.db.tickets().restore_ticket(id).await?,
+        }
+        Ok(())
+    }
+
+    /// Removes an entity in the trash for good, without waiting for its retention to end.
+    pub async fn remove(&self, kind: DeletedKind, id: &str, retention: TimeDelta) -> Result<(), AppError> {
+        self.find(kind, id, retention).await?;
+        self.remove_for_good(kind, id).await
+    }
+
+    async fn remove_for_good(&self, kind: DeletedKind, id: &str) -> Result<(), AppError> {
+        match kind {
+            DeletedKind::User => self.db.users().delete_user(id, true).await,
+            DeletedKind::Group => self.db.groups().delete_group(id, true).await,
+            DeletedKind::Project => self.db.projects().delete_project(id, true).await,
+            DeletedKind::Ticket => self.db.tickets().delete_ticket(id, true).await,
+        }
+    }
+
+    /// Removes for good what was deleted longer than `retention` before `now`. Returns
+    /// what was removed.
+    pub async fn purge_expired(&self, retention: TimeDelta, now: DateTime<Utc>) -> Result<Vec<DeletedEntity>, AppError> {
+        let mut purged = Vec::new();
+        for entity in self.entities(retention).await? {
+            if entity.purge_at > now {
+                break; // oldest first, the rest are younger
+            }
+            match self.remove_for_good(entity.kind, &entity.id).await {
+                // Removed meanwhile
+                Err(AppError::NotFound(_)) => continue,
+                result => result?,
+            }
+            purged.push(entity);
+        }
+        Ok(purged)
+    }
+}
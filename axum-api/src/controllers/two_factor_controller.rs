@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    middleware::auth::now_secs,
+    models::{TwoFactor, User},
+    utils::{constant_time_eq, encryption::FieldCipher, random_token, sha256_hex},
+};
+
+const RECOVERY_CODES_COUNT: usize = 8;
+const RECOVERY_CODE_LENGTH: usize = 10;
+const STEP_SECS: u64 = 30;
+// Wrong codes a login challenge takes before it is rejected, even with the right one
+pub const MAX_CHALLENGE_FAILURES: u32 = 5;
+// Authenticated along with the sealed secret, see `utils::encryption`
+const SECRET_FIELD: &str = "two_factor.secret";
+
+pub struct TwoFactorController {
+    pub db: Arc<dyn DatabaseInterface>,
+    cipher: Option<FieldCipher>,                   // seals secrets, if encryption keys are configured
+    failures: Mutex<HashMap<String, (u32, usize)>>, // wrong codes and expiry, by user and challenge id
+}
+
+fn build_totp(secret: &str, issuer: Option<String>, username: &str) -> Result<TOTP, AppError> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::Internal(anyhow!("Invalid TOTP secret: {:?}", e)))?;
+    TOTP::new(Algorithm::SHA1, 6, 1, 30, bytes, issuer, username.to_string())
+        .map_err(|e| AppError::Internal(anyhow!("Invalid TOTP parameters: {}", e)))
+}
+
+/// The time step the code belongs to, if it is one of the current step or of the
+/// steps next to it, for clocks a little off.
+fn matching_step(totp: &TOTP, code: &str) -> Option<u64> {
+    let current = now_secs() as u64 / STEP_SECS;
+    (current.saturating_sub(1)..=current + 1).find(|step| constant_time_eq(&totp.generate(step * STEP_SECS), code.trim()))
+}
+
+impl TwoFactorController {
+    pub fn new(db: Arc<dyn DatabaseInterface>, cipher: Option<FieldCipher>) -> Self {
+        Self {
+            db,
+            cipher,
+            failures: Mutex::default(),
+        }
+    }
+
+    fn seal(&self, secret: String) -> Result<String, AppError> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(SECRET_FIELD, &secret),
+            None => Ok(secret),
+        }
+    }
+
+    /// The plain secret. Secrets stored before encryption was configured are plain already.
+    fn open(&self, secret: &str) -> Result<String, AppError> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(SECRET_FIELD, secret),
+            None if FieldCipher::is_encrypted(secret) => Err(AppError::Internal(anyhow!(
+                "TOTP secret is encrypted, but no encryption keys are configured"
+            ))),
+            None => Ok(secret.to_string()),
+        }
+    }
+
+    /// Checks a TOTP code and spends it with every code before it: a code is accepted
+    /// once, however long its step lasts.
+    fn check_code(&self, two_factor: &mut TwoFactor, username: &str, code: &str) -> Result<bool, AppError> {
+        let totp = build_totp(&self.open(&two_factor.secret)?, None, username)?;
+        match matching_step(&totp, code) {
+            Some(step) if two_factor.last_step.is_none_or(|last| step > last) => {
+                two_factor.last_step = Some(step);
+                Ok(true)
+            }
+            Some(_) => {
+                log::warn!(target: "audit", "Reused TOTP code rejected for {}", username);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn is_enabled(user: &User) -> bool {
+        user.two_factor.as_ref().is_some_and(|tf| tf.enabled)
+    }
+
+    /// Generates a new (not yet enabled) TOTP secret for the user.
+    /// Returns the otpauth URI and plain recovery codes; only hashes are stored.
+    pub async fn enroll(
+        &self,
+        username: &str,
+        issuer: &str,
+    ) -> Result<(String, Vec<String>), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        if Self::is_enabled(&user) {
+            return Err(AppError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        let secret = match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(s) => s,
+            Secret::Raw(_) => unreachable!(),
+        };
+        let uri = build_totp(&secret, Some(issuer.to_string()), username)?.get_url();
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT)
+            .map(|_| random_token(RECOVERY_CODE_LENGTH))
+            .collect();
+
+        user.two_factor = Some(TwoFactor {
+            secret: self.seal(secret)?,
+            enabled: false,
+            recovery_codes: recovery_codes.iter().map(|c| sha256_hex(c)).collect(),
+            last_step: None,
+        });
+        self.db.users().update_user(username, user).await?;
+
+        Ok((uri, recovery_codes))
+    }
+
+    /// Enables 2FA after the user proves the authenticator app is set up.
+    pub async fn confirm(&self, username: &str, code: &str) -> Result<(), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        let two_factor = user.two_factor.as_mut().ok_or_else(|| {
+            AppError::BadRequest("Two-factor enrollment not started".to_string())
+        })?;
+        if two_factor.enabled {
+            return Err(AppError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+        if !self.check_code(two_factor, username, code)? {
+            return Err(AppError::Validation("Invalid verification code".to_string()));
+        }
+
+        two_factor.enabled = true;
+        self.db.users().update_user(username, user).await
+    }
+
+    /// Checks the code answering the login challenge with id `challenge`, see `verify`. After
+    /// `MAX_CHALLENGE_FAILURES` wrong codes the challenge is spent: guessing goes on
+    /// only with the password again. `expires` is when the challenge does anyway.
+    pub async fn verify_challenge(&self, challenge: &str, expires: usize, username: &str, code: &str) -> Result<bool, AppError> {
+        let key = format!("{}/{}", username, challenge);
+        let spent = || AppError::Authorization("Too many wrong codes, log in again".to_string());
+        if self.failures.lock().unwrap().get(&key).is_some_and(|(count, _)| *count >= MAX_CHALLENGE_FAILURES) {
+            return Err(spent());
+        }
+        if self.verify(username, code).await? {
+            self.failures.lock().unwrap().remove(&key);
+            return Ok(true);
+        }
+        let mut failures = self.failures.lock().unwrap();
+        let now = now_secs();
+        failures.retain(|_, (_, expiry)| *expiry > now);
+        let count = &mut failures.entry(key).or_insert((0, expires)).0;
+        *count += 1;
+        if *count >= MAX_CHALLENGE_FAILURES {
+            log::warn!(target: "audit", "2FA challenge of {} spent after {} wrong codes", username, count);
+            return Err(spent());
+        }
+        Ok(false)
+    }
+
+    /// Checks a TOTP code or a recovery code, either of which is spent on success.
+    /// Secrets still stored plain are sealed on the way.
+    pub async fn verify(&self, username: &str, code: &str) -> Result<bool, AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        let Some(two_factor) = user.two_factor.as_mut().filter(|tf| tf.enabled) else {
+            return Ok(false);
+        };
+
+        if self.check_code(two_factor, username, code)? {
+            if self.cipher.as_ref().is_some_and(|c| !c.is_current(&two_factor.secret)) {
+                two_factor.secret = self.seal(self.open(&two_factor.secret)?)?;
+            }
+            self.db.users().update_user(username, user).await?;
+            return Ok(true);
+        }
+
+        let hashed = sha256_hex(code.trim());
+        let before = two_factor.recovery_codes.len();
+        two_factor.recovery_codes.retain(|c| *c != hashed);
+        if two_factor.recovery_codes.len() == before {
+            return Ok(false);
+        }
+
+        log::info!("Recovery code used by {}", username);
+        self.db.users().update_user(username, user).await?;
+        Ok(true)
+    }
+
+    /// Removes 2FA from the user entirely (management action).
+    pub async fn reset(&self, username: &str) -> Result<(), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        user.two_factor = None;
+        self.db.users().update_user(username, user).await
+    }
+}
@@ -1,18 +1,394 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use crate::db::DatabaseInterface;
+use bcrypt::{DEFAULT_COST, hash, verify};
+use chrono::Utc;
+
+use crate::{
+    config::AppConfig,
+    controllers::{principal_controller::current_user, session_controller::ValidatedSessions},
+    db::DatabaseInterface,
+    error::AppError,
+    events::{DomainEvent, EventBus},
+    models::User,
+    schema,
+    utils::{encryption::FieldCipher, random_token, sha256_hex, time::parse_timezone},
+    validation::{
+        email::validate_email_address,
+        metadata::{MAX_KEYS, validate_key, validate_value},
+        naming::validate_username,
+    },
+};
+
+const CALENDAR_TOKEN_LENGTH: usize = 40;
+
+/// What the app config asks of the email of new accounts.
+#[derive(Clone, Copy, Default)]
+pub struct RegistrationRules<'a> {
+    pub allowed_email_domains: &'a [String],
+    pub require_email: bool,
+}
+
+/// Result of a password login. Rejections carry the detail recorded as a security
+/// event, the client only ever sees a generic error.
+pub enum Authentication {
+    Authenticated(Box<User>),
+    Rejected(&'static str),
+}
+
+/// User metadata keys stored encrypted, with the cipher sealing them. Callers of the
+/// controller only ever see the values decrypted.
+pub struct MetadataEncryption {
+    cipher: FieldCipher,
+    keys: Vec<String>,
+}
+
+impl MetadataEncryption {
+    /// `None` unless the config names metadata keys to encrypt.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        if config.encrypted_metadata_keys.is_empty() {
+            return None;
+        }
+        Some(Self {
+            cipher: FieldCipher::new(&config.encryption_keys)?,
+            keys: config.encrypted_metadata_keys.clone(),
+        })
+    }
+
+    fn field(key: &str) -> String {
+        format!("metadata.{}", key)
+    }
+
+    fn is_encrypted(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k == key)
+    }
+
+    /// Encrypts a value being set, if its key is one of the encrypted ones.
+    fn encrypt(&self, key: &str, value: String) -> Result<String, AppError> {
+        if self.is_encrypted(key) {
+            return self.cipher.encrypt(&Self::field(key), &value);
+        }
+        Ok(value)
+    }
+
+    /// Re-encrypts stored values of encrypted keys that aren't under the current key,
+    /// plain ones included. Returns how many were.
+    fn reseal(&self, metadata: &mut HashMap<String, String>) -> Result<usize, AppError> {
+        let mut resealed = 0;
+        for (key, value) in metadata.iter_mut() {
+            if self.is_encrypted(key) && !self.cipher.is_current(value) {
+                let field = Self::field(key);
+                *value = self.cipher.encrypt(&field, &self.cipher.decrypt(&field, value)?)?;
+                resealed += 1;
+            }
+        }
+        Ok(resealed)
+    }
+
+    /// Decrypts the values of encrypted keys. Keys no longer encrypted may still hold
+    /// sealed values, those are decrypted if they can be and left as they are if not.
+    fn decrypt(&self, metadata: &mut HashMap<String, String>) -> Result<(), AppError> {
+        for (key, value) in metadata.iter_mut() {
+            let field = Self::field(key);
+            if self.is_encrypted(key) {
+                *value = self.cipher.decrypt(&field, value)?;
+            } else if FieldCipher::is_encrypted(value)
+                && let Ok(plain) = self.cipher.decrypt(&field, value)
+            {
+                *value = plain;
+            }
+        }
+        Ok(())
+    }
+}
 
 pub struct UserController {
     pub db: Arc<dyn DatabaseInterface>,
+    validated: Arc<ValidatedSessions>,
+    events: Arc<EventBus>,
+    encryption: Option<MetadataEncryption>,
 }
 
 impl UserController {
-    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
-        Self { db }
+    pub fn new(
+        db: Arc<dyn DatabaseInterface>,
+        validated: Arc<ValidatedSessions>,
+        events: Arc<EventBus>,
+        encryption: Option<MetadataEncryption>,
+    ) -> Self {
+        Self {
+            db,
+            validated,
+            events,
+            encryption,
+        }
+    }
+
+    fn encrypt_metadata(&self, key: &str, value: String) -> Result<String, AppError> {
+        match &self.encryption {
+            Some(encryption) => encryption.encrypt(key, value),
+            None => Ok(value),
+        }
+    }
+
+    fn decrypt_metadata(&self, metadata: &mut HashMap<String, String>) -> Result<(), AppError> {
+        self.encryption.as_ref().map_or(Ok(()), |e| e.decrypt(metadata))
+    }
+
+    pub async fn get_user(&self, username: &str) -> Result<User, AppError> {
+        self.db.users().get_user(username).await
+    }
+
+    /// The user with the email address, if any.
+    pub async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        match self.db.users().find_user_by_email(&email.trim().to_lowercase()).await {
+            Ok(user) => Ok(Some(user)),
+            Err(AppError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Hashes a plain text password using bcrypt.
+    pub fn hash_password(password: &str) -> Result<String, AppError> {
+        hash(password, DEFAULT_COST).map_err(AppError::BcryptError)
+    }
+
+    /// Verifies a plain text password against a bcrypt hash.
+    pub fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
+        verify(password, hash).map_err(AppError::BcryptError)
+    }
+
+    /// Builds a new account: validates username and email, hashes the password.
+    /// Nothing is stored, see `register`.
+    pub fn new_user(
+        &self,
+        username: &str,
+        password: &str,
+        email: Option<&str>,
+        rules: RegistrationRules,
+    ) -> Result<User, AppError> {
+        let email = match email {
+            Some(email) => Some(
+                validate_email_address(email, rules.allowed_email_domains).map_err(AppError::Validation)?,
+            ),
+            None if rules.require_email || !rules.allowed_email_domains.is_empty() => {
+                return Err(AppError::Validation("Email is required".to_string()));
+            }
+            None => None,
+        };
+
+        let mut user: User = schema::User {
+            username: validate_username(username).map_err(AppError::Validation)?,
+            password_hash: Self::hash_password(password)?,
+        }
+        .into();
+        user.email = email;
+        Ok(user)
+    }
+
+    /// Stores a user built by `new_user`. Fails with `Conflict` if the username or
+    /// email is taken.
+    pub async fn register(&self, mut user: User) -> Result<(), AppError> {
+        for (key, value) in user.metadata.iter_mut() {
+            *value = self.encrypt_metadata(key, std::mem::take(value))?;
+        }
+        let username = user.username.clone();
+        self.db.users().create_user(user).await?;
+        let event = DomainEvent::UserRegistered {
+            username: username.clone(),
+            at: Utc::now(),
+        };
+        if let Err(e) = self.events.publish(event).await {
+            if let Err(cleanup) = self.db.users().delete_user(&username, true).await {
+                log::error!("User {} stays without their registration event: {}", username, cleanup);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Checks a password login. `login` is the username, or the user's email (usernames
+    /// can't contain `@`). With `require_verified`, users who haven't verified their
+    /// email are refused with an error of their own.
+    pub async fn authenticate(
+        &self,
+        login: &str,
+        password: &str,
+        require_verified: bool,
+    ) -> Result<Authentication, AppError> {
+        let user = if login.contains('@') {
+            self.db.users().find_user_by_email(&login.trim().to_lowercase()).await
+        } else {
+            self.db.users().get_user(login).await
+        };
+        let user = match user {
+            Ok(user) => user,
+            Err(AppError::NotFound(_)) => return Ok(Authentication::Rejected("Unknown user")),
+            Err(e) => return Err(e),
+        };
+
+        if user.service_account {
+            return Ok(Authentication::Rejected("Password login of a service account"));
+        }
+        if !Self::verify_password(password, &user.password_hash)? {
+            return Ok(Authentication::Rejected("Wrong password"));
+        }
+        if require_verified && !user.verified {
+            return Err(AppError::Authorization("Email not verified".to_string()));
+        }
+        Ok(Authentication::Authenticated(Box::new(user)))
+    }
+
+    pub async fn mark_email_verified(&self, username: &str) -> Result<(), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        user.verified = true;
+        self.db.users().update_user(username, user).await
+    }
+
+    /// The username the user goes by now, `username` unless they were renamed since.
+    pub async fn current_username(&self, username: &str) -> Result<String, AppError> {
+        match current_user(self.db.as_ref(), username).await {
+            Ok(user) => Ok(user.username),
+            Err(AppError::NotFound(_)) => Ok(username.to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks that the user exists, is active and tokens of the given generation are still valid.
+    pub async fn validate_user(&self, username: &str, generation: u64) -> Result<bool, AppError> {
+        match self.db.users().get_user(username).await {
+            Ok(user) => Ok(!user.deactivated && user.token_generation == generation),
+            Err(AppError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn token_generation(&self, username: &str) -> Result<u64, AppError> {
+        Ok(self.db.users().get_user(username).await?.token_generation)
+    }
+
+    /// Invalidates all outstanding tokens of the user, returns the new generation.
+    pub async fn bump_token_generation(&self, username: &str) -> Result<u64, AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        user.token_generation += 1;
+        let generation = user.token_generation;
+        self.db.users().update_user(username, user).await?;
+        self.validated.forget_user(username, None);
+        Ok(generation)
     }
 
-    pub async fn validate_user(&self, username: &str) -> bool {
-        let user_res = self.db.users().get_user(username).await;
-        user_res.is_ok()
-    }   
+    /// Issues a token for the user's calendar feed, replacing the previous one.
+    pub async fn issue_calendar_token(&self, username: &str) -> Result<String, AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        let token = random_token(CALENDAR_TOKEN_LENGTH);
+        user.calendar_token = Some(sha256_hex(&token));
+        self.db.users().update_user(username, user).await?;
+        Ok(token)
+    }
+
+    pub async fn revoke_calendar_token(&self, username: &str) -> Result<(), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        if user.calendar_token.take().is_some() {
+            self.db.users().update_user(username, user).await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the user's timezone by IANA name, or back to UTC with `None`.
+    pub async fn set_timezone(&self, username: &str, timezone: Option<&str>) -> Result<User, AppError> {
+        let timezone = timezone.map(parse_timezone).transpose()?;
+        let mut user = self.db.users().get_user(username).await?;
+        user.personal.timezone = timezone.map(|tz| tz.name().to_string());
+        self.db.users().update_user(username, user.clone()).await?;
+        Ok(user)
+    }
+
+    pub async fn metadata(&self, username: &str) -> Result<HashMap<String, String>, AppError> {
+        let mut metadata = self.db.users().get_user(username).await?.metadata;
+        self.decrypt_metadata(&mut metadata)?;
+        Ok(metadata)
+    }
+
+    /// Sets a metadata key of the user, returns all of their metadata.
+    pub async fn set_metadata(
+        &self,
+        username: &str,
+        key: &str,
+        value: String,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let key = validate_key(key).map_err(AppError::Validation)?;
+        validate_value(&key, &value).map_err(AppError::Validation)?;
+        let mut user = self.db.users().get_user(username).await?;
+        if !user.metadata.contains_key(&key) && user.metadata.len() >= MAX_KEYS {
+            return Err(AppError::Validation(format!("Users have at most {} metadata keys", MAX_KEYS)));
+        }
+        let encrypted = self.encrypt_metadata(&key, value)?;
+        user.metadata.insert(key.clone(), encrypted);
+        let mut metadata = user.metadata.clone();
+        self.db.users().update_user(username, user).await?;
+        self.decrypt_metadata(&mut metadata)?;
+        Ok(metadata)
+    }
+
+    pub async fn remove_metadata(&self, username: &str, key: &str) -> Result<(), AppError> {
+        let mut user = self.db.users().get_user(username).await?;
+        let key = key.trim().to_lowercase();
+        if user.metadata.remove(&key).is_none() {
+            return Err(AppError::NotFound(format!("User {} has no metadata '{}'", username, key)));
+        }
+        self.db.users().update_user(username, user).await
+    }
+
+    /// Users with the metadata key, set to `value` if given. Service accounts only
+    /// if `service_accounts` is set. Encrypted values are compared once decrypted,
+    /// after a lookup by key alone.
+    pub async fn find_by_metadata(
+        &self,
+        key: &str,
+        value: Option<&str>,
+        service_accounts: bool,
+    ) -> Result<Vec<User>, AppError> {
+        let key = validate_key(key).map_err(AppError::Validation)?;
+        let encrypted = self.encryption.as_ref().is_some_and(|e| e.is_encrypted(&key));
+        let lookup = if encrypted { None } else { value };
+        let mut users = self.db.users().find_users_by_metadata(&key, lookup).await?;
+        users.retain(|u| service_accounts || !u.service_account);
+        for user in &mut users {
+            self.decrypt_metadata(&mut user.metadata)?;
+        }
+        if encrypted && let Some(value) = value {
+            users.retain(|u| u.metadata.get(&key).is_some_and(|v| v == value));
+        }
+        Ok(users)
+    }
+
+    /// Re-encrypts metadata under the current key, and encrypts values of keys that
+    /// were stored in plain before. Returns how many values were. Run after adding a
+    /// key, before removing the previous one from the config.
+    pub async fn reencrypt_metadata(&self) -> Result<usize, AppError> {
+        let Some(encryption) = &self.encryption else {
+            return Err(AppError::BadRequest("No user metadata is encrypted".to_string()));
+        };
+        let mut reencrypted = 0;
+        for mut user in self.db.users().list_users().await? {
+            let sealed = encryption.reseal(&mut user.metadata)?;
+            if sealed > 0 {
+                let username = user.username.clone();
+                self.db.users().update_user(&username, user).await?;
+                reencrypted += sealed;
+            }
+        }
+        Ok(reencrypted)
+    }
+
+    /// The active user a calendar feed token belongs to.
+    pub async fn calendar_owner(&self, token: &str) -> Result<User, AppError> {
+        let hashed = sha256_hex(token);
+        self.db
+            .users()
+            .list_users()
+            .await?
+            .into_iter()
+            .find(|u| !u.deactivated && u.calendar_token.as_deref() == Some(hashed.as_str()))
+            .ok_or_else(|| AppError::Authentication("Invalid calendar token".to_string()))
+    }
 }
@@ -0,0 +1,338 @@
+//! AQL queries whose values only ever travel as bind variables.
+//!
+//! Query text comes from string literals and from the builder, which writes a bind
+//! variable for every value it is given. Field paths and collection names are
+//! `&'static str`, so nothing a client sends can end up in the text. Before running,
+//! `Aql::check` makes sure the parameters of the text and the bound values match.
+//!
+//! ```ignore
+//! let query = Query::new("principals")
+//!     .filter("doc_type", Op::Eq, "user")
+//!     .filter("email", Op::Eq, email)
+//!     .limit(1)
+//!     .build();
+//! // FOR doc IN @@collection FILTER doc.doc_type == @filter0 FILTER doc.email == @filter1 LIMIT @limit RETURN doc
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Query text and its bind variables. Collection parameters are named with their
+/// leading `@`, as ArangoDB expects them (`@@edges` is bound as `@edges`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aql {
+    text: String,
+    bind_vars: BTreeMap<String, Value>,
+}
+
+impl Aql {
+    /// A query written out in full, its values given with `bind`.
+    pub fn new(text: &'static str) -> Self {
+        Self {
+            text: text.to_string(),
+            bind_vars: BTreeMap::new(),
+        }
+    }
+
+    pub fn bind(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.insert(name, value.into());
+        self
+    }
+
+    fn insert(&mut self, name: &str, value: Value) {
+        let previous = self.bind_vars.insert(name.to_string(), value);
+        debug_assert!(previous.is_none(), "@{} bound twice", name);
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn bind_vars(&self) -> &BTreeMap<String, Value> {
+        &self.bind_vars
+    }
+
+    /// Fails if a parameter of the text isn't bound, or a bound value isn't used.
+    pub fn check(self) -> Result<Self, AppError> {
+        let used = parameters(&self.text);
+        let bound: BTreeSet<&str> = self.bind_vars.keys().map(String::as_str).collect();
+        if used != bound {
+            let unbound: Vec<_> = used.difference(&bound).collect();
+            let unused: Vec<_> = bound.difference(&used).collect();
+            return Err(AppError::Internal(anyhow!(
+                "AQL parameters unbound: {:?}, bound but unused: {:?}, in: {}",
+                unbound,
+                unused,
+                self.text
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// Names of the parameters in a query's text, outside of string literals.
+fn parameters(text: &str) -> BTreeSet<&str> {
+    let mut names = BTreeSet::new();
+    let mut quote = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), '\\') if q != '`' => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '@') => {
+                let start = i + 1;
+                let mut end = start;
+                while let Some(&(j, next)) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_' || (next == '@' && j == start)) {
+                        break;
+                    }
+                    end = j + next.len_utf8();
+                    chars.next();
+                }
+                if end > start {
+                    names.insert(&text[start..end]);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+    names
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// `FOR doc IN <collection>`, filtered, sorted and limited, returning documents,
+/// some of their fields, a count or whether there is any.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: &'static str,
+    clauses: Vec<String>,
+    sort: Vec<String>,
+    limit: Option<usize>,
+    aql: Aql,
+    filters: usize,
+}
+
+impl Query {
+    /// Every document of a collection.
+    pub fn new(collection: &'static str) -> Self {
+        Self::from_source("@@collection", collection)
+    }
+
+    /// The documents of a collection with these keys, in their order. Missing ones are
+    /// `null` and fail every filter.
+    pub fn by_keys(collection: &'static str, keys: &[String]) -> Self {
+        let mut query = Self::from_source("DOCUMENT(@collection, @keys)", collection);
+        query.aql.insert("keys", keys.into());
+        query
+    }
+
+    fn from_source(source: &'static str, collection: &'static str) -> Self {
+        let name = if source.contains("@@collection") { "@collection" } else { "collection" };
+        Self {
+            source,
+            clauses: Vec::new(),
+            sort: Vec::new(),
+            limit: None,
+            aql: Aql::new("").bind(name, collection),
+            filters: 0,
+        }
+    }
+
+    fn next_parameter(&mut self, value: impl Into<Value>) -> String {
+        let name = format!("filter{}", self.filters);
+        self.filters += 1;
+        self.aql.insert(&name, value.into());
+        name
+    }
+
+    /// `FILTER doc.<field> <op> <value>`
+    pub fn filter(mut self, field: &'static str, op: Op, value: impl Into<Value>) -> Self {
+        let name = self.next_parameter(value);
+        self.clauses.push(format!("FILTER doc.{} {} @{}", field, op.as_str(), name));
+        self
+    }
+
+    /// `FILTER <value> IN doc.<field>`, for fields holding arrays, e.g. `tags` or `tokens[*].hash`.
+    pub fn filter_contains(mut self, field: &'static str, value: impl Into<Value>) -> Self {
+        let name = self.next_parameter(value);
+        self.clauses.push(format!("FILTER @{} IN doc.{}", name, field));
+        self
+    }
+
+    /// `FILTER doc.<field> IN <values>`
+    pub fn filter_in(mut self, field: &'static str, values: Vec<Value>) -> Self {
+        let name = self.next_parameter(values);
+        self.clauses.push(format!("FILTER doc.{} IN @{}", field, name));
+        self
+    }
+
+    /// A filter the typed ones can't express, e.g. on a date. Its values are bound by name.
+    pub fn filter_expr(mut self, expression: &'static str, bind_vars: impl IntoIterator<Item = (&'static str, Value)>) -> Self {
+        self.clauses.push(format!("FILTER {}", expression));
+        for (name, value) in bind_vars {
+            self.aql.insert(name, value);
+        }
+        self
+    }
+
+    /// `FILTER doc.deleted_at == null`, leaving out soft-deleted documents.
+    pub fn live(mut self) -> Self {
+        self.clauses.push("FILTER doc.deleted_at == null".to_string());
+        self
+    }
+
+    /// Sorts by one more field, after the ones before it.
+    pub fn sort(mut self, field: &'static str, direction: Direction) -> Self {
+        self.sort.push(match direction {
+            Direction::Asc => format!("doc.{}", field),
+            Direction::Desc => format!("doc.{} DESC", field),
+        });
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn body(&self) -> String {
+        let mut body = format!("FOR doc IN {}", self.source);
+        for clause in &self.clauses {
+            body.push(' ');
+            body.push_str(clause);
+        }
+        if !self.sort.is_empty() {
+            body.push_str(" SORT ");
+            body.push_str(&self.sort.join(", "));
+        }
+        if self.limit.is_some() {
+            body.push_str(" LIMIT @limit");
+        }
+        body
+    }
+
+    fn finish(self, text: String) -> Aql {
+        let mut aql = self.aql;
+        aql.text = text;
+        match self.limit {
+            Some(limit) => aql.bind("limit", limit),
+            None => aql,
+        }
+    }
+
+    /// Returns the documents.
+    pub fn build(self) -> Aql {
+        let text = format!("{} RETURN doc", self.body());
+        self.finish(text)
+    }
+
+    /// Returns only these fields of each document.
+    pub fn keep(self, fields: &[String]) -> Aql {
+        let text = format!("{} RETURN KEEP(doc, @fields)", self.body());
+        self.finish(text).bind("fields", fields.to_vec())
+    }
+
+    /// Returns the number of documents.
+    pub fn count(self) -> Aql {
+        let text = format!("RETURN COUNT({} RETURN 1)", self.body());
+        self.finish(text)
+    }
+
+    /// Returns whether there is a document at all.
+    pub fn exists(self) -> Aql {
+        let query = self.limit(1);
+        let text = format!("RETURN LENGTH({} RETURN 1) > 0", query.body());
+        query.finish(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn builds_queries_with_bound_values() {
+        let query = Query::new("principals")
+            .filter("doc_type", Op::Eq, "user")
+            .filter_contains("external_ids[*]", "github|1' OR true")
+            .sort("_key", Direction::Desc)
+            .limit(10)
+            .build()
+            .check()
+            .unwrap();
+        assert_eq!(
+            query.text(),
+            "FOR doc IN @@collection FILTER doc.doc_type == @filter0 FILTER @filter1 IN doc.external_ids[*] \
+             SORT doc._key DESC LIMIT @limit RETURN doc"
+        );
+        assert_eq!(query.bind_vars()["@collection"], "principals");
+        assert_eq!(query.bind_vars()["filter1"], "github|1' OR true");
+        assert_eq!(query.bind_vars()["limit"], 10);
+
+        let count = Query::by_keys("tickets", &["1".to_string()]).live().filter("project", Op::Eq, "p").count();
+        assert_eq!(
+            count.text(),
+            "RETURN COUNT(FOR doc IN DOCUMENT(@collection, @keys) FILTER doc.deleted_at == null \
+             FILTER doc.project == @filter0 RETURN 1)"
+        );
+        assert!(count.check().is_ok());
+
+        let exists = Query::new("tickets").filter("_key", Op::Eq, "1").exists().check().unwrap();
+        assert_eq!(exists.text(), "RETURN LENGTH(FOR doc IN @@collection FILTER doc._key == @filter0 LIMIT @limit RETURN 1) > 0");
+
+        let dated = Query::new("outbox")
+            .filter_expr("DATE_TIMESTAMP(doc.created_at) >= DATE_TIMESTAMP(@since)", [("since", json!("2024-01-01"))])
+            .keep(&["id".to_string()])
+            .check()
+            .unwrap();
+        assert!(dated.text().ends_with("RETURN KEEP(doc, @fields)"));
+    }
+
+    #[test]
+    fn parameters_must_match_bound_values() {
+        assert!(Aql::new("RETURN DOCUMENT(projects, @id) != null").check().is_err());
+        assert!(Aql::new("RETURN 1").bind("id", "x").check().is_err());
+        assert!(Aql::new("REMOVE { _key: @key } IN @@edges").bind("key", "1").bind("@edges", "owns").check().is_ok());
+        // Not parameters inside strings
+        assert!(Aql::new(r#"RETURN CONCAT("@x", '@y', @z)"#).bind("z", 1).check().is_ok());
+        assert!(Aql::new(r#"RETURN "a \" @x""#).check().is_ok());
+    }
+}
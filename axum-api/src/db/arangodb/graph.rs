@@ -0,0 +1,94 @@
+// Traversals of the `membership`, `owns` and `parentOf` edges, and upkeep of the
+// membership edges, which mirror the principals listed in each group
+use std::sync::Arc;
+
+use arangors::{Database, client::ClientExt};
+use serde_json::Value;
+
+use super::{ArangoProject, run};
+use crate::db::{BoxFuture, GraphRepo, aql::Aql};
+use crate::error::AppError;
+use crate::models::Project;
+
+/// Points one membership edge from each member to the group, replacing those it had.
+/// Edges go from `principals/<member>` to `principals/<gid>`.
+pub(super) async fn set_members<C: ClientExt + Send + Sync>(
+    db: &Database<C>,
+    gid: &str,
+    members: &[String],
+) -> Result<(), AppError> {
+    let group = format!("principals/{}", gid);
+    // A query can't read a collection after writing it, so removing and inserting are two
+    let removed = Aql::new("FOR edge IN membership FILTER edge._to == @group REMOVE edge IN membership")
+        .bind("group", group.as_str());
+    let _: Vec<Value> = run(db, removed).await?;
+
+    let inserted = Aql::new(
+        "FOR member IN @members \
+         INSERT { _from: CONCAT('principals/', member), _to: @group } INTO membership",
+    )
+    .bind("members", members)
+    .bind("group", group);
+    let _: Vec<Value> = run(db, inserted).await?;
+    Ok(())
+}
+
+/// Adds the membership edges of groups stored before the edges were kept.
+pub(super) async fn sync_membership<C: ClientExt + Send + Sync>(db: &Database<C>) -> Result<(), AppError> {
+    let query = Aql::new(
+        "FOR doc IN principals FILTER doc.doc_type == 'group' AND doc.deleted_at == null \
+         FOR member IN NOT_NULL(doc.principals, []) \
+         UPSERT { _from: CONCAT('principals/', member), _to: doc._id } \
+         INSERT { _from: CONCAT('principals/', member), _to: doc._id } \
+         UPDATE {} IN membership",
+    );
+    let _: Vec<Value> = run(db, query).await?;
+    Ok(())
+}
+
+pub struct ArangoGraphRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoGraphRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+}
+
+impl<C: ClientExt + Send + Sync> GraphRepo for ArangoGraphRepo<C> {
+    fn groups_of<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<String>, AppError>> {
+        Box::pin(async move {
+            let query = Aql::new(
+                "FOR v IN 1..100 OUTBOUND @start membership \
+                 OPTIONS { order: 'bfs', uniqueVertices: 'global' } \
+                 SORT v._key RETURN v._key",
+            )
+            .bind("start", format!("principals/{}", principal));
+
+            run(&self.db, query).await
+        })
+    }
+
+    fn owned_projects<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        Box::pin(async move {
+            // From the principal and its groups, over an owns edge and then parentOf edges
+            let query = Aql::new(
+                "LET owners = UNION_DISTINCT([@start], ( \
+                     FOR v IN 1..100 OUTBOUND @start membership \
+                     OPTIONS { order: 'bfs', uniqueVertices: 'global' } \
+                     RETURN v._id)) \
+                 LET projects = ( \
+                     FOR owner IN owners \
+                     FOR project IN 1..100 OUTBOUND owner owns, parentOf \
+                     OPTIONS { order: 'bfs', uniqueVertices: 'global' } \
+                     RETURN project) \
+                 FOR project IN UNIQUE(projects) SORT project._key RETURN project",
+            )
+            .bind("start", format!("principals/{}", principal));
+
+            let docs: Vec<ArangoProject> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.project).collect())
+        })
+    }
+}
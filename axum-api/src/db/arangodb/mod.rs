@@ -1,6 +1,11 @@
-use std::sync::Arc;
+pub mod graph;
+pub mod search;
+mod trash;
+
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, Utc};
 
 use arangors::{
     AqlQuery, Connection, Database,
@@ -10,16 +15,25 @@ use arangors::{
         Document,
         options::{InsertOptions, RemoveOptions, ReplaceOptions},
     },
+    index::{Index, IndexSettings},
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::db::aql::{Aql, Direction, Op, Query};
+use crate::db::versioned;
 use crate::error::AppError;
-use crate::models::{Group, Project, Ticket};
+use crate::models::{Activity, ChatChannel, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, Notification, OutboxEntry, Preference, Project, ReadReceipt, SecurityEvent, Session, Ticket, TicketShare, TicketStatus};
 use crate::{
-    db::{BoxFuture, DatabaseInterface, GroupsRepo, ProjectsRepo, TicketsRepo, UsersRepo},
+    db::{
+        ActivityFilter, ActivityRepo, AssigneeCount, BackendInfo, Batch, BoxFuture, ChatChannelsRepo, CommentsRepo, DatabaseInterface, Deleted, DraftsRepo, GraphRepo, GroupsRepo, IdempotencyRepo, InvitesRepo, MilestonesRepo, NotificationFilter, NotificationsRepo, OutboxFilter, OutboxRepo, PreferencesRepo, ProjectsRepo, ReadReceiptsRepo, SearchService, SecurityEventFilter,
+        SecurityEventsRepo, SessionsRepo, SharesRepo, TicketCount, TicketDayCount, TicketsRepo, UsersRepo,
+    },
     models::User,
 }; // Assuming User is in models, not schema
+use graph::ArangoGraphRepo;
+use search::ArangoSearchService;
 
 pub async fn connect_or_create_db_no_auth(
     conn: &Connection,
@@ -101,8 +115,10 @@ impl<T> MapArangoError<T> for Result<T, arangors::ClientError> {
 struct ArangoUser {
     #[serde(rename = "_key")]
     key: String,
-    #[serde(flatten)]
+    #[serde(flatten, with = "versioned")]
     user: User,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTime<Utc>>, // set while in the trash
     doc_type: String, // Always "user"
 }
 
@@ -112,8 +128,10 @@ struct ArangoUser {
 struct ArangoGroup {
     #[serde(rename = "_key")]
     key: String,
-    #[serde(flatten)]
+    #[serde(flatten, with = "versioned")]
     group: Group,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTime<Utc>>, // set while in the trash
     doc_type: String, // Always "group"
 }
 
@@ -123,18 +141,173 @@ struct ArangoGroup {
 struct ArangoProject {
     #[serde(rename = "_key")]
     key: String,
-    #[serde(flatten)]
+    #[serde(flatten, with = "versioned")]
     project: Project,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTime<Utc>>, // set while in the trash
 }
 
+/// Key in the 'counters' collection of the highest ticket id reserved or created.
+const TICKET_COUNTER: &str = "tickets";
+
 /// Represents a Ticket document as stored in the 'tickets' collection.
 /// `_key` is set to the `ticket.id`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ArangoTicket {
     #[serde(rename = "_key")]
     key: String,
-    #[serde(flatten)]
+    #[serde(flatten, with = "versioned")]
     ticket: Ticket,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTime<Utc>>, // set while in the trash
+}
+
+/// Represents a Session document as stored in the 'sessions' collection.
+/// `_key` is set to the `session.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoSession {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(default)]
+    purge_at: i64, // `expires_at` in seconds since the epoch, for the TTL index
+    #[serde(flatten)]
+    session: Session,
+}
+
+/// Represents an Invite document as stored in the 'invites' collection.
+/// `_key` is set to the `invite.id` (token hash).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoInvite {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    invite: Invite,
+}
+
+/// Represents a SecurityEvent document as stored in the 'security_events' collection.
+/// `_key` is set to the `event.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoSecurityEvent {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    event: SecurityEvent,
+}
+
+/// Represents an IdempotencyRecord document as stored in the 'idempotency' collection.
+/// `_key` is set to the `record.id` (hash of principal and key).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoIdempotencyRecord {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(default)]
+    purge_at: i64, // `expires_at` in seconds since the epoch, for the TTL index
+    #[serde(flatten)]
+    record: IdempotencyRecord,
+}
+
+/// Represents a Notification document as stored in the 'notifications' collection.
+/// `_key` is set to the `notification.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoNotification {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    notification: Notification,
+}
+
+/// Represents an Activity document as stored in the 'activity' collection.
+/// `_key` is set to the `activity.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoActivity {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    activity: Activity,
+}
+
+/// Represents a Draft document as stored in the 'drafts' collection.
+/// `_key` is set to the `draft.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoDraft {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(default)]
+    purge_at: i64, // `expires_at` in seconds since the epoch, for the TTL index
+    #[serde(flatten)]
+    draft: Draft,
+}
+
+/// Represents a TicketShare document as stored in the 'shares' collection.
+/// `_key` is set to the `share.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoShare {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(default)]
+    purge_at: i64, // `expires_at` in seconds since the epoch, for the TTL index
+    #[serde(flatten)]
+    share: TicketShare,
+}
+
+/// Represents a Preference document as stored in the 'preferences' collection.
+/// `_key` is set to the `preference.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoPreference {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    preference: Preference,
+}
+
+/// Represents a ReadReceipt document as stored in the 'receipts' collection.
+/// `_key` is the ticket id and the username, joined by `:`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoReadReceipt {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    receipt: ReadReceipt,
+}
+
+/// Represents a Milestone document as stored in the 'milestones' collection.
+/// `_key` is set to the `milestone.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoMilestone {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    milestone: Milestone,
+}
+
+/// Represents a Comment document as stored in the 'comments' collection.
+/// `_key` is set to the `comment.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoComment {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    comment: Comment,
+}
+
+/// Represents a ChatChannel document as stored in the 'chat_channels' collection.
+/// `_key` is set to the `channel.project`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoChatChannel {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    channel: ChatChannel,
+}
+
+/// Represents an OutboxEntry document as stored in the 'outbox' collection.
+/// `_key` is set to the `entry.id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArangoOutboxEntry {
+    #[serde(rename = "_key")]
+    key: String,
+    #[serde(flatten)]
+    entry: OutboxEntry,
 }
 
 // ===================================================================
@@ -148,6 +321,22 @@ pub struct ArangoDatabase<C: ClientExt + Send + Sync> {
     projects_repo: ArangoProjectsRepo<C>,
     groups_repo: ArangoGroupsRepo<C>,
     tickets_repo: ArangoTicketsRepo<C>,
+    sessions_repo: ArangoSessionsRepo<C>,
+    invites_repo: ArangoInvitesRepo<C>,
+    security_events_repo: ArangoSecurityEventsRepo<C>,
+    idempotency_repo: ArangoIdempotencyRepo<C>,
+    notifications_repo: ArangoNotificationsRepo<C>,
+    activity_repo: ArangoActivityRepo<C>,
+    receipts_repo: ArangoReadReceiptsRepo<C>,
+    drafts_repo: ArangoDraftsRepo<C>,
+    shares_repo: ArangoSharesRepo<C>,
+    preferences_repo: ArangoPreferencesRepo<C>,
+    milestones_repo: ArangoMilestonesRepo<C>,
+    comments_repo: ArangoCommentsRepo<C>,
+    chat_channels_repo: ArangoChatChannelsRepo<C>,
+    outbox_repo: ArangoOutboxRepo<C>,
+    graph_repo: ArangoGraphRepo<C>,
+    search_service: ArangoSearchService<C>,
 }
 
 // CORRECTED: Impl block is generic
@@ -163,6 +352,22 @@ impl<C: ClientExt + Send + Sync> ArangoDatabase<C> {
             projects_repo: ArangoProjectsRepo::new(db_arc.clone()),
             groups_repo: ArangoGroupsRepo::new(db_arc.clone()),
             tickets_repo: ArangoTicketsRepo::new(db_arc.clone()),
+            sessions_repo: ArangoSessionsRepo::new(db_arc.clone()),
+            invites_repo: ArangoInvitesRepo::new(db_arc.clone()),
+            security_events_repo: ArangoSecurityEventsRepo::new(db_arc.clone()),
+            idempotency_repo: ArangoIdempotencyRepo::new(db_arc.clone()),
+            notifications_repo: ArangoNotificationsRepo::new(db_arc.clone()),
+            activity_repo: ArangoActivityRepo::new(db_arc.clone()),
+            receipts_repo: ArangoReadReceiptsRepo::new(db_arc.clone()),
+            drafts_repo: ArangoDraftsRepo::new(db_arc.clone()),
+            shares_repo: ArangoSharesRepo::new(db_arc.clone()),
+            preferences_repo: ArangoPreferencesRepo::new(db_arc.clone()),
+            milestones_repo: ArangoMilestonesRepo::new(db_arc.clone()),
+            comments_repo: ArangoCommentsRepo::new(db_arc.clone()),
+            chat_channels_repo: ArangoChatChannelsRepo::new(db_arc.clone()),
+            outbox_repo: ArangoOutboxRepo::new(db_arc.clone()),
+            graph_repo: ArangoGraphRepo::new(db_arc.clone()),
+            search_service: ArangoSearchService::new(db_arc.clone()),
         }
     }
 
@@ -174,12 +379,93 @@ impl<C: ClientExt + Send + Sync> ArangoDatabase<C> {
         Self::create_collection(db, "principals", CollectionType::Document).await?;
         Self::create_collection(db, "projects", CollectionType::Document).await?;
         Self::create_collection(db, "tickets", CollectionType::Document).await?;
+        Self::create_collection(db, "sessions", CollectionType::Document).await?;
+        Self::create_collection(db, "invites", CollectionType::Document).await?;
+        Self::create_collection(db, "security_events", CollectionType::Document).await?;
+        Self::create_collection(db, "idempotency", CollectionType::Document).await?;
+        Self::create_collection(db, "notifications", CollectionType::Document).await?;
+        Self::create_collection(db, "activity", CollectionType::Document).await?;
+        Self::create_collection(db, "receipts", CollectionType::Document).await?;
+        Self::create_collection(db, "drafts", CollectionType::Document).await?;
+        Self::create_collection(db, "shares", CollectionType::Document).await?;
+        Self::create_collection(db, "preferences", CollectionType::Document).await?;
+        Self::create_collection(db, "milestones", CollectionType::Document).await?;
+        Self::create_collection(db, "comments", CollectionType::Document).await?;
+        Self::create_collection(db, "chat_channels", CollectionType::Document).await?;
+        Self::create_collection(db, "outbox", CollectionType::Document).await?;
+        Self::create_collection(db, "counters", CollectionType::Document).await?;
 
         // Edge Collections
         Self::create_collection(db, "membership", CollectionType::Edge).await?;
         Self::create_collection(db, "parentOf", CollectionType::Edge).await?;
         Self::create_collection(db, "owns", CollectionType::Edge).await?;
 
+        // Lookups of users by other identifiers than the key. Sparse, groups and users
+        // without an email or external ids aren't indexed.
+        Self::create_unique_index(db, "principals", "email").await?;
+        Self::create_unique_index(db, "principals", "external_ids[*]").await?;
+
+        // Expired sessions, and with them their refresh tokens, idempotency records,
+        // drafts and ticket shares are purged by the server
+        for collection in ["sessions", "idempotency", "drafts", "shares"] {
+            Self::backfill_purge_at(db, collection).await?;
+            Self::create_ttl_index(db, collection, "purge_at").await?;
+        }
+
+        search::create_view(db).await?;
+
+        // Ticket ids are reserved from a counter, which databases older than it start
+        // at their highest id
+        let query = Aql::new(
+            "LET highest = NOT_NULL(MAX(FOR t IN tickets RETURN t.id), 0) \
+             UPSERT { _key: @key } INSERT { _key: @key, value: highest } \
+             UPDATE { value: MAX([OLD.value, highest]) } IN counters OPTIONS { exclusive: true }",
+        )
+        .bind("key", TICKET_COUNTER);
+        let _: Vec<Value> = run(db, query).await?;
+
+        Ok(())
+    }
+
+    /// Private helper to create a TTL index, removing documents once the time in
+    /// seconds since the epoch in `field` has passed.
+    async fn create_ttl_index(db: &Database<C>, collection: &str, field: &str) -> Result<(), AppError> {
+        let index = Index::builder()
+            .fields(vec![field.to_string()])
+            .settings(IndexSettings::Ttl { expire_after: 0 })
+            .build();
+
+        db.create_index(collection, &index).await.map_err_app_error()?;
+
+        Ok(())
+    }
+
+    /// Sets `purge_at` on documents written before it was, the TTL index skips them
+    /// otherwise. AQL doesn't read chrono's nanoseconds, so only whole seconds are kept.
+    async fn backfill_purge_at(db: &Database<C>, collection: &'static str) -> Result<(), AppError> {
+        let query = Aql::new(
+            "FOR doc IN @@collection FILTER doc.purge_at == null \
+             UPDATE doc WITH { purge_at: FLOOR(DATE_TIMESTAMP(LEFT(doc.expires_at, 19)) / 1000) } IN @@collection",
+        )
+        .bind("@collection", collection);
+        let _: Vec<Value> = run(db, query).await?;
+        Ok(())
+    }
+
+    /// Private helper to create a sparse unique persistent index. Creating an index
+    /// that already exists does nothing.
+    async fn create_unique_index(db: &Database<C>, collection: &str, field: &str) -> Result<(), AppError> {
+        let index = Index::builder()
+            .fields(vec![field.to_string()])
+            .settings(IndexSettings::Persistent {
+                unique: true,
+                sparse: true,
+                deduplicate: true,
+            })
+            .build();
+
+        db.create_index(collection, &index).await.map_err_app_error()?;
+
         Ok(())
     }
 
@@ -224,11 +510,87 @@ impl<C: ClientExt + Send + Sync> DatabaseInterface for ArangoDatabase<C> {
         &self.tickets_repo
     }
 
+    fn sessions(&self) -> &dyn SessionsRepo {
+        &self.sessions_repo
+    }
+
+    fn invites(&self) -> &dyn InvitesRepo {
+        &self.invites_repo
+    }
+
+    fn security_events(&self) -> &dyn SecurityEventsRepo {
+        &self.security_events_repo
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepo {
+        &self.idempotency_repo
+    }
+
+    fn notifications(&self) -> &dyn NotificationsRepo {
+        &self.notifications_repo
+    }
+
+    fn activity(&self) -> &dyn ActivityRepo {
+        &self.activity_repo
+    }
+
+    fn receipts(&self) -> &dyn ReadReceiptsRepo {
+        &self.receipts_repo
+    }
+
+    fn drafts(&self) -> &dyn DraftsRepo {
+        &self.drafts_repo
+    }
+
+    fn shares(&self) -> &dyn SharesRepo {
+        &self.shares_repo
+    }
+
+    fn preferences(&self) -> &dyn PreferencesRepo {
+        &self.preferences_repo
+    }
+
+    fn milestones(&self) -> &dyn MilestonesRepo {
+        &self.milestones_repo
+    }
+
+    fn comments(&self) -> &dyn CommentsRepo {
+        &self.comments_repo
+    }
+
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo {
+        &self.chat_channels_repo
+    }
+
+    fn outbox(&self) -> &dyn OutboxRepo {
+        &self.outbox_repo
+    }
+
+    fn graph(&self) -> &dyn GraphRepo {
+        &self.graph_repo
+    }
+
+    fn search(&self) -> &dyn SearchService {
+        &self.search_service
+    }
+
     // ADDED: initialize method
     fn initialize<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             // Call the static setup_schema helper, passing the db instance
-            ArangoDatabase::setup_schema(&self.db).await
+            ArangoDatabase::setup_schema(&self.db).await?;
+            graph::sync_membership(&self.db).await
+        })
+    }
+
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>> {
+        Box::pin(async move {
+            let version = self.db.arango_version().await.map_err_app_error()?;
+            Ok(BackendInfo {
+                backend: "arangodb".to_string(),
+                database: Some(self.db.name().to_string()),
+                version: Some(version.version),
+            })
         })
     }
 
@@ -248,6 +610,71 @@ impl<C: ClientExt + Send + Sync> DatabaseInterface for ArangoDatabase<C> {
     }
 }
 
+/// What an upsert in the 'principals' collection stored, or found.
+#[derive(Deserialize)]
+struct Upserted<T> {
+    doc: T,
+    created: bool,
+}
+
+/// Writes a user or group document under `key` in one AQL statement: inserts it, or
+/// replaces the stored one when `replace` is set and keeps it otherwise. Fails with
+/// `Conflict` if the key is taken by a principal of another type, or by a deleted one
+/// that would be kept.
+async fn upsert_principal<C, T>(
+    db: &Database<C>,
+    key: &str,
+    doc: &T,
+    doc_type: &str,
+    replace: bool,
+) -> Result<Upserted<T>, AppError>
+where
+    C: ClientExt + Send + Sync,
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let query = if replace {
+        Aql::new(
+            "LET old = DOCUMENT('principals', @key) \
+             FILTER old == null OR old.doc_type == @doc_type \
+             UPSERT { _key: @key } INSERT @doc REPLACE @doc IN principals \
+             RETURN { doc: NEW, created: OLD == null }",
+        )
+    } else {
+        Aql::new(
+            "LET old = DOCUMENT('principals', @key) \
+             FILTER old == null OR (old.doc_type == @doc_type AND old.deleted_at == null) \
+             UPSERT { _key: @key } INSERT @doc UPDATE {} IN principals \
+             RETURN { doc: NEW, created: OLD == null }",
+        )
+    };
+    let query = query
+        .bind("key", key)
+        .bind("doc", serde_json::to_value(doc)?)
+        .bind("doc_type", doc_type);
+
+    let upserted: Vec<Upserted<T>> = run(db, query).await?;
+    upserted
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::Conflict(format!("Principal {} is not a live {}", key, doc_type)))
+}
+
+/// Runs a query built with `db::aql`, once its parameters and bound values match.
+async fn run<C, T>(db: &Database<C>, query: Aql) -> Result<Vec<T>, AppError>
+where
+    C: ClientExt + Send + Sync,
+    T: serde::de::DeserializeOwned,
+{
+    let query = query.check()?;
+    let bind_vars: HashMap<&str, Value> = query
+        .bind_vars()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    let aql = AqlQuery::builder().query(query.text()).bind_vars(bind_vars).build();
+    db.aql_query(aql).await.map_err_app_error()
+}
+
 // ===================================================================
 // Users Repository Implementation
 // ===================================================================
@@ -274,7 +701,7 @@ impl<C: ClientExt + Send + Sync> UsersRepo for ArangoUsersRepo<C> {
             let collection = self.collection().await?;
             let doc: Document<ArangoUser> = collection.document(id).await.map_err_app_error()?;
 
-            if doc.document.doc_type != "user" {
+            if doc.document.doc_type != "user" || doc.document.deleted_at.is_some() {
                 return Err(AppError::NotFound(format!("User {} not found", id)));
             }
 
@@ -282,6 +709,16 @@ impl<C: ClientExt + Send + Sync> UsersRepo for ArangoUsersRepo<C> {
         })
     }
 
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>> {
+        Box::pin(async move {
+            let query = Query::by_keys("principals", ids).live().filter("doc_type", Op::Eq, "user").build();
+
+            let docs: Vec<ArangoUser> = run(&self.db, query).await?;
+            let mut by_key: HashMap<String, User> = docs.into_iter().map(|d| (d.key, d.user)).collect();
+            Ok(Batch::lookup(ids, |id| by_key.remove(id)))
+        })
+    }
+
     fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
@@ -289,6 +726,7 @@ impl<C: ClientExt + Send + Sync> UsersRepo for ArangoUsersRepo<C> {
                 key: user.username.clone(),
                 user,
                 doc_type: "user".to_string(),
+                deleted_at: None,
             };
 
             let options = InsertOptions::builder().overwrite(false).build();
@@ -309,6 +747,7 @@ impl<C: ClientExt + Send + Sync> UsersRepo for ArangoUsersRepo<C> {
                 key: id.to_string(),
                 user,
                 doc_type: "user".to_string(),
+                deleted_at: None,
             };
 
             let options = ReplaceOptions::builder().ignore_revs(true).build();
@@ -320,32 +759,169 @@ impl<C: ClientExt + Send + Sync> UsersRepo for ArangoUsersRepo<C> {
         })
     }
 
-    fn delete_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>> {
         Box::pin(async move {
-            let collection = self.collection().await?;
-            self.get_user(id).await?; // Check type and existence
+            let doc = ArangoUser {
+                key: user.username.clone(),
+                user,
+                doc_type: "user".to_string(),
+                deleted_at: None,
+            };
+            let upserted = upsert_principal(&self.db, &doc.key, &doc, "user", true).await?;
+            Ok(upserted.created)
+        })
+    }
 
-            let options = RemoveOptions::builder().silent(true).build();
-            collection
-                .remove_document::<ArangoUser>(id, options, None)
-                .await
-                .map_err_app_error()?;
-            Ok(())
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>> {
+        Box::pin(async move {
+            let doc = ArangoUser {
+                key: user.username.clone(),
+                user,
+                doc_type: "user".to_string(),
+                deleted_at: None,
+            };
+            let upserted = upsert_principal(&self.db, &doc.key, &doc, "user", false).await?;
+            Ok((upserted.doc.user, upserted.created))
+        })
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            if hard {
+                trash::USERS.remove(&self.db, id).await
+            } else {
+                trash::USERS.soft_delete(&self.db, id).await
+            }
+        })
+    }
+
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>> {
+        Box::pin(async move {
+            let docs: Vec<ArangoUser> = trash::USERS.list(&self.db).await?;
+            Ok(trash::deleted(docs.into_iter().map(|d| (d.user, d.deleted_at))))
         })
     }
 
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { trash::USERS.restore(&self.db, id).await })
+    }
+
     fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
         Box::pin(async move {
-            let query = "FOR doc IN principals FILTER doc.doc_type == 'user' RETURN doc";
-            // CORRECTED: Use AqlQuery::builder()
-            let aql = AqlQuery::builder().query(query).build();
+            let query = Query::new("principals").live().filter("doc_type", Op::Eq, "user").build();
 
-            let arango_users: Vec<ArangoUser> = self.db.aql_query(aql).await.map_err_app_error()?;
+            let arango_users: Vec<ArangoUser> = run(&self.db, query).await?;
 
             let users = arango_users.into_iter().map(|au| au.user).collect();
             Ok(users)
         })
     }
+
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals").live().filter("doc_type", Op::Eq, "user").count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("doc_type", Op::Eq, "user")
+                .filter("deactivated", Op::Eq, true)
+                .count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("_key", Op::Eq, id)
+                .filter("doc_type", Op::Eq, "user")
+                .exists();
+
+            let found: Vec<bool> = run(&self.db, query).await?;
+            Ok(found.first().copied().unwrap_or(false))
+        })
+    }
+
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        Box::pin(async move {
+            let mut query = Query::new("principals")
+                .live()
+                .filter("doc_type", Op::Eq, "user")
+                .filter_expr("HAS(doc.metadata, @key)", [("key", Value::from(key))]);
+            if let Some(value) = value {
+                // @key is bound by the filter above
+                query = query.filter_expr("doc.metadata[@key] == @value", [("value", Value::from(value))]);
+            }
+            let query = query.sort("_key", Direction::Asc).build();
+
+            let arango_users: Vec<ArangoUser> = run(&self.db, query).await?;
+            Ok(arango_users.into_iter().map(|au| au.user).collect())
+        })
+    }
+
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("doc_type", Op::Eq, "user")
+                .filter_contains("api_tokens[*].hash", hash)
+                .limit(1)
+                .build();
+
+            let arango_users: Vec<ArangoUser> = run(&self.db, query).await?;
+            arango_users
+                .into_iter()
+                .next()
+                .map(|au| au.user)
+                .ok_or_else(|| AppError::NotFound("API token not found".to_string()))
+        })
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("doc_type", Op::Eq, "user")
+                .filter("email", Op::Eq, email)
+                .limit(1)
+                .build();
+
+            let arango_users: Vec<ArangoUser> = run(&self.db, query).await?;
+            arango_users
+                .into_iter()
+                .next()
+                .map(|au| au.user)
+                .ok_or_else(|| AppError::NotFound(format!("No user with email {}", email)))
+        })
+    }
+
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("doc_type", Op::Eq, "user")
+                .filter_contains("external_ids[*]", id)
+                .limit(1)
+                .build();
+
+            let arango_users: Vec<ArangoUser> = run(&self.db, query).await?;
+            arango_users
+                .into_iter()
+                .next()
+                .map(|au| au.user)
+                .ok_or_else(|| AppError::NotFound(format!("No user with external id {}", id)))
+        })
+    }
 }
 
 // ===================================================================
@@ -374,7 +950,7 @@ impl<C: ClientExt + Send + Sync> GroupsRepo for ArangoGroupsRepo<C> {
             let collection = self.collection().await?;
             let doc: Document<ArangoGroup> = collection.document(id).await.map_err_app_error()?;
 
-            if doc.document.doc_type != "group" {
+            if doc.document.doc_type != "group" || doc.document.deleted_at.is_some() {
                 return Err(AppError::NotFound(format!("Group {} not found", id)));
             }
 
@@ -382,21 +958,34 @@ impl<C: ClientExt + Send + Sync> GroupsRepo for ArangoGroupsRepo<C> {
         })
     }
 
+    fn get_groups<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Group>, AppError>> {
+        Box::pin(async move {
+            let query = Query::by_keys("principals", ids).live().filter("doc_type", Op::Eq, "group").build();
+
+            let docs: Vec<ArangoGroup> = run(&self.db, query).await?;
+            let mut by_key: HashMap<String, Group> = docs.into_iter().map(|d| (d.key, d.group)).collect();
+            Ok(Batch::lookup(ids, |id| by_key.remove(id)))
+        })
+    }
+
     fn create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
+            let members = group.principals.clone();
             let doc = ArangoGroup {
                 key: group.gid.to_string(), // Assuming Group has an `id` field
                 group,
                 doc_type: "group".to_string(),
+                deleted_at: None,
             };
+            let key = doc.key.clone();
 
             let options = InsertOptions::builder().overwrite(false).build();
             collection
                 .create_document(doc, options)
                 .await
                 .map_err_app_error()?;
-            Ok(())
+            graph::set_members(&self.db, &key, &members).await
         })
     }
 
@@ -409,48 +998,112 @@ impl<C: ClientExt + Send + Sync> GroupsRepo for ArangoGroupsRepo<C> {
             let collection = self.collection().await?;
             self.get_group(id).await?; // Check type and existence
 
+            let members = group.principals.clone();
             let doc = ArangoGroup {
                 key: id.to_string(),
                 group,
                 doc_type: "group".to_string(),
+                deleted_at: None,
             };
             let options = ReplaceOptions::builder().silent(true).build();
             collection
                 .replace_document(id, doc, options, None)
                 .await
                 .map_err_app_error()?;
-            Ok(())
+            graph::set_members(&self.db, id, &members).await
         })
     }
 
-    fn delete_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn upsert_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<bool, AppError>> {
         Box::pin(async move {
-            let collection = self.collection().await?;
-            self.get_group(id).await?; // Check type and existence
+            let doc = ArangoGroup {
+                key: group.gid.to_string(),
+                group,
+                doc_type: "group".to_string(),
+                deleted_at: None,
+            };
+            let upserted = upsert_principal(&self.db, &doc.key, &doc, "group", true).await?;
+            graph::set_members(&self.db, &doc.key, &doc.group.principals).await?;
+            Ok(upserted.created)
+        })
+    }
 
-            let options = RemoveOptions::builder().silent(true);
-            collection
-                .remove_document::<ArangoGroup>(id, options.build(), None)
-                .await
-                .map_err_app_error()?;
-            Ok(())
+    fn get_or_create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(Group, bool), AppError>> {
+        Box::pin(async move {
+            let doc = ArangoGroup {
+                key: group.gid.to_string(),
+                group,
+                doc_type: "group".to_string(),
+                deleted_at: None,
+            };
+            let upserted = upsert_principal(&self.db, &doc.key, &doc, "group", false).await?;
+            if upserted.created {
+                graph::set_members(&self.db, &doc.key, &doc.group.principals).await?;
+            }
+            Ok((upserted.doc.group, upserted.created))
         })
     }
 
-    fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>> {
+    fn delete_group<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let query = "FOR doc IN principals FILTER doc.doc_type == 'group' RETURN doc";
-            // CORRECTED: Use AqlQuery::builder()
-            let aql = AqlQuery::builder().query(query).build();
+            if hard {
+                trash::GROUPS.remove(&self.db, id).await?;
+            } else {
+                trash::GROUPS.soft_delete(&self.db, id).await?;
+            }
+            // A deleted group has no members until restored
+            graph::set_members(&self.db, id, &[]).await
+        })
+    }
 
-            let arango_groups: Vec<ArangoGroup> =
-                self.db.aql_query(aql).await.map_err_app_error()?;
+    fn list_deleted_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Group>>, AppError>> {
+        Box::pin(async move {
+            let docs: Vec<ArangoGroup> = trash::GROUPS.list(&self.db).await?;
+            Ok(trash::deleted(docs.into_iter().map(|d| (d.group, d.deleted_at))))
+        })
+    }
 
-            let groups = arango_groups.into_iter().map(|ag| ag.group).collect();
-            Ok(groups)
+    fn restore_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            trash::GROUPS.restore(&self.db, id).await?;
+            let group = self.get_group(id).await?;
+            graph::set_members(&self.db, id, &group.principals).await
         })
     }
-}
+
+    fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals").live().filter("doc_type", Op::Eq, "group").build();
+
+            let arango_groups: Vec<ArangoGroup> = run(&self.db, query).await?;
+
+            let groups = arango_groups.into_iter().map(|ag| ag.group).collect();
+            Ok(groups)
+        })
+    }
+
+    fn count_groups<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals").live().filter("doc_type", Op::Eq, "group").count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+
+    fn exists_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("principals")
+                .live()
+                .filter("_key", Op::Eq, id)
+                .filter("doc_type", Op::Eq, "group")
+                .exists();
+
+            let found: Vec<bool> = run(&self.db, query).await?;
+            Ok(found.first().copied().unwrap_or(false))
+        })
+    }
+}
 
 // ===================================================================
 // Projects Repository Implementation
@@ -469,6 +1122,32 @@ impl<C: ClientExt + Send + Sync> ArangoProjectsRepo<C> {
     async fn collection(&self) -> Result<Collection<C>, AppError> {
         self.db.collection("projects").await.map_err_app_error()
     }
+
+    /// Points the project's edge in `collection` from `from`, or removes it when there is
+    /// none. Edges are keyed by the project id: a project has one owner and one parent.
+    async fn set_edge(&self, collection: &str, id: &str, from: Option<String>) -> Result<(), AppError> {
+        let query = match from {
+            Some(from) => Aql::new(
+                "UPSERT { _key: @key } \
+                 INSERT { _key: @key, _from: @from, _to: @to } \
+                 REPLACE { _key: @key, _from: @from, _to: @to } IN @@edges",
+            )
+            .bind("from", from)
+            .bind("to", format!("projects/{}", id)),
+            None => Aql::new("REMOVE { _key: @key } IN @@edges OPTIONS { ignoreErrors: true }"),
+        };
+        let query = query.bind("@edges", collection).bind("key", id);
+        let _: Vec<serde_json::Value> = run(&self.db, query).await?;
+        Ok(())
+    }
+
+    /// Keeps the `owns` and `parentOf` edges in line with the project's fields.
+    async fn set_edges(&self, id: &str, project: Option<&Project>) -> Result<(), AppError> {
+        let owner = project.and_then(|p| p.owner.as_ref()).map(|o| format!("principals/{}", o));
+        let parent = project.and_then(|p| p.parent_id.as_ref()).map(|p| format!("projects/{}", p));
+        self.set_edge("owns", id, owner).await?;
+        self.set_edge("parentOf", id, parent).await
+    }
 }
 
 // CORRECTED: Impl block is generic
@@ -477,6 +1156,9 @@ impl<C: ClientExt + Send + Sync> ProjectsRepo for ArangoProjectsRepo<C> {
         Box::pin(async move {
             let collection = self.collection().await?;
             let doc: Document<ArangoProject> = collection.document(id).await.map_err_app_error()?;
+            if doc.document.deleted_at.is_some() {
+                return Err(AppError::NotFound(format!("Project {} not found", id)));
+            }
             Ok(doc.document.project)
         })
     }
@@ -484,17 +1166,19 @@ impl<C: ClientExt + Send + Sync> ProjectsRepo for ArangoProjectsRepo<C> {
     fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
+            let id = project.id.to_string();
             let doc = ArangoProject {
-                key: project.id.to_string(),
+                key: id.clone(),
                 project,
+                deleted_at: None,
             };
 
             let options = InsertOptions::builder().overwrite(false).build();
             collection
-                .create_document(doc, options)
+                .create_document(doc.clone(), options)
                 .await
                 .map_err_app_error()?;
-            Ok(())
+            self.set_edges(&id, Some(&doc.project)).await
         })
     }
 
@@ -505,46 +1189,89 @@ impl<C: ClientExt + Send + Sync> ProjectsRepo for ArangoProjectsRepo<C> {
     ) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
+            self.get_project(id).await?; // A deleted project isn't brought back by replacing it
             let doc = ArangoProject {
                 key: id.to_string(),
                 project,
+                deleted_at: None,
             };
 
             let options = ReplaceOptions::builder().silent(true).build();
             collection
-                .replace_document(id, doc, options, None)
+                .replace_document(id, doc.clone(), options, None)
                 .await
                 .map_err_app_error()?;
-            Ok(())
+            self.set_edges(id, Some(&doc.project)).await
         })
     }
 
-    fn delete_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let collection = self.collection().await?;
+            if hard {
+                trash::PROJECTS.remove(&self.db, id).await?;
+            } else {
+                trash::PROJECTS.soft_delete(&self.db, id).await?;
+            }
+            // Traversals don't reach a deleted project, nor its sub-projects through it
+            self.set_edges(id, None).await
+        })
+    }
 
-            let options = RemoveOptions::builder().silent(true);
-            collection
-                .remove_document::<ArangoProject>(id, options.build(), None)
-                .await
-                .map_err_app_error()?;
-            Ok(())
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>> {
+        Box::pin(async move {
+            let docs: Vec<ArangoProject> = trash::PROJECTS.list(&self.db).await?;
+            Ok(trash::deleted(docs.into_iter().map(|d| (d.project, d.deleted_at))))
+        })
+    }
+
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            trash::PROJECTS.restore(&self.db, id).await?;
+            let project = self.get_project(id).await?;
+            self.set_edges(id, Some(&project)).await
         })
     }
 
     fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
         Box::pin(async move {
-            let query = "FOR doc IN projects RETURN doc";
-            // CORRECTED: Use AqlQuery::builder()
-            let aql = AqlQuery::builder().query(query).build();
+            let query = Query::new("projects").live().build();
 
-            let arango_projects: Vec<ArangoProject> =
-                self.db.aql_query(aql).await.map_err_app_error()?;
+            let arango_projects: Vec<ArangoProject> = run(&self.db, query).await?;
 
             let projects = arango_projects.into_iter().map(|ap| ap.project).collect();
             Ok(projects)
         })
     }
+
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("projects").live().count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("projects").live().filter("_key", Op::Eq, id).exists();
+
+            let found: Vec<bool> = run(&self.db, query).await?;
+            Ok(found.first().copied().unwrap_or(false))
+        })
+    }
+
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        Box::pin(async move {
+            let query = "FOR v IN 1..100 OUTBOUND @start parentOf \
+                         OPTIONS { order: 'bfs', uniqueVertices: 'global' } \
+                         RETURN v";
+            let query = Aql::new(query).bind("start", format!("projects/{}", id));
+
+            let docs: Vec<ArangoProject> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.project).collect())
+        })
+    }
 }
 
 // ===================================================================
@@ -572,27 +1299,65 @@ impl<C: ClientExt + Send + Sync> TicketsRepo for ArangoTicketsRepo<C> {
         Box::pin(async move {
             let collection = self.collection().await?;
             let doc: Document<ArangoTicket> = collection.document(id).await.map_err_app_error()?;
+            if doc.document.deleted_at.is_some() {
+                return Err(AppError::NotFound(format!("Ticket {} not found", id)));
+            }
             Ok(doc.document.ticket)
         })
     }
 
+    fn get_tickets<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Ticket>, AppError>> {
+        Box::pin(async move {
+            let query = Query::by_keys("tickets", ids).live().build();
+
+            let docs: Vec<ArangoTicket> = run(&self.db, query).await?;
+            let mut by_key: HashMap<String, Ticket> = docs.into_iter().map(|d| (d.key, d.ticket)).collect();
+            Ok(Batch::lookup(ids, |id| by_key.remove(id)))
+        })
+    }
+
     fn create_ticket<'a>(&'a self, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
             let doc = ArangoTicket {
                 key: ticket.id.to_string(),
                 ticket,
+                deleted_at: None,
             };
 
+            let id = doc.ticket.id;
             let options = InsertOptions::builder().overwrite(false);
             collection
                 .create_document(doc, options.build())
                 .await
                 .map_err_app_error()?;
+            let query = Aql::new(
+                "UPSERT { _key: @key } INSERT { _key: @key, value: @id } \
+                 UPDATE { value: MAX([OLD.value, @id]) } IN counters OPTIONS { exclusive: true }",
+            )
+            .bind("key", TICKET_COUNTER)
+            .bind("id", id);
+            let _: Vec<Value> = run(&self.db, query).await?;
             Ok(())
         })
     }
 
+    fn reserve_ticket_ids<'a>(&'a self, count: i64) -> BoxFuture<'a, Result<i64, AppError>> {
+        Box::pin(async move {
+            // The exclusive lock serializes reservations, none reads a value another changes
+            let query = Aql::new(
+                "UPSERT { _key: @key } INSERT { _key: @key, value: @count } \
+                 UPDATE { value: OLD.value + @count } IN counters OPTIONS { exclusive: true } \
+                 RETURN NEW.value",
+            )
+            .bind("key", TICKET_COUNTER)
+            .bind("count", count);
+            let reserved: Vec<i64> = run(&self.db, query).await?;
+            let last = reserved.into_iter().next().ok_or_else(|| anyhow!("Ticket counter not updated"))?;
+            Ok(last - count + 1)
+        })
+    }
+
     fn update_ticket<'a>(
         &'a self,
         id: &'a str,
@@ -600,9 +1365,11 @@ impl<C: ClientExt + Send + Sync> TicketsRepo for ArangoTicketsRepo<C> {
     ) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
+            self.get_ticket(id).await?; // A deleted ticket isn't brought back by replacing it
             let doc = ArangoTicket {
                 key: id.to_string(),
                 ticket,
+                deleted_at: None,
             };
 
             let options = ReplaceOptions::builder().silent(true);
@@ -614,30 +1381,1424 @@ impl<C: ClientExt + Send + Sync> TicketsRepo for ArangoTicketsRepo<C> {
         })
     }
 
-    fn delete_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn delete_ticket<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            if hard {
+                trash::TICKETS.remove(&self.db, id).await
+            } else {
+                trash::TICKETS.soft_delete(&self.db, id).await
+            }
+        })
+    }
+
+    fn list_deleted_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Ticket>>, AppError>> {
+        Box::pin(async move {
+            let docs: Vec<ArangoTicket> = trash::TICKETS.list(&self.db).await?;
+            Ok(trash::deleted(docs.into_iter().map(|d| (d.ticket, d.deleted_at))))
+        })
+    }
+
+    fn restore_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { trash::TICKETS.restore(&self.db, id).await })
+    }
+
+    fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("tickets").live().build();
+
+            let arango_tickets: Vec<ArangoTicket> = run(&self.db, query).await?;
+
+            let tickets = arango_tickets.into_iter().map(|at| at.ticket).collect();
+            Ok(tickets)
+        })
+    }
+
+    fn list_tickets_fields<'a>(
+        &'a self,
+        fields: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Value>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("tickets").live().keep(fields);
+
+            let tickets: Vec<Value> = run(&self.db, query).await?;
+            Ok(tickets)
+        })
+    }
+
+    fn get_ticket_fields<'a>(
+        &'a self,
+        id: &'a str,
+        fields: &'a [String],
+    ) -> BoxFuture<'a, Result<Value, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("tickets").live().filter("_key", Op::Eq, id).keep(fields);
+
+            let mut tickets: Vec<Value> = run(&self.db, query).await?;
+            tickets
+                .pop()
+                .ok_or_else(|| AppError::NotFound(format!("Ticket {} not found", id)))
+        })
+    }
+
+    fn count_tickets<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("tickets").live().count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+
+    fn exists_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("tickets").live().filter("_key", Op::Eq, id).exists();
+
+            let found: Vec<bool> = run(&self.db, query).await?;
+            Ok(found.first().copied().unwrap_or(false))
+        })
+    }
+
+    fn ticket_counts<'a>(
+        &'a self,
+        project: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<TicketCount>, AppError>> {
+        Box::pin(async move {
+            // Tickets stored before status existed count as open, older severities are pairs
+            let query = r#"
+                FOR doc IN tickets
+                    FILTER doc.deleted_at == null
+                    FILTER @project == null OR doc.project == @project
+                    COLLECT project = doc.project,
+                            status = NOT_NULL(doc.status, "open"),
+                            severity = IS_ARRAY(doc.severity) ? doc.severity[0] : doc.severity.level
+                    WITH COUNT INTO count
+                    RETURN { project, status, severity, count }
+            "#;
+            let query = Aql::new(query).bind("project", project);
+
+            let mut counts: Vec<TicketCount> = run(&self.db, query).await?;
+            // AQL would sort statuses by name, keep the enum order like the other backends
+            counts.sort();
+            Ok(counts)
+        })
+    }
+
+    fn ticket_activity<'a>(
+        &'a self,
+        project: &'a str,
+        since: NaiveDate,
+    ) -> BoxFuture<'a, Result<Vec<TicketDayCount>, AppError>> {
+        Box::pin(async move {
+            // Dates are stored as RFC 3339 strings, so the first 10 characters are the UTC day
+            let query = r#"
+                FOR event IN UNION(
+                    (FOR doc IN tickets
+                        FILTER doc.deleted_at == null AND doc.project == @project
+                            AND LEFT(doc.creation_date, 10) >= @since
+                        RETURN { day: LEFT(doc.creation_date, 10), opened: 1, resolved: 0 }),
+                    (FOR doc IN tickets
+                        FILTER doc.deleted_at == null AND doc.project == @project
+                            AND doc.resolved_at != null AND LEFT(doc.resolved_at, 10) >= @since
+                        RETURN { day: LEFT(doc.resolved_at, 10), opened: 0, resolved: 1 })
+                )
+                    COLLECT day = event.day
+                    AGGREGATE opened = SUM(event.opened), resolved = SUM(event.resolved)
+                    SORT day
+                    RETURN { day, opened, resolved }
+            "#;
+            let query = Aql::new(query).bind("project", project).bind("since", since.to_string());
+
+            let days: Vec<TicketDayCount> = run(&self.db, query).await?;
+            Ok(days)
+        })
+    }
+
+    fn average_resolution_secs<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Option<f64>, AppError>> {
+        Box::pin(async move {
+            // Whole seconds only: chrono writes more fractional digits than AQL date functions read
+            let query = r#"
+                RETURN AVERAGE(
+                    FOR doc IN tickets
+                        FILTER doc.deleted_at == null AND doc.project == @project AND doc.resolved_at != null
+                        RETURN DATE_DIFF(LEFT(doc.creation_date, 19), LEFT(doc.resolved_at, 19), "s")
+                )
+            "#;
+            let query = Aql::new(query).bind("project", project);
+
+            let averages: Vec<Option<f64>> = run(&self.db, query).await?;
+            Ok(averages.into_iter().next().flatten())
+        })
+    }
+
+    fn assignee_counts<'a>(
+        &'a self,
+        project: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<AssigneeCount>, AppError>> {
+        Box::pin(async move {
+            let query = r#"
+                FOR doc IN tickets
+                    FILTER doc.deleted_at == null AND doc.project == @project AND doc.assigned_to != ""
+                    COLLECT assignee = doc.assigned_to WITH COUNT INTO count
+                    SORT count DESC, assignee
+                    LIMIT @limit
+                    RETURN { assignee, count }
+            "#;
+            let query = Aql::new(query).bind("project", project).bind("limit", limit);
+
+            let counts: Vec<AssigneeCount> = run(&self.db, query).await?;
+            Ok(counts)
+        })
+    }
+
+    fn last_rank<'a>(
+        &'a self,
+        project: Option<&'a str>,
+        status: TicketStatus,
+    ) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        Box::pin(async move {
+            // Ranks are lowercase base 36, which sorts the same bytewise and by collation
+            let query = r#"
+                FOR doc IN tickets
+                    FILTER doc.deleted_at == null AND doc.project == @project
+                    FILTER NOT_NULL(doc.status, "open") == @status AND doc.rank != null AND doc.rank != ""
+                    SORT doc.rank DESC
+                    LIMIT 1
+                    RETURN doc.rank
+            "#;
+            let query = Aql::new(query).bind("project", project).bind("status", serde_json::to_value(status)?);
+
+            let ranks: Vec<String> = run(&self.db, query).await?;
+            Ok(ranks.into_iter().next())
+        })
+    }
+
+    fn last_assignee<'a>(
+        &'a self,
+        project: &'a str,
+        among: &'a [String],
+    ) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        Box::pin(async move {
+            let query = r#"
+                FOR doc IN tickets
+                    FILTER doc.deleted_at == null AND doc.project == @project AND doc.assigned_to IN @among
+                    SORT doc.id DESC
+                    LIMIT 1
+                    RETURN doc.assigned_to
+            "#;
+            let query = Aql::new(query).bind("project", project).bind("among", among);
+
+            let assignees: Vec<String> = run(&self.db, query).await?;
+            Ok(assignees.into_iter().next())
+        })
+    }
+}
+
+
+// ===================================================================
+// Sessions Repository Implementation
+// ===================================================================
+
+pub struct ArangoSessionsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoSessionsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("sessions").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> SessionsRepo for ArangoSessionsRepo<C> {
+    fn get_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Session, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoSession> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.session)
+        })
+    }
+
+    fn create_session<'a>(&'a self, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoSession {
+                key: session.id.clone(),
+                purge_at: session.expires_at.timestamp(),
+                session,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_session<'a>(
+        &'a self,
+        id: &'a str,
+        session: Session,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoSession {
+                key: id.to_string(),
+                purge_at: session.expires_at.timestamp(),
+                session,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             let collection = self.collection().await?;
 
             let options = RemoveOptions::builder().silent(true).build();
             collection
-                .remove_document::<ArangoTicket>(id, options, None)
+                .remove_document::<ArangoSession>(id, options, None)
                 .await
                 .map_err_app_error()?;
             Ok(())
         })
     }
 
-    fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>> {
+    fn list_user_sessions<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Session>, AppError>> {
         Box::pin(async move {
-            let query = "FOR doc IN tickets RETURN doc";
-            // CORRECTED: Use AqlQuery::builder()
-            let aql = AqlQuery::builder().query(query).build();
+            let query = Query::new("sessions").filter("username", Op::Eq, username).build();
 
-            let arango_tickets: Vec<ArangoTicket> =
-                self.db.aql_query(aql).await.map_err_app_error()?;
+            let arango_sessions: Vec<ArangoSession> = run(&self.db, query).await?;
 
-            let tickets = arango_tickets.into_iter().map(|at| at.ticket).collect();
-            Ok(tickets)
+            let sessions = arango_sessions.into_iter().map(|s| s.session).collect();
+            Ok(sessions)
+        })
+    }
+}
+
+// ===================================================================
+// Invites Repository Implementation
+// ===================================================================
+
+pub struct ArangoInvitesRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoInvitesRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("invites").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> InvitesRepo for ArangoInvitesRepo<C> {
+    fn get_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Invite, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoInvite> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.invite)
+        })
+    }
+
+    fn create_invite<'a>(&'a self, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoInvite {
+                key: invite.id.clone(),
+                invite,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_invite<'a>(
+        &'a self,
+        id: &'a str,
+        invite: Invite,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoInvite {
+                key: id.to_string(),
+                invite,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn claim_invite<'a>(&'a self, id: &'a str, username: &'a str, at: DateTime<Utc>) -> BoxFuture<'a, Result<Invite, AppError>> {
+        Box::pin(async move {
+            // The revision read is a precondition of the update: a concurrent claim
+            // changes it, and this one then fails instead of overwriting that one
+            let query = Aql::new(
+                "LET invite = DOCUMENT('invites', @key) \
+                 FILTER invite != null AND invite.used_by == null \
+                 UPDATE { _key: invite._key, _rev: invite._rev } WITH { used_by: @username, used_at: @at } \
+                 IN invites OPTIONS { ignoreRevs: false } \
+                 RETURN NEW",
+            )
+            .bind("key", id)
+            .bind("username", username)
+            .bind("at", serde_json::to_value(at)?);
+            let claimed: Vec<ArangoInvite> = match run(&self.db, query).await {
+                Ok(claimed) => claimed,
+                Err(AppError::Conflict(_)) => vec![],
+                Err(e) => return Err(e),
+            };
+            match claimed.into_iter().next() {
+                Some(claimed) => Ok(claimed.invite),
+                None => {
+                    self.get_invite(id).await?;
+                    Err(AppError::Conflict(format!("Invite {} already used", id)))
+                }
+            }
         })
     }
+
+    fn delete_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoInvite>(id, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_invites<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Invite>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("invites").build();
+
+            let arango_invites: Vec<ArangoInvite> = run(&self.db, query).await?;
+
+            let invites = arango_invites.into_iter().map(|ai| ai.invite).collect();
+            Ok(invites)
+        })
+    }
+}
+
+// ===================================================================
+// Security Events Repository Implementation
+// ===================================================================
+
+/// The events matching a filter, unset fields leaving their condition out.
+fn security_events_query(filter: &SecurityEventFilter) -> Result<Query, AppError> {
+    let mut query = Query::new("security_events");
+    if let Some(kind) = filter.kind {
+        query = query.filter("kind", Op::Eq, serde_json::to_value(kind)?);
+    }
+    if let Some(username) = &filter.username {
+        query = query.filter("username", Op::Eq, username.as_str());
+    }
+    if let Some(ip) = &filter.ip {
+        query = query.filter("ip", Op::Eq, ip.as_str());
+    }
+    if let Some(since) = filter.since {
+        query = query.filter_expr(
+            "DATE_TIMESTAMP(doc.created_at) >= DATE_TIMESTAMP(@since)",
+            [("since", serde_json::to_value(since)?)],
+        );
+    }
+    Ok(query)
+}
+
+pub struct ArangoSecurityEventsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoSecurityEventsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("security_events").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> SecurityEventsRepo for ArangoSecurityEventsRepo<C> {
+    fn create_event<'a>(&'a self, event: SecurityEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoSecurityEvent {
+                key: event.id.clone(),
+                event,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_events<'a>(
+        &'a self,
+        filter: &'a SecurityEventFilter,
+    ) -> BoxFuture<'a, Result<Vec<SecurityEvent>, AppError>> {
+        Box::pin(async move {
+            // Keys are time-ordered UUIDv7, so sorting by key is sorting by time
+            let mut query = security_events_query(filter)?;
+            if let Some(cursor) = &filter.cursor {
+                query = query.filter("_key", Op::Lt, cursor.as_str());
+            }
+            query = query.sort("_key", Direction::Desc);
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit);
+            }
+
+            let arango_events: Vec<ArangoSecurityEvent> = run(&self.db, query.build()).await?;
+
+            let events = arango_events.into_iter().map(|ae| ae.event).collect();
+            Ok(events)
+        })
+    }
+
+    fn count_events<'a>(
+        &'a self,
+        filter: &'a SecurityEventFilter,
+    ) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = security_events_query(filter)?.count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+}
+
+// ===================================================================
+// Idempotency Repository
+// ===================================================================
+
+pub struct ArangoIdempotencyRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoIdempotencyRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("idempotency").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> IdempotencyRepo for ArangoIdempotencyRepo<C> {
+    fn get_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<IdempotencyRecord, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoIdempotencyRecord> =
+                collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.record)
+        })
+    }
+
+    fn create_record<'a>(&'a self, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoIdempotencyRecord {
+                key: record.id.clone(),
+                purge_at: record.expires_at.timestamp(),
+                record,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_record<'a>(
+        &'a self,
+        id: &'a str,
+        record: IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoIdempotencyRecord {
+                key: id.to_string(),
+                purge_at: record.expires_at.timestamp(),
+                record,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn delete_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoIdempotencyRecord>(id, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+}
+
+// ===================================================================
+// Notifications Repository
+// ===================================================================
+
+/// A recipient's notifications, only read or unread ones if the filter says so.
+fn notifications_query(recipient: &str, filter: &NotificationFilter) -> Query {
+    let query = Query::new("notifications").filter("recipient", Op::Eq, recipient);
+    match filter.unread {
+        Some(unread) => query.filter("read", Op::Ne, unread),
+        None => query,
+    }
+}
+
+pub struct ArangoNotificationsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoNotificationsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("notifications").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> NotificationsRepo for ArangoNotificationsRepo<C> {
+    fn get_notification<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Notification, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoNotification> =
+                collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.notification)
+        })
+    }
+
+    fn create_notification<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoNotification {
+                key: notification.id.clone(),
+                notification,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_notification<'a>(
+        &'a self,
+        id: &'a str,
+        notification: Notification,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoNotification {
+                key: id.to_string(),
+                notification,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_notifications<'a>(
+        &'a self,
+        recipient: &'a str,
+        filter: &'a NotificationFilter,
+    ) -> BoxFuture<'a, Result<Vec<Notification>, AppError>> {
+        Box::pin(async move {
+            // Keys are time-ordered UUIDv7, so sorting by key is sorting by time
+            let mut query = notifications_query(recipient, filter);
+            if let Some(cursor) = &filter.cursor {
+                query = query.filter("_key", Op::Lt, cursor.as_str());
+            }
+            query = query.sort("_key", Direction::Desc);
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit);
+            }
+
+            let docs: Vec<ArangoNotification> = run(&self.db, query.build()).await?;
+            Ok(docs.into_iter().map(|d| d.notification).collect())
+        })
+    }
+
+    fn count_notifications<'a>(
+        &'a self,
+        recipient: &'a str,
+        filter: &'a NotificationFilter,
+    ) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let query = notifications_query(recipient, filter).count();
+
+            let counts: Vec<usize> = run(&self.db, query).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+}
+
+// ===================================================================
+// Activity Repository
+// ===================================================================
+
+/// Activities matching the filter, but for its cursor and limit.
+fn activity_query(filter: &ActivityFilter) -> Result<Query, AppError> {
+    let mut query = Query::new("activity");
+    if let Some(project) = &filter.project {
+        query = query.filter("project", Op::Eq, project.as_str());
+    }
+    if let Some(actor) = &filter.actor {
+        query = query.filter("actor", Op::Eq, actor.as_str());
+    }
+    if !filter.kinds.is_empty() {
+        let kinds = filter.kinds.iter().map(serde_json::to_value).collect::<Result<_, _>>()?;
+        query = query.filter_in("kind", kinds);
+    }
+    Ok(query)
+}
+
+pub struct ArangoActivityRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoActivityRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("activity").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> ActivityRepo for ArangoActivityRepo<C> {
+    fn create_activity<'a>(&'a self, activity: Activity) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoActivity {
+                key: activity.id.clone(),
+                activity,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<Vec<Activity>, AppError>> {
+        Box::pin(async move {
+            // Keys are time-ordered UUIDv7, so sorting by key is sorting by time
+            let mut query = activity_query(filter)?;
+            if let Some(cursor) = &filter.cursor {
+                query = query.filter("_key", Op::Lt, cursor.as_str());
+            }
+            query = query.sort("_key", Direction::Desc);
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit);
+            }
+
+            let docs: Vec<ArangoActivity> = run(&self.db, query.build()).await?;
+            Ok(docs.into_iter().map(|d| d.activity).collect())
+        })
+    }
+
+    fn count_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let counts: Vec<usize> = run(&self.db, activity_query(filter)?.count()).await?;
+            Ok(counts.first().copied().unwrap_or(0))
+        })
+    }
+}
+
+// ===================================================================
+// Drafts Repository
+// ===================================================================
+
+pub struct ArangoDraftsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoDraftsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("drafts").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> DraftsRepo for ArangoDraftsRepo<C> {
+    fn get_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Draft, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoDraft> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.draft)
+        })
+    }
+
+    fn put_draft<'a>(&'a self, draft: Draft) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoDraft {
+                key: draft.id.clone(),
+                purge_at: draft.expires_at.timestamp(),
+                draft,
+            };
+
+            let options = InsertOptions::builder().overwrite(true).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn delete_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoDraft>(id, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+}
+
+// ===================================================================
+// Shares Repository
+// ===================================================================
+
+pub struct ArangoSharesRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoSharesRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("shares").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> SharesRepo for ArangoSharesRepo<C> {
+    fn create_share<'a>(&'a self, share: TicketShare) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoShare {
+                key: share.id.clone(),
+                purge_at: share.expires_at.timestamp(),
+                share,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn get_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<TicketShare, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoShare> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.share)
+        })
+    }
+
+    fn list_shares<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<TicketShare>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("shares")
+                .filter("ticket", Op::Eq, ticket)
+                .sort("created_at", Direction::Asc)
+                .build();
+
+            let docs: Vec<ArangoShare> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.share).collect())
+        })
+    }
+
+    fn delete_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoShare>(id, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+}
+
+// ===================================================================
+// Preferences Repository
+// ===================================================================
+
+pub struct ArangoPreferencesRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoPreferencesRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("preferences").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> PreferencesRepo for ArangoPreferencesRepo<C> {
+    fn get_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Preference, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoPreference> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.preference)
+        })
+    }
+
+    fn put_preference<'a>(&'a self, preference: Preference) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let id = preference.id.clone();
+            let version = preference.version;
+            let doc = ArangoPreference {
+                key: id.clone(),
+                preference,
+            };
+            if version <= 1 {
+                let options = InsertOptions::builder().overwrite(false).build();
+                self.collection()
+                    .await?
+                    .create_document(doc, options)
+                    .await
+                    .map_err_app_error()?;
+                return Ok(());
+            }
+
+            // As for invite claims, the revision read is a precondition of the replace
+            let query = Aql::new(
+                "LET stored = DOCUMENT('preferences', @key) \
+                 FILTER stored != null AND stored.version == @previous \
+                 REPLACE { _key: stored._key, _rev: stored._rev } WITH @doc \
+                 IN preferences OPTIONS { ignoreRevs: false } \
+                 RETURN NEW._key",
+            )
+            .bind("key", id.as_str())
+            .bind("previous", version - 1)
+            .bind("doc", serde_json::to_value(&doc)?);
+            let replaced: Vec<String> = match run(&self.db, query).await {
+                Ok(replaced) => replaced,
+                Err(AppError::Conflict(_)) => vec![],
+                Err(e) => return Err(e),
+            };
+            if replaced.is_empty() {
+                let stored = self.get_preference(&id).await?;
+                return Err(AppError::Conflict(format!("Preference {} is at version {}", id, stored.version)));
+            }
+            Ok(())
+        })
+    }
+
+    fn list_preferences<'a>(&'a self, owner: &'a str) -> BoxFuture<'a, Result<Vec<Preference>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("preferences")
+                .filter("owner", Op::Eq, owner)
+                .sort("namespace", Direction::Asc)
+                .build();
+
+            let docs: Vec<ArangoPreference> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.preference).collect())
+        })
+    }
+
+    fn delete_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoPreference>(id, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+}
+
+// ===================================================================
+// Read Receipts Repository
+// ===================================================================
+
+pub struct ArangoReadReceiptsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoReadReceiptsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("receipts").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> ReadReceiptsRepo for ArangoReadReceiptsRepo<C> {
+    fn set_receipt<'a>(&'a self, receipt: ReadReceipt) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoReadReceipt {
+                key: format!("{}:{}", receipt.ticket, receipt.username),
+                receipt,
+            };
+
+            let options = InsertOptions::builder().overwrite(true).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_receipts_of<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("receipts").filter("username", Op::Eq, username);
+            let docs: Vec<ArangoReadReceipt> = run(&self.db, query.build()).await?;
+            Ok(docs.into_iter().map(|d| d.receipt).collect())
+        })
+    }
+
+    fn list_receipts<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("receipts")
+                .filter("ticket", Op::Eq, ticket)
+                .sort("seen_at", Direction::Desc);
+            let docs: Vec<ArangoReadReceipt> = run(&self.db, query.build()).await?;
+            Ok(docs.into_iter().map(|d| d.receipt).collect())
+        })
+    }
+}
+
+// ===================================================================
+// Milestones Repository
+// ===================================================================
+
+pub struct ArangoMilestonesRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoMilestonesRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("milestones").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> MilestonesRepo for ArangoMilestonesRepo<C> {
+    fn get_milestone<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Milestone, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoMilestone> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.milestone)
+        })
+    }
+
+    fn create_milestone<'a>(&'a self, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoMilestone {
+                key: milestone.id.clone(),
+                milestone,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_milestone<'a>(
+        &'a self,
+        id: &'a str,
+        milestone: Milestone,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoMilestone {
+                key: id.to_string(),
+                milestone,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_milestones<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Vec<Milestone>, AppError>> {
+        Box::pin(async move {
+            // Dates are stored as YYYY-MM-DD, which sorts chronologically
+            let query = Query::new("milestones")
+                .filter("project", Op::Eq, project)
+                .sort("start", Direction::Asc)
+                .sort("_key", Direction::Asc)
+                .build();
+
+            let docs: Vec<ArangoMilestone> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.milestone).collect())
+        })
+    }
+}
+
+// ===================================================================
+// Comments Repository
+// ===================================================================
+
+pub struct ArangoCommentsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoCommentsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("comments").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> CommentsRepo for ArangoCommentsRepo<C> {
+    fn create_comment<'a>(&'a self, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoComment {
+                key: comment.id.clone(),
+                comment,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_comments<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("comments")
+                .filter("ticket", Op::Eq, ticket)
+                .sort("_key", Direction::Asc)
+                .build();
+
+            let docs: Vec<ArangoComment> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.comment).collect())
+        })
+    }
+
+    fn list_comments_by<'a>(&'a self, author: &'a str) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("comments")
+                .filter("author", Op::Eq, author)
+                .sort("_key", Direction::Asc)
+                .build();
+
+            let docs: Vec<ArangoComment> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.comment).collect())
+        })
+    }
+
+    fn update_comment<'a>(&'a self, id: &'a str, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoComment {
+                key: id.to_string(),
+                comment,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn find_comment_by_message_id<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<Option<Comment>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("comments").filter("message_id", Op::Eq, message_id).limit(1).build();
+
+            let docs: Vec<ArangoComment> = run(&self.db, query).await?;
+            Ok(docs.into_iter().next().map(|d| d.comment))
+        })
+    }
+}
+
+// ===================================================================
+// Chat Channels Repository
+// ===================================================================
+
+pub struct ArangoChatChannelsRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoChatChannelsRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("chat_channels").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> ChatChannelsRepo for ArangoChatChannelsRepo<C> {
+    fn get_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<ChatChannel, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoChatChannel> = collection.document(project).await.map_err_app_error()?;
+            Ok(doc.document.channel)
+        })
+    }
+
+    fn create_chat_channel<'a>(&'a self, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoChatChannel {
+                key: channel.project.clone(),
+                channel,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_chat_channel<'a>(
+        &'a self,
+        project: &'a str,
+        channel: ChatChannel,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoChatChannel {
+                key: project.to_string(),
+                channel,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(project, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn delete_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+
+            let options = RemoveOptions::builder().silent(true).build();
+            collection
+                .remove_document::<ArangoChatChannel>(project, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_chat_channels<'a>(&'a self) -> BoxFuture<'a, Result<Vec<ChatChannel>, AppError>> {
+        Box::pin(async move {
+            let query = Query::new("chat_channels").sort("_key", Direction::Asc).build();
+
+            let docs: Vec<ArangoChatChannel> = run(&self.db, query).await?;
+            Ok(docs.into_iter().map(|d| d.channel).collect())
+        })
+    }
+}
+
+// ===================================================================
+// Outbox Repository
+// ===================================================================
+
+pub struct ArangoOutboxRepo<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoOutboxRepo<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+    async fn collection(&self) -> Result<Collection<C>, AppError> {
+        self.db.collection("outbox").await.map_err_app_error()
+    }
+}
+
+impl<C: ClientExt + Send + Sync> OutboxRepo for ArangoOutboxRepo<C> {
+    fn get_outbox_entry<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<OutboxEntry, AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc: Document<ArangoOutboxEntry> = collection.document(id).await.map_err_app_error()?;
+            Ok(doc.document.entry)
+        })
+    }
+
+    fn create_outbox_entry<'a>(&'a self, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoOutboxEntry {
+                key: entry.id.clone(),
+                entry,
+            };
+
+            let options = InsertOptions::builder().overwrite(false).build();
+            collection
+                .create_document(doc, options)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn update_outbox_entry<'a>(&'a self, id: &'a str, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let collection = self.collection().await?;
+            let doc = ArangoOutboxEntry {
+                key: id.to_string(),
+                entry,
+            };
+
+            let options = ReplaceOptions::builder().silent(true).build();
+            collection
+                .replace_document(id, doc, options, None)
+                .await
+                .map_err_app_error()?;
+            Ok(())
+        })
+    }
+
+    fn list_outbox_entries<'a>(&'a self, filter: &'a OutboxFilter) -> BoxFuture<'a, Result<Vec<OutboxEntry>, AppError>> {
+        Box::pin(async move {
+            // Keys are time-ordered UUIDv7, so sorting by key is sorting by time
+            let mut query = Query::new("outbox");
+            if let Some(status) = filter.status {
+                query = query.filter("status", Op::Eq, serde_json::to_value(status)?);
+            }
+            if let Some(since) = filter.since {
+                query = query.filter_expr(
+                    "DATE_TIMESTAMP(doc.created_at) >= DATE_TIMESTAMP(@since)",
+                    [("since", serde_json::to_value(since)?)],
+                );
+            }
+            query = query.sort("_key", Direction::Asc);
+            if let Some(limit) = filter.limit {
+                query = query.limit(limit);
+            }
+
+            let docs: Vec<ArangoOutboxEntry> = run(&self.db, query.build()).await?;
+            Ok(docs.into_iter().map(|d| d.entry).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{db::versioned::FIELD, test::app::sample_ticket};
+
+    #[test]
+    fn documents_carry_their_schema_version() {
+        let doc = ArangoTicket {
+            key: "1".to_string(),
+            ticket: sample_ticket(1, "Stored"),
+            deleted_at: None,
+        };
+        let mut stored = serde_json::to_value(&doc).unwrap();
+        assert_eq!((stored["_key"].clone(), stored[FIELD].clone()), (json!("1"), json!(1)));
+
+        // As read back, with the fields ArangoDB adds, from before versions were kept
+        stored["_id"] = json!("tickets/1");
+        stored["_rev"] = json!("_hV3bq--_");
+        stored.as_object_mut().unwrap().remove(FIELD);
+        stored["severity"] = json!([1, "critical"]);
+        let read: ArangoTicket = serde_json::from_value(stored).unwrap();
+        assert_eq!((read.key.as_str(), read.ticket.severity.level, read.deleted_at), ("1", 1, None));
+    }
 }
@@ -0,0 +1,158 @@
+// Full-text search through an ArangoSearch view over the principals, projects and
+// tickets collections, scored with BM25
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arangors::{
+    Database,
+    client::ClientExt,
+    view::{ArangoSearchViewLink, ArangoSearchViewPropertiesOptions, ViewOptions},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use super::{ArangoGroup, ArangoProject, ArangoTicket, ArangoUser, MapArangoError, run};
+use crate::db::{BoxFuture, Scored, SearchHits, SearchService, aql::Aql};
+use crate::error::AppError;
+
+const VIEW: &str = "search_view"; // named in the queries below, `search` is a keyword
+
+/// Text is split into lowercased, stemmed words, by the view and by the queries.
+const ANALYZER: &str = "text_en";
+
+/// The fields of each collection searched through the view, as dotted paths. Array
+/// elements are indexed under the array's name, and numbers whatever the analyzer.
+const LINKS: [(&str, &[&str]); 3] = [
+    ("principals", &["username", "personal.name", "personal.job_title", "gid", "name"]),
+    ("projects", &["id", "tickets.prefix"]),
+    ("tickets", &["id", "title", "description"]),
+];
+
+/// A link indexing `fields` with the analyzer, nested as the paths say.
+fn link(fields: &[&str]) -> ArangoSearchViewLink {
+    let mut nested: HashMap<String, Vec<&str>> = HashMap::new();
+    for field in fields {
+        match field.split_once('.') {
+            Some((parent, child)) => nested.entry(parent.to_string()).or_default().push(child),
+            None => {
+                nested.entry(field.to_string()).or_default();
+            }
+        }
+    }
+    let fields = nested
+        .into_iter()
+        .map(|(name, children)| {
+            let field = if children.is_empty() {
+                ArangoSearchViewLink::builder().build()
+            } else {
+                link(&children)
+            };
+            (name, field)
+        })
+        .collect();
+    ArangoSearchViewLink::builder()
+        .analyzers(vec![ANALYZER.to_string()])
+        .fields(fields)
+        .build()
+}
+
+/// Creates the view if it doesn't exist.
+pub(super) async fn create_view<C: ClientExt + Send + Sync>(db: &Database<C>) -> Result<(), AppError> {
+    if db.view(VIEW).await.is_ok() {
+        return Ok(());
+    }
+    let links: HashMap<String, ArangoSearchViewLink> = LINKS
+        .iter()
+        .map(|(collection, fields)| (collection.to_string(), link(fields)))
+        .collect();
+
+    let options = ViewOptions::builder()
+        .name(VIEW.to_string())
+        .properties(ArangoSearchViewPropertiesOptions::builder().links(links).build())
+        .build();
+    db.create_view(options).await.map_err_app_error()?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Hit<T> {
+    doc: T,
+    score: f64,
+}
+
+pub struct ArangoSearchService<C: ClientExt + Send + Sync> {
+    db: Arc<Database<C>>,
+}
+
+impl<C: ClientExt + Send + Sync> ArangoSearchService<C> {
+    pub fn new(db: Arc<Database<C>>) -> Self {
+        Self { db }
+    }
+
+    /// Runs one of the queries below, each searching one collection.
+    async fn hits<T: DeserializeOwned>(&self, query: Aql, text: &str, limit: usize) -> Result<Vec<Hit<T>>, AppError> {
+        let query = query.bind("query", text).bind("limit", limit);
+        run(&self.db, query).await
+    }
+}
+
+impl<C: ClientExt + Send + Sync> SearchService for ArangoSearchService<C> {
+    fn find<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<SearchHits, AppError>> {
+        Box::pin(async move {
+            let numbers: Vec<i64> = query.split_whitespace().filter_map(|w| w.parse().ok()).collect();
+            let tickets = Aql::new(
+                "LET terms = TOKENS(@query, 'text_en') \
+                 FOR doc IN search_view \
+                 SEARCH ANALYZER(BOOST(doc.title IN terms, 3) OR doc.description IN terms, 'text_en') \
+                     OR BOOST(doc.id IN @numbers, 3) \
+                 OPTIONS { collections: ['tickets'] } \
+                 FILTER doc.deleted_at == null \
+                 SORT BM25(doc) DESC, doc._key \
+                 LIMIT @limit \
+                 RETURN { doc: doc, score: BM25(doc) }",
+            )
+            .bind("numbers", numbers);
+            let projects = Aql::new(
+                "LET terms = TOKENS(@query, 'text_en') \
+                 FOR doc IN search_view \
+                 SEARCH ANALYZER(BOOST(doc.id IN terms, 3) OR BOOST(doc.tickets.prefix IN terms, 2), 'text_en') \
+                 OPTIONS { collections: ['projects'] } \
+                 FILTER doc.deleted_at == null \
+                 SORT BM25(doc) DESC, doc._key \
+                 LIMIT @limit \
+                 RETURN { doc: doc, score: BM25(doc) }",
+            );
+            let users = Aql::new(
+                "LET terms = TOKENS(@query, 'text_en') \
+                 FOR doc IN search_view \
+                 SEARCH ANALYZER(BOOST(doc.username IN terms, 3) OR BOOST(doc.personal.name IN terms, 3) \
+                     OR doc.personal.job_title IN terms, 'text_en') \
+                 OPTIONS { collections: ['principals'] } \
+                 FILTER doc.doc_type == 'user' AND doc.deleted_at == null \
+                 SORT BM25(doc) DESC, doc._key \
+                 LIMIT @limit \
+                 RETURN { doc: doc, score: BM25(doc) }",
+            );
+            let groups = Aql::new(
+                "LET terms = TOKENS(@query, 'text_en') \
+                 FOR doc IN search_view \
+                 SEARCH ANALYZER(BOOST(doc.gid IN terms, 3) OR BOOST(doc.name IN terms, 3), 'text_en') \
+                 OPTIONS { collections: ['principals'] } \
+                 FILTER doc.doc_type == 'group' AND doc.deleted_at == null \
+                 SORT BM25(doc) DESC, doc._key \
+                 LIMIT @limit \
+                 RETURN { doc: doc, score: BM25(doc) }",
+            );
+
+            let tickets: Vec<Hit<ArangoTicket>> = self.hits(tickets, query, limit).await?;
+            let projects: Vec<Hit<ArangoProject>> = self.hits(projects, query, limit).await?;
+            let users: Vec<Hit<ArangoUser>> = self.hits(users, query, limit).await?;
+            let groups: Vec<Hit<ArangoGroup>> = self.hits(groups, query, limit).await?;
+            Ok(SearchHits {
+                tickets: tickets.into_iter().map(|h| Scored { entity: h.doc.ticket, score: h.score }).collect(),
+                projects: projects.into_iter().map(|h| Scored { entity: h.doc.project, score: h.score }).collect(),
+                users: users.into_iter().map(|h| Scored { entity: h.doc.user, score: h.score }).collect(),
+                groups: groups.into_iter().map(|h| Scored { entity: h.doc.group, score: h.score }).collect(),
+            })
+        })
+    }
+}
@@ -0,0 +1,137 @@
+// Soft deletion: documents stay in their collection with `deleted_at` set, which the
+// queries of live documents filter out with `Query::live`
+use arangors::{Database, client::ClientExt};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::run;
+use crate::db::{Deleted, aql::{Aql, Op, Query}};
+use crate::error::AppError;
+
+/// Where the documents of one kind of entity are, for soft deletion.
+pub(super) struct Trash {
+    collection: &'static str,
+    doc_type: Option<&'static str>, // in collections shared by several kinds
+    entity: &'static str,           // for error messages
+}
+
+pub(super) const USERS: Trash = Trash {
+    collection: "principals",
+    doc_type: Some("user"),
+    entity: "User",
+};
+
+pub(super) const GROUPS: Trash = Trash {
+    collection: "principals",
+    doc_type: Some("group"),
+    entity: "Group",
+};
+
+pub(super) const PROJECTS: Trash = Trash {
+    collection: "projects",
+    doc_type: None,
+    entity: "Project",
+};
+
+pub(super) const TICKETS: Trash = Trash {
+    collection: "tickets",
+    doc_type: None,
+    entity: "Ticket",
+};
+
+impl Trash {
+    /// Binds the collection, the key and the type the document must have.
+    fn bind(&self, query: Aql, key: &str) -> Aql {
+        query
+            .bind("@collection", self.collection)
+            .bind("key", key)
+            .bind("doc_type", self.doc_type)
+    }
+
+    /// Sets `deleted_at` on the live document.
+    pub async fn soft_delete<C: ClientExt + Send + Sync>(&self, db: &Database<C>, key: &str) -> Result<(), AppError> {
+        let query = Aql::new(
+            "FOR doc IN @@collection \
+             FILTER doc._key == @key AND doc.deleted_at == null \
+             FILTER @doc_type == null OR doc.doc_type == @doc_type \
+             UPDATE doc WITH { deleted_at: @now } IN @@collection \
+             RETURN NEW._key",
+        );
+        let query = self.bind(query, key).bind("now", serde_json::to_value(Utc::now())?);
+
+        let updated: Vec<String> = run(db, query).await?;
+        if updated.is_empty() {
+            return Err(AppError::NotFound(format!("{} {} not found", self.entity, key)));
+        }
+        Ok(())
+    }
+
+    /// Clears `deleted_at` of the deleted document.
+    pub async fn restore<C: ClientExt + Send + Sync>(&self, db: &Database<C>, key: &str) -> Result<(), AppError> {
+        let query = Aql::new(
+            "FOR doc IN @@collection \
+             FILTER doc._key == @key AND doc.deleted_at != null \
+             FILTER @doc_type == null OR doc.doc_type == @doc_type \
+             UPDATE doc WITH { deleted_at: null } IN @@collection OPTIONS { keepNull: false } \
+             RETURN NEW._key",
+        );
+        let query = self.bind(query, key);
+
+        let updated: Vec<String> = run(db, query).await?;
+        if updated.is_empty() {
+            return Err(AppError::NotFound(format!(
+                "Deleted {} {} not found",
+                self.entity.to_lowercase(),
+                key
+            )));
+        }
+        Ok(())
+    }
+
+    /// Removes the live or deleted document for good.
+    pub async fn remove<C: ClientExt + Send + Sync>(&self, db: &Database<C>, key: &str) -> Result<(), AppError> {
+        let query = Aql::new(
+            "FOR doc IN @@collection \
+             FILTER doc._key == @key \
+             FILTER @doc_type == null OR doc.doc_type == @doc_type \
+             REMOVE doc IN @@collection \
+             RETURN OLD._key",
+        );
+        let query = self.bind(query, key);
+
+        let removed: Vec<String> = run(db, query).await?;
+        if removed.is_empty() {
+            return Err(AppError::NotFound(format!("{} {} not found", self.entity, key)));
+        }
+        Ok(())
+    }
+
+    /// The deleted documents.
+    pub async fn list<C, T>(&self, db: &Database<C>) -> Result<Vec<T>, AppError>
+    where
+        C: ClientExt + Send + Sync,
+        T: DeserializeOwned,
+    {
+        let mut query = Query::new(self.collection);
+        if let Some(doc_type) = self.doc_type {
+            query = query.filter("doc_type", Op::Eq, doc_type);
+        }
+        let query = query
+            .filter("deleted_at", Op::Ne, Value::Null)
+            .build();
+
+        run(db, query).await
+    }
+}
+
+/// Deleted entities with when they were deleted, oldest deletion first. The dates are
+/// compared parsed, chrono writes a varying number of fractional digits.
+pub(super) fn deleted<T>(docs: impl IntoIterator<Item = (T, Option<DateTime<Utc>>)>) -> Vec<Deleted<T>> {
+    let mut deleted: Vec<Deleted<T>> = docs
+        .into_iter()
+        .filter_map(|(entity, deleted_at)| Some(Deleted { entity, deleted_at: deleted_at? }))
+        .collect();
+    deleted.sort_by_key(|d| d.deleted_at);
+    deleted
+}
@@ -0,0 +1,132 @@
+// Circuit breaker in front of a database, failing fast while the backend is down
+use std::sync::{
+    Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{BoxFuture, guarded::{Guard, GuardedDatabase}};
+use crate::error::AppError;
+
+/// A database behind a circuit breaker.
+pub type BreakerDatabase = GuardedDatabase<CircuitBreaker>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,   // calls go through
+    Open,     // calls fail right away
+    HalfOpen, // one call goes through to probe the backend
+}
+
+/// The breaker's state, for operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BreakerStats {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub trips: u64, // times it opened since the instance started
+}
+
+enum Circuit {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant }, // when the probe started
+}
+
+/// Opens after `threshold` consecutive backend failures. While open, calls fail
+/// with `AppError::Unavailable` without reaching the backend. After `cooldown` one
+/// call probes it: success closes the circuit, failure opens it for another cooldown.
+///
+/// Only internal and I/O errors are failures, a missing entity or a conflict says
+/// nothing about the backend's health.
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    circuit: Mutex<Circuit>,
+    trips: AtomicU64,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            circuit: Mutex::new(Circuit::Closed { failures: 0 }),
+            trips: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> BreakerStats {
+        let circuit = self.circuit.lock().unwrap();
+        let (state, consecutive_failures) = match *circuit {
+            Circuit::Closed { failures } => (CircuitState::Closed, failures),
+            Circuit::Open { .. } => (CircuitState::Open, self.threshold),
+            Circuit::HalfOpen { .. } => (CircuitState::HalfOpen, self.threshold),
+        };
+        BreakerStats {
+            state,
+            consecutive_failures,
+            trips: self.trips.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Whether a call may go through, turning an expired open circuit into a probe.
+    fn admit(&self) -> Result<(), AppError> {
+        let mut circuit = self.circuit.lock().unwrap();
+        match *circuit {
+            Circuit::Closed { .. } => Ok(()),
+            Circuit::Open { until } if Instant::now() >= until => {
+                *circuit = Circuit::HalfOpen { since: Instant::now() };
+                Ok(())
+            }
+            // The probe was dropped before it answered, try another one
+            Circuit::HalfOpen { since } if since.elapsed() >= self.cooldown => {
+                *circuit = Circuit::HalfOpen { since: Instant::now() };
+                Ok(())
+            }
+            Circuit::Open { until } => Err(AppError::Unavailable(format!(
+                "Database circuit open, retry in {}s",
+                until.saturating_duration_since(Instant::now()).as_secs().max(1)
+            ))),
+            Circuit::HalfOpen { .. } => Err(AppError::Unavailable("Database circuit open, probing the backend".to_string())),
+        }
+    }
+
+    fn record(&self, failed: bool) {
+        let mut circuit = self.circuit.lock().unwrap();
+        if !failed {
+            if !matches!(*circuit, Circuit::Closed { .. }) {
+                log::info!("Database circuit closed, the backend answers again");
+            }
+            *circuit = Circuit::Closed { failures: 0 };
+            return;
+        }
+        let failures = match *circuit {
+            Circuit::Closed { failures } => failures + 1,
+            Circuit::HalfOpen { .. } => self.threshold,
+            // Admitted before the circuit opened
+            Circuit::Open { .. } => return,
+        };
+        if failures >= self.threshold {
+            log::warn!("Database circuit open for {:?} after {} failures", self.cooldown, failures);
+            self.trips.fetch_add(1, Ordering::Relaxed);
+            *circuit = Circuit::Open { until: Instant::now() + self.cooldown };
+        } else {
+            *circuit = Circuit::Closed { failures };
+        }
+    }
+}
+
+impl Guard for CircuitBreaker {
+    fn call<'a, T: Send + 'a>(&'a self, call: BoxFuture<'a, Result<T, AppError>>) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            self.admit()?;
+            let result = call.await;
+            self.record(matches!(result, Err(AppError::Internal(_) | AppError::Io(_))));
+            result
+        })
+    }
+}
@@ -0,0 +1,353 @@
+// Read-through cache in front of any database, for the reads every request makes
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::{future::Cache, policy::EvictionPolicy};
+
+use crate::db::{
+    ActivityRepo, BackendInfo, Batch, BoxFuture, ChatChannelsRepo, CommentsRepo, DatabaseInterface, Deleted, DraftsRepo, GraphRepo, GroupsRepo, IdempotencyRepo,
+    InvitesRepo, MilestonesRepo, NotificationsRepo, OutboxRepo, PreferencesRepo, ProjectsRepo, ReadReceiptsRepo, SearchService, SecurityEventsRepo, SessionsRepo, SharesRepo, TicketsRepo,
+    UsersRepo,
+};
+use crate::error::AppError;
+use crate::models::{Project, User};
+
+/// Key of the cached project list.
+const ALL: &str = "*";
+
+/// Entries kept for at most `ttl`, the least recently used evicted once `capacity` is
+/// reached.
+fn ttl_cache<T: Clone + Send + Sync + 'static>(capacity: usize, ttl: Duration) -> Cache<String, T> {
+    Cache::builder()
+        .max_capacity(capacity as u64)
+        .time_to_live(ttl)
+        .eviction_policy(EvictionPolicy::lru())
+        .build()
+}
+
+/// Wraps a database and caches user and project lookups, the reads behind
+/// authentication and ACL checks. Writes through the wrapper invalidate what they
+/// change. Writes made around it, e.g. by another instance of the app, show up once
+/// the cached entry expires, so `ttl` is how stale a read can be.
+pub struct CachedDatabase {
+    inner: Arc<dyn DatabaseInterface>,
+    users: CachedUsers,
+    projects: CachedProjects,
+}
+
+impl CachedDatabase {
+    /// Keeps up to `capacity` users and as many projects, each for `ttl`.
+    pub fn new(inner: Arc<dyn DatabaseInterface>, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            users: CachedUsers {
+                inner: inner.clone(),
+                cache: ttl_cache(capacity, ttl),
+            },
+            projects: CachedProjects {
+                inner: inner.clone(),
+                cache: ttl_cache(capacity, ttl),
+                list: ttl_cache(1, ttl),
+            },
+            inner,
+        }
+    }
+
+    /// Applies pending evictions now. Moka otherwise does it in the background, as
+    /// the caches are used.
+    pub async fn run_pending_tasks(&self) {
+        self.users.cache.run_pending_tasks().await;
+        self.projects.cache.run_pending_tasks().await;
+        self.projects.list.run_pending_tasks().await;
+    }
+}
+
+impl DatabaseInterface for CachedDatabase {
+    fn users(&self) -> &dyn UsersRepo {
+        &self.users
+    }
+
+    fn projects(&self) -> &dyn ProjectsRepo {
+        &self.projects
+    }
+
+    fn groups(&self) -> &dyn GroupsRepo {
+        self.inner.groups()
+    }
+
+    fn tickets(&self) -> &dyn TicketsRepo {
+        self.inner.tickets()
+    }
+
+    fn sessions(&self) -> &dyn SessionsRepo {
+        self.inner.sessions()
+    }
+
+    fn invites(&self) -> &dyn InvitesRepo {
+        self.inner.invites()
+    }
+
+    fn security_events(&self) -> &dyn SecurityEventsRepo {
+        self.inner.security_events()
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepo {
+        self.inner.idempotency()
+    }
+
+    fn notifications(&self) -> &dyn NotificationsRepo {
+        self.inner.notifications()
+    }
+
+    fn activity(&self) -> &dyn ActivityRepo {
+        self.inner.activity()
+    }
+
+    fn receipts(&self) -> &dyn ReadReceiptsRepo {
+        self.inner.receipts()
+    }
+
+    fn drafts(&self) -> &dyn DraftsRepo {
+        self.inner.drafts()
+    }
+
+    fn shares(&self) -> &dyn SharesRepo {
+        self.inner.shares()
+    }
+
+    fn preferences(&self) -> &dyn PreferencesRepo {
+        self.inner.preferences()
+    }
+
+    fn milestones(&self) -> &dyn MilestonesRepo {
+        self.inner.milestones()
+    }
+
+    fn comments(&self) -> &dyn CommentsRepo {
+        self.inner.comments()
+    }
+
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo {
+        self.inner.chat_channels()
+    }
+
+    fn outbox(&self) -> &dyn OutboxRepo {
+        self.inner.outbox()
+    }
+
+    fn graph(&self) -> &dyn GraphRepo {
+        self.inner.graph()
+    }
+
+    fn search(&self) -> &dyn SearchService {
+        self.inner.search()
+    }
+
+    fn begin_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.inner.begin_transaction()
+    }
+
+    fn commit_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.inner.commit_transaction()
+    }
+
+    // What the transaction wrote may be cached already
+    fn rollback_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.users.cache.invalidate_all();
+        self.projects.cache.invalidate_all();
+        self.projects.list.invalidate_all();
+        self.inner.rollback_transaction()
+    }
+
+    fn initialize(&self) -> BoxFuture<'_, Result<(), AppError>> {
+        self.inner.initialize()
+    }
+
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>> {
+        self.inner.backend_info()
+    }
+}
+
+pub struct CachedUsers {
+    inner: Arc<dyn DatabaseInterface>,
+    cache: Cache<String, User>,
+}
+
+impl CachedUsers {
+    /// Runs a write, then drops the user it changed.
+    fn write<'a, T: Send + 'a>(
+        &'a self,
+        id: String,
+        call: BoxFuture<'a, Result<T, AppError>>,
+    ) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            let result = call.await;
+            self.cache.invalidate(&id).await;
+            result
+        })
+    }
+}
+
+impl UsersRepo for CachedUsers {
+    fn get_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move {
+            if let Some(user) = self.cache.get(id).await {
+                return Ok(user);
+            }
+            let user = self.inner.users().get_user(id).await?;
+            self.cache.insert(id.to_string(), user.clone()).await;
+            Ok(user)
+        })
+    }
+
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>> {
+        self.inner.users().get_users(ids)
+    }
+
+    fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(user.username.clone(), self.inner.users().create_user(user))
+    }
+
+    fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.users().update_user(id, user))
+    }
+
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.write(user.username.clone(), self.inner.users().upsert_user(user))
+    }
+
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>> {
+        self.write(user.username.clone(), self.inner.users().get_or_create_user(user))
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.users().delete_user(id, hard))
+    }
+
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>> {
+        self.inner.users().list_deleted_users()
+    }
+
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.users().restore_user(id))
+    }
+
+    fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.inner.users().list_users()
+    }
+
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.inner.users().count_users()
+    }
+
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.inner.users().count_deactivated_users()
+    }
+
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            if self.cache.get(id).await.is_some() {
+                return Ok(true);
+            }
+            self.inner.users().exists_user(id).await
+        })
+    }
+
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.inner.users().find_users_by_metadata(key, value)
+    }
+
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.inner.users().find_user_by_api_token(hash)
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.inner.users().find_user_by_email(email)
+    }
+
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.inner.users().find_user_by_external_id(id)
+    }
+}
+
+pub struct CachedProjects {
+    inner: Arc<dyn DatabaseInterface>,
+    cache: Cache<String, Project>,
+    list: Cache<String, Vec<Project>>, // every project, for ACL inheritance
+}
+
+impl CachedProjects {
+    /// Runs a write, then drops the project it changed and the project list.
+    fn write<'a, T: Send + 'a>(
+        &'a self,
+        id: String,
+        call: BoxFuture<'a, Result<T, AppError>>,
+    ) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            let result = call.await;
+            self.cache.invalidate(&id).await;
+            self.list.invalidate_all();
+            result
+        })
+    }
+}
+
+impl ProjectsRepo for CachedProjects {
+    fn get_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Project, AppError>> {
+        Box::pin(async move {
+            if let Some(project) = self.cache.get(id).await {
+                return Ok(project);
+            }
+            let project = self.inner.projects().get_project(id).await?;
+            self.cache.insert(id.to_string(), project.clone()).await;
+            Ok(project)
+        })
+    }
+
+    fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(project.id.to_string(), self.inner.projects().create_project(project))
+    }
+
+    fn update_project<'a>(&'a self, id: &'a str, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.projects().update_project(id, project))
+    }
+
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.projects().delete_project(id, hard))
+    }
+
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>> {
+        self.inner.projects().list_deleted_projects()
+    }
+
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.write(id.to_string(), self.inner.projects().restore_project(id))
+    }
+
+    fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        Box::pin(async move {
+            if let Some(projects) = self.list.get(ALL).await {
+                return Ok(projects);
+            }
+            let projects = self.inner.projects().list_projects().await?;
+            self.list.insert(ALL.to_string(), projects.clone()).await;
+            Ok(projects)
+        })
+    }
+
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.inner.projects().count_projects()
+    }
+
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            if self.cache.get(id).await.is_some() {
+                return Ok(true);
+            }
+            self.inner.projects().exists_project(id).await
+        })
+    }
+
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.inner.projects().list_subprojects(id)
+    }
+}
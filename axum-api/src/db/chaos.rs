@@ -0,0 +1,729 @@
+// Failure-injection wrapper around any database, for resilience tests
+use std::sync::{
+    Arc, Mutex, RwLock,
+    atomic::{AtomicUsize, Ordering},
+};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde_json::Value;
+
+use crate::db::{
+    ActivityFilter, ActivityRepo, AssigneeCount, BackendInfo, Batch, BoxFuture, ChatChannelsRepo, CommentsRepo, DatabaseInterface, Deleted, DraftsRepo, GraphRepo, GroupsRepo, IdempotencyRepo, InvitesRepo, MilestonesRepo, NotificationFilter, NotificationsRepo, OutboxFilter, OutboxRepo, PreferencesRepo, ProjectsRepo, ReadReceiptsRepo, SearchHits, SearchService, SecurityEventFilter,
+    SecurityEventsRepo, SessionsRepo, SharesRepo, TicketCount, TicketDayCount, TicketsRepo, UsersRepo,
+};
+use crate::error::AppError;
+use crate::models::{Activity, ChatChannel, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, Notification, OutboxEntry, Preference, Project, ReadReceipt, SecurityEvent, Session, Ticket, TicketShare, TicketStatus, User};
+
+/// What to inject; rates are shares of calls between 0.0 and 1.0.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    pub latency: Duration, // added to every call
+    pub jitter: Duration,  // random extra latency, up to this much
+    pub error_rate: f64,   // calls failing with an internal error
+    pub conflict_rate: f64, // writes failing with a conflict
+}
+
+pub struct ChaosDatabase {
+    repo: ChaosRepo,
+}
+
+impl ChaosDatabase {
+    pub fn new(inner: Arc<dyn DatabaseInterface>, config: ChaosConfig) -> Self {
+        Self::with_rng(inner, config, StdRng::from_os_rng())
+    }
+
+    /// Same faults on the same calls on every run.
+    pub fn with_seed(inner: Arc<dyn DatabaseInterface>, config: ChaosConfig, seed: u64) -> Self {
+        Self::with_rng(inner, config, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(inner: Arc<dyn DatabaseInterface>, config: ChaosConfig, rng: StdRng) -> Self {
+        Self {
+            repo: ChaosRepo {
+                inner,
+                config: RwLock::new(config),
+                rng: Mutex::new(rng),
+                injected: AtomicUsize::new(0),
+            },
+        }
+    }
+
+    /// Replaces the faults, e.g. to seed data first and break things afterwards.
+    pub fn set_config(&self, config: ChaosConfig) {
+        *self.repo.config.write().unwrap() = config;
+    }
+
+    /// Number of calls failed on purpose so far.
+    pub fn injected_failures(&self) -> usize {
+        self.repo.injected.load(Ordering::Relaxed)
+    }
+}
+
+impl DatabaseInterface for ChaosDatabase {
+    fn users(&self) -> &dyn UsersRepo {
+        &self.repo
+    }
+
+    fn projects(&self) -> &dyn ProjectsRepo {
+        &self.repo
+    }
+
+    fn groups(&self) -> &dyn GroupsRepo {
+        &self.repo
+    }
+
+    fn tickets(&self) -> &dyn TicketsRepo {
+        &self.repo
+    }
+
+    fn sessions(&self) -> &dyn SessionsRepo {
+        &self.repo
+    }
+
+    fn invites(&self) -> &dyn InvitesRepo {
+        &self.repo
+    }
+
+    fn security_events(&self) -> &dyn SecurityEventsRepo {
+        &self.repo
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepo {
+        &self.repo
+    }
+
+    fn notifications(&self) -> &dyn NotificationsRepo {
+        &self.repo
+    }
+
+    fn activity(&self) -> &dyn ActivityRepo {
+        &self.repo
+    }
+
+    fn receipts(&self) -> &dyn ReadReceiptsRepo {
+        &self.repo
+    }
+
+    fn drafts(&self) -> &dyn DraftsRepo {
+        &self.repo
+    }
+
+    fn shares(&self) -> &dyn SharesRepo {
+        &self.repo
+    }
+
+    fn preferences(&self) -> &dyn PreferencesRepo {
+        &self.repo
+    }
+
+    fn milestones(&self) -> &dyn MilestonesRepo {
+        &self.repo
+    }
+
+    fn comments(&self) -> &dyn CommentsRepo {
+        &self.repo
+    }
+
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo {
+        &self.repo
+    }
+
+    fn outbox(&self) -> &dyn OutboxRepo {
+        &self.repo
+    }
+
+    fn graph(&self) -> &dyn GraphRepo {
+        &self.repo
+    }
+
+    fn search(&self) -> &dyn SearchService {
+        &self.repo
+    }
+
+    fn begin_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.begin_transaction()
+    }
+
+    fn commit_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.commit_transaction()
+    }
+
+    fn rollback_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.rollback_transaction()
+    }
+
+    fn initialize(&self) -> BoxFuture<'_, Result<(), AppError>> {
+        self.repo.inner.initialize()
+    }
+
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>> {
+        self.repo.inner.backend_info()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Implements every repo trait by delegating to the wrapped database.
+pub struct ChaosRepo {
+    inner: Arc<dyn DatabaseInterface>,
+    config: RwLock<ChaosConfig>,
+    rng: Mutex<StdRng>,
+    injected: AtomicUsize,
+}
+
+impl ChaosRepo {
+    /// Delays the call and decides whether it reaches the wrapped database at all.
+    fn call<'a, T: Send + 'a>(
+        &'a self,
+        access: Access,
+        call: BoxFuture<'a, Result<T, AppError>>,
+    ) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            let (delay, fault) = {
+                let config = self.config.read().unwrap();
+                let mut rng = self.rng.lock().unwrap();
+                let jitter = config.jitter.mul_f64(rng.random::<f64>());
+                let fault = if rng.random::<f64>() < config.error_rate {
+                    Some(AppError::Internal(anyhow!("Injected database failure")))
+                } else if access == Access::Write && rng.random::<f64>() < config.conflict_rate {
+                    Some(AppError::Conflict("Injected write conflict".to_string()))
+                } else {
+                    None
+                };
+                (config.latency + jitter, fault)
+            };
+
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(fault) = fault {
+                self.injected.fetch_add(1, Ordering::Relaxed);
+                return Err(fault);
+            }
+            call.await
+        })
+    }
+}
+
+impl UsersRepo for ChaosRepo {
+    fn get_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(Access::Read, self.inner.users().get_user(id))
+    }
+
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>> {
+        self.call(Access::Read, self.inner.users().get_users(ids))
+    }
+
+    fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.users().create_user(user))
+    }
+
+    fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.users().update_user(id, user))
+    }
+
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Write, self.inner.users().upsert_user(user))
+    }
+
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>> {
+        self.call(Access::Write, self.inner.users().get_or_create_user(user))
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.users().delete_user(id, hard))
+    }
+
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>> {
+        self.call(Access::Read, self.inner.users().list_deleted_users())
+    }
+
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.users().restore_user(id))
+    }
+
+    fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.call(Access::Read, self.inner.users().list_users())
+    }
+
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.users().count_users())
+    }
+
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.users().count_deactivated_users())
+    }
+
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Read, self.inner.users().exists_user(id))
+    }
+
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.call(Access::Read, self.inner.users().find_users_by_metadata(key, value))
+    }
+
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(Access::Read, self.inner.users().find_user_by_api_token(hash))
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(Access::Read, self.inner.users().find_user_by_email(email))
+    }
+
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(Access::Read, self.inner.users().find_user_by_external_id(id))
+    }
+}
+
+impl ProjectsRepo for ChaosRepo {
+    fn get_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Project, AppError>> {
+        self.call(Access::Read, self.inner.projects().get_project(id))
+    }
+
+    fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.projects().create_project(project))
+    }
+
+    fn update_project<'a>(&'a self, id: &'a str, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.projects().update_project(id, project))
+    }
+
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.projects().delete_project(id, hard))
+    }
+
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>> {
+        self.call(Access::Read, self.inner.projects().list_deleted_projects())
+    }
+
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.projects().restore_project(id))
+    }
+
+    fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(Access::Read, self.inner.projects().list_projects())
+    }
+
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.projects().count_projects())
+    }
+
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Read, self.inner.projects().exists_project(id))
+    }
+
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(Access::Read, self.inner.projects().list_subprojects(id))
+    }
+}
+
+impl GroupsRepo for ChaosRepo {
+    fn get_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Group, AppError>> {
+        self.call(Access::Read, self.inner.groups().get_group(id))
+    }
+
+    fn get_groups<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Group>, AppError>> {
+        self.call(Access::Read, self.inner.groups().get_groups(ids))
+    }
+
+    fn create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.groups().create_group(group))
+    }
+
+    fn update_group<'a>(&'a self, id: &'a str, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.groups().update_group(id, group))
+    }
+
+    fn upsert_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Write, self.inner.groups().upsert_group(group))
+    }
+
+    fn get_or_create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(Group, bool), AppError>> {
+        self.call(Access::Write, self.inner.groups().get_or_create_group(group))
+    }
+
+    fn delete_group<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.groups().delete_group(id, hard))
+    }
+
+    fn list_deleted_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Group>>, AppError>> {
+        self.call(Access::Read, self.inner.groups().list_deleted_groups())
+    }
+
+    fn restore_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.groups().restore_group(id))
+    }
+
+    fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>> {
+        self.call(Access::Read, self.inner.groups().list_groups())
+    }
+
+    fn count_groups<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.groups().count_groups())
+    }
+
+    fn exists_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Read, self.inner.groups().exists_group(id))
+    }
+}
+
+impl TicketsRepo for ChaosRepo {
+    fn get_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Ticket, AppError>> {
+        self.call(Access::Read, self.inner.tickets().get_ticket(id))
+    }
+
+    fn get_tickets<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Ticket>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().get_tickets(ids))
+    }
+
+    fn create_ticket<'a>(&'a self, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.tickets().create_ticket(ticket))
+    }
+
+    fn update_ticket<'a>(&'a self, id: &'a str, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.tickets().update_ticket(id, ticket))
+    }
+
+    fn delete_ticket<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.tickets().delete_ticket(id, hard))
+    }
+
+    fn list_deleted_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Ticket>>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().list_deleted_tickets())
+    }
+
+    fn restore_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.tickets().restore_ticket(id))
+    }
+
+    fn reserve_ticket_ids<'a>(&'a self, count: i64) -> BoxFuture<'a, Result<i64, AppError>> {
+        self.call(Access::Write, self.inner.tickets().reserve_ticket_ids(count))
+    }
+
+    fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().list_tickets())
+    }
+
+    fn list_tickets_fields<'a>(&'a self, fields: &'a [String]) -> BoxFuture<'a, Result<Vec<Value>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().list_tickets_fields(fields))
+    }
+
+    fn get_ticket_fields<'a>(&'a self, id: &'a str, fields: &'a [String]) -> BoxFuture<'a, Result<Value, AppError>> {
+        self.call(Access::Read, self.inner.tickets().get_ticket_fields(id, fields))
+    }
+
+    fn count_tickets<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.tickets().count_tickets())
+    }
+
+    fn exists_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(Access::Read, self.inner.tickets().exists_ticket(id))
+    }
+
+    fn ticket_counts<'a>(&'a self, project: Option<&'a str>) -> BoxFuture<'a, Result<Vec<TicketCount>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().ticket_counts(project))
+    }
+
+    fn ticket_activity<'a>(&'a self, project: &'a str, since: NaiveDate) -> BoxFuture<'a, Result<Vec<TicketDayCount>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().ticket_activity(project, since))
+    }
+
+    fn average_resolution_secs<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Option<f64>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().average_resolution_secs(project))
+    }
+
+    fn assignee_counts<'a>(&'a self, project: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<AssigneeCount>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().assignee_counts(project, limit))
+    }
+
+    fn last_rank<'a>(&'a self, project: Option<&'a str>, status: TicketStatus) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().last_rank(project, status))
+    }
+
+    fn last_assignee<'a>(&'a self, project: &'a str, among: &'a [String]) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        self.call(Access::Read, self.inner.tickets().last_assignee(project, among))
+    }
+}
+
+impl SessionsRepo for ChaosRepo {
+    fn get_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Session, AppError>> {
+        self.call(Access::Read, self.inner.sessions().get_session(id))
+    }
+
+    fn create_session<'a>(&'a self, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.sessions().create_session(session))
+    }
+
+    fn update_session<'a>(&'a self, id: &'a str, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.sessions().update_session(id, session))
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.sessions().delete_session(id))
+    }
+
+    fn list_user_sessions<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<Session>, AppError>> {
+        self.call(Access::Read, self.inner.sessions().list_user_sessions(username))
+    }
+}
+
+impl InvitesRepo for ChaosRepo {
+    fn get_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Invite, AppError>> {
+        self.call(Access::Read, self.inner.invites().get_invite(id))
+    }
+
+    fn create_invite<'a>(&'a self, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.invites().create_invite(invite))
+    }
+
+    fn update_invite<'a>(&'a self, id: &'a str, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.invites().update_invite(id, invite))
+    }
+
+    fn claim_invite<'a>(&'a self, id: &'a str, username: &'a str, at: DateTime<Utc>) -> BoxFuture<'a, Result<Invite, AppError>> {
+        self.call(Access::Write, self.inner.invites().claim_invite(id, username, at))
+    }
+
+    fn delete_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.invites().delete_invite(id))
+    }
+
+    fn list_invites<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Invite>, AppError>> {
+        self.call(Access::Read, self.inner.invites().list_invites())
+    }
+}
+
+impl SecurityEventsRepo for ChaosRepo {
+    fn create_event<'a>(&'a self, event: SecurityEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.security_events().create_event(event))
+    }
+
+    fn list_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<Vec<SecurityEvent>, AppError>> {
+        self.call(Access::Read, self.inner.security_events().list_events(filter))
+    }
+
+    fn count_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.security_events().count_events(filter))
+    }
+}
+
+impl IdempotencyRepo for ChaosRepo {
+    fn get_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<IdempotencyRecord, AppError>> {
+        self.call(Access::Read, self.inner.idempotency().get_record(id))
+    }
+
+    fn create_record<'a>(&'a self, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.idempotency().create_record(record))
+    }
+
+    fn update_record<'a>(&'a self, id: &'a str, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.idempotency().update_record(id, record))
+    }
+
+    fn delete_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.idempotency().delete_record(id))
+    }
+}
+
+impl NotificationsRepo for ChaosRepo {
+    fn get_notification<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Notification, AppError>> {
+        self.call(Access::Read, self.inner.notifications().get_notification(id))
+    }
+
+    fn create_notification<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.notifications().create_notification(notification))
+    }
+
+    fn update_notification<'a>(&'a self, id: &'a str, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.notifications().update_notification(id, notification))
+    }
+
+    fn list_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<Vec<Notification>, AppError>> {
+        self.call(Access::Read, self.inner.notifications().list_notifications(recipient, filter))
+    }
+
+    fn count_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.notifications().count_notifications(recipient, filter))
+    }
+}
+
+impl ActivityRepo for ChaosRepo {
+    fn create_activity<'a>(&'a self, activity: Activity) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.activity().create_activity(activity))
+    }
+
+    fn list_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<Vec<Activity>, AppError>> {
+        self.call(Access::Read, self.inner.activity().list_activity(filter))
+    }
+
+    fn count_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(Access::Read, self.inner.activity().count_activity(filter))
+    }
+}
+
+impl DraftsRepo for ChaosRepo {
+    fn get_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Draft, AppError>> {
+        self.call(Access::Read, self.inner.drafts().get_draft(id))
+    }
+
+    fn put_draft<'a>(&'a self, draft: Draft) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.drafts().put_draft(draft))
+    }
+
+    fn delete_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.drafts().delete_draft(id))
+    }
+}
+
+impl SharesRepo for ChaosRepo {
+    fn create_share<'a>(&'a self, share: TicketShare) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.shares().create_share(share))
+    }
+
+    fn get_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<TicketShare, AppError>> {
+        self.call(Access::Read, self.inner.shares().get_share(id))
+    }
+
+    fn list_shares<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<TicketShare>, AppError>> {
+        self.call(Access::Read, self.inner.shares().list_shares(ticket))
+    }
+
+    fn delete_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.shares().delete_share(id))
+    }
+}
+
+impl PreferencesRepo for ChaosRepo {
+    fn get_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Preference, AppError>> {
+        self.call(Access::Read, self.inner.preferences().get_preference(id))
+    }
+
+    fn put_preference<'a>(&'a self, preference: Preference) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.preferences().put_preference(preference))
+    }
+
+    fn list_preferences<'a>(&'a self, owner: &'a str) -> BoxFuture<'a, Result<Vec<Preference>, AppError>> {
+        self.call(Access::Read, self.inner.preferences().list_preferences(owner))
+    }
+
+    fn delete_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.preferences().delete_preference(id))
+    }
+}
+
+impl ReadReceiptsRepo for ChaosRepo {
+    fn set_receipt<'a>(&'a self, receipt: ReadReceipt) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.receipts().set_receipt(receipt))
+    }
+
+    fn list_receipts_of<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        self.call(Access::Read, self.inner.receipts().list_receipts_of(username))
+    }
+
+    fn list_receipts<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        self.call(Access::Read, self.inner.receipts().list_receipts(ticket))
+    }
+}
+
+impl MilestonesRepo for ChaosRepo {
+    fn get_milestone<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Milestone, AppError>> {
+        self.call(Access::Read, self.inner.milestones().get_milestone(id))
+    }
+
+    fn create_milestone<'a>(&'a self, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.milestones().create_milestone(milestone))
+    }
+
+    fn update_milestone<'a>(&'a self, id: &'a str, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.milestones().update_milestone(id, milestone))
+    }
+
+    fn list_milestones<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Vec<Milestone>, AppError>> {
+        self.call(Access::Read, self.inner.milestones().list_milestones(project))
+    }
+}
+
+impl CommentsRepo for ChaosRepo {
+    fn create_comment<'a>(&'a self, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.comments().create_comment(comment))
+    }
+
+    fn list_comments<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        self.call(Access::Read, self.inner.comments().list_comments(ticket))
+    }
+
+    fn list_comments_by<'a>(&'a self, author: &'a str) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        self.call(Access::Read, self.inner.comments().list_comments_by(author))
+    }
+
+    fn update_comment<'a>(&'a self, id: &'a str, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.comments().update_comment(id, comment))
+    }
+
+    fn find_comment_by_message_id<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<Option<Comment>, AppError>> {
+        self.call(Access::Read, self.inner.comments().find_comment_by_message_id(message_id))
+    }
+}
+
+impl ChatChannelsRepo for ChaosRepo {
+    fn get_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<ChatChannel, AppError>> {
+        self.call(Access::Read, self.inner.chat_channels().get_chat_channel(project))
+    }
+
+    fn create_chat_channel<'a>(&'a self, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.chat_channels().create_chat_channel(channel))
+    }
+
+    fn update_chat_channel<'a>(&'a self, project: &'a str, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.chat_channels().update_chat_channel(project, channel))
+    }
+
+    fn delete_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.chat_channels().delete_chat_channel(project))
+    }
+
+    fn list_chat_channels<'a>(&'a self) -> BoxFuture<'a, Result<Vec<ChatChannel>, AppError>> {
+        self.call(Access::Read, self.inner.chat_channels().list_chat_channels())
+    }
+}
+
+impl OutboxRepo for ChaosRepo {
+    fn get_outbox_entry<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<OutboxEntry, AppError>> {
+        self.call(Access::Read, self.inner.outbox().get_outbox_entry(id))
+    }
+
+    fn create_outbox_entry<'a>(&'a self, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.outbox().create_outbox_entry(entry))
+    }
+
+    fn update_outbox_entry<'a>(&'a self, id: &'a str, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(Access::Write, self.inner.outbox().update_outbox_entry(id, entry))
+    }
+
+    fn list_outbox_entries<'a>(&'a self, filter: &'a OutboxFilter) -> BoxFuture<'a, Result<Vec<OutboxEntry>, AppError>> {
+        self.call(Access::Read, self.inner.outbox().list_outbox_entries(filter))
+    }
+}
+
+impl GraphRepo for ChaosRepo {
+    fn groups_of<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<String>, AppError>> {
+        self.call(Access::Read, self.inner.graph().groups_of(principal))
+    }
+
+    fn owned_projects<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(Access::Read, self.inner.graph().owned_projects(principal))
+    }
+}
+
+impl SearchService for ChaosRepo {
+    fn find<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<SearchHits, AppError>> {
+        self.call(Access::Read, self.inner.search().find(query, limit))
+    }
+}
@@ -0,0 +1,50 @@
+// Deadline of the request being served, so database calls don't outlive it
+use std::future::Future;
+
+use tokio::time::{Instant, timeout_at};
+
+use crate::db::{BoxFuture, guarded::{Guard, GuardedDatabase}};
+use crate::error::AppError;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// When the request has to be answered by. Set in the request's extensions by the
+/// deadline middleware, and for the database calls made while serving it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(pub Instant);
+
+/// Runs a future with `deadline` applying to the database calls it makes. Tasks it
+/// spawns don't inherit it.
+pub async fn scope<F: Future>(deadline: Deadline, future: F) -> F::Output {
+    DEADLINE.scope(deadline.0, future).await
+}
+
+/// The deadline of the calls made right now, if any.
+pub fn current() -> Option<Deadline> {
+    DEADLINE.try_with(|deadline| Deadline(*deadline)).ok()
+}
+
+/// A database whose calls are abandoned once the current deadline has passed.
+pub type DeadlineDatabase = GuardedDatabase<DeadlineGuard>;
+
+/// Fails calls made past the deadline without starting them, and drops those still
+/// running when it passes, which cancels their request to the backend.
+pub struct DeadlineGuard;
+
+impl Guard for DeadlineGuard {
+    fn call<'a, T: Send + 'a>(&'a self, call: BoxFuture<'a, Result<T, AppError>>) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            let Some(Deadline(deadline)) = current() else {
+                return call.await;
+            };
+            if Instant::now() >= deadline {
+                return Err(AppError::DeadlineExceeded("Request deadline passed before the database call".to_string()));
+            }
+            timeout_at(deadline, call)
+                .await
+                .map_err(|_| AppError::DeadlineExceeded("Database call cut off at the request deadline".to_string()))?
+        })
+    }
+}
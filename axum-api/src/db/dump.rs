@@ -0,0 +1,218 @@
+// Whole-database dumps, for backups and moves between backends
+//
+// A dump is a sequence of records in an order they can be restored in: principals,
+// then projects (parents first), then what refers to them, closed by an `End` record
+// counting the others. Edges aren't records of their own: memberships, owners and
+// parents are fields of the groups and projects, and backends keeping edges derive
+// them from those on import.
+//
+// Sessions, idempotency records, notifications, security events and the outbox are
+// left out: they describe the running instance, not its data.
+//
+// Users, groups, projects and tickets carry their schema version, dumps of older
+// versions are upgraded as they are read.
+
+use std::{collections::HashSet, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::{
+    db::{DatabaseInterface, versioned},
+    error::AppError,
+    models::{ChatChannel, Comment, Group, Invite, Milestone, Project, Ticket, User},
+};
+
+// Records exported ahead of a slow reader
+const EXPORT_BUFFER: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Record {
+    User(#[serde(with = "versioned")] User),
+    Group(#[serde(with = "versioned")] Group),
+    Project(#[serde(with = "versioned")] Project),
+    Milestone(Milestone),
+    Ticket(#[serde(with = "versioned")] Ticket),
+    Comment(Comment),
+    ChatChannel(ChatChannel),
+    Invite(Invite),
+    End { records: usize },
+}
+
+impl Record {
+    /// The `type` of the record in a dump.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Record::User(_) => "user",
+            Record::Group(_) => "group",
+            Record::Project(_) => "project",
+            Record::Milestone(_) => "milestone",
+            Record::Ticket(_) => "ticket",
+            Record::Comment(_) => "comment",
+            Record::ChatChannel(_) => "chat_channel",
+            Record::Invite(_) => "invite",
+            Record::End { .. } => "end",
+        }
+    }
+
+    /// Parses line `line` of a dump, `None` when it is blank.
+    pub fn from_line(text: &[u8], line: usize) -> Result<Option<Self>, AppError> {
+        if text.trim_ascii().is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_slice(text)
+            .map(Some)
+            .map_err(|e| AppError::Validation(format!("Line {}: {}", line, e)))
+    }
+}
+
+/// Reads the database into a dump, in the background. A failure is the last item
+/// received, there is no `End` record then.
+pub fn export(db: Arc<dyn DatabaseInterface>) -> mpsc::Receiver<Result<Record, AppError>> {
+    let (records, receiver) = mpsc::channel(EXPORT_BUFFER);
+    tokio::spawn(async move {
+        if let Err(e) = write_records(db.as_ref(), &records).await {
+            // Fails only when the reader is gone
+            let _ = records.send(Err(e)).await;
+        }
+    });
+    receiver
+}
+
+/// Sends records to the reader of an export, counting them.
+struct Writer<'a> {
+    out: &'a mpsc::Sender<Result<Record, AppError>>,
+    records: usize,
+}
+
+impl Writer<'_> {
+    async fn send(&mut self, record: Record) -> Result<(), AppError> {
+        self.records += 1;
+        self.out
+            .send(Ok(record))
+            .await
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Export abandoned by its reader")))
+    }
+}
+
+async fn write_records(db: &dyn DatabaseInterface, out: &mpsc::Sender<Result<Record, AppError>>) -> Result<(), AppError> {
+    let mut writer = Writer { out, records: 0 };
+    for user in db.users().list_users().await? {
+        writer.send(Record::User(user)).await?;
+    }
+    for group in db.groups().list_groups().await? {
+        writer.send(Record::Group(group)).await?;
+    }
+    let projects = parents_first(db.projects().list_projects().await?);
+    for project in &projects {
+        writer.send(Record::Project(project.clone())).await?;
+    }
+    for project in &projects {
+        for milestone in db.milestones().list_milestones(&project.id.to_string()).await? {
+            writer.send(Record::Milestone(milestone)).await?;
+        }
+    }
+    let tickets = db.tickets().list_tickets().await?;
+    for ticket in &tickets {
+        writer.send(Record::Ticket(ticket.clone())).await?;
+    }
+    for ticket in &tickets {
+        for comment in db.comments().list_comments(ticket.id).await? {
+            writer.send(Record::Comment(comment)).await?;
+        }
+    }
+    for channel in db.chat_channels().list_chat_channels().await? {
+        writer.send(Record::ChatChannel(channel)).await?;
+    }
+    for invite in db.invites().list_invites().await? {
+        writer.send(Record::Invite(invite)).await?;
+    }
+    let records = writer.records;
+    writer.send(Record::End { records }).await
+}
+
+/// Orders projects so that each comes after its parent. Projects whose parent is
+/// missing come last.
+fn parents_first(mut projects: Vec<Project>) -> Vec<Project> {
+    let mut ordered = Vec::with_capacity(projects.len());
+    let mut placed = HashSet::new();
+    loop {
+        let (ready, waiting): (Vec<Project>, Vec<Project>) = projects
+            .into_iter()
+            .partition(|p| p.parent_id.as_ref().is_none_or(|parent| placed.contains(parent)));
+        if ready.is_empty() {
+            ordered.extend(waiting);
+            return ordered;
+        }
+        placed.extend(ready.iter().map(|p| p.id.to_string()));
+        ordered.extend(ready);
+        projects = waiting;
+    }
+}
+
+/// Restores a dump, record by record, into an empty database.
+pub struct Importer<'a> {
+    db: &'a dyn DatabaseInterface,
+    records: usize,
+    ended: bool,
+}
+
+impl<'a> Importer<'a> {
+    /// Fails with `Conflict` unless the database is empty, a dump isn't merged into
+    /// existing data.
+    pub async fn new(db: &'a dyn DatabaseInterface) -> Result<Self, AppError> {
+        let empty = db.users().count_users().await? == 0
+            && db.groups().list_groups().await?.is_empty()
+            && db.projects().list_projects().await?.is_empty()
+            && db.tickets().list_tickets().await?.is_empty();
+        if !empty {
+            return Err(AppError::Conflict("Dumps are only imported into an empty database".to_string()));
+        }
+        Ok(Self {
+            db,
+            records: 0,
+            ended: false,
+        })
+    }
+
+    pub async fn import(&mut self, record: Record) -> Result<(), AppError> {
+        if self.ended {
+            return Err(AppError::Validation("Records after the end of the dump".to_string()));
+        }
+        match record {
+            Record::User(user) => self.db.users().create_user(user).await?,
+            Record::Group(group) => self.db.groups().create_group(group).await?,
+            Record::Project(project) => self.db.projects().create_project(project).await?,
+            Record::Milestone(milestone) => self.db.milestones().create_milestone(milestone).await?,
+            Record::Ticket(ticket) => self.db.tickets().create_ticket(ticket).await?,
+            Record::Comment(comment) => self.db.comments().create_comment(comment).await?,
+            Record::ChatChannel(channel) => self.db.chat_channels().create_chat_channel(channel).await?,
+            Record::Invite(invite) => self.db.invites().create_invite(invite).await?,
+            Record::End { records } => {
+                if records != self.records {
+                    return Err(AppError::Validation(format!(
+                        "Dump ends after {} records, {} were read",
+                        records, self.records
+                    )));
+                }
+                self.ended = true;
+                return Ok(());
+            }
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Number of records imported. Fails if the dump had no `End` record, when it
+    /// was cut short.
+    pub fn finish(self) -> Result<usize, AppError> {
+        if !self.ended {
+            return Err(AppError::Validation(format!(
+                "Dump is incomplete, it stops after {} records",
+                self.records
+            )));
+        }
+        Ok(self.records)
+    }
+}
@@ -0,0 +1,665 @@
+// Wrapper running every repo call through a guard, e.g. a concurrency limit
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::db::{
+    ActivityFilter, ActivityRepo, AssigneeCount, BackendInfo, Batch, BoxFuture, ChatChannelsRepo, CommentsRepo, DatabaseInterface, Deleted, DraftsRepo, GraphRepo, GroupsRepo, IdempotencyRepo, InvitesRepo, MilestonesRepo, NotificationFilter, NotificationsRepo, OutboxFilter, OutboxRepo, PreferencesRepo, ProjectsRepo, ReadReceiptsRepo, SearchHits, SearchService, SecurityEventFilter,
+    SecurityEventsRepo, SessionsRepo, SharesRepo, TicketCount, TicketDayCount, TicketsRepo, UsersRepo,
+};
+use crate::error::AppError;
+use crate::models::{Activity, ChatChannel, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, Notification, OutboxEntry, Preference, Project, ReadReceipt, SecurityEvent, Session, Ticket, TicketShare, TicketStatus, User};
+
+/// Decides how, and whether, a call reaches the wrapped database.
+pub trait Guard: Send + Sync + 'static {
+    fn call<'a, T: Send + 'a>(&'a self, call: BoxFuture<'a, Result<T, AppError>>) -> BoxFuture<'a, Result<T, AppError>>;
+}
+
+/// A database whose repo calls all go through `guard`. Transactions, initialization
+/// and backend details aren't guarded.
+pub struct GuardedDatabase<G: Guard> {
+    repo: GuardedRepo<G>,
+}
+
+impl<G: Guard> GuardedDatabase<G> {
+    pub fn new(inner: Arc<dyn DatabaseInterface>, guard: Arc<G>) -> Self {
+        Self {
+            repo: GuardedRepo { inner, guard },
+        }
+    }
+
+    pub fn guard(&self) -> &G {
+        &self.repo.guard
+    }
+}
+
+impl<G: Guard> DatabaseInterface for GuardedDatabase<G> {
+    fn users(&self) -> &dyn UsersRepo {
+        &self.repo
+    }
+
+    fn projects(&self) -> &dyn ProjectsRepo {
+        &self.repo
+    }
+
+    fn groups(&self) -> &dyn GroupsRepo {
+        &self.repo
+    }
+
+    fn tickets(&self) -> &dyn TicketsRepo {
+        &self.repo
+    }
+
+    fn sessions(&self) -> &dyn SessionsRepo {
+        &self.repo
+    }
+
+    fn invites(&self) -> &dyn InvitesRepo {
+        &self.repo
+    }
+
+    fn security_events(&self) -> &dyn SecurityEventsRepo {
+        &self.repo
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepo {
+        &self.repo
+    }
+
+    fn notifications(&self) -> &dyn NotificationsRepo {
+        &self.repo
+    }
+
+    fn activity(&self) -> &dyn ActivityRepo {
+        &self.repo
+    }
+
+    fn receipts(&self) -> &dyn ReadReceiptsRepo {
+        &self.repo
+    }
+
+    fn drafts(&self) -> &dyn DraftsRepo {
+        &self.repo
+    }
+
+    fn shares(&self) -> &dyn SharesRepo {
+        &self.repo
+    }
+
+    fn preferences(&self) -> &dyn PreferencesRepo {
+        &self.repo
+    }
+
+    fn milestones(&self) -> &dyn MilestonesRepo {
+        &self.repo
+    }
+
+    fn comments(&self) -> &dyn CommentsRepo {
+        &self.repo
+    }
+
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo {
+        &self.repo
+    }
+
+    fn outbox(&self) -> &dyn OutboxRepo {
+        &self.repo
+    }
+
+    fn graph(&self) -> &dyn GraphRepo {
+        &self.repo
+    }
+
+    fn search(&self) -> &dyn SearchService {
+        &self.repo
+    }
+
+    fn begin_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.begin_transaction()
+    }
+
+    fn commit_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.commit_transaction()
+    }
+
+    fn rollback_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
+        self.repo.inner.rollback_transaction()
+    }
+
+    fn initialize(&self) -> BoxFuture<'_, Result<(), AppError>> {
+        self.repo.inner.initialize()
+    }
+
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>> {
+        self.repo.inner.backend_info()
+    }
+}
+
+/// Implements every repo trait by delegating to the wrapped database through the guard.
+pub struct GuardedRepo<G: Guard> {
+    inner: Arc<dyn DatabaseInterface>,
+    guard: Arc<G>,
+}
+
+impl<G: Guard> GuardedRepo<G> {
+    fn call<'a, T: Send + 'a>(&'a self, call: BoxFuture<'a, Result<T, AppError>>) -> BoxFuture<'a, Result<T, AppError>> {
+        self.guard.call(call)
+    }
+}
+
+impl<G: Guard> UsersRepo for GuardedRepo<G> {
+    fn get_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(self.inner.users().get_user(id))
+    }
+
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>> {
+        self.call(self.inner.users().get_users(ids))
+    }
+
+    fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.users().create_user(user))
+    }
+
+    fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.users().update_user(id, user))
+    }
+
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.users().upsert_user(user))
+    }
+
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>> {
+        self.call(self.inner.users().get_or_create_user(user))
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.users().delete_user(id, hard))
+    }
+
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>> {
+        self.call(self.inner.users().list_deleted_users())
+    }
+
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.users().restore_user(id))
+    }
+
+    fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.call(self.inner.users().list_users())
+    }
+
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.users().count_users())
+    }
+
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.users().count_deactivated_users())
+    }
+
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.users().exists_user(id))
+    }
+
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        self.call(self.inner.users().find_users_by_metadata(key, value))
+    }
+
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(self.inner.users().find_user_by_api_token(hash))
+    }
+
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(self.inner.users().find_user_by_email(email))
+    }
+
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        self.call(self.inner.users().find_user_by_external_id(id))
+    }
+}
+
+impl<G: Guard> ProjectsRepo for GuardedRepo<G> {
+    fn get_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Project, AppError>> {
+        self.call(self.inner.projects().get_project(id))
+    }
+
+    fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.projects().create_project(project))
+    }
+
+    fn update_project<'a>(&'a self, id: &'a str, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.projects().update_project(id, project))
+    }
+
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.projects().delete_project(id, hard))
+    }
+
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>> {
+        self.call(self.inner.projects().list_deleted_projects())
+    }
+
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.projects().restore_project(id))
+    }
+
+    fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(self.inner.projects().list_projects())
+    }
+
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.projects().count_projects())
+    }
+
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.projects().exists_project(id))
+    }
+
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(self.inner.projects().list_subprojects(id))
+    }
+}
+
+impl<G: Guard> GroupsRepo for GuardedRepo<G> {
+    fn get_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Group, AppError>> {
+        self.call(self.inner.groups().get_group(id))
+    }
+
+    fn get_groups<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Group>, AppError>> {
+        self.call(self.inner.groups().get_groups(ids))
+    }
+
+    fn create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.groups().create_group(group))
+    }
+
+    fn update_group<'a>(&'a self, id: &'a str, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.groups().update_group(id, group))
+    }
+
+    fn upsert_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.groups().upsert_group(group))
+    }
+
+    fn get_or_create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(Group, bool), AppError>> {
+        self.call(self.inner.groups().get_or_create_group(group))
+    }
+
+    fn delete_group<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.groups().delete_group(id, hard))
+    }
+
+    fn list_deleted_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Group>>, AppError>> {
+        self.call(self.inner.groups().list_deleted_groups())
+    }
+
+    fn restore_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.groups().restore_group(id))
+    }
+
+    fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>> {
+        self.call(self.inner.groups().list_groups())
+    }
+
+    fn count_groups<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.groups().count_groups())
+    }
+
+    fn exists_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.groups().exists_group(id))
+    }
+}
+
+impl<G: Guard> TicketsRepo for GuardedRepo<G> {
+    fn get_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Ticket, AppError>> {
+        self.call(self.inner.tickets().get_ticket(id))
+    }
+
+    fn get_tickets<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Ticket>, AppError>> {
+        self.call(self.inner.tickets().get_tickets(ids))
+    }
+
+    fn create_ticket<'a>(&'a self, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.tickets().create_ticket(ticket))
+    }
+
+    fn update_ticket<'a>(&'a self, id: &'a str, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.tickets().update_ticket(id, ticket))
+    }
+
+    fn delete_ticket<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.tickets().delete_ticket(id, hard))
+    }
+
+    fn list_deleted_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Ticket>>, AppError>> {
+        self.call(self.inner.tickets().list_deleted_tickets())
+    }
+
+    fn restore_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.tickets().restore_ticket(id))
+    }
+
+    fn reserve_ticket_ids<'a>(&'a self, count: i64) -> BoxFuture<'a, Result<i64, AppError>> {
+        self.call(self.inner.tickets().reserve_ticket_ids(count))
+    }
+
+    fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>> {
+        self.call(self.inner.tickets().list_tickets())
+    }
+
+    fn list_tickets_fields<'a>(&'a self, fields: &'a [String]) -> BoxFuture<'a, Result<Vec<Value>, AppError>> {
+        self.call(self.inner.tickets().list_tickets_fields(fields))
+    }
+
+    fn get_ticket_fields<'a>(&'a self, id: &'a str, fields: &'a [String]) -> BoxFuture<'a, Result<Value, AppError>> {
+        self.call(self.inner.tickets().get_ticket_fields(id, fields))
+    }
+
+    fn count_tickets<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.tickets().count_tickets())
+    }
+
+    fn exists_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        self.call(self.inner.tickets().exists_ticket(id))
+    }
+
+    fn ticket_counts<'a>(&'a self, project: Option<&'a str>) -> BoxFuture<'a, Result<Vec<TicketCount>, AppError>> {
+        self.call(self.inner.tickets().ticket_counts(project))
+    }
+
+    fn ticket_activity<'a>(&'a self, project: &'a str, since: NaiveDate) -> BoxFuture<'a, Result<Vec<TicketDayCount>, AppError>> {
+        self.call(self.inner.tickets().ticket_activity(project, since))
+    }
+
+    fn average_resolution_secs<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Option<f64>, AppError>> {
+        self.call(self.inner.tickets().average_resolution_secs(project))
+    }
+
+    fn assignee_counts<'a>(&'a self, project: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<AssigneeCount>, AppError>> {
+        self.call(self.inner.tickets().assignee_counts(project, limit))
+    }
+
+    fn last_rank<'a>(&'a self, project: Option<&'a str>, status: TicketStatus) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        self.call(self.inner.tickets().last_rank(project, status))
+    }
+
+    fn last_assignee<'a>(&'a self, project: &'a str, among: &'a [String]) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        self.call(self.inner.tickets().last_assignee(project, among))
+    }
+}
+
+impl<G: Guard> SessionsRepo for GuardedRepo<G> {
+    fn get_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Session, AppError>> {
+        self.call(self.inner.sessions().get_session(id))
+    }
+
+    fn create_session<'a>(&'a self, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.sessions().create_session(session))
+    }
+
+    fn update_session<'a>(&'a self, id: &'a str, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.sessions().update_session(id, session))
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.sessions().delete_session(id))
+    }
+
+    fn list_user_sessions<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<Session>, AppError>> {
+        self.call(self.inner.sessions().list_user_sessions(username))
+    }
+}
+
+impl<G: Guard> InvitesRepo for GuardedRepo<G> {
+    fn get_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Invite, AppError>> {
+        self.call(self.inner.invites().get_invite(id))
+    }
+
+    fn create_invite<'a>(&'a self, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.invites().create_invite(invite))
+    }
+
+    fn update_invite<'a>(&'a self, id: &'a str, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.invites().update_invite(id, invite))
+    }
+
+    fn claim_invite<'a>(&'a self, id: &'a str, username: &'a str, at: DateTime<Utc>) -> BoxFuture<'a, Result<Invite, AppError>> {
+        self.call(self.inner.invites().claim_invite(id, username, at))
+    }
+
+    fn delete_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.invites().delete_invite(id))
+    }
+
+    fn list_invites<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Invite>, AppError>> {
+        self.call(self.inner.invites().list_invites())
+    }
+}
+
+impl<G: Guard> SecurityEventsRepo for GuardedRepo<G> {
+    fn create_event<'a>(&'a self, event: SecurityEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.security_events().create_event(event))
+    }
+
+    fn list_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<Vec<SecurityEvent>, AppError>> {
+        self.call(self.inner.security_events().list_events(filter))
+    }
+
+    fn count_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.security_events().count_events(filter))
+    }
+}
+
+impl<G: Guard> IdempotencyRepo for GuardedRepo<G> {
+    fn get_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<IdempotencyRecord, AppError>> {
+        self.call(self.inner.idempotency().get_record(id))
+    }
+
+    fn create_record<'a>(&'a self, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.idempotency().create_record(record))
+    }
+
+    fn update_record<'a>(&'a self, id: &'a str, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.idempotency().update_record(id, record))
+    }
+
+    fn delete_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.idempotency().delete_record(id))
+    }
+}
+
+impl<G: Guard> NotificationsRepo for GuardedRepo<G> {
+    fn get_notification<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Notification, AppError>> {
+        self.call(self.inner.notifications().get_notification(id))
+    }
+
+    fn create_notification<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.notifications().create_notification(notification))
+    }
+
+    fn update_notification<'a>(&'a self, id: &'a str, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.notifications().update_notification(id, notification))
+    }
+
+    fn list_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<Vec<Notification>, AppError>> {
+        self.call(self.inner.notifications().list_notifications(recipient, filter))
+    }
+
+    fn count_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.notifications().count_notifications(recipient, filter))
+    }
+}
+
+impl<G: Guard> ActivityRepo for GuardedRepo<G> {
+    fn create_activity<'a>(&'a self, activity: Activity) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.activity().create_activity(activity))
+    }
+
+    fn list_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<Vec<Activity>, AppError>> {
+        self.call(self.inner.activity().list_activity(filter))
+    }
+
+    fn count_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        self.call(self.inner.activity().count_activity(filter))
+    }
+}
+
+impl<G: Guard> DraftsRepo for GuardedRepo<G> {
+    fn get_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Draft, AppError>> {
+        self.call(self.inner.drafts().get_draft(id))
+    }
+
+    fn put_draft<'a>(&'a self, draft: Draft) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.drafts().put_draft(draft))
+    }
+
+    fn delete_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.drafts().delete_draft(id))
+    }
+}
+
+impl<G: Guard> SharesRepo for GuardedRepo<G> {
+    fn create_share<'a>(&'a self, share: TicketShare) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.shares().create_share(share))
+    }
+
+    fn get_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<TicketShare, AppError>> {
+        self.call(self.inner.shares().get_share(id))
+    }
+
+    fn list_shares<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<TicketShare>, AppError>> {
+        self.call(self.inner.shares().list_shares(ticket))
+    }
+
+    fn delete_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.shares().delete_share(id))
+    }
+}
+
+impl<G: Guard> PreferencesRepo for GuardedRepo<G> {
+    fn get_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Preference, AppError>> {
+        self.call(self.inner.preferences().get_preference(id))
+    }
+
+    fn put_preference<'a>(&'a self, preference: Preference) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.preferences().put_preference(preference))
+    }
+
+    fn list_preferences<'a>(&'a self, owner: &'a str) -> BoxFuture<'a, Result<Vec<Preference>, AppError>> {
+        self.call(self.inner.preferences().list_preferences(owner))
+    }
+
+    fn delete_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.preferences().delete_preference(id))
+    }
+}
+
+impl<G: Guard> ReadReceiptsRepo for GuardedRepo<G> {
+    fn set_receipt<'a>(&'a self, receipt: ReadReceipt) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.receipts().set_receipt(receipt))
+    }
+
+    fn list_receipts_of<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        self.call(self.inner.receipts().list_receipts_of(username))
+    }
+
+    fn list_receipts<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        self.call(self.inner.receipts().list_receipts(ticket))
+    }
+}
+
+impl<G: Guard> MilestonesRepo for GuardedRepo<G> {
+    fn get_milestone<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Milestone, AppError>> {
+        self.call(self.inner.milestones().get_milestone(id))
+    }
+
+    fn create_milestone<'a>(&'a self, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.milestones().create_milestone(milestone))
+    }
+
+    fn update_milestone<'a>(&'a self, id: &'a str, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.milestones().update_milestone(id, milestone))
+    }
+
+    fn list_milestones<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Vec<Milestone>, AppError>> {
+        self.call(self.inner.milestones().list_milestones(project))
+    }
+}
+
+impl<G: Guard> CommentsRepo for GuardedRepo<G> {
+    fn create_comment<'a>(&'a self, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.comments().create_comment(comment))
+    }
+
+    fn list_comments<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        self.call(self.inner.comments().list_comments(ticket))
+    }
+
+    fn list_comments_by<'a>(&'a self, author: &'a str) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        self.call(self.inner.comments().list_comments_by(author))
+    }
+
+    fn update_comment<'a>(&'a self, id: &'a str, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.comments().update_comment(id, comment))
+    }
+
+    fn find_comment_by_message_id<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<Option<Comment>, AppError>> {
+        self.call(self.inner.comments().find_comment_by_message_id(message_id))
+    }
+}
+
+impl<G: Guard> ChatChannelsRepo for GuardedRepo<G> {
+    fn get_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<ChatChannel, AppError>> {
+        self.call(self.inner.chat_channels().get_chat_channel(project))
+    }
+
+    fn create_chat_channel<'a>(&'a self, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.chat_channels().create_chat_channel(channel))
+    }
+
+    fn update_chat_channel<'a>(&'a self, project: &'a str, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.chat_channels().update_chat_channel(project, channel))
+    }
+
+    fn delete_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.chat_channels().delete_chat_channel(project))
+    }
+
+    fn list_chat_channels<'a>(&'a self) -> BoxFuture<'a, Result<Vec<ChatChannel>, AppError>> {
+        self.call(self.inner.chat_channels().list_chat_channels())
+    }
+}
+
+impl<G: Guard> OutboxRepo for GuardedRepo<G> {
+    fn get_outbox_entry<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<OutboxEntry, AppError>> {
+        self.call(self.inner.outbox().get_outbox_entry(id))
+    }
+
+    fn create_outbox_entry<'a>(&'a self, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.outbox().create_outbox_entry(entry))
+    }
+
+    fn update_outbox_entry<'a>(&'a self, id: &'a str, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        self.call(self.inner.outbox().update_outbox_entry(id, entry))
+    }
+
+    fn list_outbox_entries<'a>(&'a self, filter: &'a OutboxFilter) -> BoxFuture<'a, Result<Vec<OutboxEntry>, AppError>> {
+        self.call(self.inner.outbox().list_outbox_entries(filter))
+    }
+}
+
+impl<G: Guard> GraphRepo for GuardedRepo<G> {
+    fn groups_of<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<String>, AppError>> {
+        self.call(self.inner.graph().groups_of(principal))
+    }
+
+    fn owned_projects<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        self.call(self.inner.graph().owned_projects(principal))
+    }
+}
+
+impl<G: Guard> SearchService for GuardedRepo<G> {
+    fn find<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<SearchHits, AppError>> {
+        self.call(self.inner.search().find(query, limit))
+    }
+}
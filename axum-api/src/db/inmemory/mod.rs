@@ -1,18 +1,261 @@
 // Example implementation structure for in-memory database
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry};
+use std::sync::{
+    Arc, RwLock,
+    atomic::{AtomicI64, Ordering},
+};
+use std::time::{Duration, Instant};
 
-use crate::db::{BoxFuture, DatabaseInterface, GroupsRepo, ProjectsRepo, TicketsRepo, UsersRepo};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::db::{
+    ActivityFilter, ActivityRepo, AssigneeCount, BackendInfo, Batch, BoxFuture, ChatChannelsRepo, CommentsRepo, DatabaseInterface, Deleted, DraftsRepo, GraphRepo, GroupsRepo, IdempotencyRepo, InvitesRepo, MilestonesRepo, NotificationFilter, NotificationsRepo, OutboxFilter, OutboxRepo, PreferencesRepo, ProjectsRepo, ReadReceiptsRepo, Scored, SearchHits, SearchService, SecurityEventFilter,
+    SecurityEventsRepo, SessionsRepo, SharesRepo, TicketCount, TicketDayCount, TicketsRepo, UsersRepo, keep_fields,
+};
 use crate::error::AppError;
-use crate::models::Ticket;
+use crate::utils::relevance::{score, words};
+use crate::models::{Ticket, TicketStatus};
+
+use crate::models::{Activity, ChatChannel, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, Notification, OutboxEntry, Preference, Project, ReadReceipt, SecurityEvent, Session, TicketShare, User};
+
+/// Bounds on what the in-memory database keeps, so a public demo can't be made to grow
+/// forever. Both apply to every collection separately; `None` means unbounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InMemoryLimits {
+    pub max_entities: Option<usize>,
+    pub ttl: Option<Duration>, // entries untouched for this long are evicted
+}
+
+/// How often the sweeper started by `InMemoryDatabase::spawn_sweeper` runs in the server.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Entities of one collection, keyed by id, with the limits enforced. Soft-deleted
+/// ones wait in `trash`, out of the limits' reach, and keep their id taken.
+struct Table<T> {
+    entity: &'static str, // for error messages
+    rows: RwLock<HashMap<String, (T, Instant)>>,
+    trash: RwLock<HashMap<String, (T, DateTime<Utc>)>>, // locked after `rows` when both are
+    limits: InMemoryLimits,
+}
+
+impl<T: Clone> Table<T> {
+    fn new(entity: &'static str, limits: InMemoryLimits) -> Self {
+        Self {
+            entity,
+            rows: RwLock::new(HashMap::new()),
+            trash: RwLock::new(HashMap::new()),
+            limits,
+        }
+    }
+
+    fn is_live(&self, written: &Instant) -> bool {
+        self.limits.ttl.is_none_or(|ttl| written.elapsed() < ttl)
+    }
+
+    fn not_found(&self, id: &str) -> AppError {
+        AppError::NotFound(format!("{} {} not found", self.entity, id))
+    }
+
+    fn get(&self, id: &str) -> Result<T, AppError> {
+        let rows = self.rows.read().unwrap();
+        rows.get(id)
+            .filter(|(_, written)| self.is_live(written))
+            .map(|(value, _)| value.clone())
+            .ok_or_else(|| self.not_found(id))
+    }
+
+    fn insert(&self, id: String, value: T) -> Result<(), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        if self.limits.ttl.is_some() {
+            rows.retain(|_, (_, written)| self.is_live(written));
+        }
+        if rows.contains_key(&id) {
+            return Err(AppError::Conflict(format!("{} {} already exists", self.entity, id)));
+        }
+        if self.trash.read().unwrap().contains_key(&id) {
+            return Err(self.deleted(&id));
+        }
+        if self.limits.max_entities.is_some_and(|max| rows.len() >= max) {
+            return Err(AppError::StorageFull(format!(
+                "{} limit of the in-memory database reached",
+                self.entity
+            )));
+        }
+        rows.insert(id, (value, Instant::now()));
+        Ok(())
+    }
+
+    /// Inserts an entity or replaces the live or deleted one with its id. Returns whether
+    /// it was inserted.
+    fn upsert(&self, id: String, value: T) -> Result<bool, AppError> {
+        let mut rows = self.rows.write().unwrap();
+        if self.limits.ttl.is_some() {
+            rows.retain(|_, (_, written)| self.is_live(written));
+        }
+        let mut trash = self.trash.write().unwrap();
+        let inserted = !rows.contains_key(&id) && !trash.contains_key(&id);
+        if inserted && self.limits.max_entities.is_some_and(|max| rows.len() >= max) {
+            return Err(AppError::StorageFull(format!(
+                "{} limit of the in-memory database reached",
+                self.entity
+            )));
+        }
+        trash.remove(&id);
+        rows.insert(id, (value, Instant::now()));
+        Ok(inserted)
+    }
+
+    /// The live entity with the id, or `value` inserted under it. Returns whether it
+    /// was inserted.
+    fn get_or_insert(&self, id: String, value: T) -> Result<(T, bool), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        if self.limits.ttl.is_some() {
+            rows.retain(|_, (_, written)| self.is_live(written));
+        }
+        if self.trash.read().unwrap().contains_key(&id) {
+            return Err(self.deleted(&id));
+        }
+        let full = self.limits.max_entities.is_some_and(|max| rows.len() >= max);
+        match rows.entry(id) {
+            Entry::Occupied(row) => Ok((row.get().0.clone(), false)),
+            Entry::Vacant(_) if full => Err(AppError::StorageFull(format!(
+                "{} limit of the in-memory database reached",
+                self.entity
+            ))),
+            Entry::Vacant(row) => {
+                row.insert((value.clone(), Instant::now()));
+                Ok((value, true))
+            }
+        }
+    }
+
+    /// Replaces an entity, which also restarts its TTL.
+    fn update(&self, id: &str, value: T) -> Result<(), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        match rows.get_mut(id) {
+            Some(row) if self.is_live(&row.1) => {
+                *row = (value, Instant::now());
+                Ok(())
+            }
+            _ => Err(self.not_found(id)),
+        }
+    }
+
+    /// Replaces the live entity with what `change` makes of it, under one lock so that
+    /// nothing changes it in between. Returns the new value.
+    fn modify(&self, id: &str, change: impl FnOnce(&T) -> Result<T, AppError>) -> Result<T, AppError> {
+        let mut rows = self.rows.write().unwrap();
+        match rows.get_mut(id) {
+            Some(row) if self.is_live(&row.1) => {
+                let value = change(&row.0)?;
+                *row = (value.clone(), Instant::now());
+                Ok(value)
+            }
+            _ => Err(self.not_found(id)),
+        }
+    }
+
+    /// Removes the live or deleted entity with the id for good.
+    fn remove(&self, id: &str) -> Result<(), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        match rows.remove(id) {
+            Some((_, written)) if self.is_live(&written) => Ok(()),
+            _ => match self.trash.write().unwrap().remove(id) {
+                Some(_) => Ok(()),
+                None => Err(self.not_found(id)),
+            },
+        }
+    }
+
+    /// Moves the live entity with the id to the trash.
+    fn soft_remove(&self, id: &str) -> Result<(), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        match rows.remove(id) {
+            Some((value, written)) if self.is_live(&written) => {
+                self.trash.write().unwrap().insert(id.to_string(), (value, Utc::now()));
+                Ok(())
+            }
+            _ => Err(self.not_found(id)),
+        }
+    }
 
-use crate::models::{Group, Project, User};
+    /// Moves the entity with the id out of the trash, which restarts its TTL.
+    fn restore(&self, id: &str) -> Result<(), AppError> {
+        let mut rows = self.rows.write().unwrap();
+        match self.trash.write().unwrap().remove(id) {
+            Some((value, _)) => {
+                rows.insert(id.to_string(), (value, Instant::now()));
+                Ok(())
+            }
+            None => Err(AppError::NotFound(format!("Deleted {} {} not found", self.entity.to_lowercase(), id))),
+        }
+    }
+
+    /// The entities in the trash, oldest deletion first.
+    fn trashed(&self) -> Vec<Deleted<T>> {
+        let trash = self.trash.read().unwrap();
+        let mut deleted: Vec<Deleted<T>> = trash
+            .values()
+            .map(|(entity, deleted_at)| Deleted {
+                entity: entity.clone(),
+                deleted_at: *deleted_at,
+            })
+            .collect();
+        deleted.sort_by_key(|d| d.deleted_at);
+        deleted
+    }
+
+    fn deleted(&self, id: &str) -> AppError {
+        AppError::Conflict(format!("{} {} is deleted, restore it instead", self.entity, id))
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        let rows = self.rows.read().unwrap();
+        rows.get(id).is_some_and(|(_, written)| self.is_live(written))
+    }
+
+    fn len(&self) -> usize {
+        let rows = self.rows.read().unwrap();
+        rows.values().filter(|(_, written)| self.is_live(written)).count()
+    }
+
+    fn values(&self) -> Vec<T> {
+        let rows = self.rows.read().unwrap();
+        rows.values()
+            .filter(|(_, written)| self.is_live(written))
+            .map(|(value, _)| value.clone())
+            .collect()
+    }
+
+    /// Drops the entities `keep` turns down. Returns how many were dropped.
+    fn retain(&self, keep: impl Fn(&T) -> bool) -> usize {
+        let mut rows = self.rows.write().unwrap();
+        let before = rows.len();
+        rows.retain(|_, (value, _)| keep(value));
+        before - rows.len()
+    }
+}
 
 pub struct InMemoryDatabase {
     users_repo: InMemoryUsersRepo,
     projects_repo: InMemoryProjectsRepo,
     groups_repo: InMemoryGroupsRepo,
     tickets_repo: InMemoryTicketsRepo,
+    sessions_repo: InMemorySessionsRepo,
+    invites_repo: InMemoryInvitesRepo,
+    security_events_repo: InMemorySecurityEventsRepo,
+    idempotency_repo: InMemoryIdempotencyRepo,
+    notifications_repo: InMemoryNotificationsRepo,
+    activity_repo: InMemoryActivityRepo,
+    receipts_repo: InMemoryReadReceiptsRepo,
+    drafts_repo: InMemoryDraftsRepo,
+    shares_repo: InMemorySharesRepo,
+    preferences_repo: InMemoryPreferencesRepo,
+    milestones_repo: InMemoryMilestonesRepo,
+    comments_repo: InMemoryCommentsRepo,
+    chat_channels_repo: InMemoryChatChannelsRepo,
+    outbox_repo: InMemoryOutboxRepo,
 }
 
 impl Default for InMemoryDatabase {
@@ -23,13 +266,59 @@ impl Default for InMemoryDatabase {
 
 impl InMemoryDatabase {
     pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
         Self {
-            users_repo: InMemoryUsersRepo::new(),
-            projects_repo: InMemoryProjectsRepo::new(),
-            groups_repo: InMemoryGroupsRepo::new(),
-            tickets_repo: InMemoryTicketsRepo::new(),
+            users_repo: InMemoryUsersRepo::with_limits(limits),
+            projects_repo: InMemoryProjectsRepo::with_limits(limits),
+            groups_repo: InMemoryGroupsRepo::with_limits(limits),
+            tickets_repo: InMemoryTicketsRepo::with_limits(limits),
+            sessions_repo: InMemorySessionsRepo::with_limits(limits),
+            invites_repo: InMemoryInvitesRepo::with_limits(limits),
+            security_events_repo: InMemorySecurityEventsRepo::with_limits(limits),
+            idempotency_repo: InMemoryIdempotencyRepo::with_limits(limits),
+            notifications_repo: InMemoryNotificationsRepo::with_limits(limits),
+            activity_repo: InMemoryActivityRepo::with_limits(limits),
+            receipts_repo: InMemoryReadReceiptsRepo::with_limits(limits),
+            drafts_repo: InMemoryDraftsRepo::with_limits(limits),
+            shares_repo: InMemorySharesRepo::with_limits(limits),
+            preferences_repo: InMemoryPreferencesRepo::with_limits(limits),
+            milestones_repo: InMemoryMilestonesRepo::with_limits(limits),
+            comments_repo: InMemoryCommentsRepo::with_limits(limits),
+            chat_channels_repo: InMemoryChatChannelsRepo::with_limits(limits),
+            outbox_repo: InMemoryOutboxRepo::with_limits(limits),
         }
     }
+
+    /// Drops the sessions, idempotency records, drafts and ticket shares expired at `now`, as the TTL
+    /// indexes of the ArangoDB backend do. Returns how many were dropped.
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> usize {
+        self.sessions_repo.sessions.retain(|s| s.expires_at >= now)
+            + self.idempotency_repo.records.retain(|r| r.expires_at > now)
+            + self.drafts_repo.drafts.retain(|d| d.expires_at > now)
+            + self.shares_repo.shares.retain(|s| s.expires_at > now)
+    }
+
+    /// Purges expired entities every `every` until the database is dropped.
+    pub fn spawn_sweeper(self: &Arc<Self>, every: Duration) {
+        let db = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(every);
+            ticks.tick().await; // the first tick is immediate, nothing has expired yet
+            loop {
+                ticks.tick().await;
+                let Some(db) = db.upgrade() else {
+                    break;
+                };
+                let purged = db.purge_expired(Utc::now());
+                if purged > 0 {
+                    log::debug!("Purged {} expired entities from the in-memory database", purged);
+                }
+            }
+        });
+    }
 }
 
 impl DatabaseInterface for InMemoryDatabase {
@@ -49,6 +338,70 @@ impl DatabaseInterface for InMemoryDatabase {
         &self.tickets_repo
     }
 
+    fn sessions(&self) -> &dyn SessionsRepo {
+        &self.sessions_repo
+    }
+
+    fn invites(&self) -> &dyn InvitesRepo {
+        &self.invites_repo
+    }
+
+    fn security_events(&self) -> &dyn SecurityEventsRepo {
+        &self.security_events_repo
+    }
+
+    fn idempotency(&self) -> &dyn IdempotencyRepo {
+        &self.idempotency_repo
+    }
+
+    fn notifications(&self) -> &dyn NotificationsRepo {
+        &self.notifications_repo
+    }
+
+    fn activity(&self) -> &dyn ActivityRepo {
+        &self.activity_repo
+    }
+
+    fn receipts(&self) -> &dyn ReadReceiptsRepo {
+        &self.receipts_repo
+    }
+
+    fn drafts(&self) -> &dyn DraftsRepo {
+        &self.drafts_repo
+    }
+
+    fn shares(&self) -> &dyn SharesRepo {
+        &self.shares_repo
+    }
+
+    fn preferences(&self) -> &dyn PreferencesRepo {
+        &self.preferences_repo
+    }
+
+    fn milestones(&self) -> &dyn MilestonesRepo {
+        &self.milestones_repo
+    }
+
+    fn comments(&self) -> &dyn CommentsRepo {
+        &self.comments_repo
+    }
+
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo {
+        &self.chat_channels_repo
+    }
+
+    fn outbox(&self) -> &dyn OutboxRepo {
+        &self.outbox_repo
+    }
+
+    fn graph(&self) -> &dyn GraphRepo {
+        self
+    }
+
+    fn search(&self) -> &dyn SearchService {
+        self
+    }
+
     fn begin_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
             // No-op for in-memory implementation
@@ -74,11 +427,120 @@ impl DatabaseInterface for InMemoryDatabase {
         // do nothing, succesfully
         Box::pin(async move { Ok(()) })
     }
+
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>> {
+        Box::pin(async move {
+            Ok(BackendInfo {
+                backend: "inmemory".to_string(),
+                database: None,
+                version: None,
+            })
+        })
+    }
+}
+
+// In-memory graph traversals, over the groups and projects tables
+impl GraphRepo for InMemoryDatabase {
+    fn groups_of<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<String>, AppError>> {
+        Box::pin(async move {
+            let groups = self.groups_repo.groups.values();
+            let mut found: BTreeSet<String> = BTreeSet::new();
+            let mut members = vec![principal.to_string()];
+            // Breadth-first, a group already found is not visited again should groups nest in a loop
+            while !members.is_empty() {
+                members = groups
+                    .iter()
+                    .filter(|g| g.gid != principal && !found.contains(&g.gid))
+                    .filter(|g| g.principals.iter().any(|p| members.contains(p)))
+                    .map(|g| g.gid.clone())
+                    .collect();
+                found.extend(members.iter().cloned());
+            }
+            Ok(found.into_iter().collect())
+        })
+    }
+
+    fn owned_projects<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        Box::pin(async move {
+            let mut owners = self.groups_of(principal).await?;
+            owners.push(principal.to_string());
+            let mut found = BTreeMap::new();
+            let owned = self.projects_repo.projects.values().into_iter();
+            for project in owned.filter(|p| p.owner.as_ref().is_some_and(|owner| owners.contains(owner))) {
+                for sub in self.projects_repo.list_subprojects(&project.id.to_string()).await? {
+                    found.insert(sub.id, sub);
+                }
+                found.insert(project.id, project);
+            }
+            Ok(found.into_values().collect())
+        })
+    }
+}
+
+/// The entities matching, best first and then by key, up to `limit`.
+fn ranked<T, K: Ord>(
+    entities: Vec<T>,
+    limit: usize,
+    score_of: impl Fn(&T) -> Option<f64>,
+    key: impl Fn(&T) -> K,
+) -> Vec<Scored<T>> {
+    let mut hits: Vec<Scored<T>> = entities
+        .into_iter()
+        .filter_map(|entity| score_of(&entity).map(|score| Scored { entity, score }))
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| key(&a.entity).cmp(&key(&b.entity))));
+    hits.truncate(limit);
+    hits
+}
+
+// In-memory search, scoring every live entity of the tables
+impl SearchService for InMemoryDatabase {
+    fn find<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<SearchHits, AppError>> {
+        Box::pin(async move {
+            let terms = words(query);
+            if terms.is_empty() {
+                return Ok(SearchHits::default());
+            }
+            let tickets = ranked(
+                self.tickets_repo.tickets.values(),
+                limit,
+                |t| score(&terms, &[(&t.title, 3.0), (&t.description, 1.0), (&t.id.to_string(), 3.0)]),
+                |t| t.id,
+            );
+            let projects = ranked(
+                self.projects_repo.projects.values(),
+                limit,
+                |p| {
+                    let prefixes: Vec<&str> = p.tickets.iter().map(|g| g.prefix.as_str()).collect();
+                    score(&terms, &[(&p.id.to_string(), 3.0), (&prefixes.join(" "), 2.0)])
+                },
+                |p| p.id,
+            );
+            let users = ranked(
+                self.users_repo.users.values(),
+                limit,
+                |u| score(&terms, &[(&u.username, 3.0), (&u.personal.name, 3.0), (&u.personal.job_title, 1.0)]),
+                |u| u.username.clone(),
+            );
+            let groups = ranked(
+                self.groups_repo.groups.values(),
+                limit,
+                |g| score(&terms, &[(&g.gid, 3.0), (&g.name, 3.0)]),
+                |g| g.gid.clone(),
+            );
+            Ok(SearchHits {
+                tickets,
+                projects,
+                users,
+                groups,
+            })
+        })
+    }
 }
 
 // In-memory Users Repository
 pub struct InMemoryUsersRepo {
-    users: RwLock<HashMap<String, User>>,
+    users: Table<User>,
 }
 
 impl Default for InMemoryUsersRepo {
@@ -89,67 +551,153 @@ impl Default for InMemoryUsersRepo {
 
 impl InMemoryUsersRepo {
     pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
         Self {
-            users: RwLock::new(HashMap::new()),
+            users: Table::new("User", limits),
         }
     }
+
+    /// The in-memory stand-in for the unique indexes on emails and external ids, which
+    /// deleted users are still in.
+    fn check_unique(&self, user: &User) -> Result<(), AppError> {
+        let deleted = self.users.trashed().into_iter().map(|d| d.entity);
+        let users: Vec<User> = self.users.values().into_iter().chain(deleted).collect();
+        for other in users.iter().filter(|u| u.username != user.username) {
+            if user.email.is_some() && other.email == user.email {
+                return Err(AppError::Conflict("Email is taken".to_string()));
+            }
+            if let Some(id) = user.external_ids.iter().find(|id| other.external_ids.contains(id)) {
+                return Err(AppError::Conflict(format!("External id {} is taken", id)));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl UsersRepo for InMemoryUsersRepo {
     fn get_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move { self.users.get(id) })
+    }
+
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>> {
+        Box::pin(async move { Ok(Batch::lookup(ids, |id| self.users.get(id).ok())) })
+    }
+
+    fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let users = self.users.read().unwrap();
-            users
-                .get(id)
-                .cloned()
-                .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))
+            self.check_unique(&user)?;
+            self.users.insert(user.username.clone(), user)
         })
     }
 
-    fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+    fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            self.check_unique(&user)?;
+            self.users.update(id, user)
+        })
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let mut users = self.users.write().unwrap();
-            let id = user.username.clone();
-            if users.contains_key(&id.to_string()) {
-                return Err(AppError::Conflict(format!("User {} already exists", id)));
+            if hard {
+                self.users.remove(id)
+            } else {
+                self.users.soft_remove(id)
             }
-            users.insert(id.to_string(), user);
-            Ok(())
         })
     }
 
-    fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>> {
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>> {
+        Box::pin(async move { Ok(self.users.trashed()) })
+    }
+
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.users.restore(id) })
+    }
+
+    fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+        Box::pin(async move { Ok(self.users.values()) })
+    }
+
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move { Ok(self.users.len()) })
+    }
+
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move { Ok(self.users.values().iter().filter(|u| u.deactivated).count()) })
+    }
+
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.users.contains(id)) })
+    }
+
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
         Box::pin(async move {
-            let mut users = self.users.write().unwrap();
-            if !users.contains_key(id) {
-                return Err(AppError::NotFound(format!("User {} not found", id)));
+            let mut users: Vec<User> = self
+                .users
+                .values()
+                .into_iter()
+                .filter(|u| u.metadata.get(key).is_some_and(|v| value.is_none_or(|value| v == value)))
+                .collect();
+            users.sort_by(|a, b| a.username.cmp(&b.username));
+            Ok(users)
+        })
+    }
+
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
+        Box::pin(async move {
+            self.users
+                .values()
+                .into_iter()
+                .find(|u| u.api_tokens.iter().any(|t| t.hash == hash))
+                .ok_or_else(|| AppError::NotFound("API token not found".to_string()))
+        })
+    }
+
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move {
+            self.check_unique(&user)?;
+            self.users.upsert(user.username.clone(), user)
+        })
+    }
+
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>> {
+        Box::pin(async move {
+            if let Ok(existing) = self.users.get(&user.username) {
+                return Ok((existing, false));
             }
-            users.insert(id.to_string(), user);
-            Ok(())
+            self.check_unique(&user)?;
+            self.users.get_or_insert(user.username.clone(), user)
         })
     }
 
-    fn delete_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
         Box::pin(async move {
-            let mut users = self.users.write().unwrap();
-            users
-                .remove(id)
-                .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))?;
-            Ok(())
+            self.users
+                .values()
+                .into_iter()
+                .find(|u| u.email.as_deref() == Some(email))
+                .ok_or_else(|| AppError::NotFound(format!("No user with email {}", email)))
         })
     }
 
-    fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>> {
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>> {
         Box::pin(async move {
-            let users = self.users.read().unwrap();
-            Ok(users.values().cloned().collect())
+            self.users
+                .values()
+                .into_iter()
+                .find(|u| u.external_ids.iter().any(|e| e == id))
+                .ok_or_else(|| AppError::NotFound(format!("No user with external id {}", id)))
         })
     }
 }
 
 // In-memory Projects Repository
 pub struct InMemoryProjectsRepo {
-    projects: RwLock<HashMap<String, Project>>,
+    projects: Table<Project>,
 }
 
 impl Default for InMemoryProjectsRepo {
@@ -160,33 +708,23 @@ impl Default for InMemoryProjectsRepo {
 
 impl InMemoryProjectsRepo {
     pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
         Self {
-            projects: RwLock::new(HashMap::new()),
+            projects: Table::new("Project", limits),
         }
     }
 }
 
 impl ProjectsRepo for InMemoryProjectsRepo {
     fn get_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Project, AppError>> {
-        Box::pin(async move {
-            let projects = self.projects.read().unwrap();
-            projects
-                .get(id)
-                .cloned()
-                .ok_or_else(|| AppError::NotFound(format!("Project {} not found", id)))
-        })
+        Box::pin(async move { self.projects.get(id) })
     }
 
     fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>> {
-        Box::pin(async move {
-            let mut projects = self.projects.write().unwrap();
-            let id = project.id;
-            if projects.contains_key(&id.to_string()) {
-                return Err(AppError::Conflict(format!("Project {} already exists", id)));
-            }
-            projects.insert(id.to_string(), project);
-            Ok(())
-        })
+        Box::pin(async move { self.projects.insert(project.id.to_string(), project) })
     }
 
     fn update_project<'a>(
@@ -194,37 +732,64 @@ impl ProjectsRepo for InMemoryProjectsRepo {
         id: &'a str,
         project: Project,
     ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.projects.update(id, project) })
+    }
+
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let mut projects = self.projects.write().unwrap();
-            if !projects.contains_key(id) {
-                return Err(AppError::NotFound(format!("Project {} not found", id)));
+            if hard {
+                self.projects.remove(id)
+            } else {
+                self.projects.soft_remove(id)
             }
-            projects.insert(id.to_string(), project);
-            Ok(())
         })
     }
 
-    fn delete_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
-        Box::pin(async move {
-            let mut projects = self.projects.write().unwrap();
-            projects
-                .remove(id)
-                .ok_or_else(|| AppError::NotFound(format!("Project {} not found", id)))?;
-            Ok(())
-        })
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>> {
+        Box::pin(async move { Ok(self.projects.trashed()) })
+    }
+
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.projects.restore(id) })
     }
 
     fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
+        Box::pin(async move { Ok(self.projects.values()) })
+    }
+
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move { Ok(self.projects.len()) })
+    }
+
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.projects.contains(id)) })
+    }
+
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>> {
         Box::pin(async move {
-            let projects = self.projects.read().unwrap();
-            Ok(projects.values().cloned().collect())
+            let mut projects = self.projects.values();
+            projects.sort_by_key(|p| p.id);
+            let mut found: Vec<Project> = Vec::new();
+            let mut parents = vec![id.to_string()];
+            // Breadth-first, a project already found is not visited again should parents loop
+            while !parents.is_empty() {
+                let children: Vec<Project> = projects
+                    .iter()
+                    .filter(|p| p.parent_id.as_ref().is_some_and(|parent| parents.contains(parent)))
+                    .filter(|p| p.id.to_string() != id && !found.iter().any(|f| f.id == p.id))
+                    .cloned()
+                    .collect();
+                parents = children.iter().map(|p| p.id.to_string()).collect();
+                found.extend(children);
+            }
+            Ok(found)
         })
     }
 }
 
 // In-memory Groups Repository
 pub struct InMemoryGroupsRepo {
-    groups: RwLock<HashMap<String, Group>>,
+    groups: Table<Group>,
 }
 
 impl Default for InMemoryGroupsRepo {
@@ -235,33 +800,27 @@ impl Default for InMemoryGroupsRepo {
 
 impl InMemoryGroupsRepo {
     pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
         Self {
-            groups: RwLock::new(HashMap::new()),
+            groups: Table::new("Group", limits),
         }
     }
 }
 
 impl GroupsRepo for InMemoryGroupsRepo {
     fn get_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Group, AppError>> {
-        Box::pin(async move {
-            let groups = self.groups.read().unwrap();
-            groups
-                .get(id)
-                .cloned()
-                .ok_or_else(|| AppError::NotFound(format!("Group {} not found", id)))
-        })
+        Box::pin(async move { self.groups.get(id) })
+    }
+
+    fn get_groups<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Group>, AppError>> {
+        Box::pin(async move { Ok(Batch::lookup(ids, |id| self.groups.get(id).ok())) })
     }
 
     fn create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(), AppError>> {
-        Box::pin(async move {
-            let mut groups = self.groups.write().unwrap();
-            let id = group.gid.to_string();
-            if groups.contains_key(&id) {
-                return Err(AppError::Conflict(format!("Group {} already exists", id)));
-            }
-            groups.insert(id.to_string(), group);
-            Ok(())
-        })
+        Box::pin(async move { self.groups.insert(group.gid.clone(), group) })
     }
 
     fn update_group<'a>(
@@ -269,37 +828,52 @@ impl GroupsRepo for InMemoryGroupsRepo {
         id: &'a str,
         group: Group,
     ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.groups.update(id, group) })
+    }
+
+    fn upsert_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { self.groups.upsert(group.gid.clone(), group) })
+    }
+
+    fn get_or_create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(Group, bool), AppError>> {
+        Box::pin(async move { self.groups.get_or_insert(group.gid.clone(), group) })
+    }
+
+    fn delete_group<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let mut groups = self.groups.write().unwrap();
-            if !groups.contains_key(id) {
-                return Err(AppError::NotFound(format!("Group {} not found", id)));
+            if hard {
+                self.groups.remove(id)
+            } else {
+                self.groups.soft_remove(id)
             }
-            groups.insert(id.to_string(), group);
-            Ok(())
         })
     }
 
-    fn delete_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
-        Box::pin(async move {
-            let mut groups = self.groups.write().unwrap();
-            groups
-                .remove(id)
-                .ok_or_else(|| AppError::NotFound(format!("Group {} not found", id)))?;
-            Ok(())
-        })
+    fn list_deleted_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Group>>, AppError>> {
+        Box::pin(async move { Ok(self.groups.trashed()) })
+    }
+
+    fn restore_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.groups.restore(id) })
     }
 
     fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>> {
-        Box::pin(async move {
-            let groups = self.groups.read().unwrap();
-            Ok(groups.values().cloned().collect())
-        })
+        Box::pin(async move { Ok(self.groups.values()) })
+    }
+
+    fn count_groups<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move { Ok(self.groups.len()) })
+    }
+
+    fn exists_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.groups.contains(id)) })
     }
 }
 
 // In-memory Tickets Repository
 pub struct InMemoryTicketsRepo {
-    tickets: RwLock<HashMap<String, Ticket>>,
+    tickets: Table<Ticket>,
+    last_id: AtomicI64, // the highest id reserved or created
 }
 
 impl Default for InMemoryTicketsRepo {
@@ -310,64 +884,1004 @@ impl Default for InMemoryTicketsRepo {
 
 impl InMemoryTicketsRepo {
     pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
         Self {
-            tickets: RwLock::new(HashMap::new()),
+            tickets: Table::new("Ticket", limits),
+            last_id: AtomicI64::new(0),
         }
     }
 }
 
 impl TicketsRepo for InMemoryTicketsRepo {
     fn get_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Ticket, AppError>> {
-        Box::pin(async move {
-            let tickets = self.tickets.read().unwrap();
-            tickets
-                .get(id)
-                .cloned()
-                .ok_or_else(|| AppError::NotFound(format!("Ticket {} not found", id)))
-        })
+        Box::pin(async move { self.tickets.get(id) })
+    }
+
+    fn get_tickets<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Ticket>, AppError>> {
+        Box::pin(async move { Ok(Batch::lookup(ids, |id| self.tickets.get(id).ok())) })
     }
 
     fn create_ticket<'a>(&'a self, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let mut tickets = self.tickets.write().unwrap();
             let id = ticket.id;
-            if tickets.contains_key(&id.to_string()) {
-                return Err(AppError::Conflict(format!("Ticket {} already exists", id)));
-            }
-            tickets.insert(id.to_string(), ticket);
+            self.tickets.insert(id.to_string(), ticket)?;
+            self.last_id.fetch_max(id, Ordering::SeqCst);
             Ok(())
         })
     }
 
+    fn reserve_ticket_ids<'a>(&'a self, count: i64) -> BoxFuture<'a, Result<i64, AppError>> {
+        Box::pin(async move { Ok(self.last_id.fetch_add(count, Ordering::SeqCst) + 1) })
+    }
+
     fn update_ticket<'a>(
         &'a self,
         id: &'a str,
         ticket: Ticket,
     ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.tickets.update(id, ticket) })
+    }
+
+    fn delete_ticket<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>> {
         Box::pin(async move {
-            let mut tickets = self.tickets.write().unwrap();
-            if !tickets.contains_key(id) {
-                return Err(AppError::NotFound(format!("Ticket {} not found", id)));
+            if hard {
+                self.tickets.remove(id)
+            } else {
+                self.tickets.soft_remove(id)
             }
-            tickets.insert(id.to_string(), ticket);
-            Ok(())
         })
     }
 
-    fn delete_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+    fn list_deleted_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Ticket>>, AppError>> {
+        Box::pin(async move { Ok(self.tickets.trashed()) })
+    }
+
+    fn restore_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.tickets.restore(id) })
+    }
+
+    fn list_tickets_fields<'a>(
+        &'a self,
+        fields: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Value>, AppError>> {
         Box::pin(async move {
-            let mut tickets = self.tickets.write().unwrap();
-            tickets
-                .remove(id)
-                .ok_or_else(|| AppError::NotFound(format!("Ticket {} not found", id)))?;
-            Ok(())
+            self.tickets
+                .values()
+                .into_iter()
+                .map(|t| Ok(keep_fields(serde_json::to_value(t)?, fields)))
+                .collect()
+        })
+    }
+
+    fn get_ticket_fields<'a>(
+        &'a self,
+        id: &'a str,
+        fields: &'a [String],
+    ) -> BoxFuture<'a, Result<Value, AppError>> {
+        Box::pin(async move {
+            let ticket = self.get_ticket(id).await?;
+            Ok(keep_fields(serde_json::to_value(ticket)?, fields))
         })
     }
 
     fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>> {
+        Box::pin(async move { Ok(self.tickets.values()) })
+    }
+
+    fn count_tickets<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move { Ok(self.tickets.len()) })
+    }
+
+    fn exists_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.tickets.contains(id)) })
+    }
+
+    fn ticket_counts<'a>(
+        &'a self,
+        project: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<TicketCount>, AppError>> {
+        Box::pin(async move {
+            let mut counts: BTreeMap<(Option<String>, TicketStatus, u8), usize> = BTreeMap::new();
+            for ticket in self.tickets.values() {
+                if project.is_some_and(|p| ticket.project.as_deref() != Some(p)) {
+                    continue;
+                }
+                *counts
+                    .entry((ticket.project, ticket.status, ticket.severity.level))
+                    .or_default() += 1;
+            }
+            Ok(counts
+                .into_iter()
+                .map(|((project, status, severity), count)| TicketCount {
+                    project,
+                    status,
+                    severity,
+                    count,
+                })
+                .collect())
+        })
+    }
+
+    fn ticket_activity<'a>(
+        &'a self,
+        project: &'a str,
+        since: NaiveDate,
+    ) -> BoxFuture<'a, Result<Vec<TicketDayCount>, AppError>> {
+        Box::pin(async move {
+            let mut days: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+            for ticket in self.tickets.values() {
+                if ticket.project.as_deref() != Some(project) {
+                    continue;
+                }
+                let opened = ticket.creation_date.date_naive();
+                if opened >= since {
+                    days.entry(opened).or_default().0 += 1;
+                }
+                if let Some(resolved) = ticket.resolved_at.map(|at| at.date_naive())
+                    && resolved >= since
+                {
+                    days.entry(resolved).or_default().1 += 1;
+                }
+            }
+            Ok(days
+                .into_iter()
+                .map(|(day, (opened, resolved))| TicketDayCount {
+                    day,
+                    opened,
+                    resolved,
+                })
+                .collect())
+        })
+    }
+
+    fn average_resolution_secs<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Option<f64>, AppError>> {
+        Box::pin(async move {
+            let durations: Vec<f64> = self
+                .tickets
+                .values()
+                .into_iter()
+                .filter(|t| t.project.as_deref() == Some(project))
+                .filter_map(|t| t.resolved_at.map(|at| (at - t.creation_date).num_seconds() as f64))
+                .collect();
+            if durations.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(durations.iter().sum::<f64>() / durations.len() as f64))
+        })
+    }
+
+    fn assignee_counts<'a>(
+        &'a self,
+        project: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<AssigneeCount>, AppError>> {
+        Box::pin(async move {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for ticket in self.tickets.values() {
+                if ticket.project.as_deref() == Some(project) && !ticket.assigned_to.is_empty() {
+                    *counts.entry(ticket.assigned_to).or_default() += 1;
+                }
+            }
+            let mut counts: Vec<AssigneeCount> = counts
+                .into_iter()
+                .map(|(assignee, count)| AssigneeCount { assignee, count })
+                .collect();
+            counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.assignee.cmp(&b.assignee)));
+            counts.truncate(limit);
+            Ok(counts)
+        })
+    }
+
+    fn last_rank<'a>(
+        &'a self,
+        project: Option<&'a str>,
+        status: TicketStatus,
+    ) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        Box::pin(async move {
+            Ok(self
+                .tickets
+                .values()
+                .into_iter()
+                .filter(|t| t.project.as_deref() == project && t.status == status && !t.rank.is_empty())
+                .map(|t| t.rank)
+                .max())
+        })
+    }
+
+    fn last_assignee<'a>(
+        &'a self,
+        project: &'a str,
+        among: &'a [String],
+    ) -> BoxFuture<'a, Result<Option<String>, AppError>> {
+        Box::pin(async move {
+            Ok(self
+                .tickets
+                .values()
+                .into_iter()
+                .filter(|t| t.project.as_deref() == Some(project) && among.contains(&t.assigned_to))
+                .max_by_key(|t| t.id)
+                .map(|t| t.assigned_to))
+        })
+    }
+}
+
+
+// In-memory Sessions Repository
+pub struct InMemorySessionsRepo {
+    sessions: Table<Session>,
+}
+
+impl Default for InMemorySessionsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemorySessionsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            sessions: Table::new("Session", limits),
+        }
+    }
+}
+
+impl SessionsRepo for InMemorySessionsRepo {
+    fn get_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Session, AppError>> {
+        Box::pin(async move { self.sessions.get(id) })
+    }
+
+    fn create_session<'a>(&'a self, session: Session) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.sessions.insert(session.id.clone(), session) })
+    }
+
+    fn update_session<'a>(
+        &'a self,
+        id: &'a str,
+        session: Session,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.sessions.update(id, session) })
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.sessions.remove(id) })
+    }
+
+    fn list_user_sessions<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Session>, AppError>> {
+        Box::pin(async move {
+            Ok(self
+                .sessions
+                .values()
+                .into_iter()
+                .filter(|s| s.username == username)
+                .collect())
+        })
+    }
+}
+
+// In-memory Invites Repository
+pub struct InMemoryInvitesRepo {
+    invites: Table<Invite>,
+}
+
+impl Default for InMemoryInvitesRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryInvitesRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            invites: Table::new("Invite", limits),
+        }
+    }
+}
+
+impl InvitesRepo for InMemoryInvitesRepo {
+    fn get_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Invite, AppError>> {
+        Box::pin(async move { self.invites.get(id) })
+    }
+
+    fn create_invite<'a>(&'a self, invite: Invite) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.invites.insert(invite.id.clone(), invite) })
+    }
+
+    fn update_invite<'a>(
+        &'a self,
+        id: &'a str,
+        invite: Invite,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.invites.update(id, invite) })
+    }
+
+    fn claim_invite<'a>(&'a self, id: &'a str, username: &'a str, at: DateTime<Utc>) -> BoxFuture<'a, Result<Invite, AppError>> {
+        Box::pin(async move {
+            self.invites.modify(id, |invite| match invite.used_by {
+                Some(_) => Err(AppError::Conflict(format!("Invite {} already used", id))),
+                None => Ok(Invite {
+                    used_by: Some(username.to_string()),
+                    used_at: Some(at),
+                    ..invite.clone()
+                }),
+            })
+        })
+    }
+
+    fn delete_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.invites.remove(id) })
+    }
+
+    fn list_invites<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Invite>, AppError>> {
+        Box::pin(async move { Ok(self.invites.values()) })
+    }
+}
+
+// In-memory Security Events Repository
+// An audit log must not reject writes, so when full it drops the oldest events instead.
+pub struct InMemorySecurityEventsRepo {
+    events: RwLock<Vec<SecurityEvent>>,
+    limits: InMemoryLimits,
+}
+
+impl Default for InMemorySecurityEventsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemorySecurityEventsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+            limits,
+        }
+    }
+}
+
+impl SecurityEventsRepo for InMemorySecurityEventsRepo {
+    fn create_event<'a>(&'a self, event: SecurityEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut events = self.events.write().unwrap();
+            if let Some(ttl) = self.limits.ttl.and_then(|ttl| chrono::Duration::from_std(ttl).ok()) {
+                let cutoff = Utc::now() - ttl;
+                events.retain(|e| e.created_at >= cutoff);
+            }
+            if let Some(max) = self.limits.max_entities {
+                let excess = (events.len() + 1).saturating_sub(max).min(events.len());
+                events.drain(..excess);
+            }
+            events.push(event);
+            Ok(())
+        })
+    }
+
+    fn list_events<'a>(
+        &'a self,
+        filter: &'a SecurityEventFilter,
+    ) -> BoxFuture<'a, Result<Vec<SecurityEvent>, AppError>> {
+        Box::pin(async move {
+            let events = self.events.read().unwrap();
+            Ok(events
+                .iter()
+                .rev()
+                .filter(|e| filter.matches(e))
+                .take(filter.limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn count_events<'a>(
+        &'a self,
+        filter: &'a SecurityEventFilter,
+    ) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let filter = SecurityEventFilter {
+                cursor: None,
+                ..filter.clone()
+            };
+            let events = self.events.read().unwrap();
+            Ok(events.iter().filter(|e| filter.matches(e)).count())
+        })
+    }
+}
+
+// In-memory Idempotency Repository
+pub struct InMemoryIdempotencyRepo {
+    records: Table<IdempotencyRecord>,
+}
+
+impl Default for InMemoryIdempotencyRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryIdempotencyRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            records: Table::new("Idempotency record", limits),
+        }
+    }
+}
+
+impl IdempotencyRepo for InMemoryIdempotencyRepo {
+    fn get_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<IdempotencyRecord, AppError>> {
+        Box::pin(async move { self.records.get(id) })
+    }
+
+    fn create_record<'a>(&'a self, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.records.insert(record.id.clone(), record) })
+    }
+
+    fn update_record<'a>(
+        &'a self,
+        id: &'a str,
+        record: IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.records.update(id, record) })
+    }
+
+    fn delete_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.records.remove(id) })
+    }
+}
+
+// In-memory Notifications Repository
+pub struct InMemoryNotificationsRepo {
+    notifications: Table<Notification>,
+}
+
+impl Default for InMemoryNotificationsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryNotificationsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            notifications: Table::new("Notification", limits),
+        }
+    }
+}
+
+impl NotificationsRepo for InMemoryNotificationsRepo {
+    fn get_notification<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Notification, AppError>> {
+        Box::pin(async move { self.notifications.get(id) })
+    }
+
+    fn create_notification<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.notifications.insert(notification.id.clone(), notification) })
+    }
+
+    fn update_notification<'a>(
+        &'a self,
+        id: &'a str,
+        notification: Notification,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.notifications.update(id, notification) })
+    }
+
+    fn list_notifications<'a>(
+        &'a self,
+        recipient: &'a str,
+        filter: &'a NotificationFilter,
+    ) -> BoxFuture<'a, Result<Vec<Notification>, AppError>> {
+        Box::pin(async move {
+            let mut notifications: Vec<Notification> = self
+                .notifications
+                .values()
+                .into_iter()
+                .filter(|n| n.recipient == recipient && filter.matches(n))
+                .collect();
+            notifications.sort_by(|a, b| b.id.cmp(&a.id));
+            notifications.truncate(filter.limit.unwrap_or(usize::MAX));
+            Ok(notifications)
+        })
+    }
+
+    fn count_notifications<'a>(
+        &'a self,
+        recipient: &'a str,
+        filter: &'a NotificationFilter,
+    ) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let filter = NotificationFilter {
+                cursor: None,
+                ..filter.clone()
+            };
+            Ok(self
+                .notifications
+                .values()
+                .iter()
+                .filter(|n| n.recipient == recipient && filter.matches(n))
+                .count())
+        })
+    }
+}
+
+// In-memory Activity Repository
+pub struct InMemoryActivityRepo {
+    activities: Table<Activity>,
+}
+
+impl Default for InMemoryActivityRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryActivityRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            activities: Table::new("Activity", limits),
+        }
+    }
+}
+
+impl ActivityRepo for InMemoryActivityRepo {
+    fn create_activity<'a>(&'a self, activity: Activity) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.activities.insert(activity.id.clone(), activity) })
+    }
+
+    fn list_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<Vec<Activity>, AppError>> {
+        Box::pin(async move {
+            let mut activities: Vec<Activity> =
+                self.activities.values().into_iter().filter(|a| filter.matches(a)).collect();
+            activities.sort_by(|a, b| b.id.cmp(&a.id));
+            activities.truncate(filter.limit.unwrap_or(usize::MAX));
+            Ok(activities)
+        })
+    }
+
+    fn count_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<usize, AppError>> {
+        Box::pin(async move {
+            let filter = ActivityFilter {
+                cursor: None,
+                ..filter.clone()
+            };
+            Ok(self.activities.values().iter().filter(|a| filter.matches(a)).count())
+        })
+    }
+}
+
+// In-memory Drafts Repository
+pub struct InMemoryDraftsRepo {
+    drafts: Table<Draft>,
+}
+
+impl Default for InMemoryDraftsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryDraftsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            drafts: Table::new("Draft", limits),
+        }
+    }
+}
+
+impl DraftsRepo for InMemoryDraftsRepo {
+    fn get_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Draft, AppError>> {
+        Box::pin(async move { self.drafts.get(id) })
+    }
+
+    fn put_draft<'a>(&'a self, draft: Draft) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.drafts.upsert(draft.id.clone(), draft).map(|_| ()) })
+    }
+
+    fn delete_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.drafts.remove(id) })
+    }
+}
+
+// In-memory Shares Repository
+pub struct InMemorySharesRepo {
+    shares: Table<TicketShare>,
+}
+
+impl Default for InMemorySharesRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemorySharesRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            shares: Table::new("Share", limits),
+        }
+    }
+}
+
+impl SharesRepo for InMemorySharesRepo {
+    fn create_share<'a>(&'a self, share: TicketShare) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.shares.insert(share.id.clone(), share) })
+    }
+
+    fn get_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<TicketShare, AppError>> {
+        Box::pin(async move { self.shares.get(id) })
+    }
+
+    fn list_shares<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<TicketShare>, AppError>> {
+        Box::pin(async move {
+            let mut shares: Vec<TicketShare> = self
+                .shares
+                .values()
+                .into_iter()
+                .filter(|s| s.ticket == ticket)
+                .collect();
+            shares.sort_by_key(|s| s.created_at);
+            Ok(shares)
+        })
+    }
+
+    fn delete_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.shares.remove(id) })
+    }
+}
+
+// In-memory Preferences Repository
+pub struct InMemoryPreferencesRepo {
+    preferences: Table<Preference>,
+}
+
+impl Default for InMemoryPreferencesRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryPreferencesRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            preferences: Table::new("Preference", limits),
+        }
+    }
+}
+
+impl PreferencesRepo for InMemoryPreferencesRepo {
+    fn get_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Preference, AppError>> {
+        Box::pin(async move { self.preferences.get(id) })
+    }
+
+    fn put_preference<'a>(&'a self, preference: Preference) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            if preference.version <= 1 {
+                return self.preferences.insert(preference.id.clone(), preference);
+            }
+            let id = preference.id.clone();
+            self.preferences
+                .modify(&id, |stored| {
+                    if stored.version + 1 != preference.version {
+                        return Err(AppError::Conflict(format!("Preference {} is at version {}", id, stored.version)));
+                    }
+                    Ok(preference)
+                })
+                .map(|_| ())
+        })
+    }
+
+    fn list_preferences<'a>(&'a self, owner: &'a str) -> BoxFuture<'a, Result<Vec<Preference>, AppError>> {
+        Box::pin(async move {
+            let mut preferences: Vec<Preference> = self
+                .preferences
+                .values()
+                .into_iter()
+                .filter(|p| p.owner == owner)
+                .collect();
+            preferences.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+            Ok(preferences)
+        })
+    }
+
+    fn delete_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.preferences.remove(id) })
+    }
+}
+
+// In-memory Read Receipts Repository
+pub struct InMemoryReadReceiptsRepo {
+    receipts: Table<ReadReceipt>, // keyed by ticket and username
+}
+
+impl Default for InMemoryReadReceiptsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryReadReceiptsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            receipts: Table::new("ReadReceipt", limits),
+        }
+    }
+}
+
+impl ReadReceiptsRepo for InMemoryReadReceiptsRepo {
+    fn set_receipt<'a>(&'a self, receipt: ReadReceipt) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let key = format!("{}:{}", receipt.ticket, receipt.username);
+            self.receipts.upsert(key, receipt).map(|_| ())
+        })
+    }
+
+    fn list_receipts_of<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        Box::pin(async move { Ok(self.receipts.values().into_iter().filter(|r| r.username == username).collect()) })
+    }
+
+    fn list_receipts<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>> {
+        Box::pin(async move {
+            let mut receipts: Vec<ReadReceipt> =
+                self.receipts.values().into_iter().filter(|r| r.ticket == ticket).collect();
+            receipts.sort_by_key(|r| std::cmp::Reverse(r.seen_at));
+            Ok(receipts)
+        })
+    }
+}
+
+// In-memory Milestones Repository
+pub struct InMemoryMilestonesRepo {
+    milestones: Table<Milestone>,
+}
+
+impl Default for InMemoryMilestonesRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryMilestonesRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            milestones: Table::new("Milestone", limits),
+        }
+    }
+}
+
+impl MilestonesRepo for InMemoryMilestonesRepo {
+    fn get_milestone<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Milestone, AppError>> {
+        Box::pin(async move { self.milestones.get(id) })
+    }
+
+    fn create_milestone<'a>(&'a self, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.milestones.insert(milestone.id.clone(), milestone) })
+    }
+
+    fn update_milestone<'a>(&'a self, id: &'a str, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.milestones.update(id, milestone) })
+    }
+
+    fn list_milestones<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Vec<Milestone>, AppError>> {
+        Box::pin(async move {
+            let mut milestones: Vec<Milestone> = self
+                .milestones
+                .values()
+                .into_iter()
+                .filter(|m| m.project == project)
+                .collect();
+            milestones.sort_by(|a, b| (a.start, &a.id).cmp(&(b.start, &b.id)));
+            Ok(milestones)
+        })
+    }
+}
+
+// In-memory Comments Repository
+pub struct InMemoryCommentsRepo {
+    comments: Table<Comment>,
+}
+
+impl Default for InMemoryCommentsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryCommentsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            comments: Table::new("Comment", limits),
+        }
+    }
+}
+
+impl CommentsRepo for InMemoryCommentsRepo {
+    fn create_comment<'a>(&'a self, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.comments.insert(comment.id.clone(), comment) })
+    }
+
+    fn list_comments<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        Box::pin(async move {
+            let mut comments: Vec<Comment> = self
+                .comments
+                .values()
+                .into_iter()
+                .filter(|c| c.ticket == ticket)
+                .collect();
+            comments.sort_by(|a, b| a.id.cmp(&b.id));
+            Ok(comments)
+        })
+    }
+
+    fn list_comments_by<'a>(&'a self, author: &'a str) -> BoxFuture<'a, Result<Vec<Comment>, AppError>> {
+        Box::pin(async move {
+            let mut comments: Vec<Comment> = self
+                .comments
+                .values()
+                .into_iter()
+                .filter(|c| c.author == author)
+                .collect();
+            comments.sort_by(|a, b| a.id.cmp(&b.id));
+            Ok(comments)
+        })
+    }
+
+    fn update_comment<'a>(&'a self, id: &'a str, comment: Comment) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.comments.update(id, comment) })
+    }
+
+    fn find_comment_by_message_id<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<Option<Comment>, AppError>> {
+        Box::pin(async move {
+            Ok(self
+                .comments
+                .values()
+                .into_iter()
+                .find(|c| c.message_id.as_deref() == Some(message_id)))
+        })
+    }
+}
+
+// In-memory Chat Channels Repository
+pub struct InMemoryChatChannelsRepo {
+    channels: Table<ChatChannel>,
+}
+
+impl Default for InMemoryChatChannelsRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryChatChannelsRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            channels: Table::new("ChatChannel", limits),
+        }
+    }
+}
+
+impl ChatChannelsRepo for InMemoryChatChannelsRepo {
+    fn get_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<ChatChannel, AppError>> {
+        Box::pin(async move { self.channels.get(project) })
+    }
+
+    fn create_chat_channel<'a>(&'a self, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.channels.insert(channel.project.clone(), channel) })
+    }
+
+    fn update_chat_channel<'a>(&'a self, project: &'a str, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.channels.update(project, channel) })
+    }
+
+    fn delete_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.channels.remove(project) })
+    }
+
+    fn list_chat_channels<'a>(&'a self) -> BoxFuture<'a, Result<Vec<ChatChannel>, AppError>> {
+        Box::pin(async move {
+            let mut channels = self.channels.values();
+            channels.sort_by(|a, b| a.project.cmp(&b.project));
+            Ok(channels)
+        })
+    }
+}
+
+// In-memory Outbox Repository
+pub struct InMemoryOutboxRepo {
+    entries: Table<OutboxEntry>,
+}
+
+impl Default for InMemoryOutboxRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryOutboxRepo {
+    pub fn new() -> Self {
+        Self::with_limits(InMemoryLimits::default())
+    }
+
+    pub fn with_limits(limits: InMemoryLimits) -> Self {
+        Self {
+            entries: Table::new("OutboxEntry", limits),
+        }
+    }
+}
+
+impl OutboxRepo for InMemoryOutboxRepo {
+    fn get_outbox_entry<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<OutboxEntry, AppError>> {
+        Box::pin(async move { self.entries.get(id) })
+    }
+
+    fn create_outbox_entry<'a>(&'a self, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.entries.insert(entry.id.clone(), entry) })
+    }
+
+    fn update_outbox_entry<'a>(&'a self, id: &'a str, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.entries.update(id, entry) })
+    }
+
+    fn list_outbox_entries<'a>(&'a self, filter: &'a OutboxFilter) -> BoxFuture<'a, Result<Vec<OutboxEntry>, AppError>> {
         Box::pin(async move {
-            let tickets = self.tickets.read().unwrap();
-            Ok(tickets.values().cloned().collect())
+            let mut entries: Vec<OutboxEntry> =
+                self.entries.values().into_iter().filter(|e| filter.matches(e)).collect();
+            entries.sort_by(|a, b| a.id.cmp(&b.id));
+            entries.truncate(filter.limit.unwrap_or(usize::MAX));
+            Ok(entries)
         })
     }
 }
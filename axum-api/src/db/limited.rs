@@ -0,0 +1,90 @@
+// Concurrency limit in front of a database, so overload is refused instead of piling up
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use utoipa::ToSchema;
+
+use crate::db::{BoxFuture, guarded::{Guard, GuardedDatabase}};
+use crate::error::AppError;
+
+/// A database running at most `limit` calls at once.
+pub type LimitedDatabase = GuardedDatabase<ConcurrencyLimit>;
+
+/// Lets `limit` calls run and up to `max_queue` more wait for their turn. Calls
+/// beyond that fail right away with `AppError::Unavailable`.
+pub struct ConcurrencyLimit {
+    permits: Semaphore,
+    limit: usize,
+    max_queue: usize,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+/// How busy the limit is, for operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PoolStats {
+    pub limit: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    pub max_queue: usize,
+    pub rejected: u64, // since the instance started
+}
+
+impl ConcurrencyLimit {
+    pub fn new(limit: usize, max_queue: usize) -> Self {
+        Self {
+            permits: Semaphore::new(limit),
+            limit,
+            max_queue,
+            queued: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            limit: self.limit,
+            in_flight: self.limit - self.permits.available_permits(),
+            queued: self.queued.load(Ordering::Relaxed),
+            max_queue: self.max_queue,
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Guard for ConcurrencyLimit {
+    fn call<'a, T: Send + 'a>(&'a self, call: BoxFuture<'a, Result<T, AppError>>) -> BoxFuture<'a, Result<T, AppError>> {
+        Box::pin(async move {
+            let _permit = match self.permits.try_acquire() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    // Counted before checking, so two callers can't both take the last place
+                    if self.queued.fetch_add(1, Ordering::AcqRel) >= self.max_queue {
+                        self.queued.fetch_sub(1, Ordering::AcqRel);
+                        self.rejected.fetch_add(1, Ordering::Relaxed);
+                        return Err(AppError::Unavailable(format!(
+                            "Database busy, {} calls running and {} waiting",
+                            self.limit, self.max_queue
+                        )));
+                    }
+                    let _waiting = Waiting(&self.queued);
+                    self.permits
+                        .acquire()
+                        .await
+                        .map_err(|_| AppError::Unavailable("Database closed".to_string()))?
+                }
+            };
+            call.await
+        })
+    }
+}
+
+/// A place in the queue, given up when the wait ends, also when the caller gives up.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
@@ -1,39 +1,455 @@
 pub mod inmemory;
 pub mod arangodb;
+pub mod aql;
+pub mod breaker;
+pub mod cached;
+pub mod deadline;
+pub mod dump;
+pub mod guarded;
+pub mod limited;
+pub mod versioned;
+#[cfg(test)]
+pub mod chaos;
 
-use crate::{error::AppError, models::{Group, Project, Ticket, User}, utils::BoxFuture};
+use std::collections::HashSet;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{error::AppError, models::{Activity, ActivityKind, ChatChannel, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, Notification, OutboxEntry, OutboxStatus, Preference, Project, ReadReceipt, SecurityEvent, SecurityEventKind, Session, Ticket, TicketShare, TicketStatus, User}, utils::BoxFuture};
 
 // Individual repository traits
 pub trait UsersRepo: Send + Sync {
     fn get_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>>;
+    /// The users with these usernames, in one round trip.
+    fn get_users<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<User>, AppError>>;
+    /// Fails with `Conflict` if the username, the email or an external id is taken.
     fn create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Fails with `Conflict` if another user has the email or an external id.
     fn update_user<'a>(&'a self, id: &'a str, user: User) -> BoxFuture<'a, Result<(), AppError>>;
-    fn delete_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Creates the user or replaces the stored one, in one atomic write, taking a deleted
+    /// one out of the trash. Returns whether it was created.
+    fn upsert_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<bool, AppError>>;
+    /// The stored user with this username, or this one created. Returns whether it was created.
+    /// Fails with `Conflict` if a deleted user has it.
+    fn get_or_create_user<'a>(&'a self, user: User) -> BoxFuture<'a, Result<(User, bool), AppError>>;
+    /// Moves the user to the trash, or removes it for good when `hard`, also from the trash.
+    fn delete_user<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Users in the trash, oldest deletion first.
+    fn list_deleted_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<User>>, AppError>>;
+    /// Takes the user out of the trash.
+    fn restore_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
     fn list_users<'a>(&'a self) -> BoxFuture<'a, Result<Vec<User>, AppError>>;
+    fn count_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>>;
+    fn count_deactivated_users<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>>;
+    fn exists_user<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>>;
+    /// Users whose metadata has the key, with that value if given, by username.
+    fn find_users_by_metadata<'a>(&'a self, key: &'a str, value: Option<&'a str>) -> BoxFuture<'a, Result<Vec<User>, AppError>>;
+    /// The user holding an API token with the given hash.
+    fn find_user_by_api_token<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<User, AppError>>;
+    /// The user with this email. Emails are stored lowercased, the argument is expected to be too.
+    fn find_user_by_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<User, AppError>>;
+    /// The user with this external identity, `provider:subject`.
+    fn find_user_by_external_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<User, AppError>>;
 }
 
 pub trait ProjectsRepo: Send + Sync {
     fn get_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Project, AppError>>;
     fn create_project<'a>(&'a self, project: Project) -> BoxFuture<'a, Result<(), AppError>>;
     fn update_project<'a>(&'a self, id: &'a str, project: Project) -> BoxFuture<'a, Result<(), AppError>>;
-    fn delete_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Moves the project to the trash, or removes it for good when `hard`, also from the trash.
+    fn delete_project<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Projects in the trash, oldest deletion first.
+    fn list_deleted_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Project>>, AppError>>;
+    /// Takes the project out of the trash.
+    fn restore_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
     fn list_projects<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Project>, AppError>>;
+    fn count_projects<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>>;
+    fn exists_project<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>>;
+    /// Every project below the project, through `parent_id`, closest first.
+    fn list_subprojects<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>>;
 }
 
 pub trait GroupsRepo: Send + Sync {
     fn get_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Group, AppError>>;
+    /// The groups with these ids, in one round trip.
+    fn get_groups<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Group>, AppError>>;
     fn create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(), AppError>>;
     fn update_group<'a>(&'a self, id: &'a str, group: Group) -> BoxFuture<'a, Result<(), AppError>>;
-    fn delete_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Creates the group or replaces the stored one, in one atomic write, taking a deleted
+    /// one out of the trash. Returns whether it was created.
+    fn upsert_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<bool, AppError>>;
+    /// The stored group with this id, or this one created. Returns whether it was created.
+    /// Fails with `Conflict` if a deleted group has it.
+    fn get_or_create_group<'a>(&'a self, group: Group) -> BoxFuture<'a, Result<(Group, bool), AppError>>;
+    /// Moves the group to the trash, or removes it for good when `hard`, also from the trash.
+    fn delete_group<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Groups in the trash, oldest deletion first.
+    fn list_deleted_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Group>>, AppError>>;
+    /// Takes the group out of the trash.
+    fn restore_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
     fn list_groups<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Group>, AppError>>;
+    fn count_groups<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>>;
+    fn exists_group<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>>;
 }
 
 pub trait TicketsRepo: Send + Sync {
     fn get_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Ticket, AppError>>;
+    /// The tickets with these ids, in one round trip.
+    fn get_tickets<'a>(&'a self, ids: &'a [String]) -> BoxFuture<'a, Result<Batch<Ticket>, AppError>>;
+    /// Ids above those reserved are taken as if reserved, so restored tickets keep theirs.
     fn create_ticket<'a>(&'a self, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Reserves `count` consecutive ids no ticket has had, returning the first. Concurrent
+    /// reservations get distinct ids, and ids stay taken after tickets are removed for good.
+    fn reserve_ticket_ids<'a>(&'a self, count: i64) -> BoxFuture<'a, Result<i64, AppError>>;
     fn update_ticket<'a>(&'a self, id: &'a str, ticket: Ticket) -> BoxFuture<'a, Result<(), AppError>>;
-    fn delete_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Moves the ticket to the trash, or removes it for good when `hard`, also from the trash.
+    fn delete_ticket<'a>(&'a self, id: &'a str, hard: bool) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Tickets in the trash, oldest deletion first.
+    fn list_deleted_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Deleted<Ticket>>, AppError>>;
+    /// Takes the ticket out of the trash.
+    fn restore_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
     fn list_tickets<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Ticket>, AppError>>;
+    /// Tickets reduced to the given top-level fields, projected by the database.
+    fn list_tickets_fields<'a>(&'a self, fields: &'a [String]) -> BoxFuture<'a, Result<Vec<Value>, AppError>>;
+    fn get_ticket_fields<'a>(&'a self, id: &'a str, fields: &'a [String]) -> BoxFuture<'a, Result<Value, AppError>>;
+    fn count_tickets<'a>(&'a self) -> BoxFuture<'a, Result<usize, AppError>>;
+    fn exists_ticket<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, AppError>>;
+    /// Ticket counts grouped by project, status and severity level, sorted by those.
+    /// Only the given project's tickets if set.
+    fn ticket_counts<'a>(&'a self, project: Option<&'a str>) -> BoxFuture<'a, Result<Vec<TicketCount>, AppError>>;
+    /// Tickets of a project created and resolved per UTC day, from `since` on, sorted by day.
+    /// Days without either are left out.
+    fn ticket_activity<'a>(&'a self, project: &'a str, since: NaiveDate) -> BoxFuture<'a, Result<Vec<TicketDayCount>, AppError>>;
+    /// Mean seconds from creation to resolution over a project's resolved tickets, `None` if there are none.
+    fn average_resolution_secs<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Option<f64>, AppError>>;
+    /// Assignees with the most tickets in a project, most first.
+    fn assignee_counts<'a>(&'a self, project: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<AssigneeCount>, AppError>>;
+    /// The highest rank among the tickets of the project, or of no project, in the status.
+    /// `None` if none of them is ranked.
+    fn last_rank<'a>(&'a self, project: Option<&'a str>, status: TicketStatus) -> BoxFuture<'a, Result<Option<String>, AppError>>;
+    /// Who the project's newest ticket assigned to one of the principals is assigned to.
+    fn last_assignee<'a>(&'a self, project: &'a str, among: &'a [String]) -> BoxFuture<'a, Result<Option<String>, AppError>>;
+}
+
+pub trait SessionsRepo: Send + Sync {
+    fn get_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Session, AppError>>;
+    fn create_session<'a>(&'a self, session: Session) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_session<'a>(&'a self, id: &'a str, session: Session) -> BoxFuture<'a, Result<(), AppError>>;
+    fn delete_session<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    fn list_user_sessions<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<Session>, AppError>>;
+}
+
+pub trait InvitesRepo: Send + Sync {
+    fn get_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Invite, AppError>>;
+    fn create_invite<'a>(&'a self, invite: Invite) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_invite<'a>(&'a self, id: &'a str, invite: Invite) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Marks the invite used by `username` unless it already is, in one atomic step:
+    /// of concurrent claims only one succeeds, the others get a `Conflict`.
+    fn claim_invite<'a>(&'a self, id: &'a str, username: &'a str, at: DateTime<Utc>) -> BoxFuture<'a, Result<Invite, AppError>>;
+    fn delete_invite<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    fn list_invites<'a>(&'a self) -> BoxFuture<'a, Result<Vec<Invite>, AppError>>;
+}
+
+/// Keeps only the given top-level attributes of a JSON object, like AQL `KEEP()`.
+pub fn keep_fields(value: Value, fields: &[String]) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            map.retain(|k, _| fields.contains(k));
+            Value::Object(map)
+        }
+        other => other,
+    }
+}
+
+/// Number of tickets sharing a project, status and severity level.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TicketCount {
+    pub project: Option<String>,
+    pub status: TicketStatus,
+    pub severity: u8,
+    pub count: usize,
+}
+
+/// Tickets opened and resolved on one day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TicketDayCount {
+    pub day: NaiveDate,
+    pub opened: usize,
+    pub resolved: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AssigneeCount {
+    pub assignee: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BackendInfo {
+    pub backend: String,
+    pub database: Option<String>, // database name, if the backend has one
+    pub version: Option<String>,  // server version, if the backend is a server
+}
+
+/// A soft-deleted entity, hidden from gets and lists until restored or removed for good.
+/// Its id stays taken meanwhile: creating another entity with it is a conflict.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deleted<T> {
+    pub entity: T,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// An entity found by a search, with how well it matches the query: the higher the
+/// better. Scores only compare within one search.
+#[derive(Debug, Clone)]
+pub struct Scored<T> {
+    pub entity: T,
+    pub score: f64,
+}
+
+/// What a search found, by kind of entity, best match first.
+#[derive(Debug, Clone, Default)]
+pub struct SearchHits {
+    pub tickets: Vec<Scored<Ticket>>,
+    pub projects: Vec<Scored<Project>>,
+    pub users: Vec<Scored<User>>,
+    pub groups: Vec<Scored<Group>>,
+}
+
+/// Entities fetched by id: those found, in the order asked for, and the ids of the rest.
+#[derive(Debug, Clone)]
+pub struct Batch<T> {
+    pub found: Vec<T>,
+    pub missing: Vec<String>,
+}
+
+impl<T> Batch<T> {
+    /// Looks every id up once, skipping repeated ids.
+    pub fn lookup(ids: &[String], mut get: impl FnMut(&str) -> Option<T>) -> Self {
+        let mut seen = HashSet::new();
+        let mut batch = Batch {
+            found: Vec::new(),
+            missing: Vec::new(),
+        };
+        for id in ids.iter().filter(|id| seen.insert(id.as_str())) {
+            match get(id) {
+                Some(entity) => batch.found.push(entity),
+                None => batch.missing.push(id.clone()),
+            }
+        }
+        batch
+    }
+}
+
+/// Filter for security event queries, unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SecurityEventFilter {
+    pub kind: Option<SecurityEventKind>,
+    pub username: Option<String>,
+    pub ip: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// Only events older than this event id (ids are time-ordered UUIDv7).
+    #[serde(skip)] // from the `PageParams` of a request
+    pub cursor: Option<String>,
+    #[serde(skip)]
+    pub limit: Option<usize>,
+}
+
+impl SecurityEventFilter {
+    pub fn matches(&self, event: &SecurityEvent) -> bool {
+        self.kind.is_none_or(|k| k == event.kind)
+            && self.username.as_ref().is_none_or(|u| event.username.as_ref() == Some(u))
+            && self.ip.as_ref().is_none_or(|ip| event.ip.as_ref() == Some(ip))
+            && self.since.is_none_or(|since| event.created_at >= since)
+            && self.cursor.as_ref().is_none_or(|cursor| event.id < *cursor)
+    }
+}
+
+pub trait SecurityEventsRepo: Send + Sync {
+    fn create_event<'a>(&'a self, event: SecurityEvent) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Newest first, truncated to `filter.limit` if set.
+    fn list_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<Vec<SecurityEvent>, AppError>>;
+    /// Number of matching events, ignoring `cursor` and `limit`.
+    fn count_events<'a>(&'a self, filter: &'a SecurityEventFilter) -> BoxFuture<'a, Result<usize, AppError>>;
+}
+
+pub trait IdempotencyRepo: Send + Sync {
+    fn get_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<IdempotencyRecord, AppError>>;
+    /// Fails with `Conflict` if a record with the same id exists.
+    fn create_record<'a>(&'a self, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_record<'a>(&'a self, id: &'a str, record: IdempotencyRecord) -> BoxFuture<'a, Result<(), AppError>>;
+    fn delete_record<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// Filter for a user's notifications, unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NotificationFilter {
+    pub unread: Option<bool>, // true for unread only, false for read only
+    /// Only notifications older than this notification id (ids are time-ordered UUIDv7).
+    #[serde(skip)] // from the `PageParams` of a request
+    pub cursor: Option<String>,
+    #[serde(skip)]
+    pub limit: Option<usize>,
+}
+
+impl NotificationFilter {
+    pub fn matches(&self, notification: &Notification) -> bool {
+        self.unread.is_none_or(|unread| notification.read != unread)
+            && self.cursor.as_ref().is_none_or(|cursor| notification.id < *cursor)
+    }
+}
+
+pub trait NotificationsRepo: Send + Sync {
+    fn get_notification<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Notification, AppError>>;
+    fn create_notification<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_notification<'a>(&'a self, id: &'a str, notification: Notification) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Notifications of the recipient, newest first, truncated to `filter.limit` if set.
+    fn list_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<Vec<Notification>, AppError>>;
+    /// Number of matching notifications of the recipient, ignoring `cursor` and `limit`.
+    fn count_notifications<'a>(&'a self, recipient: &'a str, filter: &'a NotificationFilter) -> BoxFuture<'a, Result<usize, AppError>>;
+}
+
+/// Filter for activity queries, unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct ActivityFilter {
+    pub project: Option<String>,
+    pub actor: Option<String>,
+    pub kinds: Vec<ActivityKind>, // empty for every kind
+    /// Only activities older than this activity id (ids are time-ordered UUIDv7).
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl ActivityFilter {
+    pub fn matches(&self, activity: &Activity) -> bool {
+        self.project.as_ref().is_none_or(|p| activity.project.as_ref() == Some(p))
+            && self.actor.as_ref().is_none_or(|a| activity.actor == *a)
+            && (self.kinds.is_empty() || self.kinds.contains(&activity.kind))
+            && self.cursor.as_ref().is_none_or(|cursor| activity.id < *cursor)
+    }
+}
+
+pub trait ActivityRepo: Send + Sync {
+    /// Fails with `Conflict` if an activity with the same id exists.
+    fn create_activity<'a>(&'a self, activity: Activity) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Newest first, truncated to `filter.limit` if set.
+    fn list_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<Vec<Activity>, AppError>>;
+    /// Number of matching activities, ignoring `cursor` and `limit`.
+    fn count_activity<'a>(&'a self, filter: &'a ActivityFilter) -> BoxFuture<'a, Result<usize, AppError>>;
+}
+
+pub trait DraftsRepo: Send + Sync {
+    fn get_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Draft, AppError>>;
+    /// Stores the draft, replacing the one with the same id.
+    fn put_draft<'a>(&'a self, draft: Draft) -> BoxFuture<'a, Result<(), AppError>>;
+    fn delete_draft<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+pub trait PreferencesRepo: Send + Sync {
+    fn get_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Preference, AppError>>;
+    /// Stores the preference in place of its previous version, in one atomic step: at
+    /// version 1 it must not exist yet, else the stored one must be at the version
+    /// before. Fails with `Conflict` otherwise, or `NotFound` if there is nothing to
+    /// replace.
+    fn put_preference<'a>(&'a self, preference: Preference) -> BoxFuture<'a, Result<(), AppError>>;
+    fn list_preferences<'a>(&'a self, owner: &'a str) -> BoxFuture<'a, Result<Vec<Preference>, AppError>>;
+    fn delete_preference<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+pub trait SharesRepo: Send + Sync {
+    /// Fails with `Conflict` if a share with the same id exists.
+    fn create_share<'a>(&'a self, share: TicketShare) -> BoxFuture<'a, Result<(), AppError>>;
+    fn get_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<TicketShare, AppError>>;
+    /// The ticket's shares, oldest first, expired ones included until they are purged.
+    fn list_shares<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<TicketShare>, AppError>>;
+    fn delete_share<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+pub trait ReadReceiptsRepo: Send + Sync {
+    /// Stores the receipt, replacing the user's previous one for the ticket.
+    fn set_receipt<'a>(&'a self, receipt: ReadReceipt) -> BoxFuture<'a, Result<(), AppError>>;
+    /// The user's receipts, in no particular order.
+    fn list_receipts_of<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>>;
+    /// Receipts for the ticket, most recently seen first.
+    fn list_receipts<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<ReadReceipt>, AppError>>;
+}
+
+pub trait MilestonesRepo: Send + Sync {
+    fn get_milestone<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Milestone, AppError>>;
+    fn create_milestone<'a>(&'a self, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_milestone<'a>(&'a self, id: &'a str, milestone: Milestone) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Milestones of a project, by start date then id.
+    fn list_milestones<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<Vec<Milestone>, AppError>>;
+}
+
+pub trait CommentsRepo: Send + Sync {
+    fn create_comment<'a>(&'a self, comment: Comment) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Comments on a ticket, oldest first.
+    fn list_comments<'a>(&'a self, ticket: i64) -> BoxFuture<'a, Result<Vec<Comment>, AppError>>;
+    /// Comments by an author, on any ticket, oldest first.
+    fn list_comments_by<'a>(&'a self, author: &'a str) -> BoxFuture<'a, Result<Vec<Comment>, AppError>>;
+    fn update_comment<'a>(&'a self, id: &'a str, comment: Comment) -> BoxFuture<'a, Result<(), AppError>>;
+    /// The comment made from the email with this `Message-ID`, if any.
+    fn find_comment_by_message_id<'a>(&'a self, message_id: &'a str) -> BoxFuture<'a, Result<Option<Comment>, AppError>>;
+}
+
+pub trait ChatChannelsRepo: Send + Sync {
+    fn get_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<ChatChannel, AppError>>;
+    fn create_chat_channel<'a>(&'a self, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_chat_channel<'a>(&'a self, project: &'a str, channel: ChatChannel) -> BoxFuture<'a, Result<(), AppError>>;
+    fn delete_chat_channel<'a>(&'a self, project: &'a str) -> BoxFuture<'a, Result<(), AppError>>;
+    /// All channels, by project.
+    fn list_chat_channels<'a>(&'a self) -> BoxFuture<'a, Result<Vec<ChatChannel>, AppError>>;
+}
+
+/// Filter for outbox queries, unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OutboxFilter {
+    pub status: Option<OutboxStatus>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl OutboxFilter {
+    pub fn matches(&self, entry: &OutboxEntry) -> bool {
+        self.status.is_none_or(|status| status == entry.status)
+            && self.since.is_none_or(|since| entry.created_at >= since)
+    }
+}
+
+pub trait OutboxRepo: Send + Sync {
+    fn get_outbox_entry<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<OutboxEntry, AppError>>;
+    fn create_outbox_entry<'a>(&'a self, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>>;
+    fn update_outbox_entry<'a>(&'a self, id: &'a str, entry: OutboxEntry) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Oldest first, truncated to `filter.limit` if set.
+    fn list_outbox_entries<'a>(&'a self, filter: &'a OutboxFilter) -> BoxFuture<'a, Result<Vec<OutboxEntry>, AppError>>;
+}
+
+/// Traversals of what links principals and projects: groups contain principals, groups
+/// included, principals own projects and projects contain their sub-projects.
+pub trait GraphRepo: Send + Sync {
+    /// Ids of the groups the principal is in, directly or through other groups, sorted.
+    fn groups_of<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<String>, AppError>>;
+    /// Projects owned by the principal or one of its groups, and the projects below them,
+    /// sorted by id.
+    fn owned_projects<'a>(&'a self, principal: &'a str) -> BoxFuture<'a, Result<Vec<Project>, AppError>>;
+}
+
+/// Full-text search over live tickets, projects, users and groups. ACLs are not
+/// applied, callers filter the hits.
+///
+/// Tickets match on their title, description and id, projects on their id and ticket
+/// group prefixes, users on their username, name and job title, groups on their id and
+/// name. How words match and hits are scored is up to the backend.
+pub trait SearchService: Send + Sync {
+    /// Up to `limit` entities of each kind matching the words of `query`.
+    fn find<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<SearchHits, AppError>>;
 }
 
 // Main database interface that provides access to all repositories
@@ -43,6 +459,22 @@ pub trait DatabaseInterface: Send + Sync {
     fn projects(&self) -> &dyn ProjectsRepo;
     fn groups(&self) -> &dyn GroupsRepo;
     fn tickets(&self) -> &dyn TicketsRepo;
+    fn sessions(&self) -> &dyn SessionsRepo;
+    fn invites(&self) -> &dyn InvitesRepo;
+    fn security_events(&self) -> &dyn SecurityEventsRepo;
+    fn idempotency(&self) -> &dyn IdempotencyRepo;
+    fn notifications(&self) -> &dyn NotificationsRepo;
+    fn activity(&self) -> &dyn ActivityRepo;
+    fn receipts(&self) -> &dyn ReadReceiptsRepo;
+    fn drafts(&self) -> &dyn DraftsRepo;
+    fn shares(&self) -> &dyn SharesRepo;
+    fn preferences(&self) -> &dyn PreferencesRepo;
+    fn milestones(&self) -> &dyn MilestonesRepo;
+    fn comments(&self) -> &dyn CommentsRepo;
+    fn chat_channels(&self) -> &dyn ChatChannelsRepo;
+    fn outbox(&self) -> &dyn OutboxRepo;
+    fn graph(&self) -> &dyn GraphRepo;
+    fn search(&self) -> &dyn SearchService;
     
     // Transaction support (optional but recommended)
     fn begin_transaction<'a>(&'a self) -> BoxFuture<'a, Result<(), AppError>>;
@@ -51,4 +483,7 @@ pub trait DatabaseInterface: Send + Sync {
 
     // Initialization (called on app start, can do migrations, db creation)
     fn initialize(&self) -> BoxFuture<'_, Result<(), AppError>>;
+
+    /// Which backend serves the data, for operators.
+    fn backend_info(&self) -> BoxFuture<'_, Result<BackendInfo, AppError>>;
 }
@@ -0,0 +1,156 @@
+// Schema versions of stored documents
+//
+// Users, groups, projects and tickets are written with the `schema_version` of their
+// shape, in the database and in dumps. A document of an older version is upgraded
+// when it is read, one version at a time, and stored in the current shape the next
+// time it is written, so a rolling upgrade needs no migration job. Documents from
+// before versions were kept are version 0.
+//
+// Fields added to a model without changing the meaning of the others get a
+// `#[serde(default)]` and no new version; only changes older documents can't be read
+// with, such as a field changing type, need one. Unknown fields are ignored, which
+// lets a server still being upgraded read what a newer one wrote.
+//
+// Used as `#[serde(with = "versioned")]`, also on flattened fields.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned, de::Error as _, ser::Error as _};
+use serde_json::{Map, Value, json};
+
+use crate::models::{Group, Project, Ticket, User};
+
+pub const FIELD: &str = "schema_version";
+
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// For error messages.
+    const KIND: &'static str;
+    /// The version documents are written with.
+    const VERSION: u64;
+
+    /// Rewrites a document of version `from` into the shape of version `from + 1`.
+    fn upgrade(doc: &mut Map<String, Value>, from: u64);
+}
+
+impl Versioned for User {
+    const KIND: &'static str = "User";
+    const VERSION: u64 = 1;
+
+    fn upgrade(_: &mut Map<String, Value>, _: u64) {}
+}
+
+impl Versioned for Group {
+    const KIND: &'static str = "Group";
+    const VERSION: u64 = 1;
+
+    fn upgrade(_: &mut Map<String, Value>, _: u64) {}
+}
+
+impl Versioned for Project {
+    const KIND: &'static str = "Project";
+    const VERSION: u64 = 1;
+
+    fn upgrade(_: &mut Map<String, Value>, _: u64) {}
+}
+
+impl Versioned for Ticket {
+    const KIND: &'static str = "Ticket";
+    const VERSION: u64 = 1;
+
+    fn upgrade(doc: &mut Map<String, Value>, from: u64) {
+        // 1: severities are `{ level, label }`, they were `[level, label]` pairs
+        if from == 0
+            && let Some(Value::Array(pair)) = doc.get("severity")
+            && let [level, label] = pair.as_slice()
+        {
+            let severity = json!({ "level": level, "label": label });
+            doc.insert("severity".to_string(), severity);
+        }
+    }
+}
+
+/// Reads a stored document, upgrading it from the version it was written with.
+pub fn from_stored<T: Versioned>(doc: Value) -> Result<T, String> {
+    let Value::Object(mut doc) = doc else {
+        return Err(format!("{} is not a JSON object", T::KIND));
+    };
+    let version = match doc.remove(FIELD) {
+        None | Some(Value::Null) => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("{} has schema version {}, expected a number", T::KIND, version))?,
+    };
+    for from in version..T::VERSION {
+        T::upgrade(&mut doc, from);
+    }
+    serde_json::from_value(Value::Object(doc)).map_err(|e| {
+        if version > T::VERSION {
+            format!(
+                "{} of schema version {} is newer than this server reads ({}): {}",
+                T::KIND,
+                version,
+                T::VERSION,
+                e
+            )
+        } else {
+            e.to_string()
+        }
+    })
+}
+
+/// The document as stored, with its schema version.
+pub fn to_stored<T: Versioned>(value: &T) -> Result<Value, serde_json::Error> {
+    let mut doc = serde_json::to_value(value)?;
+    if let Value::Object(fields) = &mut doc {
+        fields.insert(FIELD.to_string(), T::VERSION.into());
+    }
+    Ok(doc)
+}
+
+pub fn serialize<T: Versioned, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    to_stored(value).map_err(S::Error::custom)?.serialize(serializer)
+}
+
+pub fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    from_stored(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::dump::Record, models::Severity, test::app::sample_ticket};
+
+    #[test]
+    fn writes_the_version() {
+        let doc = to_stored(&sample_ticket(1, "New")).unwrap();
+        assert_eq!(doc[FIELD], json!(Ticket::VERSION));
+        let ticket: Ticket = from_stored(doc).unwrap();
+        assert_eq!(ticket.title, "New");
+    }
+
+    #[test]
+    fn upgrades_unversioned_documents() {
+        let mut doc = serde_json::to_value(sample_ticket(1, "Old")).unwrap();
+        doc["severity"] = json!([3, "minor"]);
+        let ticket: Ticket = from_stored(doc.clone()).unwrap();
+        assert_eq!(ticket.severity, Severity::new(3, "minor"));
+
+        // Old dumps too
+        let line = json!({ "type": "ticket", "data": doc }).to_string();
+        let Some(Record::Ticket(ticket)) = Record::from_line(line.as_bytes(), 1).unwrap() else {
+            panic!("not a ticket record");
+        };
+        assert_eq!(ticket.severity, Severity::new(3, "minor"));
+    }
+
+    #[test]
+    fn reads_newer_documents_leniently() {
+        let mut doc = to_stored(&sample_ticket(1, "Future")).unwrap();
+        doc[FIELD] = json!(Ticket::VERSION + 1);
+        doc["watchers"] = json!(["alice"]);
+        let ticket: Ticket = from_stored(doc.clone()).unwrap();
+        assert_eq!(ticket.title, "Future");
+
+        doc["severity"] = json!("critical");
+        let error = from_stored::<Ticket>(doc).unwrap_err();
+        assert!(error.starts_with("Ticket of schema version 2 is newer than this server reads (1)"), "{}", error);
+    }
+}
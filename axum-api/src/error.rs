@@ -6,11 +6,12 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
-use serde_json::json;
 use thiserror::Error;
+
+use crate::i18n::ErrorMessage;
 use utoipa::{
-    IntoResponses, PartialSchema, ToSchema,
-    openapi::{self, ContentBuilder, RefOr, ResponseBuilder},
+    IntoResponses, ToSchema,
+    openapi::{self, ContentBuilder, Ref, RefOr, ResponseBuilder},
 };
 
 #[derive(Error, Debug)]
@@ -42,6 +43,18 @@ pub enum AppError {
     #[error("Scheduling impossible: {0}")]
     SchedulingImpossible(String),
 
+    #[error("Storage full: {0}")]
+    StorageFull(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
     #[error("JWT error: {0}")]
     Jwt(#[from] jsonwebtoken::errors::Error),
 
@@ -72,6 +85,10 @@ impl AppError {
             AppError::Parse(_) => StatusCode::BAD_REQUEST,
             AppError::BcryptError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::SchedulingImpossible(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::StorageFull(_) => StatusCode::INSUFFICIENT_STORAGE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::DeadlineExceeded(_) => StatusCode::GATEWAY_TIMEOUT,
         }
     }
 
@@ -91,6 +108,33 @@ impl AppError {
             AppError::Parse(_) => "parse_error",
             AppError::BcryptError(_) => "bcrypt_error",
             AppError::SchedulingImpossible(_) => "scheduling impossible",
+            AppError::StorageFull(_) => "storage_full",
+            AppError::TooManyRequests(_) => "rate_limited",
+            AppError::Unavailable(_) => "unavailable",
+            AppError::DeadlineExceeded(_) => "deadline_exceeded",
+        }
+    }
+
+    /// The message without the title of its kind, in English.
+    pub fn detail(&self) -> String {
+        match self {
+            AppError::Internal(e) => e.to_string(),
+            AppError::Jwt(e) => e.to_string(),
+            AppError::Io(e) => e.to_string(),
+            AppError::BcryptError(e) => e.to_string(),
+            AppError::Serialization(msg)
+            | AppError::Authentication(msg)
+            | AppError::Authorization(msg)
+            | AppError::Validation(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::BadRequest(msg)
+            | AppError::SchedulingImpossible(msg)
+            | AppError::StorageFull(msg)
+            | AppError::TooManyRequests(msg)
+            | AppError::Unavailable(msg)
+            | AppError::DeadlineExceeded(msg)
+            | AppError::Parse(msg) => msg.clone(),
         }
     }
 
@@ -102,7 +146,9 @@ impl AppError {
             | AppError::NotFound(_)
             | AppError::BadRequest(_)
             | AppError::Jwt(_)
-            | AppError::Parse(_) => false,
+            | AppError::Parse(_)
+            | AppError::TooManyRequests(_)
+            | AppError::Unavailable(_) => false,
             AppError::Validation(_)
             | AppError::Internal(_)
             | AppError::Serialization(_)
@@ -110,118 +156,47 @@ impl AppError {
             | AppError::Conflict(_)
             | AppError::BcryptError(_) => true,
             AppError::SchedulingImpossible(_) => true,
+            AppError::StorageFull(_) => true,
+            AppError::DeadlineExceeded(_) => true,
         }
     }
 }
 
+/// Body of every error response, registered once in `ApiDoc` and referred to by the
+/// error responses of every endpoint.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ErrorResponse,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct ErrorResponse {
     pub r#type: String, // Use r# to allow "type" keyword
-    pub message: String,
+    pub code: String,   // of the message, stable across languages; the `type` for untranslated ones
+    pub message: String, // in the language asked for with `Accept-Language`, English by default
     pub status: u16,
 }
 
 impl IntoResponses for AppError {
     fn responses() -> BTreeMap<String, RefOr<openapi::Response>> {
-        let mut responses = BTreeMap::new();
-
-        // 400 Bad Request
-        responses.insert(
-            "400".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Bad Request")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        // 401 Unauthorized
-        responses.insert(
-            "401".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Unauthorized")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        // 404 Not Found
-        responses.insert(
-            "404".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Not Found")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        // 409 Conflict
-        responses.insert(
-            "409".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Conflict")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        // 500 Internal Server Error
-        responses.insert(
-            "500".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Internal Server Error")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        // 503 Service Unavailable
-        responses.insert(
-            "503".to_string(),
-            RefOr::T(
-                ResponseBuilder::new()
-                    .description("Service Unavailable")
-                    .content(
-                        "application/json",
-                        ContentBuilder::new()
-                            .schema(Some(ErrorResponse::schema()))
-                            .build(),
-                    )
-                    .build(),
-            ),
-        );
-
-        responses
+        [
+            (StatusCode::BAD_REQUEST, "Bad Request"),
+            (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            (StatusCode::NOT_FOUND, "Not Found"),
+            (StatusCode::CONFLICT, "Conflict"),
+            (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests"),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error"),
+            (StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable"),
+            (StatusCode::GATEWAY_TIMEOUT, "Gateway Timeout"),
+            (StatusCode::INSUFFICIENT_STORAGE, "Insufficient Storage"),
+        ]
+        .into_iter()
+        .map(|(status, description)| {
+            let body = ContentBuilder::new().schema(Some(Ref::from_schema_name(ErrorBody::name()))).build();
+            let response = ResponseBuilder::new().description(description).content("application/json", body);
+            (status.as_u16().to_string(), RefOr::T(response.build()))
+        })
+        .collect()
     }
 }
 
@@ -237,15 +212,20 @@ impl IntoResponse for AppError {
             tracing::debug!("AppError: {} (status: {})", self, status);
         }
 
-        let body = json!({
-            "error": {
-                "type": self.error_type(),
-                "message": self.to_string(),
-                "status": status.as_u16()
-            }
-        });
-
-        (status, Json(body)).into_response()
+        // Translated by `middleware::locale` once the client's language is known
+        let message = ErrorMessage::new(self.error_type(), self.detail());
+        let body = ErrorBody {
+            error: ErrorResponse {
+                r#type: self.error_type().to_string(),
+                code: message.code.clone(),
+                message: self.to_string(),
+                status: status.as_u16(),
+            },
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        response.extensions_mut().insert(message);
+        response
     }
 }
 
@@ -0,0 +1,128 @@
+//! Domain events: controllers publish what happened, subsystems react to it.
+//!
+//! Subscribers registered with `EventBus::subscribe` run in order before `publish`
+//! returns, so their effects are visible to the request that caused the event. Live
+//! feeds (gRPC `WatchTickets`, the chat relay) read the `stream` instead, which a
+//! slow reader can fall behind on.
+//!
+//! With an outbox (`OUTBOX=true`) events are stored instead, and both the subscribers
+//! and the stream get them from the dispatcher in `scheduler`. A stored event is
+//! handled at least once: a subscriber that fails gets the event again, as may the
+//! ones that succeeded before it, and a restart picks up where the dispatcher left.
+//!
+//! The entry is written after the change that caused it, not in a transaction with
+//! it, which the backends don't have. A crash between the two writes loses the event,
+//! so as seen from the change events are delivered at most once.
+
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{
+    db::DatabaseInterface,
+    error::AppError,
+    models::{Comment, OutboxEntry, OutboxStatus, Ticket, TicketEvent},
+    utils::BoxFuture,
+};
+
+// Events buffered per stream reader before a slow one starts missing them
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    /// Through open registration or an invite.
+    UserRegistered { username: String, at: DateTime<Utc> },
+    TicketUpdated(TicketEvent),
+    /// `ticket` as of after the comment.
+    CommentAdded { comment: Comment, ticket: Ticket },
+}
+
+/// Reacts to published events. What caused the event has already happened, so an
+/// error doesn't undo it: it is logged, or retried when the event came from the outbox.
+pub trait Subscriber: Send + Sync {
+    fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+pub struct EventBus {
+    subscribers: RwLock<Vec<Arc<dyn Subscriber>>>,
+    stream: broadcast::Sender<DomainEvent>,
+    outbox: Option<Arc<dyn DatabaseInterface>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (stream, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+            stream,
+            outbox: None,
+        }
+    }
+}
+
+impl EventBus {
+    /// A bus storing events in the database's outbox, for the dispatcher to deliver.
+    pub fn with_outbox(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self {
+            outbox: Some(db),
+            ..Self::default()
+        }
+    }
+
+    pub fn subscribe(&self, subscriber: Arc<dyn Subscriber>) {
+        self.subscribers.write().unwrap().push(subscriber);
+    }
+
+    /// Live feed of the events published through this instance, from now on.
+    pub fn stream(&self) -> broadcast::Receiver<DomainEvent> {
+        self.stream.subscribe()
+    }
+
+    /// Hands the event to the subscribers and the stream, or stores it in the outbox.
+    /// Fails only if the outbox can't be written. The change the event is about is
+    /// written by then: ticket creation and registration remove what they created
+    /// again, other changes stay without their event.
+    pub async fn publish(&self, event: DomainEvent) -> Result<(), AppError> {
+        let Some(db) = &self.outbox else {
+            if let Err(e) = self.deliver(&event).await {
+                log::error!("Delivery of a domain event failed: {}", e);
+            }
+            self.broadcast(event);
+            return Ok(());
+        };
+        let entry = OutboxEntry {
+            id: uuid::Uuid::now_v7().to_string(),
+            event,
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            created_at: Utc::now(),
+            delivered_at: None,
+        };
+        db.outbox().create_outbox_entry(entry).await
+    }
+
+    /// Sends the event to the stream readers.
+    pub fn broadcast(&self, event: DomainEvent) {
+        // Fails only when nobody is listening
+        let _ = self.stream.send(event);
+    }
+
+    /// Runs every subscriber on the event, returns the first error. Subscribers after
+    /// a failed one still run.
+    pub async fn deliver(&self, event: &DomainEvent) -> Result<(), AppError> {
+        let subscribers = self.subscribers.read().unwrap().clone();
+        let mut result = Ok(());
+        for subscriber in subscribers {
+            if let Err(e) = subscriber.handle(event).await
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
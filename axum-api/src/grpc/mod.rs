@@ -0,0 +1,264 @@
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use tonic::{Request, Response, Status, transport::Server};
+
+use crate::{
+    controllers::two_factor_controller::TwoFactorController,
+    error::AppError,
+    events::DomainEvent,
+    middleware::{auth::Claims, verify_access_token},
+    models::{self, Permissions},
+    schema::CreateTicketRequest,
+    state::AppState,
+    utils::time::rfc3339,
+};
+
+pub mod proto {
+    tonic::include_proto!("tracker.v1");
+}
+
+use proto::{
+    ticket_service_server::{TicketService, TicketServiceServer},
+    user_service_server::{UserService, UserServiceServer},
+};
+
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        let message = err.to_string();
+        match err.status_code().as_u16() {
+            400 | 422 => Status::invalid_argument(message),
+            401 => Status::unauthenticated(message),
+            403 => Status::permission_denied(message),
+            404 => Status::not_found(message),
+            409 => Status::already_exists(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+impl From<models::Ticket> for proto::Ticket {
+    fn from(ticket: models::Ticket) -> Self {
+        Self {
+            id: ticket.id,
+            title: ticket.title,
+            severity: ticket.severity.level.into(),
+            severity_label: ticket.severity.label,
+            description: ticket.description,
+            created_by: ticket.created_by,
+            assigned_to: ticket.assigned_to,
+            mentioned: ticket.mentioned,
+            last_modification: rfc3339(ticket.last_modification),
+            creation_date: rfc3339(ticket.creation_date),
+        }
+    }
+}
+
+impl From<models::TicketEvent> for proto::TicketEvent {
+    fn from(event: models::TicketEvent) -> Self {
+        let kind = match event.kind {
+            models::TicketEventKind::Created => proto::ticket_event::Kind::Created,
+            models::TicketEventKind::Moved => proto::ticket_event::Kind::Moved,
+        };
+        Self {
+            kind: kind.into(),
+            ticket: Some(event.ticket.into()),
+            actor: event.actor,
+            at: rfc3339(event.at),
+        }
+    }
+}
+
+impl From<models::User> for proto::User {
+    fn from(user: models::User) -> Self {
+        Self {
+            two_factor_enabled: TwoFactorController::is_enabled(&user),
+            username: user.username,
+            email: user.email,
+            verified: user.verified,
+            name: user.personal.name,
+            job_title: user.personal.job_title,
+            manager: user.personal.manager,
+            deactivated: user.deactivated,
+            created_at: rfc3339(user.created_at),
+        }
+    }
+}
+
+/// Authenticates a call from its `authorization: Bearer <jwt>` metadata,
+/// with the same checks as the HTTP API.
+async fn authenticate<T>(app_state: &AppState, req: &Request<T>) -> Result<Claims, Status> {
+    let token = req
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("Unauthorized"))?;
+    let ip = req.remote_addr().map(|addr| addr.ip().to_string());
+    Ok(verify_access_token(app_state, token, ip).await?)
+}
+
+/// Whether the user can fetch the ticket.
+async fn fetches(app_state: &AppState, username: &str, ticket: &models::Ticket) -> Result<bool, AppError> {
+    let principals = app_state.controller.group.principals_of(username).await?;
+    let permissions = app_state.controller.ticket.permissions(ticket, &principals).await?;
+    Ok(permissions.contains(Permissions::FETCH))
+}
+
+pub struct TicketGrpc {
+    app_state: Arc<AppState>,
+}
+
+#[tonic::async_trait]
+impl TicketService for TicketGrpc {
+    async fn get_ticket(
+        &self,
+        request: Request<proto::GetTicketRequest>,
+    ) -> Result<Response<proto::Ticket>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        let principals = self.app_state.controller.group.principals_of(&claims.sub).await?;
+        let id = request.into_inner().id.to_string();
+        let ticket = self
+            .app_state
+            .controller
+            .ticket
+            .ticket_for(&id, &principals, Permissions::FETCH)
+            .await?;
+        Ok(Response::new(ticket.into()))
+    }
+
+    async fn list_tickets(
+        &self,
+        request: Request<proto::ListTicketsRequest>,
+    ) -> Result<Response<proto::ListTicketsResponse>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        let principals = self.app_state.controller.group.principals_of(&claims.sub).await?;
+        let tickets = self
+            .app_state
+            .controller
+            .ticket
+            .tickets_for(&principals, Permissions::LIST)
+            .await?;
+        Ok(Response::new(proto::ListTicketsResponse {
+            tickets: tickets.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn create_ticket(
+        &self,
+        request: Request<proto::CreateTicketRequest>,
+    ) -> Result<Response<proto::Ticket>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        let principals = self.app_state.controller.group.principals_of(&claims.sub).await?;
+        let req = request.into_inner();
+        let severity = u8::try_from(req.severity)
+            .map_err(|_| Status::invalid_argument("Severity out of range"))?;
+        let ticket = self
+            .app_state
+            .controller
+            .ticket
+            .create_ticket(
+                &claims.sub,
+                &principals,
+                CreateTicketRequest {
+                    title: req.title,
+                    severity,
+                    severity_label: req.severity_label,
+                    description: req.description,
+                    assigned_to: req.assigned_to,
+                    mentioned: req.mentioned,
+                    project: None,
+                    custom_fields: Default::default(),
+                    due_date: None,
+                    ticket_group: None,
+                    draft: None,
+                },
+            )
+            .await?;
+
+        log::info!("Ticket event -> Ticket {} created by {}", ticket.id, &claims.sub);
+
+        Ok(Response::new(ticket.into()))
+    }
+
+    type WatchTicketsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::TicketEvent, Status>> + Send + 'static>>;
+
+    async fn watch_tickets(
+        &self,
+        request: Request<proto::WatchTicketsRequest>,
+    ) -> Result<Response<Self::WatchTicketsStream>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        log::info!("gRPC event -> {} watching tickets", &claims.sub);
+
+        // A subscriber that falls behind skips the events it missed. Access is checked
+        // for every event, so it follows ACL and membership changes
+        let app_state = self.app_state.clone();
+        let username = claims.sub;
+        let events = BroadcastStream::new(self.app_state.events.stream())
+            .then(move |event| {
+                let app_state = app_state.clone();
+                let username = username.clone();
+                async move {
+                    let Ok(DomainEvent::TicketUpdated(event)) = event else {
+                        return None;
+                    };
+                    match fetches(&app_state, &username, &event.ticket).await {
+                        Ok(true) => Some(Ok::<proto::TicketEvent, Status>(event.into())),
+                        Ok(false) => None,
+                        Err(e) => {
+                            log::error!("Failed to check access of {} to ticket {}: {}", username, event.ticket.id, e);
+                            None
+                        }
+                    }
+                }
+            })
+            .filter_map(|event| event);
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+pub struct UserGrpc {
+    app_state: Arc<AppState>,
+}
+
+#[tonic::async_trait]
+impl UserService for UserGrpc {
+    async fn get_me(
+        &self,
+        request: Request<proto::GetMeRequest>,
+    ) -> Result<Response<proto::User>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        let user = self.app_state.controller.user.get_user(&claims.sub).await?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn logout_all(
+        &self,
+        request: Request<proto::LogoutAllRequest>,
+    ) -> Result<Response<proto::LogoutAllResponse>, Status> {
+        let claims = authenticate(&self.app_state, &request).await?;
+        self.app_state
+            .controller
+            .user
+            .bump_token_generation(&claims.sub)
+            .await?;
+
+        log::info!("Session event -> User {} logged out everywhere", &claims.sub);
+
+        Ok(Response::new(proto::LogoutAllResponse {}))
+    }
+}
+
+/// Router with the ticket and user services, sharing the HTTP API's state.
+pub fn services(app_state: Arc<AppState>) -> tonic::transport::server::Router {
+    Server::builder()
+        .add_service(TicketServiceServer::new(TicketGrpc {
+            app_state: app_state.clone(),
+        }))
+        .add_service(UserServiceServer::new(UserGrpc { app_state }))
+}
+
+pub async fn serve(app_state: Arc<AppState>, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+    services(app_state).serve(addr).await
+}
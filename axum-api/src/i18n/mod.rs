@@ -0,0 +1,246 @@
+//! Translations of error messages, from the locale files embedded in the binary.
+//!
+//! Messages are keyed by code, e.g. `validation.field_required`. The English file
+//! doubles as the list of messages the server sends: an error whose message matches
+//! one of its templates is answered with that code, which stays the same whatever
+//! the language, and the message of the client's language.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.json")),
+    ("uk", include_str!("locales/uk.json")),
+];
+
+/// Prefix of the codes titling each kind of error, as in `error.not_found`.
+const TITLE_PREFIX: &str = "error.";
+
+/// Code of the title of an error type. Types are codes as they are, but for the one
+/// with a space, which clients already match on.
+fn title_code(kind: &str) -> String {
+    match kind {
+        "scheduling impossible" => format!("{}scheduling_impossible", TITLE_PREFIX),
+        kind => format!("{}{}", TITLE_PREFIX, kind),
+    }
+}
+
+static CATALOGS: LazyLock<HashMap<&'static str, HashMap<String, String>>> = LazyLock::new(|| {
+    LOCALES
+        .iter()
+        .map(|(locale, file)| {
+            let catalog = serde_json::from_str(file)
+                .unwrap_or_else(|e| panic!("Locale file {}.json is not a JSON object of strings: {}", locale, e));
+            (*locale, catalog)
+        })
+        .collect()
+});
+
+/// The English messages, the most specific first so that e.g. `Ticket {id} not found`
+/// is recognized before a more generic template could swallow it.
+static TEMPLATES: LazyLock<Vec<(String, Template)>> = LazyLock::new(|| {
+    let mut templates: Vec<(String, Template)> = CATALOGS[DEFAULT_LOCALE]
+        .iter()
+        .filter(|(code, _)| !code.starts_with(TITLE_PREFIX))
+        .map(|(code, text)| (code.clone(), Template::parse(text)))
+        .collect();
+    templates.sort_by(|(a_code, a), (b_code, b)| b.literal_len().cmp(&a.literal_len()).then(a_code.cmp(b_code)));
+    templates
+});
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Arg(String),
+}
+
+/// A message with `{name}` placeholders.
+#[derive(Debug)]
+struct Template(Vec<Part>);
+
+impl Template {
+    fn parse(text: &str) -> Self {
+        let mut parts = vec![];
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else { break };
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            parts.push(Part::Arg(rest[start + 1..start + len].to_string()));
+            rest = &rest[start + len + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Template(parts)
+    }
+
+    fn literal_len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.len(),
+                Part::Arg(_) => 0,
+            })
+            .sum()
+    }
+
+    /// The values of the placeholders if the message is this template filled in. A
+    /// placeholder takes the text up to where the next literal part is first found.
+    fn matches(&self, message: &str) -> Option<Vec<(String, String)>> {
+        let mut args = vec![];
+        let mut rest = message;
+        for (i, part) in self.0.iter().enumerate() {
+            match part {
+                Part::Text(text) => rest = rest.strip_prefix(text.as_str())?,
+                Part::Arg(name) => {
+                    let end = match self.0.get(i + 1) {
+                        Some(Part::Text(next)) => rest.find(next.as_str())?,
+                        _ => rest.len(),
+                    };
+                    if end == 0 {
+                        return None;
+                    }
+                    args.push((name.clone(), rest[..end].to_string()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        rest.is_empty().then_some(args)
+    }
+
+    fn render(&self, args: &[(String, String)]) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Arg(name) => args
+                    .iter()
+                    .find(|(arg, _)| arg == name)
+                    .map_or_else(|| format!("{{{}}}", name), |(_, value)| value.clone()),
+            })
+            .collect()
+    }
+}
+
+/// The locale of the catalog best matching an `Accept-Language` header, by quality
+/// and then order. `uk-UA` is served from `uk`; without a match, English.
+pub fn negotiate(accept_language: Option<&str>) -> &'static str {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let tag = params.next()?.trim();
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable, ties keep the client's order
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+        .iter()
+        .find_map(|(tag, _)| {
+            let tag = tag.to_ascii_lowercase();
+            let primary = tag.split('-').next().unwrap_or_default();
+            LOCALES.iter().map(|(locale, _)| *locale).find(|locale| *locale == tag || *locale == primary)
+        })
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// What an error response says, kept apart from its English body so that it can be
+/// translated once the client's language is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorMessage {
+    pub kind: &'static str, // the error's `type`
+    pub code: String,       // of the message if it is a known one, else the `type`
+    pub args: Vec<(String, String)>,
+    pub detail: String, // in English
+}
+
+impl ErrorMessage {
+    pub fn new(kind: &'static str, detail: String) -> Self {
+        let known = TEMPLATES
+            .iter()
+            .find_map(|(code, template)| template.matches(&detail).map(|args| (code.clone(), args)));
+        let (code, args) = known.unwrap_or_else(|| (kind.to_string(), vec![]));
+        Self { kind, code, args, detail }
+    }
+
+    /// The message in the locale, titled with the kind of error as the English ones
+    /// are. Messages without a translation are left in English.
+    pub fn localized(&self, locale: &str) -> String {
+        let text = |code: &str| {
+            let catalog = &CATALOGS[locale];
+            catalog.get(code).or_else(|| CATALOGS[DEFAULT_LOCALE].get(code)).map(|t| Template::parse(t))
+        };
+        let title = text(&title_code(self.kind)).map_or_else(|| self.kind.to_string(), |t| t.render(&[]));
+        let detail = if self.code == self.kind {
+            self.detail.clone()
+        } else {
+            text(&self.code).map_or_else(|| self.detail.clone(), |t| t.render(&self.args))
+        };
+        format!("{}: {}", title, detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_translate_every_code() {
+        let english = &CATALOGS[DEFAULT_LOCALE];
+        for (locale, catalog) in CATALOGS.iter() {
+            let mut missing: Vec<_> = english.keys().filter(|code| !catalog.contains_key(*code)).collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} lacks {:?}", locale, missing);
+            for (code, text) in catalog {
+                let names = |text: &str| {
+                    let mut names: Vec<String> = Template::parse(text)
+                        .0
+                        .into_iter()
+                        .filter_map(|part| match part {
+                            Part::Arg(name) => Some(name),
+                            Part::Text(_) => None,
+                        })
+                        .collect();
+                    names.sort();
+                    names
+                };
+                assert_eq!(names(text), names(&english[code]), "placeholders of {} in {}", code, locale);
+            }
+        }
+    }
+
+    #[test]
+    fn negotiates_by_quality() {
+        assert_eq!(negotiate(None), "en");
+        assert_eq!(negotiate(Some("uk-UA,uk;q=0.9,en;q=0.8")), "uk");
+        assert_eq!(negotiate(Some("de, en;q=0.5, uk;q=0.7")), "uk");
+        assert_eq!(negotiate(Some("uk;q=0, fr")), "en");
+        assert_eq!(negotiate(Some("*")), "en");
+    }
+
+    #[test]
+    fn recognizes_messages() {
+        let message = ErrorMessage::new("validation_error", "Field 'browser' must be a string".to_string());
+        assert_eq!(message.code, "validation.field_type");
+        assert_eq!(message.localized("en"), "Validation error: Field 'browser' must be a string");
+        assert_eq!(message.localized("uk"), "Помилка перевірки: Поле 'browser' має бути a string");
+
+        let message = ErrorMessage::new("not_found", "Ticket 12 not found".to_string());
+        assert_eq!((message.code.as_str(), message.args.clone()), ("not_found.ticket", vec![("id".to_string(), "12".to_string())]));
+
+        let unknown = ErrorMessage::new("conflict", "Something else".to_string());
+        assert_eq!(unknown.code, "conflict");
+        assert_eq!(unknown.localized("uk"), "Конфлікт: Something else");
+
+        let scheduling = ErrorMessage::new("scheduling impossible", "No worker left".to_string());
+        assert_eq!(scheduling.localized("uk"), "Планування неможливе: No worker left");
+    }
+}
@@ -0,0 +1,785 @@
+pub mod acl;
+pub mod api;
+pub mod captcha;
+pub mod config;
+pub mod controllers;
+pub mod db;
+pub mod error;
+pub mod events;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod i18n;
+pub mod middleware;
+pub mod migrate;
+pub mod models;
+pub mod notifier;
+pub mod scheduler;
+pub mod schema;
+pub mod search;
+pub mod secrets;
+pub mod seed;
+pub mod startup;
+pub mod state;
+pub mod test;
+pub mod utils;
+pub mod validation;
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use crate::{
+    api::{
+        v1::ws::ws_handler,
+        versions::{REVOKE_ALL_SESSIONS_V1, v1_doc, v2_doc},
+    },
+    config::SwaggerAccess,
+    error::{ErrorBody, ErrorResponse},
+    db::{
+        DatabaseInterface,
+        arangodb::{ArangoDatabase, connect_or_create_db_no_auth},
+        breaker::{BreakerDatabase, CircuitBreaker},
+        cached::CachedDatabase,
+        deadline::{DeadlineDatabase, DeadlineGuard},
+        inmemory::{InMemoryDatabase, InMemoryLimits, SWEEP_INTERVAL},
+        limited::{ConcurrencyLimit, LimitedDatabase},
+    },
+    middleware::{auth::Auth, scope::require_scope},
+    models::{Activity, Notification, SecurityEvent},
+    schema::{
+        HealthStatus, JsonOk, ListResponse, PageParams, Readiness, SessionInfo, SortOrder, SortParams, VersionInfo,
+    },
+    state::AppState,
+};
+use axum::{
+    Router,
+    extract::{State, connect_info::IntoMakeServiceWithConnectInfo},
+    middleware::{from_fn, from_fn_with_state},
+    routing::*,
+};
+use log::info;
+use tokio::net::TcpListener;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    trace::TraceLayer,
+};
+use utoipa::{
+    Modify, OpenApi, ToSchema,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_swagger_ui::SwaggerUi;
+
+
+// Path and schema discovery scans the sources at build time, which makes IDEs fail,
+// so it is only enabled with `--features swagger`.
+#[cfg_attr(feature = "swagger", utoipauto::utoipauto)]
+#[derive(OpenApi)]
+#[openapi(
+    modifiers(&SecurityAddon, &SharedComponents),
+    components(schemas(ErrorBody, ErrorResponse, PageParams, SortParams, SortOrder)),
+)]
+struct ApiDoc;
+
+/// The pages of the listings. Their schemas are references (see `ListResponse`), the
+/// pages themselves are registered here.
+struct SharedComponents;
+
+impl SharedComponents {
+    fn register<T: ToSchema>(components: &mut utoipa::openapi::Components) {
+        let mut schemas = Vec::new();
+        T::schemas(&mut schemas);
+        components.schemas.extend(schemas);
+    }
+}
+
+impl Modify for SharedComponents {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        Self::register::<ListResponse<Activity>>(components);
+        Self::register::<ListResponse<Notification>>(components);
+        Self::register::<ListResponse<SecurityEvent>>(components);
+        Self::register::<ListResponse<SessionInfo>>(components);
+        Self::register::<ListResponse<serde_json::Value>>(components);
+    }
+}
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "mgmt_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Authenticated user routes shared by every API version.
+fn user_routes(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/ws", get(ws_handler).route_layer(require_scope("account")))
+        .route(
+            "/me/sessions",
+            get(api::v1::me::sessions::list_sessions).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/sessions/{id}",
+            delete(api::v1::me::sessions::revoke_session).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/logout-all",
+            post(api::v1::me::sessions::logout_all).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/notifications",
+            get(api::v1::me::notifications::list_notifications)
+                .route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/notifications/unread-count",
+            get(api::v1::me::notifications::unread_count).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/notifications/read-all",
+            post(api::v1::me::notifications::mark_all_read).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/notifications/{id}/read",
+            post(api::v1::me::notifications::mark_read).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/activity",
+            get(api::v1::me::activity::my_activity).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/drafts/{key}",
+            get(api::v1::me::drafts::get_draft)
+                .put(api::v1::me::drafts::save_draft)
+                .delete(api::v1::me::drafts::discard_draft)
+                .route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/me/preferences",
+            get(api::v1::me::preferences::list_preferences).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/preferences/{namespace}",
+            get(api::v1::me::preferences::get_preferences)
+                .put(api::v1::me::preferences::save_preferences)
+                .delete(api::v1::me::preferences::delete_preferences)
+                .route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/metadata",
+            get(api::v1::me::metadata::my_metadata).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/timezone",
+            get(api::v1::me::timezone::my_timezone)
+                .put(api::v1::me::timezone::set_timezone)
+                .route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/calendar-token",
+            post(api::v1::me::calendar::issue_calendar_token)
+                .delete(api::v1::me::calendar::revoke_calendar_token)
+                .route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/2fa/enroll",
+            post(api::v1::me::two_factor::enroll).route_layer(require_scope("account")),
+        )
+        .route(
+            "/me/2fa/confirm",
+            post(api::v1::me::two_factor::confirm).route_layer(require_scope("account")),
+        )
+        .route(
+            "/tickets",
+            get(api::v1::tickets::list_tickets)
+                .post(api::v1::tickets::create_ticket)
+                .layer(from_fn_with_state(
+                    state.clone(),
+                    middleware::idempotency::idempotency_middleware,
+                ))
+                .route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/check-duplicates",
+            post(api::v1::tickets::check_duplicates).route_layer(require_scope("tickets:read")),
+        )
+        .route(
+            "/tickets/{id}",
+            get(api::v1::tickets::get_ticket).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/move",
+            post(api::v1::tickets::move_ticket).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/comments",
+            get(api::v1::tickets::list_comments)
+                .post(api::v1::tickets::create_comment)
+                .route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/seen",
+            get(api::v1::tickets::list_ticket_receipts)
+                .post(api::v1::tickets::mark_ticket_seen)
+                .route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/share",
+            post(api::v1::shares::share_ticket).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/shares",
+            get(api::v1::shares::list_shares).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/shares/{share_id}",
+            delete(api::v1::shares::revoke_share).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/tickets/{id}/milestone",
+            put(api::v1::milestones::assign_milestone).route_layer(require_scope("tickets")),
+        )
+        .route(
+            "/projects/{id}/stats",
+            get(api::v1::projects::project_stats).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/activity",
+            get(api::v1::projects::project_activity).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/online",
+            get(api::v1::projects::project_online).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/board",
+            get(api::v1::projects::project_board).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/severities",
+            get(api::v1::projects::project_severities).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/custom-fields",
+            get(api::v1::projects::custom_fields)
+                .put(api::v1::projects::set_custom_fields)
+                .route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/assignment-rules",
+            get(api::v1::projects::assignment_rules)
+                .put(api::v1::projects::set_assignment_rules)
+                .route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/assignment-rules/dry-run",
+            post(api::v1::projects::assignment_dry_run).route_layer(require_scope("projects:read")),
+        )
+        .route(
+            "/projects/{id}/escalation-policies",
+            get(api::v1::projects::escalation_policies)
+                .put(api::v1::projects::set_escalation_policies)
+                .route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/portal",
+            get(api::v1::projects::public_portal)
+                .put(api::v1::projects::set_public_portal)
+                .delete(api::v1::projects::remove_public_portal)
+                .route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/acl",
+            get(api::v1::projects::project_acl)
+                .put(api::v1::projects::set_project_acl)
+                .route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/acl/grant",
+            post(api::v1::projects::grant_permissions).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/acl/revoke",
+            post(api::v1::projects::revoke_permissions).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/clone",
+            post(api::v1::projects::clone_project).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/parent",
+            put(api::v1::projects::set_project_parent).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/tree",
+            get(api::v1::projects::project_tree).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/transfer-ownership",
+            post(api::v1::projects::transfer_ownership).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/projects/{id}/milestones",
+            get(api::v1::milestones::list_milestones)
+                .post(api::v1::milestones::create_milestone)
+                .route_layer(require_scope("milestones")),
+        )
+        .route(
+            "/milestones/{id}",
+            get(api::v1::milestones::get_milestone).route_layer(require_scope("milestones")),
+        )
+        .route(
+            "/milestones/{id}/burndown",
+            get(api::v1::milestones::milestone_burndown).route_layer(require_scope("milestones")),
+        )
+        .route(
+            "/milestones/{id}/close",
+            post(api::v1::milestones::close_milestone).route_layer(require_scope("milestones")),
+        )
+        .route(
+            "/principals/suggest",
+            get(api::v1::principals::suggest_principals).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/search",
+            get(api::v1::search::search).route_layer(require_scope("projects")),
+        )
+        .route(
+            "/render/markdown",
+            post(api::v1::render::render_markdown).route_layer(require_scope("tickets")),
+        )
+}
+
+pub fn create_app(shared_state: Arc<AppState>) -> IntoMakeServiceWithConnectInfo<Router, SocketAddr> {
+    let mainrt = Router::new()
+        // Health check and stats
+        .route(
+            "/register",
+            post(api::v1::authentication::login::register),
+        )
+        .route(
+            "/register/invite/{token}",
+            post(api::v1::authentication::login::register_with_invite),
+        )
+        .route(
+            "/verify-email",
+            post(api::v1::authentication::login::verify_email),
+        )
+        .route("/login", post(api::v1::authentication::login::login))
+        .route("/inbound/email", post(api::inbound::inbound_email))
+        .route(
+            "/public/projects/{slug}/tickets",
+            post(api::public::submit_ticket).route_layer(from_fn_with_state(
+                shared_state.clone(),
+                middleware::rate_limit::portal_rate_limit_middleware,
+            )),
+        )
+        // Outside the JWT layer: authenticated by the token in their URL
+        .route("/v1/me/calendar.ics", get(api::v1::me::calendar::calendar_feed))
+        .route("/public/tickets/shared", get(api::public::shared_ticket))
+        .route("/refresh", post(api::v1::authentication::login::refresh))
+        .route(
+            "/login/2fa",
+            post(api::v1::authentication::login::login_two_factor),
+        )
+        .nest(
+            "/v1",
+            user_routes(&shared_state)
+                .route(
+                    "/me/sessions/revoke-all",
+                    post(api::v1::me::sessions::revoke_all_sessions)
+                        .layer(from_fn_with_state(
+                            REVOKE_ALL_SESSIONS_V1,
+                            middleware::deprecation::deprecation_headers,
+                        ))
+                        .route_layer(require_scope("account")),
+                )
+                .layer(from_fn_with_state(
+                    shared_state.clone(),
+                    middleware::jwt_auth_middleware,
+                )),
+        )
+        .nest(
+            "/v2",
+            user_routes(&shared_state)
+                .route(
+                    "/me/sessions",
+                    delete(api::v2::me::revoke_other_sessions).route_layer(require_scope("account")),
+                )
+                .layer(from_fn_with_state(
+                    shared_state.clone(),
+                    middleware::jwt_auth_middleware,
+                )),
+        )
+        .nest(
+            "/mgmt",
+            Router::new()
+                .route(
+                    "/chat-channels",
+                    get(api::mgmt::chat_channels::list_chat_channels),
+                )
+                .route(
+                    "/chat-channels/{project}",
+                    put(api::mgmt::chat_channels::set_chat_channel)
+                        .delete(api::mgmt::chat_channels::remove_chat_channel),
+                )
+                .route("/export", get(api::mgmt::dump::export))
+                .route("/import", post(api::mgmt::dump::import))
+                .route(
+                    "/invites",
+                    post(api::mgmt::invites::create_invite).layer(from_fn_with_state(
+                        shared_state.clone(),
+                        middleware::idempotency::idempotency_middleware,
+                    )),
+                )
+                .route("/outbox", get(api::mgmt::outbox::list_outbox))
+                .route("/outbox/replay", post(api::mgmt::outbox::replay_since))
+                .route(
+                    "/outbox/{id}/replay",
+                    post(api::mgmt::outbox::replay_entry),
+                )
+                .route("/seed", post(api::mgmt::seed::load_fixtures))
+                .route(
+                    "/security-events",
+                    get(api::mgmt::security_events::list_security_events),
+                )
+                .route(
+                    "/service-accounts",
+                    get(api::mgmt::service_accounts::list_service_accounts)
+                        .post(api::mgmt::service_accounts::create_service_account),
+                )
+                .route(
+                    "/service-accounts/{id}",
+                    delete(api::mgmt::service_accounts::deactivate_service_account),
+                )
+                .route(
+                    "/service-accounts/{id}/tokens",
+                    post(api::mgmt::service_accounts::issue_api_token),
+                )
+                .route(
+                    "/service-accounts/{id}/tokens/{token_id}",
+                    delete(api::mgmt::service_accounts::revoke_api_token),
+                )
+                .route("/stats", get(api::mgmt::stats::admin_stats))
+                .route("/trash", get(api::mgmt::trash::list_deleted))
+                .route(
+                    "/trash/{kind}/{id}",
+                    delete(api::mgmt::trash::purge_deleted),
+                )
+                .route(
+                    "/trash/{kind}/{id}/restore",
+                    post(api::mgmt::trash::restore_deleted),
+                )
+                .route("/groups/{id}/rename", post(api::mgmt::groups::rename_group))
+                .route("/users", get(api::mgmt::users::find_users))
+                .route("/users/merge", post(api::mgmt::users::merge_users))
+                .route(
+                    "/users/reencrypt-metadata",
+                    post(api::mgmt::users::reencrypt_metadata),
+                )
+                .route(
+                    "/users/{id}/2fa",
+                    delete(api::mgmt::users::reset_two_factor),
+                )
+                .route("/users/{id}/rename", post(api::mgmt::users::rename_user))
+                .route(
+                    "/users/{id}/logout-all",
+                    post(api::mgmt::users::logout_all),
+                )
+                .route(
+                    "/users/{id}/metadata",
+                    get(api::mgmt::users::user_metadata),
+                )
+                .route(
+                    "/users/{id}/metadata/{key}",
+                    put(api::mgmt::users::set_user_metadata)
+                        .delete(api::mgmt::users::remove_user_metadata),
+                )
+                .layer(from_fn_with_state(
+                    shared_state.clone(),
+                    middleware::token_auth_middleware_mgmt,
+                )),
+        );
+    #[cfg(feature = "graphql")]
+    let mainrt = mainrt.route(
+        "/graphql",
+        post(api::graphql::graphql_handler)
+            .with_state(api::graphql::build_schema(shared_state.clone()))
+            .route_layer(require_scope("graphql"))
+            .layer(from_fn_with_state(
+                shared_state.clone(),
+                middleware::jwt_auth_middleware,
+            )),
+    );
+    let mainrt = mainrt
+        .with_state(shared_state.clone())
+        .layer(TraceLayer::new_for_http())
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        );
+    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .nest("/api", mainrt.into())
+        .route("/health", get(health_check))
+        .route("/health/ready", get(readiness).with_state(shared_state.clone()))
+        .route("/version", get(version))
+        .split_for_parts();
+    let swagger = SwaggerUi::new("/swagger-ui")
+        .url("/api-docs/v1/openapi.json", v1_doc(&api))
+        .url("/api-docs/v2/openapi.json", v2_doc(&api))
+        .url("/api-docs/openapi.json", api);
+    let router = match shared_state.config.swagger_access {
+        SwaggerAccess::Public => router.merge(swagger),
+        SwaggerAccess::Management => router.merge(Router::from(swagger).layer(
+            from_fn_with_state(shared_state.clone(), middleware::token_auth_middleware_mgmt),
+        )),
+        SwaggerAccess::Disabled => router,
+    };
+    let router = if shared_state.rate_limiter.is_empty() {
+        router
+    } else {
+        router.layer(from_fn_with_state(
+            shared_state.clone(),
+            middleware::rate_limit::rate_limit_middleware,
+        ))
+    };
+    let router = match shared_state.config.request_timeout {
+        Some(secs) => router.layer(from_fn_with_state(
+            Duration::from_secs(secs),
+            middleware::deadline::request_deadline,
+        )),
+        None => router,
+    };
+    // Errors of every layer above are translated
+    let router = router.layer(from_fn(middleware::locale::localize_errors));
+    let router = match shared_state.config.log_bodies {
+        Some(limit) => router.layer(from_fn_with_state(limit, middleware::body_logging::log_bodies)),
+        None => router,
+    };
+    // Outermost: everything inside reads the client's address this resolved
+    let router = router.layer(from_fn_with_state(
+        Arc::new(shared_state.config.trusted_proxies.clone()),
+        middleware::real_ip::resolve_client_ip,
+    ));
+
+    router.into_make_service_with_connect_info::<SocketAddr>()
+}
+
+pub fn create_mock_shared_state() -> Result<AppState, Box<dyn std::error::Error>> {
+    let config = config::AppConfig::from_env()?;
+    let auth = Auth::new(config.jwt_secret.as_bytes());
+    Ok(AppState::new(
+        config,
+        auth,
+        Arc::new(InMemoryDatabase::new()),
+    ))
+}
+
+/// Runs the server, or the command given as the first argument.
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize tracing
+    // tracing_subscriber::init();
+
+    let mut config = config::AppConfig::from_env()?;
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    secrets::Secrets::from_config(&config).resolve_config(&mut config).await?;
+
+    // Without arguments the server starts, the one command copies data and exits
+    let mut args = std::env::args().skip(1);
+    if let Some(command) = args.next() {
+        if command != migrate::COMMAND {
+            return Err(format!("Unknown command {}, the only one is {}", command, migrate::COMMAND).into());
+        }
+        return migrate::run(&config, args).await;
+    }
+
+    info!("Starting application with config:");
+    info!("  Host: {}", config.host);
+    info!("  Port: {}", config.port);
+    info!(
+        "  Database connection: {}",
+        config.database_connection_string
+    );
+    info!("  Database name: {}", config.database_name);
+    info!("  Client API keys: {:?}", config.client_api_keys);
+    info!("  Management token: {}", config.management_token);
+    info!("  Swagger UI access: {:?}", config.swagger_access);
+    info!("  Rate limit rules: {}", config.rate_limits.len());
+    if config.outbox {
+        info!("  Delivering events through the outbox every {}s", config.outbox_interval);
+    }
+    info!("  Deleted entities kept for {} days", config.soft_delete_retention);
+    info!("  Search backend: {:?}", config.search_backend);
+    if !config.encrypted_metadata_keys.is_empty() {
+        let current = &config.encryption_keys[0].id;
+        info!("  Encrypted user metadata: {:?}, key {}", config.encrypted_metadata_keys, current);
+    }
+    if let Some(limit) = config.log_bodies {
+        log::warn!("  Logging request and response bodies up to {} bytes", limit);
+    }
+
+    let mut database: Option<Arc<dyn DatabaseInterface>> = None;
+
+    if config.database_connection_string.starts_with("http") {
+        info!("Using ArangoDB as database backend, waiting up to {}s for it", config.db_startup_wait);
+        let db = startup::wait_for("ArangoDB", Duration::from_secs(config.db_startup_wait), || async {
+            let conn =
+                arangors::Connection::establish_without_auth(config.database_connection_string.clone()).await?;
+            connect_or_create_db_no_auth(&conn, &config.database_name).await
+        })
+        .await?;
+        let wrapper = ArangoDatabase::new(db);
+        database = Some(Arc::new(wrapper));
+    }
+
+    let database: Arc<dyn DatabaseInterface> = database.unwrap_or_else(|| {
+        let db = Arc::new(InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: config.inmemory_max_entities,
+            ttl: config.inmemory_ttl.map(Duration::from_secs),
+        }));
+        // Expired sessions and idempotency records, ArangoDB purges them with TTL indexes
+        db.spawn_sweeper(SWEEP_INTERVAL);
+        db
+    });
+    // Inside the cache, so cached reads don't wait for a turn
+    let db_limit = config
+        .db_max_concurrency
+        .map(|limit| Arc::new(ConcurrencyLimit::new(limit, config.db_max_queue)));
+    let database: Arc<dyn DatabaseInterface> = match &db_limit {
+        Some(limit) => {
+            info!("  At most {} database calls at once, {} more waiting", limit.stats().limit, config.db_max_queue);
+            Arc::new(LimitedDatabase::new(database, limit.clone()))
+        }
+        None => database,
+    };
+    // Outside the limit, an open circuit doesn't queue
+    let db_breaker = config
+        .db_breaker_threshold
+        .map(|threshold| Arc::new(CircuitBreaker::new(threshold, Duration::from_secs(config.db_breaker_cooldown))));
+    let database: Arc<dyn DatabaseInterface> = match &db_breaker {
+        Some(breaker) => {
+            info!(
+                "  Database circuit opens after {} failures, for {}s",
+                config.db_breaker_threshold.unwrap_or_default(),
+                config.db_breaker_cooldown
+            );
+            Arc::new(BreakerDatabase::new(database, breaker.clone()))
+        }
+        None => database,
+    };
+    let database: Arc<dyn DatabaseInterface> = match config.request_timeout {
+        Some(secs) => {
+            info!("  Requests and their database calls cut off after {}s", secs);
+            Arc::new(DeadlineDatabase::new(database, Arc::new(DeadlineGuard)))
+        }
+        None => database,
+    };
+    let database: Arc<dyn DatabaseInterface> = match config.db_cache_size {
+        Some(capacity) => {
+            info!("  Caching up to {} users and projects for {}s", capacity, config.db_cache_ttl);
+            Arc::new(CachedDatabase::new(database, capacity, Duration::from_secs(config.db_cache_ttl)))
+        }
+        None => database,
+    };
+
+    // Create app state
+    let auth = Auth::new(config.jwt_secret.as_bytes());
+    let mut app_state = AppState::new(config.clone(), auth, database);
+    app_state.db_limit = db_limit;
+    app_state.db_breaker = db_breaker;
+    let shared_state = Arc::new(app_state);
+
+    // Serve probes while the database is initialized, `/health/ready` says when it is done
+    let app = create_app(shared_state.clone());
+    let bind_address = format!("{}:{}", config.host, config.port);
+    let listener = TcpListener::bind(&bind_address).await?;
+    info!("Server starting on http://{}", bind_address);
+    let server = tokio::spawn(async move { axum::serve(listener, app).await });
+
+    if let Err(e) = start(&shared_state).await {
+        shared_state.startup.failed();
+        return Err(e);
+    }
+    shared_state.startup.ready();
+    info!("Startup complete, ready for traffic");
+
+    server.await??;
+    Ok(())
+}
+
+/// Everything between binding the port and being ready for traffic.
+async fn start(shared_state: &Arc<AppState>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = &shared_state.config;
+
+    info!("  Database initialization...");
+    let wait = Duration::from_secs(config.db_startup_wait);
+    startup::wait_for("Database", wait, || shared_state.db.initialize()).await?;
+    info!("  Database initialization complete");
+
+    if let Some(path) = &config.seed_file {
+        let report = seed::load(shared_state, seed::Fixtures::from_file(path)?).await?;
+        info!("  Seeded from {}: {:?}", path, report);
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        let grpc_address: SocketAddr =
+            format!("{}:{}", config.host, config.grpc_port).parse()?;
+        info!("gRPC server starting on {}", grpc_address);
+        let grpc_state = shared_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_state, grpc_address).await {
+                log::error!("gRPC server failed: {}", e);
+            }
+        });
+    }
+
+    scheduler::spawn(shared_state.clone());
+
+    Ok(())
+}
+
+// Utility handlers
+#[utoipa::path(get, path = "/health", tag = "health")]
+async fn health_check() -> JsonOk<HealthStatus> {
+    JsonOk(HealthStatus {
+        status: "healthy".to_string(),
+        timestamp: chrono::Utc::now(),
+    })
+}
+
+/// Readiness probe, `starting` until the database is initialized.
+#[utoipa::path(get, path = "/health/ready", tag = "health", responses(Readiness))]
+async fn readiness(State(app_state): State<Arc<AppState>>) -> Readiness {
+    Readiness(app_state.startup.phase())
+}
+
+#[utoipa::path(get, path = "/version", tag = "health")]
+async fn version() -> JsonOk<VersionInfo> {
+    let built = env!("BUILD_TIMESTAMP").parse().unwrap_or_default();
+    JsonOk(VersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("GIT_COMMIT_HASH").to_string(),
+        build_timestamp: chrono::DateTime::from_timestamp(built, 0).unwrap_or_default(),
+        features: env!("ENABLED_FEATURES")
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
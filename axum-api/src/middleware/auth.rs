@@ -1,22 +1,60 @@
 // src/auth/mod.rs
-use bcrypt::{DEFAULT_COST, hash, verify};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::error::AppError;
+use crate::{error::AppError, utils::random_token};
 
-// Token expiration time (e.g., 7 days)
-const ONE_WEEK: usize = 60 * 60 * 24 * 7;
+// Default token expiration time (e.g., 7 days), see AppConfig for the configured values
+pub const ONE_WEEK: usize = 60 * 60 * 24 * 7;
+
+// Lifetime of the intermediate token handed out between password and TOTP check
+pub const TWO_FACTOR_CHALLENGE_LIFETIME: usize = 60 * 5;
+
+// Lifetime of email verification tokens
+pub const EMAIL_VERIFICATION_LIFETIME: usize = 60 * 60 * 24;
 
 pub struct AuthenticatedUser(pub String);
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Id of the session the current request's token was issued for.
+#[derive(Debug, Clone)]
+pub struct CurrentSession(pub String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
+    pub sid: String,
+    pub generation: u64,
+    pub iat: usize,
+    pub nbf: usize,
     pub exp: usize,
 }
 
+/// Claims of single-purpose tokens (2FA challenges etc.), never accepted as access tokens.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScopedClaims {
+    pub sub: String,
+    pub purpose: String,
+    pub iat: usize,
+    pub nbf: usize,
+    pub exp: usize,
+    #[serde(default)]
+    pub jti: String, // random, tells apart tokens issued for the same subject in the same second
+}
+
+pub fn now_secs() -> usize {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap() // Safe to unwrap unless system time is before epoch
+        .as_secs() as usize
+}
+
+fn validation() -> Validation {
+    let mut validation = Validation::default();
+    validation.validate_nbf = true;
+    validation
+}
+
 // Auth struct holds the JWT keys
 #[derive(Clone)]
 pub struct Auth {
@@ -44,29 +82,25 @@ impl Auth {
         }
     }
 
-    /// Hashes a plain text password using bcrypt.
-    pub fn hash_password(&self, password: &str) -> Result<String, AppError> {
-        // bcrypt::hash is a synchronous operation
-        hash(password, DEFAULT_COST).map_err(AppError::BcryptError)
-    }
-
-    /// Verifies a plain text password against a bcrypt hash.
-    pub fn verify_password(&self, password: &str, hash: &str) -> Result<bool, AppError> {
-        // bcrypt::verify is a synchronous operation
-        verify(password, hash).map_err(AppError::BcryptError)
-    }
-
-    /// Creates a new JWT token for the given user email.
-    pub fn create_token(&self, user_email: &str) -> Result<(String, usize), AppError> {
+    /// Creates a new JWT token for the given user email, bound to a session id
+    /// and the user's current token generation.
+    pub fn create_token(
+        &self,
+        user_email: &str,
+        session_id: &str,
+        generation: u64,
+        lifetime_secs: usize,
+    ) -> Result<(String, usize), AppError> {
         // Calculate expiration time
-        let expiration_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap() // Safe to unwrap unless system time is before epoch
-            .as_secs() as usize
-            + ONE_WEEK;
+        let issued_at = now_secs();
+        let expiration_time = issued_at + lifetime_secs;
 
         let claims = Claims {
             sub: user_email.to_owned(), // Subject is the user's email
+            sid: session_id.to_owned(), // Session the token belongs to
+            generation,                 // Invalidated once the user's generation moves on
+            iat: issued_at,             // Issued at
+            nbf: issued_at,             // Not valid before issuing
             exp: expiration_time,       // Expiration time
         };
 
@@ -76,10 +110,46 @@ impl Auth {
             .map_err(AppError::Jwt)
     }
 
+    /// Creates a token usable only for the given purpose.
+    pub fn create_scoped_token(
+        &self,
+        subject: &str,
+        purpose: &str,
+        lifetime_secs: usize,
+    ) -> Result<(String, usize), AppError> {
+        let issued_at = now_secs();
+        let expiration_time = issued_at + lifetime_secs;
+
+        let claims = ScopedClaims {
+            sub: subject.to_owned(),
+            purpose: purpose.to_owned(),
+            iat: issued_at,
+            nbf: issued_at,
+            exp: expiration_time,
+            jti: random_token(16),
+        };
+
+        encode(&Header::default(), &claims, &self.encoding_key)
+            .map(|str| (str, expiration_time))
+            .map_err(AppError::Jwt)
+    }
+
+    /// Decodes a scoped token and checks that it was issued for `purpose`.
+    pub fn decode_scoped_token(&self, token: &str, purpose: &str) -> Result<ScopedClaims, AppError> {
+        let claims = decode::<ScopedClaims>(token, &self.decoding_key, &validation())
+            .map(|data| data.claims)
+            .map_err(AppError::Jwt)?;
+
+        if claims.purpose != purpose {
+            return Err(AppError::Authorization("Unauthorized".to_string()));
+        }
+        Ok(claims)
+    }
+
     /// Decodes and validates a JWT token, returning the claims if valid.
     pub fn decode_token(&self, token: &str) -> Result<Claims, AppError> {
         // Decode the token and validate it (signature, expiration)
-        decode::<Claims>(token, &self.decoding_key, &Validation::default())
+        decode::<Claims>(token, &self.decoding_key, &validation())
             .map(|data| data.claims) // Extract the claims from the token data
             .map_err(AppError::Jwt) // Convert jsonwebtoken error to AppError
     }
@@ -0,0 +1,157 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, Uri, header},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::error::AppError;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Keys (JSON fields or headers, case-insensitive) whose values are never logged.
+const SENSITIVE: &[&str] = &["password", "token", "secret", "authorization", "cookie", "code"];
+
+/// Path prefixes followed by a secret segment.
+const SECRET_PATHS: &[&str] = &["/api/register/invite/"];
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE.iter().any(|s| key.contains(s))
+}
+
+/// Replaces the value of every sensitive key, at any depth.
+pub fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// The URI with the secret segments of its path and every query value redacted:
+/// tokens travel in queries, as in calendar feed URLs.
+pub fn redact_uri(uri: &Uri) -> String {
+    let mut path = uri.path().to_string();
+    for prefix in SECRET_PATHS {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            let tail = rest.find('/').map_or("", |i| &rest[i..]);
+            path = format!("{}{}{}", prefix, REDACTED, tail);
+        }
+    }
+    let Some(query) = uri.query() else {
+        return path;
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) => format!("{}={}", name, REDACTED),
+            None => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{}", path, query)
+}
+
+fn redact_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive(name.as_str()) {
+                REDACTED
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Only JSON bodies are buffered: anything else may be binary or streamed.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json") || ct.contains("+json"))
+}
+
+/// The redacted body, cut to `limit` bytes.
+fn describe_body(bytes: &Bytes, limit: usize) -> String {
+    let mut text = match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => return format!("<{} bytes, not valid JSON>", bytes.len()),
+    };
+    if text.len() > limit {
+        let mut end = limit;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(&format!("... ({} bytes)", bytes.len()));
+    }
+    text
+}
+
+async fn buffer(body: Body) -> Result<Bytes, AppError> {
+    axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to read body: {}", e)))
+}
+
+/// Development aid: logs requests and responses with their JSON bodies, up to
+/// `limit` bytes each, with credentials redacted. Enabled by `LOG_BODIES`.
+pub async fn log_bodies(
+    State(limit): State<usize>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let method = req.method().clone();
+    let uri = redact_uri(req.uri());
+    let (parts, body) = req.into_parts();
+    let req = if is_json(&parts.headers) {
+        let bytes = buffer(body).await?;
+        log::info!(
+            "--> {} {} [{}] {}",
+            method,
+            uri,
+            redact_headers(&parts.headers),
+            describe_body(&bytes, limit)
+        );
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        log::info!("--> {} {} [{}]", method, uri, redact_headers(&parts.headers));
+        Request::from_parts(parts, body)
+    };
+
+    let response = next.run(req).await;
+
+    let (parts, body) = response.into_parts();
+    if !is_json(&parts.headers) {
+        log::info!("<-- {} {} {}", parts.status, method, uri);
+        return Ok(Response::from_parts(parts, body));
+    }
+    let bytes = buffer(body).await?;
+    log::info!(
+        "<-- {} {} {} [{}] {}",
+        parts.status,
+        method,
+        uri,
+        redact_headers(&parts.headers),
+        describe_body(&bytes, limit)
+    );
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
@@ -0,0 +1,69 @@
+use std::convert::Infallible;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Serialize;
+
+use crate::{
+    error::AppError,
+    schema::Conditional,
+    utils::{parse_http_date, sha256_hex},
+};
+
+/// Validators sent by the client on a conditional GET (`If-None-Match`, `If-Modified-Since`).
+#[derive(Debug, Clone, Default)]
+pub struct Preconditions {
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<DateTime<Utc>>,
+}
+
+impl<S> FromRequestParts<S> for Preconditions
+where
+    S: Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| parts.headers.get(name).and_then(|h| h.to_str().ok());
+        Ok(Preconditions {
+            if_none_match: header("If-None-Match").map(|s| s.to_string()),
+            if_modified_since: header("If-Modified-Since").and_then(parse_http_date),
+        })
+    }
+}
+
+/// Strong entity tag of a response payload.
+fn entity_tag<T: Serialize>(data: &T) -> Result<String, AppError> {
+    let body = serde_json::to_string(data)?;
+    Ok(format!("\"{}\"", &sha256_hex(&body)[..32]))
+}
+
+impl Preconditions {
+    /// Wraps `data` with its `ETag`/`Last-Modified` validators, dropping the body (304)
+    /// when the client's copy is still current. `If-None-Match` takes precedence over
+    /// `If-Modified-Since`, as in RFC 9110.
+    pub fn evaluate<T: Serialize>(
+        &self,
+        data: T,
+        last_modified: Option<DateTime<Utc>>,
+    ) -> Result<Conditional<T>, AppError> {
+        let etag = entity_tag(&data)?;
+        // HTTP-dates have second precision
+        let last_modified = last_modified.map(|dt| dt.trunc_subsecs(0));
+
+        let not_modified = match (&self.if_none_match, self.if_modified_since) {
+            (Some(candidates), _) => candidates
+                .split(',')
+                .map(|tag| tag.trim())
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag),
+            (None, Some(since)) => last_modified.is_some_and(|modified| modified <= since),
+            (None, None) => false,
+        };
+
+        Ok(Conditional {
+            data: (!not_modified).then_some(data),
+            etag,
+            last_modified,
+        })
+    }
+}
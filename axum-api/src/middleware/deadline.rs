@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::time::{Instant, timeout_at};
+
+use crate::{
+    db::deadline::{self, Deadline},
+    error::AppError,
+};
+
+/// Gives every request `timeout` to be answered. The deadline goes into the request's
+/// extensions and applies to the database calls made while serving it, so a slow
+/// query is abandoned with its request instead of piling up. Past the deadline the
+/// client gets a 504. Enabled by `REQUEST_TIMEOUT`.
+pub async fn request_deadline(State(timeout): State<Duration>, mut req: Request, next: Next) -> Response {
+    let deadline = Deadline(Instant::now() + timeout);
+    req.extensions_mut().insert(deadline);
+    match timeout_at(deadline.0, deadline::scope(deadline, next.run(req))).await {
+        Ok(response) => response,
+        Err(_) => AppError::DeadlineExceeded(format!("No response within {}s", timeout.as_secs_f64())).into_response(),
+    }
+}
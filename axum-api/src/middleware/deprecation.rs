@@ -0,0 +1,48 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use chrono::DateTime;
+
+use crate::utils::http_date;
+
+/// Deprecation notice of a route, advertised through the `Deprecation` (RFC 9745),
+/// `Sunset` (RFC 8594) and `Link: rel="successor-version"` response headers.
+#[derive(Clone, Debug)]
+pub struct Deprecation {
+    pub since: i64,          // unix seconds
+    pub sunset: Option<i64>, // unix seconds, when the route is going away
+    pub successor: Option<&'static str>,
+}
+
+pub async fn deprecation_headers(
+    State(deprecation): State<Deprecation>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.since)) {
+        headers.insert("Deprecation", value);
+    }
+    if let Some(value) = deprecation
+        .sunset
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+        .map(http_date)
+        .and_then(|date| HeaderValue::from_str(&date).ok())
+    {
+        headers.insert("Sunset", value);
+    }
+    if let Some(value) = deprecation
+        .successor
+        .and_then(|link| HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", link)).ok())
+    {
+        headers.insert("Link", value);
+    }
+
+    response
+}
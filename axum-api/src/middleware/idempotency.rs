@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    controllers::idempotency_controller::{IdempotencyController, IdempotencyStatus},
+    error::AppError,
+    state::AppState,
+    utils::sha256_hex,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Makes POST requests carrying an `Idempotency-Key` header safe to retry: the first
+/// response is stored per key and principal for `IDEMPOTENCY_TTL` seconds and replayed
+/// on retries. Responses with a 5xx status are not stored, so those can be retried.
+/// Must run inside the auth middleware; requests without an authenticated user
+/// (management API) share one key namespace.
+pub async fn idempotency_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|h| h.to_str().map(|s| s.to_string()));
+    let (Some(key), &Method::POST) = (key, req.method()) else {
+        return Ok(next.run(req).await);
+    };
+    let key = key
+        .ok()
+        .filter(|k| !k.is_empty() && k.len() <= MAX_KEY_LENGTH)
+        .ok_or_else(|| AppError::BadRequest("Invalid Idempotency-Key".to_string()))?;
+
+    let principal = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .unwrap_or_else(|| "mgmt".to_string());
+    let id = IdempotencyController::record_id(&principal, &key);
+
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to read request body: {}", e)))?;
+    let fingerprint = sha256_hex(&format!(
+        "{} {}\n{}",
+        parts.method,
+        parts.uri.path(),
+        String::from_utf8_lossy(&body)
+    ));
+
+    let controller = &app_state.controller.idempotency;
+    match controller
+        .begin(&id, fingerprint, app_state.config.idempotency_ttl)
+        .await?
+    {
+        IdempotencyStatus::Replay(record) => {
+            log::info!("Idempotency event -> Replaying response for {}", parts.uri.path());
+            return Ok(replay(record.status, record.content_type, record.body));
+        }
+        IdempotencyStatus::Started => {}
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        controller.abandon(&id).await?;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            controller.abandon(&id).await?;
+            return Err(AppError::Internal(anyhow::anyhow!(
+                "Failed to read response body: {}",
+                e
+            )));
+        }
+    };
+    let content_type = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+    controller
+        .complete(
+            &id,
+            parts.status.as_u16(),
+            content_type,
+            String::from_utf8_lossy(&body).into_owned(),
+        )
+        .await?;
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay(status: Option<u16>, content_type: Option<String>, body: String) -> Response {
+    let status = status
+        .and_then(|s| StatusCode::from_u16(s).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = (status, body).into_response();
+    let headers = response.headers_mut();
+    match content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        Some(value) => {
+            headers.insert(header::CONTENT_TYPE, value);
+        }
+        None => {
+            headers.remove(header::CONTENT_TYPE);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
@@ -0,0 +1,40 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderValue,
+        header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_LENGTH},
+    },
+    middleware::Next,
+    response::Response,
+};
+use serde_json::json;
+
+use crate::i18n::{DEFAULT_LOCALE, ErrorMessage, negotiate};
+
+/// Answers errors in the language of the client's `Accept-Language`, where there is a
+/// translation. Error bodies are rewritten from the `ErrorMessage` that `AppError`
+/// leaves on its responses; their `type` and `code` stay as they are.
+pub async fn localize_errors(req: Request, next: Next) -> Response {
+    let locale = negotiate(req.headers().get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()));
+    let mut response = next.run(req).await;
+    let Some(message) = response.extensions_mut().remove::<ErrorMessage>() else {
+        return response;
+    };
+    response.headers_mut().insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale));
+    if locale == DEFAULT_LOCALE {
+        return response;
+    }
+
+    let body = json!({
+        "error": {
+            "type": message.kind,
+            "code": message.code,
+            "message": message.localized(locale),
+            "status": response.status().as_u16()
+        }
+    });
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     body::Body,
@@ -9,8 +9,27 @@ use axum::{
 };
 
 pub mod auth;
+pub mod body_logging;
+pub mod conditional;
+pub mod deadline;
+pub mod deprecation;
+pub mod idempotency;
+pub mod locale;
+pub mod rate_limit;
+pub mod real_ip;
+pub mod scope;
 
-use crate::{error::AppError, middleware::auth::AuthenticatedUser, state::AppState};
+use crate::{
+    controllers::service_account_controller::ServiceAccountController,
+    error::AppError,
+    middleware::{
+        auth::{AuthenticatedUser, Claims, CurrentSession},
+        scope::GrantedScopes,
+    },
+    models::SecurityEventKind,
+    state::AppState,
+    utils::client_ip,
+};
 
 impl<S> FromRequestParts<S> for AuthenticatedUser
 where
@@ -31,6 +50,41 @@ where
     }
 }
 
+impl<S> FromRequestParts<S> for CurrentSession
+where
+    S: Send + Sync + 'static,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentSession>()
+            .cloned()
+            .ok_or(AppError::BadRequest(
+                "Missing extension: session".to_string(),
+            ))
+    }
+}
+
+/// Claims of the verified access token, for handlers that outlive the request.
+impl<S> FromRequestParts<S> for Claims
+where
+    S: Send + Sync + 'static,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Claims>()
+            .cloned()
+            .ok_or(AppError::BadRequest(
+                "Missing extension: claims".to_string(),
+            ))
+    }
+}
+
 pub async fn jwt_auth_middleware(
     State(app_state): State<Arc<AppState>>,
     req: Request<Body>,
@@ -75,24 +129,140 @@ pub async fn jwt_auth_middleware(
         .or(token_from_cookie)
         .ok_or_else(|| AppError::Authorization("Unauthorized".to_string()))?;
 
-    match app_state.auth.decode_token(&token) {
-        Ok(claims) => {
-            if app_state.controller.user.validate_user(&claims.sub).await {
-                __parts__.extensions.insert(claims.sub);
-                let req = Request::from_parts(__parts__, body);
-                Ok(next.run(req).await)
-            } else {
-                log::warn!("User invalid: {}", &claims.sub);
-                Err(AppError::Authorization("Unauthorized".to_string()))
+    // Service accounts have no sessions, their requests carry the username and the
+    // token's scopes for the route guards
+    if ServiceAccountController::is_api_token(&token) {
+        let (user, scopes) = match app_state.controller.service_account.account_of(&token).await {
+            Ok(account) => account,
+            Err(e) => {
+                if let AppError::Authorization(detail) = &e {
+                    app_state
+                        .controller
+                        .security
+                        .record(
+                            SecurityEventKind::ApiKeyMisuse,
+                            None,
+                            client_ip(&__parts__.headers),
+                            &format!("API token rejected: {}", detail),
+                            &app_state.config.security_alert,
+                        )
+                        .await;
+                }
+                return Err(e);
+            }
+        };
+        __parts__.extensions.insert(user.username);
+        __parts__.extensions.insert(GrantedScopes(scopes));
+        let req = Request::from_parts(__parts__, body);
+        return Ok(next.run(req).await);
+    }
+
+    let claims = verify_access_token(&app_state, &token, client_ip(&__parts__.headers)).await?;
+    __parts__.extensions.insert(CurrentSession(claims.sid.clone()));
+    __parts__.extensions.insert(claims.sub.clone());
+    __parts__.extensions.insert(claims);
+    let req = Request::from_parts(__parts__, body);
+    Ok(next.run(req).await)
+}
+
+/// Validates an access token: signature and expiry, then `revalidate_claims` unless
+/// the session passed it within `AUTH_CACHE_TTL` seconds.
+/// Failures are recorded as security events.
+pub async fn verify_access_token(
+    app_state: &AppState,
+    token: &str,
+    ip: Option<String>,
+) -> Result<Claims, AppError> {
+    match app_state.auth.decode_token(token) {
+        Ok(mut claims) => {
+            let max_age = Duration::from_secs(app_state.config.auth_cache_ttl);
+            let validated = app_state.controller.session.validated();
+            // Sessions are stored under the current username, so tokens from before a
+            // rename miss and are revalidated on every request until they expire
+            if max_age.is_zero() || !validated.contains(&claims.sid, &claims.sub, claims.generation, max_age) {
+                claims.sub = revalidate_claims(app_state, &claims, ip).await?;
+                if !max_age.is_zero() {
+                    validated.store(&claims.sid, &claims.sub, claims.generation, max_age);
+                }
             }
+            Ok(claims)
         }
         Err(e) => {
             log::warn!("JWT validation failed: {}", e);
+            app_state
+                .controller
+                .security
+                .record(
+                    SecurityEventKind::TokenValidationFailure,
+                    None,
+                    ip,
+                    &format!("JWT validation failed: {}", e),
+                    &app_state.config.security_alert,
+                )
+                .await;
             Err(AppError::Authorization("Unauthorized".to_string()))
         }
     }
 }
 
+/// Checks that decoded claims are still honoured: the session they are bound to is live
+/// and the user is active with the same token generation. Long-lived connections call
+/// this periodically, as the token was only verified when they were opened. Returns
+/// the username the user goes by now, see `UserController::current_username`.
+pub async fn revalidate_claims(
+    app_state: &AppState,
+    claims: &Claims,
+    ip: Option<String>,
+) -> Result<String, AppError> {
+    let session = &app_state.controller.session;
+    let mut username = claims.sub.clone();
+    let mut valid = session.validate_session(&claims.sid, &username).await?;
+    if !valid {
+        // Tokens from before a rename carry the old username, their session moved along
+        let current = app_state.controller.user.current_username(&claims.sub).await?;
+        if current != username {
+            username = current;
+            valid = session.validate_session(&claims.sid, &username).await?;
+        }
+    }
+    if !valid {
+        log::warn!("Session invalid or revoked: {}", &claims.sid);
+        app_state
+            .controller
+            .security
+            .record(
+                SecurityEventKind::TokenValidationFailure,
+                Some(&claims.sub),
+                ip,
+                "Session invalid or revoked",
+                &app_state.config.security_alert,
+            )
+            .await;
+        return Err(AppError::Authorization("Unauthorized".to_string()));
+    }
+    if !app_state
+        .controller
+        .user
+        .validate_user(&username, claims.generation)
+        .await?
+    {
+        log::warn!("User invalid: {}", &claims.sub);
+        app_state
+            .controller
+            .security
+            .record(
+                SecurityEventKind::TokenValidationFailure,
+                Some(&claims.sub),
+                ip,
+                "Token generation or user invalid",
+                &app_state.config.security_alert,
+            )
+            .await;
+        return Err(AppError::Authorization("Unauthorized".to_string()));
+    }
+    Ok(username)
+}
+
 pub async fn token_auth_middleware_mgmt(
     State(app_state): State<Arc<AppState>>,
     req: Request<Body>,
@@ -121,6 +291,17 @@ pub async fn token_auth_middleware_mgmt(
         let req = Request::from_parts(parts, body);
         Ok(next.run(req).await)
     } else {
+        app_state
+            .controller
+            .security
+            .record(
+                SecurityEventKind::ApiKeyMisuse,
+                None,
+                client_ip(&parts.headers),
+                "Invalid management token",
+                &app_state.config.security_alert,
+            )
+            .await;
         Err(AppError::Authorization("Unauthorized".to_string()))
     }
 }
@@ -144,6 +325,17 @@ pub async fn apikey_auth_middleware_user(
         .ok_or_else(|| AppError::Authorization("Missing API key in headers".to_string()))?;
 
     if !app_state.config.client_api_keys.contains(&api_key) {
+        app_state
+            .controller
+            .security
+            .record(
+                SecurityEventKind::ApiKeyMisuse,
+                None,
+                client_ip(headers),
+                "Invalid API key",
+                &app_state.config.security_alert,
+            )
+            .await;
         return Err(AppError::Authorization("Unauthorized: Invalid API key".to_string()));
     }
 
@@ -0,0 +1,275 @@
+//! Request rate limits configured with one rule per line (or `;`-separated):
+//!
+//! ```text
+//! POST /api/register: 5/min/ip
+//! /api/v1/*: 600/hour/user
+//! * /api/mgmt/*: 100/min/global
+//! ```
+//!
+//! A rule is an optional method (`*` for any), a path pattern, a count and a window
+//! (`sec`, `min`, `hour` or `day`), and what is counted separately: `ip` (the default),
+//! `user` (anonymous requests count by IP) or `global`. In patterns `{name}` matches one
+//! path segment and a trailing `*` matches the rest. Every matching rule applies.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request, State},
+    http::{HeaderValue, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    controllers::service_account_controller::ServiceAccountController, error::AppError, state::AppState,
+    utils::client_ip,
+};
+
+// Counters kept before expired windows are swept out
+const SWEEP_THRESHOLD: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKey {
+    Ip,
+    User,
+    Global,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitRule {
+    pub method: Option<Method>, // None matches any method
+    pub pattern: String,
+    pub limit: u32,
+    pub window: Duration,
+    pub key: LimitKey,
+}
+
+impl RateLimitRule {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        if self.method.as_ref().is_some_and(|m| m != method) {
+            return false;
+        }
+        let mut path = path.trim_end_matches('/').split('/');
+        for segment in self.pattern.trim_end_matches('/').split('/') {
+            if segment == "*" {
+                return true;
+            }
+            match path.next() {
+                Some(actual) if segment.starts_with('{') && segment.ends_with('}') => {
+                    if actual.is_empty() {
+                        return false;
+                    }
+                }
+                Some(actual) if actual == segment => {}
+                _ => return false,
+            }
+        }
+        path.next().is_none()
+    }
+}
+
+impl std::str::FromStr for RateLimitRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("Invalid rate limit rule '{}': {}", s.trim(), reason);
+        let (target, limit) = s.rsplit_once(':').ok_or_else(|| invalid("expected 'route: limit'"))?;
+
+        let target: Vec<&str> = target.split_whitespace().collect();
+        let (method, pattern) = match target.as_slice() {
+            [pattern] => (None, *pattern),
+            ["*", pattern] => (None, *pattern),
+            [method, pattern] => {
+                let method = match method.to_uppercase().as_str() {
+                    "GET" => Method::GET,
+                    "POST" => Method::POST,
+                    "PUT" => Method::PUT,
+                    "PATCH" => Method::PATCH,
+                    "DELETE" => Method::DELETE,
+                    "HEAD" => Method::HEAD,
+                    "OPTIONS" => Method::OPTIONS,
+                    _ => return Err(invalid("unknown method")),
+                };
+                (Some(method), *pattern)
+            }
+            _ => return Err(invalid("expected '[METHOD] /path'")),
+        };
+        if !pattern.starts_with('/') {
+            return Err(invalid("path must start with '/'"));
+        }
+        if pattern.split('/').rev().skip(1).any(|segment| segment == "*") {
+            return Err(invalid("'*' is only allowed as the last segment"));
+        }
+
+        let parts: Vec<&str> = limit.trim().split('/').map(str::trim).collect();
+        let (count, unit, key) = match parts.as_slice() {
+            [count, unit] => (*count, *unit, "ip"),
+            [count, unit, key] => (*count, *unit, *key),
+            _ => return Err(invalid("expected 'count/window[/key]'")),
+        };
+        let limit = count
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| invalid("count must be a positive number"))?;
+        let window = match unit.to_lowercase().as_str() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(60 * 60),
+            "d" | "day" => Duration::from_secs(60 * 60 * 24),
+            _ => return Err(invalid("window must be sec, min, hour or day")),
+        };
+        let key = match key.to_lowercase().as_str() {
+            "ip" => LimitKey::Ip,
+            "user" => LimitKey::User,
+            "global" => LimitKey::Global,
+            _ => return Err(invalid("key must be ip, user or global")),
+        };
+
+        Ok(Self {
+            method,
+            pattern: pattern.to_string(),
+            limit,
+            window,
+            key,
+        })
+    }
+}
+
+/// Parses a rule set, one rule per line or `;`-separated. Blank lines and `#` comments are skipped.
+pub fn parse_rules(s: &str) -> Result<Vec<RateLimitRule>, String> {
+    s.split(['\n', ';'])
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Fixed-window counters for the configured rules.
+pub struct RateLimiter {
+    rules: Vec<RateLimitRule>,
+    windows: Mutex<HashMap<(usize, String), (Instant, u32)>>, // by rule index and client
+}
+
+impl RateLimiter {
+    pub fn new(rules: Vec<RateLimitRule>) -> Self {
+        Self {
+            rules,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Counts a request against every matching rule. When one is exceeded, nothing is
+    /// counted and the time until its window ends is returned.
+    pub fn check(&self, method: &Method, path: &str, ip: &str, user: Option<&str>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let keys: Vec<(usize, String)> = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(method, path))
+            .map(|(i, rule)| {
+                let client = match (rule.key, user) {
+                    (LimitKey::Global, _) => String::new(),
+                    (LimitKey::User, Some(user)) => format!("user:{}", user),
+                    _ => format!("ip:{}", ip),
+                };
+                (i, client)
+            })
+            .collect();
+
+        for key in &keys {
+            let rule = &self.rules[key.0];
+            if let Some((started, count)) = windows.get(key)
+                && now.duration_since(*started) < rule.window
+                && *count >= rule.limit
+            {
+                return Err(rule.window - now.duration_since(*started));
+            }
+        }
+
+        if windows.len() > SWEEP_THRESHOLD {
+            windows.retain(|(i, _), (started, _)| now.duration_since(*started) < self.rules[*i].window);
+        }
+        for key in keys {
+            let window = self.rules[key.0].window;
+            let entry = windows.entry(key).or_insert((now, 0));
+            if now.duration_since(entry.0) >= window {
+                *entry = (now, 0);
+            }
+            entry.1 += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Rejects requests over the configured limits with 429 and `Retry-After`.
+/// Service accounts are not limited: CI runs come in bursts from shared IPs.
+pub async fn rate_limit_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let ip = client_ip(req.headers()).unwrap_or_else(|| "unknown".to_string());
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+    if let Some(token) = token
+        && ServiceAccountController::is_api_token(token)
+        && app_state.controller.service_account.account_of(token).await.is_ok()
+    {
+        return next.run(req).await;
+    }
+    // Only the signature is checked: a revoked token still identifies who is asking
+    let user = token
+        .and_then(|token| app_state.auth.decode_token(token).ok())
+        .map(|claims| claims.sub);
+
+    match app_state
+        .rate_limiter
+        .check(req.method(), req.uri().path(), &ip, user.as_deref())
+    {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+/// Limits anonymous ticket submissions by IP, on top of the configured limits, which
+/// are usually looser. Applied to the submission route only.
+pub async fn portal_rate_limit_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let ip = client_ip(req.headers()).unwrap_or_else(|| "unknown".to_string());
+    // Nested routes see their path without the prefix the rule is written with
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| req.uri().path().to_string(), |uri| uri.path().to_string());
+    match app_state.portal_limiter.check(req.method(), &path, &ip, None) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs().max(1);
+    let mut response = AppError::TooManyRequests(format!("Rate limit exceeded, retry in {}s", secs)).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    response
+}
@@ -0,0 +1,67 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State, connect_info::MockConnectInfo},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use ipnet::IpNet;
+
+const FORWARDED_FOR: &str = "X-Forwarded-For";
+const REAL_IP: &str = "X-Real-IP";
+
+/// Replaces the forwarding headers of the request with the address of its client, in
+/// `X-Real-IP` where `utils::client_ip` reads it. The headers are believed only when
+/// they come from one of the `trusted` proxies (`TRUSTED_PROXIES`); anyone else could
+/// send them to pass as another client, so the peer's own address is used instead.
+/// Without a known peer, as behind a mocked transport, the client stays unknown.
+pub async fn resolve_client_ip(State(trusted): State<Arc<Vec<IpNet>>>, mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .or_else(|| {
+            req.extensions()
+                .get::<MockConnectInfo<SocketAddr>>()
+                .map(|MockConnectInfo(addr)| addr.ip())
+        })
+        .map(|ip| ip.to_canonical());
+    let client = peer.map(|peer| forwarded_client(&req, peer, &trusted));
+    let headers = req.headers_mut();
+    headers.remove(FORWARDED_FOR);
+    headers.remove(REAL_IP);
+    if let Some(client) = client
+        && let Ok(value) = HeaderValue::from_str(&client.to_string())
+    {
+        headers.insert(REAL_IP, value);
+    }
+    next.run(req).await
+}
+
+/// The client behind the trusted proxies: `X-Forwarded-For` is read from the right,
+/// where the proxies closest to us appended, up to the first address that isn't one
+/// of them. Falls back to `X-Real-IP`, then to the peer itself.
+fn forwarded_client(req: &Request, peer: IpAddr, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+    let header = |name: &str| req.headers().get_all(name).iter().filter_map(|v| v.to_str().ok()).collect::<Vec<_>>();
+    let hops: Vec<IpAddr> = header(FORWARDED_FOR)
+        .iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+        .collect();
+    if let Some(client) = hops.iter().rev().find(|ip| !is_trusted(ip)).or(hops.first()) {
+        return *client;
+    }
+    header(REAL_IP)
+        .first()
+        .and_then(|value| value.trim().parse::<IpAddr>().ok())
+        .map_or(peer, |ip| ip.to_canonical())
+}
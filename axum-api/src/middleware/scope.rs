@@ -0,0 +1,113 @@
+//! Route guards declaring which token scope a route needs, next to the routing table:
+//!
+//! ```text
+//! .route("/tickets/{id}", get(get_ticket).route_layer(require_scope("tickets")))
+//! .route("/tickets/check-duplicates", post(check_duplicates).route_layer(require_scope("tickets:read")))
+//! ```
+//!
+//! A bare area asks for read access on `GET` and `HEAD` and write access otherwise,
+//! `area:read` and `area:write` ask for that access whatever the method. Requests with
+//! a user's session pass every guard, they are limited by ACLs alone. Requests with a
+//! service account's API token pass only if one of the token's scopes allows it.
+
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::Request,
+    http::Method,
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use crate::{
+    error::AppError,
+    models::{SCOPE_AREAS, ScopeAccess, TokenScope},
+    utils::BoxFuture,
+};
+
+/// Scopes of the API token a request was made with. Absent for user sessions.
+#[derive(Debug, Clone)]
+pub struct GrantedScopes(pub Vec<TokenScope>);
+
+impl GrantedScopes {
+    pub fn allows(&self, area: &str, access: ScopeAccess) -> bool {
+        self.0.iter().any(|scope| scope.allows(area, access))
+    }
+}
+
+/// What a guarded route needs.
+#[derive(Debug, Clone)]
+pub struct RequireScope {
+    area: &'static str,
+    access: Option<ScopeAccess>, // None to go by the request method
+}
+
+impl RequireScope {
+    fn access(&self, method: &Method) -> ScopeAccess {
+        match self.access {
+            Some(access) => access,
+            None if matches!(*method, Method::GET | Method::HEAD) => ScopeAccess::Read,
+            None => ScopeAccess::Write,
+        }
+    }
+}
+
+/// Guards a route with a scope: `area`, `area:read` or `area:write`. Panics on unknown
+/// areas, so a typo fails when the router is built.
+pub fn require_scope(scope: &'static str) -> RequireScope {
+    let (area, access) = match scope.split_once(':') {
+        Some((area, "read")) => (area, Some(ScopeAccess::Read)),
+        Some((area, "write")) => (area, Some(ScopeAccess::Write)),
+        None => (scope, None),
+        Some(_) => panic!("Invalid route scope '{}'", scope),
+    };
+    assert!(SCOPE_AREAS.contains(&area), "Unknown route scope area '{}'", area);
+    RequireScope { area, access }
+}
+
+impl<S> Layer<S> for RequireScope {
+    type Service = ScopeGuard<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ScopeGuard {
+            inner,
+            requirement: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopeGuard<S> {
+    inner: S,
+    requirement: RequireScope,
+}
+
+impl<S> Service<Request> for ScopeGuard<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let area = self.requirement.area;
+        let access = self.requirement.access(req.method());
+        if let Some(granted) = req.extensions().get::<GrantedScopes>()
+            && !granted.allows(area, access)
+        {
+            let needed = TokenScope {
+                area: Some(area.to_string()),
+                access,
+            };
+            let error = AppError::Authorization(format!("Token lacks the {} scope", needed));
+            return Box::pin(async move { Ok(error.into_response()) });
+        }
+        Box::pin(self.inner.call(req))
+    }
+}
@@ -0,0 +1,246 @@
+//! `migrate-data --from <conn> --to <conn>`: copies every entity from one backend
+//! into another, e.g. a demo into ArangoDB, without starting the server.
+//!
+//! A connection is an ArangoDB URL (`http://` or `https://`, the database named by
+//! `--from-db`/`--to-db`, `DB_NAME` by default), a path to an NDJSON dump ending in
+//! `.ndjson`, or `memory`. The in-memory backend starts empty, so a running demo is
+//! moved through its dump: `GET /api/mgmt/export` into a file, then migrated from it.
+//!
+//! Records go through `db::dump` in restorable order and only into an empty target.
+//! Once copied, the target is read back and its counts compared to the source's.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    sync::mpsc,
+};
+
+use crate::{
+    config::AppConfig,
+    db::{
+        DatabaseInterface,
+        arangodb::{ArangoDatabase, connect_or_create_db_no_auth},
+        dump::{self, Importer, Record},
+        inmemory::InMemoryDatabase,
+    },
+    error::AppError,
+};
+
+pub const COMMAND: &str = "migrate-data";
+
+// Records copied between two progress lines
+const PROGRESS_EVERY: usize = 1000;
+
+/// Where records are copied from or to.
+pub enum Endpoint {
+    Database(Arc<dyn DatabaseInterface>),
+    Dump(PathBuf),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MigrateArgs {
+    pub from: String,
+    pub to: String,
+    pub from_db: Option<String>,
+    pub to_db: Option<String>,
+}
+
+impl MigrateArgs {
+    /// Parses the arguments following the command name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, AppError> {
+        let (mut from, mut to, mut from_db, mut to_db) = (None, None, None, None);
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let slot = match flag.as_str() {
+                "--from" => &mut from,
+                "--to" => &mut to,
+                "--from-db" => &mut from_db,
+                "--to-db" => &mut to_db,
+                _ => return Err(AppError::BadRequest(format!("Unknown argument {}", flag))),
+            };
+            *slot = Some(args.next().ok_or_else(|| AppError::BadRequest(format!("{} needs a value", flag)))?);
+        }
+        match (from, to) {
+            (Some(from), Some(to)) => Ok(Self { from, to, from_db, to_db }),
+            _ => Err(AppError::BadRequest(format!(
+                "Usage: {} --from <conn> --to <conn> [--from-db <name>] [--to-db <name>]",
+                COMMAND
+            ))),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Connects to the backend named by a connection string, and sets it up.
+    pub async fn open(connection: &str, database_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if connection.ends_with(".ndjson") {
+            return Ok(Endpoint::Dump(PathBuf::from(connection)));
+        }
+
+        let mut database: Option<Arc<dyn DatabaseInterface>> = None;
+        if connection.starts_with("http") {
+            let conn = arangors::Connection::establish_without_auth(connection).await?;
+            let db = connect_or_create_db_no_auth(&conn, database_name).await?;
+            database = Some(Arc::new(ArangoDatabase::new(db)));
+        }
+        let db: Arc<dyn DatabaseInterface> = match database {
+            Some(db) => db,
+            None if connection == "memory" => Arc::new(InMemoryDatabase::new()),
+            None => {
+                return Err(format!("Unknown connection {}, expected a URL, an .ndjson file or memory", connection).into());
+            }
+        };
+        db.initialize().await?;
+        Ok(Endpoint::Database(db))
+    }
+}
+
+/// Records copied, by kind.
+pub type MigrationReport = BTreeMap<&'static str, usize>;
+
+/// Runs the command with the arguments following its name.
+pub async fn run(config: &AppConfig, args: impl IntoIterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let args = MigrateArgs::parse(args)?;
+    let from = Endpoint::open(&args.from, args.from_db.as_deref().unwrap_or(&config.database_name)).await?;
+    let to = Endpoint::open(&args.to, args.to_db.as_deref().unwrap_or(&config.database_name)).await?;
+    log::info!("Migrating data from {} to {}", args.from, args.to);
+
+    let report = migrate(&from, &to).await?;
+    for (kind, count) in &report {
+        log::info!("  {}: {}", kind, count);
+    }
+    log::info!("Migration complete, {} records copied", report.values().sum::<usize>());
+    Ok(())
+}
+
+/// Copies every record from one endpoint into the other, which must be empty.
+pub async fn migrate(from: &Endpoint, to: &Endpoint) -> Result<MigrationReport, AppError> {
+    let mut records = match from {
+        Endpoint::Database(db) => dump::export(db.clone()),
+        Endpoint::Dump(path) => read_dump(path).await?,
+    };
+    let mut sink = match to {
+        Endpoint::Database(db) => Sink::Database(Importer::new(db.as_ref()).await?),
+        Endpoint::Dump(path) => Sink::Dump(create_dump(path).await?),
+    };
+
+    let mut report = MigrationReport::new();
+    let mut copied = 0;
+    let mut ended = false;
+    while let Some(record) = records.recv().await {
+        let record = record?;
+        if let Record::End { records } = record {
+            if records != copied {
+                return Err(AppError::Validation(format!("Source ends after {} records, {} were read", records, copied)));
+            }
+            ended = true;
+        } else {
+            if !report.contains_key(record.kind()) {
+                log::info!("  Copying {} records...", record.kind());
+            }
+            *report.entry(record.kind()).or_default() += 1;
+            copied += 1;
+            if copied % PROGRESS_EVERY == 0 {
+                log::info!("  {} records copied", copied);
+            }
+        }
+        sink.write(record).await?;
+    }
+    if !ended {
+        return Err(AppError::Validation(format!("Source is incomplete, it stops after {} records", copied)));
+    }
+    sink.finish().await?;
+
+    if let Endpoint::Database(db) = to {
+        verify(db.clone(), &report).await?;
+    }
+    Ok(report)
+}
+
+enum Sink<'a> {
+    Database(Importer<'a>),
+    Dump(BufWriter<File>),
+}
+
+impl Sink<'_> {
+    async fn write(&mut self, record: Record) -> Result<(), AppError> {
+        match self {
+            Sink::Database(importer) => importer.import(record).await,
+            Sink::Dump(file) => {
+                let mut line = serde_json::to_vec(&record)?;
+                line.push(b'\n');
+                file.write_all(&line).await.map_err(|e| AppError::Internal(e.into()))
+            }
+        }
+    }
+
+    async fn finish(self) -> Result<(), AppError> {
+        match self {
+            Sink::Database(importer) => importer.finish().map(|_| ()),
+            Sink::Dump(mut file) => file.flush().await.map_err(|e| AppError::Internal(e.into())),
+        }
+    }
+}
+
+/// Reads a dump file in the background, the same way `dump::export` reads a database.
+async fn read_dump(path: &Path) -> Result<mpsc::Receiver<Result<Record, AppError>>, AppError> {
+    let file = File::open(path)
+        .await
+        .map_err(|e| AppError::Validation(format!("Dump {} can't be read: {}", path.display(), e)))?;
+    let (records, receiver) = mpsc::channel(PROGRESS_EVERY);
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(file).lines();
+        let mut line = 0;
+        loop {
+            line += 1;
+            let record = match lines.next_line().await {
+                Ok(Some(text)) => Record::from_line(text.as_bytes(), line).transpose(),
+                Ok(None) => return,
+                Err(e) => Some(Err(AppError::Validation(format!("Line {}: {}", line, e)))),
+            };
+            let Some(record) = record else { continue };
+            let failed = record.is_err();
+            if records.send(record).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Creates the file a dump is written to, never overwriting one.
+async fn create_dump(path: &Path) -> Result<BufWriter<File>, AppError> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+        .map(BufWriter::new)
+        .map_err(|e| AppError::Conflict(format!("Dump {} can't be created: {}", path.display(), e)))
+}
+
+/// Reads the target back and compares what it holds to what was copied.
+async fn verify(db: Arc<dyn DatabaseInterface>, copied: &MigrationReport) -> Result<(), AppError> {
+    let mut stored = MigrationReport::new();
+    let mut records = dump::export(db);
+    while let Some(record) = records.recv().await {
+        let record = record?;
+        if !matches!(record, Record::End { .. }) {
+            *stored.entry(record.kind()).or_default() += 1;
+        }
+    }
+    if &stored != copied {
+        return Err(AppError::Internal(anyhow::anyhow!(
+            "Target holds {:?} after copying {:?}",
+            stored,
+            copied
+        )));
+    }
+    Ok(())
+}
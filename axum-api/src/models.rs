@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use crate::schema;
 use bitflags::bitflags;
+use utoipa::ToSchema;
 
 bitflags! {
     // derive common traits for easier usage
@@ -26,14 +27,81 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+impl Permissions {
+    /// What is left of `grants` once `denies` are taken out: a deny always overrides
+    /// a grant of the same permission, wherever either comes from.
+    pub fn effective(grants: Self, denies: Self) -> Self {
+        grants.difference(denies)
+    }
+}
+
+/// Lists granting permissions, and lists denying them. See `Permissions::effective`
+/// for how they combine.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
 pub struct AccessControlStore {
     pub list: Vec<AccessControlList>,
+    #[serde(default)]
+    pub deny: Vec<AccessControlList>,
     pub last_mod_date: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+impl AccessControlStore {
+    /// Whether any of the principals (a user and their groups) is granted `permission`
+    /// and none of them is denied it.
+    pub fn allows(&self, principals: &[String], permission: Permissions) -> bool {
+        self.effective(principals).contains(permission)
+    }
+
+    /// Permissions granted to any of the principals and denied to none.
+    pub fn effective(&self, principals: &[String]) -> Permissions {
+        Permissions::effective(self.granted(principals), self.denied(principals))
+    }
+
+    /// Permissions granted to any of the principals, denied or not.
+    pub fn granted(&self, principals: &[String]) -> Permissions {
+        union_of(&self.list, principals)
+    }
+
+    /// Permissions denied to any of the principals.
+    pub fn denied(&self, principals: &[String]) -> Permissions {
+        union_of(&self.deny, principals)
+    }
+
+    /// Permissions the principal is granted itself, not through its groups.
+    pub fn granted_to(&self, principal: &str) -> Permissions {
+        union_of(&self.list, &[principal.to_string()])
+    }
+
+    /// Grants the principal exactly `permissions`: it is moved to the list granting
+    /// those, lists left without principals are dropped.
+    pub fn set_permissions(&mut self, principal: &str, permissions: Permissions) {
+        for acl in self.list.iter_mut() {
+            acl.principals.retain(|p| p != principal);
+        }
+        self.list.retain(|acl| !acl.principals.is_empty());
+        if permissions.is_empty() {
+            return;
+        }
+        match self.list.iter_mut().find(|acl| acl.permissions == permissions) {
+            Some(acl) => acl.principals.push(principal.to_string()),
+            None => self.list.push(AccessControlList {
+                permissions,
+                principals: vec![principal.to_string()],
+            }),
+        }
+    }
+}
+
+fn union_of(lists: &[AccessControlList], principals: &[String]) -> Permissions {
+    lists
+        .iter()
+        .filter(|acl| acl.principals.iter().any(|p| principals.contains(p)))
+        .fold(Permissions::NONE, |granted, acl| granted | acl.permissions)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
 pub struct AccessControlList {
+    #[schema(value_type = String, example = "READ | MODIFY")]
     pub permissions: Permissions,
     pub principals: Vec<String>
 }
@@ -44,6 +112,8 @@ pub struct PersonalInfo {
     pub gender: String,
     pub job_title: String,
     pub manager: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>, // IANA name, e.g. `Europe/Kyiv`, UTC if unset
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -55,12 +125,124 @@ pub struct User {
     pub deactivated: bool,
     pub personal: PersonalInfo,
     pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub two_factor: Option<TwoFactor>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub verified: bool, // email ownership confirmed
+    #[serde(default)]
+    pub token_generation: u64, // bumping it invalidates every token issued before
+    #[serde(default)]
+    pub calendar_token: Option<String>, // sha256 of the token of the user's calendar feed
+    #[serde(default)]
+    pub service_account: bool, // automation, authenticates with API tokens only
+    #[serde(default)]
+    pub api_tokens: Vec<ApiToken>,
+    #[serde(default)]
+    pub external_ids: Vec<String>, // `provider:subject` of the user's OIDC and LDAP identities
+    #[serde(default)]
+    pub renamed_to: Option<String>, // set on the deactivated alias a rename leaves at the old username
+}
+
+/// A long-lived credential of a service account. Only the hash of the token is stored.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub hash: String, // sha256 of the token
+    pub scopes: Vec<TokenScope>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Parts of the API that route guards and token scopes name.
+pub const SCOPE_AREAS: &[&str] = &["account", "tickets", "projects", "milestones", "graphql"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeAccess {
+    Read,
+    Write, // implies read
+}
+
+/// What an API token may do on top of the account's own permissions: read or write
+/// one area of the API (`tickets:write`), or every area (`*:read`, or just `read`).
+/// Stored as that string.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct TokenScope {
+    pub area: Option<String>, // None for every area
+    pub access: ScopeAccess,
+}
+
+impl TokenScope {
+    pub fn allows(&self, area: &str, access: ScopeAccess) -> bool {
+        self.area.as_deref().is_none_or(|a| a == area) && (self.access == ScopeAccess::Write || access == ScopeAccess::Read)
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (area, access) = s.split_once(':').unwrap_or(("*", s.as_str()));
+        let access = match access {
+            "read" => ScopeAccess::Read,
+            "write" => ScopeAccess::Write,
+            _ => return Err(format!("Invalid scope '{}', expected 'area:read' or 'area:write'", s)),
+        };
+        let area = match area {
+            "*" => None,
+            area if SCOPE_AREAS.contains(&area) => Some(area.to_string()),
+            area => {
+                return Err(format!(
+                    "Unknown scope area '{}', expected one of {} or '*'",
+                    area,
+                    SCOPE_AREAS.join(", ")
+                ));
+            }
+        };
+        Ok(Self { area, access })
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let access = match self.access {
+            ScopeAccess::Read => "read",
+            ScopeAccess::Write => "write",
+        };
+        write!(f, "{}:{}", self.area.as_deref().unwrap_or("*"), access)
+    }
+}
+
+impl TryFrom<String> for TokenScope {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TokenScope> for String {
+    fn from(scope: TokenScope) -> Self {
+        scope.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TwoFactor {
+    pub secret: String, // base32-encoded TOTP secret, sealed when encryption keys are configured
+    pub enabled: bool,  // false until the user confirms enrollment with a valid code
+    pub recovery_codes: Vec<String>, // sha256 hashes, each code is single-use
+    #[serde(default)]
+    pub last_step: Option<u64>, // time step of the last code accepted, codes up to it are spent
 }
 
 impl From<crate::schema::User> for User {
     fn from(src: schema::User) -> Self {
         let mut metadata = HashMap::new();
-        metadata.insert("registered_at".to_string(), Utc::now().to_rfc3339());
+        metadata.insert("registered_at".to_string(), crate::utils::time::rfc3339(Utc::now()));
 
         Self {
             username: src.username,
@@ -75,31 +257,466 @@ impl From<crate::schema::User> for User {
 pub struct Project {
     pub id: uuid::Uuid,
     pub acl: AccessControlStore,
-    pub tickets: Vec<TicketGroup>
+    pub tickets: Vec<TicketGroup>,
+    #[serde(default)]
+    pub severities: Vec<Severity>, // the project's scale, `Severity::default_scale()` when empty
+    #[serde(default)]
+    pub custom_fields: Vec<CustomFieldDefinition>, // extra fields of the project's tickets
+    #[serde(default)]
+    pub assignment_rules: Vec<AssignmentRule>, // in order, the first matching one applies
+    #[serde(default)]
+    pub escalation_policies: Vec<EscalationPolicy>, // in order, the first matching one applies
+    #[serde(default)]
+    pub owner: Option<String>, // a user, always has ROOT; projects from before owners have none
+    #[serde(default)]
+    pub parent_id: Option<String>, // the project this one is a sub-project of
+    #[serde(default)]
+    pub portal: Option<PublicPortal>, // none unless tickets can be filed without an account
+}
+
+impl Project {
+    pub fn severity_scale(&self) -> Vec<Severity> {
+        if self.severities.is_empty() {
+            Severity::default_scale()
+        } else {
+            self.severities.clone()
+        }
+    }
+}
+
+/// Lets people without accounts file tickets in the project through
+/// `POST /api/public/projects/{slug}/tickets`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PublicPortal {
+    pub slug: String, // in the portal's URL, unique among projects
+    pub token_hash: Option<String>, // sha256 of the token submissions must carry, none if open
+    pub captcha: bool, // submissions must carry a captcha solution
+}
+
+impl PublicPortal {
+    /// The synthetic principal tickets filed through the portal are created by. Usernames
+    /// can't contain `:`, so it is never a user's.
+    pub fn reporter(&self) -> String {
+        format!("portal:{}", self.slug)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TicketGroup {
     pub prefix: String,
-    pub acl: AccessControlStore
+    pub acl: AccessControlStore,
+    #[serde(default)]
+    pub inheritance: AclInheritance, // how `acl` combines with the project's, see `acl`
+}
+
+/// How a ticket group's ACL combines with its project's.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AclInheritance {
+    /// Principals keep what the project grants them, plus what the group does
+    #[default]
+    Extend,
+    /// Principals keep only what both the project and the group grant them
+    Narrow,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Boolean,
+    Date, // `YYYY-MM-DD`
+    Select,
+}
+
+/// A field tickets of a project carry in `custom_fields`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct CustomFieldDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: CustomFieldType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub options: Vec<String>, // the values a `select` field can take
+}
+
+/// Which new tickets an assignment rule applies to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssignmentCondition {
+    Any,
+    /// Tickets at one of the severity levels.
+    Severity { levels: Vec<u8> },
+    /// Tickets whose custom field holds the value, like a `component` select field.
+    Label { field: String, value: String },
+}
+
+/// Who tickets matching an assignment rule are assigned to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssignmentTarget {
+    /// A user or group.
+    Principal { principal: String },
+    /// Members of the group in turn, after the one assigned the project's latest ticket.
+    RoundRobin { group: String },
+}
+
+/// Assigns new tickets of a project created without an assignee.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct AssignmentRule {
+    pub name: String,
+    pub when: AssignmentCondition,
+    pub assign: AssignmentTarget,
+}
+
+/// Escalates unresolved tickets at `severity` or more severe (a lower level) once
+/// nothing happened to them for `idle_hours`: the group's members are notified, and
+/// the ticket is assigned to the group if `reassign` is set.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct EscalationPolicy {
+    pub name: String,
+    pub severity: u8,
+    pub idle_hours: u32,
+    pub group: String,
+    #[serde(default)]
+    pub reassign: bool,
+}
+
+/// A level of a severity scale, 1 being the most severe.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct Severity {
+    pub level: u8,
+    pub label: String,
+}
+
+impl Severity {
+    pub fn new(level: u8, label: &str) -> Self {
+        Self {
+            level,
+            label: label.to_string(),
+        }
+    }
+
+    /// Used by tickets outside projects and projects without a scale of their own.
+    pub fn default_scale() -> Vec<Self> {
+        vec![
+            Self::new(1, "critical"),
+            Self::new(2, "major"),
+            Self::new(3, "minor"),
+            Self::new(4, "trivial"),
+        ]
+    }
+
+    /// Picks the level from `scale`. The label is optional, but must be the level's
+    /// (in any case) when given.
+    pub fn on_scale(scale: &[Severity], level: u8, label: &str) -> Result<Self, String> {
+        let Some(severity) = scale.iter().find(|s| s.level == level) else {
+            let levels: Vec<String> = scale.iter().map(|s| format!("{} ({})", s.level, s.label)).collect();
+            return Err(format!("Severity must be one of {}", levels.join(", ")));
+        };
+        let label = label.trim();
+        if !label.is_empty() && !label.eq_ignore_ascii_case(&severity.label) {
+            return Err(format!("Severity {} is labelled '{}'", level, severity.label));
+        }
+        Ok(severity.clone())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ticket {
     pub id: i64,
     pub title: String,
-    pub severity: (u8, String),
+    pub severity: Severity,
     pub description: String,
     pub created_by: String, // only user
     pub assigned_to: String, // can be group
     pub mentioned: Vec<String>, // principals
     pub last_modification: DateTime<Utc>,
     pub creation_date: DateTime<Utc>,
+    #[serde(default)]
+    pub status: TicketStatus,
+    #[serde(default)]
+    pub project: Option<String>, // project id, tickets created before projects had none
+    #[serde(default)]
+    pub resolved_at: Option<DateTime<Utc>>, // set when the ticket was resolved or closed
+    #[serde(default)]
+    pub custom_fields: HashMap<String, serde_json::Value>, // as defined by the project
+    #[serde(default)]
+    pub rank: String, // order within its board column, see `utils::rank`, empty if never ranked
+    #[serde(default)]
+    pub milestone: Option<String>, // milestone id, of the ticket's project
+    #[serde(default)]
+    pub escalated_at: Option<DateTime<Utc>>, // last escalation, once per period of inactivity
+    #[serde(default)]
+    pub message_id: Option<String>, // of the email the ticket was filed from
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub ticket_group: Option<String>, // prefix of one of its project's ticket groups
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[serde(rename_all = "snake_case")]
+pub enum TicketStatus {
+    #[default]
+    Open,
+    InProgress,
+    Resolved,
+    Closed,
+}
+
+impl TicketStatus {
+    /// Every status, in workflow order.
+    pub const ALL: [TicketStatus; 4] = [Self::Open, Self::InProgress, Self::Resolved, Self::Closed];
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneState {
+    Open,
+    Closed,
+}
+
+/// A sprint or release of a project, tickets are planned into it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct Milestone {
+    pub id: String, // UUIDv7
+    pub project: String,
+    pub name: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate, // last day, inclusive
+    pub state: MilestoneState,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// A reply on a ticket, in the app or by email.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct Comment {
+    pub id: String, // UUIDv7
+    pub ticket: i64,
+    pub author: String, // username, or the sender's address for emails from unknown senders
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub message_id: Option<String>, // of the email the comment came from
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatPlatform {
+    Slack,
+    Teams,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatEvent {
+    TicketCreated,
+    HighSeverity, // a ticket created at `high_severity` or more severe
+    SlaBreach,    // a ticket escalated by one of the project's escalation policies
+}
+
+/// Where events of a project are posted: an incoming webhook of a Slack or Teams channel.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct ChatChannel {
+    pub project: String,
+    pub platform: ChatPlatform,
+    pub webhook_url: String,
+    pub events: Vec<ChatEvent>,
+    pub high_severity: u8, // a level of the project's scale
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketEventKind {
+    Created,
+    Moved, // to another position or status on the board
+}
+
+/// A change to a ticket, published to live subscribers (gRPC `WatchTickets`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketEvent {
+    pub kind: TicketEventKind,
+    pub ticket: Ticket,
+    pub actor: String,
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub previous_status: Option<TicketStatus>, // before a move, none for a created ticket
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Group {
     pub gid: String,
     pub name: String,
-    pub principals: Vec<String>
+    pub principals: Vec<String>,
+    #[serde(default)]
+    pub renamed_to: Option<String>, // set on the memberless alias a rename leaves at the old id
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Session {
+    pub id: String,
+    pub username: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(default)]
+    pub token_generation: u64, // user's token generation when the session was opened
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Invite {
+    pub id: String, // sha256 of the invite token, the token itself is never stored
+    pub groups: Vec<String>, // group ids the invitee joins on registration
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_by: Option<String>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+/// Response of the first request made with an `Idempotency-Key`, replayed on retries.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IdempotencyRecord {
+    pub id: String,          // sha256 of "<principal>:<key>"
+    pub fingerprint: String, // sha256 of method, path and body of the first request
+    pub status: Option<u16>, // None while the first request is still running
+    pub content_type: Option<String>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventKind {
+    LoginFailure,
+    TokenValidationFailure,
+    ApiKeyMisuse,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SecurityEvent {
+    pub id: String,
+    pub kind: SecurityEventKind,
+    pub username: Option<String>,
+    pub ip: Option<String>,
+    pub detail: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    DirectMessage,
+    Assignment,
+    Mention,
+    Escalation,
+}
+
+/// Something a user has yet to see, e.g. a direct message sent while they were offline.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Notification {
+    pub id: String, // UUIDv7, so ids sort by creation time
+    pub recipient: String,
+    pub kind: NotificationKind,
+    pub payload: serde_json::Value,
+    pub link: Option<String>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    TicketCreated,
+    StatusChanged,
+    CommentAdded,
+}
+
+/// Something done to a ticket, as shown in the activity feeds of its project and of
+/// the user who did it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Activity {
+    pub id: String, // UUIDv7, so ids sort by time
+    pub kind: ActivityKind,
+    pub actor: String,
+    pub ticket: i64,
+    pub title: String,           // of the ticket at the time
+    pub project: Option<String>, // same
+    pub from_status: Option<TicketStatus>, // status changes only
+    pub to_status: Option<TicketStatus>,   // same
+    pub comment: Option<String>, // id of the added comment
+    pub at: DateTime<Utc>,
+}
+
+/// A ticket its owner is still writing, saved as they type so a reload doesn't lose it.
+/// The payload is whatever the form held, only checked once the ticket is created.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Draft {
+    pub id: String, // sha256 of "<owner>:<key>"
+    pub owner: String,
+    pub key: String, // chosen by the client, e.g. one per open form
+    pub payload: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Settings a client keeps for its user, e.g. a theme or the widths of board columns,
+/// under a namespace of its choosing. `version` counts the saves: a save names the
+/// version it replaces, so that two clients can't overwrite each other unknowingly.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Preference {
+    pub id: String, // sha256 of "<owner>:<namespace>"
+    pub owner: String,
+    pub namespace: String,
+    pub value: serde_json::Value,
+    pub version: u64, // 1 once created
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A link letting anyone holding it read one ticket without logging in, until it
+/// expires or is revoked. The link's token is signed, only its id is stored.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct TicketShare {
+    pub id: String,
+    pub ticket: i64,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// When a user last looked at a ticket. Tickets changed since are unread for them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ReadReceipt {
+    pub ticket: i64,
+    pub username: String,
+    pub seen_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    Pending,
+    Delivered,
+    Failed, // gave up after the maximum number of attempts, until replayed
+}
+
+/// A published domain event, stored until every subscriber has handled it.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OutboxEntry {
+    pub id: String, // UUIDv7, so ids sort by publication time
+    #[schema(value_type = Object)]
+    pub event: crate::events::DomainEvent,
+    pub status: OutboxStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
 }
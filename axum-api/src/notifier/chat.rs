@@ -0,0 +1,166 @@
+//! Messages posted to Slack and Teams channels through their incoming webhooks.
+
+use std::{sync::RwLock, time::Duration};
+
+use anyhow::anyhow;
+use serde_json::{Value, json};
+
+use crate::{
+    error::AppError,
+    models::{ChatEvent, ChatPlatform, EscalationPolicy, Ticket},
+    utils::BoxFuture,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Accent of messages about urgent tickets, and of the others
+const URGENT_COLOR: &str = "D93F0B";
+const DEFAULT_COLOR: &str = "0078D7";
+
+/// Delivery of formatted messages to chat webhooks.
+pub trait ChatSender: Send + Sync {
+    fn post<'a>(&'a self, webhook_url: &'a str, payload: Value) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// Posts messages as JSON, as both Slack and Teams webhooks expect.
+pub struct HttpChatSender {
+    client: reqwest::Client,
+}
+
+impl Default for HttpChatSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpChatSender {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("HTTP client builds with a timeout only");
+        Self { client }
+    }
+}
+
+impl ChatSender for HttpChatSender {
+    fn post<'a>(&'a self, webhook_url: &'a str, payload: Value) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(webhook_url)
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Chat webhook unreachable: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::Internal(anyhow!(
+                    "Chat webhook answered {}",
+                    response.status()
+                )));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Keeps posted messages in memory, for tests and local development.
+#[derive(Default)]
+pub struct InMemoryChatSender {
+    posted: RwLock<Vec<(String, Value)>>, // webhook URL and payload
+}
+
+impl InMemoryChatSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn posted(&self) -> Vec<(String, Value)> {
+        self.posted.read().unwrap().clone()
+    }
+}
+
+impl ChatSender for InMemoryChatSender {
+    fn post<'a>(&'a self, webhook_url: &'a str, payload: Value) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            self.posted.write().unwrap().push((webhook_url.to_string(), payload));
+            Ok(())
+        })
+    }
+}
+
+/// Slack treats these as control characters in message text.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// The message about a ticket in the platform's format: a headline and the
+/// ticket's details, with the policy it breached for `SlaBreach`.
+pub fn format_message(
+    platform: ChatPlatform,
+    event: ChatEvent,
+    ticket: &Ticket,
+    policy: Option<&EscalationPolicy>,
+) -> Value {
+    let headline = match event {
+        ChatEvent::TicketCreated => format!("New ticket #{}: {}", ticket.id, ticket.title),
+        ChatEvent::HighSeverity => format!("High severity ticket #{}: {}", ticket.id, ticket.title),
+        ChatEvent::SlaBreach => format!("SLA breached on ticket #{}: {}", ticket.id, ticket.title),
+    };
+    let mut facts = vec![
+        ("Severity", ticket.severity.label.clone()),
+        (
+            "Assigned to",
+            if ticket.assigned_to.is_empty() {
+                "nobody".to_string()
+            } else {
+                ticket.assigned_to.clone()
+            },
+        ),
+        ("Created by", ticket.created_by.clone()),
+    ];
+    if let Some(policy) = policy {
+        facts.push((
+            "Policy",
+            format!(
+                "{}: idle for {}h, escalated to {}",
+                policy.name, policy.idle_hours, policy.group
+            ),
+        ));
+    }
+
+    match platform {
+        ChatPlatform::Slack => json!({
+            "text": headline,
+            "blocks": [
+                {
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": format!("*{}*", slack_escape(&headline)) },
+                },
+                {
+                    "type": "section",
+                    "fields": facts
+                        .iter()
+                        .map(|(name, value)| json!({
+                            "type": "mrkdwn",
+                            "text": format!("*{}*\n{}", name, slack_escape(value)),
+                        }))
+                        .collect::<Vec<_>>(),
+                },
+            ],
+        }),
+        ChatPlatform::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": headline,
+            "title": headline,
+            "themeColor": if event == ChatEvent::TicketCreated { DEFAULT_COLOR } else { URGENT_COLOR },
+            "sections": [{
+                "facts": facts
+                    .iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+    }
+}
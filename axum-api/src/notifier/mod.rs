@@ -0,0 +1,61 @@
+pub mod chat;
+
+use std::sync::RwLock;
+
+use crate::{error::AppError, utils::BoxFuture};
+
+/// Outgoing message to a user (email address or username, depending on the transport).
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// Delivery channel for user-facing messages (verification links etc.)
+pub trait Notifier: Send + Sync {
+    fn send<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// Default notifier, only writes messages to the application log.
+#[derive(Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn send<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            log::info!(
+                "Notification -> to: {}, subject: {}, body: {}",
+                notification.recipient,
+                notification.subject,
+                notification.body
+            );
+            Ok(())
+        })
+    }
+}
+
+/// Keeps sent notifications in memory, for tests and local development.
+#[derive(Default)]
+pub struct InMemoryNotifier {
+    sent: RwLock<Vec<Notification>>,
+}
+
+impl InMemoryNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.read().unwrap().clone()
+    }
+}
+
+impl Notifier for InMemoryNotifier {
+    fn send<'a>(&'a self, notification: Notification) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            self.sent.write().unwrap().push(notification);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,154 @@
+//! Background jobs of the server, run while it is up: periodic ones, the outbox
+//! dispatcher, and the relay of ticket events to chat channels.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    error::AppError,
+    events::DomainEvent,
+    models::{ChatEvent, EscalationPolicy, Ticket, TicketEventKind},
+    notifier::chat::format_message,
+    search::REBUILD_INTERVAL,
+    state::AppState,
+};
+
+/// How often deleted entities past their retention are purged.
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Starts the chat relay, the outbox dispatcher if enabled, the purge of the trash,
+/// the rebuilds of the search index if there is one, and the jobs whose interval
+/// is configured.
+pub fn spawn(app_state: Arc<AppState>) {
+    spawn_chat_relay(app_state.clone());
+    if app_state.config.outbox {
+        spawn_outbox_dispatcher(app_state.clone());
+    }
+    spawn_trash_purge(app_state.clone());
+    if app_state.controller.search.index.is_some() {
+        spawn_search_rebuild(app_state.clone());
+    }
+    let interval = app_state.config.escalation_interval;
+    if interval == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(Duration::from_secs(interval));
+        loop {
+            ticks.tick().await;
+            if let Err(e) = escalate_stale_tickets(&app_state, Utc::now()).await {
+                log::error!("Escalation of stale tickets failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Applies the projects' escalation policies as of `now`, records every escalation
+/// in the audit log and notifies the escalation groups. Returns how many tickets
+/// were escalated.
+pub async fn escalate_stale_tickets(app_state: &AppState, now: DateTime<Utc>) -> Result<usize, AppError> {
+    let escalated = app_state.controller.ticket.escalate_stale(now).await?;
+    for (ticket, policy) in &escalated {
+        log::warn!(
+            target: "audit",
+            "Escalation -> ticket {} (severity {}) escalated to {} by policy '{}'{}",
+            ticket.id,
+            ticket.severity.level,
+            policy.group,
+            policy.name,
+            if policy.reassign { ", reassigned" } else { "" }
+        );
+        app_state.controller.notification.ticket_escalated(ticket, policy).await;
+        post_to_chat(app_state, ticket, ChatEvent::SlaBreach, Some(policy)).await;
+    }
+    Ok(escalated.len())
+}
+
+/// Delivers the events stored in the outbox, see `events`.
+fn spawn_outbox_dispatcher(app_state: Arc<AppState>) {
+    let interval = Duration::from_secs(app_state.config.outbox_interval.max(1));
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = app_state.controller.outbox.dispatch_pending().await {
+                log::error!("Dispatch of the outbox failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Removes for good what was deleted longer than the retention ago.
+fn spawn_trash_purge(app_state: Arc<AppState>) {
+    let retention = TimeDelta::days(i64::from(app_state.config.soft_delete_retention));
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(TRASH_PURGE_INTERVAL);
+        loop {
+            ticks.tick().await;
+            match app_state.controller.trash.purge_expired(retention, Utc::now()).await {
+                Ok(purged) => {
+                    for entity in purged {
+                        log::info!(
+                            target: "audit",
+                            "Purge -> deleted {:?} {} removed for good, deleted at {}",
+                            entity.kind,
+                            entity.id,
+                            entity.deleted_at
+                        );
+                    }
+                }
+                Err(e) => log::error!("Purge of deleted entities failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Fills the search index from the database, now and then every `REBUILD_INTERVAL`.
+fn spawn_search_rebuild(app_state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(REBUILD_INTERVAL);
+        loop {
+            ticks.tick().await;
+            match app_state.controller.search.rebuild_index().await {
+                Ok(count) => log::info!("Search index rebuilt with {} entities", count),
+                Err(e) => log::error!("Rebuild of the search index failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Posts new tickets to their project's chat channel.
+fn spawn_chat_relay(app_state: Arc<AppState>) {
+    let mut events = app_state.events.stream();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(DomainEvent::TicketUpdated(event)) if event.kind == TicketEventKind::Created => {
+                    post_to_chat(&app_state, &event.ticket, ChatEvent::TicketCreated, None).await
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => log::warn!("Chat relay missed {} ticket events", missed),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Posts the ticket to its project's chat channel if the channel takes the event.
+/// Never fails: a channel being down doesn't affect the ticket.
+pub async fn post_to_chat(app_state: &AppState, ticket: &Ticket, event: ChatEvent, policy: Option<&EscalationPolicy>) {
+    let (channel, event) = match app_state.controller.chat.route(ticket, event).await {
+        Ok(Some(route)) => route,
+        Ok(None) => return,
+        Err(e) => {
+            log::error!("Chat channel of ticket {} could not be read: {}", ticket.id, e);
+            return;
+        }
+    };
+    let message = format_message(channel.platform, event, ticket, policy);
+    if let Err(e) = app_state.chat.post(&channel.webhook_url, message).await {
+        log::error!("Failed to post ticket {} to chat of project {}: {}", ticket.id, channel.project, e);
+    }
+}
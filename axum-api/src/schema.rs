@@ -1,6 +1,21 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use std::collections::HashMap;
+
+use axum::{
+    http::{HeaderValue, StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    controllers::two_factor_controller::TwoFactorController,
+    db::{AssigneeCount, BackendInfo, TicketDayCount, breaker::BreakerStats, limited::PoolStats},
+    models,
+    startup::StartupPhase,
+    utils::http_date,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct User {
@@ -12,12 +27,39 @@ pub struct User {
 pub struct RegisterRequest {
     pub user: String,
     pub password: String,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    #[serde(default)]
+    pub groups: Vec<String>,
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateInviteResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
-    pub user: String,
+    pub user: String, // username, or the user's email
     pub password: String,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -29,6 +71,76 @@ pub struct ImpersonateRequest {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     pub token: String,
+    pub expires_at: usize,
+    pub refresh_token: String,
+}
+
+/// Returned by login instead of a token when the user has 2FA enabled.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorChallenge {
+    pub challenge_token: String,
+    pub expires_at: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    pub code: String,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorEnrollResponse {
+    pub otpauth_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct SessionInfo {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn new(session: models::Session, current_session: &str) -> Self {
+        Self {
+            current: session.id == current_session,
+            id: session.id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct RevokedSessions {
+    pub revoked: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UnreadCount {
+    pub unread: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MarkedRead {
+    pub marked: usize,
 }
 
 #[derive(ToSchema)]
@@ -54,4 +166,1148 @@ impl utoipa::IntoResponses for Created {
         );
         responses
     }
-}
\ No newline at end of file
+}
+#[derive(ToSchema)]
+pub struct NoContent;
+
+impl IntoResponse for NoContent {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        StatusCode::NO_CONTENT.into_response()
+    }
+}
+
+impl utoipa::IntoResponses for NoContent {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        use utoipa::openapi::{ResponseBuilder, RefOr};
+        let mut responses = std::collections::BTreeMap::new();
+        responses.insert(
+            "204".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("No Content")
+                    .build(),
+            ),
+        );
+        responses
+    }
+}
+
+/// An iCalendar document, served as `text/calendar`.
+pub struct ICalendar(pub String);
+
+impl IntoResponse for ICalendar {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        ([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], self.0).into_response()
+    }
+}
+
+impl utoipa::IntoResponses for ICalendar {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        use utoipa::{
+            PartialSchema,
+            openapi::{ContentBuilder, RefOr, ResponseBuilder},
+        };
+        let mut responses = std::collections::BTreeMap::new();
+        responses.insert(
+            "200".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("iCalendar feed")
+                    .content("text/calendar", ContentBuilder::new().schema(Some(String::schema())).build())
+                    .build(),
+            ),
+        );
+        responses
+    }
+}
+
+/// A stream of JSON documents, one per line, served as `application/x-ndjson`.
+pub struct Ndjson(pub axum::body::Body);
+
+impl IntoResponse for Ndjson {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        ([(header::CONTENT_TYPE, "application/x-ndjson")], self.0).into_response()
+    }
+}
+
+impl utoipa::IntoResponses for Ndjson {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        use utoipa::{
+            PartialSchema,
+            openapi::{ContentBuilder, RefOr, ResponseBuilder},
+        };
+        let mut responses = std::collections::BTreeMap::new();
+        responses.insert(
+            "200".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("JSON documents, one per line")
+                    .content("application/x-ndjson", ContentBuilder::new().schema(Some(String::schema())).build())
+                    .build(),
+            ),
+        );
+        responses
+    }
+}
+
+/// Envelope of every successful JSON response: `{ "data": ... }`.
+/// Errors are sent as `{ "error": ErrorResponse }` by `AppError`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+fn json_response<T: utoipa::PartialSchema>(
+    status: StatusCode,
+    description: &str,
+) -> (String, utoipa::openapi::RefOr<utoipa::openapi::Response>) {
+    use utoipa::openapi::{ContentBuilder, ObjectBuilder, RefOr, ResponseBuilder};
+    let envelope = ObjectBuilder::new()
+        .property("data", T::schema())
+        .required("data")
+        .build();
+    (
+        status.as_u16().to_string(),
+        RefOr::T(
+            ResponseBuilder::new()
+                .description(description)
+                .content(
+                    "application/json",
+                    ContentBuilder::new().schema(Some(envelope)).build(),
+                )
+                .build(),
+        ),
+    )
+}
+
+/// 200 with a JSON body in the `ApiResponse` envelope; unlike `axum::Json` it
+/// documents itself in OpenAPI.
+pub struct JsonOk<T>(pub T);
+
+impl<T: Serialize> IntoResponse for JsonOk<T> {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        axum::Json(ApiResponse::new(self.0)).into_response()
+    }
+}
+
+impl<T: utoipa::PartialSchema> utoipa::IntoResponses for JsonOk<T> {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        std::collections::BTreeMap::from([json_response::<T>(StatusCode::OK, "OK")])
+    }
+}
+
+/// 201 with a JSON body describing the created resource.
+pub struct JsonCreated<T>(pub T);
+
+impl<T: Serialize> IntoResponse for JsonCreated<T> {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        (StatusCode::CREATED, axum::Json(ApiResponse::new(self.0))).into_response()
+    }
+}
+
+impl<T: utoipa::PartialSchema> utoipa::IntoResponses for JsonCreated<T> {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        std::collections::BTreeMap::from([json_response::<T>(StatusCode::CREATED, "Created")])
+    }
+}
+
+/// Response to a conditional GET: 200 with `ETag`/`Last-Modified` validators,
+/// or an empty 304 when the client already has the current version.
+/// Built by `Preconditions::evaluate`.
+pub struct Conditional<T> {
+    pub data: Option<T>, // None when not modified
+    pub etag: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl<T: Serialize> IntoResponse for Conditional<T> {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        let mut response = match self.data {
+            Some(data) => JsonOk(data).into_response(),
+            None => StatusCode::NOT_MODIFIED.into_response(),
+        };
+        let headers = response.headers_mut();
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if let Some(value) = self
+            .last_modified
+            .and_then(|dt| HeaderValue::from_str(&http_date(dt)).ok())
+        {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+        response
+    }
+}
+
+impl<T: utoipa::PartialSchema> utoipa::IntoResponses for Conditional<T> {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        use utoipa::openapi::{ResponseBuilder, RefOr};
+        std::collections::BTreeMap::from([
+            json_response::<T>(StatusCode::OK, "OK"),
+            (
+                "304".to_string(),
+                RefOr::T(ResponseBuilder::new().description("Not Modified").build()),
+            ),
+        ])
+    }
+}
+
+/// Login result: a token, or a 2FA challenge (202) if the user has 2FA enabled.
+pub enum LoginOutcome {
+    Token(LoginResponse),
+    Challenge(TwoFactorChallenge),
+}
+
+impl IntoResponse for LoginOutcome {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        match self {
+            LoginOutcome::Token(token) => JsonOk(token).into_response(),
+            LoginOutcome::Challenge(challenge) => {
+                (StatusCode::ACCEPTED, axum::Json(ApiResponse::new(challenge))).into_response()
+            }
+        }
+    }
+}
+
+impl utoipa::IntoResponses for LoginOutcome {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        std::collections::BTreeMap::from([
+            json_response::<LoginResponse>(StatusCode::OK, "Logged in"),
+            json_response::<TwoFactorChallenge>(StatusCode::ACCEPTED, "Two-factor code required"),
+        ])
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthStatus {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Readiness probe answer: 200 once started, 503 while starting or if startup failed.
+pub struct Readiness(pub StartupPhase);
+
+impl IntoResponse for Readiness {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        let status = HealthStatus {
+            status: self.0.as_str().to_string(),
+            timestamp: Utc::now(),
+        };
+        match self.0 {
+            StartupPhase::Ready => JsonOk(status).into_response(),
+            _ => (StatusCode::SERVICE_UNAVAILABLE, axum::Json(ApiResponse::new(status))).into_response(),
+        }
+    }
+}
+
+impl utoipa::IntoResponses for Readiness {
+    fn responses() -> std::collections::BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::Response>> {
+        std::collections::BTreeMap::from([
+            json_response::<HealthStatus>(StatusCode::OK, "Ready"),
+            json_response::<HealthStatus>(StatusCode::SERVICE_UNAVAILABLE, "Starting, or startup failed"),
+        ])
+    }
+}
+
+/// What is deployed, recorded at build time.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    pub version: String,
+    pub git_commit: String, // "unknown" when built outside a git checkout
+    pub build_timestamp: DateTime<Utc>,
+    pub features: Vec<String>, // enabled cargo features
+}
+
+/// `?fields=a,b,c` selection of top-level response fields.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+/// Field selection and filters of a ticket listing. `custom` holds `name:value` pairs
+/// separated by commas, a ticket is listed if all its custom fields match.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TicketListQuery {
+    pub fields: Option<String>,
+    pub project: Option<String>,
+    pub custom: Option<String>,
+}
+
+/// `?cursor=` and `?limit=` of a paged listing: up to `limit` items following the
+/// one `cursor` names, the `next_cursor` of the page before.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl PageParams {
+    /// The limit asked for, `default` if none, between 1 and `max`.
+    pub fn limit_or(&self, default: usize, max: usize) -> usize {
+        self.limit.unwrap_or(default).clamp(1, max)
+    }
+}
+
+/// `?sort=` field to order a listing by, and `?order=` to sort it in.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct SortParams {
+    pub sort: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A page of items. `total` counts every item matching the query, `next_cursor`
+/// is passed back as `cursor` to fetch the following page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub next_cursor: Option<String>,
+}
+
+// Documented once per item type as `ListResponse_<item>`, by `ApiDoc`; responses
+// refer to that rather than each spelling the page out
+impl<T: ToSchema> utoipa::PartialSchema for ListResponse<T> {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        utoipa::openapi::Ref::from_schema_name(Self::name()).into()
+    }
+}
+
+impl<T: ToSchema> ToSchema for ListResponse<T> {
+    fn name() -> std::borrow::Cow<'static, str> {
+        format!("ListResponse_{}", T::name()).into()
+    }
+
+    fn schemas(schemas: &mut Vec<(String, utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>)>) {
+        use utoipa::{
+            PartialSchema,
+            openapi::{ArrayBuilder, ObjectBuilder, Ref},
+        };
+        let page = ObjectBuilder::new()
+            .description(Some("A page of items, `next_cursor` is the `cursor` of the following page"))
+            .property("items", ArrayBuilder::new().items(Ref::from_schema_name(T::name())))
+            .required("items")
+            .property("total", usize::schema())
+            .required("total")
+            .property("next_cursor", Option::<String>::schema());
+        schemas.push((Self::name().into_owned(), page.into()));
+        schemas.push((T::name().into_owned(), T::schema()));
+        T::schemas(schemas);
+    }
+}
+
+impl<T> ListResponse<T> {
+    /// A list that fits in a single page.
+    pub fn complete(items: Vec<T>) -> Self {
+        Self {
+            total: items.len(),
+            items,
+            next_cursor: None,
+        }
+    }
+}
+
+/// Public view of a user: no password hash, no 2FA secrets.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct UserResponse {
+    pub username: String,
+    pub email: Option<String>,
+    pub verified: bool,
+    pub name: String,
+    pub job_title: String,
+    pub manager: Option<String>,
+    pub timezone: Option<String>,
+    pub deactivated: bool,
+    pub two_factor_enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<models::User> for UserResponse {
+    fn from(user: models::User) -> Self {
+        Self {
+            two_factor_enabled: TwoFactorController::is_enabled(&user),
+            username: user.username,
+            email: user.email,
+            verified: user.verified,
+            name: user.personal.name,
+            job_title: user.personal.job_title,
+            manager: user.personal.manager,
+            timezone: user.personal.timezone,
+            deactivated: user.deactivated,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::InputObject))]
+pub struct CreateTicketRequest {
+    pub title: String,
+    pub severity: u8, // a level of the project's scale
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub severity_label: String, // taken from the scale when empty
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub description: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub assigned_to: String, // picked by the project's assignment rules when empty
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub mentioned: Vec<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub project: Option<String>,
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub custom_fields: HashMap<String, Value>, // checked against the project's definitions
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub ticket_group: Option<String>, // prefix of one of the project's ticket groups
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    pub draft: Option<String>, // key of the creator's draft of it, discarded once it's created
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct TicketResponse {
+    pub id: i64,
+    pub title: String,
+    pub severity: u8,
+    pub severity_label: String,
+    pub description: String,
+    pub created_by: String,
+    pub assigned_to: String,
+    pub mentioned: Vec<String>,
+    pub last_modification: DateTime<Utc>,
+    pub creation_date: DateTime<Utc>,
+    pub status: models::TicketStatus,
+    pub project: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub custom_fields: HashMap<String, Value>,
+    pub rank: String,
+    pub milestone: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    pub ticket_group: Option<String>,
+}
+
+impl From<models::Ticket> for TicketResponse {
+    fn from(ticket: models::Ticket) -> Self {
+        Self {
+            id: ticket.id,
+            title: ticket.title,
+            severity: ticket.severity.level,
+            severity_label: ticket.severity.label,
+            description: ticket.description,
+            created_by: ticket.created_by,
+            assigned_to: ticket.assigned_to,
+            mentioned: ticket.mentioned,
+            last_modification: ticket.last_modification,
+            creation_date: ticket.creation_date,
+            status: ticket.status,
+            project: ticket.project,
+            resolved_at: ticket.resolved_at,
+            custom_fields: ticket.custom_fields,
+            rank: ticket.rank,
+            milestone: ticket.milestone,
+            due_date: ticket.due_date,
+            ticket_group: ticket.ticket_group,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct GroupResponse {
+    pub gid: String,
+    pub name: String,
+    pub principals: Vec<String>,
+}
+
+impl From<models::Group> for GroupResponse {
+    fn from(group: models::Group) -> Self {
+        Self {
+            gid: group.gid,
+            name: group.name,
+            principals: group.principals,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ProjectResponse {
+    pub id: String,
+    pub ticket_prefixes: Vec<String>,
+}
+
+impl From<models::Project> for ProjectResponse {
+    fn from(project: models::Project) -> Self {
+        Self {
+            id: project.id.to_string(),
+            ticket_prefixes: project.tickets.into_iter().map(|g| g.prefix).collect(),
+        }
+    }
+}
+
+/// `?days=` window of the ticket trend in project stats, 30 by default.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    pub days: Option<u32>,
+}
+
+/// Filter of an activity feed: `?kind=` a comma-separated list of kinds
+/// (`ticket_created`, `status_changed`, `comment_added`). Feeds are paged by
+/// `PageParams`, 50 activities by default and 200 at most.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActivityQuery {
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatusCount {
+    pub status: models::TicketStatus,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SeverityCount {
+    pub severity: u8,
+    pub count: usize,
+}
+
+/// Ticket dashboard of a project. Computed at `generated_at` and served from a
+/// short-lived cache, so it can lag behind recent changes by a few seconds.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProjectStatsResponse {
+    pub project: String,
+    pub total: usize,
+    pub by_status: Vec<StatusCount>,
+    pub by_severity: Vec<SeverityCount>,
+    pub trend: Vec<TicketDayCount>, // one entry per day of the window, oldest first
+    pub average_resolution_secs: Option<f64>,
+    pub top_assignees: Vec<AssigneeCount>,
+    pub subprojects: Vec<SubprojectCount>, // those the caller can fetch, closest first
+    pub rollup_total: usize,               // tickets of the project and of those sub-projects
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Tickets of a project below the one of the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubprojectCount {
+    pub project: String,
+    pub parent: String,
+    pub total: usize,
+    pub by_status: Vec<StatusCount>,
+}
+
+/// Members of a project connected to this instance's WebSocket hub.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProjectOnlineResponse {
+    pub project: String,
+    pub users: Vec<String>, // sorted
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserTotals {
+    pub total: usize,
+    pub active: usize,
+    pub deactivated: usize,
+}
+
+/// Deployment-wide numbers for operators.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AdminStatsResponse {
+    pub users: UserTotals,
+    pub groups: usize,
+    pub projects: usize,
+    pub tickets: usize,
+    pub ws_connections: usize, // on this instance only
+    pub ws_totals: WsTotals,
+    pub acl_cache: AclCacheTotals,
+    pub database: BackendInfo,
+    pub database_limit: Option<PoolStats>, // if calls to the database are limited
+    pub database_breaker: Option<BreakerStats>, // if the database is behind a circuit breaker
+}
+
+/// WebSocket connections of this instance since it started.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct WsTotals {
+    pub opened: u64,
+    pub closed: u64,
+    pub rejected: u64,  // over the per-user limit
+    pub timed_out: u64, // idle or missing heartbeats
+}
+
+/// Lookups of memberships and permissions in this instance's ACL cache since it started.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct AclCacheTotals {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize, // cached right now
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PrincipalKind {
+    User,
+    Group,
+}
+
+/// A user or group that can be `@mentioned` or named in an ACL.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PrincipalSuggestion {
+    pub id: String,
+    pub kind: PrincipalKind,
+    pub name: String, // display name of the user or group
+}
+
+/// `?q=` prefix of a principal id or name, up to `limit` suggestions (10 by default).
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// Drops a ticket into a board column, `position` 0 being the top. Positions past
+/// the end put it last. With `project` or `ticket_group` the column is on another
+/// project's board: the ticket moves there, into that ticket group or into none.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MoveTicketRequest {
+    pub status: models::TicketStatus,
+    pub position: usize,
+    #[serde(default)]
+    pub project: Option<String>, // the ticket's own when only `ticket_group` is set
+    #[serde(default)]
+    pub ticket_group: Option<String>, // prefix of a ticket group of the destination
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BoardColumn {
+    pub status: models::TicketStatus,
+    pub tickets: Vec<TicketResponse>, // top first
+}
+
+/// A project's tickets by status, one column per status in workflow order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BoardResponse {
+    pub project: String,
+    pub columns: Vec<BoardColumn>,
+}
+
+/// `end` is the milestone's last day and can't be before `start`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateMilestoneRequest {
+    pub name: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// Plans the ticket into an open milestone of its project, `null` takes it out.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AssignMilestoneRequest {
+    pub milestone: Option<String>,
+}
+
+/// Where unfinished tickets go, the project's next open milestone by default.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct CloseMilestoneRequest {
+    #[serde(default)]
+    pub rollover_to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BurndownDay {
+    pub day: NaiveDate,
+    pub open: usize, // tickets of the milestone still open at the end of the day
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BurndownResponse {
+    pub milestone: String,
+    pub days: Vec<BurndownDay>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClosedMilestoneResponse {
+    pub milestone: models::Milestone,
+    pub rolled_over: Vec<i64>,       // ids of the unfinished tickets, sorted
+    pub rollover_to: Option<String>, // none if they were taken out of any milestone
+}
+
+/// A hypothetical new ticket of the project, to see which assignment rule it meets.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AssignmentDryRunRequest {
+    pub severity: u8,
+    #[serde(default)]
+    pub custom_fields: HashMap<String, Value>,
+}
+
+/// Both are null when no rule matches, the ticket would be left unassigned.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AssignmentDryRunResponse {
+    pub rule: Option<String>,
+    pub assigned_to: Option<String>,
+}
+
+/// `?ticket_group=PREFIX` addresses the ACL of one of the project's ticket groups
+/// instead of the project's.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AclQuery {
+    pub ticket_group: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct CloneProjectRequest {
+    #[serde(default)]
+    pub include_open_tickets: bool, // copy open and in progress tickets into the new project
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CloneProjectResponse {
+    pub project: String, // id of the new project
+    pub source: String,
+    pub tickets: usize, // copied
+}
+
+/// `confirm` repeats the project id, so a project isn't given away by mistake.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferOwnershipRequest {
+    pub new_owner: String,
+    pub confirm: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProjectOwnershipResponse {
+    pub project: String,
+    pub owner: String,
+    pub previous_owner: Option<String>,
+}
+
+/// Moves a project below another, or to the top level without `parent_id`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetParentRequest {
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProjectTreeEntry {
+    pub project: String,
+    pub parent: String,
+    pub depth: usize, // 1 for the project's own sub-projects
+}
+
+/// Where a project sits in its tree, with only the projects the caller can fetch.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProjectTreeResponse {
+    pub project: String,
+    pub ancestors: Vec<String>, // its parent first
+    pub subprojects: Vec<ProjectTreeEntry>, // closest first
+}
+
+/// Permissions granted to, or revoked from, a user or group.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AclChangeRequest {
+    pub principal: String,
+    #[schema(value_type = String, example = "READ | MODIFY")]
+    pub permissions: models::Permissions,
+}
+
+/// A ticket about to be filed, `project` being the one it would be created in.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DuplicateCheckRequest {
+    pub title: String,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DuplicateCandidate {
+    pub ticket: TicketResponse,
+    pub score: f64, // share of title words in common, from 0 to 1
+}
+
+/// An email to file, `Message-ID`s without their angle brackets.
+#[derive(Debug, Clone)]
+pub struct IncomingEmail {
+    pub sender: String,
+    pub subject: String,
+    pub body: String,
+    pub message_id: Option<String>,
+    pub replied_to: Vec<String>, // from `In-Reply-To` and `References`
+}
+
+/// An inbound email as posted by a Mailgun route, form-encoded. Only the fields used
+/// are listed. `signature` is the HMAC-SHA256 of `timestamp` and `token` keyed with
+/// the webhook signing key.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MailgunInboundEmail {
+    pub sender: String,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default, rename = "body-plain")]
+    pub body_plain: String,
+    #[serde(default, rename = "stripped-text")]
+    pub stripped_text: Option<String>, // the body without quoted replies
+    #[serde(default, rename = "Message-Id")]
+    pub message_id: Option<String>,
+    #[serde(default, rename = "In-Reply-To")]
+    pub in_reply_to: Option<String>,
+    #[serde(default, rename = "References")]
+    pub references: Option<String>,
+    #[serde(default, rename = "X-Mailgun-Spf")]
+    pub spf: Option<String>, // Pass, Neutral, Fail or SoftFail
+    #[serde(default, rename = "X-Mailgun-Dkim-Check-Result")]
+    pub dkim: Option<String>, // Pass or Fail
+    pub timestamp: String,
+    pub token: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InboundEmailResponse {
+    pub ticket: i64,
+    pub comment: Option<String>, // id of the comment a reply was filed as
+    pub filed: bool,             // false if the email was filed before
+}
+
+/// Turns on the project's public portal or changes it. With `require_token`,
+/// submissions must carry the token issued with the portal, a new one is issued with
+/// `rotate_token`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicPortalRequest {
+    pub slug: String,
+    #[serde(default)]
+    pub require_token: bool,
+    #[serde(default)]
+    pub rotate_token: bool,
+    #[serde(default)]
+    pub captcha: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicPortalResponse {
+    pub slug: String,
+    pub reporter: String, // the principal tickets filed through the portal are created by
+    pub token_required: bool,
+    pub captcha: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>, // only when just issued, it can't be shown again
+}
+
+impl PublicPortalResponse {
+    pub fn new(portal: &models::PublicPortal, token: Option<String>) -> Self {
+        Self {
+            slug: portal.slug.clone(),
+            reporter: portal.reporter(),
+            token_required: portal.token_hash.is_some(),
+            captcha: portal.captcha,
+            token,
+        }
+    }
+}
+
+/// A ticket filed without an account. The portal may require its token and a
+/// captcha solution.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicTicketRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub email: Option<String>, // where the reporter can be reached, added to the description
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub captcha: Option<String>, // the solution the captcha widget produced
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicTicketResponse {
+    pub ticket: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCommentRequest {
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChatChannelRequest {
+    pub platform: models::ChatPlatform,
+    pub webhook_url: String,
+    pub events: Vec<models::ChatEvent>,
+    #[serde(default)]
+    pub high_severity: Option<u8>, // defaults to 2
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CalendarTokenResponse {
+    pub token: String, // shown once
+    pub url: String,   // of the feed, relative to the server
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetTimezoneRequest {
+    pub timezone: Option<String>, // IANA name, null for UTC
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TimezoneResponse {
+    pub timezone: Option<String>,
+    pub today: NaiveDate, // the date it is for the user
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CalendarQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SavePreferenceRequest {
+    pub value: Value,
+    #[serde(default)]
+    pub version: u64, // that the value replaces, 0 creates the namespace
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ShareTicketRequest {
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TicketShareResponse {
+    pub id: String,
+    pub token: String, // shown once
+    pub url: String,   // of the shared ticket, relative to the server
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SharedTicketQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserMetadataResponse {
+    pub username: String,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MetadataValueRequest {
+    pub value: String,
+}
+
+/// Users whose metadata has `key`, set to `value` if given. Service accounts are
+/// left out unless asked for.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MetadataQuery {
+    pub key: String,
+    pub value: Option<String>,
+    #[serde(default)]
+    pub service_accounts: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateServiceAccountRequest {
+    pub username: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// An API token without its secret.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<models::ApiToken> for ApiTokenInfo {
+    fn from(token: models::ApiToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes.iter().map(ToString::to_string).collect(),
+            created_at: token.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ServiceAccountResponse {
+    pub username: String,
+    pub name: String,
+    pub deactivated: bool,
+    pub created_at: DateTime<Utc>,
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+impl From<models::User> for ServiceAccountResponse {
+    fn from(user: models::User) -> Self {
+        Self {
+            username: user.username,
+            name: user.personal.name,
+            deactivated: user.deactivated,
+            created_at: user.created_at,
+            tokens: user.api_tokens.into_iter().map(ApiTokenInfo::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>, // `area:read` or `area:write`, `*` for every area
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiTokenResponse {
+    pub id: String,
+    pub token: String, // shown once
+    pub scopes: Vec<String>,
+}
+
+/// Outbox entries created since then are delivered again.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReplayOutboxRequest {
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReplayedEntries {
+    pub replayed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportedRecords {
+    pub imported: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReencryptedValues {
+    pub reencrypted: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MergeUsersRequest {
+    pub source: String, // the duplicate, deactivated once merged
+    pub target: String, // the account kept
+}
+
+/// What was changed to name the target instead of the source. The changes were made
+/// one entity at a time, not atomically.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MergeUsersResponse {
+    pub source: String,
+    pub target: String,
+    pub tickets: usize,
+    pub comments: usize,
+    pub groups: usize,   // the source was a member of
+    pub projects: usize, // with ACL entries or owned by the source
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenamePrincipalRequest {
+    pub to: String, // the new username or group id
+}
+
+/// What was changed to name the principal by its new name.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenamePrincipalResponse {
+    pub from: String,
+    pub to: String,
+    pub tickets: usize,
+    pub comments: usize,
+    pub groups: usize,   // the user was a member of
+    pub projects: usize, // with ACL entries or owned by the principal
+    pub sessions: usize, // of the user, moved along
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletedKind {
+    User,
+    Group,
+    Project,
+    Ticket,
+}
+
+/// A soft-deleted entity, which can be restored until `purge_at`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeletedEntity {
+    pub kind: DeletedKind,
+    pub id: String,
+    pub deleted_at: DateTime<Utc>,
+    pub purge_at: DateTime<Utc>, // removed for good from then on
+}
+
+/// `?q=` words to search for, up to `limit` results of each kind (10 by default).
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// An entity matching a search, the higher the `score` the better.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String, // of a ticket, name of a user or group, id of a project
+    pub score: f64,
+}
+
+/// Search results by kind, each ranked best first, among what the caller can see.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SearchResults {
+    pub tickets: Vec<SearchResult>,
+    pub projects: Vec<SearchResult>,
+    pub users: Vec<SearchResult>,
+    pub groups: Vec<SearchResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenderMarkdownRequest {
+    pub markdown: String,
+}
+
+/// HTML safe to show as is.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenderedMarkdown {
+    pub html: String,
+}
@@ -0,0 +1,179 @@
+//! Search index in a Meilisearch server, through its HTTP API.
+//!
+//! Documents are written to the `entities` index. Meilisearch applies writes in the
+//! background, in the order they were sent: they show in searches a moment later.
+//! A rebuild fills a second index and swaps the two, so searches never see a partial one.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use reqwest::{Method, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{IndexHit, SearchDocument, SearchIndex, SearchKind};
+use crate::{error::AppError, utils::BoxFuture};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const INDEX: &str = "entities";
+const REBUILT_INDEX: &str = "entities_rebuild";
+
+const KINDS: [SearchKind; 4] = [SearchKind::Ticket, SearchKind::Project, SearchKind::User, SearchKind::Group];
+
+/// A document as stored, under a key Meilisearch accepts: letters, digits, `-` and `_`
+/// only, which usernames and ids may not be.
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    key: String,
+    #[serde(flatten)]
+    document: SearchDocument,
+}
+
+impl From<SearchDocument> for Stored {
+    fn from(document: SearchDocument) -> Self {
+        let id: String = document.id.bytes().map(|b| format!("{:02x}", b)).collect();
+        Self {
+            key: format!("{}_{}", document.kind.as_str(), id),
+            document,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MultiSearchResponse {
+    results: Vec<SearchResponse>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    kind: SearchKind,
+    id: String,
+    #[serde(rename = "_rankingScore")]
+    score: f64,
+}
+
+pub struct MeilisearchIndex {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+impl MeilisearchIndex {
+    pub fn new(url: &str, api_key: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("HTTP client builds with a timeout only");
+        Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.url, path));
+        if self.api_key.is_empty() {
+            request
+        } else {
+            request.bearer_auth(&self.api_key)
+        }
+    }
+
+    /// Sends the request, failing unless Meilisearch accepted it. Writes are only
+    /// enqueued as tasks by then.
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, AppError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::Unavailable(format!("Meilisearch unreachable: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::Internal(anyhow!("Meilisearch answered {}: {}", status, body)));
+        }
+        Ok(response)
+    }
+
+    /// Creates the index, or leaves it as it is, and sets which fields are searched,
+    /// in their order of importance.
+    async fn create_index(&self, uid: &str) -> Result<(), AppError> {
+        // An index that exists fails the task, not the request
+        self.send(self.request(Method::POST, "/indexes").json(&json!({ "uid": uid, "primaryKey": "key" })))
+            .await?;
+        let settings = json!({
+            "searchableAttributes": ["keywords", "title", "body"],
+            "filterableAttributes": ["kind"],
+        });
+        self.send(self.request(Method::PATCH, &format!("/indexes/{}/settings", uid)).json(&settings))
+            .await?;
+        Ok(())
+    }
+
+    async fn add_documents(&self, uid: &str, documents: Vec<SearchDocument>) -> Result<(), AppError> {
+        let stored: Vec<Stored> = documents.into_iter().map(Stored::from).collect();
+        self.send(self.request(Method::POST, &format!("/indexes/{}/documents", uid)).json(&stored))
+            .await?;
+        Ok(())
+    }
+}
+
+impl SearchIndex for MeilisearchIndex {
+    fn upsert<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move { self.add_documents(INDEX, documents).await })
+    }
+
+    fn replace_all<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            self.create_index(INDEX).await?;
+            self.send(self.request(Method::DELETE, &format!("/indexes/{}", REBUILT_INDEX))).await.ok();
+            self.create_index(REBUILT_INDEX).await?;
+            self.add_documents(REBUILT_INDEX, documents).await?;
+            let swap = json!([{ "indexes": [INDEX, REBUILT_INDEX] }]);
+            self.send(self.request(Method::POST, "/swap-indexes").json(&swap)).await?;
+            self.send(self.request(Method::DELETE, &format!("/indexes/{}", REBUILT_INDEX))).await?;
+            Ok(())
+        })
+    }
+
+    fn query<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<IndexHit>, AppError>> {
+        Box::pin(async move {
+            let queries: Vec<Value> = KINDS
+                .iter()
+                .map(|kind| {
+                    json!({
+                        "indexUid": INDEX,
+                        "q": query,
+                        "filter": format!("kind = {}", kind.as_str()),
+                        "limit": limit,
+                        "attributesToRetrieve": ["kind", "id"],
+                        "showRankingScore": true,
+                    })
+                })
+                .collect();
+            let response = self
+                .send(self.request(Method::POST, "/multi-search").json(&json!({ "queries": queries })))
+                .await?;
+            let response: MultiSearchResponse = response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Unexpected Meilisearch response: {}", e)))?;
+            Ok(response
+                .results
+                .into_iter()
+                .flat_map(|r| r.hits)
+                .map(|hit| IndexHit {
+                    kind: hit.kind,
+                    id: hit.id,
+                    score: hit.score,
+                })
+                .collect())
+        })
+    }
+}
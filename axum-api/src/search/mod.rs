@@ -0,0 +1,337 @@
+//! Search indexes kept next to the database, for deployments wanting other relevance
+//! than the database's own search (`db::SearchService`). `SEARCH_BACKEND` picks one.
+//!
+//! An index holds a document per live ticket, project, user and group. It is rebuilt
+//! from the database at startup and every `REBUILD_INTERVAL` by the scheduler. Between
+//! rebuilds `SearchIndexer` keeps it current from the domain events: created and moved
+//! tickets, and registered users. Other changes show at the next rebuild, and hits on
+//! entities deleted since are dropped when the search controller loads them.
+
+pub mod meilisearch;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, TantivyError, Term,
+    collector::TopDocs,
+    doc,
+    query::{BooleanQuery, BoostQuery, Occur, Query, RegexQuery, TermQuery},
+    schema::{Field, IndexRecordOption, STORED, STRING, Schema, TEXT, Value},
+};
+
+use crate::{
+    config::{AppConfig, SearchBackend},
+    db::DatabaseInterface,
+    error::AppError,
+    events::{DomainEvent, Subscriber},
+    models::{Group, Project, Ticket, User},
+    utils::{BoxFuture, relevance::words},
+};
+
+/// How often the scheduler rebuilds the index from the database.
+pub const REBUILD_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Ticket,
+    Project,
+    User,
+    Group,
+}
+
+impl SearchKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchKind::Ticket => "ticket",
+            SearchKind::Project => "project",
+            SearchKind::User => "user",
+            SearchKind::Group => "group",
+        }
+    }
+}
+
+/// What an index knows of an entity: words matched whole, such as ids, and its text,
+/// the title mattering more than the body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub kind: SearchKind,
+    pub id: String,
+    pub keywords: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl SearchDocument {
+    pub fn ticket(ticket: &Ticket) -> Self {
+        Self {
+            kind: SearchKind::Ticket,
+            id: ticket.id.to_string(),
+            keywords: ticket.id.to_string(),
+            title: ticket.title.clone(),
+            body: ticket.description.clone(),
+        }
+    }
+
+    pub fn project(project: &Project) -> Self {
+        let prefixes: Vec<&str> = project.tickets.iter().map(|g| g.prefix.as_str()).collect();
+        Self {
+            kind: SearchKind::Project,
+            id: project.id.to_string(),
+            keywords: format!("{} {}", project.id, prefixes.join(" ")),
+            title: String::new(),
+            body: String::new(),
+        }
+    }
+
+    pub fn user(user: &User) -> Self {
+        Self {
+            kind: SearchKind::User,
+            id: user.username.clone(),
+            keywords: user.username.clone(),
+            title: user.personal.name.clone(),
+            body: user.personal.job_title.clone(),
+        }
+    }
+
+    pub fn group(group: &Group) -> Self {
+        Self {
+            kind: SearchKind::Group,
+            id: group.gid.clone(),
+            keywords: group.gid.clone(),
+            title: group.name.clone(),
+            body: String::new(),
+        }
+    }
+}
+
+/// An entity matching a search, the higher the score the better. Scores only compare
+/// within one search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexHit {
+    pub kind: SearchKind,
+    pub id: String,
+    pub score: f64,
+}
+
+pub trait SearchIndex: Send + Sync {
+    /// Adds the documents, replacing those of the same kind and id.
+    fn upsert<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Replaces everything in the index with the documents.
+    fn replace_all<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>>;
+    /// Up to `limit` entities of each kind matching the words of `query`, best first.
+    fn query<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<IndexHit>, AppError>>;
+}
+
+/// The index of the configured backend, `None` when the database searches.
+pub fn from_config(config: &AppConfig) -> Option<Arc<dyn SearchIndex>> {
+    match config.search_backend {
+        SearchBackend::Database => None,
+        SearchBackend::Embedded => Some(Arc::new(EmbeddedIndex::new())),
+        SearchBackend::Meilisearch => Some(Arc::new(meilisearch::MeilisearchIndex::new(
+            config.meilisearch_url.as_deref().unwrap_or_default(),
+            &config.meilisearch_api_key,
+        ))),
+    }
+}
+
+/// Tantivy index in the server's memory, ranking by BM25. Nothing is written to disk:
+/// the index is rebuilt from the database at startup anyway.
+pub struct EmbeddedIndex {
+    writer: Mutex<IndexWriter>,
+    reader: IndexReader,
+    fields: EmbeddedFields,
+}
+
+#[derive(Clone, Copy)]
+struct EmbeddedFields {
+    key: Field, // "kind/id", what upserts replace by
+    kind: Field,
+    id: Field,
+    keywords: Field,
+    title: Field,
+    body: Field,
+}
+
+/// Memory the writer buffers documents in before flushing a segment.
+const WRITER_MEMORY: usize = 15_000_000;
+
+impl EmbeddedIndex {
+    pub fn new() -> Self {
+        let mut schema = Schema::builder();
+        let fields = EmbeddedFields {
+            key: schema.add_text_field("key", STRING),
+            kind: schema.add_text_field("kind", STRING | STORED),
+            id: schema.add_text_field("id", STRING | STORED),
+            keywords: schema.add_text_field("keywords", TEXT),
+            title: schema.add_text_field("title", TEXT),
+            body: schema.add_text_field("body", TEXT),
+        };
+        let index = Index::create_in_ram(schema.build());
+        let writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY)
+            .expect("Failed to create search index writer");
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .expect("Failed to create search index reader");
+        Self {
+            writer: Mutex::new(writer),
+            reader,
+            fields,
+        }
+    }
+
+    fn key(document: &SearchDocument) -> String {
+        format!("{}/{}", document.kind.as_str(), document.id)
+    }
+
+    fn add(&self, writer: &IndexWriter, document: SearchDocument) -> Result<(), AppError> {
+        let f = self.fields;
+        writer
+            .add_document(doc!(
+                f.key => Self::key(&document),
+                f.kind => document.kind.as_str(),
+                f.id => document.id,
+                f.keywords => document.keywords,
+                f.title => document.title,
+                f.body => document.body,
+            ))
+            .map_err(index_error)?;
+        Ok(())
+    }
+
+    fn commit(&self, writer: &mut IndexWriter) -> Result<(), AppError> {
+        writer.commit().map_err(index_error)?;
+        self.reader.reload().map_err(index_error)
+    }
+
+    /// Every term must be in a field, as a word or the start of one, the title and
+    /// keywords weighing more than the body.
+    fn text_query(&self, kind: SearchKind, terms: &[String]) -> Result<BooleanQuery, AppError> {
+        let f = self.fields;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Box::new(exact(f.kind, kind.as_str())))];
+        for term in terms {
+            let mut matches: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+            for (field, weight) in [(f.keywords, 3.0), (f.title, 3.0), (f.body, 1.0)] {
+                let whole = BoostQuery::new(Box::new(exact(field, term)), weight);
+                let prefix = RegexQuery::from_pattern(&format!("{}.*", term), field).map_err(index_error)?;
+                matches.push((Occur::Should, Box::new(whole)));
+                matches.push((Occur::Should, Box::new(BoostQuery::new(Box::new(prefix), weight / 2.0))));
+            }
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(matches))));
+        }
+        Ok(BooleanQuery::new(clauses))
+    }
+}
+
+impl Default for EmbeddedIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn exact(field: Field, text: &str) -> TermQuery {
+    TermQuery::new(Term::from_field_text(field, text), IndexRecordOption::WithFreqs)
+}
+
+fn index_error(e: TantivyError) -> AppError {
+    AppError::Internal(anyhow::anyhow!("Search index error: {}", e))
+}
+
+impl SearchIndex for EmbeddedIndex {
+    fn upsert<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut writer = self.writer.lock().unwrap();
+            for document in documents {
+                writer.delete_term(Term::from_field_text(self.fields.key, &Self::key(&document)));
+                self.add(&writer, document)?;
+            }
+            self.commit(&mut writer)
+        })
+    }
+
+    fn replace_all<'a>(&'a self, documents: Vec<SearchDocument>) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut writer = self.writer.lock().unwrap();
+            writer.delete_all_documents().map_err(index_error)?;
+            for document in documents {
+                self.add(&writer, document)?;
+            }
+            self.commit(&mut writer)
+        })
+    }
+
+    fn query<'a>(&'a self, query: &'a str, limit: usize) -> BoxFuture<'a, Result<Vec<IndexHit>, AppError>> {
+        Box::pin(async move {
+            let terms = words(query);
+            if terms.is_empty() || limit == 0 {
+                return Ok(Vec::new());
+            }
+            let searcher = self.reader.searcher();
+            let mut hits: Vec<IndexHit> = Vec::new();
+            for kind in [SearchKind::Ticket, SearchKind::Project, SearchKind::User, SearchKind::Group] {
+                let found = searcher
+                    .search(&self.text_query(kind, &terms)?, &TopDocs::with_limit(limit))
+                    .map_err(index_error)?;
+                for (score, address) in found {
+                    let document: TantivyDocument = searcher.doc(address).map_err(index_error)?;
+                    if let Some(id) = document.get_first(self.fields.id).and_then(|v| v.as_str()) {
+                        hits.push(IndexHit {
+                            kind,
+                            id: id.to_string(),
+                            score: score as f64,
+                        });
+                    }
+                }
+            }
+            hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+            Ok(hits)
+        })
+    }
+}
+
+/// Every live ticket, project, user and group, as documents. Aliases left by renames
+/// aren't, their principal is found by its new name.
+pub async fn documents(db: &dyn DatabaseInterface) -> Result<Vec<SearchDocument>, AppError> {
+    let mut documents: Vec<SearchDocument> = Vec::new();
+    documents.extend(db.tickets().list_tickets().await?.iter().map(SearchDocument::ticket));
+    documents.extend(db.projects().list_projects().await?.iter().map(SearchDocument::project));
+    let users = db.users().list_users().await?;
+    documents.extend(users.iter().filter(|u| u.renamed_to.is_none()).map(SearchDocument::user));
+    let groups = db.groups().list_groups().await?;
+    documents.extend(groups.iter().filter(|g| g.renamed_to.is_none()).map(SearchDocument::group));
+    Ok(documents)
+}
+
+/// Updates the index from domain events.
+pub struct SearchIndexer {
+    db: Arc<dyn DatabaseInterface>,
+    index: Arc<dyn SearchIndex>,
+}
+
+impl SearchIndexer {
+    pub fn new(db: Arc<dyn DatabaseInterface>, index: Arc<dyn SearchIndex>) -> Self {
+        Self { db, index }
+    }
+}
+
+impl Subscriber for SearchIndexer {
+    fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let document = match event {
+                DomainEvent::TicketUpdated(event) => SearchDocument::ticket(&event.ticket),
+                DomainEvent::UserRegistered { username, .. } => {
+                    SearchDocument::user(&self.db.users().get_user(username).await?)
+                }
+                DomainEvent::CommentAdded { .. } => return Ok(()),
+            };
+            self.index.upsert(vec![document]).await
+        })
+    }
+}
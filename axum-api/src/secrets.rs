@@ -0,0 +1,166 @@
+//! Secrets kept outside of the environment.
+//!
+//! A secret setting whose value is a reference, `<scheme>:<reference>`, is replaced
+//! at startup by what the provider registered for the scheme returns. Only Vault is
+//! built in: `vault:<mount>/<path>#<key>` reads `key` from a KV v2 secret, e.g.
+//! `JWT_SECRET=vault:secret/tickets#jwt`. Settings can also be read from files, see
+//! the `_FILE` variables of `AppConfig::from_env`.
+
+use std::{collections::HashMap, sync::RwLock, time::Duration};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::{config::AppConfig, error::AppError, utils::BoxFuture};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A store secrets are fetched from by reference.
+pub trait SecretProvider: Send + Sync {
+    /// The prefix of the references it resolves, without the colon.
+    fn scheme(&self) -> &'static str;
+
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, AppError>>;
+}
+
+/// Reads secrets from the KV v2 engine of a HashiCorp Vault server.
+pub struct VaultProvider {
+    client: reqwest::Client,
+    address: Option<String>,
+    token: String,
+}
+
+impl VaultProvider {
+    /// Without an address, every reference fails rather than being taken literally.
+    pub fn new(address: Option<String>, token: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("HTTP client builds with a timeout only");
+        let address = address.map(|a| a.trim_end_matches('/').to_string());
+        Self { client, address, token }
+    }
+}
+
+impl SecretProvider for VaultProvider {
+    fn scheme(&self) -> &'static str {
+        "vault"
+    }
+
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, AppError>> {
+        Box::pin(async move {
+            let address = self
+                .address
+                .as_deref()
+                .ok_or_else(|| AppError::Validation(format!("vault:{} needs VAULT_ADDR", reference)))?;
+            let invalid = || AppError::Validation(format!("Expected vault:<mount>/<path>#<key>, got vault:{}", reference));
+            let (secret, key) = reference.split_once('#').ok_or_else(invalid)?;
+            let (mount, path) = secret.split_once('/').ok_or_else(invalid)?;
+            if mount.is_empty() || path.is_empty() || key.is_empty() {
+                return Err(invalid());
+            }
+
+            let response = self
+                .client
+                .get(format!("{}/v1/{}/data/{}", address, mount, path))
+                .header("X-Vault-Token", &self.token)
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Vault unreachable: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(AppError::Internal(anyhow!(
+                    "Vault answered {} for {}",
+                    response.status(),
+                    secret
+                )));
+            }
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(anyhow!("Vault answer for {} unreadable: {}", secret, e)))?;
+            body["data"]["data"][key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| AppError::Internal(anyhow!("Vault secret {} has no string {}", secret, key)))
+        })
+    }
+}
+
+/// Serves secrets from memory, for tests and local development.
+pub struct InMemorySecretProvider {
+    scheme: &'static str,
+    secrets: RwLock<HashMap<String, String>>,
+}
+
+impl InMemorySecretProvider {
+    pub fn new(scheme: &'static str) -> Self {
+        Self { scheme, secrets: RwLock::new(HashMap::new()) }
+    }
+
+    pub fn insert(&self, reference: &str, secret: &str) {
+        self.secrets.write().unwrap().insert(reference.to_string(), secret.to_string());
+    }
+}
+
+impl SecretProvider for InMemorySecretProvider {
+    fn scheme(&self) -> &'static str {
+        self.scheme
+    }
+
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, AppError>> {
+        Box::pin(async move {
+            self.secrets
+                .read()
+                .unwrap()
+                .get(reference)
+                .cloned()
+                .ok_or_else(|| AppError::NotFound(format!("Secret {}:{}", self.scheme, reference)))
+        })
+    }
+}
+
+/// The registered providers, by scheme.
+pub struct Secrets {
+    providers: Vec<Box<dyn SecretProvider>>,
+}
+
+impl Secrets {
+    pub fn new(providers: Vec<Box<dyn SecretProvider>>) -> Self {
+        Self { providers }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(vec![Box::new(VaultProvider::new(
+            config.vault_address.clone(),
+            config.vault_token.clone(),
+        ))])
+    }
+
+    /// The secret a value refers to, or the value itself if it isn't a reference.
+    pub async fn resolve(&self, value: &str) -> Result<String, AppError> {
+        let provider = value.split_once(':').and_then(|(scheme, reference)| {
+            self.providers.iter().find(|p| p.scheme() == scheme).map(|p| (p, reference))
+        });
+        match provider {
+            Some((provider, reference)) => provider.fetch(reference).await,
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Replaces the references among the secret settings. Encryption keys are parsed
+    /// before this runs, they can come from a file but not from a provider.
+    pub async fn resolve_config(&self, config: &mut AppConfig) -> Result<(), AppError> {
+        for secret in [
+            &mut config.jwt_secret,
+            &mut config.management_token,
+            &mut config.database_connection_string,
+            &mut config.inbound_email_signing_key,
+        ] {
+            *secret = self.resolve(secret).await?;
+        }
+        for key in config.client_api_keys.iter_mut() {
+            *key = self.resolve(key).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+//! Fixtures: users, groups, projects and tickets loaded into the configured backend,
+//! for demo environments and integration tests. Loaded on startup from `SEED_FILE`,
+//! or through `POST /api/mgmt/seed`.
+//!
+//! Entities go through the checks they'd get through the API: usernames and emails
+//! are validated, passwords hashed, tickets numbered and checked against their
+//! project. Principals have to exist before they're referred to, so a file lists
+//! them in that order. Entities already in the database are skipped: users, groups
+//! and projects by id, tickets by title within their project. Loading a file twice
+//! changes nothing.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    acl,
+    controllers::user_controller::RegistrationRules,
+    error::AppError,
+    models::{AccessControlList, AccessControlStore, CustomFieldDefinition, Group, Project, Severity},
+    schema::CreateTicketRequest,
+    state::AppState,
+    validation::custom_fields::validate_definitions,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct Fixtures {
+    #[serde(default)]
+    pub users: Vec<SeedUser>,
+    #[serde(default)]
+    pub groups: Vec<SeedGroup>,
+    #[serde(default)]
+    pub projects: Vec<SeedProject>,
+    #[serde(default)]
+    pub tickets: Vec<SeedTicket>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeedUser {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub verified: bool, // only with an email
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeedGroup {
+    pub gid: String,
+    #[serde(default)]
+    pub name: String, // the gid when empty
+    #[serde(default)]
+    pub principals: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeedProject {
+    pub id: uuid::Uuid, // fixed, for tickets and other fixtures to refer to
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<uuid::Uuid>,
+    #[serde(default)]
+    pub acl: Vec<AccessControlList>,
+    #[serde(default)]
+    pub severities: Vec<Severity>,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomFieldDefinition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeedTicket {
+    pub created_by: String,
+    #[serde(flatten)]
+    pub ticket: CreateTicketRequest,
+}
+
+/// Entities created by a load, those already there are counted as skipped.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SeedReport {
+    pub users: usize,
+    pub groups: usize,
+    pub projects: usize,
+    pub tickets: usize,
+    pub skipped: usize,
+}
+
+impl Fixtures {
+    /// Parses fixtures written in YAML, or in JSON, which YAML reads as well.
+    pub fn parse(content: &str) -> Result<Self, AppError> {
+        serde_yaml::from_str(content).map_err(|e| AppError::Validation(format!("Invalid fixtures: {}", e)))
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Validation(format!("Fixture file {} can't be read: {}", path, e)))?;
+        Self::parse(&content)
+    }
+}
+
+/// Names the fixture a validation error is about.
+fn in_fixture(kind: &str, id: &str, e: AppError) -> AppError {
+    match e {
+        AppError::Validation(message) => AppError::Validation(format!("{} {}: {}", kind, id, message)),
+        e => e,
+    }
+}
+
+async fn principal_exists(app_state: &AppState, principal: &str) -> Result<bool, AppError> {
+    match app_state.db.groups().get_group(principal).await {
+        Ok(_) => Ok(true),
+        Err(AppError::NotFound(_)) => app_state.db.users().exists_user(principal).await,
+        Err(e) => Err(e),
+    }
+}
+
+async fn require_principals(app_state: &AppState, principals: &[String]) -> Result<(), AppError> {
+    for principal in principals {
+        if !principal_exists(app_state, principal).await? {
+            return Err(AppError::Validation(format!("Principal {} not found", principal)));
+        }
+    }
+    Ok(())
+}
+
+/// Loads the fixtures in order, stopping at the first invalid one. What was loaded
+/// before it stays.
+pub async fn load(app_state: &AppState, fixtures: Fixtures) -> Result<SeedReport, AppError> {
+    let db = &app_state.db;
+    let controller = &app_state.controller;
+    let mut report = SeedReport::default();
+
+    for seed in fixtures.users {
+        if principal_exists(app_state, &seed.username).await? {
+            report.skipped += 1;
+            continue;
+        }
+        let mut user = controller
+            .user
+            .new_user(&seed.username, &seed.password, seed.email.as_deref(), RegistrationRules::default())
+            .map_err(|e| in_fixture("User", &seed.username, e))?;
+        user.verified = seed.verified && user.email.is_some();
+        controller.user.register(user).await?;
+        report.users += 1;
+    }
+
+    for seed in fixtures.groups {
+        if principal_exists(app_state, &seed.gid).await? {
+            report.skipped += 1;
+            continue;
+        }
+        if seed.gid.trim().is_empty() {
+            return Err(AppError::Validation("Group id is required".to_string()));
+        }
+        require_principals(app_state, &seed.principals)
+            .await
+            .map_err(|e| in_fixture("Group", &seed.gid, e))?;
+        db.groups()
+            .create_group(Group {
+                name: if seed.name.is_empty() { seed.gid.clone() } else { seed.name },
+                gid: seed.gid,
+                principals: seed.principals,
+                renamed_to: None,
+            })
+            .await?;
+        report.groups += 1;
+    }
+
+    for seed in fixtures.projects {
+        let id = seed.id.to_string();
+        match db.projects().get_project(&id).await {
+            Ok(_) => {
+                report.skipped += 1;
+                continue;
+            }
+            Err(AppError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        let principals: Vec<String> =
+            seed.acl.iter().flat_map(|acl| acl.principals.clone()).chain(seed.owner.clone()).collect();
+        require_principals(app_state, &principals)
+            .await
+            .map_err(|e| in_fixture("Project", &id, e))?;
+        validate_definitions(&seed.custom_fields).map_err(|e| in_fixture("Project", &id, AppError::Validation(e)))?;
+        let project = Project {
+            id: seed.id,
+            acl: AccessControlStore {
+                list: seed.acl,
+                deny: vec![],
+                last_mod_date: chrono::Utc::now(),
+            },
+            tickets: vec![],
+            severities: seed.severities,
+            custom_fields: seed.custom_fields,
+            assignment_rules: vec![],
+            escalation_policies: vec![],
+            owner: seed.owner,
+            parent_id: seed.parent_id.map(|parent| parent.to_string()),
+            portal: None,
+        };
+        if let Some(parent) = &project.parent_id {
+            match db.projects().get_project(parent).await {
+                Ok(_) => {}
+                Err(AppError::NotFound(_)) => {
+                    return Err(AppError::Validation(format!("Project {}: parent {} not found", id, parent)));
+                }
+                Err(e) => return Err(e),
+            }
+            if acl::ancestors(db.as_ref(), &project).await?.len() == acl::MAX_DEPTH {
+                return Err(AppError::Validation(format!("Project {}: nested too deep", id)));
+            }
+        }
+        db.projects().create_project(project).await?;
+        report.projects += 1;
+    }
+
+    let mut existing = controller.ticket.tickets().await?;
+    for seed in fixtures.tickets {
+        let title = seed.ticket.title.trim();
+        if existing.iter().any(|t| t.title == title && t.project == seed.ticket.project) {
+            report.skipped += 1;
+            continue;
+        }
+        let title = title.to_string();
+        if !db.users().exists_user(&seed.created_by).await? {
+            return Err(AppError::Validation(format!("Ticket {}: author {} not found", title, seed.created_by)));
+        }
+        let ticket = controller
+            .ticket
+            .import_ticket(&seed.created_by, seed.ticket)
+            .await
+            .map_err(|e| in_fixture("Ticket", &title, e))?;
+        existing.push(ticket);
+        report.tickets += 1;
+    }
+
+    Ok(report)
+}
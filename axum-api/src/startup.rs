@@ -0,0 +1,90 @@
+//! Startup ordering. The server listens as soon as the database is reachable and
+//! reports `starting` on `/health/ready` until the database is initialized, so
+//! orchestrators hold traffic back instead of restarting a server still setting up.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::atomic::{AtomicU8, Ordering},
+    time::{Duration, Instant},
+};
+
+// Delay before the second attempt, doubled after each failure up to the maximum
+const FIRST_RETRY: Duration = Duration::from_millis(250);
+const MAX_RETRY: Duration = Duration::from_secs(8);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartupPhase {
+    Starting,
+    Ready,
+    Failed,
+}
+
+impl StartupPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StartupPhase::Starting => "starting",
+            StartupPhase::Ready => "ready",
+            StartupPhase::Failed => "failed",
+        }
+    }
+}
+
+/// Where startup is. It only moves forward, from starting to ready or failed.
+pub struct Startup(AtomicU8);
+
+impl Default for Startup {
+    fn default() -> Self {
+        Self(AtomicU8::new(StartupPhase::Starting as u8))
+    }
+}
+
+impl Startup {
+    pub fn phase(&self) -> StartupPhase {
+        match self.0.load(Ordering::Acquire) {
+            0 => StartupPhase::Starting,
+            1 => StartupPhase::Ready,
+            _ => StartupPhase::Failed,
+        }
+    }
+
+    pub fn ready(&self) {
+        self.finish(StartupPhase::Ready);
+    }
+
+    pub fn failed(&self) {
+        self.finish(StartupPhase::Failed);
+    }
+
+    fn finish(&self, phase: StartupPhase) {
+        let _ = self.0.compare_exchange(
+            StartupPhase::Starting as u8,
+            phase as u8,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+}
+
+/// Repeats an attempt with exponential backoff until it succeeds or `limit` has
+/// passed, then returns the last error. With a zero limit it is tried once.
+pub async fn wait_for<T, E, F, Fut>(what: &str, limit: Duration, mut attempt: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let mut delay = FIRST_RETRY;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) if started.elapsed() + delay > limit => return Err(e),
+            Err(e) => {
+                log::warn!("{} not available, retrying in {:?}: {}", what, delay, e);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY);
+            }
+        }
+    }
+}
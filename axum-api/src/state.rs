@@ -1,10 +1,20 @@
 use std::sync::Arc;
 
 use crate::{
+    api::v1::ws::connections::WsConnections,
+    captcha::{CaptchaVerifier, HttpCaptchaVerifier},
     config::{AppConfig, RuntimeConfig},
-    controllers::Controller,
-    db::DatabaseInterface,
-    middleware::auth::Auth,
+    controllers::{Controller, user_controller::MetadataEncryption},
+    db::{DatabaseInterface, breaker::CircuitBreaker, limited::ConcurrencyLimit},
+    events::EventBus,
+    middleware::{auth::Auth, rate_limit::RateLimiter},
+    notifier::{
+        LogNotifier, Notifier,
+        chat::{ChatSender, HttpChatSender},
+    },
+    search,
+    startup::Startup,
+    utils::encryption::FieldCipher,
 };
 
 #[derive(Clone)]
@@ -13,17 +23,51 @@ pub struct AppState {
     pub auth: Arc<Auth>,
     pub controller: Arc<Controller>,
     pub db: Arc<dyn DatabaseInterface>,
+    pub events: Arc<EventBus>,
     pub runtime_config: Arc<RuntimeConfig>,
+    pub notifier: Arc<dyn Notifier>,
+    pub chat: Arc<dyn ChatSender>,
+    pub ws_connections: Arc<WsConnections>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub portal_limiter: Arc<RateLimiter>, // of anonymous ticket submissions
+    pub captcha: Arc<dyn CaptchaVerifier>,
+    pub startup: Arc<Startup>,
+    pub db_limit: Option<Arc<ConcurrencyLimit>>, // set when the database is wrapped in one
+    pub db_breaker: Option<Arc<CircuitBreaker>>,  // same
 }
 
 impl AppState {
     pub fn new(config: AppConfig, auth: Auth, database: Arc<dyn DatabaseInterface>) -> Self {
+        let events = Arc::new(if config.outbox {
+            EventBus::with_outbox(database.clone())
+        } else {
+            EventBus::default()
+        });
+        let encryption = MetadataEncryption::from_config(&config);
+        let index = search::from_config(&config);
+        let cipher = FieldCipher::new(&config.encryption_keys);
+        let controller = Arc::new(Controller::new(database.clone(), events.clone(), encryption, cipher, index));
+        let ws_connections = Arc::new(WsConnections::new(controller.clone()));
+        events.subscribe(ws_connections.clone());
         Self {
+            rate_limiter: Arc::new(RateLimiter::new(config.rate_limits.clone())),
+            portal_limiter: Arc::new(RateLimiter::new(vec![config.portal_rate_limit.clone()])),
+            captcha: Arc::new(HttpCaptchaVerifier::new(
+                config.captcha_verify_url.clone(),
+                config.captcha_secret.clone(),
+            )),
             config: Arc::new(config),
             auth: Arc::new(auth),
             db: database.clone(),
             runtime_config: Arc::new(AppConfig::runtime_from_env().unwrap_or_default()),
-            controller: Arc::new(Controller::new(database.clone())),
+            controller,
+            events,
+            notifier: Arc::new(LogNotifier),
+            chat: Arc::new(HttpChatSender::new()),
+            ws_connections,
+            startup: Arc::new(Startup::default()),
+            db_limit: None,
+            db_breaker: None,
         }
     }
 }
@@ -0,0 +1,227 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+    use proptest::prelude::*;
+
+    use crate::{
+        acl::{self, AclCache, MAX_DEPTH},
+        controllers::group_controller::GroupController,
+        db::{DatabaseInterface, inmemory::InMemoryDatabase},
+        models::{AccessControlList, AccessControlStore, AclInheritance, Group, Permissions, Project, TicketGroup},
+        test::app::{sample_project, sample_ticket},
+    };
+
+    const USERS: &[&str] = &["ana", "ben", "cy"];
+    const GROUPS: &[&str] = &["ops", "dev", "qa"];
+
+    /// A project with its ancestors and maybe a ticket group, and who is in which group.
+    #[derive(Debug, Clone)]
+    struct Structure {
+        lineage: Vec<Project>, // the project first, then its parent and so on
+        group: Option<TicketGroup>,
+        memberships: Vec<Vec<String>>, // groups of each user
+    }
+
+    impl Structure {
+        fn principals(&self, user: usize) -> Vec<String> {
+            let mut principals = vec![USERS[user].to_string()];
+            principals.extend(self.memberships[user].iter().cloned());
+            principals
+        }
+    }
+
+    fn permissions() -> impl Strategy<Value = Permissions> {
+        any::<u8>().prop_map(Permissions::from_bits_truncate)
+    }
+
+    fn entries() -> impl Strategy<Value = Vec<AccessControlList>> {
+        let principals: Vec<&str> = USERS.iter().chain(GROUPS).copied().collect();
+        let entry = (permissions(), proptest::sample::subsequence(principals, 1..=3));
+        proptest::collection::vec(entry, 0..4).prop_map(|entries| {
+            entries
+                .into_iter()
+                .map(|(permissions, principals)| AccessControlList {
+                    permissions,
+                    principals: principals.into_iter().map(str::to_string).collect(),
+                })
+                .collect()
+        })
+    }
+
+    fn store() -> impl Strategy<Value = AccessControlStore> {
+        (entries(), entries()).prop_map(|(list, deny)| AccessControlStore {
+            list,
+            deny,
+            last_mod_date: Utc::now(),
+        })
+    }
+
+    fn structure() -> impl Strategy<Value = Structure> {
+        // Owners are rare, else they'd hide everything else
+        let owner = prop_oneof![8 => Just(None), 1 => proptest::sample::select(USERS).prop_map(Some)];
+        let lineage = proptest::collection::vec((store(), owner), 1..5).prop_map(|projects| {
+            let mut lineage: Vec<Project> = projects
+                .into_iter()
+                .map(|(acl, owner)| Project {
+                    acl,
+                    owner: owner.map(str::to_string),
+                    ..sample_project(&[])
+                })
+                .collect();
+            for i in 1..lineage.len() {
+                lineage[i - 1].parent_id = Some(lineage[i].id.to_string());
+            }
+            lineage
+        });
+        let inheritance = prop_oneof![Just(AclInheritance::Extend), Just(AclInheritance::Narrow)];
+        let group = proptest::option::of((store(), inheritance)).prop_map(|group| {
+            group.map(|(acl, inheritance)| TicketGroup {
+                prefix: "OPS".to_string(),
+                acl,
+                inheritance,
+            })
+        });
+        let memberships = proptest::collection::vec(proptest::sample::subsequence(GROUPS.to_vec(), 0..=3), USERS.len())
+            .prop_map(|memberships| {
+                memberships
+                    .into_iter()
+                    .map(|groups| groups.into_iter().map(str::to_string).collect())
+                    .collect()
+            });
+        (lineage, group, memberships).prop_map(|(mut lineage, group, memberships)| {
+            lineage[0].tickets.extend(group.clone());
+            Structure {
+                lineage,
+                group,
+                memberships,
+            }
+        })
+    }
+
+    /// The rules of `acl` applied one permission at a time.
+    fn oracle(structure: &Structure, in_group: bool, principals: &[String]) -> Permissions {
+        let lineage = &structure.lineage;
+        if lineage.iter().any(|p| p.owner.as_ref() == principals.first()) {
+            return Permissions::ROOT;
+        }
+        let names = |entries: &[AccessControlList], bit: Permissions| {
+            entries
+                .iter()
+                .any(|e| e.permissions.contains(bit) && e.principals.iter().any(|p| principals.contains(p)))
+        };
+        (0..8)
+            .map(|i| Permissions::from_bits_truncate(1 << i))
+            .filter(|bit| !bit.is_empty())
+            .filter(|&bit| {
+                let granted = lineage.iter().any(|p| names(&p.acl.list, bit));
+                let denied = lineage.iter().any(|p| names(&p.acl.deny, bit));
+                match structure.group.as_ref().filter(|_| in_group) {
+                    None => granted && !denied,
+                    Some(group) => {
+                        let own = names(&group.acl.list, bit);
+                        let granted = match group.inheritance {
+                            AclInheritance::Extend => granted || own,
+                            AclInheritance::Narrow => granted && own,
+                        };
+                        granted && !denied && !names(&group.acl.deny, bit)
+                    }
+                }
+            })
+            .fold(Permissions::NONE, |acc, bit| acc | bit)
+    }
+
+    proptest! {
+        #[test]
+        fn resolves_as_the_oracle(structure in structure(), user in 0..USERS.len()) {
+            let principals = structure.principals(user);
+            let (project, ancestors) = (&structure.lineage[0], &structure.lineage[1..]);
+            prop_assert_eq!(
+                acl::project_permissions(project, ancestors, &principals),
+                oracle(&structure, false, &principals)
+            );
+            prop_assert_eq!(
+                acl::ticket_permissions_in(project, ancestors, Some("OPS"), &principals),
+                oracle(&structure, true, &principals)
+            );
+            // A ticket group that is gone falls back to the project
+            prop_assert_eq!(
+                acl::ticket_permissions_in(project, ancestors, Some("DEV"), &principals),
+                oracle(&structure, false, &principals)
+            );
+        }
+
+        #[test]
+        fn denies_are_never_lifted(structure in structure(), user in 0..USERS.len()) {
+            let principals = structure.principals(user);
+            let (project, ancestors) = (&structure.lineage[0], &structure.lineage[1..]);
+            if !structure.lineage.iter().any(|p| acl::owns(p, &principals)) {
+                let denied = structure.lineage.iter().fold(Permissions::NONE, |acc, p| acc | p.acl.denied(&principals));
+                let permissions = acl::ticket_permissions_in(project, ancestors, Some("OPS"), &principals);
+                prop_assert!(!permissions.intersects(denied));
+            }
+        }
+
+        #[test]
+        fn ancestors_follow_parents(parents in proptest::collection::vec(proptest::option::of(0usize..12), 1..10)) {
+            // Indexes past the end are parents that are missing
+            let mut projects: Vec<Project> = parents.iter().map(|_| sample_project(&[])).collect();
+            for (i, parent) in parents.iter().enumerate() {
+                projects[i].parent_id = parent.map(|p| projects.get(p).map_or("gone".to_string(), |p| p.id.to_string()));
+            }
+            for project in &projects {
+                let ancestors = acl::ancestors_among(&projects, project);
+                prop_assert!(ancestors.len() <= MAX_DEPTH);
+                prop_assert!(ancestors.iter().all(|a| a.id != project.id));
+                let mut child = project;
+                for (i, ancestor) in ancestors.iter().enumerate() {
+                    prop_assert_eq!(child.parent_id.clone(), Some(ancestor.id.to_string()));
+                    prop_assert!(ancestors[..i].iter().all(|a| a.id != ancestor.id));
+                    child = ancestor;
+                }
+            }
+        }
+
+        #[test]
+        fn resolves_from_the_database(structure in structure(), user in 0..USERS.len()) {
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let db: Arc<dyn DatabaseInterface> = Arc::new(InMemoryDatabase::new());
+            let groups = GroupController::new(db.clone(), Arc::new(AclCache::new()));
+            let mut ticket = sample_ticket(1, "Disk full");
+            ticket.project = Some(structure.lineage[0].id.to_string());
+            ticket.ticket_group = Some("OPS".to_string());
+
+            let (principals, permissions) = runtime.block_on(async {
+                for project in &structure.lineage {
+                    db.projects().create_project(project.clone()).await.unwrap();
+                }
+                for gid in GROUPS {
+                    let members = (0..USERS.len())
+                        .filter(|u| structure.memberships[*u].iter().any(|g| g == gid))
+                        .map(|u| USERS[u].to_string())
+                        .collect();
+                    let group = Group {
+                        gid: gid.to_string(),
+                        name: gid.to_string(),
+                        principals: members,
+                        renamed_to: None,
+                    };
+                    db.groups().create_group(group).await.unwrap();
+                }
+                let principals = groups.principals_of(USERS[user]).await.unwrap();
+                let project = &structure.lineage[0];
+                let ancestors = acl::ancestors(db.as_ref(), project).await.unwrap();
+                (principals.clone(), acl::ticket_permissions(project, &ancestors, &ticket, &principals))
+            });
+            // The user first, their groups in any order
+            let mut expected = structure.principals(user);
+            let mut resolved = principals.clone();
+            prop_assert_eq!(resolved.first(), expected.first());
+            resolved.sort();
+            expected.sort();
+            prop_assert_eq!(resolved, expected);
+            prop_assert_eq!(permissions, oracle(&structure, true, &principals));
+        }
+    }
+}
@@ -0,0 +1,278 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, AclInheritance, AccessControlStore, Permissions, TicketGroup},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::ROOT,
+            principals: vec!["alice".to_string()],
+        });
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["carol".to_string()],
+        });
+        project.tickets.push(TicketGroup {
+            prefix: "OPS".to_string(),
+            acl: AccessControlStore::default(),
+            inheritance: AclInheritance::Extend,
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .user(UserFixture::new("dave"))
+            .group("support", &["bob"])
+            .project(project)
+            .build()
+            .await;
+        (app, id)
+    }
+
+    async fn acl_of(app: &TestApp, username: &str, path: &str) -> AccessControlStore {
+        app.get_as(username, path)
+            .await
+            .json::<ApiResponse<AccessControlStore>>()
+            .data
+    }
+
+    #[tokio::test]
+    async fn test_grant_and_revoke() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/acl", id);
+        let before = acl_of(&app, "alice", &path).await;
+
+        let acl = app
+            .post_as("alice", &format!("{}/grant", path))
+            .json(&json!({"principal": "bob", "permissions": "CREATE | MODIFY"}))
+            .await
+            .json::<ApiResponse<AccessControlStore>>()
+            .data;
+        assert_eq!(acl.granted_to("bob"), Permissions::WRITE);
+        // bob joined carol's list instead of getting one of his own
+        assert_eq!(acl.list.len(), 2);
+        assert!(acl.last_mod_date > before.last_mod_date);
+
+        let acl = app
+            .post_as("alice", &format!("{}/revoke", path))
+            .json(&json!({"principal": "bob", "permissions": "MODIFY"}))
+            .await
+            .json::<ApiResponse<AccessControlStore>>()
+            .data;
+        assert_eq!(acl.granted_to("bob"), Permissions::READ | Permissions::CREATE);
+        assert_eq!(acl_of(&app, "alice", &path).await.list.len(), 3);
+
+        // bob's cached permissions are dropped along with the change
+        app.get_as("bob", &path).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("alice", &format!("{}/grant", path))
+            .json(&json!({"principal": "bob", "permissions": "MODIFY"}))
+            .await
+            .assert_status_ok();
+        acl_of(&app, "bob", &path).await;
+
+        // Unknown principals can't be granted anything
+        app.post_as("alice", &format!("{}/grant", path))
+            .json(&json!({"principal": "mallory", "permissions": "READ"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_changes_require_root() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/acl", id);
+
+        // Writers can see the ACL but not change it, readers can't see it
+        acl_of(&app, "carol", &path).await;
+        app.get_as("bob", &path).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("carol", &format!("{}/grant", path))
+            .json(&json!({"principal": "carol", "permissions": "ROOT"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.put_as("carol", &path)
+            .json(&json!([]))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_root_is_kept() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/acl", id);
+
+        app.post_as("alice", &format!("{}/revoke", path))
+            .json(&json!({"principal": "alice", "permissions": "CUSTOM1"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.put_as("alice", &path)
+            .json(&json!([{"permissions": "READ", "principals": ["alice"]}]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Handing ROOT over to a group first lets alice step down
+        app.post_as("alice", &format!("{}/grant", path))
+            .json(&json!({"principal": "support", "permissions": "ROOT"}))
+            .await
+            .assert_status_ok();
+        let acl = app
+            .post_as("alice", &format!("{}/revoke", path))
+            .json(&json!({"principal": "alice", "permissions": "ROOT"}))
+            .await
+            .json::<ApiResponse<AccessControlStore>>()
+            .data;
+        assert_eq!(acl.granted_to("alice"), Permissions::NONE);
+        assert!(acl.allows(&["bob".to_string(), "support".to_string()], Permissions::ROOT));
+    }
+
+    #[tokio::test]
+    async fn test_replace_ticket_group_acl() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/acl?ticket_group=OPS", id);
+
+        let acl = app
+            .put_as("alice", &path)
+            .json(&json!([
+                {"permissions": "READ | CREATE", "principals": ["support"]},
+                {"permissions": "ROOT", "principals": []},
+            ]))
+            .await
+            .json::<ApiResponse<AccessControlStore>>()
+            .data;
+        assert_eq!(acl.list.len(), 1);
+        assert_eq!(acl.granted_to("support"), Permissions::READ | Permissions::CREATE);
+
+        // The project's own ACL is untouched
+        let project = app.state.db.projects().get_project(&id).await.unwrap();
+        assert!(project.acl.allows(&["alice".to_string()], Permissions::ROOT));
+        assert_eq!(project.tickets[0].acl.list.len(), 1);
+
+        app.get_as("alice", &format!("/api/v1/projects/{}/acl?ticket_group=DEV", id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.put_as("alice", &path)
+            .json(&json!([{"permissions": "READ", "principals": ["nobody"]}]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_ticket_group_narrows_project() {
+        let (app, id) = setup().await;
+        let mut project = app.state.db.projects().get_project(&id).await.unwrap();
+        project.tickets[0].inheritance = AclInheritance::Narrow;
+        project.tickets[0].acl.set_permissions("alice", Permissions::ROOT);
+        app.state.db.projects().update_project(&id, project).await.unwrap();
+
+        let ticket = |group: &str| json!({"title": "Disk full", "severity": 3, "project": id, "ticket_group": group});
+        let created = app
+            .post_as("alice", "/api/v1/tickets")
+            .json(&ticket("OPS"))
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(created.ticket_group.as_deref(), Some("OPS"));
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&ticket("DEV"))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // carol writes to the project, but the group grants her nothing
+        app.post_as("carol", "/api/v1/tickets")
+            .json(&ticket("OPS"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let path = format!("/api/v1/tickets/{}/move", created.id);
+        let move_to = json!({"status": "in_progress", "position": 0});
+        app.post_as("carol", &path)
+            .json(&move_to)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.get_as("carol", &format!("/api/v1/tickets/{}", created.id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.post_as("alice", &path).json(&move_to).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_tickets_follow_the_acl() {
+        let (app, id) = setup().await;
+        let create = |username: &str| {
+            app.post_as(username, "/api/v1/tickets")
+                .json(&json!({"title": "Disk full", "severity": 3, "project": id}))
+        };
+        let created = create("carol").await.json::<ApiResponse<TicketResponse>>().data;
+        // bob reads the project, dave has no access to it
+        create("bob").await.assert_status(StatusCode::UNAUTHORIZED);
+        create("dave").await.assert_status(StatusCode::BAD_REQUEST);
+
+        let ticket = format!("/api/v1/tickets/{}", created.id);
+        let comment = json!({"body": "Cleaned up /var/log"});
+        for path in [ticket.clone(), format!("{}/comments", ticket), format!("{}/seen", ticket)] {
+            app.get_as("bob", &path).await.assert_status_ok();
+            app.get_as("dave", &path).await.assert_status(StatusCode::NOT_FOUND);
+        }
+        app.post_as("dave", &format!("{}/seen", ticket))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.post_as("dave", &format!("{}/comments", ticket))
+            .json(&comment)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.post_as("bob", &format!("{}/comments", ticket))
+            .json(&comment)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("carol", &format!("{}/comments", ticket))
+            .json(&comment)
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let listed = |username: &'static str, path: &'static str| {
+            let app = &app;
+            async move {
+                app.get_as(username, path)
+                    .await
+                    .json::<ApiResponse<ListResponse<serde_json::Value>>>()
+                    .data
+                    .items
+                    .len()
+            }
+        };
+        assert_eq!(listed("bob", "/api/v1/tickets").await, 1);
+        assert_eq!(listed("bob", "/api/v1/tickets?fields=id,title").await, 1);
+        assert_eq!(listed("dave", "/api/v1/tickets").await, 0);
+        assert_eq!(listed("dave", "/api/v1/tickets?fields=id,title").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_tickets_of_no_project() {
+        let (app, _) = setup().await;
+        let created = app
+            .post_as("carol", "/api/v1/tickets")
+            .json(&json!({"title": "Printer jam", "severity": 3, "assigned_to": "support"}))
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        let ticket = format!("/api/v1/tickets/{}", created.id);
+        // carol created it, bob is in the group it is assigned to, dave is neither
+        app.get_as("carol", &ticket).await.assert_status_ok();
+        app.get_as("bob", &ticket).await.assert_status_ok();
+        app.get_as("dave", &ticket).await.assert_status(StatusCode::NOT_FOUND);
+        app.post_as("dave", &format!("{}/move", ticket))
+            .json(&json!({"status": "in_progress", "position": 0}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        for path in ["/api/v1/tickets", "/api/v1/tickets?fields=id,title"] {
+            let listed = app.get_as("dave", path).await.json::<ApiResponse<ListResponse<serde_json::Value>>>();
+            assert!(listed.data.items.is_empty());
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Activity, ActivityKind, Permissions, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    /// Alice creates a ticket in the project, starts work on it and comments, bob
+    /// creates a ticket in no project.
+    async fn setup() -> (TestApp, String, i64) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .project(project)
+            .build()
+            .await;
+
+        let ticket = create_ticket(&app, "alice", Some(&id)).await;
+        for status in ["in_progress", "in_progress"] {
+            app.post_as("alice", &format!("/api/v1/tickets/{}/move", ticket))
+                .json(&json!({ "status": status, "position": 0 }))
+                .await
+                .assert_status_ok();
+        }
+        app.post_as("alice", &format!("/api/v1/tickets/{}/comments", ticket))
+            .json(&json!({ "body": "On it" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        create_ticket(&app, "bob", None).await;
+        (app, id, ticket)
+    }
+
+    async fn create_ticket(app: &TestApp, username: &str, project: Option<&str>) -> i64 {
+        let response = app
+            .post_as(username, "/api/v1/tickets")
+            .json(&json!({
+                "title": "Printer on fire",
+                "severity": 2,
+                "severity_label": "major",
+                "project": project,
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        response.json::<ApiResponse<TicketResponse>>().data.id
+    }
+
+    async fn feed(app: &TestApp, username: &str, path: &str) -> ListResponse<Activity> {
+        let response = app.get_as(username, path).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<ListResponse<Activity>>>().data
+    }
+
+    fn kinds(feed: &ListResponse<Activity>) -> Vec<ActivityKind> {
+        feed.items.iter().map(|a| a.kind).collect()
+    }
+
+    #[tokio::test]
+    async fn test_project_activity() {
+        let (app, id, ticket) = setup().await;
+        let path = format!("/api/v1/projects/{}/activity", id);
+
+        // Moves within a column aren't activity
+        let all = feed(&app, "bob", &path).await;
+        assert_eq!(
+            kinds(&all),
+            vec![ActivityKind::CommentAdded, ActivityKind::StatusChanged, ActivityKind::TicketCreated]
+        );
+        assert!(all.items.iter().all(|a| a.ticket == ticket && a.actor == "alice"));
+        assert_eq!(all.items[1].from_status, Some(TicketStatus::Open));
+        assert_eq!(all.items[1].to_status, Some(TicketStatus::InProgress));
+        assert!(all.items[0].comment.is_some());
+
+        let filtered = feed(&app, "bob", &format!("{}?kind=ticket_created,comment_added", path)).await;
+        assert_eq!(kinds(&filtered), vec![ActivityKind::CommentAdded, ActivityKind::TicketCreated]);
+        assert_eq!(filtered.total, 2);
+
+        let first = feed(&app, "bob", &format!("{}?limit=2", path)).await;
+        assert_eq!(first.items, all.items[..2]);
+        let cursor = first.next_cursor.unwrap();
+        let second = feed(&app, "bob", &format!("{}?limit=2&cursor={}", path, cursor)).await;
+        assert_eq!(second.items, all.items[2..]);
+        assert_eq!((second.total, second.next_cursor), (3, None));
+
+        app.get_as("bob", &format!("{}?kind=renamed", path))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.get_as("carol", &path).await.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_my_activity() {
+        let (app, _, ticket) = setup().await;
+
+        let mine = feed(&app, "alice", "/api/v1/me/activity").await;
+        assert_eq!(mine.items.len(), 3);
+        assert!(mine.items.iter().all(|a| a.actor == "alice"));
+        let bobs = feed(&app, "bob", "/api/v1/me/activity").await;
+        assert_eq!(kinds(&bobs), vec![ActivityKind::TicketCreated]);
+        assert_eq!(bobs.items[0].project, None);
+        assert!(feed(&app, "carol", "/api/v1/me/activity").await.items.is_empty());
+
+        // Deleted tickets leave the feeds
+        app.state.db.tickets().delete_ticket(&ticket.to_string(), false).await.unwrap();
+        assert!(feed(&app, "alice", "/api/v1/me/activity").await.items.is_empty());
+    }
+}
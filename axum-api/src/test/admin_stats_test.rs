@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    #[tokio::test]
+    async fn test_admin_stats() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .group("devs", &["alice"])
+            .project(sample_project(&["devs"]))
+            .ticket(sample_ticket(1, "First"))
+            .ticket(sample_ticket(2, "Second"))
+            .websockets()
+            .build()
+            .await;
+        let users = app.state.db.users();
+        let mut bob = users.get_user("bob").await.unwrap();
+        bob.deactivated = true;
+        users.update_user("bob", bob).await.unwrap();
+
+        // Wait for the echo so the connection is surely being handled
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        socket.send_text("ping").await;
+        socket.assert_receive_text("alice said: ping").await;
+
+        let stats = app
+            .get_mgmt("/api/mgmt/stats")
+            .await
+            .json::<ApiResponse<AdminStatsResponse>>()
+            .data;
+        assert_eq!(
+            (stats.users.total, stats.users.active, stats.users.deactivated),
+            (2, 1, 1)
+        );
+        assert_eq!((stats.groups, stats.projects, stats.tickets), (1, 1, 2));
+        assert_eq!(stats.ws_connections, 1);
+        // Announcing alice's presence resolved her groups
+        assert!(stats.acl_cache.misses > 0);
+        assert_eq!(stats.database.backend, "inmemory");
+
+        socket.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_admin_stats_requires_mgmt_token() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .build()
+            .await;
+
+        app.get_as("alice", "/api/mgmt/stats")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,313 @@
+//! Reusable integration test harness: an app backed by a seeded database (an
+//! `InMemoryDatabase` unless given another), with the fixture users already logged in.
+//!
+//! ```ignore
+//! let app = TestApp::builder()
+//!     .user(UserFixture::new("alice"))
+//!     .ticket(sample_ticket(1, "Login page broken"))
+//!     .build()
+//!     .await;
+//! let ticket = app.get_as("alice", "/api/v1/tickets/1").await;
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+use axum::http::StatusCode;
+use axum_test::{TestRequest, TestServer, TestWebSocket};
+use chrono::Utc;
+
+use crate::{
+    middleware::auth::Auth,
+    config::AppConfig,
+    controllers::user_controller::UserController,
+    create_app,
+    db::{DatabaseInterface, inmemory::InMemoryDatabase},
+    models::{
+        AccessControlList, AccessControlStore, Group, Permissions, Project, Severity, Ticket,
+        TicketStatus, User,
+    },
+    schema::{ApiResponse, LoginRequest, LoginResponse},
+    state::AppState,
+};
+
+pub const DEFAULT_PASSWORD: &str = "securepassword123";
+
+/// A user created directly in the database, bypassing registration.
+#[derive(Debug, Clone)]
+pub struct UserFixture {
+    pub username: String,
+    pub password: String,
+    pub email: Option<String>,
+    pub verified: bool,
+}
+
+impl UserFixture {
+    pub fn new(username: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: DEFAULT_PASSWORD.to_string(),
+            email: None,
+            verified: false,
+        }
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_string());
+        self.verified = true;
+        self
+    }
+}
+
+/// Ticket with sensible defaults, override fields as needed.
+pub fn sample_ticket(id: i64, title: &str) -> Ticket {
+    Ticket {
+        id,
+        title: title.to_string(),
+        severity: Severity::new(2, "major"),
+        description: "Steps to reproduce".to_string(),
+        created_by: "reporter".to_string(),
+        assigned_to: "support".to_string(),
+        mentioned: vec![],
+        last_modification: Utc::now(),
+        creation_date: Utc::now(),
+        status: TicketStatus::Open,
+        project: None,
+        resolved_at: None,
+        custom_fields: HashMap::new(),
+        rank: String::new(),
+        milestone: None,
+        escalated_at: None,
+        message_id: None,
+        due_date: None,
+        ticket_group: None,
+    }
+}
+
+/// Project whose ACL grants `READ` to the given principals.
+pub fn sample_project(readers: &[&str]) -> Project {
+    Project {
+        id: uuid::Uuid::now_v7(),
+        acl: AccessControlStore {
+            list: vec![AccessControlList {
+                permissions: Permissions::READ,
+                principals: readers.iter().map(|r| r.to_string()).collect(),
+            }],
+            deny: vec![],
+            last_mod_date: Utc::now(),
+        },
+        tickets: vec![],
+        severities: vec![],
+        custom_fields: vec![],
+        assignment_rules: vec![],
+        escalation_policies: vec![],
+        owner: None,
+        parent_id: None,
+        portal: None,
+    }
+}
+
+type ConfigHook = Box<dyn FnOnce(&mut AppConfig)>;
+type StateHook = Box<dyn FnOnce(&mut AppState)>;
+
+#[derive(Default)]
+pub struct TestAppBuilder {
+    users: Vec<UserFixture>,
+    groups: Vec<Group>,
+    projects: Vec<Project>,
+    tickets: Vec<Ticket>,
+    config: Vec<ConfigHook>,
+    state: Vec<StateHook>,
+    database: Option<Arc<dyn DatabaseInterface>>,
+    websockets: bool,
+}
+
+impl TestAppBuilder {
+    pub fn user(mut self, user: UserFixture) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Group with the given members; members are not created implicitly.
+    pub fn group(mut self, gid: &str, members: &[&str]) -> Self {
+        self.groups.push(Group {
+            gid: gid.to_string(),
+            name: gid.to_string(),
+            principals: members.iter().map(|m| m.to_string()).collect(),
+            renamed_to: None,
+        });
+        self
+    }
+
+    pub fn project(mut self, project: Project) -> Self {
+        self.projects.push(project);
+        self
+    }
+
+    pub fn ticket(mut self, ticket: Ticket) -> Self {
+        self.tickets.push(ticket);
+        self
+    }
+
+    /// Seeds and serves this database instead of a fresh `InMemoryDatabase`.
+    pub fn database(mut self, database: Arc<dyn DatabaseInterface>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Adjusts the config loaded from the environment before the app is built.
+    pub fn config(mut self, configure: impl FnOnce(&mut AppConfig) + 'static) -> Self {
+        self.config.push(Box::new(configure));
+        self
+    }
+
+    /// Adjusts the state (e.g. swaps the notifier) before the app is built.
+    pub fn state(mut self, configure: impl FnOnce(&mut AppState) + 'static) -> Self {
+        self.state.push(Box::new(configure));
+        self
+    }
+
+    /// Serves over a real HTTP transport, required by `TestApp::ws_as`.
+    pub fn websockets(mut self) -> Self {
+        self.websockets = true;
+        self
+    }
+
+    pub async fn build(self) -> TestApp {
+        let mut config = AppConfig::from_env().expect("Failed to load config");
+        for configure in self.config {
+            configure(&mut config);
+        }
+        let auth = Auth::new(config.jwt_secret.as_bytes());
+        let db = self
+            .database
+            .unwrap_or_else(|| Arc::new(InMemoryDatabase::new()));
+
+        for fixture in &self.users {
+            let mut user = User::from(crate::schema::User {
+                username: fixture.username.clone(),
+                password_hash: UserController::hash_password(&fixture.password).unwrap(),
+            });
+            user.email = fixture.email.clone();
+            user.verified = fixture.verified;
+            db.users().create_user(user).await.unwrap();
+        }
+        for group in self.groups {
+            db.groups().create_group(group).await.unwrap();
+        }
+        for project in self.projects {
+            db.projects().create_project(project).await.unwrap();
+        }
+        for ticket in self.tickets {
+            db.tickets().create_ticket(ticket).await.unwrap();
+        }
+
+        let mut state = AppState::new(config, auth, db);
+        // Already started, unless a test swaps the startup state
+        state.startup.ready();
+        for configure in self.state {
+            configure(&mut state);
+        }
+        let state = Arc::new(state);
+
+        let builder = TestServer::builder();
+        let builder = if self.websockets {
+            builder.http_transport()
+        } else {
+            builder
+        };
+        let server = builder
+            .build(create_app(state.clone()))
+            .expect("Failed to create TestServer");
+
+        let mut app = TestApp {
+            server,
+            state,
+            tokens: HashMap::new(),
+        };
+        for fixture in &self.users {
+            let login = app.login(&fixture.username, &fixture.password).await;
+            app.tokens.insert(fixture.username.clone(), login.token);
+        }
+        app
+    }
+}
+
+pub struct TestApp {
+    pub server: TestServer,
+    pub state: Arc<AppState>,
+    tokens: HashMap<String, String>,
+}
+
+impl TestApp {
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder::default()
+    }
+
+    /// Logs in through the API, panicking on anything but 200.
+    pub async fn login(&self, username: &str, password: &str) -> LoginResponse {
+        let response = self
+            .server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: username.to_string(),
+                password: password.to_string(),
+                remember_me: false,
+            })
+            .await;
+        response.assert_status(StatusCode::OK);
+        response.json::<ApiResponse<LoginResponse>>().data
+    }
+
+    /// Access token of a fixture user, issued when the app was built.
+    pub fn token(&self, username: &str) -> &str {
+        self.tokens
+            .get(username)
+            .unwrap_or_else(|| panic!("No fixture user {}", username))
+    }
+
+    pub fn mgmt_token(&self) -> &str {
+        &self.state.config.management_token
+    }
+
+    pub fn get_as(&self, username: &str, path: &str) -> TestRequest {
+        self.server.get(path).authorization_bearer(self.token(username))
+    }
+
+    pub fn post_as(&self, username: &str, path: &str) -> TestRequest {
+        self.server.post(path).authorization_bearer(self.token(username))
+    }
+
+    pub fn put_as(&self, username: &str, path: &str) -> TestRequest {
+        self.server.put(path).authorization_bearer(self.token(username))
+    }
+
+    pub fn delete_as(&self, username: &str, path: &str) -> TestRequest {
+        self.server.delete(path).authorization_bearer(self.token(username))
+    }
+
+    pub fn get_mgmt(&self, path: &str) -> TestRequest {
+        self.server.get(path).authorization_bearer(self.mgmt_token())
+    }
+
+    pub fn post_mgmt(&self, path: &str) -> TestRequest {
+        self.server.post(path).authorization_bearer(self.mgmt_token())
+    }
+
+    pub fn put_mgmt(&self, path: &str) -> TestRequest {
+        self.server.put(path).authorization_bearer(self.mgmt_token())
+    }
+
+    pub fn delete_mgmt(&self, path: &str) -> TestRequest {
+        self.server.delete(path).authorization_bearer(self.mgmt_token())
+    }
+
+    /// Opens a WebSocket as a fixture user, the app must be built with `websockets()`.
+    pub async fn ws_as(&self, username: &str, path: &str) -> TestWebSocket {
+        self.server
+            .get_websocket(path)
+            .authorization_bearer(self.token(username))
+            .await
+            .into_websocket()
+            .await
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        models::{AccessControlList, AssignmentRule, CustomFieldDefinition, CustomFieldType, Permissions},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    /// Alice can change the project, bob can only read it. Carol and dave triage.
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        project.custom_fields.push(CustomFieldDefinition {
+            name: "component".to_string(),
+            kind: CustomFieldType::Select,
+            required: false,
+            options: vec!["ui".to_string(), "db".to_string()],
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .user(UserFixture::new("dave"))
+            .group("triage", &["carol", "dave"])
+            .group("nobody", &[])
+            .project(project)
+            .build()
+            .await;
+        (app, id)
+    }
+
+    fn rules() -> Value {
+        json!([
+            {"name": "critical", "when": {"kind": "severity", "levels": [1]}, "assign": {"kind": "principal", "principal": "alice"}},
+            {"name": "frontend", "when": {"kind": "label", "field": "component", "value": "ui"}, "assign": {"kind": "principal", "principal": "bob"}},
+            {"name": "triage", "when": {"kind": "any"}, "assign": {"kind": "round_robin", "group": "triage"}},
+        ])
+    }
+
+    async fn define(app: &TestApp, id: &str) {
+        app.put_as("alice", &format!("/api/v1/projects/{}/assignment-rules", id))
+            .json(&rules())
+            .await
+            .assert_status_ok();
+    }
+
+    async fn create(app: &TestApp, id: &str, body: Value) -> String {
+        let mut ticket = json!({"title": "Checkout fails", "severity": 3, "project": id});
+        ticket.as_object_mut().unwrap().extend(body.as_object().unwrap().clone());
+        let response = app.post_as("alice", "/api/v1/tickets").json(&ticket).await;
+        response.assert_status(StatusCode::CREATED);
+        response.json::<ApiResponse<TicketResponse>>().data.assigned_to
+    }
+
+    #[tokio::test]
+    async fn test_manage_rules() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/assignment-rules", id);
+        define(&app, &id).await;
+
+        let defined = app.get_as("bob", &path).await.json::<ApiResponse<Vec<AssignmentRule>>>().data;
+        assert_eq!(defined, serde_json::from_value::<Vec<AssignmentRule>>(rules()).unwrap());
+
+        app.put_as("bob", &path).json(&rules()).await.assert_status(StatusCode::UNAUTHORIZED);
+        for invalid in [
+            json!([{"name": "a", "when": {"kind": "any"}, "assign": {"kind": "principal", "principal": "ghost"}}]),
+            json!([{"name": "a", "when": {"kind": "any"}, "assign": {"kind": "round_robin", "group": "nobody"}}]),
+            json!([{"name": "a", "when": {"kind": "label", "field": "team", "value": "ui"}, "assign": {"kind": "principal", "principal": "bob"}}]),
+            json!([{"name": "a", "when": {"kind": "severity", "levels": [7]}, "assign": {"kind": "principal", "principal": "bob"}}]),
+        ] {
+            app.put_as("alice", &path).json(&invalid).await.assert_status(StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(app.get_as("bob", &path).await.json::<ApiResponse<Vec<AssignmentRule>>>().data, defined);
+    }
+
+    #[tokio::test]
+    async fn test_auto_assign() {
+        let (app, id) = setup().await;
+        // Without rules tickets stay unassigned
+        assert_eq!(create(&app, &id, json!({})).await, "");
+        define(&app, &id).await;
+
+        assert_eq!(create(&app, &id, json!({"severity": 1})).await, "alice");
+        assert_eq!(create(&app, &id, json!({"custom_fields": {"component": "ui"}})).await, "bob");
+        assert_eq!(create(&app, &id, json!({"assigned_to": "bob", "severity": 1})).await, "bob");
+
+        // Members of the group take turns
+        let turns = [
+            create(&app, &id, json!({})).await,
+            create(&app, &id, json!({"custom_fields": {"component": "db"}})).await,
+            create(&app, &id, json!({})).await,
+        ];
+        assert_eq!(turns, ["carol", "dave", "carol"]);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/assignment-rules/dry-run", id);
+        let preview = |body: Value| {
+            let (app, path) = (&app, &path);
+            async move {
+                app.post_as("bob", path)
+                    .json(&body)
+                    .await
+                    .json::<ApiResponse<AssignmentDryRunResponse>>()
+                    .data
+            }
+        };
+
+        let unmatched = preview(json!({"severity": 2})).await;
+        assert_eq!((unmatched.rule, unmatched.assigned_to), (None, None));
+
+        define(&app, &id).await;
+        let critical = preview(json!({"severity": 1, "custom_fields": {"component": "ui"}})).await;
+        assert_eq!(critical.rule.as_deref(), Some("critical"));
+        assert_eq!(critical.assigned_to.as_deref(), Some("alice"));
+        let frontend = preview(json!({"severity": 3, "custom_fields": {"component": "ui"}})).await;
+        assert_eq!(frontend.rule.as_deref(), Some("frontend"));
+
+        // Previews don't take a turn
+        for _ in 0..2 {
+            assert_eq!(preview(json!({"severity": 4})).await.assigned_to.as_deref(), Some("carol"));
+        }
+        assert!(app.state.db.tickets().list_tickets().await.unwrap().is_empty());
+
+        app.post_as("carol", &path)
+            .json(&json!({"severity": 4}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
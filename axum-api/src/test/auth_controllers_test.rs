@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use chrono::Utc;
+
+    use crate::{
+        controllers::{
+            Controller,
+            user_controller::{Authentication, RegistrationRules},
+        },
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        events::EventBus,
+        models::User,
+    };
+
+    async fn controller_with(db: Arc<dyn DatabaseInterface>, username: &str) -> Controller {
+        db.users()
+            .create_user(User {
+                username: username.to_string(),
+                ..User::default()
+            })
+            .await
+            .unwrap();
+        Controller::new(db, Arc::new(EventBus::default()), None, None, None)
+    }
+
+    async fn start(controller: &Controller, username: &str, lifetime: chrono::Duration) -> String {
+        controller
+            .session
+            .start_session(username, None, None, Utc::now() + lifetime, 0)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validate_user() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let controller = controller_with(db.clone(), "alice").await;
+        let users = &controller.user;
+
+        assert!(users.validate_user("alice", 0).await.unwrap());
+        assert!(!users.validate_user("alice", 1).await.unwrap());
+        assert!(!users.validate_user("nobody", 0).await.unwrap());
+
+        assert_eq!(users.bump_token_generation("alice").await.unwrap(), 1);
+        assert!(!users.validate_user("alice", 0).await.unwrap());
+        assert!(users.validate_user("alice", 1).await.unwrap());
+
+        let mut alice = db.users().get_user("alice").await.unwrap();
+        alice.deactivated = true;
+        db.users().update_user("alice", alice).await.unwrap();
+        assert!(!users.validate_user("alice", 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_validate_session() {
+        let controller = controller_with(Arc::new(InMemoryDatabase::new()), "alice").await;
+        let sessions = &controller.session;
+
+        let live = start(&controller, "alice", chrono::Duration::hours(1)).await;
+        let expired = start(&controller, "alice", chrono::Duration::hours(-1)).await;
+        assert!(sessions.validate_session(&live, "alice").await.unwrap());
+        assert!(!sessions.validate_session(&live, "bob").await.unwrap());
+        assert!(!sessions.validate_session(&expired, "alice").await.unwrap());
+        assert!(!sessions.validate_session("no-such-session", "alice").await.unwrap());
+
+        sessions.revoke_session("alice", &live).await.unwrap();
+        assert!(!sessions.validate_session(&live, "alice").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_backend_failures_are_errors() {
+        let db = Arc::new(ChaosDatabase::with_seed(
+            Arc::new(InMemoryDatabase::new()),
+            ChaosConfig::default(),
+            1,
+        ));
+        let controller = controller_with(db.clone(), "alice").await;
+        let session = start(&controller, "alice", chrono::Duration::hours(1)).await;
+
+        db.set_config(ChaosConfig {
+            error_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+        assert!(matches!(
+            controller.user.validate_user("alice", 0).await,
+            Err(AppError::Internal(_))
+        ));
+        assert!(matches!(
+            controller.session.validate_session(&session, "alice").await,
+            Err(AppError::Internal(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validated_sessions_are_forgotten_on_revocation() {
+        let controller = controller_with(Arc::new(InMemoryDatabase::new()), "alice").await;
+        let max_age = Duration::from_secs(60);
+        let laptop = start(&controller, "alice", chrono::Duration::hours(1)).await;
+        let phone = start(&controller, "alice", chrono::Duration::hours(1)).await;
+        let validated = controller.session.validated();
+
+        validated.store(&laptop, "alice", 0, max_age);
+        validated.store(&phone, "alice", 0, max_age);
+        assert!(validated.contains(&laptop, "alice", 0, max_age));
+        assert!(!validated.contains(&laptop, "alice", 1, max_age));
+        assert!(!validated.contains(&laptop, "bob", 0, max_age));
+        assert!(!validated.contains(&laptop, "alice", 0, Duration::ZERO));
+
+        controller.session.revoke_session("alice", &phone).await.unwrap();
+        assert!(!validated.contains(&phone, "alice", 0, max_age));
+        assert!(validated.contains(&laptop, "alice", 0, max_age));
+
+        controller.session.revoke_all_sessions("alice", Some(&laptop)).await.unwrap();
+        assert!(validated.contains(&laptop, "alice", 0, max_age));
+
+        controller.user.bump_token_generation("alice").await.unwrap();
+        assert!(!validated.contains(&laptop, "alice", 0, max_age));
+    }
+
+    #[tokio::test]
+    async fn test_new_user_follows_registration_rules() {
+        let controller = Controller::new(Arc::new(InMemoryDatabase::new()), Arc::new(EventBus::default()), None, None, None);
+        let users = &controller.user;
+        let domains = vec!["example.com".to_string()];
+        let open = RegistrationRules::default();
+        let restricted = RegistrationRules {
+            allowed_email_domains: &domains,
+            require_email: true,
+        };
+
+        let user = users.new_user("amy", "securepassword123", Some("Amy@Example.com"), open).unwrap();
+        assert_eq!(user.email.as_deref(), Some("amy@example.com"));
+        assert_ne!(user.password_hash, "securepassword123");
+        assert!(users.new_user("amy", "securepassword123", None, open).unwrap().email.is_none());
+
+        for (username, email) in [("amy*", None), ("amy", None), ("amy", Some("amy@elsewhere.org"))] {
+            assert!(matches!(
+                users.new_user(username, "securepassword123", email, restricted),
+                Err(AppError::Validation(_))
+            ));
+        }
+
+        users.register(user.clone()).await.unwrap();
+        assert!(matches!(users.register(user).await, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let controller = Controller::new(db.clone(), Arc::new(EventBus::default()), None, None, None);
+        let users = &controller.user;
+        let amy = users
+            .new_user("amy", "securepassword123", Some("amy@example.com"), RegistrationRules::default())
+            .unwrap();
+        users.register(amy).await.unwrap();
+        let rejected = |auth| match auth {
+            Authentication::Rejected(detail) => detail,
+            Authentication::Authenticated(user) => panic!("{} was let in", user.username),
+        };
+
+        for login in ["amy", " AMY@example.com"] {
+            let Authentication::Authenticated(user) =
+                users.authenticate(login, "securepassword123", false).await.unwrap()
+            else {
+                panic!("{} was rejected", login);
+            };
+            assert_eq!(user.username, "amy");
+        }
+        assert_eq!(rejected(users.authenticate("amy", "wrong", false).await.unwrap()), "Wrong password");
+        assert_eq!(rejected(users.authenticate("bob", "wrong", false).await.unwrap()), "Unknown user");
+        assert!(matches!(
+            users.authenticate("amy", "securepassword123", true).await,
+            Err(AppError::Authorization(_))
+        ));
+
+        users.mark_email_verified("amy").await.unwrap();
+        assert!(matches!(
+            users.authenticate("amy", "securepassword123", true).await.unwrap(),
+            Authentication::Authenticated(_)
+        ));
+
+        let mut amy = db.users().get_user("amy").await.unwrap();
+        amy.service_account = true;
+        db.users().update_user("amy", amy).await.unwrap();
+        assert_eq!(
+            rejected(users.authenticate("amy", "securepassword123", false).await.unwrap()),
+            "Password login of a service account"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        db::{
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        models::{AccessControlList, Permissions, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let mut builder = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project);
+        // Unranked, as stored before ranks existed
+        for n in 1..=4 {
+            builder = builder.ticket(Ticket {
+                project: Some(id.clone()),
+                ..sample_ticket(n, "Board ticket")
+            });
+        }
+        (builder.build().await, id)
+    }
+
+    async fn board(app: &TestApp, id: &str) -> Vec<(TicketStatus, Vec<i64>)> {
+        app.get_as("bob", &format!("/api/v1/projects/{}/board", id))
+            .await
+            .json::<ApiResponse<BoardResponse>>()
+            .data
+            .columns
+            .into_iter()
+            .map(|c| (c.status, c.tickets.into_iter().map(|t| t.id).collect()))
+            .collect()
+    }
+
+    async fn move_ticket(
+        app: &TestApp,
+        username: &str,
+        id: i64,
+        status: &str,
+        position: usize,
+    ) -> TicketResponse {
+        let response = app
+            .post_as(username, &format!("/api/v1/tickets/{}/move", id))
+            .json(&json!({ "status": status, "position": position }))
+            .await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<TicketResponse>>().data
+    }
+
+    #[tokio::test]
+    async fn test_board_order() {
+        let (app, id) = setup().await;
+        assert_eq!(
+            board(&app, &id).await,
+            vec![
+                (TicketStatus::Open, vec![1, 2, 3, 4]),
+                (TicketStatus::InProgress, vec![]),
+                (TicketStatus::Resolved, vec![]),
+                (TicketStatus::Closed, vec![]),
+            ]
+        );
+
+        move_ticket(&app, "alice", 4, "open", 0).await;
+        move_ticket(&app, "alice", 1, "open", 2).await;
+        assert_eq!(board(&app, &id).await[0].1, vec![4, 2, 1, 3]);
+
+        let moved = move_ticket(&app, "alice", 2, "in_progress", 5).await;
+        assert_eq!(moved.status, TicketStatus::InProgress);
+        let resolved = move_ticket(&app, "alice", 3, "resolved", 0).await;
+        assert!(resolved.resolved_at.is_some());
+        let reopened = move_ticket(&app, "alice", 3, "open", 1).await;
+        assert!(reopened.resolved_at.is_none());
+
+        let columns = board(&app, &id).await;
+        assert_eq!(columns[0].1, vec![4, 3, 1]);
+        assert_eq!(columns[1].1, vec![2]);
+
+        // New tickets join the bottom of the open column
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({"title": "New", "severity": 3, "assigned_to": "alice", "project": id}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        assert_eq!(board(&app, &id).await[0].1, vec![4, 3, 1, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_move_requires_modify() {
+        let (app, id) = setup().await;
+        app.post_as("bob", "/api/v1/tickets/1/move")
+            .json(&json!({"status": "closed", "position": 0}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        assert_eq!(board(&app, &id).await[3].1, Vec::<i64>::new());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_moves() {
+        let (app, id) = setup().await;
+        move_ticket(&app, "alice", 1, "in_progress", 0).await;
+
+        // Dropped into the same spot at once, each lands there and none is lost
+        let (a, b, c) = tokio::join!(
+            move_ticket(&app, "alice", 2, "in_progress", 0),
+            move_ticket(&app, "alice", 3, "in_progress", 0),
+            move_ticket(&app, "alice", 4, "in_progress", 0),
+        );
+        assert!(a.rank != b.rank && b.rank != c.rank && a.rank != c.rank);
+        let mut column = board(&app, &id).await[1].1.clone();
+        assert_eq!(column.pop(), Some(1));
+        column.sort();
+        assert_eq!(column, vec![2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_creates() {
+        let db = Arc::new(ChaosDatabase::new(Arc::new(InMemoryDatabase::new()), ChaosConfig::default()));
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .database(db.clone())
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project)
+            .build()
+            .await;
+        // Slow enough for every create to look for the last rank before any is stored
+        db.set_config(ChaosConfig {
+            latency: Duration::from_millis(5),
+            ..ChaosConfig::default()
+        });
+        let create = || async {
+            let response = app
+                .post_as("alice", "/api/v1/tickets")
+                .json(&json!({"title": "Board ticket", "severity": 3, "project": id}))
+                .await;
+            response.assert_status(StatusCode::CREATED);
+            response.json::<ApiResponse<TicketResponse>>().data
+        };
+
+        let (a, b, c) = tokio::join!(create(), create(), create());
+        assert!(a.rank != b.rank && b.rank != c.rank && a.rank != c.rank);
+        let last = create().await;
+        let mut column = board(&app, &id).await[0].1.clone();
+        assert_eq!(column.pop(), Some(last.id));
+        let mut created = vec![a.id, b.id, c.id];
+        column.sort();
+        created.sort();
+        assert_eq!(column, created);
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        middleware::body_logging::{redact_json, redact_uri},
+        schema::*,
+        test::app::{DEFAULT_PASSWORD, TestApp, UserFixture},
+    };
+
+    #[test]
+    fn test_redact_json() {
+        let mut body = json!({
+            "user": "alice",
+            "password": "hunter2",
+            "data": {
+                "token": "jwt",
+                "refresh_token": "refresh",
+                "code": "123456",
+                "sessions": [{"id": "s1", "Authorization": "Bearer x"}],
+            },
+        });
+        redact_json(&mut body);
+        assert_eq!(
+            body,
+            json!({
+                "user": "alice",
+                "password": "[REDACTED]",
+                "data": {
+                    "token": "[REDACTED]",
+                    "refresh_token": "[REDACTED]",
+                    "code": "[REDACTED]",
+                    "sessions": [{"id": "s1", "Authorization": "[REDACTED]"}],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_redact_uri() {
+        for (uri, redacted) in [
+            ("/api/v1/tickets/1", "/api/v1/tickets/1"),
+            ("/api/v1/me/calendar.ics?token=secret", "/api/v1/me/calendar.ics?token=[REDACTED]"),
+            ("/api/v1/tickets?status=open&flag", "/api/v1/tickets?status=[REDACTED]&flag"),
+            ("/api/register/invite/abc123", "/api/register/invite/[REDACTED]"),
+        ] {
+            assert_eq!(redact_uri(&uri.parse().unwrap()), redacted);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bodies_pass_through_unchanged() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .config(|config| config.log_bodies = Some(16))
+            .build()
+            .await;
+
+        // Logging must not alter or truncate what the handlers and clients see
+        let login = app.login("alice", DEFAULT_PASSWORD).await;
+        assert!(!login.token.is_empty());
+        app.server
+            .post("/api/login")
+            .json(&json!({"user": "alice", "password": "wrong-password"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let response = app.get_as("alice", "/api/v1/me/sessions").await;
+        response.assert_status_ok();
+        let sessions = response.json::<ApiResponse<ListResponse<SessionInfo>>>().data;
+        assert_eq!(sessions.items.len(), 2);
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    use crate::{
+        db::{
+            DatabaseInterface,
+            breaker::{BreakerDatabase, BreakerStats, CircuitBreaker, CircuitState},
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        schema::*,
+        test::app::{TestApp, UserFixture},
+    };
+
+    const COOLDOWN: Duration = Duration::from_millis(100);
+
+    fn failing() -> ChaosConfig {
+        ChaosConfig {
+            error_rate: 1.0,
+            ..ChaosConfig::default()
+        }
+    }
+
+    fn breaker_db() -> (Arc<ChaosDatabase>, Arc<CircuitBreaker>, BreakerDatabase) {
+        let chaos = Arc::new(ChaosDatabase::with_seed(Arc::new(InMemoryDatabase::new()), ChaosConfig::default(), 1));
+        let breaker = Arc::new(CircuitBreaker::new(3, COOLDOWN));
+        let db = BreakerDatabase::new(chaos.clone(), breaker.clone());
+        (chaos, breaker, db)
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_consecutive_failures() {
+        let (chaos, breaker, db) = breaker_db();
+        // Answers other than backend failures don't count
+        for _ in 0..5 {
+            assert!(matches!(db.users().get_user("nobody").await, Err(AppError::NotFound(_))));
+        }
+        assert_eq!(breaker.stats().state, CircuitState::Closed);
+
+        chaos.set_config(failing());
+        for _ in 0..3 {
+            assert!(matches!(db.users().count_users().await, Err(AppError::Internal(_))));
+        }
+        assert_eq!(breaker.stats(), BreakerStats { state: CircuitState::Open, consecutive_failures: 3, trips: 1 });
+        // Failing fast, the backend isn't called
+        let injected = chaos.injected_failures();
+        assert!(matches!(db.users().count_users().await, Err(AppError::Unavailable(_))));
+        assert_eq!(chaos.injected_failures(), injected);
+
+        // A failed probe opens it again
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(matches!(db.users().count_users().await, Err(AppError::Internal(_))));
+        assert!(matches!(db.users().count_users().await, Err(AppError::Unavailable(_))));
+        assert_eq!(breaker.stats().trips, 2);
+
+        // A successful one closes it
+        chaos.set_config(ChaosConfig::default());
+        tokio::time::sleep(COOLDOWN).await;
+        assert_eq!(db.users().count_users().await.unwrap(), 0);
+        assert_eq!(breaker.stats(), BreakerStats { state: CircuitState::Closed, consecutive_failures: 0, trips: 2 });
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_answers_503() {
+        let (chaos, breaker, db) = breaker_db();
+        let shared = breaker.clone();
+        let app = TestApp::builder()
+            .database(Arc::new(db))
+            .user(UserFixture::new("alice"))
+            .state(move |s| s.db_breaker = Some(shared))
+            .build()
+            .await;
+
+        chaos.set_config(failing());
+        for _ in 0..3 {
+            app.get_as("alice", "/api/v1/tickets").await.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        let response = app.get_as("alice", "/api/v1/tickets").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.json::<Value>()["error"]["type"], "unavailable");
+
+        chaos.set_config(ChaosConfig::default());
+        tokio::time::sleep(COOLDOWN).await;
+        app.get_as("alice", "/api/v1/tickets").await.assert_status_ok();
+        let stats = app.get_mgmt("/api/mgmt/stats").await.json::<ApiResponse<AdminStatsResponse>>().data;
+        assert_eq!(stats.database_breaker.unwrap().trips, 1);
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+
+    use crate::{
+        db::{DatabaseInterface, cached::CachedDatabase, inmemory::InMemoryDatabase},
+        models::User,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    fn user(username: &str, name: &str) -> User {
+        let mut user = User {
+            username: username.to_string(),
+            ..User::default()
+        };
+        user.personal.name = name.to_string();
+        user
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_cached_and_writes_invalidate() {
+        let inner = Arc::new(InMemoryDatabase::new());
+        let cached = CachedDatabase::new(inner.clone(), 10, Duration::from_secs(60));
+        cached.users().create_user(user("alice", "Alice")).await.unwrap();
+        assert_eq!(cached.users().get_user("alice").await.unwrap().personal.name, "Alice");
+
+        // Written around the cache: the cached user is served until it expires
+        inner.users().update_user("alice", user("alice", "Behind")).await.unwrap();
+        assert_eq!(cached.users().get_user("alice").await.unwrap().personal.name, "Alice");
+
+        // Written through the cache: seen right away
+        cached.users().update_user("alice", user("alice", "Through")).await.unwrap();
+        assert_eq!(cached.users().get_user("alice").await.unwrap().personal.name, "Through");
+        cached.users().delete_user("alice", true).await.unwrap();
+        assert!(cached.users().get_user("alice").await.is_err());
+        assert!(!cached.users().exists_user("alice").await.unwrap());
+
+        let project = sample_project(&["alice"]);
+        let id = project.id.to_string();
+        cached.projects().create_project(project.clone()).await.unwrap();
+        assert_eq!(cached.projects().list_projects().await.unwrap().len(), 1);
+        cached.projects().get_project(&id).await.unwrap();
+        cached.projects().delete_project(&id, true).await.unwrap();
+        assert!(cached.projects().get_project(&id).await.is_err());
+        assert!(cached.projects().list_projects().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_and_are_bounded() {
+        let inner = Arc::new(InMemoryDatabase::new());
+        let cached = CachedDatabase::new(inner.clone(), 1, Duration::from_millis(200));
+        cached.users().create_user(user("alice", "Alice")).await.unwrap();
+        cached.users().create_user(user("bob", "Bob")).await.unwrap();
+        cached.users().get_user("alice").await.unwrap();
+
+        inner.users().update_user("alice", user("alice", "Later")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(cached.users().get_user("alice").await.unwrap().personal.name, "Later");
+
+        // Caching bob evicts alice, the least recently used entry
+        cached.users().get_user("bob").await.unwrap();
+        cached.run_pending_tasks().await;
+        inner.users().update_user("alice", user("alice", "Evicted")).await.unwrap();
+        assert_eq!(cached.users().get_user("alice").await.unwrap().personal.name, "Evicted");
+    }
+
+    #[tokio::test]
+    async fn test_deactivation_is_honoured_at_once() {
+        let db = Arc::new(CachedDatabase::new(
+            Arc::new(InMemoryDatabase::new()),
+            100,
+            Duration::from_secs(60),
+        ));
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .database(db.clone())
+            .build()
+            .await;
+        app.get_as("alice", "/api/v1/me/metadata").await.assert_status_ok();
+
+        let mut alice = db.users().get_user("alice").await.unwrap();
+        alice.deactivated = true;
+        app.state.db.users().update_user("alice", alice).await.unwrap();
+        app.get_as("alice", "/api/v1/me/metadata")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
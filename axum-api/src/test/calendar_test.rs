@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{NaiveDate, Utc};
+    use serde_json::json;
+
+    use crate::{
+        models::{Milestone, MilestoneState, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 11, d).unwrap()
+    }
+
+    fn milestone(project: &str, name: &str, state: MilestoneState) -> Milestone {
+        Milestone {
+            id: uuid::Uuid::now_v7().to_string(),
+            project: project.to_string(),
+            name: name.to_string(),
+            start: day(1),
+            end: day(14),
+            state,
+            created_at: Utc::now(),
+            closed_at: None,
+        }
+    }
+
+    /// Alice is in the support group and can read one of the two projects.
+    async fn setup() -> TestApp {
+        let visible = sample_project(&["alice"]);
+        let hidden = sample_project(&["bob"]);
+        let due = |id: i64, title: &str, assigned_to: &str, d: u32| Ticket {
+            assigned_to: assigned_to.to_string(),
+            due_date: Some(day(d)),
+            ..sample_ticket(id, title)
+        };
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice"])
+            .ticket(due(1, "Renew certificate", "alice", 20))
+            .ticket(due(2, "Answer the auditors, today", "support", 3))
+            .ticket(due(3, "Bob's deadline", "bob", 5))
+            .ticket(Ticket {
+                status: TicketStatus::Resolved,
+                ..due(4, "Done already", "alice", 6)
+            })
+            .ticket(Ticket {
+                assigned_to: "alice".to_string(),
+                ..sample_ticket(5, "No deadline")
+            })
+            .project(visible.clone())
+            .project(hidden.clone())
+            .build()
+            .await;
+        let milestones = app.state.db.milestones();
+        let visible = visible.id.to_string();
+        milestones.create_milestone(milestone(&visible, "Sprint 1", MilestoneState::Open)).await.unwrap();
+        milestones.create_milestone(milestone(&visible, "Sprint 0", MilestoneState::Closed)).await.unwrap();
+        milestones
+            .create_milestone(milestone(&hidden.id.to_string(), "Secret sprint", MilestoneState::Open))
+            .await
+            .unwrap();
+        app
+    }
+
+    async fn issue_token(app: &TestApp, username: &str) -> CalendarTokenResponse {
+        let response = app.post_as(username, "/api/v1/me/calendar-token").await;
+        response.assert_status(StatusCode::CREATED);
+        response.json::<ApiResponse<CalendarTokenResponse>>().data
+    }
+
+    #[tokio::test]
+    async fn test_feed_of_deadlines() {
+        let app = setup().await;
+        let token = issue_token(&app, "alice").await;
+        assert_eq!(token.url, format!("/api/v1/me/calendar.ics?token={}", token.token));
+
+        let response = app.server.get(&token.url).await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "text/calendar; charset=utf-8");
+        let feed = response.text();
+        let summaries: Vec<&str> = feed.lines().filter_map(|l| l.strip_prefix("SUMMARY:")).collect();
+        // By date: the group's ticket, the open milestone of the visible project, alice's ticket
+        assert_eq!(
+            summaries,
+            vec![
+                "Due: #2 Answer the auditors\\, today",
+                "Milestone ends: Sprint 1",
+                "Due: #1 Renew certificate"
+            ]
+        );
+        assert!(feed.contains("UID:ticket-1@startemplates\r\nDTSTAMP:"));
+        assert!(feed.contains("DTSTART;VALUE=DATE:20261120\r\nDTEND;VALUE=DATE:20261121\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_token_is_revocable() {
+        let app = setup().await;
+        let first = issue_token(&app, "alice").await;
+        let second = issue_token(&app, "alice").await;
+        // Issuing a token replaces the previous one
+        app.server.get(&first.url).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.server.get(&second.url).await.assert_status_ok();
+
+        app.delete_as("alice", "/api/v1/me/calendar-token")
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        app.server.get(&second.url).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.server
+            .get("/api/v1/me/calendar.ics?token=guess")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_due_date_on_create() {
+        let app = setup().await;
+        let response = app
+            .post_as("alice", "/api/v1/tickets")
+            .json(&json!({ "title": "Ship it", "severity": 2, "assigned_to": "alice", "due_date": "2026-11-09" }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(response.json::<ApiResponse<TicketResponse>>().data.due_date, Some(day(9)));
+
+        let token = issue_token(&app, "alice").await;
+        assert!(app.server.get(&token.url).await.text().contains("SUMMARY:Due: #6 Ship it\r\n"));
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    use crate::{
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    fn chaos_db(seed: u64) -> Arc<ChaosDatabase> {
+        Arc::new(ChaosDatabase::with_seed(
+            Arc::new(InMemoryDatabase::new()),
+            ChaosConfig::default(),
+            seed,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_backend_failures_map_to_internal_errors() {
+        let db = chaos_db(1);
+        let app = TestApp::builder()
+            .database(db.clone())
+            .user(UserFixture::new("chaosuser"))
+            .group("support", &["chaosuser"])
+            .ticket(sample_ticket(1, "Seeded before the outage"))
+            .build()
+            .await;
+
+        db.set_config(ChaosConfig {
+            error_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+        let response = app.get_as("chaosuser", "/api/v1/tickets/1").await;
+        response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = response.json::<Value>();
+        assert_eq!(body["error"]["type"], "internal_error");
+        assert!(db.injected_failures() > 0);
+
+        // Nothing is left broken once the backend recovers
+        db.set_config(ChaosConfig::default());
+        app.get_as("chaosuser", "/api/v1/tickets/1")
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_survives_backend_failure() {
+        let db = chaos_db(2);
+        let app = TestApp::builder()
+            .database(db.clone())
+            .user(UserFixture::new("chaosuser"))
+            .build()
+            .await;
+        let request = CreateTicketRequest {
+            title: "Created during an outage".to_string(),
+            severity: 1,
+            severity_label: "critical".to_string(),
+            description: String::new(),
+            assigned_to: "support".to_string(),
+            mentioned: vec![],
+            project: None,
+            custom_fields: Default::default(),
+            due_date: None,
+            ticket_group: None,
+            draft: None,
+        };
+
+        db.set_config(ChaosConfig {
+            error_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+        app.post_as("chaosuser", "/api/v1/tickets")
+            .add_header("Idempotency-Key", "outage-1")
+            .json(&request)
+            .await
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+        // A retry after recovery goes through instead of being stuck or replaying the 500
+        db.set_config(ChaosConfig::default());
+        let response = app
+            .post_as("chaosuser", "/api/v1/tickets")
+            .add_header("Idempotency-Key", "outage-1")
+            .json(&request)
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(
+            response.json::<ApiResponse<TicketResponse>>().data.title,
+            "Created during an outage"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_only_hit_writes() {
+        let db = chaos_db(3);
+        db.set_config(ChaosConfig {
+            conflict_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+
+        assert!(matches!(
+            db.tickets().create_ticket(sample_ticket(1, "Never stored")).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert!(db.tickets().list_tickets().await.unwrap().is_empty());
+        assert_eq!(db.injected_failures(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_error_rate_and_latency() {
+        let db = chaos_db(4);
+        db.set_config(ChaosConfig {
+            error_rate: 0.3,
+            ..ChaosConfig::default()
+        });
+        let mut failures = 0;
+        for _ in 0..1000 {
+            if db.tickets().list_tickets().await.is_err() {
+                failures += 1;
+            }
+        }
+        assert_eq!(failures, db.injected_failures());
+        assert!((200..400).contains(&failures), "{} failures", failures);
+
+        db.set_config(ChaosConfig {
+            latency: Duration::from_millis(50),
+            ..ChaosConfig::default()
+        });
+        let started = std::time::Instant::now();
+        db.users().list_users().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
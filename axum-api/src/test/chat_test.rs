@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::{Value, json};
+
+    use crate::{
+        models::{ChatChannel, ChatEvent, EscalationPolicy, Permissions, Severity, Ticket},
+        notifier::chat::InMemoryChatSender,
+        scheduler::{self, escalate_stale_tickets, post_to_chat},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    const WEBHOOK: &str = "https://hooks.slack.example/services/T0/B0/x";
+
+    async fn setup() -> (TestApp, Arc<InMemoryChatSender>, String) {
+        let mut project = sample_project(&[]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        project.escalation_policies = vec![EscalationPolicy {
+            name: "urgent".to_string(),
+            severity: 2,
+            idle_hours: 4,
+            group: "oncall".to_string(),
+            reassign: false,
+        }];
+        let id = project.id.to_string();
+        let sender = Arc::new(InMemoryChatSender::new());
+        let chat = sender.clone();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .group("oncall", &["alice"])
+            .project(project)
+            .config(|c| c.escalation_interval = 0)
+            .state(move |s| s.chat = chat)
+            .build()
+            .await;
+        (app, sender, id)
+    }
+
+    async fn set_channel(app: &TestApp, project: &str, body: Value) -> axum_test::TestResponse {
+        app.server
+            .put(&format!("/api/mgmt/chat-channels/{}", project))
+            .authorization_bearer(app.mgmt_token())
+            .json(&body)
+            .await
+    }
+
+    fn ticket(id: i64, level: u8, project: &str) -> Ticket {
+        Ticket {
+            project: Some(project.to_string()),
+            severity: Severity::new(level, &format!("level {}", level)),
+            ..sample_ticket(id, "Checkout <fails>")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manage_channels() {
+        let (app, _, id) = setup().await;
+        let response = set_channel(
+            &app,
+            &id,
+            json!({ "platform": "slack", "webhook_url": WEBHOOK, "events": ["ticket_created", "ticket_created"] }),
+        )
+        .await;
+        response.assert_status_ok();
+        let channel = response.json::<ApiResponse<ChatChannel>>().data;
+        assert_eq!(channel.events, vec![ChatEvent::TicketCreated]);
+        assert_eq!(channel.high_severity, 2);
+
+        // Setting it again replaces it
+        set_channel(
+            &app,
+            &id,
+            json!({ "platform": "teams", "webhook_url": WEBHOOK, "events": ["sla_breach"], "high_severity": 1 }),
+        )
+        .await
+        .assert_status_ok();
+        let response = app.get_mgmt("/api/mgmt/chat-channels").await;
+        let channels = response.json::<ApiResponse<Vec<ChatChannel>>>().data;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].events, vec![ChatEvent::SlaBreach]);
+
+        app.server
+            .delete(&format!("/api/mgmt/chat-channels/{}", id))
+            .authorization_bearer(app.mgmt_token())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let response = app.get_mgmt("/api/mgmt/chat-channels").await;
+        assert!(response.json::<ApiResponse<Vec<ChatChannel>>>().data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_channels() {
+        let (app, _, id) = setup().await;
+        let channel = |url: &str, events: Value, high: u8| {
+            json!({ "platform": "slack", "webhook_url": url, "events": events, "high_severity": high })
+        };
+        for body in [
+            channel("hooks.slack.example/x", json!(["ticket_created"]), 2),
+            channel(WEBHOOK, json!([]), 2),
+            channel(WEBHOOK, json!(["ticket_created"]), 9),
+        ] {
+            set_channel(&app, &id, body).await.assert_status(StatusCode::BAD_REQUEST);
+        }
+        set_channel(&app, "missing", channel(WEBHOOK, json!(["ticket_created"]), 2))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.server
+            .put(&format!("/api/mgmt/chat-channels/{}", id))
+            .json(&channel(WEBHOOK, json!(["ticket_created"]), 2))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_only_selected_events_are_posted() {
+        let (app, sender, id) = setup().await;
+        set_channel(
+            &app,
+            &id,
+            json!({ "platform": "slack", "webhook_url": WEBHOOK, "events": ["high_severity"] }),
+        )
+        .await
+        .assert_status_ok();
+
+        post_to_chat(&app.state, &ticket(1, 3, &id), ChatEvent::TicketCreated, None).await;
+        post_to_chat(&app.state, &ticket(2, 1, &id), ChatEvent::TicketCreated, None).await;
+        post_to_chat(&app.state, &sample_ticket(3, "No project"), ChatEvent::TicketCreated, None).await;
+
+        let posted = sender.posted();
+        assert_eq!(posted.len(), 1);
+        let (url, message) = &posted[0];
+        assert_eq!(url, WEBHOOK);
+        assert_eq!(message["text"], "High severity ticket #2: Checkout <fails>");
+        assert_eq!(message["blocks"][0]["text"]["text"], "*High severity ticket #2: Checkout &lt;fails&gt;*");
+    }
+
+    #[tokio::test]
+    async fn test_sla_breach_is_posted_to_teams() {
+        let (app, sender, id) = setup().await;
+        set_channel(
+            &app,
+            &id,
+            json!({ "platform": "teams", "webhook_url": WEBHOOK, "events": ["ticket_created", "sla_breach"] }),
+        )
+        .await
+        .assert_status_ok();
+        let stale = Ticket {
+            last_modification: Utc::now() - Duration::hours(5),
+            ..ticket(1, 2, &id)
+        };
+        app.state.db.tickets().create_ticket(stale).await.unwrap();
+
+        assert_eq!(escalate_stale_tickets(&app.state, Utc::now()).await.unwrap(), 1);
+        let posted = sender.posted();
+        assert_eq!(posted.len(), 1);
+        let message = &posted[0].1;
+        assert_eq!(message["@type"], "MessageCard");
+        assert_eq!(message["title"], "SLA breached on ticket #1: Checkout <fails>");
+        assert_eq!(
+            message["sections"][0]["facts"][3],
+            json!({ "name": "Policy", "value": "urgent: idle for 4h, escalated to oncall" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_tickets_are_relayed() {
+        let (app, sender, id) = setup().await;
+        set_channel(
+            &app,
+            &id,
+            json!({ "platform": "slack", "webhook_url": WEBHOOK, "events": ["ticket_created"] }),
+        )
+        .await
+        .assert_status_ok();
+        scheduler::spawn(app.state.clone());
+
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({ "title": "Refunds are late", "severity": 3, "project": id }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        for _ in 0..100 {
+            if !sender.posted().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let posted = sender.posted();
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0].1["text"], "New ticket #1: Refunds are late");
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        db::inmemory::{InMemoryDatabase, InMemoryLimits},
+        models::{
+            AccessControlList, AccessControlStore, AclInheritance, CustomFieldDefinition, Permissions, Project,
+            TicketGroup, TicketStatus,
+        },
+        schema::*,
+        test::app::{TestApp, TestAppBuilder, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn source() -> Project {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        project.tickets.push(TicketGroup {
+            prefix: "OPS".to_string(),
+            acl: AccessControlStore::default(),
+            inheritance: AclInheritance::Narrow,
+        });
+        let field: CustomFieldDefinition = serde_json::from_value(json!({"name": "customer", "type": "text"})).unwrap();
+        project.custom_fields.push(field);
+        project
+    }
+
+    fn with_tickets(builder: TestAppBuilder, project: &str) -> TestAppBuilder {
+        [
+            (1, "Open", TicketStatus::Open),
+            (2, "Started", TicketStatus::InProgress),
+            (3, "Done", TicketStatus::Resolved),
+        ]
+        .into_iter()
+        .fold(builder, |builder, (id, title, status)| {
+            let mut ticket = sample_ticket(id, title);
+            ticket.project = Some(project.to_string());
+            ticket.status = status;
+            builder.ticket(ticket)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_clone_project() {
+        let project = source();
+        let id = project.id.to_string();
+        let app = with_tickets(TestApp::builder(), &id)
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project)
+            .build()
+            .await;
+        let path = format!("/api/v1/projects/{}/clone", id);
+
+        // Readers can't
+        app.post_as("bob", &path)
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = app.post_as("alice", &path).json(&json!({"include_open_tickets": true})).await;
+        response.assert_status(StatusCode::CREATED);
+        let cloned = response.json::<ApiResponse<CloneProjectResponse>>().data;
+        assert_eq!(cloned.source, id);
+        assert_eq!(cloned.tickets, 2);
+
+        let clone = app.state.db.projects().get_project(&cloned.project).await.unwrap();
+        assert_eq!(clone.owner.as_deref(), Some("alice"));
+        assert_eq!(clone.tickets[0].prefix, "OPS");
+        assert_eq!(clone.tickets[0].inheritance, AclInheritance::Narrow);
+        assert_eq!(clone.custom_fields.len(), 1);
+        assert!(clone.acl.allows(&["bob".to_string()], Permissions::READ));
+
+        let mut copied: Vec<(String, i64)> = app
+            .state
+            .db
+            .tickets()
+            .list_tickets()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|t| t.project.as_deref() == Some(cloned.project.as_str()))
+            .map(|t| (t.title, t.id))
+            .collect();
+        copied.sort();
+        assert_eq!(copied, vec![("Open".to_string(), 4), ("Started".to_string(), 5)]);
+
+        // Without tickets by default
+        let cloned = app
+            .post_as("alice", &path)
+            .json(&json!({}))
+            .await
+            .json::<ApiResponse<CloneProjectResponse>>()
+            .data;
+        assert_eq!(cloned.tickets, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_clone_leaves_nothing_behind() {
+        // Room for the clone and one copied ticket, not two
+        let db = Arc::new(InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: Some(4),
+            ttl: None,
+        }));
+        let project = source();
+        let id = project.id.to_string();
+        let app = with_tickets(TestApp::builder().database(db), &id)
+            .user(UserFixture::new("alice"))
+            .project(project)
+            .build()
+            .await;
+
+        app.post_as("alice", &format!("/api/v1/projects/{}/clone", id))
+            .json(&json!({"include_open_tickets": true}))
+            .await
+            .assert_status(StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(app.state.db.projects().count_projects().await.unwrap(), 1);
+        assert_eq!(app.state.db.tickets().count_tickets().await.unwrap(), 3);
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        models::{AccessControlList, CustomFieldDefinition, Permissions},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project)
+            .build()
+            .await;
+        (app, id)
+    }
+
+    fn definitions() -> Value {
+        json!([
+            {"name": "environment", "type": "select", "required": true, "options": ["staging", "production"]},
+            {"name": "customer", "type": "text"},
+            {"name": "version", "type": "number"},
+        ])
+    }
+
+    async fn define(app: &TestApp, id: &str) {
+        app.put_as("alice", &format!("/api/v1/projects/{}/custom-fields", id))
+            .json(&definitions())
+            .await
+            .assert_status_ok();
+    }
+
+    fn ticket(project: Option<&str>, custom_fields: Value) -> Value {
+        json!({
+            "title": "Checkout fails",
+            "severity": 2,
+            "assigned_to": "alice",
+            "project": project,
+            "custom_fields": custom_fields,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_manage_definitions() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/custom-fields", id);
+        define(&app, &id).await;
+
+        let defined = app
+            .get_as("bob", &path)
+            .await
+            .json::<ApiResponse<Vec<CustomFieldDefinition>>>()
+            .data;
+        assert_eq!(defined.len(), 3);
+        assert!(defined[0].required);
+
+        // Readers can't change them, and select fields need options
+        app.put_as("bob", &path)
+            .json(&definitions())
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.put_as("alice", &path)
+            .json(&json!([{"name": "environment", "type": "select"}]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_values_validated() {
+        let (app, id) = setup().await;
+        define(&app, &id).await;
+
+        let response = app
+            .post_as("alice", "/api/v1/tickets")
+            .json(&ticket(Some(&id), json!({"environment": "production", "version": 3})))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let created = response.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!(created.custom_fields["version"], 3);
+
+        for invalid in [
+            json!({"customer": "acme"}),
+            json!({"environment": "qa"}),
+            json!({"environment": "staging", "version": "3"}),
+            json!({"environment": "staging", "team": "web"}),
+        ] {
+            app.post_as("alice", "/api/v1/tickets")
+                .json(&ticket(Some(&id), invalid))
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+
+        // Nothing is defined outside projects
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&ticket(None, json!({"environment": "staging"})))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_custom_fields() {
+        let (app, id) = setup().await;
+        define(&app, &id).await;
+        for custom_fields in [
+            json!({"environment": "production", "customer": "acme", "version": 2}),
+            json!({"environment": "production", "customer": "globex"}),
+            json!({"environment": "staging", "customer": "acme"}),
+        ] {
+            app.post_as("alice", "/api/v1/tickets")
+                .json(&ticket(Some(&id), custom_fields))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        let ids = |query: &'static str| {
+            let app = &app;
+            async move {
+                app.get_as("alice", &format!("/api/v1/tickets?fields=id{}", query))
+                    .await
+                    .json::<ApiResponse<ListResponse<Value>>>()
+                    .data
+                    .items
+                    .into_iter()
+                    .map(|t| t["id"].as_i64().unwrap())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(ids("&custom=environment:production").await, vec![1, 2]);
+        assert_eq!(ids("&custom=environment:production,customer:acme").await, vec![1]);
+        assert_eq!(ids("&custom=version:2").await, vec![1]);
+        assert!(ids("&custom=customer:initech").await.is_empty());
+
+        app.get_as("alice", "/api/v1/tickets?custom=environment")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,1211 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration as StdDuration};
+
+    use chrono::{Duration, NaiveDate, Utc};
+    use serde_json::json;
+
+    use crate::{
+        db::{
+            ActivityFilter, DatabaseInterface, NotificationFilter, OutboxFilter, SearchHits, SecurityEventFilter, TicketCount, cached::CachedDatabase,
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        events::DomainEvent,
+        models::{
+            Activity, ActivityKind, ApiToken, ChatChannel, ChatEvent, ChatPlatform, Comment, Draft, Group, IdempotencyRecord, Invite, Milestone, MilestoneState, Notification, NotificationKind, OutboxEntry, OutboxStatus, Preference, Project, ReadReceipt,
+            SecurityEvent, SecurityEventKind, Session, Severity, Ticket, TicketShare, TicketStatus, User,
+        },
+        test::app::{sample_project, sample_ticket},
+    };
+
+    fn assert_not_found<T: std::fmt::Debug>(result: Result<T, AppError>) {
+        assert!(
+            matches!(result, Err(AppError::NotFound(_))),
+            "expected NotFound, got {:?}",
+            result
+        );
+    }
+
+    fn assert_conflict<T: std::fmt::Debug>(result: Result<T, AppError>) {
+        assert!(
+            matches!(result, Err(AppError::Conflict(_))),
+            "expected Conflict, got {:?}",
+            result
+        );
+    }
+
+    /// Semantics every `DatabaseInterface` implementation has to share.
+    /// Expects an empty database.
+    async fn run_contract(db: &dyn DatabaseInterface) {
+        users_contract(db).await;
+        groups_contract(db).await;
+        tickets_contract(db).await;
+        sessions_contract(db).await;
+        invites_contract(db).await;
+        security_events_contract(db).await;
+        idempotency_contract(db).await;
+        notifications_contract(db).await;
+        activity_contract(db).await;
+        receipts_contract(db).await;
+        drafts_contract(db).await;
+        shares_contract(db).await;
+        preferences_contract(db).await;
+        milestones_contract(db).await;
+        comments_contract(db).await;
+        chat_channels_contract(db).await;
+        outbox_contract(db).await;
+        projects_contract(db).await;
+        graph_contract(db).await;
+        trash_contract(db).await;
+        search_contract(db).await;
+    }
+
+    async fn users_contract(db: &dyn DatabaseInterface) {
+        let repo = db.users();
+        let user = User {
+            username: "contract-user".to_string(),
+            password_hash: "hash".to_string(),
+            ..User::default()
+        };
+
+        repo.create_user(user.clone()).await.unwrap();
+        assert_conflict(repo.create_user(user.clone()).await);
+        assert_eq!(repo.get_user("contract-user").await.unwrap().password_hash, "hash");
+        assert_not_found(repo.get_user("nobody").await);
+        let batch = repo
+            .get_users(&["nobody".to_string(), "contract-user".to_string(), "nobody".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(batch.found.len(), 1);
+        assert_eq!(batch.missing, vec!["nobody"]);
+
+        assert_eq!(repo.count_deactivated_users().await.unwrap(), 0);
+
+        let updated = User {
+            verified: true,
+            deactivated: true,
+            ..user
+        };
+        repo.update_user("contract-user", updated.clone()).await.unwrap();
+        assert!(repo.get_user("contract-user").await.unwrap().verified);
+        assert_eq!(repo.count_deactivated_users().await.unwrap(), 1);
+        assert_not_found(repo.update_user("nobody", updated).await);
+        assert_eq!(repo.list_users().await.unwrap().len(), 1);
+        assert_eq!(repo.count_users().await.unwrap(), 1);
+        assert!(repo.exists_user("contract-user").await.unwrap());
+        assert!(!repo.exists_user("nobody").await.unwrap());
+
+        let tagged = User {
+            metadata: [("beta".to_string(), "yes".to_string())].into(),
+            ..repo.get_user("contract-user").await.unwrap()
+        };
+        repo.update_user("contract-user", tagged).await.unwrap();
+        assert_eq!(repo.find_users_by_metadata("beta", None).await.unwrap().len(), 1);
+        assert_eq!(repo.find_users_by_metadata("beta", Some("yes")).await.unwrap().len(), 1);
+        assert!(repo.find_users_by_metadata("beta", Some("no")).await.unwrap().is_empty());
+        assert!(repo.find_users_by_metadata("source", None).await.unwrap().is_empty());
+
+        let with_token = User {
+            api_tokens: vec![ApiToken {
+                id: "ci".to_string(),
+                name: "CI".to_string(),
+                hash: "token-hash".to_string(),
+                scopes: vec!["tickets:read".parse().unwrap()],
+                created_at: Utc::now(),
+            }],
+            ..repo.get_user("contract-user").await.unwrap()
+        };
+        repo.update_user("contract-user", with_token).await.unwrap();
+        assert_eq!(repo.find_user_by_api_token("token-hash").await.unwrap().username, "contract-user");
+        assert_not_found(repo.find_user_by_api_token("other-hash").await);
+
+        let identified = User {
+            email: Some("contract@example.com".to_string()),
+            external_ids: vec!["oidc:1234".to_string()],
+            ..repo.get_user("contract-user").await.unwrap()
+        };
+        repo.update_user("contract-user", identified).await.unwrap();
+        assert_eq!(repo.find_user_by_email("contract@example.com").await.unwrap().username, "contract-user");
+        assert_eq!(repo.find_user_by_external_id("oidc:1234").await.unwrap().username, "contract-user");
+        assert_not_found(repo.find_user_by_email("other@example.com").await);
+        assert_not_found(repo.find_user_by_external_id("ldap:1234").await);
+        // Emails and external ids are unique
+        let twin = User {
+            username: "contract-twin".to_string(),
+            email: Some("contract@example.com".to_string()),
+            ..User::default()
+        };
+        assert_conflict(repo.create_user(twin).await);
+        let twin = User {
+            username: "contract-twin".to_string(),
+            external_ids: vec!["ldap:twin".to_string(), "oidc:1234".to_string()],
+            ..User::default()
+        };
+        assert_conflict(repo.create_user(twin).await);
+
+        repo.delete_user("contract-user", true).await.unwrap();
+        assert_not_found(repo.delete_user("contract-user", true).await);
+        assert_not_found(repo.get_user("contract-user").await);
+        assert!(repo.list_users().await.unwrap().is_empty());
+        assert_eq!(repo.count_users().await.unwrap(), 0);
+
+        // Idempotent writes for provisioning
+        let provisioned = User {
+            username: "contract-provisioned".to_string(),
+            password_hash: "first".to_string(),
+            ..User::default()
+        };
+        assert!(repo.upsert_user(provisioned.clone()).await.unwrap());
+        let second = User {
+            password_hash: "second".to_string(),
+            ..provisioned.clone()
+        };
+        assert!(!repo.upsert_user(second.clone()).await.unwrap());
+        assert_eq!(repo.get_user("contract-provisioned").await.unwrap().password_hash, "second");
+        let (found, created) = repo.get_or_create_user(provisioned.clone()).await.unwrap();
+        assert!(!created);
+        assert_eq!(found.password_hash, "second");
+        repo.delete_user("contract-provisioned", true).await.unwrap();
+        let (found, created) = repo.get_or_create_user(provisioned).await.unwrap();
+        assert!(created);
+        assert_eq!(found.password_hash, "first");
+        repo.delete_user("contract-provisioned", true).await.unwrap();
+    }
+
+    async fn groups_contract(db: &dyn DatabaseInterface) {
+        let repo = db.groups();
+        let group = Group {
+            gid: "contract-group".to_string(),
+            name: "Contract".to_string(),
+            principals: vec!["a".to_string()],
+            renamed_to: None,
+        };
+
+        repo.create_group(group.clone()).await.unwrap();
+        assert_conflict(repo.create_group(group.clone()).await);
+        // Groups are not users, even where they share storage
+        assert_not_found(db.users().get_user("contract-group").await);
+        assert!(!db.users().exists_user("contract-group").await.unwrap());
+        assert_eq!(db.users().get_users(&["contract-group".to_string()]).await.unwrap().missing.len(), 1);
+        let batch = repo
+            .get_groups(&["contract-group".to_string(), "nobody".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(batch.found[0].gid, "contract-group");
+        assert_eq!(batch.missing, vec!["nobody"]);
+        assert_eq!(db.users().count_users().await.unwrap(), 0);
+        assert!(repo.exists_group("contract-group").await.unwrap());
+        assert_eq!(repo.count_groups().await.unwrap(), 1);
+
+        let updated = Group {
+            principals: vec!["a".to_string(), "b".to_string()],
+            ..group
+        };
+        repo.update_group("contract-group", updated.clone()).await.unwrap();
+        assert_eq!(repo.get_group("contract-group").await.unwrap().principals.len(), 2);
+        assert_not_found(repo.update_group("nobody", updated).await);
+
+        repo.delete_group("contract-group", true).await.unwrap();
+        assert_not_found(repo.get_group("contract-group").await);
+        assert!(!repo.exists_group("contract-group").await.unwrap());
+        assert_not_found(repo.delete_group("contract-group", true).await);
+
+        let (found, created) = repo.get_or_create_group(group_of(&["a"])).await.unwrap();
+        assert!(created);
+        assert_eq!(found.principals, vec!["a"]);
+        let (found, created) = repo.get_or_create_group(group_of(&["b"])).await.unwrap();
+        assert!(!created);
+        assert_eq!(found.principals, vec!["a"]);
+        assert!(!repo.upsert_group(group_of(&["b"])).await.unwrap());
+        assert_eq!(repo.get_group("contract-group").await.unwrap().principals, vec!["b"]);
+        repo.delete_group("contract-group", true).await.unwrap();
+        assert!(repo.upsert_group(group_of(&["c"])).await.unwrap());
+        repo.delete_group("contract-group", true).await.unwrap();
+    }
+
+    fn group_of(principals: &[&str]) -> Group {
+        Group {
+            gid: "contract-group".to_string(),
+            name: "Contract".to_string(),
+            principals: principals.iter().map(|p| p.to_string()).collect(),
+            renamed_to: None,
+        }
+    }
+
+    async fn tickets_contract(db: &dyn DatabaseInterface) {
+        let repo = db.tickets();
+
+        repo.create_ticket(sample_ticket(1, "First")).await.unwrap();
+        repo.create_ticket(sample_ticket(2, "Second")).await.unwrap();
+        assert_conflict(repo.create_ticket(sample_ticket(1, "Duplicate")).await);
+        assert_eq!(repo.get_ticket("1").await.unwrap().title, "First");
+        assert_not_found(repo.get_ticket("404").await);
+        let batch = repo
+            .get_tickets(&["2".to_string(), "404".to_string(), "1".to_string()])
+            .await
+            .unwrap();
+        let titles: Vec<&str> = batch.found.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Second", "First"]);
+        assert_eq!(batch.missing, vec!["404"]);
+
+        repo.update_ticket("2", sample_ticket(2, "Second, edited")).await.unwrap();
+        assert_eq!(repo.get_ticket("2").await.unwrap().title, "Second, edited");
+        assert_not_found(repo.update_ticket("404", sample_ticket(404, "Ghost")).await);
+
+        let fields = vec!["title".to_string(), "severity".to_string()];
+        assert_eq!(
+            repo.get_ticket_fields("1", &fields).await.unwrap(),
+            json!({"title": "First", "severity": {"level": 2, "label": "major"}})
+        );
+        assert_not_found(repo.get_ticket_fields("404", &fields).await);
+        let projected = repo.list_tickets_fields(&fields).await.unwrap();
+        assert_eq!(projected.len(), 2);
+        assert!(projected.iter().all(|t| t.as_object().unwrap().len() == 2));
+
+        repo.delete_ticket("1", true).await.unwrap();
+        assert_not_found(repo.delete_ticket("1", true).await);
+        assert_eq!(repo.list_tickets().await.unwrap().len(), 1);
+        assert_eq!(repo.count_tickets().await.unwrap(), 1);
+        assert!(repo.exists_ticket("2").await.unwrap());
+        assert!(!repo.exists_ticket("1").await.unwrap());
+
+        for (id, project, status, severity) in [
+            (10, "web", TicketStatus::Open, 1),
+            (11, "web", TicketStatus::Open, 1),
+            (12, "web", TicketStatus::Resolved, 1),
+            (13, "web", TicketStatus::Open, 3),
+            (14, "api", TicketStatus::Open, 1),
+        ] {
+            repo.create_ticket(Ticket {
+                project: Some(project.to_string()),
+                status,
+                severity: Severity::new(severity, "level"),
+                ..sample_ticket(id, "Aggregated")
+            })
+            .await
+            .unwrap();
+        }
+        // Reserved past every id created, the removed one too, and never twice
+        assert_eq!(repo.reserve_ticket_ids(1).await.unwrap(), 15);
+        let (a, b) = tokio::join!(repo.reserve_ticket_ids(2), repo.reserve_ticket_ids(2));
+        let mut firsts = [a.unwrap(), b.unwrap()];
+        firsts.sort();
+        assert_eq!(firsts, [16, 18]);
+        let count = |project: Option<&str>, status, severity, count| TicketCount {
+            project: project.map(str::to_string),
+            status,
+            severity,
+            count,
+        };
+        assert_eq!(
+            repo.ticket_counts(Some("web")).await.unwrap(),
+            vec![
+                count(Some("web"), TicketStatus::Open, 1, 2),
+                count(Some("web"), TicketStatus::Open, 3, 1),
+                count(Some("web"), TicketStatus::Resolved, 1, 1),
+            ]
+        );
+        let all = repo.ticket_counts(None).await.unwrap();
+        assert_eq!(all.len(), 5);
+        assert!(all.contains(&count(None, TicketStatus::Open, 2, 1)));
+        assert_eq!(all.iter().map(|c| c.count).sum::<usize>(), 6);
+        assert!(repo.ticket_counts(Some("nothing")).await.unwrap().is_empty());
+
+        let today = Utc::now().date_naive();
+        let activity = repo.ticket_activity("web", today).await.unwrap();
+        assert_eq!(activity.len(), 1);
+        assert_eq!((activity[0].day, activity[0].opened, activity[0].resolved), (today, 4, 0));
+        assert_eq!(repo.average_resolution_secs("web").await.unwrap(), None);
+
+        let mut resolved = repo.get_ticket("12").await.unwrap();
+        resolved.creation_date = Utc::now() - Duration::minutes(10);
+        resolved.resolved_at = Some(resolved.creation_date + Duration::minutes(5));
+        repo.update_ticket("12", resolved).await.unwrap();
+        assert_eq!(repo.average_resolution_secs("web").await.unwrap(), Some(300.0));
+        assert_eq!(
+            repo.ticket_activity("web", today)
+                .await
+                .unwrap()
+                .iter()
+                .map(|d| d.resolved)
+                .sum::<usize>(),
+            1
+        );
+
+        let assignees = repo.assignee_counts("web", 1).await.unwrap();
+        assert_eq!(assignees.len(), 1);
+        assert_eq!((assignees[0].assignee.as_str(), assignees[0].count), ("support", 4));
+
+        // Unranked tickets, as stored before ranks existed, don't count
+        assert_eq!(repo.last_rank(Some("web"), TicketStatus::Open).await.unwrap(), None);
+        for (id, rank) in [("10", "h"), ("11", "hh"), ("12", "z"), ("14", "y")] {
+            let mut ticket = repo.get_ticket(id).await.unwrap();
+            ticket.rank = rank.to_string();
+            repo.update_ticket(id, ticket).await.unwrap();
+        }
+        let last = |project, status| repo.last_rank(project, status);
+        assert_eq!(last(Some("web"), TicketStatus::Open).await.unwrap().as_deref(), Some("hh"));
+        assert_eq!(last(Some("web"), TicketStatus::Resolved).await.unwrap().as_deref(), Some("z"));
+        assert_eq!(last(None, TicketStatus::Open).await.unwrap(), None);
+
+        let among = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let mut assigned = repo.get_ticket("11").await.unwrap();
+        assigned.assigned_to = "alice".to_string();
+        repo.update_ticket("11", assigned).await.unwrap();
+        let last = |project, names| async move { repo.last_assignee(project, &among(names)).await.unwrap() };
+        assert_eq!(last("web", &["alice", "support"]).await.as_deref(), Some("support"));
+        assert_eq!(last("web", &["alice", "bob"]).await.as_deref(), Some("alice"));
+        assert_eq!(last("api", &["alice"]).await, None);
+        assert_eq!(last("web", &[]).await, None);
+    }
+
+    async fn sessions_contract(db: &dyn DatabaseInterface) {
+        let repo = db.sessions();
+        for (id, username) in [("s1", "alice"), ("s2", "alice"), ("s3", "bob")] {
+            repo.create_session(Session {
+                id: id.to_string(),
+                username: username.to_string(),
+                ..Session::default()
+            })
+            .await
+            .unwrap();
+        }
+        assert_conflict(
+            repo.create_session(Session {
+                id: "s1".to_string(),
+                ..Session::default()
+            })
+            .await,
+        );
+
+        assert_eq!(repo.list_user_sessions("alice").await.unwrap().len(), 2);
+        assert!(repo.list_user_sessions("carol").await.unwrap().is_empty());
+
+        let mut session = repo.get_session("s3").await.unwrap();
+        session.ip = Some("10.0.0.1".to_string());
+        repo.update_session("s3", session.clone()).await.unwrap();
+        assert_eq!(repo.get_session("s3").await.unwrap().ip.as_deref(), Some("10.0.0.1"));
+        assert_not_found(repo.update_session("s404", session).await);
+
+        repo.delete_session("s3").await.unwrap();
+        assert_not_found(repo.get_session("s3").await);
+        assert_not_found(repo.delete_session("s3").await);
+    }
+
+    async fn invites_contract(db: &dyn DatabaseInterface) {
+        let repo = db.invites();
+        let invite = Invite {
+            id: "invite-hash".to_string(),
+            groups: vec!["devs".to_string()],
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::days(1),
+            ..Invite::default()
+        };
+
+        repo.create_invite(invite.clone()).await.unwrap();
+        assert_conflict(repo.create_invite(invite.clone()).await);
+
+        let used = Invite {
+            used_by: Some("alice".to_string()),
+            ..invite.clone()
+        };
+        repo.update_invite("invite-hash", used.clone()).await.unwrap();
+        assert_eq!(
+            repo.get_invite("invite-hash").await.unwrap().used_by.as_deref(),
+            Some("alice")
+        );
+        assert_not_found(repo.update_invite("nope", used).await);
+        assert_eq!(repo.list_invites().await.unwrap().len(), 1);
+
+        // Claims succeed once, on unused invites
+        assert_conflict(repo.claim_invite("invite-hash", "bob", Utc::now()).await);
+        repo.update_invite("invite-hash", invite.clone()).await.unwrap();
+        let claimed = repo.claim_invite("invite-hash", "bob", Utc::now()).await.unwrap();
+        assert_eq!((claimed.used_by.as_deref(), claimed.groups), (Some("bob"), vec!["devs".to_string()]));
+        assert!(claimed.used_at.is_some());
+        assert_conflict(repo.claim_invite("invite-hash", "carol", Utc::now()).await);
+        assert_not_found(repo.claim_invite("nope", "bob", Utc::now()).await);
+
+        repo.delete_invite("invite-hash").await.unwrap();
+        assert_not_found(repo.get_invite("invite-hash").await);
+    }
+
+    async fn security_events_contract(db: &dyn DatabaseInterface) {
+        let repo = db.security_events();
+        let mut ids = vec![];
+        for i in 0..5 {
+            let id = uuid::Uuid::now_v7().to_string();
+            ids.push(id.clone());
+            repo.create_event(SecurityEvent {
+                id,
+                kind: if i % 2 == 0 {
+                    SecurityEventKind::LoginFailure
+                } else {
+                    SecurityEventKind::ApiKeyMisuse
+                },
+                username: Some("alice".to_string()),
+                ip: None,
+                detail: format!("event {}", i),
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        // Newest first, pages don't overlap and cover everything
+        let mut filter = SecurityEventFilter {
+            limit: Some(2),
+            ..SecurityEventFilter::default()
+        };
+        let mut seen = vec![];
+        loop {
+            let page = repo.list_events(&filter).await.unwrap();
+            assert!(page.len() <= 2);
+            assert_eq!(repo.count_events(&filter).await.unwrap(), 5);
+            seen.extend(page.iter().map(|e| e.id.clone()));
+            match page.last() {
+                Some(last) if page.len() == 2 => filter.cursor = Some(last.id.clone()),
+                _ => break,
+            }
+        }
+        ids.reverse();
+        assert_eq!(seen, ids);
+
+        let filter = SecurityEventFilter {
+            kind: Some(SecurityEventKind::LoginFailure),
+            ..SecurityEventFilter::default()
+        };
+        assert_eq!(repo.list_events(&filter).await.unwrap().len(), 3);
+        assert_eq!(repo.count_events(&filter).await.unwrap(), 3);
+        let filter = SecurityEventFilter {
+            username: Some("bob".to_string()),
+            ..SecurityEventFilter::default()
+        };
+        assert_eq!(repo.count_events(&filter).await.unwrap(), 0);
+    }
+
+    async fn idempotency_contract(db: &dyn DatabaseInterface) {
+        let repo = db.idempotency();
+        let record = IdempotencyRecord {
+            id: "record".to_string(),
+            fingerprint: "fp".to_string(),
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::hours(1),
+            ..IdempotencyRecord::default()
+        };
+
+        repo.create_record(record.clone()).await.unwrap();
+        assert_conflict(repo.create_record(record.clone()).await);
+
+        let completed = IdempotencyRecord {
+            status: Some(201),
+            ..record
+        };
+        repo.update_record("record", completed.clone()).await.unwrap();
+        assert_eq!(repo.get_record("record").await.unwrap().status, Some(201));
+        assert_not_found(repo.update_record("missing", completed).await);
+
+        repo.delete_record("record").await.unwrap();
+        assert_not_found(repo.get_record("record").await);
+        assert_not_found(repo.delete_record("record").await);
+    }
+
+    async fn notifications_contract(db: &dyn DatabaseInterface) {
+        let repo = db.notifications();
+        let notification = |recipient: &str| Notification {
+            id: uuid::Uuid::now_v7().to_string(),
+            recipient: recipient.to_string(),
+            kind: NotificationKind::DirectMessage,
+            payload: json!({ "body": "hi" }),
+            link: None,
+            read: false,
+            created_at: Utc::now(),
+        };
+        let first = notification("alice");
+        let second = notification("alice");
+
+        repo.create_notification(first.clone()).await.unwrap();
+        repo.create_notification(second.clone()).await.unwrap();
+        repo.create_notification(notification("bob")).await.unwrap();
+        assert_conflict(repo.create_notification(first.clone()).await);
+
+        let all = NotificationFilter::default();
+        let ids: Vec<String> = repo
+            .list_notifications("alice", &all)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![second.id.clone(), first.id.clone()]);
+        assert!(repo.list_notifications("carol", &all).await.unwrap().is_empty());
+
+        let read = Notification {
+            read: true,
+            ..first.clone()
+        };
+        repo.update_notification(&first.id, read.clone()).await.unwrap();
+        assert!(repo.get_notification(&first.id).await.unwrap().read);
+        assert_not_found(repo.update_notification("missing", read).await);
+        assert_not_found(repo.get_notification("missing").await);
+
+        let unread = NotificationFilter {
+            unread: Some(true),
+            ..Default::default()
+        };
+        let listed = repo.list_notifications("alice", &unread).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, second.id);
+        assert_eq!(repo.count_notifications("alice", &unread).await.unwrap(), 1);
+        assert_eq!(repo.count_notifications("alice", &all).await.unwrap(), 2);
+
+        let page = NotificationFilter {
+            cursor: Some(second.id.clone()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let older = repo.list_notifications("alice", &page).await.unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].id, first.id);
+        assert_eq!(repo.count_notifications("alice", &page).await.unwrap(), 2);
+    }
+
+    async fn activity_contract(db: &dyn DatabaseInterface) {
+        let repo = db.activity();
+        let activity = |kind: ActivityKind, actor: &str, project: Option<&str>| Activity {
+            id: uuid::Uuid::now_v7().to_string(),
+            kind,
+            actor: actor.to_string(),
+            ticket: 1,
+            title: "Printer on fire".to_string(),
+            project: project.map(str::to_string),
+            from_status: None,
+            to_status: None,
+            comment: None,
+            at: Utc::now(),
+        };
+        let created = activity(ActivityKind::TicketCreated, "alice", Some("p1"));
+        let moved = Activity {
+            from_status: Some(TicketStatus::Open),
+            to_status: Some(TicketStatus::Resolved),
+            ..activity(ActivityKind::StatusChanged, "bob", Some("p1"))
+        };
+        let commented = activity(ActivityKind::CommentAdded, "alice", None);
+
+        for a in [&created, &moved, &commented] {
+            repo.create_activity(a.clone()).await.unwrap();
+        }
+        assert_conflict(repo.create_activity(created.clone()).await);
+
+        let all = ActivityFilter::default();
+        assert_eq!(repo.list_activity(&all).await.unwrap(), vec![commented.clone(), moved.clone(), created.clone()]);
+
+        let project = ActivityFilter {
+            project: Some("p1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(repo.list_activity(&project).await.unwrap(), vec![moved.clone(), created.clone()]);
+        let alice = ActivityFilter {
+            actor: Some("alice".to_string()),
+            kinds: vec![ActivityKind::TicketCreated, ActivityKind::StatusChanged],
+            ..Default::default()
+        };
+        assert_eq!(repo.list_activity(&alice).await.unwrap(), vec![created.clone()]);
+        assert_eq!(repo.count_activity(&alice).await.unwrap(), 1);
+
+        let page = ActivityFilter {
+            cursor: Some(commented.id.clone()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(repo.list_activity(&page).await.unwrap(), vec![moved]);
+        assert_eq!(repo.count_activity(&page).await.unwrap(), 3);
+    }
+
+    async fn receipts_contract(db: &dyn DatabaseInterface) {
+        let repo = db.receipts();
+        let receipt = |ticket: i64, username: &str, minutes: i64| ReadReceipt {
+            ticket,
+            username: username.to_string(),
+            seen_at: Utc::now() - Duration::minutes(minutes),
+        };
+        let alice = receipt(1, "alice", 10);
+        let bob = receipt(1, "bob", 5);
+        let elsewhere = receipt(2, "alice", 1);
+
+        for r in [&alice, &bob, &elsewhere] {
+            repo.set_receipt(r.clone()).await.unwrap();
+        }
+        assert_eq!(repo.list_receipts(1).await.unwrap(), vec![bob.clone(), alice.clone()]);
+
+        // Seeing the ticket again replaces the receipt
+        let again = receipt(1, "alice", 0);
+        repo.set_receipt(again.clone()).await.unwrap();
+        assert_eq!(repo.list_receipts(1).await.unwrap(), vec![again.clone(), bob]);
+        let mut of_alice = repo.list_receipts_of("alice").await.unwrap();
+        of_alice.sort_by_key(|r| r.ticket);
+        assert_eq!(of_alice, vec![again, elsewhere]);
+        assert!(repo.list_receipts_of("carol").await.unwrap().is_empty());
+        assert!(repo.list_receipts(3).await.unwrap().is_empty());
+    }
+
+    async fn drafts_contract(db: &dyn DatabaseInterface) {
+        let repo = db.drafts();
+        let draft = Draft {
+            id: "draft-1".to_string(),
+            owner: "alice".to_string(),
+            key: "new-ticket".to_string(),
+            payload: json!({ "title": "Printer" }),
+            updated_at: Utc::now(),
+            expires_at: Utc::now() + Duration::days(7),
+        };
+
+        assert_not_found(repo.get_draft("draft-1").await);
+        repo.put_draft(draft.clone()).await.unwrap();
+        assert_eq!(repo.get_draft("draft-1").await.unwrap(), draft);
+
+        let saved = Draft {
+            payload: json!({ "title": "Printer on fire" }),
+            ..draft
+        };
+        repo.put_draft(saved.clone()).await.unwrap();
+        assert_eq!(repo.get_draft("draft-1").await.unwrap(), saved);
+
+        repo.delete_draft("draft-1").await.unwrap();
+        assert_not_found(repo.get_draft("draft-1").await);
+        assert_not_found(repo.delete_draft("draft-1").await);
+    }
+
+    async fn shares_contract(db: &dyn DatabaseInterface) {
+        let repo = db.shares();
+        let share = |id: &str, ticket: i64, minutes: i64| TicketShare {
+            id: id.to_string(),
+            ticket,
+            created_by: "alice".to_string(),
+            created_at: Utc::now() - Duration::minutes(minutes),
+            expires_at: Utc::now() + Duration::days(3),
+        };
+        let newer = share("share-1", 1, 0);
+        let older = share("share-2", 1, 5);
+
+        assert_not_found(repo.get_share("share-1").await);
+        repo.create_share(newer.clone()).await.unwrap();
+        repo.create_share(older.clone()).await.unwrap();
+        repo.create_share(share("share-3", 2, 0)).await.unwrap();
+        assert_conflict(repo.create_share(newer.clone()).await);
+        assert_eq!(repo.get_share("share-1").await.unwrap(), newer);
+        assert_eq!(repo.list_shares(1).await.unwrap(), vec![older, newer]);
+        assert!(repo.list_shares(3).await.unwrap().is_empty());
+
+        repo.delete_share("share-1").await.unwrap();
+        assert_not_found(repo.get_share("share-1").await);
+        assert_not_found(repo.delete_share("share-1").await);
+        assert_eq!(repo.list_shares(1).await.unwrap().len(), 1);
+    }
+
+    async fn preferences_contract(db: &dyn DatabaseInterface) {
+        let repo = db.preferences();
+        let preference = |owner: &str, namespace: &str, version: u64| Preference {
+            id: format!("{}-{}", owner, namespace),
+            owner: owner.to_string(),
+            namespace: namespace.to_string(),
+            value: json!({ "version": version }),
+            version,
+            updated_at: Utc::now(),
+        };
+
+        assert_not_found(repo.get_preference("alice-ui").await);
+        assert_not_found(repo.put_preference(preference("alice", "ui", 2)).await);
+        repo.put_preference(preference("alice", "ui", 1)).await.unwrap();
+        assert_conflict(repo.put_preference(preference("alice", "ui", 1)).await);
+        repo.put_preference(preference("alice", "ui", 2)).await.unwrap();
+        // A save based on a version that was replaced since
+        assert_conflict(repo.put_preference(preference("alice", "ui", 2)).await);
+        assert_conflict(repo.put_preference(preference("alice", "ui", 4)).await);
+        let stored = repo.get_preference("alice-ui").await.unwrap();
+        assert_eq!((stored.version, stored.value), (2, json!({ "version": 2 })));
+
+        repo.put_preference(preference("alice", "board", 1)).await.unwrap();
+        repo.put_preference(preference("bob", "ui", 1)).await.unwrap();
+        let namespaces: Vec<String> = repo
+            .list_preferences("alice")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.namespace)
+            .collect();
+        assert_eq!(namespaces, ["board", "ui"]);
+
+        repo.delete_preference("alice-ui").await.unwrap();
+        assert_not_found(repo.get_preference("alice-ui").await);
+        assert_not_found(repo.delete_preference("alice-ui").await);
+        assert_eq!(repo.list_preferences("bob").await.unwrap().len(), 1);
+    }
+
+    async fn milestones_contract(db: &dyn DatabaseInterface) {
+        let repo = db.milestones();
+        let milestone = |project: &str, name: &str, start: u32| Milestone {
+            id: uuid::Uuid::now_v7().to_string(),
+            project: project.to_string(),
+            name: name.to_string(),
+            start: NaiveDate::from_ymd_opt(2026, 3, start).unwrap(),
+            end: NaiveDate::from_ymd_opt(2026, 3, start + 13).unwrap(),
+            state: MilestoneState::Open,
+            created_at: Utc::now(),
+            closed_at: None,
+        };
+        let later = milestone("web", "Sprint 2", 15);
+        let earlier = milestone("web", "Sprint 1", 1);
+
+        repo.create_milestone(later.clone()).await.unwrap();
+        repo.create_milestone(earlier.clone()).await.unwrap();
+        repo.create_milestone(milestone("api", "Sprint 1", 1)).await.unwrap();
+        assert_conflict(repo.create_milestone(later.clone()).await);
+        assert_eq!(repo.get_milestone(&later.id).await.unwrap(), later);
+        assert_not_found(repo.get_milestone("missing").await);
+
+        let listed = repo.list_milestones("web").await.unwrap();
+        assert_eq!(listed, vec![earlier.clone(), later.clone()]);
+        assert!(repo.list_milestones("mobile").await.unwrap().is_empty());
+
+        let closed = Milestone {
+            state: MilestoneState::Closed,
+            closed_at: Some(Utc::now()),
+            ..earlier.clone()
+        };
+        repo.update_milestone(&earlier.id, closed.clone()).await.unwrap();
+        assert_eq!(repo.get_milestone(&earlier.id).await.unwrap().state, MilestoneState::Closed);
+        assert_not_found(repo.update_milestone("missing", closed).await);
+    }
+
+    async fn comments_contract(db: &dyn DatabaseInterface) {
+        let repo = db.comments();
+        let comment = |ticket: i64, body: &str, message_id: Option<&str>| Comment {
+            id: uuid::Uuid::now_v7().to_string(),
+            ticket,
+            author: "alice".to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+            message_id: message_id.map(str::to_string),
+        };
+        let first = comment(1, "First", None);
+        let second = comment(1, "Second", Some("reply@mail.example"));
+
+        repo.create_comment(first.clone()).await.unwrap();
+        repo.create_comment(second.clone()).await.unwrap();
+        repo.create_comment(comment(2, "Elsewhere", None)).await.unwrap();
+        assert_conflict(repo.create_comment(first.clone()).await);
+
+        assert_eq!(repo.list_comments(1).await.unwrap(), vec![first.clone(), second.clone()]);
+        assert!(repo.list_comments(3).await.unwrap().is_empty());
+
+        assert_eq!(
+            repo.find_comment_by_message_id("reply@mail.example").await.unwrap(),
+            Some(second.clone())
+        );
+        assert_eq!(repo.find_comment_by_message_id("missing@mail.example").await.unwrap(), None);
+
+        let by_bob = Comment {
+            author: "bob".to_string(),
+            ..first.clone()
+        };
+        repo.update_comment(&first.id, by_bob.clone()).await.unwrap();
+        assert_eq!(repo.list_comments(1).await.unwrap(), vec![by_bob.clone(), second]);
+        assert_eq!(repo.list_comments_by("bob").await.unwrap(), vec![by_bob.clone()]);
+        assert_eq!(repo.list_comments_by("alice").await.unwrap().len(), 2);
+        assert_not_found(repo.update_comment("missing", by_bob).await);
+    }
+
+    async fn chat_channels_contract(db: &dyn DatabaseInterface) {
+        let repo = db.chat_channels();
+        let updated_at = Utc::now();
+        let channel = |project: &str| ChatChannel {
+            project: project.to_string(),
+            platform: ChatPlatform::Slack,
+            webhook_url: "https://hooks.slack.example/services/x".to_string(),
+            events: vec![ChatEvent::TicketCreated],
+            high_severity: 2,
+            updated_at,
+        };
+
+        repo.create_chat_channel(channel("web")).await.unwrap();
+        repo.create_chat_channel(channel("api")).await.unwrap();
+        assert_conflict(repo.create_chat_channel(channel("web")).await);
+        assert_eq!(repo.get_chat_channel("web").await.unwrap(), channel("web"));
+        assert_not_found(repo.get_chat_channel("mobile").await);
+
+        let teams = ChatChannel {
+            platform: ChatPlatform::Teams,
+            ..channel("web")
+        };
+        repo.update_chat_channel("web", teams.clone()).await.unwrap();
+        assert_eq!(repo.get_chat_channel("web").await.unwrap(), teams);
+        assert_not_found(repo.update_chat_channel("mobile", channel("mobile")).await);
+
+        let projects: Vec<String> = repo.list_chat_channels().await.unwrap().into_iter().map(|c| c.project).collect();
+        assert_eq!(projects, vec!["api", "web"]);
+        repo.delete_chat_channel("api").await.unwrap();
+        assert_not_found(repo.get_chat_channel("api").await);
+        assert_not_found(repo.delete_chat_channel("api").await);
+    }
+
+    async fn outbox_contract(db: &dyn DatabaseInterface) {
+        let repo = db.outbox();
+        let mut ids = vec![];
+        for i in 0..4 {
+            let id = uuid::Uuid::now_v7().to_string();
+            ids.push(id.clone());
+            repo.create_outbox_entry(OutboxEntry {
+                id,
+                event: DomainEvent::UserRegistered {
+                    username: format!("user{}", i),
+                    at: Utc::now(),
+                },
+                status: OutboxStatus::Pending,
+                attempts: 0,
+                last_error: None,
+                created_at: Utc::now(),
+                delivered_at: None,
+            })
+            .await
+            .unwrap();
+        }
+        let mut entry = repo.get_outbox_entry(&ids[1]).await.unwrap();
+        assert!(matches!(&entry.event, DomainEvent::UserRegistered { username, .. } if username == "user1"));
+        assert_conflict(repo.create_outbox_entry(entry.clone()).await);
+        assert_not_found(repo.get_outbox_entry("missing").await);
+
+        entry.status = OutboxStatus::Delivered;
+        entry.attempts = 1;
+        entry.delivered_at = Some(Utc::now());
+        repo.update_outbox_entry(&ids[1], entry.clone()).await.unwrap();
+        assert_eq!(repo.get_outbox_entry(&ids[1]).await.unwrap().status, OutboxStatus::Delivered);
+        assert_not_found(repo.update_outbox_entry("missing", entry).await);
+
+        // Oldest first
+        let listed = |filter: OutboxFilter| async move {
+            let entries = repo.list_outbox_entries(&filter).await.unwrap();
+            entries.into_iter().map(|e| e.id).collect::<Vec<_>>()
+        };
+        assert_eq!(listed(OutboxFilter::default()).await, ids);
+        let pending = OutboxFilter {
+            status: Some(OutboxStatus::Pending),
+            limit: Some(2),
+            ..OutboxFilter::default()
+        };
+        assert_eq!(listed(pending).await, vec![ids[0].clone(), ids[2].clone()]);
+        let later = OutboxFilter {
+            since: Some(Utc::now() + Duration::hours(1)),
+            ..OutboxFilter::default()
+        };
+        assert!(listed(later).await.is_empty());
+    }
+
+    async fn projects_contract(db: &dyn DatabaseInterface) {
+        let repo = db.projects();
+        let root = sample_project(&["reader"]);
+        let below = |parent: &Project| Project {
+            parent_id: Some(parent.id.to_string()),
+            ..sample_project(&[])
+        };
+        let (a, b) = (below(&root), below(&root));
+        let a1 = below(&a);
+        for project in [&root, &a, &b, &a1] {
+            repo.create_project(project.clone()).await.unwrap();
+        }
+        assert_conflict(repo.create_project(root.clone()).await);
+        assert!(repo.exists_project(&a.id.to_string()).await.unwrap());
+
+        // Closest first, siblings in any order
+        let ids = |projects: Vec<Project>| projects.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        let tree = ids(repo.list_subprojects(&root.id.to_string()).await.unwrap());
+        assert_eq!(tree.len(), 3);
+        assert!(tree[..2].contains(&a.id) && tree[..2].contains(&b.id));
+        assert_eq!(tree[2], a1.id);
+        assert_eq!(ids(repo.list_subprojects(&a.id.to_string()).await.unwrap()), vec![a1.id]);
+        assert!(repo.list_subprojects(&b.id.to_string()).await.unwrap().is_empty());
+
+        // Moving a1 to the top level takes it out of the tree
+        let moved = Project {
+            parent_id: None,
+            ..a1.clone()
+        };
+        repo.update_project(&a1.id.to_string(), moved).await.unwrap();
+        assert!(repo.list_subprojects(&a.id.to_string()).await.unwrap().is_empty());
+
+        repo.delete_project(&b.id.to_string(), true).await.unwrap();
+        assert_not_found(repo.get_project(&b.id.to_string()).await);
+        assert_eq!(ids(repo.list_subprojects(&root.id.to_string()).await.unwrap()), vec![a.id]);
+        for project in [&root, &a, &a1] {
+            repo.delete_project(&project.id.to_string(), true).await.unwrap();
+        }
+        assert!(repo.list_projects().await.unwrap().is_empty());
+    }
+
+    async fn graph_contract(db: &dyn DatabaseInterface) {
+        let group = |gid: &str, principals: &[&str]| Group {
+        renamed_to: None,
+            gid: gid.to_string(),
+            name: gid.to_string(),
+            principals: principals.iter().map(|p| p.to_string()).collect(),
+        };
+        // all and loop contain each other
+        for g in [
+            group("graph-devs", &["alice"]),
+            group("graph-eng", &["graph-devs", "bob"]),
+            group("graph-all", &["graph-eng", "graph-loop"]),
+            group("graph-loop", &["graph-all"]),
+        ] {
+            db.groups().create_group(g).await.unwrap();
+        }
+        let graph = db.graph();
+        assert_eq!(graph.groups_of("alice").await.unwrap(), vec!["graph-all", "graph-devs", "graph-eng", "graph-loop"]);
+        assert_eq!(graph.groups_of("bob").await.unwrap(), vec!["graph-all", "graph-eng", "graph-loop"]);
+        assert_eq!(graph.groups_of("graph-all").await.unwrap(), vec!["graph-loop"]);
+        assert!(graph.groups_of("nobody").await.unwrap().is_empty());
+
+        let owned = |owner: &str, parent: Option<&Project>| Project {
+            owner: Some(owner.to_string()),
+            parent_id: parent.map(|p| p.id.to_string()),
+            ..sample_project(&[])
+        };
+        let mine = owned("alice", None);
+        let below = owned("carol", Some(&mine));
+        let further = owned("carol", Some(&below));
+        let team = owned("graph-eng", None);
+        let other = owned("carol", None);
+        for project in [&mine, &below, &further, &team, &other] {
+            db.projects().create_project(project.clone()).await.unwrap();
+        }
+        let ids = |projects: Vec<Project>| projects.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        let mut expected = vec![mine.id, below.id, further.id, team.id];
+        expected.sort();
+        assert_eq!(ids(graph.owned_projects("alice").await.unwrap()), expected);
+        assert_eq!(ids(graph.owned_projects("bob").await.unwrap()), vec![team.id]);
+        assert!(graph.owned_projects("nobody").await.unwrap().is_empty());
+
+        // Leaving a group leaves what it owns
+        db.groups().update_group("graph-devs", group("graph-devs", &[])).await.unwrap();
+        assert!(graph.groups_of("alice").await.unwrap().is_empty());
+        let mut expected = vec![mine.id, below.id, further.id];
+        expected.sort();
+        assert_eq!(ids(graph.owned_projects("alice").await.unwrap()), expected);
+        db.groups().delete_group("graph-eng", true).await.unwrap();
+        assert!(graph.groups_of("bob").await.unwrap().is_empty());
+
+        for gid in ["graph-devs", "graph-all", "graph-loop"] {
+            db.groups().delete_group(gid, true).await.unwrap();
+        }
+        for project in [&mine, &below, &further, &team, &other] {
+            db.projects().delete_project(&project.id.to_string(), true).await.unwrap();
+        }
+    }
+
+    async fn trash_contract(db: &dyn DatabaseInterface) {
+        let users = db.users();
+        let user = User {
+            username: "trash-user".to_string(),
+            email: Some("trash@example.com".to_string()),
+            ..User::default()
+        };
+        users.create_user(user.clone()).await.unwrap();
+        users.delete_user("trash-user", false).await.unwrap();
+        assert_not_found(users.delete_user("trash-user", false).await);
+        assert_not_found(users.get_user("trash-user").await);
+        assert_not_found(users.find_user_by_email("trash@example.com").await);
+        assert!(users.list_users().await.unwrap().is_empty());
+        assert_eq!(users.count_users().await.unwrap(), 0);
+        assert!(!users.exists_user("trash-user").await.unwrap());
+        assert_eq!(users.get_users(&["trash-user".to_string()]).await.unwrap().missing, vec!["trash-user"]);
+        assert_not_found(users.update_user("trash-user", user.clone()).await);
+        // Its username and email stay taken
+        assert_conflict(users.create_user(user.clone()).await);
+        assert_conflict(users.get_or_create_user(user.clone()).await);
+        let twin = User {
+            username: "trash-twin".to_string(),
+            email: Some("trash@example.com".to_string()),
+            ..User::default()
+        };
+        assert_conflict(users.create_user(twin).await);
+        let deleted = users.list_deleted_users().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].entity.username, "trash-user");
+        assert!(deleted[0].deleted_at <= Utc::now());
+
+        users.restore_user("trash-user").await.unwrap();
+        assert_not_found(users.restore_user("trash-user").await);
+        assert_eq!(users.get_user("trash-user").await.unwrap().email.as_deref(), Some("trash@example.com"));
+        assert!(users.list_deleted_users().await.unwrap().is_empty());
+        // An upsert takes a deleted user out of the trash, it wasn't created
+        users.delete_user("trash-user", false).await.unwrap();
+        assert!(!users.upsert_user(user.clone()).await.unwrap());
+        assert!(users.exists_user("trash-user").await.unwrap());
+        // Removing for good works from the trash too, and frees the username
+        users.delete_user("trash-user", false).await.unwrap();
+        users.delete_user("trash-user", true).await.unwrap();
+        assert_not_found(users.delete_user("trash-user", true).await);
+        assert_not_found(users.restore_user("trash-user").await);
+        assert!(users.list_deleted_users().await.unwrap().is_empty());
+        users.create_user(user).await.unwrap();
+        users.delete_user("trash-user", true).await.unwrap();
+
+        // A deleted group has no members until restored
+        let group = Group {
+            gid: "trash-group".to_string(),
+            name: "Trash".to_string(),
+            principals: vec!["alice".to_string()],
+            renamed_to: None,
+        };
+        db.groups().create_group(group.clone()).await.unwrap();
+        db.groups().delete_group("trash-group", false).await.unwrap();
+        assert!(db.graph().groups_of("alice").await.unwrap().is_empty());
+        assert!(db.groups().list_groups().await.unwrap().is_empty());
+        assert_conflict(db.groups().create_group(group.clone()).await);
+        assert_eq!(db.groups().list_deleted_groups().await.unwrap()[0].entity.gid, "trash-group");
+        db.groups().restore_group("trash-group").await.unwrap();
+        assert_eq!(db.graph().groups_of("alice").await.unwrap(), vec!["trash-group"]);
+        db.groups().delete_group("trash-group", true).await.unwrap();
+
+        // Traversals don't reach a deleted project, nor what is below it
+        let project = Project {
+            owner: Some("alice".to_string()),
+            ..sample_project(&[])
+        };
+        let below = Project {
+            owner: Some("carol".to_string()),
+            parent_id: Some(project.id.to_string()),
+            ..sample_project(&[])
+        };
+        let (id, below_id) = (project.id.to_string(), below.id.to_string());
+        db.projects().create_project(project.clone()).await.unwrap();
+        db.projects().create_project(below.clone()).await.unwrap();
+        db.projects().delete_project(&id, false).await.unwrap();
+        assert_not_found(db.projects().get_project(&id).await);
+        assert_not_found(db.projects().update_project(&id, project.clone()).await);
+        assert!(!db.projects().exists_project(&id).await.unwrap());
+        assert_eq!(db.projects().count_projects().await.unwrap(), 1);
+        assert_eq!(db.projects().list_projects().await.unwrap().len(), 1);
+        assert!(db.graph().owned_projects("alice").await.unwrap().is_empty());
+        assert_eq!(db.projects().list_deleted_projects().await.unwrap()[0].entity.id, project.id);
+        db.projects().restore_project(&id).await.unwrap();
+        let owned: Vec<_> = db.graph().owned_projects("alice").await.unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(owned, vec![project.id, below.id]);
+        for id in [&id, &below_id] {
+            db.projects().delete_project(id, true).await.unwrap();
+        }
+
+        // Earlier contracts leave tickets behind
+        let tickets = db.tickets();
+        let before = tickets.count_tickets().await.unwrap();
+        tickets.create_ticket(sample_ticket(7, "Kept")).await.unwrap();
+        tickets.create_ticket(sample_ticket(8, "Deleted")).await.unwrap();
+        tickets.delete_ticket("8", false).await.unwrap();
+        assert_not_found(tickets.get_ticket("8").await);
+        assert_not_found(tickets.get_ticket_fields("8", &["title".to_string()]).await);
+        assert_not_found(tickets.update_ticket("8", sample_ticket(8, "Edited")).await);
+        assert_eq!(tickets.list_tickets().await.unwrap().len(), before + 1);
+        assert_eq!(tickets.list_tickets_fields(&["title".to_string()]).await.unwrap().len(), before + 1);
+        assert_eq!(tickets.count_tickets().await.unwrap(), before + 1);
+        assert!(!tickets.exists_ticket("8").await.unwrap());
+        let counted: usize = tickets.ticket_counts(None).await.unwrap().iter().map(|c| c.count).sum();
+        assert_eq!(counted, before + 1);
+        assert_conflict(tickets.create_ticket(sample_ticket(8, "Reused id")).await);
+        assert_eq!(tickets.list_deleted_tickets().await.unwrap()[0].entity.title, "Deleted");
+        tickets.restore_ticket("8").await.unwrap();
+        assert_eq!(tickets.get_ticket("8").await.unwrap().title, "Deleted");
+        for id in ["7", "8"] {
+            tickets.delete_ticket(id, true).await.unwrap();
+        }
+    }
+
+    /// Searches until something is found, ArangoDB indexes for search a moment after writes.
+    async fn search_until_found(db: &dyn DatabaseInterface, query: &str, limit: usize) -> SearchHits {
+        for _ in 0..50 {
+            let hits = db.search().find(query, limit).await.unwrap();
+            if !hits.tickets.is_empty() {
+                return hits;
+            }
+            tokio::time::sleep(StdDuration::from_millis(100)).await;
+        }
+        panic!("nothing found for {}", query);
+    }
+
+    async fn search_contract(db: &dyn DatabaseInterface) {
+        let mut described = sample_ticket(901, "Printer jam");
+        described.description = "Seen in the Zanzibar office".to_string();
+        db.tickets().create_ticket(described).await.unwrap();
+        db.tickets().create_ticket(sample_ticket(902, "Zanzibar printer jam")).await.unwrap();
+        db.tickets().create_ticket(sample_ticket(903, "Zanzibar is offline")).await.unwrap();
+        db.tickets().delete_ticket("903", false).await.unwrap();
+        let mut user = User {
+            username: "zed".to_string(),
+            ..User::default()
+        };
+        user.personal.name = "Zed Zanzibar".to_string();
+        db.users().create_user(user).await.unwrap();
+        db.groups()
+            .create_group(Group {
+                gid: "zanzibar-office".to_string(),
+                name: "Zanzibar".to_string(),
+                principals: vec![],
+                renamed_to: None,
+            })
+            .await
+            .unwrap();
+
+        let hits = search_until_found(db, "zanzibar", 10).await;
+        let ids: Vec<i64> = hits.tickets.iter().map(|h| h.entity.id).collect();
+        assert_eq!(ids, vec![902, 901], "titles rank above descriptions, deleted tickets aren't found");
+        assert!(hits.tickets[0].score > hits.tickets[1].score);
+        assert_eq!(hits.users.iter().map(|h| h.entity.username.as_str()).collect::<Vec<_>>(), vec!["zed"]);
+        assert_eq!(hits.groups.iter().map(|h| h.entity.gid.as_str()).collect::<Vec<_>>(), vec!["zanzibar-office"]);
+        assert!(hits.projects.is_empty());
+
+        assert_eq!(db.search().find("zanzibar", 1).await.unwrap().tickets.len(), 1);
+        let nothing = db.search().find("quetzalcoatl", 10).await.unwrap();
+        assert!(nothing.tickets.is_empty() && nothing.users.is_empty() && nothing.groups.is_empty());
+
+        for id in ["901", "902", "903"] {
+            db.tickets().delete_ticket(id, true).await.unwrap();
+        }
+        db.users().delete_user("zed", true).await.unwrap();
+        db.groups().delete_group("zanzibar-office", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_inmemory_contract() {
+        run_contract(&InMemoryDatabase::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_cached_contract() {
+        run_contract(&CachedDatabase::new(Arc::new(InMemoryDatabase::new()), 100, StdDuration::from_secs(60))).await;
+    }
+
+    mod arango_backend {
+        use arangors::Connection;
+
+        use crate::db::{
+            DatabaseInterface,
+            arangodb::{ArangoDatabase, connect_or_create_db_no_auth},
+        };
+
+        /// Runs the contract against a real ArangoDB in a throwaway database,
+        /// only when `ARANGO_TEST_URL` (e.g. `http://localhost:8529`) is set.
+        #[tokio::test]
+        async fn test_arangodb_contract() {
+            let Ok(url) = std::env::var("ARANGO_TEST_URL") else {
+                return;
+            };
+            let conn = Connection::establish_without_auth(url).await.unwrap();
+            let name = format!("contract_{}", uuid::Uuid::now_v7().simple());
+            let database = ArangoDatabase::new(connect_or_create_db_no_auth(&conn, &name).await.unwrap());
+            database.initialize().await.unwrap();
+
+            super::run_contract(&database).await;
+
+            conn.drop_database(&name).await.unwrap();
+        }
+    }
+}
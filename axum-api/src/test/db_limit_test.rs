@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    use crate::{
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+            limited::{ConcurrencyLimit, LimitedDatabase, PoolStats},
+        },
+        error::AppError,
+        schema::*,
+        test::app::{TestApp, UserFixture},
+    };
+
+    // Every call takes a while, so concurrent calls overlap
+    fn slow_db(limit: &Arc<ConcurrencyLimit>) -> (Arc<ChaosDatabase>, Arc<LimitedDatabase>) {
+        let chaos = Arc::new(ChaosDatabase::with_seed(Arc::new(InMemoryDatabase::new()), ChaosConfig::default(), 1));
+        let limited = Arc::new(LimitedDatabase::new(chaos.clone(), limit.clone()));
+        (chaos, limited)
+    }
+
+    fn slow() -> ChaosConfig {
+        ChaosConfig {
+            latency: Duration::from_millis(200),
+            ..ChaosConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calls_beyond_the_queue_are_refused() {
+        let limit = Arc::new(ConcurrencyLimit::new(1, 1));
+        let (chaos, db) = slow_db(&limit);
+        chaos.set_config(slow());
+
+        let (first, second, third) = tokio::join!(
+            db.users().count_users(),
+            db.users().count_users(),
+            async {
+                // Once the others run and wait
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let stats = limit.stats();
+                (stats.in_flight, stats.queued, db.users().count_users().await)
+            },
+        );
+        assert_eq!((first.unwrap(), second.unwrap()), (0, 0));
+        let (in_flight, queued, third) = third;
+        assert_eq!((in_flight, queued), (1, 1));
+        assert!(matches!(third, Err(AppError::Unavailable(_))));
+
+        assert_eq!(
+            limit.stats(),
+            PoolStats { limit: 1, in_flight: 0, queued: 0, max_queue: 1, rejected: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overload_answers_503() {
+        let limit = Arc::new(ConcurrencyLimit::new(1, 0));
+        let (chaos, db) = slow_db(&limit);
+        let shared = limit.clone();
+        let app = TestApp::builder()
+            .database(db)
+            .user(UserFixture::new("alice"))
+            .state(move |s| s.db_limit = Some(shared))
+            .build()
+            .await;
+
+        chaos.set_config(slow());
+        let (_, response) = tokio::join!(app.state.db.users().count_users(), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            app.get_as("alice", "/api/v1/tickets").await
+        });
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.json::<Value>()["error"]["type"], "unavailable");
+
+        chaos.set_config(ChaosConfig::default());
+        let stats = app.get_mgmt("/api/mgmt/stats").await.json::<ApiResponse<AdminStatsResponse>>().data;
+        let database_limit = stats.database_limit.unwrap();
+        assert_eq!((database_limit.limit, database_limit.in_flight, database_limit.rejected), (1, 0, 1));
+    }
+}
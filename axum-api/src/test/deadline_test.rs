@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use serde_json::Value;
+    use tokio::time::Instant;
+
+    use crate::{
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            deadline::{self, Deadline, DeadlineDatabase, DeadlineGuard},
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        test::app::{TestApp, UserFixture},
+    };
+
+    fn slow(latency: Duration) -> ChaosConfig {
+        ChaosConfig {
+            latency,
+            ..ChaosConfig::default()
+        }
+    }
+
+    fn deadline_db() -> (Arc<ChaosDatabase>, Arc<DeadlineDatabase>) {
+        let chaos = Arc::new(ChaosDatabase::with_seed(Arc::new(InMemoryDatabase::new()), ChaosConfig::default(), 1));
+        let db = Arc::new(DeadlineDatabase::new(chaos.clone(), Arc::new(DeadlineGuard)));
+        (chaos, db)
+    }
+
+    #[tokio::test]
+    async fn test_database_calls_stop_at_the_deadline() {
+        let (chaos, db) = deadline_db();
+        chaos.set_config(slow(Duration::from_millis(100)));
+
+        // Without a deadline calls take as long as they take
+        assert_eq!(db.users().count_users().await.unwrap(), 0);
+        assert!(deadline::current().is_none());
+
+        let started = Instant::now();
+        let result = deadline::scope(Deadline(started + Duration::from_millis(20)), async {
+            assert!(deadline::current().is_some());
+            db.users().count_users().await
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::DeadlineExceeded(_))));
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        let passed = Deadline(Instant::now() - Duration::from_millis(1));
+        let result = deadline::scope(passed, db.users().count_users()).await;
+        assert!(matches!(result, Err(AppError::DeadlineExceeded(message)) if message.contains("before")));
+    }
+
+    // On a paused clock the runtime skips ahead to the next timer once it has nothing
+    // else to do, so neither the login's password hashing nor a busy machine counts
+    // against the deadline
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_requests_answer_504() {
+        let (chaos, db) = deadline_db();
+        let app = TestApp::builder()
+            .database(db)
+            .user(UserFixture::new("alice"))
+            .config(|c| c.request_timeout = Some(1))
+            .build()
+            .await;
+
+        app.get_as("alice", "/api/v1/tickets").await.assert_status_ok();
+
+        chaos.set_config(slow(Duration::from_secs(3)));
+        let started = Instant::now();
+        let response = app.get_as("alice", "/api/v1/tickets").await;
+        response.assert_status(StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(response.json::<Value>()["error"]["type"], "deadline_exceeded");
+        assert_eq!(started.elapsed().as_secs(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        config::AppConfig,
+        models::Draft,
+        schema::*,
+        test::app::{TestApp, UserFixture},
+    };
+
+    const PATH: &str = "/api/v1/me/drafts/new-ticket";
+
+    async fn setup(configure: impl FnOnce(&mut AppConfig) + 'static) -> TestApp {
+        TestApp::builder()
+            .config(configure)
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .build()
+            .await
+    }
+
+    async fn get(app: &TestApp, username: &str) -> Draft {
+        let response = app.get_as(username, PATH).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<Draft>>().data
+    }
+
+    #[tokio::test]
+    async fn test_draft_is_saved_until_the_ticket_is_created() {
+        let app = setup(|c| c.draft_max_bytes = 100).await;
+
+        app.put_as("alice", PATH)
+            .json(&json!({ "title": "Printer" }))
+            .await
+            .assert_status_ok();
+        let payload = json!({ "title": "Printer on fire", "description": "It started" });
+        app.put_as("alice", PATH).json(&payload).await.assert_status_ok();
+        let draft = get(&app, "alice").await;
+        assert_eq!((draft.key.as_str(), draft.payload), ("new-ticket", payload));
+        app.get_as("bob", PATH).await.assert_status(StatusCode::NOT_FOUND);
+
+        app.put_as("alice", PATH)
+            .json(&json!(["Printer on fire"]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.put_as("alice", PATH)
+            .json(&json!({ "description": "x".repeat(100) }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(get(&app, "alice").await.payload["title"], "Printer on fire");
+
+        // Bob's draft of the same key is his own
+        app.put_as("bob", PATH).json(&json!({})).await.assert_status_ok();
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({
+                "title": "Printer on fire",
+                "severity": 2,
+                "severity_label": "major",
+                "draft": "new-ticket",
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        app.get_as("alice", PATH).await.assert_status(StatusCode::NOT_FOUND);
+        get(&app, "bob").await;
+
+        app.delete_as("bob", PATH).await.assert_status(StatusCode::NO_CONTENT);
+        app.get_as("bob", PATH).await.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_expired_draft_is_missing() {
+        let app = setup(|c| c.draft_ttl = 0).await;
+
+        app.put_as("alice", PATH)
+            .json(&json!({ "title": "Printer on fire" }))
+            .await
+            .assert_status_ok();
+        app.get_as("alice", PATH).await.assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn export(app: &TestApp) -> String {
+        let response = app.get_mgmt("/api/mgmt/export").await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "application/x-ndjson");
+        response.text()
+    }
+
+    async fn import(app: &TestApp, dump: &str) -> axum_test::TestResponse {
+        app.post_mgmt("/api/mgmt/import").text(dump.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import() {
+        let mut parent = sample_project(&["support"]);
+        parent.owner = Some("alice".to_string());
+        let mut child = sample_project(&["support"]);
+        child.parent_id = Some(parent.id.to_string());
+        let child_id = child.id.to_string();
+        let source = TestApp::builder()
+            .user(UserFixture::new("alice").email("alice@example.com"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice", "bob"])
+            // The child first, the dump still has its parent before it
+            .project(child)
+            .project(parent)
+            .ticket(sample_ticket(1, "Login page broken"))
+            .build()
+            .await;
+        source
+            .post_as("alice", "/api/v1/tickets/1/comments")
+            .json(&json!({ "body": "Seen on staging too" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let dump = export(&source).await;
+        let types: Vec<String> = dump
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["type"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(types, ["user", "user", "group", "project", "project", "ticket", "comment", "end"]);
+        let lines: Vec<Value> = dump.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[4]["data"]["id"], child_id.as_str());
+        assert_eq!(lines[7]["data"]["records"], 7);
+
+        let target = TestApp::builder().build().await;
+        let response = import(&target, &dump).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<ApiResponse<ImportedRecords>>().data.imported, 7);
+
+        // Password hashes came along, logins work as before
+        target.login("alice", crate::test::app::DEFAULT_PASSWORD).await;
+        assert_eq!(target.state.db.groups().get_group("support").await.unwrap().principals, ["alice", "bob"]);
+        let restored = target.state.db.projects().get_project(&child_id).await.unwrap();
+        assert!(restored.parent_id.is_some());
+        assert_eq!(target.state.db.comments().list_comments(1).await.unwrap().len(), 1);
+        // Same records, listing order within a kind is up to the backend
+        let sorted = |dump: String| {
+            let mut lines: Vec<String> = dump.lines().map(str::to_string).collect();
+            lines.sort();
+            lines
+        };
+        assert_eq!(sorted(export(&target).await), sorted(dump.clone()));
+
+        // Only into an empty database
+        import(&target, &dump).await.assert_status(StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_broken_dumps_are_rejected() {
+        let source = TestApp::builder().user(UserFixture::new("alice")).user(UserFixture::new("bob")).build().await;
+        let dump = export(&source).await;
+
+        let cut_short: String = dump.lines().take(2).map(|line| format!("{}\n", line)).collect();
+        let response = import(&TestApp::builder().build().await, &cut_short).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("Dump is incomplete"));
+
+        let miscounted = dump.replace(r#""records":2"#, r#""records":3"#);
+        import(&TestApp::builder().build().await, &miscounted)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let response = import(&TestApp::builder().build().await, "{\"type\":\"user\"}\nnot json\n").await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("Line 1"));
+
+        source.server.get("/api/mgmt/export").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
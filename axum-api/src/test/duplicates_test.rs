@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::{
+        models::Ticket,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let project = sample_project(&["bob"]);
+        let id = project.id.to_string();
+        let in_project = |n: i64, title: &str| Ticket {
+            project: Some(id.clone()),
+            ..sample_ticket(n, title)
+        };
+        let app = TestApp::builder()
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .group("support", &["bob"])
+            .ticket(in_project(1, "Login fails on Safari"))
+            .ticket(in_project(2, "Login fails on Firefox"))
+            .ticket(in_project(3, "Export to CSV is slow"))
+            .ticket(sample_ticket(4, "Login fails on Safari"))
+            .ticket(Ticket {
+                creation_date: Utc::now() - Duration::days(200),
+                ..in_project(5, "Login fails on Safari")
+            })
+            .project(project)
+            .build()
+            .await;
+        (app, id)
+    }
+
+    async fn check(app: &TestApp, username: &str, title: &str, project: Option<&str>) -> Vec<(i64, f64)> {
+        let response = app
+            .post_as(username, "/api/v1/tickets/check-duplicates")
+            .json(&json!({ "title": title, "project": project }))
+            .await;
+        response.assert_status_ok();
+        response
+            .json::<ApiResponse<Vec<DuplicateCandidate>>>()
+            .data
+            .into_iter()
+            .map(|c| (c.ticket.id, c.score))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_recent_tickets_of_the_project() {
+        let (app, id) = setup().await;
+        assert_eq!(
+            check(&app, "bob", "Safari: login fails", Some(&id)).await,
+            vec![(1, 1.0), (2, 0.5)]
+        );
+        assert!(check(&app, "bob", "Dark mode", Some(&id)).await.is_empty());
+        // Tickets outside projects are compared among themselves
+        assert_eq!(check(&app, "bob", "Login fails on Safari", None).await, vec![(4, 1.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_project() {
+        let (app, id) = setup().await;
+        app.post_as("carol", "/api/v1/tickets/check-duplicates")
+            .json(&json!({ "title": "Login fails", "project": id }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+
+    use crate::{
+        create_app, create_mock_shared_state, notifier::InMemoryNotifier, schema::*,
+        state::AppState,
+    };
+
+    /// State that requires verified emails from "example.com", with notifications kept in memory.
+    fn verifying_state() -> (AppState, Arc<InMemoryNotifier>) {
+        let mut state = create_mock_shared_state().unwrap();
+        let mut config = (*state.config).clone();
+        config.allowed_email_domains = vec!["example.com".to_string()];
+        config.require_email_verification = true;
+        state.config = Arc::new(config);
+
+        let notifier = Arc::new(InMemoryNotifier::new());
+        state.notifier = notifier.clone();
+        (state, notifier)
+    }
+
+    fn register_request(user: &str, email: Option<&str>) -> RegisterRequest {
+        RegisterRequest {
+            user: user.to_string(),
+            password: "securepassword123".to_string(),
+            email: email.map(|e| e.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_domain_allowlist() {
+        let (state, _) = verifying_state();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .post("/api/register")
+            .json(&register_request("noemail", None))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .post("/api/register")
+            .json(&register_request("wrongdomain", Some("user@evil.com")))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .post("/api/register")
+            .json(&register_request("rightdomain", Some("user@example.com")))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_login_requires_verified_email() {
+        let (state, notifier) = verifying_state();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .post("/api/register")
+            .json(&register_request("verifyme", Some("Verify.Me@example.com")))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let login_request = LoginRequest {
+            user: "verifyme".to_string(),
+            password: "securepassword123".to_string(),
+            remember_me: false,
+        };
+        server
+            .post("/api/login")
+            .json(&login_request)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let sent = notifier.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, "verify.me@example.com");
+        let token = sent[0].body.rsplit(' ').next().unwrap().to_string();
+
+        server
+            .post("/api/verify-email")
+            .json(&VerifyEmailRequest {
+                token: "garbage".to_string(),
+            })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/api/verify-email")
+            .json(&VerifyEmailRequest { token })
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        server
+            .post("/api/login")
+            .json(&login_request)
+            .await
+            .assert_status_ok();
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture},
+        utils::encryption::{EncryptionKey, FieldCipher},
+    };
+
+    fn key(id: &str, byte: u8) -> EncryptionKey {
+        EncryptionKey::parse(&format!("{}:{}", id, format!("{:02x}", byte).repeat(32))).unwrap()
+    }
+
+    async fn setup(keys: Vec<EncryptionKey>) -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .config(move |c| {
+                c.encryption_keys = keys;
+                c.encrypted_metadata_keys = vec!["ssn".to_string()];
+            })
+            .build()
+            .await
+    }
+
+    async fn stored(app: &TestApp, username: &str, key: &str) -> String {
+        app.state.db.users().get_user(username).await.unwrap().metadata[key].clone()
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_encrypted_at_rest() {
+        let app = setup(vec![key("v1", 1)]).await;
+
+        let response = app
+            .put_mgmt("/api/mgmt/users/alice/metadata/ssn")
+            .json(&json!({ "value": "078-05-1120" }))
+            .await
+            .json::<ApiResponse<UserMetadataResponse>>()
+            .data;
+        assert_eq!(response.metadata["ssn"], "078-05-1120");
+        app.put_mgmt("/api/mgmt/users/alice/metadata/beta")
+            .json(&json!({ "value": "yes" }))
+            .await
+            .assert_status_ok();
+
+        assert!(stored(&app, "alice", "ssn").await.starts_with("enc:v1:"));
+        assert_eq!(stored(&app, "alice", "beta").await, "yes");
+
+        let mine = app
+            .get_as("alice", "/api/v1/me/metadata")
+            .await
+            .json::<ApiResponse<UserMetadataResponse>>()
+            .data;
+        assert_eq!(mine.metadata["ssn"], "078-05-1120");
+
+        // Found by the decrypted value
+        let found = app
+            .get_mgmt("/api/mgmt/users?key=ssn&value=078-05-1120")
+            .await
+            .json::<ApiResponse<Vec<UserMetadataResponse>>>()
+            .data;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].metadata["ssn"], "078-05-1120");
+        let found = app
+            .get_mgmt("/api/mgmt/users?key=ssn&value=nope")
+            .await
+            .json::<ApiResponse<Vec<UserMetadataResponse>>>()
+            .data;
+        assert!(found.is_empty());
+
+        // Values looking sealed are still values
+        for key in ["ssn", "beta"] {
+            app.put_mgmt(&format!("/api/mgmt/users/bob/metadata/{}", key))
+                .json(&json!({ "value": "enc:v1:00" }))
+                .await
+                .assert_status_ok();
+        }
+        let metadata = app.state.controller.user.metadata("bob").await.unwrap();
+        assert_eq!((metadata["ssn"].as_str(), metadata["beta"].as_str()), ("enc:v1:00", "enc:v1:00"));
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_after_key_rotation() {
+        let app = setup(vec![key("v2", 2), key("v1", 1)]).await;
+        let old = FieldCipher::new(&[key("v1", 1)]).unwrap();
+        let mut alice = app.state.db.users().get_user("alice").await.unwrap();
+        alice.metadata.insert("ssn".to_string(), old.encrypt("metadata.ssn", "078-05-1120").unwrap());
+        app.state.db.users().update_user("alice", alice).await.unwrap();
+        // Stored before the key was encrypted
+        let mut bob = app.state.db.users().get_user("bob").await.unwrap();
+        bob.metadata.insert("ssn".to_string(), "219-09-9999".to_string());
+        app.state.db.users().update_user("bob", bob).await.unwrap();
+
+        let metadata = app.state.controller.user.metadata("alice").await.unwrap();
+        assert_eq!(metadata["ssn"], "078-05-1120");
+
+        let response = app.post_mgmt("/api/mgmt/users/reencrypt-metadata").await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<ApiResponse<ReencryptedValues>>().data.reencrypted, 2);
+        assert!(stored(&app, "alice", "ssn").await.starts_with("enc:v2:"));
+        assert!(stored(&app, "bob", "ssn").await.starts_with("enc:v2:"));
+        assert_eq!(app.state.controller.user.metadata("bob").await.unwrap()["ssn"], "219-09-9999");
+
+        // Nothing left to do
+        let response = app.post_mgmt("/api/mgmt/users/reencrypt-metadata").await;
+        assert_eq!(response.json::<ApiResponse<ReencryptedValues>>().data.reencrypted, 0);
+
+        let plain = TestApp::builder().build().await;
+        plain
+            .post_mgmt("/api/mgmt/users/reencrypt-metadata")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::{
+        db::NotificationFilter,
+        models::{
+            AccessControlList, EscalationPolicy, NotificationKind, Permissions, Severity, Ticket,
+            TicketStatus,
+        },
+        scheduler::escalate_stale_tickets,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn policies() -> Vec<EscalationPolicy> {
+        vec![
+            EscalationPolicy {
+                name: "urgent".to_string(),
+                severity: 2,
+                idle_hours: 4,
+                group: "oncall".to_string(),
+                reassign: true,
+            },
+            EscalationPolicy {
+                name: "forgotten".to_string(),
+                severity: 4,
+                idle_hours: 48,
+                group: "oncall".to_string(),
+                reassign: false,
+            },
+        ]
+    }
+
+    /// Alice can change the project, bob can only read it. Carol and dave are on call.
+    async fn setup(tickets: Vec<Ticket>) -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let mut builder = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .user(UserFixture::new("dave"))
+            .group("oncall", &["carol", "dave"])
+            .project(project);
+        for ticket in tickets {
+            builder = builder.ticket(Ticket {
+                project: ticket.project.map(|_| id.clone()),
+                ..ticket
+            });
+        }
+        (builder.build().await, id)
+    }
+
+    /// A ticket of the project nothing happened to for `idle_hours`.
+    fn idle(id: i64, level: u8, idle_hours: i64) -> Ticket {
+        Ticket {
+            project: Some(String::new()),
+            severity: Severity::new(level, "level"),
+            assigned_to: "alice".to_string(),
+            last_modification: Utc::now() - Duration::hours(idle_hours),
+            ..sample_ticket(id, "Stale")
+        }
+    }
+
+    async fn escalations(app: &TestApp, user: &str) -> Vec<i64> {
+        app.state
+            .db
+            .notifications()
+            .list_notifications(user, &NotificationFilter::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|n| n.kind == NotificationKind::Escalation)
+            .map(|n| n.payload["ticket"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_manage_policies() {
+        let (app, id) = setup(vec![]).await;
+        let path = format!("/api/v1/projects/{}/escalation-policies", id);
+        app.put_as("alice", &path).json(&policies()).await.assert_status_ok();
+        let stored = app
+            .get_as("bob", &path)
+            .await
+            .json::<ApiResponse<Vec<EscalationPolicy>>>()
+            .data;
+        assert_eq!(stored, policies());
+
+        app.put_as("bob", &path)
+            .json(&policies())
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        for invalid in [
+            json!([{"name": "a", "severity": 9, "idle_hours": 4, "group": "oncall"}]),
+            json!([{"name": "a", "severity": 1, "idle_hours": 0, "group": "oncall"}]),
+            json!([{"name": "a", "severity": 1, "idle_hours": 4, "group": "nobody"}]),
+            json!([
+                {"name": "a", "severity": 1, "idle_hours": 4, "group": "oncall"},
+                {"name": "a", "severity": 2, "idle_hours": 4, "group": "oncall"},
+            ]),
+        ] {
+            app.put_as("alice", &path)
+                .json(&invalid)
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_escalates_stale_tickets() {
+        let resolved = Ticket {
+            status: TicketStatus::Resolved,
+            ..idle(4, 1, 5)
+        };
+        let outside = Ticket {
+            project: None,
+            ..idle(6, 1, 5)
+        };
+        let (app, id) = setup(vec![
+            idle(1, 1, 5),
+            idle(2, 3, 5),
+            idle(3, 3, 50),
+            resolved,
+            idle(5, 1, 1),
+            outside,
+        ])
+        .await;
+        app.put_as("alice", &format!("/api/v1/projects/{}/escalation-policies", id))
+            .json(&policies())
+            .await
+            .assert_status_ok();
+
+        assert_eq!(escalate_stale_tickets(&app.state, Utc::now()).await.unwrap(), 2);
+        let ticket = |n: i64| {
+            let app = &app;
+            async move { app.state.db.tickets().get_ticket(&n.to_string()).await.unwrap() }
+        };
+        let urgent = ticket(1).await;
+        assert_eq!(urgent.assigned_to, "oncall");
+        assert!(urgent.escalated_at.is_some());
+        // Notified only
+        assert_eq!(ticket(3).await.assigned_to, "alice");
+        for untouched in [2, 4, 5, 6] {
+            assert!(ticket(untouched).await.escalated_at.is_none());
+        }
+        assert_eq!(escalations(&app, "carol").await.len(), 2);
+        assert_eq!(escalations(&app, "dave").await.len(), 2);
+
+        // Once per period of inactivity, ticket 5 only went stale since
+        let later = Utc::now() + Duration::hours(5);
+        assert_eq!(escalate_stale_tickets(&app.state, later).await.unwrap(), 1);
+        assert!(ticket(5).await.escalated_at.is_some());
+
+        // Activity starts a new one
+        app.post_as("alice", "/api/v1/tickets/1/move")
+            .json(&json!({"status": "in_progress", "position": 0}))
+            .await
+            .assert_status_ok();
+        assert_eq!(escalate_stale_tickets(&app.state, Utc::now()).await.unwrap(), 0);
+        assert_eq!(escalate_stale_tickets(&app.state, later).await.unwrap(), 1);
+        let mut notified = escalations(&app, "carol").await;
+        notified.sort();
+        assert_eq!(notified, vec![1, 1, 3, 5]);
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        db::NotificationFilter,
+        error::AppError,
+        events::{DomainEvent, Subscriber},
+        models::TicketEventKind,
+        schema::RegisterRequest,
+        test::app::{TestApp, UserFixture},
+        utils::BoxFuture,
+    };
+
+    /// Notes what it was told about, in order.
+    #[derive(Default)]
+    struct Recorder {
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl Subscriber for Recorder {
+        fn handle<'a>(&'a self, event: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+            let seen = match event {
+                DomainEvent::UserRegistered { username, .. } => format!("registered {}", username),
+                DomainEvent::TicketUpdated(event) => format!("{:?} #{} by {}", event.kind, event.ticket.id, event.actor),
+                DomainEvent::CommentAdded { comment, .. } => format!("comment on #{} by {}", comment.ticket, comment.author),
+            };
+            self.seen.lock().unwrap().push(seen);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_see_events_before_the_response() {
+        let app = TestApp::builder().user(UserFixture::new("alice")).build().await;
+        let recorder = Arc::new(Recorder::default());
+        app.state.events.subscribe(recorder.clone());
+        let mut stream = app.state.events.stream();
+
+        app.server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: "bob".to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({ "title": "Refunds are late", "severity": 3, "assigned_to": "bob" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        app.post_as("alice", "/api/v1/tickets/1/comments")
+            .json(&json!({ "body": "Seen on staging too" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        // A failed request publishes nothing
+        app.post_as("alice", "/api/v1/tickets/404/comments")
+            .json(&json!({ "body": "Lost" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        assert_eq!(
+            *recorder.seen.lock().unwrap(),
+            vec!["registered bob", "Created #1 by alice", "comment on #1 by alice"]
+        );
+        // The notification subscriber ran as well
+        let unread = NotificationFilter {
+            unread: Some(true),
+            ..NotificationFilter::default()
+        };
+        assert_eq!(app.state.db.notifications().count_notifications("bob", &unread).await.unwrap(), 1);
+
+        assert!(matches!(stream.recv().await.unwrap(), DomainEvent::UserRegistered { .. }));
+        let DomainEvent::TicketUpdated(event) = stream.recv().await.unwrap() else {
+            panic!("Expected a ticket event");
+        };
+        assert_eq!(event.kind, TicketEventKind::Created);
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(all(test, feature = "graphql"))]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use axum_test::TestServer;
+
+    use crate::{create_app, create_mock_shared_state, schema::*};
+
+    async fn login(server: &TestServer, user: &str) -> String {
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: user.to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: user.to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data
+            .token
+    }
+
+    #[tokio::test]
+    async fn test_graphql_shares_controllers_with_rest() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let token = login(&server, "graphqluser").await;
+
+        let response = server
+            .post("/api/graphql")
+            .authorization_bearer(&token)
+            .json(&json!({
+                "query": "mutation { createTicket(input: {title: \"Broken build\", severity: 2, severityLabel: \"major\", assignedTo: \"ci\"}) { id createdBy } }"
+            }))
+            .await
+            .json::<Value>();
+        assert_eq!(response["data"]["createTicket"]["id"], 1);
+        assert_eq!(response["data"]["createTicket"]["createdBy"], "graphqluser");
+
+        // Visible through REST as well
+        let ticket = server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(ticket.title, "Broken build");
+
+        let response = server
+            .post("/api/graphql")
+            .authorization_bearer(&token)
+            .json(&json!({
+                "query": "{ me { username twoFactorEnabled } tickets { title severityLabel } sessions { current } }"
+            }))
+            .await
+            .json::<Value>();
+        assert_eq!(response["data"]["me"]["username"], "graphqluser");
+        assert_eq!(response["data"]["tickets"][0]["severityLabel"], "major");
+        assert_eq!(response["data"]["sessions"][0]["current"], true);
+
+        let response = server
+            .post("/api/graphql")
+            .authorization_bearer(&token)
+            .json(&json!({ "query": "{ ticket(id: \"404\") { title } }" }))
+            .await
+            .json::<Value>();
+        assert!(response["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_graphql_requires_auth() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .post("/api/graphql")
+            .json(&json!({ "query": "{ me { username } }" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
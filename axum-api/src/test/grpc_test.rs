@@ -0,0 +1,140 @@
+#[cfg(all(test, feature = "grpc"))]
+mod tests {
+    use std::sync::Arc;
+
+    use axum_test::TestServer;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{Code, Request, metadata::MetadataValue, transport::Channel};
+
+    use crate::{
+        create_app, create_mock_shared_state,
+        grpc::{
+            proto::{
+                self, ticket_event::Kind, ticket_service_client::TicketServiceClient,
+                user_service_client::UserServiceClient,
+            },
+            services,
+        },
+        schema::*,
+    };
+
+    /// Starts the HTTP API and the gRPC services over one state, returns an access token.
+    async fn setup() -> (TestServer, Channel, String) {
+        let state = Arc::new(create_mock_shared_state().unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(services(state.clone()).serve_with_incoming(TcpListenerStream::new(listener)));
+        let channel = Channel::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+
+        let server = TestServer::new(create_app(state)).expect("Failed to create TestServer");
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: "grpcuser".to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await;
+        let token = server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: "grpcuser".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data
+            .token;
+        (server, channel, token)
+    }
+
+    fn authorized<T>(message: T, token: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        let value: MetadataValue<_> = format!("Bearer {}", token).parse().unwrap();
+        request.metadata_mut().insert("authorization", value);
+        request
+    }
+
+    #[tokio::test]
+    async fn test_ticket_service_streams_events() {
+        let (server, channel, token) = setup().await;
+        let mut tickets = TicketServiceClient::new(channel);
+
+        let mut feed = tickets
+            .watch_tickets(authorized(proto::WatchTicketsRequest {}, &token))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let created = tickets
+            .create_ticket(authorized(
+                proto::CreateTicketRequest {
+                    title: "Queue stalls".to_string(),
+                    severity: 2,
+                    severity_label: "major".to_string(),
+                    assigned_to: "platform".to_string(),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(created.created_by, "grpcuser");
+
+        let event = feed.message().await.unwrap().unwrap();
+        assert_eq!(event.kind(), Kind::Created);
+        assert_eq!(event.ticket.unwrap().id, created.id);
+
+        // Same data through the HTTP API
+        let ticket = server
+            .get(&format!("/api/v1/tickets/{}", created.id))
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(ticket.title, "Queue stalls");
+
+        let missing = tickets
+            .get_ticket(authorized(proto::GetTicketRequest { id: 404 }, &token))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_user_service_requires_valid_token() {
+        let (_server, channel, token) = setup().await;
+        let mut users = UserServiceClient::new(channel);
+
+        let status = users
+            .get_me(Request::new(proto::GetMeRequest {}))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let me = users
+            .get_me(authorized(proto::GetMeRequest {}, &token))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(me.username, "grpcuser");
+
+        users
+            .logout_all(authorized(proto::LogoutAllRequest {}, &token))
+            .await
+            .unwrap();
+        let status = users
+            .get_me(authorized(proto::GetMeRequest {}, &token))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use crate::{
+        models::SecurityEvent,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    #[tokio::test]
+    async fn test_fixtures_are_seeded() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob").email("bob@example.com"))
+            .group("devs", &["alice", "bob"])
+            .group("support", &["bob"])
+            .ticket(sample_ticket(7, "Flaky test"))
+            .build()
+            .await;
+
+        let ticket = app
+            .get_as("bob", "/api/v1/tickets/7")
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(ticket.title, "Flaky test");
+
+        let bob = app.state.controller.user.get_user("bob").await.unwrap();
+        assert!(bob.verified);
+        let principals = app.state.controller.group.principals_of("alice").await.unwrap();
+        assert!(principals.contains(&"devs".to_string()));
+
+        // Fixture logins went through the API
+        let events = app
+            .get_mgmt("/api/mgmt/security-events")
+            .add_query_param("kind", "login_failure")
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data;
+        assert_eq!(events.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_config_override() {
+        let app = TestApp::builder()
+            .config(|config| config.management_token = "custom-mgmt-token".to_string())
+            .build()
+            .await;
+
+        assert_eq!(app.mgmt_token(), "custom-mgmt-token");
+        app.get_mgmt("/api/mgmt/security-events")
+            .await
+            .assert_status_ok();
+        app.server
+            .get("/api/v1/tickets")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_echo() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("wsuser"))
+            .websockets()
+            .build()
+            .await;
+
+        let mut socket = app.ws_as("wsuser", "/api/v1/ws").await;
+        socket.send_text("hello").await;
+        socket.assert_receive_text("wsuser said: hello").await;
+        socket.close().await;
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Permissions, Project, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    struct Tree {
+        root: String,
+        team: String,
+        sub: String,
+        other: String,
+    }
+
+    fn below(parent: &Project, readers: &[&str]) -> Project {
+        Project {
+            parent_id: Some(parent.id.to_string()),
+            ..sample_project(readers)
+        }
+    }
+
+    fn project_ticket(id: i64, project: &Project, status: TicketStatus) -> Ticket {
+        Ticket {
+            project: Some(project.id.to_string()),
+            status,
+            ..sample_ticket(id, "Project ticket")
+        }
+    }
+
+    /// root (owned by alice, bob reads) > team (carol has ROOT) > sub, and other,
+    /// owned by alice too.
+    async fn setup(deny_team_to_bob: bool) -> (TestApp, Tree) {
+        let mut root = sample_project(&["bob"]);
+        root.owner = Some("alice".to_string());
+        let mut team = below(&root, &[]);
+        team.acl.list.push(AccessControlList {
+            permissions: Permissions::ROOT,
+            principals: vec!["carol".to_string()],
+        });
+        if deny_team_to_bob {
+            team.acl.deny.push(AccessControlList {
+                permissions: Permissions::READ,
+                principals: vec!["bob".to_string()],
+            });
+        }
+        let sub = below(&team, &[]);
+        let mut other = sample_project(&[]);
+        other.owner = Some("alice".to_string());
+
+        let tree = Tree {
+            root: root.id.to_string(),
+            team: team.id.to_string(),
+            sub: sub.id.to_string(),
+            other: other.id.to_string(),
+        };
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .user(UserFixture::new("dave"))
+            .ticket(project_ticket(1, &root, TicketStatus::Open))
+            .ticket(project_ticket(2, &team, TicketStatus::Open))
+            .ticket(project_ticket(3, &team, TicketStatus::Resolved))
+            .ticket(project_ticket(4, &sub, TicketStatus::InProgress))
+            .project(root)
+            .project(team)
+            .project(sub)
+            .project(other)
+            .build()
+            .await;
+        (app, tree)
+    }
+
+    #[tokio::test]
+    async fn test_subprojects_inherit_acl() {
+        let (app, tree) = setup(false).await;
+        let severities = |id: &str| format!("/api/v1/projects/{}/severities", id);
+
+        // bob reads the root, so everything below it
+        app.get_as("bob", &severities(&tree.sub)).await.assert_status_ok();
+        app.get_as("dave", &severities(&tree.sub)).await.assert_status(StatusCode::NOT_FOUND);
+        // The root's owner owns the sub-projects too, carol only below the team
+        app.get_as("alice", &format!("/api/v1/projects/{}/acl", tree.sub))
+            .await
+            .assert_status_ok();
+        app.get_as("carol", &format!("/api/v1/projects/{}/acl", tree.sub))
+            .await
+            .assert_status_ok();
+        app.get_as("carol", &severities(&tree.root)).await.assert_status(StatusCode::NOT_FOUND);
+
+        // Granting on the root reaches the sub-projects at once
+        app.post_as("alice", &format!("/api/v1/projects/{}/acl/grant", tree.root))
+            .json(&json!({"principal": "dave", "permissions": "READ"}))
+            .await
+            .assert_status_ok();
+        app.get_as("dave", &severities(&tree.sub)).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_project_tree() {
+        let (app, tree) = setup(false).await;
+
+        let root = app
+            .get_as("bob", &format!("/api/v1/projects/{}/tree", tree.root))
+            .await
+            .json::<ApiResponse<ProjectTreeResponse>>()
+            .data;
+        assert!(root.ancestors.is_empty());
+        let entries: Vec<_> = root.subprojects.iter().map(|e| (e.project.as_str(), e.parent.as_str(), e.depth)).collect();
+        assert_eq!(entries, vec![(tree.team.as_str(), tree.root.as_str(), 1), (tree.sub.as_str(), tree.team.as_str(), 2)]);
+
+        let sub = app
+            .get_as("bob", &format!("/api/v1/projects/{}/tree", tree.sub))
+            .await
+            .json::<ApiResponse<ProjectTreeResponse>>()
+            .data;
+        assert_eq!(sub.ancestors, vec![tree.team.clone(), tree.root.clone()]);
+        assert!(sub.subprojects.is_empty());
+
+        // carol sees the team and below, not the root above it
+        let team = app
+            .get_as("carol", &format!("/api/v1/projects/{}/tree", tree.team))
+            .await
+            .json::<ApiResponse<ProjectTreeResponse>>()
+            .data;
+        assert!(team.ancestors.is_empty());
+        assert_eq!(team.subprojects.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_denies_hide_subprojects() {
+        let (app, tree) = setup(true).await;
+
+        let root = app
+            .get_as("bob", &format!("/api/v1/projects/{}/tree", tree.root))
+            .await
+            .json::<ApiResponse<ProjectTreeResponse>>()
+            .data;
+        assert!(root.subprojects.is_empty());
+        app.get_as("bob", &format!("/api/v1/projects/{}/severities", tree.sub))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_set_parent() {
+        let (app, tree) = setup(false).await;
+        let parent = |id: &str| format!("/api/v1/projects/{}/parent", id);
+
+        // Not below itself or its own sub-projects
+        app.put_as("alice", &parent(&tree.team))
+            .json(&json!({"parent_id": tree.sub}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.put_as("alice", &parent(&tree.team))
+            .json(&json!({"parent_id": tree.team}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        // carol's ROOT comes from the team, taking the sub out of it would lose it
+        app.put_as("carol", &parent(&tree.sub))
+            .json(&json!({"parent_id": null}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        // Nor can she put it below a project she can't modify
+        app.put_as("carol", &parent(&tree.sub))
+            .json(&json!({"parent_id": tree.other}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.put_as("bob", &parent(&tree.sub))
+            .json(&json!({"parent_id": null}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let moved = app
+            .put_as("alice", &parent(&tree.sub))
+            .json(&json!({"parent_id": tree.other}))
+            .await
+            .json::<ApiResponse<ProjectTreeResponse>>()
+            .data;
+        assert_eq!(moved.ancestors, vec![tree.other.clone()]);
+        assert_eq!(
+            app.state.db.projects().get_project(&tree.sub).await.unwrap().parent_id,
+            Some(tree.other.clone())
+        );
+        // It no longer inherits from the root bob reads
+        app.get_as("bob", &format!("/api/v1/projects/{}/severities", tree.sub))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_stats_roll_up() {
+        let (app, tree) = setup(false).await;
+
+        let stats = app
+            .get_as("bob", &format!("/api/v1/projects/{}/stats", tree.root))
+            .await
+            .json::<ApiResponse<ProjectStatsResponse>>()
+            .data;
+        assert_eq!(stats.total, 1);
+        assert_eq!(stats.rollup_total, 4);
+        let totals: Vec<_> = stats.subprojects.iter().map(|s| (s.project.as_str(), s.total)).collect();
+        assert_eq!(totals, vec![(tree.team.as_str(), 2), (tree.sub.as_str(), 1)]);
+        let by_status: Vec<_> = stats.subprojects[0].by_status.iter().map(|c| (c.status, c.count)).collect();
+        assert_eq!(by_status, vec![(TicketStatus::Open, 1), (TicketStatus::Resolved, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_stats_leave_out_hidden_subprojects() {
+        let (app, tree) = setup(true).await;
+
+        let stats = app
+            .get_as("bob", &format!("/api/v1/projects/{}/stats", tree.root))
+            .await
+            .json::<ApiResponse<ProjectStatsResponse>>()
+            .data;
+        assert!(stats.subprojects.is_empty());
+        assert_eq!(stats.rollup_total, 1);
+        // The same cached numbers, all of them for the owner
+        let stats = app
+            .get_as("alice", &format!("/api/v1/projects/{}/stats", tree.root))
+            .await
+            .json::<ApiResponse<ProjectStatsResponse>>()
+            .data;
+        assert_eq!(stats.rollup_total, 4);
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::test::app::{TestApp, UserFixture};
+
+    async fn setup() -> TestApp {
+        TestApp::builder().user(UserFixture::new("alice")).build().await
+    }
+
+    #[tokio::test]
+    async fn test_errors_in_the_clients_language() {
+        let app = setup().await;
+        let response = app
+            .get_as("alice", "/api/v1/tickets/999")
+            .add_header("Accept-Language", "uk-UA,uk;q=0.9,en;q=0.8")
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(response.header("content-language"), "uk");
+        assert_eq!(
+            response.json::<Value>()["error"],
+            json!({
+                "type": "not_found",
+                "code": "not_found.ticket",
+                "message": "Не знайдено: Тікет 999 не знайдено",
+                "status": 404
+            })
+        );
+
+        // Messages without a translation keep their English detail
+        let response = app
+            .put_as("alice", "/api/v1/me/preferences/Board")
+            .add_header("Accept-Language", "uk")
+            .json(&json!({ "value": {} }))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let error = response.json::<Value>()["error"].clone();
+        assert_eq!(error["code"], "validation_error");
+        assert!(error["message"].as_str().unwrap().starts_with("Помилка перевірки: Preference namespaces"));
+    }
+
+    #[tokio::test]
+    async fn test_english_by_default() {
+        let app = setup().await;
+        for language in [None, Some("fr-CH, fr;q=0.9"), Some("uk;q=0")] {
+            let mut request = app.server.get("/api/v1/tickets");
+            if let Some(language) = language {
+                request = request.add_header("Accept-Language", language);
+            }
+            let response = request.await;
+            response.assert_status(StatusCode::UNAUTHORIZED);
+            assert_eq!(response.header("content-language"), "en");
+            let error = response.json::<Value>()["error"].clone();
+            assert_eq!((error["code"].as_str(), error["message"].as_str()), (Some("auth.unauthorized"), Some("Authorization failed: Unauthorized")));
+        }
+    }
+}
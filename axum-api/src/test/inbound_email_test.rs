@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::Utc;
+
+    use crate::{
+        models::{Comment, Permissions, Ticket},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+        utils::{hmac_sha256_hex, random_token},
+    };
+
+    const KEY: &str = "mailgun-signing-key";
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&[]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        let id = project.id.to_string();
+        let configured = id.clone();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice").email("alice@example.com"))
+            .user(UserFixture::new("bob").email("bob@example.com"))
+            .project(project)
+            .config(move |c| {
+                c.inbound_email_project = Some(configured);
+                c.inbound_email_signing_key = KEY.to_string();
+            })
+            .build()
+            .await;
+        (app, id)
+    }
+
+    /// A signed Mailgun form with the given fields, under a fresh signature.
+    fn form(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        let timestamp = Utc::now().timestamp().to_string();
+        let token = random_token(16);
+        let signature = hmac_sha256_hex(KEY.as_bytes(), format!("{}{}", timestamp, token).as_bytes());
+        let mut form: Vec<(String, String)> = fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        form.push(("timestamp".to_string(), timestamp));
+        form.push(("token".to_string(), token.to_string()));
+        form.push(("signature".to_string(), signature));
+        form
+    }
+
+    async fn post(app: &TestApp, form: &[(String, String)]) -> InboundEmailResponse {
+        let response = app.server.post("/api/inbound/email").form(&form).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<InboundEmailResponse>>().data
+    }
+
+    fn new_email() -> Vec<(String, String)> {
+        form(&[
+            ("sender", "Dave@Customer.example"),
+            ("subject", "Export is broken"),
+            ("body-plain", "The CSV export times out."),
+            ("Message-Id", "<first@customer.example>"),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_email_files_a_ticket() {
+        let (app, id) = setup().await;
+        let filed = post(&app, &new_email()).await;
+        assert!(filed.filed);
+        assert_eq!(filed.comment, None);
+
+        let ticket: Ticket = app.state.db.tickets().get_ticket(&filed.ticket.to_string()).await.unwrap();
+        assert_eq!(ticket.title, "Export is broken");
+        assert_eq!(ticket.description, "The CSV export times out.");
+        assert_eq!(ticket.created_by, "Dave@Customer.example");
+        assert_eq!(ticket.project, Some(id));
+        assert_eq!(ticket.severity.label, "trivial");
+        assert_eq!(ticket.message_id.as_deref(), Some("first@customer.example"));
+    }
+
+    #[tokio::test]
+    async fn test_reply_is_threaded_as_comment() {
+        let (app, _) = setup().await;
+        let filed = post(&app, &new_email()).await;
+
+        let reply = post(
+            &app,
+            &form(&[
+                ("sender", "ALICE@example.com"),
+                ("subject", "Re: Export is broken"),
+                ("body-plain", "Looking into it.\n\n> The CSV export times out."),
+                ("stripped-text", "Looking into it."),
+                ("Message-Id", "<second@example.com>"),
+                ("In-Reply-To", "<first@customer.example>"),
+                ("X-Mailgun-Dkim-Check-Result", "Pass"),
+            ]),
+        )
+        .await;
+        assert_eq!(reply.ticket, filed.ticket);
+        assert!(reply.filed);
+
+        // Replies to the reply are found through the comment
+        let again = post(
+            &app,
+            &form(&[
+                ("sender", "dave@customer.example"),
+                ("subject", "Re: Export is broken"),
+                ("body-plain", "Thanks!"),
+                ("Message-Id", "<third@customer.example>"),
+                ("References", "<unknown@elsewhere.example> <second@example.com>"),
+            ]),
+        )
+        .await;
+        assert_eq!(again.ticket, filed.ticket);
+
+        let response = app.get_as("alice", &format!("/api/v1/tickets/{}/comments", filed.ticket)).await;
+        response.assert_status_ok();
+        let comments = response.json::<ApiResponse<Vec<Comment>>>().data;
+        let summary: Vec<(&str, &str)> = comments.iter().map(|c| (c.author.as_str(), c.body.as_str())).collect();
+        // The sender is matched to the user with that address
+        assert_eq!(
+            summary,
+            vec![("alice", "Looking into it."), ("dave@customer.example", "Thanks!")]
+        );
+        assert_eq!(comments[0].id, reply.comment.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_email_is_not_filed_twice() {
+        let (app, _) = setup().await;
+        let filed = post(&app, &new_email()).await;
+        let redelivered = post(&app, &new_email()).await;
+        assert_eq!(redelivered.ticket, filed.ticket);
+        assert!(!redelivered.filed);
+        assert_eq!(app.state.db.tickets().list_tickets().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_sender_is_not_matched_to_user() {
+        let (app, _) = setup().await;
+        let filed = post(
+            &app,
+            &form(&[
+                ("sender", "alice@example.com"),
+                ("subject", "Please grant access"),
+                ("body-plain", "Hi"),
+                ("X-Mailgun-Spf", "Fail"),
+                ("X-Mailgun-Dkim-Check-Result", "Fail"),
+            ]),
+        )
+        .await;
+        let ticket: Ticket = app.state.db.tickets().get_ticket(&filed.ticket.to_string()).await.unwrap();
+        assert_eq!(ticket.created_by, "alice@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_sender_needs_create_permission() {
+        let (app, _) = setup().await;
+        let filed = post(&app, &new_email()).await;
+
+        // A known user is held to their permissions, not treated as an outsider
+        let response = app
+            .server
+            .post("/api/inbound/email")
+            .form(&form(&[
+                ("sender", "bob@example.com"),
+                ("subject", "Re: Export is broken"),
+                ("body-plain", "Closing this."),
+                ("In-Reply-To", "<first@customer.example>"),
+                ("X-Mailgun-Spf", "pass"),
+            ]))
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let comments = app.state.db.comments().list_comments(filed.ticket).await.unwrap();
+        assert!(comments.is_empty());
+        assert_eq!(app.state.db.tickets().list_tickets().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_webhook_is_rejected() {
+        let (app, _) = setup().await;
+        let email = new_email();
+        post(&app, &email).await;
+        app.server
+            .post("/api/inbound/email")
+            .form(&email)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_comments_api() {
+        let (app, _) = setup().await;
+        let filed = post(&app, &new_email()).await;
+        let path = format!("/api/v1/tickets/{}/comments", filed.ticket);
+
+        let response = app.post_as("alice", &path).json(&CreateCommentRequest { body: " On it ".to_string() }).await;
+        response.assert_status(StatusCode::CREATED);
+        let comment = response.json::<ApiResponse<Comment>>().data;
+        assert_eq!((comment.author.as_str(), comment.body.as_str()), ("alice", "On it"));
+
+        app.post_as("alice", &path)
+            .json(&CreateCommentRequest { body: "  ".to_string() })
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.get_as("alice", "/api/v1/tickets/99/comments")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_bad_signature() {
+        let (app, _) = setup().await;
+        let mut forged = new_email();
+        forged.last_mut().unwrap().1 = "0".repeat(64);
+        app.server
+            .post("/api/inbound/email")
+            .form(&forged)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        assert!(app.state.db.tickets().list_tickets().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_without_project() {
+        let app = TestApp::builder().build().await;
+        app.server
+            .post("/api/inbound/email")
+            .form(&new_email())
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use chrono::{Duration as ChronoDuration, Utc};
+    use serde_json::Value;
+
+    use crate::{
+        db::{
+            DatabaseInterface, SecurityEventFilter,
+            inmemory::{InMemoryDatabase, InMemoryLimits},
+        },
+        error::AppError,
+        models::{Draft, IdempotencyRecord, SecurityEvent, SecurityEventKind, Session, TicketShare},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    #[tokio::test]
+    async fn test_capacity_limit_rejects_new_entities() {
+        let db = Arc::new(InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: Some(2),
+            ttl: None,
+        }));
+        let app = TestApp::builder()
+            .database(db.clone())
+            .user(UserFixture::new("firstuser"))
+            .user(UserFixture::new("seconduser"))
+            .build()
+            .await;
+
+        let response = app
+            .server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: "thirduser".to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await;
+        response.assert_status(StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(response.json::<Value>()["error"]["type"], "storage_full");
+
+        // Existing entities can still be changed
+        let mut user = db.users().get_user("firstuser").await.unwrap();
+        user.verified = true;
+        db.users().update_user("firstuser", user).await.unwrap();
+
+        // Freeing a slot makes room again
+        db.users().delete_user("seconduser", true).await.unwrap();
+        app.server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: "thirduser".to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_evicts_stale_entities() {
+        let db = InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: Some(1),
+            ttl: Some(Duration::from_millis(100)),
+        });
+
+        db.tickets().create_ticket(sample_ticket(1, "Short-lived")).await.unwrap();
+        assert!(matches!(
+            db.tickets().create_ticket(sample_ticket(2, "No room")).await,
+            Err(AppError::StorageFull(_))
+        ));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(matches!(
+            db.tickets().get_ticket("1").await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(db.tickets().list_tickets().await.unwrap().is_empty());
+
+        // The expired ticket no longer counts against the limit
+        db.tickets().create_ticket(sample_ticket(2, "Fits now")).await.unwrap();
+        assert_eq!(db.tickets().list_tickets().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_security_events_drop_oldest() {
+        let db = InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: Some(3),
+            ttl: None,
+        });
+
+        for i in 0..5 {
+            db.security_events()
+                .create_event(SecurityEvent {
+                    id: uuid::Uuid::now_v7().to_string(),
+                    kind: SecurityEventKind::LoginFailure,
+                    username: None,
+                    ip: None,
+                    detail: format!("event {}", i),
+                    created_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let events = db
+            .security_events()
+            .list_events(&SecurityEventFilter::default())
+            .await
+            .unwrap();
+        let details: Vec<_> = events.iter().map(|e| e.detail.as_str()).collect();
+        assert_eq!(details, ["event 4", "event 3", "event 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_purges_expired_sessions_and_records() {
+        let db = Arc::new(InMemoryDatabase::new());
+        let now = Utc::now();
+        for (id, expires_at) in [("old", now - ChronoDuration::seconds(1)), ("live", now + ChronoDuration::hours(1))] {
+            db.sessions()
+                .create_session(Session {
+                    id: id.to_string(),
+                    expires_at,
+                    ..Session::default()
+                })
+                .await
+                .unwrap();
+            db.idempotency()
+                .create_record(IdempotencyRecord {
+                    id: id.to_string(),
+                    expires_at,
+                    ..IdempotencyRecord::default()
+                })
+                .await
+                .unwrap();
+            db.drafts()
+                .put_draft(Draft {
+                    id: id.to_string(),
+                    owner: "alice".to_string(),
+                    key: id.to_string(),
+                    payload: serde_json::json!({}),
+                    updated_at: now,
+                    expires_at,
+                })
+                .await
+                .unwrap();
+            db.shares()
+                .create_share(TicketShare {
+                    id: id.to_string(),
+                    ticket: 1,
+                    created_by: "alice".to_string(),
+                    created_at: now,
+                    expires_at,
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(db.purge_expired(now), 4);
+        assert!(matches!(db.sessions().get_session("old").await, Err(AppError::NotFound(_))));
+        assert!(matches!(db.idempotency().get_record("old").await, Err(AppError::NotFound(_))));
+        assert!(matches!(db.drafts().get_draft("old").await, Err(AppError::NotFound(_))));
+        assert!(matches!(db.shares().get_share("old").await, Err(AppError::NotFound(_))));
+        assert_eq!(db.purge_expired(now), 0);
+
+        // And in the background
+        let mut session = db.sessions().get_session("live").await.unwrap();
+        session.expires_at = now;
+        db.sessions().update_session("live", session).await.unwrap();
+        db.spawn_sweeper(Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(matches!(db.sessions().get_session("live").await, Err(AppError::NotFound(_))));
+        assert!(db.idempotency().get_record("live").await.is_ok());
+    }
+}
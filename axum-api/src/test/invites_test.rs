@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    use crate::{
+        acl::AclCache,
+        config::RuntimeConfig,
+        controllers::invite_controller::InviteController,
+        create_app, create_mock_shared_state,
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        error::AppError,
+        events::EventBus,
+        models::{Group, User},
+        schema::*,
+        state::AppState,
+    };
+
+    /// State with open registration disabled and a single "devs" group.
+    async fn closed_registration_state() -> AppState {
+        let mut state = create_mock_shared_state().unwrap();
+        state.runtime_config = Arc::new(RuntimeConfig {
+            user_login_allowed: false,
+        });
+        state
+            .db
+            .groups()
+            .create_group(Group {
+                gid: "devs".to_string(),
+                name: "Developers".to_string(),
+                principals: vec![],
+                renamed_to: None,
+            })
+            .await
+            .unwrap();
+        state
+    }
+
+    fn register_request(user: &str) -> RegisterRequest {
+        RegisterRequest {
+            user: user.to_string(),
+            password: "securepassword123".to_string(),
+            email: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invite_registration_joins_groups() {
+        let state = Arc::new(closed_registration_state().await);
+        let mgmt_token = state.config.management_token.clone();
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+
+        server
+            .post("/api/register")
+            .json(&register_request("invitee"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/api/mgmt/invites")
+            .authorization_bearer(&mgmt_token)
+            .json(&json!({ "groups": ["devs"] }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let invite = response.json::<ApiResponse<CreateInviteResponse>>().data;
+
+        server
+            .post(&format!("/api/register/invite/{}", invite.token))
+            .json(&register_request("invitee"))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let group = state.db.groups().get_group("devs").await.unwrap();
+        assert_eq!(group.principals, vec!["invitee".to_string()]);
+
+        // Single use
+        server
+            .post(&format!("/api/register/invite/{}", invite.token))
+            .json(&register_request("secondinvitee"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_invites_are_rejected() {
+        let state = closed_registration_state().await;
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .post("/api/register/invite/doesnotexist")
+            .json(&register_request("invitee"))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .post("/api/mgmt/invites")
+            .authorization_bearer(&mgmt_token)
+            .json(&json!({ "groups": ["nosuchgroup"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        server
+            .post("/api/mgmt/invites")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    fn user(username: &str) -> User {
+        User {
+            username: username.to_string(),
+            ..User::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invite_is_claimed_once() {
+        let db = Arc::new(InMemoryDatabase::new());
+        db.groups()
+            .create_group(Group {
+                gid: "devs".to_string(),
+                name: "Developers".to_string(),
+                principals: vec![],
+                renamed_to: None,
+            })
+            .await
+            .unwrap();
+        // Slow enough for both redemptions to pass the check of the unused invite
+        let slow = ChaosConfig {
+            latency: std::time::Duration::from_millis(5),
+            ..ChaosConfig::default()
+        };
+        let chaos = Arc::new(ChaosDatabase::with_seed(db.clone(), slow, 1));
+        let invites = InviteController::new(chaos, Arc::new(AclCache::new()), Arc::new(EventBus::default()));
+        let (token, _) = invites.create_invite(vec!["devs".to_string()], chrono::Duration::days(1)).await.unwrap();
+
+        let (first, second) = tokio::join!(invites.redeem(&token, user("first")), invites.redeem(&token, user("second")));
+        assert_eq!([first.is_ok(), second.is_ok()].iter().filter(|ok| **ok).count(), 1);
+        let group = db.groups().get_group("devs").await.unwrap();
+        assert_eq!(group.principals.len(), 1);
+        assert_eq!(db.users().list_users().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_redemption_releases_the_invite() {
+        let state = closed_registration_state().await;
+        state.db.users().create_user(user("taken")).await.unwrap();
+        let invites = &state.controller.invite;
+        let (token, invite) = invites.create_invite(vec![], chrono::Duration::days(1)).await.unwrap();
+
+        assert!(matches!(invites.redeem(&token, user("taken")).await, Err(AppError::Conflict(_))));
+        assert_eq!(state.db.invites().get_invite(&invite.id).await.unwrap().used_by, None);
+        invites.redeem(&token, user("other")).await.unwrap();
+        let used = state.db.invites().get_invite(&invite.id).await.unwrap();
+        assert_eq!(used.used_by.as_deref(), Some("other"));
+    }
+}
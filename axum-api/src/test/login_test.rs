@@ -29,12 +29,31 @@ mod tests {
         // 5. Assert the JSON body structure and content.
         // We expect the 'status' field to be 'healthy', ignoring the 'timestamp'.
         response.assert_json_contains(&json!({
-            "status": "healthy",
+            "data": {
+                "status": "healthy",
+            }
             // The assert_json_matches method allows you to check for a subset
             // of the JSON fields, which is perfect for ignoring the dynamic timestamp.
         }));
     }
 
+    #[tokio::test]
+    async fn test_version() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let version = server.get("/version").await.json::<ApiResponse<VersionInfo>>().data;
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert!(!version.git_commit.is_empty());
+        assert!(version.build_timestamp <= chrono::Utc::now());
+        assert!(version.build_timestamp.timestamp() > 0);
+        assert_eq!(
+            version.features.contains(&"graphql".to_string()),
+            cfg!(feature = "graphql")
+        );
+    }
+
     #[tokio::test]
     async fn test_user_registration_and_login() {
         // GIVEN: A fresh application instance with an empty state
@@ -50,6 +69,7 @@ mod tests {
         let register_request = RegisterRequest {
             user: email.to_string(),
             password: password.to_string(),
+            email: None,
         };
 
         let register_response = server.post("/api/register").json(&register_request).await;
@@ -62,6 +82,7 @@ mod tests {
         let login_request = LoginRequest {
             user: email.to_string(),
             password: password.to_string(),
+            remember_me: false,
         };
 
         let login_response = server.post("/api/login").json(&login_request).await;
@@ -70,7 +91,7 @@ mod tests {
         login_response.assert_status_ok();
 
         // Deserialize the response into the LoginResponse struct
-        let body: LoginResponse = login_response.json::<LoginResponse>();
+        let body: LoginResponse = login_response.json::<ApiResponse<LoginResponse>>().data;
         assert!(limit_min_length(15)(&body.token).is_ok());
     }
 
@@ -89,6 +110,7 @@ mod tests {
             .json(&RegisterRequest {
                 user: "validusername".to_string(),
                 password: "correct_password".to_string(),
+                email: None,
             })
             .await
             .assert_status_success();
@@ -97,6 +119,7 @@ mod tests {
         let login_request = LoginRequest {
             user: "validusername".to_string(),
             password: "wrong_password".to_string(),
+            remember_me: false,
         };
 
         let login_response = server.post("/api/login").json(&login_request).await;
@@ -104,4 +127,50 @@ mod tests {
         // THEN: The status should be 401 Unauthorized
         login_response.assert_status(StatusCode::UNAUTHORIZED);
     }
+
+    #[tokio::test]
+    async fn test_login_with_email() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let register = |user: &str| RegisterRequest {
+            user: user.to_string(),
+            password: "securepassword123".to_string(),
+            email: Some("amy@example.com".to_string()),
+        };
+        server
+            .post("/api/register")
+            .json(&register("amy"))
+            .await
+            .assert_status(StatusCode::CREATED);
+        // One account per email
+        server
+            .post("/api/register")
+            .json(&register("amy2"))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+
+        let login_response = server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: " Amy@Example.com".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await;
+        login_response.assert_status_ok();
+        let body: LoginResponse = login_response.json::<ApiResponse<LoginResponse>>().data;
+        assert!(limit_min_length(15)(&body.token).is_ok());
+
+        server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: "nobody@example.com".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
 }
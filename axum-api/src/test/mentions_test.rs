@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("dave"))
+            .user(UserFixture::new("eve"))
+            .group("support", &["alice", "bob"])
+            .group("ops", &["dave"])
+            .project(sample_project(&["alice", "ops", "ghost"]))
+            .build()
+            .await
+    }
+
+    async fn suggest(app: &TestApp, username: &str, query: &str) -> Vec<String> {
+        app.get_as(username, &format!("/api/v1/principals/suggest{}", query))
+            .await
+            .json::<ApiResponse<Vec<PrincipalSuggestion>>>()
+            .data
+            .into_iter()
+            .map(|s| s.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_description_mentions() {
+        let app = setup().await;
+        let response = app
+            .post_as("alice", "/api/v1/tickets")
+            .json(&json!({
+                "title": "Disk full",
+                "severity": 2,
+                "severity_label": "major",
+                "description": "@dave and @ops, see alice@example.com. cc @nobody @bob",
+                "assigned_to": "support",
+                "mentioned": ["bob"],
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+
+        let ticket = response.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!(ticket.mentioned, vec!["bob", "dave", "ops"]);
+    }
+
+    #[tokio::test]
+    async fn test_suggest_visible_principals() {
+        let app = setup().await;
+
+        // Groups and fellow members, and the project's principals with groups expanded
+        assert_eq!(
+            suggest(&app, "alice", "?q=").await,
+            vec!["alice", "bob", "dave", "ops", "support"]
+        );
+        assert_eq!(suggest(&app, "alice", "?q=D").await, vec!["dave"]);
+        assert_eq!(suggest(&app, "alice", "?q=&limit=2").await, vec!["alice", "bob"]);
+
+        // Not a member of anything
+        assert_eq!(suggest(&app, "eve", "?q=").await, vec!["eve"]);
+        assert!(suggest(&app, "eve", "?q=a").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_suggest_requires_auth() {
+        let app = setup().await;
+        app.server
+            .get("/api/v1/principals/suggest?q=a")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
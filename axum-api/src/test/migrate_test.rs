@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        db::{DatabaseInterface, inmemory::InMemoryDatabase},
+        error::AppError,
+        migrate::{self, Endpoint, MigrateArgs},
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn args(line: &str) -> Result<MigrateArgs, AppError> {
+        MigrateArgs::parse(line.split_whitespace().map(str::to_string))
+    }
+
+    async fn source() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice", "bob"])
+            .project(sample_project(&["support"]))
+            .ticket(sample_ticket(1, "Login page broken"))
+            .ticket(sample_ticket(2, "Search is slow"))
+            .build()
+            .await
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            args("--from memory --to http://localhost:8529 --to-db tickets").unwrap(),
+            MigrateArgs {
+                from: "memory".to_string(),
+                to: "http://localhost:8529".to_string(),
+                from_db: None,
+                to_db: Some("tickets".to_string()),
+            }
+        );
+        assert!(args("--from memory").is_err());
+        assert!(args("--from memory --to").is_err());
+        assert!(args("--from memory --to memory --force").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_between_databases() {
+        let app = source().await;
+        let from = Endpoint::Database(app.state.db.clone());
+        let target = Arc::new(InMemoryDatabase::new());
+        let to = Endpoint::Database(target.clone());
+
+        let report = migrate::migrate(&from, &to).await.unwrap();
+        assert_eq!(report.into_iter().collect::<Vec<_>>(), [("group", 1), ("project", 1), ("ticket", 2), ("user", 2)]);
+        assert_eq!(
+            target.users().get_user("alice").await.unwrap().password_hash,
+            app.state.db.users().get_user("alice").await.unwrap().password_hash
+        );
+        assert_eq!(target.tickets().list_tickets().await.unwrap().len(), 2);
+
+        // Never merged into existing data
+        assert!(matches!(migrate::migrate(&from, &to).await, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_through_a_dump_file() {
+        let app = source().await;
+        let path = std::env::temp_dir().join(format!("migrate-{}.ndjson", uuid::Uuid::now_v7()));
+        let dump = Endpoint::open(path.to_str().unwrap(), "unused").await.unwrap();
+        assert!(matches!(dump, Endpoint::Dump(_)));
+
+        let written = migrate::migrate(&Endpoint::Database(app.state.db.clone()), &dump).await.unwrap();
+        // The file is never overwritten
+        assert!(migrate::migrate(&Endpoint::Database(app.state.db.clone()), &dump).await.is_err());
+
+        let target = Arc::new(InMemoryDatabase::new());
+        let read = migrate::migrate(&dump, &Endpoint::Database(target.clone())).await.unwrap();
+        assert_eq!(read, written);
+        assert_eq!(target.groups().get_group("support").await.unwrap().principals, ["alice", "bob"]);
+
+        // A dump cut short is refused
+        let content = std::fs::read_to_string(&path).unwrap();
+        let cut_short: String = content.lines().take(3).map(|line| format!("{}\n", line)).collect();
+        std::fs::write(&path, cut_short).unwrap();
+        let result = migrate::migrate(&dump, &Endpoint::Database(Arc::new(InMemoryDatabase::new()))).await;
+        assert!(matches!(result, Err(AppError::Validation(message)) if message.contains("incomplete")));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(Endpoint::open("./data", "unused").await.is_err());
+    }
+}
@@ -0,0 +1,266 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Milestone, MilestoneState, Permissions, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// Alice can change the project, bob can only read it, carol can't see it.
+    /// Tickets 1 to 3 are open, 4 is resolved and 5 belongs to another project.
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice".to_string()],
+        });
+        let id = project.id.to_string();
+        let mut other = sample_project(&[]);
+        other.acl.list[0].permissions = Permissions::WRITE;
+        other.acl.list[0].principals = vec!["alice".to_string()];
+        let mut builder = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .project(project)
+            .ticket(Ticket {
+                project: Some(other.id.to_string()),
+                ..sample_ticket(5, "Elsewhere")
+            })
+            .project(other);
+        for n in 1..=4 {
+            let status = if n == 4 { TicketStatus::Resolved } else { TicketStatus::Open };
+            builder = builder.ticket(Ticket {
+                project: Some(id.clone()),
+                status,
+                ..sample_ticket(n, "Planned")
+            });
+        }
+        (builder.build().await, id)
+    }
+
+    async fn create(app: &TestApp, project: &str, name: &str, start: &str, end: &str) -> Milestone {
+        let response = app
+            .post_as("alice", &format!("/api/v1/projects/{}/milestones", project))
+            .json(&json!({ "name": name, "start": start, "end": end }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        response.json::<ApiResponse<Milestone>>().data
+    }
+
+    async fn assign(app: &TestApp, ticket: i64, milestone: Option<&str>) -> axum_test::TestResponse {
+        app.put_as("alice", &format!("/api/v1/tickets/{}/milestone", ticket))
+            .json(&json!({ "milestone": milestone }))
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list() {
+        let (app, id) = setup().await;
+        let second = create(&app, &id, "Sprint 2", "2026-03-15", "2026-03-28").await;
+        let first = create(&app, &id, " Sprint 1 ", "2026-03-01", "2026-03-14").await;
+        assert_eq!(first.name, "Sprint 1");
+        assert_eq!(first.state, MilestoneState::Open);
+
+        let listed = app
+            .get_as("bob", &format!("/api/v1/projects/{}/milestones", id))
+            .await
+            .json::<ApiResponse<Vec<Milestone>>>()
+            .data;
+        assert_eq!(listed, vec![first.clone(), second]);
+        let fetched = app
+            .get_as("bob", &format!("/api/v1/milestones/{}", first.id))
+            .await
+            .json::<ApiResponse<Milestone>>()
+            .data;
+        assert_eq!(fetched, first);
+
+        for body in [
+            json!({"name": "Backwards", "start": "2026-03-10", "end": "2026-03-01"}),
+            json!({"name": "  ", "start": "2026-03-01", "end": "2026-03-10"}),
+        ] {
+            app.post_as("alice", &format!("/api/v1/projects/{}/milestones", id))
+                .json(&body)
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permissions() {
+        let (app, id) = setup().await;
+        let milestone = create(&app, &id, "Sprint 1", "2026-03-01", "2026-03-14").await;
+        let body = json!({"name": "Sprint 2", "start": "2026-03-15", "end": "2026-03-28"});
+
+        app.post_as("bob", &format!("/api/v1/projects/{}/milestones", id))
+            .json(&body)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.put_as("bob", "/api/v1/tickets/1/milestone")
+            .json(&json!({ "milestone": milestone.id }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("bob", &format!("/api/v1/milestones/{}/close", milestone.id))
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        // Hidden from those who can't see the project
+        app.get_as("carol", &format!("/api/v1/projects/{}/milestones", id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.get_as("carol", &format!("/api/v1/milestones/{}", milestone.id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.get_as("carol", &format!("/api/v1/milestones/{}/burndown", milestone.id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_assign() {
+        let (app, id) = setup().await;
+        let milestone = create(&app, &id, "Sprint 1", "2026-03-01", "2026-03-14").await;
+
+        let response = assign(&app, 1, Some(&milestone.id)).await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<ApiResponse<TicketResponse>>().data.milestone,
+            Some(milestone.id.clone())
+        );
+        // Only into milestones of the ticket's own project
+        assign(&app, 5, Some(&milestone.id))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        assign(&app, 2, Some("missing"))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        let response = assign(&app, 1, None).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<ApiResponse<TicketResponse>>().data.milestone, None);
+    }
+
+    #[tokio::test]
+    async fn test_close_rolls_over() {
+        let (app, id) = setup().await;
+        let first = create(&app, &id, "Sprint 1", "2026-03-01", "2026-03-14").await;
+        let second = create(&app, &id, "Sprint 2", "2026-03-15", "2026-03-28").await;
+        let third = create(&app, &id, "Sprint 3", "2026-03-29", "2026-04-11").await;
+        for ticket in [1, 2, 4] {
+            assign(&app, ticket, Some(&first.id)).await.assert_status_ok();
+        }
+
+        // The next open milestone by default
+        let response = app
+            .post_as("alice", &format!("/api/v1/milestones/{}/close", first.id))
+            .json(&json!({}))
+            .await;
+        response.assert_status_ok();
+        let closed = response.json::<ApiResponse<ClosedMilestoneResponse>>().data;
+        assert_eq!(closed.milestone.state, MilestoneState::Closed);
+        assert!(closed.milestone.closed_at.is_some());
+        assert_eq!(closed.rolled_over, vec![1, 2]);
+        assert_eq!(closed.rollover_to, Some(second.id.clone()));
+
+        let ticket = |n: i64| {
+            let app = &app;
+            async move {
+                app.get_as("alice", &format!("/api/v1/tickets/{}", n))
+                    .await
+                    .json::<ApiResponse<TicketResponse>>()
+                    .data
+                    .milestone
+            }
+        };
+        assert_eq!(ticket(1).await, Some(second.id.clone()));
+        assert_eq!(ticket(4).await, Some(first.id.clone()));
+
+        // Closed milestones take no more tickets and can't be closed again
+        assign(&app, 3, Some(&first.id))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        app.post_as("alice", &format!("/api/v1/milestones/{}/close", first.id))
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+
+        // Or to the one asked for, skipping closed ones
+        app.post_as("alice", &format!("/api/v1/milestones/{}/close", second.id))
+            .json(&json!({ "rollover_to": first.id }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        let closed = app
+            .post_as("alice", &format!("/api/v1/milestones/{}/close", second.id))
+            .json(&json!({ "rollover_to": third.id }))
+            .await
+            .json::<ApiResponse<ClosedMilestoneResponse>>()
+            .data;
+        assert_eq!(closed.rolled_over, vec![1, 2]);
+
+        // With no open milestone left, tickets are taken out of any
+        let closed = app
+            .post_as("alice", &format!("/api/v1/milestones/{}/close", third.id))
+            .json(&json!({}))
+            .await
+            .json::<ApiResponse<ClosedMilestoneResponse>>()
+            .data;
+        assert_eq!(closed.rollover_to, None);
+        assert_eq!(ticket(2).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_burndown() {
+        let now = Utc::now();
+        let project = sample_project(&["bob"]);
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("bob"))
+            .ticket(Ticket {
+                project: Some(id.clone()),
+                milestone: Some("sprint".to_string()),
+                creation_date: now - Duration::days(5),
+                status: TicketStatus::Resolved,
+                resolved_at: Some(now - Duration::days(1)),
+                ..sample_ticket(1, "Done yesterday")
+            })
+            .ticket(Ticket {
+                project: Some(id.clone()),
+                milestone: Some("sprint".to_string()),
+                creation_date: now - Duration::days(2),
+                ..sample_ticket(2, "Still open")
+            })
+            .project(project)
+            .build()
+            .await;
+        app.state
+            .db
+            .milestones()
+            .create_milestone(Milestone {
+                id: "sprint".to_string(),
+                project: id,
+                name: "Sprint".to_string(),
+                start: (now - Duration::days(3)).date_naive(),
+                end: (now + Duration::days(3)).date_naive(),
+                state: MilestoneState::Open,
+                created_at: now,
+                closed_at: None,
+            })
+            .await
+            .unwrap();
+
+        let burndown = app
+            .get_as("bob", "/api/v1/milestones/sprint/burndown")
+            .await
+            .json::<ApiResponse<BurndownResponse>>()
+            .data;
+        // Up to today only
+        let open: Vec<usize> = burndown.days.iter().map(|d| d.open).collect();
+        assert_eq!(open, vec![1, 2, 1, 1]);
+        assert_eq!(burndown.days[0].day, (now - Duration::days(3)).date_naive());
+    }
+}
@@ -1 +1,71 @@
-pub mod login_test;
\ No newline at end of file
+#[cfg(test)]
+pub mod app;
+pub mod acl_props_test;
+pub mod acl_test;
+pub mod activity_test;
+pub mod admin_stats_test;
+pub mod assignment_rules_test;
+pub mod auth_controllers_test;
+pub mod board_test;
+pub mod breaker_test;
+pub mod body_logging_test;
+pub mod cached_db_test;
+pub mod calendar_test;
+pub mod chaos_test;
+pub mod chat_test;
+pub mod clone_test;
+pub mod custom_fields_test;
+pub mod db_contract_test;
+pub mod drafts_test;
+pub mod db_limit_test;
+pub mod deadline_test;
+pub mod dump_test;
+pub mod duplicates_test;
+pub mod email_verification_test;
+pub mod encrypted_metadata_test;
+pub mod escalation_test;
+pub mod events_test;
+pub mod graphql_test;
+pub mod grpc_test;
+pub mod harness_test;
+pub mod hierarchy_test;
+pub mod i18n_test;
+pub mod inbound_email_test;
+pub mod inmemory_limits_test;
+pub mod invites_test;
+pub mod login_test;
+pub mod mentions_test;
+pub mod migrate_test;
+pub mod milestones_test;
+pub mod notifications_test;
+pub mod outbox_test;
+pub mod ownership_test;
+pub mod preferences_test;
+pub mod openapi_test;
+pub mod project_stats_test;
+pub mod public_portal_test;
+pub mod rate_limit_test;
+pub mod read_receipts_test;
+pub mod rename_test;
+pub mod render_test;
+pub mod scope_guards_test;
+pub mod secrets_test;
+pub mod security_events_test;
+pub mod search_index_test;
+pub mod search_test;
+pub mod seed_test;
+pub mod service_accounts_test;
+pub mod sessions_test;
+pub mod shares_test;
+pub mod startup_test;
+pub mod swagger_test;
+pub mod ticket_move_test;
+pub mod tickets_test;
+pub mod timezone_test;
+pub mod trash_test;
+pub mod two_factor_test;
+pub mod user_merge_test;
+pub mod user_metadata_test;
+pub mod validation_props_test;
+pub mod versioning_test;
+pub mod ws_test;
\ No newline at end of file
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum_test::WsMessage;
+    use serde_json::json;
+
+    use crate::{
+        api::v1::ws::protocol::ServerMessage,
+        models::{Notification, NotificationKind},
+        schema::*,
+        test::app::{TestApp, UserFixture},
+    };
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .group("support", &["bob", "carol", "alice"])
+            .websockets()
+            .build()
+            .await
+    }
+
+    async fn create_ticket(app: &TestApp, assigned_to: &str, mentioned: &[&str]) {
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({
+                "title": "Printer on fire",
+                "severity": 1,
+                "severity_label": "critical",
+                "assigned_to": assigned_to,
+                "mentioned": mentioned,
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    async fn notifications(app: &TestApp, username: &str, query: &str) -> ListResponse<Notification> {
+        app.get_as(username, &format!("/api/v1/me/notifications{}", query))
+            .await
+            .json::<ApiResponse<ListResponse<Notification>>>()
+            .data
+    }
+
+    async fn unread(app: &TestApp, username: &str) -> usize {
+        app.get_as(username, "/api/v1/me/notifications/unread-count")
+            .await
+            .json::<ApiResponse<UnreadCount>>()
+            .data
+            .unread
+    }
+
+    #[tokio::test]
+    async fn test_assignment_and_mentions_notify() {
+        let app = setup().await;
+
+        // The group is expanded, the creator and the already assigned bob are skipped
+        create_ticket(&app, "bob", &["support", "nobody"]).await;
+
+        let bob = notifications(&app, "bob", "").await;
+        assert_eq!(bob.total, 1);
+        assert_eq!(bob.items[0].kind, NotificationKind::Assignment);
+        assert_eq!(bob.items[0].payload["by"], "alice");
+        assert_eq!(bob.items[0].link.as_deref(), Some("/api/v1/tickets/1"));
+
+        let carol = notifications(&app, "carol", "").await;
+        assert_eq!(carol.total, 1);
+        assert_eq!(carol.items[0].kind, NotificationKind::Mention);
+        assert_eq!(notifications(&app, "alice", "").await.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_mark_read() {
+        let app = setup().await;
+        create_ticket(&app, "bob", &[]).await;
+        create_ticket(&app, "bob", &[]).await;
+        create_ticket(&app, "bob", &[]).await;
+        assert_eq!(unread(&app, "bob").await, 3);
+
+        let newest = notifications(&app, "bob", "").await.items[0].id.clone();
+        app.post_as("carol", &format!("/api/v1/me/notifications/{}/read", newest))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.post_as("bob", &format!("/api/v1/me/notifications/{}/read", newest))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(unread(&app, "bob").await, 2);
+
+        let page = notifications(&app, "bob", "?unread=true&limit=1").await;
+        assert_eq!((page.items.len(), page.total), (1, 2));
+        assert!(page.next_cursor.is_some());
+
+        let marked = app
+            .post_as("bob", "/api/v1/me/notifications/read-all")
+            .await
+            .json::<ApiResponse<MarkedRead>>()
+            .data;
+        assert_eq!(marked.marked, 2);
+        assert_eq!(unread(&app, "bob").await, 0);
+        assert_eq!(notifications(&app, "bob", "?unread=false").await.total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_pushed() {
+        let app = setup().await;
+        let mut bob = app.ws_as("bob", "/api/v1/ws").await;
+
+        create_ticket(&app, "carol", &["bob"]).await;
+
+        let text = loop {
+            match bob.receive_message().await {
+                WsMessage::Text(text) => break text.to_string(),
+                WsMessage::Ping(_) => continue,
+                other => panic!("Unexpected message {:?}", other),
+            }
+        };
+        match serde_json::from_str(&text).unwrap() {
+            ServerMessage::Notification { notification } => {
+                assert_eq!(notification.recipient, "bob");
+                assert_eq!(notification.kind, NotificationKind::Mention);
+            }
+            other => panic!("Unexpected message {:?}", other),
+        }
+    }
+}
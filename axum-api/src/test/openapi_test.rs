@@ -0,0 +1,98 @@
+#[cfg(all(test, feature = "swagger"))]
+mod tests {
+    use std::sync::Arc;
+
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    use crate::{create_app, create_mock_shared_state};
+
+    #[tokio::test]
+    async fn test_openapi_covers_routes() {
+        let state = create_mock_shared_state().unwrap();
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let spec = server
+            .get("/api-docs/openapi.json")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<Value>();
+
+        for path in [
+            "/health",
+            "/health/ready",
+            "/version",
+            "/api/register",
+            "/api/login",
+            "/api/login/2fa",
+            "/api/refresh",
+            "/api/v1/me/sessions",
+            "/api/v1/me/sessions/{id}",
+            "/api/v1/me/activity",
+            "/api/v1/me/drafts/{key}",
+            "/api/v1/me/preferences/{namespace}",
+            "/api/v1/me/timezone",
+            "/api/v1/projects/{id}/activity",
+            "/api/v1/tickets/{id}/seen",
+            "/api/v1/tickets/{id}/share",
+            "/api/public/tickets/shared",
+            "/api/v1/search",
+            "/api/v1/render/markdown",
+            "/api/v1/projects/{id}/portal",
+            "/api/public/projects/{slug}/tickets",
+            "/api/mgmt/invites",
+            "/api/mgmt/security-events",
+            "/api/mgmt/trash",
+            "/api/mgmt/users/merge",
+            "/api/mgmt/users/{id}/rename",
+            "/api/mgmt/groups/{id}/rename",
+        ] {
+            assert!(spec["paths"].get(path).is_some(), "missing path {}", path);
+        }
+        for schema in ["LoginRequest", "RegisterRequest", "SecurityEvent", "ErrorBody", "ListResponse_Activity"] {
+            assert!(
+                spec["components"]["schemas"].get(schema).is_some(),
+                "missing schema {}",
+                schema
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod components {
+    use serde_json::{Value, json};
+    use utoipa::{IntoResponses, OpenApi};
+
+    use crate::{ApiDoc, error::AppError, schema::ListResponse};
+
+    #[test]
+    fn test_shared_components_are_registered_once() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+        for schema in ["ErrorBody", "ErrorResponse", "PageParams", "SortParams", "ListResponse_Notification"] {
+            assert!(schemas.get(schema).is_some(), "missing schema {}", schema);
+        }
+        let page = &schemas["ListResponse_Activity"];
+        assert_eq!(page["properties"]["items"]["items"], json!({ "$ref": "#/components/schemas/Activity" }));
+        assert!(schemas.get("Activity").is_some());
+    }
+
+    #[test]
+    fn test_responses_refer_to_shared_components() {
+        let reference = |schema: &str| json!({ "$ref": format!("#/components/schemas/{}", schema) });
+        for (status, response) in AppError::responses() {
+            let response = serde_json::to_value(response).unwrap();
+            assert_eq!(response["content"]["application/json"]["schema"], reference("ErrorBody"), "{}", status);
+        }
+        // Every status an `AppError` answers with
+        let documented = AppError::responses();
+        for status in ["400", "401", "404", "409", "429", "500", "503", "504", "507"] {
+            assert!(documented.contains_key(status), "{} is not documented", status);
+        }
+        let page = serde_json::to_value(<ListResponse<Value> as utoipa::PartialSchema>::schema()).unwrap();
+        assert_eq!(page, reference("ListResponse_Value"));
+    }
+}
@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::{
+        controllers::outbox_controller::MAX_ATTEMPTS,
+        db::{
+            NotificationFilter,
+            inmemory::{InMemoryDatabase, InMemoryLimits},
+        },
+        error::AppError,
+        events::{DomainEvent, Subscriber},
+        models::{OutboxEntry, OutboxStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture},
+        utils::BoxFuture,
+    };
+
+    /// Fails the first `failures` events it is handed.
+    struct Flaky {
+        failures: AtomicUsize,
+        handled: AtomicUsize,
+    }
+
+    impl Flaky {
+        fn failing(failures: usize) -> Arc<Self> {
+            Arc::new(Self {
+                failures: AtomicUsize::new(failures),
+                handled: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl Subscriber for Flaky {
+        fn handle<'a>(&'a self, _: &'a DomainEvent) -> BoxFuture<'a, Result<(), AppError>> {
+            let failed = self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok();
+            Box::pin(async move {
+                if failed {
+                    return Err(AppError::Internal(anyhow::anyhow!("Webhook is down")));
+                }
+                self.handled.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .config(|c| c.outbox = true)
+            .build()
+            .await
+    }
+
+    async fn create_ticket(app: &TestApp) {
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({ "title": "Refunds are late", "severity": 3, "assigned_to": "bob" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    async fn unread(app: &TestApp, username: &str) -> usize {
+        let filter = NotificationFilter {
+            unread: Some(true),
+            ..NotificationFilter::default()
+        };
+        app.state.db.notifications().count_notifications(username, &filter).await.unwrap()
+    }
+
+    async fn entries(app: &TestApp, query: &str) -> Vec<OutboxEntry> {
+        let response = app.get_mgmt(&format!("/api/mgmt/outbox{}", query)).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<Vec<OutboxEntry>>>().data
+    }
+
+    #[tokio::test]
+    async fn test_events_wait_in_the_outbox_until_dispatched() {
+        let app = setup().await;
+        let mut stream = app.state.events.stream();
+
+        create_ticket(&app).await;
+        let pending = entries(&app, "?status=pending").await;
+        assert_eq!(pending.len(), 1);
+        assert!(matches!(pending[0].event, DomainEvent::TicketUpdated(_)));
+        assert_eq!(unread(&app, "bob").await, 0);
+        assert!(stream.try_recv().is_err());
+
+        assert_eq!(app.state.controller.outbox.dispatch_pending().await.unwrap(), 1);
+        assert_eq!(unread(&app, "bob").await, 1);
+        assert!(matches!(stream.try_recv().unwrap(), DomainEvent::TicketUpdated(_)));
+        let entry = &entries(&app, "").await[0];
+        assert_eq!(entry.status, OutboxStatus::Delivered);
+        assert_eq!(entry.attempts, 1);
+        assert!(entry.delivered_at.is_some());
+
+        // Nothing left to deliver
+        assert_eq!(app.state.controller.outbox.dispatch_pending().await.unwrap(), 0);
+        assert_eq!(unread(&app, "bob").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ticket_is_not_created_without_its_event() {
+        // Two of each: the outbox is full once two tickets have been created
+        let db = Arc::new(InMemoryDatabase::with_limits(InMemoryLimits {
+            max_entities: Some(2),
+            ttl: None,
+        }));
+        let app = TestApp::builder()
+            .database(db)
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .config(|c| c.outbox = true)
+            .build()
+            .await;
+        create_ticket(&app).await;
+        create_ticket(&app).await;
+        assert_eq!(app.state.db.tickets().count_tickets().await.unwrap(), 2);
+        for ticket in app.state.db.tickets().list_tickets().await.unwrap() {
+            app.state.db.tickets().delete_ticket(&ticket.id.to_string(), true).await.unwrap();
+        }
+
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({ "title": "Refunds are late", "severity": 3 }))
+            .await
+            .assert_status(StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(app.state.db.tickets().count_tickets().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_deliveries_are_retried() {
+        let app = setup().await;
+        let webhook = Flaky::failing(2);
+        app.state.events.subscribe(webhook.clone());
+        let outbox = &app.state.controller.outbox;
+
+        create_ticket(&app).await;
+        assert_eq!(outbox.dispatch_pending().await.unwrap(), 0);
+        assert_eq!(outbox.dispatch_pending().await.unwrap(), 0);
+        let entry = &entries(&app, "").await[0];
+        assert_eq!((entry.status, entry.attempts), (OutboxStatus::Pending, 2));
+        assert!(entry.last_error.as_deref().unwrap().contains("Webhook is down"));
+
+        assert_eq!(outbox.dispatch_pending().await.unwrap(), 1);
+        assert_eq!(webhook.handled.load(Ordering::SeqCst), 1);
+        // At least once: the notification subscriber ran on every attempt
+        assert_eq!(unread(&app, "bob").await, 3);
+
+        // Given up after too many failures
+        let webhook = Flaky::failing(usize::MAX);
+        app.state.events.subscribe(webhook);
+        create_ticket(&app).await;
+        for _ in 0..MAX_ATTEMPTS + 1 {
+            outbox.dispatch_pending().await.unwrap();
+        }
+        let failed = entries(&app, "?status=failed").await;
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].attempts, MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let app = setup().await;
+        let outbox = &app.state.controller.outbox;
+        create_ticket(&app).await;
+        create_ticket(&app).await;
+        outbox.dispatch_pending().await.unwrap();
+        assert_eq!(unread(&app, "bob").await, 2);
+
+        let id = entries(&app, "").await[0].id.clone();
+        let response = app.post_mgmt(&format!("/api/mgmt/outbox/{}/replay", id)).await;
+        response.assert_status_ok();
+        let entry = response.json::<ApiResponse<OutboxEntry>>().data;
+        assert_eq!((entry.status, entry.attempts), (OutboxStatus::Pending, 0));
+        assert_eq!(outbox.dispatch_pending().await.unwrap(), 1);
+        assert_eq!(unread(&app, "bob").await, 3);
+
+        app.post_mgmt("/api/mgmt/outbox/missing/replay")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        let response = app
+            .post_mgmt("/api/mgmt/outbox/replay")
+            .json(&json!({ "since": Utc::now() - Duration::hours(1) }))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<ApiResponse<ReplayedEntries>>().data.replayed, 2);
+        assert_eq!(outbox.dispatch_pending().await.unwrap(), 2);
+        assert_eq!(unread(&app, "bob").await, 5);
+
+        app.server
+            .get("/api/mgmt/outbox")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Permissions},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob", "carol"]);
+        project.owner = Some("alice".to_string());
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::ROOT,
+            principals: vec!["carol".to_string()],
+        });
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .project(project)
+            .build()
+            .await;
+        (app, id)
+    }
+
+    #[tokio::test]
+    async fn test_owner_has_root() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/acl", id);
+
+        // alice is in no ACL entry, yet manages the ACL
+        app.put_as("alice", &path)
+            .json(&json!([{"permissions": "READ", "principals": ["bob", "carol"]}]))
+            .await
+            .assert_status_ok();
+        // Nobody else is left with ROOT, the owner still is
+        app.get_as("alice", &path).await.assert_status_ok();
+        app.get_as("carol", &path).await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_ownership() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/transfer-ownership", id);
+
+        // Holding ROOT through the ACL is not owning
+        app.post_as("carol", &path)
+            .json(&json!({"new_owner": "carol", "confirm": id}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        // The project id must be repeated, and the new owner be a user
+        app.post_as("alice", &path)
+            .json(&json!({"new_owner": "bob", "confirm": "yes"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.post_as("alice", &path)
+            .json(&json!({"new_owner": "nobody", "confirm": id}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let transferred = app
+            .post_as("alice", &path)
+            .json(&json!({"new_owner": "bob", "confirm": id}))
+            .await
+            .json::<ApiResponse<ProjectOwnershipResponse>>()
+            .data;
+        assert_eq!(transferred.owner, "bob");
+        assert_eq!(transferred.previous_owner.as_deref(), Some("alice"));
+
+        // alice is left with what the ACL grants her, nothing
+        app.get_as("alice", &format!("/api/v1/projects/{}/severities", id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.get_as("bob", &format!("/api/v1/projects/{}/acl", id))
+            .await
+            .assert_status_ok();
+        let project = app.state.db.projects().get_project(&id).await.unwrap();
+        assert_eq!(project.owner.as_deref(), Some("bob"));
+    }
+
+    #[tokio::test]
+    async fn test_first_owner_is_set_by_root() {
+        let (app, id) = setup().await;
+        let mut project = app.state.db.projects().get_project(&id).await.unwrap();
+        project.owner = None;
+        app.state.db.projects().update_project(&id, project).await.unwrap();
+
+        let path = format!("/api/v1/projects/{}/transfer-ownership", id);
+        app.post_as("bob", &path)
+            .json(&json!({"new_owner": "bob", "confirm": id}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let transferred = app
+            .post_as("carol", &path)
+            .json(&json!({"new_owner": "carol", "confirm": id}))
+            .await
+            .json::<ApiResponse<ProjectOwnershipResponse>>()
+            .data;
+        assert_eq!(transferred.previous_owner, None);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        config::AppConfig,
+        models::Preference,
+        schema::*,
+        test::app::{TestApp, UserFixture},
+    };
+
+    const PATH: &str = "/api/v1/me/preferences/board";
+
+    async fn setup(configure: impl FnOnce(&mut AppConfig) + 'static) -> TestApp {
+        TestApp::builder()
+            .config(configure)
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .build()
+            .await
+    }
+
+    async fn save(app: &TestApp, path: &str, value: Value, version: u64) -> Preference {
+        let response = app.put_as("alice", path).json(&json!({ "value": value, "version": version })).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<Preference>>().data
+    }
+
+    #[tokio::test]
+    async fn test_saves_are_versioned() {
+        let app = setup(|_| {}).await;
+        let created = save(&app, PATH, json!({ "columns": [120, 80] }), 0).await;
+        assert_eq!(created.version, 1);
+        let saved = save(&app, PATH, json!({ "columns": [160, 80] }), 1).await;
+        assert_eq!(saved.version, 2);
+
+        let response = app.get_as("alice", PATH).await;
+        response.assert_status_ok();
+        let read = response.json::<ApiResponse<Preference>>().data;
+        assert_eq!((read.namespace.as_str(), read.version), ("board", 2));
+        assert_eq!(read.value, json!({ "columns": [160, 80] }));
+        app.get_as("bob", PATH).await.assert_status(StatusCode::NOT_FOUND);
+
+        // Another client still holding version 1, or creating the namespace again
+        for version in [1, 0] {
+            app.put_as("alice", PATH)
+                .json(&json!({ "value": { "columns": [] }, "version": version }))
+                .await
+                .assert_status(StatusCode::CONFLICT);
+        }
+        assert_eq!(save(&app, PATH, json!("dark"), 2).await.version, 3);
+
+        app.delete_as("alice", PATH).await.assert_status(StatusCode::NO_CONTENT);
+        app.delete_as("alice", PATH).await.assert_status(StatusCode::NO_CONTENT);
+        app.get_as("alice", PATH).await.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(save(&app, PATH, json!({}), 0).await.version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let app = setup(|c| {
+            c.preference_max_bytes = 32;
+            c.preference_max_namespaces = 2;
+        })
+        .await;
+        app.put_as("alice", PATH)
+            .json(&json!({ "value": "x".repeat(40) }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        for namespace in ["Board", "a%20b", &"x".repeat(65)] {
+            app.put_as("alice", &format!("/api/v1/me/preferences/{}", namespace))
+                .json(&json!({ "value": {} }))
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+
+        save(&app, PATH, json!({}), 0).await;
+        save(&app, "/api/v1/me/preferences/theme.v2", json!("dark"), 0).await;
+        app.put_as("alice", "/api/v1/me/preferences/layout")
+            .json(&json!({ "value": {} }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        // Saving existing namespaces is still allowed
+        save(&app, PATH, json!({ "columns": [] }), 1).await;
+
+        let namespaces: Vec<String> = app
+            .get_as("alice", "/api/v1/me/preferences")
+            .await
+            .json::<ApiResponse<Vec<Preference>>>()
+            .data
+            .into_iter()
+            .map(|p| p.namespace)
+            .collect();
+        assert_eq!(namespaces, ["board", "theme.v2"]);
+    }
+}
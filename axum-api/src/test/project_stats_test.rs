@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+
+    use crate::{
+        models::{Project, Severity, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn project_ticket(id: i64, project: &Project, severity: u8, assigned_to: &str) -> Ticket {
+        Ticket {
+            project: Some(project.id.to_string()),
+            severity: Severity::new(severity, "level"),
+            assigned_to: assigned_to.to_string(),
+            ..sample_ticket(id, "Project ticket")
+        }
+    }
+
+    async fn setup() -> (TestApp, String) {
+        let project = sample_project(&["alice"]);
+        let created = Utc::now() - Duration::hours(3);
+        let resolved = Ticket {
+            status: TicketStatus::Resolved,
+            creation_date: created,
+            resolved_at: Some(created + Duration::hours(2)),
+            ..project_ticket(4, &project, 1, "bob")
+        };
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("mallory"))
+            .project(project.clone())
+            .ticket(project_ticket(1, &project, 1, "bob"))
+            .ticket(project_ticket(2, &project, 1, "carol"))
+            .ticket(project_ticket(3, &project, 3, "bob"))
+            .ticket(resolved)
+            .ticket(sample_ticket(5, "Elsewhere"))
+            .build()
+            .await;
+        (app, project.id.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_project_stats() {
+        let (app, project) = setup().await;
+
+        let stats = app
+            .get_as("alice", &format!("/api/v1/projects/{}/stats", project))
+            .add_query_param("days", 7)
+            .await
+            .json::<ApiResponse<ProjectStatsResponse>>()
+            .data;
+
+        assert_eq!(stats.total, 4);
+        let by_status: Vec<_> = stats.by_status.iter().map(|c| (c.status, c.count)).collect();
+        assert_eq!(by_status, vec![(TicketStatus::Open, 3), (TicketStatus::Resolved, 1)]);
+        let by_severity: Vec<_> = stats.by_severity.iter().map(|c| (c.severity, c.count)).collect();
+        assert_eq!(by_severity, vec![(1, 3), (3, 1)]);
+        assert_eq!(stats.average_resolution_secs, Some(7200.0));
+        assert_eq!(stats.top_assignees[0].assignee, "bob");
+        assert_eq!(stats.top_assignees[0].count, 3);
+
+        assert_eq!(stats.trend.len(), 7);
+        assert_eq!(stats.trend.last().unwrap().day, Utc::now().date_naive());
+        assert_eq!(stats.trend.iter().map(|d| d.opened).sum::<usize>(), 4);
+        assert_eq!(stats.trend.iter().map(|d| d.resolved).sum::<usize>(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_project_stats_are_cached() {
+        let (app, project) = setup().await;
+        let path = format!("/api/v1/projects/{}/stats", project);
+
+        let first = app.get_as("alice", &path).await.json::<ApiResponse<ProjectStatsResponse>>().data;
+        app.state
+            .db
+            .tickets()
+            .create_ticket(Ticket {
+                project: Some(project.clone()),
+                ..sample_ticket(6, "Late arrival")
+            })
+            .await
+            .unwrap();
+        let second = app.get_as("alice", &path).await.json::<ApiResponse<ProjectStatsResponse>>().data;
+
+        assert_eq!(second.total, first.total);
+        assert_eq!(second.generated_at, first.generated_at);
+    }
+
+    #[tokio::test]
+    async fn test_project_stats_access_and_validation() {
+        let (app, project) = setup().await;
+        let path = format!("/api/v1/projects/{}/stats", project);
+
+        app.get_as("mallory", &path)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.get_as("alice", &path)
+            .add_query_param("days", 0)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.server.get(&path).await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
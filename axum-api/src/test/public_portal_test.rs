@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        captcha::FixedCaptchaVerifier,
+        models::Ticket,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project},
+    };
+
+    async fn setup() -> (TestApp, String) {
+        let mut project = sample_project(&["bob"]);
+        project.owner = Some("alice".to_string());
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project)
+            .config(|c| c.portal_rate_limit = "POST /api/public/projects/{slug}/tickets: 3/hour/ip".parse().unwrap())
+            .config(|c| c.trusted_proxies = vec!["127.0.0.1/32".parse().unwrap()])
+            .state(|s| s.captcha = Arc::new(FixedCaptchaVerifier::new("solved")))
+            .build()
+            .await;
+        (app, id)
+    }
+
+    async fn set_portal(app: &TestApp, id: &str, body: Value) -> PublicPortalResponse {
+        let response = app.put_as("alice", &format!("/api/v1/projects/{}/portal", id)).json(&body).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<PublicPortalResponse>>().data
+    }
+
+    async fn submit(app: &TestApp, slug: &str, ip: &str, body: Value) -> axum_test::TestResponse {
+        app.server
+            .post(&format!("/api/public/projects/{}/tickets", slug))
+            .add_header("X-Forwarded-For", ip)
+            .json(&body)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_submit_ticket() {
+        let (app, id) = setup().await;
+        submit(&app, "acme", "10.0.0.1", json!({ "title": "Broken" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        let portal = set_portal(&app, &id, json!({ "slug": "Acme" })).await;
+        assert_eq!(portal.slug, "acme");
+        assert_eq!(portal.reporter, "portal:acme");
+        assert!(!portal.token_required && portal.token.is_none());
+
+        let response = submit(
+            &app,
+            "acme",
+            "10.0.0.1",
+            json!({ "title": "Export is broken", "description": "Times out", "email": "Dave@Customer.example" }),
+        )
+        .await;
+        response.assert_status(StatusCode::CREATED);
+        let filed = response.json::<ApiResponse<PublicTicketResponse>>().data;
+
+        let ticket: Ticket = app.state.db.tickets().get_ticket(&filed.ticket.to_string()).await.unwrap();
+        assert_eq!(ticket.created_by, "portal:acme");
+        assert_eq!(ticket.project, Some(id));
+        assert_eq!(ticket.severity.label, "trivial");
+        assert_eq!(
+            ticket.description,
+            "Times out\n\nReported through the public portal by dave@customer.example"
+        );
+
+        submit(&app, "acme", "10.0.0.2", json!({ "title": " " }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        submit(&app, "acme", "10.0.0.2", json!({ "title": "Bad", "email": "nobody" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_token_and_captcha() {
+        let (app, id) = setup().await;
+        let portal = set_portal(&app, &id, json!({ "slug": "acme", "require_token": true, "captcha": true })).await;
+        let token = portal.token.unwrap();
+
+        submit(&app, "acme", "10.0.0.1", json!({ "title": "Broken", "captcha": "solved" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        submit(&app, "acme", "10.0.0.1", json!({ "title": "Broken", "token": token, "captcha": "guessed" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        submit(&app, "acme", "10.0.0.1", json!({ "title": "Broken", "token": token, "captcha": "solved" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // Changing the portal keeps its token unless it is rotated
+        let kept = set_portal(&app, &id, json!({ "slug": "acme", "require_token": true })).await;
+        assert!(kept.token_required && kept.token.is_none());
+        let rotated = set_portal(&app, &id, json!({ "slug": "acme", "require_token": true, "rotate_token": true })).await;
+        let new_token = rotated.token.unwrap();
+        assert_ne!(new_token, token);
+
+        let response = app.get_as("alice", &format!("/api/v1/projects/{}/portal", id)).await;
+        let shown = response.json::<ApiResponse<Option<PublicPortalResponse>>>().data.unwrap();
+        assert!(shown.token_required && !shown.captcha && shown.token.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_by_ip() {
+        let (app, id) = setup().await;
+        set_portal(&app, &id, json!({ "slug": "acme" })).await;
+        for _ in 0..3 {
+            submit(&app, "acme", "10.0.0.1", json!({ "title": "Spam" }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        let response = submit(&app, "acme", "10.0.0.1", json!({ "title": "Spam" })).await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+        submit(&app, "acme", "10.0.0.2", json!({ "title": "Real issue" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_manage_portal() {
+        let (app, id) = setup().await;
+        let path = format!("/api/v1/projects/{}/portal", id);
+        // Readers can't see or change it
+        app.get_as("bob", &path).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.put_as("bob", &path)
+            .json(&json!({ "slug": "acme" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = app.get_as("alice", &path).await;
+        assert!(response.json::<ApiResponse<Option<PublicPortalResponse>>>().data.is_none());
+        app.put_as("alice", &path)
+            .json(&json!({ "slug": "a b" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        set_portal(&app, &id, json!({ "slug": "acme" })).await;
+
+        // Slugs are unique, and clones don't take the portal along
+        let response = app
+            .post_as("alice", &format!("/api/v1/projects/{}/clone", id))
+            .json(&json!({}))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let clone = response.json::<ApiResponse<CloneProjectResponse>>().data.project;
+        let clone_path = format!("/api/v1/projects/{}/portal", clone);
+        let response = app.get_as("alice", &clone_path).await;
+        assert!(response.json::<ApiResponse<Option<PublicPortalResponse>>>().data.is_none());
+        app.put_as("alice", &clone_path)
+            .json(&json!({ "slug": "acme" }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+
+        app.delete_as("alice", &path).await.assert_status(StatusCode::NO_CONTENT);
+        app.delete_as("alice", &path).await.assert_status(StatusCode::NOT_FOUND);
+        submit(&app, "acme", "10.0.0.1", json!({ "title": "Broken" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
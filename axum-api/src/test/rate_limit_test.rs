@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::http::{Method, StatusCode, header};
+
+    use crate::{
+        middleware::rate_limit::{LimitKey, RateLimitRule, RateLimiter, parse_rules},
+        test::app::{TestApp, UserFixture},
+    };
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(
+            "POST /api/register: 5/min/ip
+             # everything else
+             /api/v1/*: 600/hour/user; * /api/mgmt/{action}: 10/sec/global",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules[0],
+            RateLimitRule {
+                method: Some(Method::POST),
+                pattern: "/api/register".to_string(),
+                limit: 5,
+                window: Duration::from_secs(60),
+                key: LimitKey::Ip,
+            }
+        );
+        assert_eq!((rules[1].method.as_ref(), rules[1].key), (None, LimitKey::User));
+        assert_eq!((rules[2].window, rules[2].key), (Duration::from_secs(1), LimitKey::Global));
+
+        for invalid in [
+            "/api/login 5/min",
+            "POST /api/login: 0/min",
+            "POST /api/login: 5/week",
+            "POST /api/login: 5/min/session",
+            "FETCH! /api/login: 5/min",
+            "api/login: 5/min",
+            "/api/*/login: 5/min",
+        ] {
+            assert!(parse_rules(invalid).is_err(), "accepted {}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_rule_matching_and_keys() {
+        let limiter = RateLimiter::new(parse_rules("GET /api/v1/tickets/{id}: 2/min/user").unwrap());
+
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets/1", "10.0.0.1", Some("alice")).is_ok());
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets/2", "10.0.0.2", Some("alice")).is_ok());
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets/3", "10.0.0.1", Some("alice")).is_err());
+        // Other users, other routes and anonymous callers have their own budget
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets/1", "10.0.0.1", Some("bob")).is_ok());
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets", "10.0.0.1", Some("alice")).is_ok());
+        assert!(limiter.check(&Method::POST, "/api/v1/tickets/1", "10.0.0.1", Some("alice")).is_ok());
+        assert!(limiter.check(&Method::GET, "/api/v1/tickets/1", "10.0.0.1", None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_route() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .config(|config| {
+                config.rate_limits = parse_rules("POST /api/login: 2/min/ip").unwrap();
+                config.trusted_proxies = vec!["127.0.0.1/32".parse().unwrap()];
+            })
+            .build()
+            .await;
+
+        // The fixture login used one request of the budget
+        app.server
+            .post("/api/login")
+            .json(&serde_json::json!({"user": "alice", "password": "wrong-password"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let response = app
+            .server
+            .post("/api/login")
+            .json(&serde_json::json!({"user": "alice", "password": "wrong-password"}))
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.header(header::RETRY_AFTER).to_str().unwrap().parse().unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Other routes and other clients are not affected
+        app.get_as("alice", "/api/v1/me/sessions").await.assert_status_ok();
+        app.server
+            .post("/api/login")
+            .add_header("X-Forwarded-For", "10.0.0.2")
+            .json(&serde_json::json!({"user": "alice", "password": "wrong-password"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_headers_need_a_trusted_proxy() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .config(|config| config.rate_limits = parse_rules("POST /api/login: 2/min/ip").unwrap())
+            .build()
+            .await;
+
+        // Each request claims another client, but comes from the same untrusted peer
+        for (ip, status) in [("10.0.0.2", StatusCode::UNAUTHORIZED), ("10.0.0.3", StatusCode::TOO_MANY_REQUESTS)] {
+            app.server
+                .post("/api/login")
+                .add_header("X-Forwarded-For", ip)
+                .add_header("X-Real-IP", ip)
+                .json(&serde_json::json!({"user": "alice", "password": "wrong-password"}))
+                .await
+                .assert_status(status);
+        }
+    }
+}
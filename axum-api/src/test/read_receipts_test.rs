@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{Value, json};
+
+    use crate::{
+        models::{NotificationKind, ReadReceipt},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice", "bob"])
+            .ticket(sample_ticket(1, "Printer on fire"))
+            .ticket(sample_ticket(2, "Scanner on fire"))
+            .build()
+            .await
+    }
+
+    async fn list(app: &TestApp, username: &str, query: &str) -> Vec<Value> {
+        let response = app.get_as(username, &format!("/api/v1/tickets{}", query)).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<ListResponse<Value>>>().data.items
+    }
+
+    async fn unread(app: &TestApp, username: &str) -> Vec<i64> {
+        let mut ids: Vec<i64> = list(app, username, "")
+            .await
+            .iter()
+            .filter(|t| t["unread"] == json!(true))
+            .map(|t| t["id"].as_i64().unwrap())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    async fn see(app: &TestApp, username: &str, ticket: &str) -> ReadReceipt {
+        let response = app.post_as(username, &format!("/api/v1/tickets/{}/seen", ticket)).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<ReadReceipt>>().data
+    }
+
+    #[tokio::test]
+    async fn test_tickets_are_unread_until_seen() {
+        let app = setup().await;
+        assert_eq!(unread(&app, "alice").await, vec![1, 2]);
+
+        let receipt = see(&app, "alice", "1").await;
+        assert_eq!((receipt.ticket, receipt.username.as_str()), (1, "alice"));
+        assert_eq!(unread(&app, "alice").await, vec![2]);
+        assert_eq!(unread(&app, "bob").await, vec![1, 2]);
+
+        // A field like any other
+        let titles = list(&app, "alice", "?fields=title,unread").await;
+        assert!(titles.iter().all(|t| t.get("id").is_none()));
+        assert_eq!(titles.iter().filter(|t| t["unread"] == json!(false)).count(), 1);
+        let titles = list(&app, "alice", "?fields=title").await;
+        assert!(titles.iter().all(|t| t.get("unread").is_none()));
+        app.get_as("alice", "/api/v1/tickets/1?fields=unread")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // A change makes it unread again
+        app.post_as("bob", "/api/v1/tickets/1/comments")
+            .json(&json!({ "body": "Still burning" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        assert_eq!(unread(&app, "alice").await, vec![1, 2]);
+
+        see(&app, "bob", "1").await;
+        let response = app.get_as("alice", "/api/v1/tickets/1/seen").await;
+        response.assert_status_ok();
+        let receipts = response.json::<ApiResponse<Vec<ReadReceipt>>>().data;
+        let seen_by: Vec<&str> = receipts.iter().map(|r| r.username.as_str()).collect();
+        assert_eq!(seen_by, vec!["bob", "alice"]);
+
+        app.post_as("alice", "/api/v1/tickets/3/seen")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_seeing_a_ticket_reads_its_notifications() {
+        let app = setup().await;
+        let notifications = &app.state.controller.notification;
+        for ticket in [1, 1, 2] {
+            let link = format!("/api/v1/tickets/{}", ticket);
+            notifications
+                .notify("alice", NotificationKind::Mention, json!({ "ticket": ticket }), Some(link))
+                .await
+                .unwrap();
+        }
+        notifications
+            .notify("bob", NotificationKind::Mention, json!({ "ticket": 1 }), Some("/api/v1/tickets/1".into()))
+            .await
+            .unwrap();
+
+        see(&app, "alice", "1").await;
+        assert_eq!(notifications.unread_count("alice").await.unwrap(), 1);
+        assert_eq!(notifications.unread_count("bob").await.unwrap(), 1);
+    }
+}
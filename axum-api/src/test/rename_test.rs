@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Comment, Permissions, Project, Ticket},
+        schema::*,
+        test::app::{DEFAULT_PASSWORD, TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// Alice filed ticket 1 and commented on it, is in support and owns the project,
+    /// which support can change.
+    fn project() -> Project {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["support".to_string()],
+        });
+        project.owner = Some("alice".to_string());
+        project
+    }
+
+    async fn setup(project: &Project) -> TestApp {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice"])
+            .ticket(Ticket {
+                created_by: "alice".to_string(),
+                project: Some(project.id.to_string()),
+                ..sample_ticket(1, "Printer on fire")
+            })
+            .project(project.clone())
+            .build()
+            .await;
+        let comment = Comment {
+            id: uuid::Uuid::now_v7().to_string(),
+            ticket: 1,
+            author: "alice".to_string(),
+            body: "On it".to_string(),
+            created_at: Utc::now(),
+            message_id: None,
+        };
+        app.state.db.comments().create_comment(comment).await.unwrap();
+        app
+    }
+
+    async fn rename(app: &TestApp, path: &str, to: &str) -> RenamePrincipalResponse {
+        let response = app.post_mgmt(path).json(&json!({ "to": to })).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<RenamePrincipalResponse>>().data
+    }
+
+    #[tokio::test]
+    async fn test_renamed_user_keeps_their_references() {
+        let project = project();
+        let app = setup(&project).await;
+
+        let renamed = rename(&app, "/api/mgmt/users/alice/rename", "alicia").await;
+        assert_eq!(
+            (renamed.tickets, renamed.comments, renamed.groups, renamed.projects, renamed.sessions),
+            (1, 1, 1, 1, 1)
+        );
+
+        let db = &app.state.db;
+        assert_eq!(db.tickets().get_ticket("1").await.unwrap().created_by, "alicia");
+        assert_eq!(db.comments().list_comments(1).await.unwrap()[0].author, "alicia");
+        assert_eq!(db.groups().get_group("support").await.unwrap().principals, vec!["alicia"]);
+        let project = db.projects().get_project(&project.id.to_string()).await.unwrap();
+        assert_eq!(project.owner.as_deref(), Some("alicia"));
+
+        let alias = db.users().get_user("alice").await.unwrap();
+        assert_eq!((alias.deactivated, alias.renamed_to.as_deref()), (true, Some("alicia")));
+        app.login("alicia", DEFAULT_PASSWORD).await;
+    }
+
+    #[tokio::test]
+    async fn test_old_token_acts_as_the_new_name() {
+        let project = project();
+        let app = setup(&project).await;
+        rename(&app, "/api/mgmt/users/alice/rename", "alicia").await;
+
+        app.get_as("alice", "/api/v1/tickets/1").await.assert_status_ok();
+        app.put_as("alice", "/api/v1/me/timezone")
+            .json(&json!({ "timezone": "Europe/Kyiv" }))
+            .await
+            .assert_status_ok();
+        let user = app.state.db.users().get_user("alicia").await.unwrap();
+        assert_eq!(user.personal.timezone.as_deref(), Some("Europe/Kyiv"));
+        assert_eq!(app.state.db.users().get_user("alice").await.unwrap().personal.timezone, None);
+    }
+
+    #[tokio::test]
+    async fn test_old_and_taken_names_are_refused() {
+        let project = project();
+        let app = setup(&project).await;
+        for to in ["bob", "support", "Not a name!"] {
+            let status = app
+                .post_mgmt("/api/mgmt/users/alice/rename")
+                .json(&json!({ "to": to }))
+                .await
+                .status_code();
+            assert!(status == StatusCode::CONFLICT || status == StatusCode::BAD_REQUEST, "{} gave {}", to, status);
+        }
+        app.post_mgmt("/api/mgmt/users/nobody/rename")
+            .json(&json!({ "to": "somebody" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        rename(&app, "/api/mgmt/users/alice/rename", "alicia").await;
+        app.post_mgmt("/api/mgmt/users/bob/rename")
+            .json(&json!({ "to": "alice" }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        app.post_mgmt("/api/mgmt/users/alice/rename")
+            .json(&json!({ "to": "alice2" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.post_as("bob", "/api/mgmt/users/bob/rename")
+            .json(&json!({ "to": "robert" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_renamed_group_keeps_members_and_acl() {
+        let project = project();
+        let app = setup(&project).await;
+
+        let renamed = rename(&app, "/api/mgmt/groups/support/rename", "helpdesk").await;
+        assert_eq!((renamed.projects, renamed.sessions), (1, 0));
+
+        let db = &app.state.db;
+        assert_eq!(db.groups().get_group("helpdesk").await.unwrap().principals, vec!["alice"]);
+        let alias = db.groups().get_group("support").await.unwrap();
+        assert!(alias.principals.is_empty());
+        assert_eq!(alias.renamed_to.as_deref(), Some("helpdesk"));
+        let project = db.projects().get_project(&project.id.to_string()).await.unwrap();
+        assert_eq!(project.acl.list[1].principals, vec!["helpdesk"]);
+
+        // Alice still can change the ticket through the group
+        app.post_as("alice", "/api/v1/tickets/1/share")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let groups: Vec<String> = db.groups().list_groups().await.unwrap().into_iter().map(|g| g.gid).collect();
+        assert!(groups.contains(&"support".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_old_invite_joins_the_renamed_group() {
+        let project = project();
+        let app = setup(&project).await;
+        let response = app.post_mgmt("/api/mgmt/invites").json(&json!({ "groups": ["support"] })).await;
+        response.assert_status(StatusCode::CREATED);
+        let invite = response.json::<ApiResponse<CreateInviteResponse>>().data;
+
+        rename(&app, "/api/mgmt/groups/support/rename", "helpdesk").await;
+        app.server
+            .post(&format!("/api/register/invite/{}", invite.token))
+            .json(&json!({ "user": "carol", "password": DEFAULT_PASSWORD }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let db = &app.state.db;
+        assert_eq!(db.groups().get_group("helpdesk").await.unwrap().principals, vec!["alice", "carol"]);
+        assert!(db.groups().get_group("support").await.unwrap().principals.is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    async fn render(app: &TestApp, markdown: &str) -> String {
+        let response = app
+            .post_as("alice", "/api/v1/render/markdown")
+            .json(&json!({ "markdown": markdown }))
+            .await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<RenderedMarkdown>>().data.html
+    }
+
+    #[tokio::test]
+    async fn test_render_markdown() {
+        let mut grouped = sample_ticket(12, "Printer on fire");
+        grouped.ticket_group = Some("OPS".to_string());
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .group("ops-team", &["alice"])
+            .ticket(grouped)
+            .ticket(sample_ticket(7, "Scanner on fire"))
+            .build()
+            .await;
+
+        let html = render(&app, "**Ask** @alice or @ops-team, not @ghost").await;
+        assert!(html.starts_with("<p><strong>Ask</strong> "));
+        assert!(html.contains(r#"<a class="mention" href="/api/v1/search?q=alice" rel="noopener noreferrer">@alice</a>"#));
+        assert!(html.contains(">@ops-team</a>"));
+        assert!(html.contains(", not @ghost</p>"));
+
+        let html = render(&app, "Same as #12, OPS-12 and #7, not WEB-12 or #8").await;
+        assert_eq!(html.matches(r#"href="/api/v1/tickets/12""#).count(), 2);
+        assert!(html.contains(r#"href="/api/v1/tickets/7""#));
+        assert!(html.contains("not WEB-12 or #8"));
+
+        let html = render(&app, "<script>alert(1)</script>\n\n<a href=\"javascript:alert(1)\" onclick=\"x\">#7</a>").await;
+        assert_eq!(html.trim(), "<p><a rel=\"noopener noreferrer\">#7</a></p>");
+
+        app.post_as("alice", "/api/v1/render/markdown")
+            .json(&json!({ "markdown": "x".repeat(256 * 1024 + 1) }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
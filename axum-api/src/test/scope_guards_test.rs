@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        models::{AccessControlList, Permissions, ScopeAccess, Ticket, TokenScope},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// Every mutating route behind `jwt_auth_middleware` of both API versions, with the
+    /// scope it must be guarded with. Routes added to `user_routes` belong here.
+    const MUTATING_ROUTES: &[(&str, &str, &str)] = &[
+        ("DELETE", "/me/sessions/s1", "account:write"),
+        ("POST", "/me/logout-all", "account:write"),
+        ("POST", "/me/notifications/read-all", "account:write"),
+        ("POST", "/me/notifications/n1/read", "account:write"),
+        ("PUT", "/me/drafts/d1", "tickets:write"),
+        ("DELETE", "/me/drafts/d1", "tickets:write"),
+        ("PUT", "/me/preferences/ui", "account:write"),
+        ("DELETE", "/me/preferences/ui", "account:write"),
+        ("PUT", "/me/timezone", "account:write"),
+        ("POST", "/me/calendar-token", "account:write"),
+        ("DELETE", "/me/calendar-token", "account:write"),
+        ("POST", "/me/2fa/enroll", "account:write"),
+        ("POST", "/me/2fa/confirm", "account:write"),
+        ("POST", "/tickets", "tickets:write"),
+        // Only reads, guarded with the read scope on purpose
+        ("POST", "/tickets/check-duplicates", "tickets:read"),
+        ("POST", "/tickets/1/move", "tickets:write"),
+        ("POST", "/tickets/1/comments", "tickets:write"),
+        ("POST", "/tickets/1/seen", "tickets:write"),
+        ("PUT", "/tickets/1/milestone", "tickets:write"),
+        ("POST", "/tickets/1/share", "tickets:write"),
+        ("DELETE", "/tickets/1/shares/s1", "tickets:write"),
+        ("PUT", "/projects/p1/custom-fields", "projects:write"),
+        ("PUT", "/projects/p1/assignment-rules", "projects:write"),
+        ("POST", "/projects/p1/assignment-rules/dry-run", "projects:read"),
+        ("PUT", "/projects/p1/escalation-policies", "projects:write"),
+        ("PUT", "/projects/p1/portal", "projects:write"),
+        ("DELETE", "/projects/p1/portal", "projects:write"),
+        ("PUT", "/projects/p1/acl", "projects:write"),
+        ("POST", "/projects/p1/acl/grant", "projects:write"),
+        ("POST", "/projects/p1/acl/revoke", "projects:write"),
+        ("POST", "/projects/p1/clone", "projects:write"),
+        ("PUT", "/projects/p1/parent", "projects:write"),
+        ("POST", "/projects/p1/transfer-ownership", "projects:write"),
+        ("POST", "/projects/p1/milestones", "milestones:write"),
+        ("POST", "/milestones/m1/close", "milestones:write"),
+        ("POST", "/render/markdown", "tickets:write"),
+    ];
+
+    /// Mutating routes of one API version only.
+    const VERSIONED_ROUTES: &[(&str, &str, &str)] = &[
+        ("POST", "/api/v1/me/sessions/revoke-all", "account:write"),
+        ("DELETE", "/api/v2/me/sessions", "account:write"),
+    ];
+
+    #[tokio::test]
+    async fn test_mutating_routes_are_guarded() {
+        let app = TestApp::builder().build().await;
+        app.post_mgmt("/api/mgmt/service-accounts")
+            .json(&json!({"username": "ci_bot"}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        // Allowed nothing the routes need, each must turn it away before its handler runs
+        let token = app
+            .post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": "unrelated", "scopes": ["graphql:read"]}))
+            .await
+            .json::<ApiResponse<CreateApiTokenResponse>>()
+            .data
+            .token;
+
+        let graphql: &[(&str, &str, &str)] = if cfg!(feature = "graphql") {
+            &[("POST", "/api/graphql", "graphql:write")]
+        } else {
+            &[]
+        };
+        let routes: Vec<(&str, String, &str)> = ["/api/v1", "/api/v2"]
+            .iter()
+            .flat_map(|version| {
+                MUTATING_ROUTES.iter().map(move |(m, path, scope)| (*m, format!("{}{}", version, path), *scope))
+            })
+            .chain(VERSIONED_ROUTES.iter().chain(graphql).map(|(m, path, scope)| (*m, path.to_string(), *scope)))
+            .collect();
+
+        for (method, path, scope) in routes {
+            let request = match method {
+                "POST" => app.server.post(&path),
+                "PUT" => app.server.put(&path),
+                "DELETE" => app.server.delete(&path),
+                other => unreachable!("{}", other),
+            };
+            let response = request.authorization_bearer(&token).json(&json!({})).await;
+            response.assert_status(StatusCode::UNAUTHORIZED);
+            let expected = format!("Token lacks the {} scope", scope);
+            assert!(response.text().contains(&expected), "{} {} is not guarded with {}", method, path, scope);
+        }
+    }
+
+    #[test]
+    fn test_parse_scopes() {
+        let scope: TokenScope = "Tickets:Write".parse().unwrap();
+        assert_eq!(scope.to_string(), "tickets:write");
+        assert!(scope.allows("tickets", ScopeAccess::Read));
+        assert!(!scope.allows("projects", ScopeAccess::Read));
+        // A bare access is for every area
+        let scope: TokenScope = "read".parse().unwrap();
+        assert_eq!(scope.to_string(), "*:read");
+        assert!(scope.allows("projects", ScopeAccess::Read));
+        assert!(!scope.allows("projects", ScopeAccess::Write));
+
+        assert!("tickets:delete".parse::<TokenScope>().is_err());
+        assert!("billing:read".parse::<TokenScope>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_limited_to_its_areas() {
+        let mut project = sample_project(&["alice"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::ROOT,
+            principals: vec!["ci_bot".to_string()],
+        });
+        let project_id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .project(project)
+            .ticket(Ticket {
+                project: Some(project_id.clone()),
+                ..sample_ticket(1, "Flaky test")
+            })
+            .build()
+            .await;
+        app.post_mgmt("/api/mgmt/service-accounts")
+            .json(&json!({"username": "ci_bot"}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let token = app
+            .post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": "triage", "scopes": ["tickets:write", "projects:read"]}))
+            .await
+            .json::<ApiResponse<CreateApiTokenResponse>>()
+            .data
+            .token;
+        app.post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": "typo", "scopes": ["tickets:admin"]}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        app.server
+            .post("/api/v1/tickets/1/comments")
+            .authorization_bearer(&token)
+            .json(&json!({"body": "Quarantined"}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        app.server
+            .get(&format!("/api/v1/projects/{}/acl", project_id))
+            .authorization_bearer(&token)
+            .await
+            .assert_status_ok();
+        // ROOT on the project, but the token only reads it and knows no milestones
+        app.server
+            .post(&format!("/api/v1/projects/{}/acl/grant", project_id))
+            .authorization_bearer(&token)
+            .json(&json!({"principal": "bob", "permissions": "READ"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.server
+            .get(&format!("/api/v1/projects/{}/milestones", project_id))
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.server
+            .get("/api/v2/me/notifications")
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        // Sessions are only limited by ACLs
+        app.get_as("alice", &format!("/api/v1/projects/{}/milestones", project_id))
+            .await
+            .assert_status_ok();
+    }
+}
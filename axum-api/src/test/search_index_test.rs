@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        config::SearchBackend,
+        search::{EmbeddedIndex, SearchDocument, SearchIndex, SearchKind},
+        models::{Permissions, Project, Ticket},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn ticket_in(id: i64, title: &str, project: &Project) -> Ticket {
+        let mut ticket = sample_ticket(id, title);
+        ticket.project = Some(project.id.to_string());
+        ticket
+    }
+
+    async fn setup() -> (TestApp, Project) {
+        let mut ours = sample_project(&[]);
+        ours.acl.set_permissions("alice", Permissions::WRITE);
+        let theirs = sample_project(&["carol"]);
+        let app = TestApp::builder()
+            .config(|c| c.search_backend = SearchBackend::Embedded)
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("carol"))
+            .group("ops-team", &["alice"])
+            .group("ops-secret", &["carol"])
+            .project(ours.clone())
+            .project(theirs.clone())
+            .ticket(ticket_in(1, "Ops outage", &ours))
+            .ticket(ticket_in(2, "Ops outage", &theirs))
+            .build()
+            .await;
+        (app, ours)
+    }
+
+    async fn search(app: &TestApp, username: &str, query: &str) -> SearchResults {
+        let response = app.get_as(username, &format!("/api/v1/search?q={}", query)).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<SearchResults>>().data
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_index_is_rebuilt_and_kept_current() {
+        let (app, ours) = setup().await;
+
+        // Empty until the first rebuild, which the scheduler runs at startup
+        assert!(search(&app, "alice", "ops").await.tickets.is_empty());
+        assert_eq!(app.state.controller.search.rebuild_index().await.unwrap(), 8);
+        let results = search(&app, "alice", "ops").await;
+        assert_eq!(ids(&results.tickets), vec!["1"]);
+        assert_eq!(ids(&results.groups), vec!["ops-team"]);
+
+        // Created tickets are indexed from their event
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&json!({
+                "title": "Ops backup failed",
+                "severity": 2,
+                "severity_label": "major",
+                "project": ours.id.to_string(),
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        assert_eq!(search(&app, "alice", "backup").await.tickets.len(), 1);
+
+        // Projects at the next rebuild
+        let project = sample_project(&["alice"]);
+        let id = project.id.to_string();
+        app.state.db.projects().create_project(project).await.unwrap();
+        assert!(search(&app, "alice", &id).await.projects.is_empty());
+        app.state.controller.search.rebuild_index().await.unwrap();
+        assert_eq!(ids(&search(&app, "alice", &id).await.projects), vec![id.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_index_hits_are_checked_against_the_database() {
+        let (app, _) = setup().await;
+        app.state.controller.search.rebuild_index().await.unwrap();
+
+        assert_eq!(ids(&search(&app, "carol", "ops").await.tickets), vec!["2"]);
+        assert_eq!(ids(&search(&app, "carol", "ops").await.groups), vec!["ops-secret"]);
+        assert!(search(&app, "alice", "carol").await.users.is_empty());
+
+        // Deleted since the rebuild
+        app.state.db.tickets().delete_ticket("1", false).await.unwrap();
+        assert!(search(&app, "alice", "ops").await.tickets.is_empty());
+    }
+
+    fn document(id: &str, title: &str, body: &str) -> SearchDocument {
+        SearchDocument {
+            kind: SearchKind::Ticket,
+            id: id.to_string(),
+            keywords: id.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedded_index_ranks_and_replaces() {
+        let index = EmbeddedIndex::new();
+        index
+            .replace_all(vec![
+                document("1", "Printer offline", "Paper jam on the third floor"),
+                document("2", "Paper jam", "The printer is stuck"),
+                document("3", "VPN down", "Nothing to do with printers"),
+            ])
+            .await
+            .unwrap();
+        let hits = |query: &'static str| {
+            let index = &index;
+            async move {
+                let hits = index.query(query, 10).await.unwrap();
+                hits.into_iter().map(|h| h.id).collect::<Vec<_>>()
+            }
+        };
+
+        // Every word must match, in the title above the body
+        assert_eq!(hits("printer jam").await, vec!["2", "1"]);
+        // Words match as prefixes too
+        assert_eq!(hits("print").await.len(), 3);
+        assert!(hits("printer wifi").await.is_empty());
+
+        index.upsert(vec![document("3", "VPN up", "")]).await.unwrap();
+        assert!(hits("down").await.is_empty());
+        assert_eq!(hits("vpn").await, vec!["3"]);
+        assert_eq!(index.query("print", 1).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use crate::{
+        models::{Project, Ticket},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn ticket_in(id: i64, title: &str, project: Option<&Project>) -> Ticket {
+        let mut ticket = sample_ticket(id, title);
+        ticket.project = project.map(|p| p.id.to_string());
+        ticket
+    }
+
+    async fn setup() -> (TestApp, Project, Project) {
+        let ours = sample_project(&["alice"]);
+        let theirs = sample_project(&["carol"]);
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .group("ops-team", &["alice", "bob"])
+            .group("ops-secret", &["carol"])
+            .project(ours.clone())
+            .project(theirs.clone())
+            .ticket(ticket_in(1, "Ops outage", Some(&ours)))
+            .ticket(ticket_in(2, "Ops outage", Some(&theirs)))
+            // Of no project, for their creator and assignees only
+            .ticket(Ticket {
+                created_by: "carol".to_string(),
+                assigned_to: "ops-team".to_string(),
+                ..ticket_in(3, "Ops printer", None)
+            })
+            .ticket(Ticket {
+                assigned_to: "ops-team".to_string(),
+                ..ticket_in(4, "Printer jam", None)
+            })
+            .build()
+            .await;
+        (app, ours, theirs)
+    }
+
+    async fn search(app: &TestApp, username: &str, query: &str) -> SearchResults {
+        let response = app.get_as(username, &format!("/api/v1/search?q={}", query)).await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<SearchResults>>().data
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_search_groups_results_by_kind() {
+        let (app, _, _) = setup().await;
+
+        let results = search(&app, "alice", "ops").await;
+        assert_eq!(ids(&results.tickets), vec!["1", "3"]);
+        assert_eq!(results.tickets[0].title, "Ops outage");
+        assert_eq!(ids(&results.groups), vec!["ops-team"]);
+        assert!(results.users.is_empty() && results.projects.is_empty());
+
+        // Every word has to match, whole words rank above prefixes
+        assert_eq!(ids(&search(&app, "alice", "ops+printer").await.tickets), vec!["3"]);
+        let results = search(&app, "alice", "print").await;
+        assert_eq!(ids(&results.tickets), vec!["3", "4"]);
+        assert!(search(&app, "alice", "printer").await.tickets[0].score > results.tickets[0].score);
+
+        let response = app.get_as("alice", "/api/v1/search?q=ops&limit=1").await;
+        assert_eq!(ids(&response.json::<ApiResponse<SearchResults>>().data.tickets), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_leaves_out_what_the_caller_cant_see() {
+        let (app, ours, theirs) = setup().await;
+
+        assert_eq!(ids(&search(&app, "alice", "bob").await.users), vec!["bob"]);
+        assert!(search(&app, "alice", "carol").await.users.is_empty());
+        assert_eq!(ids(&search(&app, "carol", "ops").await.tickets), vec!["2", "3"]);
+        assert_eq!(ids(&search(&app, "carol", "ops").await.groups), vec!["ops-secret"]);
+
+        let id = ours.id.to_string();
+        assert_eq!(ids(&search(&app, "alice", &id).await.projects), vec![id.as_str()]);
+        assert!(search(&app, "alice", &theirs.id.to_string()).await.projects.is_empty());
+
+        // Soft-deleted tickets aren't found
+        app.state.db.tickets().delete_ticket("1", false).await.unwrap();
+        assert_eq!(ids(&search(&app, "alice", "ops").await.tickets), vec!["3"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_requires_a_query() {
+        let (app, _, _) = setup().await;
+
+        app.get_as("alice", "/api/v1/search?q=%20")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.server.get("/api/v1/search?q=ops").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, http::HeaderMap, http::StatusCode, routing::get};
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+
+    use crate::{
+        config::{AppConfig, read_secret_file},
+        error::AppError,
+        secrets::{InMemorySecretProvider, Secrets, VaultProvider},
+    };
+
+    // Answers like a Vault KV v2 engine mounted at `secret` holding `secret/tickets`
+    async fn vault() -> String {
+        async fn read(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+            if headers.get("x-vault-token").is_none_or(|token| token != "root") {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            Ok(Json(json!({ "data": { "data": { "jwt": "from-vault", "mgmt": "vault-mgmt" }, "metadata": {} } })))
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/v1/secret/data/tickets", get(read));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        address
+    }
+
+    fn vault_secrets(address: Option<String>, token: &str) -> Secrets {
+        Secrets::new(vec![Box::new(VaultProvider::new(address, token.to_string()))])
+    }
+
+    #[tokio::test]
+    async fn test_config_secrets_from_vault() {
+        let address = vault().await;
+        let mut config = AppConfig::from_env().unwrap();
+        config.jwt_secret = "vault:secret/tickets#jwt".to_string();
+        config.management_token = "vault:secret/tickets#mgmt".to_string();
+        config.client_api_keys = vec!["inline-key".to_string()];
+
+        vault_secrets(Some(format!("{}/", address)), "root")
+            .resolve_config(&mut config)
+            .await
+            .unwrap();
+        assert_eq!(config.jwt_secret, "from-vault");
+        assert_eq!(config.management_token, "vault-mgmt");
+        assert_eq!(config.client_api_keys, ["inline-key"]);
+
+        let secrets = vault_secrets(Some(address.clone()), "root");
+        assert!(secrets.resolve("vault:secret/tickets#missing").await.is_err());
+        assert!(secrets.resolve("vault:secret/other#jwt").await.is_err());
+        assert!(matches!(secrets.resolve("vault:tickets").await, Err(AppError::Validation(_))));
+        assert!(vault_secrets(Some(address), "wrong").resolve("vault:secret/tickets#jwt").await.is_err());
+        // Never taken literally
+        assert!(vault_secrets(None, "").resolve("vault:secret/tickets#jwt").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_providers() {
+        let provider = InMemorySecretProvider::new("mem");
+        provider.insert("jwt", "from-memory");
+        let secrets = Secrets::new(vec![Box::new(provider)]);
+
+        assert_eq!(secrets.resolve("mem:jwt").await.unwrap(), "from-memory");
+        assert!(matches!(secrets.resolve("mem:other").await, Err(AppError::NotFound(_))));
+        // Values of other schemes are plain values
+        assert_eq!(secrets.resolve("http://localhost:8529").await.unwrap(), "http://localhost:8529");
+        assert_eq!(secrets.resolve("plain").await.unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_secret_files() {
+        let path = std::env::temp_dir().join(format!("secret-{}", uuid::Uuid::now_v7()));
+        std::fs::write(&path, "s3cr3t value\n").unwrap();
+        assert_eq!(read_secret_file(path.to_str().unwrap()).unwrap(), "s3cr3t value");
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(read_secret_file(path.to_str().unwrap()), Err(AppError::Validation(_))));
+    }
+}
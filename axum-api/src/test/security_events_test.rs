@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+
+    use crate::{
+        create_app, create_mock_shared_state,
+        models::{SecurityEvent, SecurityEventKind},
+        schema::*,
+    };
+
+    #[tokio::test]
+    async fn test_failed_logins_are_recorded() {
+        let mut state = create_mock_shared_state().unwrap();
+        Arc::make_mut(&mut state.config).trusted_proxies = vec!["127.0.0.1/32".parse().unwrap()];
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: "secuser".to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        for _ in 0..3 {
+            server
+                .post("/api/login")
+                .add_header("X-Forwarded-For", "10.0.0.7")
+                .json(&LoginRequest {
+                    user: "secuser".to_string(),
+                    password: "wrongpassword".to_string(),
+                    remember_me: false,
+                })
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer("not-a-jwt")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let events = server
+            .get("/api/mgmt/security-events")
+            .add_query_param("kind", "login_failure")
+            .add_query_param("ip", "10.0.0.7")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data
+            .items;
+        assert_eq!(events.len(), 3);
+        assert!(
+            events
+                .iter()
+                .all(|e| e.username.as_deref() == Some("secuser"))
+        );
+
+        let events = server
+            .get("/api/mgmt/security-events")
+            .add_query_param("kind", "token_validation_failure")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data
+            .items;
+        assert_eq!(events.len(), 1);
+
+        let page = server
+            .get("/api/mgmt/security-events")
+            .add_query_param("limit", "3")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data;
+        assert_eq!(page.items.len(), 3);
+        assert_eq!(page.total, 4);
+        assert_eq!(page.items[0].kind, SecurityEventKind::TokenValidationFailure);
+
+        let next_page = server
+            .get("/api/mgmt/security-events")
+            .add_query_param("limit", "3")
+            .add_query_param("cursor", page.next_cursor.unwrap())
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data;
+        assert_eq!(next_page.items.len(), 1);
+        assert!(next_page.next_cursor.is_none());
+        assert!(page.items.iter().all(|e| e.id != next_page.items[0].id));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_mgmt_token_is_recorded() {
+        let state = create_mock_shared_state().unwrap();
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        server
+            .get("/api/mgmt/security-events")
+            .authorization_bearer("guessed-token")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let events = server
+            .get("/api/mgmt/security-events")
+            .add_query_param("kind", "api_key_misuse")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .json::<ApiResponse<ListResponse<SecurityEvent>>>()
+            .data
+            .items;
+        assert_eq!(events.len(), 1);
+    }
+}
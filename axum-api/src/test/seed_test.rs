@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        schema::*,
+        seed::{self, Fixtures, SeedReport},
+        test::app::TestApp,
+    };
+
+    const FIXTURES: &str = r#"
+users:
+  - username: alice
+    password: alicepassword123
+    email: Alice@Example.com
+    verified: true
+  - username: bob
+    password: bobpassword123
+groups:
+  - gid: support
+    principals: [alice, bob]
+projects:
+  - id: 0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b
+    owner: alice
+    acl:
+      - permissions: READ | CREATE
+        principals: [support]
+tickets:
+  - created_by: alice
+    title: Checkout fails on Safari
+    severity: 2
+    assigned_to: bob
+    project: 0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b
+  - created_by: bob
+    title: Typo on the pricing page
+    severity: 4
+"#;
+
+    #[tokio::test]
+    async fn test_load_fixtures() {
+        let app = TestApp::builder().build().await;
+
+        let report = seed::load(&app.state, Fixtures::parse(FIXTURES).unwrap()).await.unwrap();
+        assert_eq!(
+            report,
+            SeedReport {
+                users: 2,
+                groups: 1,
+                projects: 1,
+                tickets: 2,
+                skipped: 0,
+            }
+        );
+
+        // Passwords were hashed, the email normalized
+        let alice = app.state.db.users().get_user("alice").await.unwrap();
+        assert_ne!(alice.password_hash, "alicepassword123");
+        assert_eq!(alice.email.as_deref(), Some("alice@example.com"));
+        assert!(alice.verified);
+        let token = app.login("bob", "bobpassword123").await.token;
+        let response = app
+            .server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&token)
+            .await;
+        response.assert_status_ok();
+        let ticket = response.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!((ticket.title.as_str(), ticket.severity_label.as_str()), ("Checkout fails on Safari", "major"));
+
+        // Loading again changes nothing
+        let report = seed::load(&app.state, Fixtures::parse(FIXTURES).unwrap()).await.unwrap();
+        assert_eq!(
+            report,
+            SeedReport {
+                skipped: 6,
+                ..SeedReport::default()
+            }
+        );
+        assert_eq!(app.state.db.tickets().list_tickets().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_fixtures_are_rejected() {
+        let app = TestApp::builder().build().await;
+        let seed = |body: serde_json::Value| app.post_mgmt("/api/mgmt/seed").json(&body);
+
+        let response = seed(json!({ "users": [{ "username": "not valid!", "password": "securepassword123" }] })).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("User not valid!"));
+
+        let response = seed(json!({ "groups": [{ "gid": "support", "principals": ["nobody"] }] })).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("Principal nobody not found"));
+
+        let response = seed(json!({
+            "users": [{ "username": "carol", "password": "securepassword123" }],
+            "tickets": [{ "created_by": "carol", "title": "Lost", "severity": 2, "project": "missing" }],
+        }))
+        .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("Project missing not found"));
+
+        assert!(Fixtures::parse("users: 3").is_err());
+        app.server
+            .post("/api/mgmt/seed")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        middleware::rate_limit::parse_rules,
+        models::{AccessControlList, Comment, Permissions, Ticket},
+        schema::*,
+        test::app::{TestApp, TestAppBuilder, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// A project alice reads and ci_bot may write to, with ticket 1 in it.
+    fn builder() -> TestAppBuilder {
+        let mut project = sample_project(&["alice"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["ci_bot".to_string()],
+        });
+        let ticket = Ticket {
+            project: Some(project.id.to_string()),
+            ..sample_ticket(1, "Nightly build fails")
+        };
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .project(project)
+            .ticket(ticket)
+    }
+
+    async fn create_account(app: &TestApp) {
+        app.post_mgmt("/api/mgmt/service-accounts")
+            .json(&json!({"username": "ci_bot", "name": "CI"}))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    async fn issue(app: &TestApp, name: &str, scopes: &[&str]) -> CreateApiTokenResponse {
+        app.post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": name, "scopes": scopes}))
+            .await
+            .json::<ApiResponse<CreateApiTokenResponse>>()
+            .data
+    }
+
+    #[tokio::test]
+    async fn test_token_scopes() {
+        let app = builder().build().await;
+        create_account(&app).await;
+        let read = issue(&app, "nightly", &["read"]).await;
+        let write = issue(&app, "release", &["write"]).await;
+        let comment = json!({"body": "Build 42 is green again"});
+
+        app.server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&read.token)
+            .await
+            .assert_status_ok();
+        app.server
+            .post("/api/v1/tickets/1/comments")
+            .authorization_bearer(&read.token)
+            .json(&comment)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        let posted = app
+            .server
+            .post("/api/v1/tickets/1/comments")
+            .authorization_bearer(&write.token)
+            .json(&comment)
+            .await
+            .json::<ApiResponse<Comment>>()
+            .data;
+        assert_eq!(posted.author, "ci_bot");
+
+        // Revoked tokens and unknown ones are rejected, the other token keeps working
+        app.delete_mgmt(&format!("/api/mgmt/service-accounts/ci_bot/tokens/{}", write.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        for token in [write.token.as_str(), "sat_unknown"] {
+            app.server
+                .get("/api/v1/tickets/1")
+                .authorization_bearer(token)
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+        app.server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&read.token)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_account_management() {
+        let app = builder().build().await;
+        create_account(&app).await;
+        let token = issue(&app, "nightly", &["read", "read"]).await;
+        assert_eq!(token.scopes.len(), 1);
+
+        app.post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": "nightly", "scopes": ["write"]}))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        app.post_mgmt("/api/mgmt/service-accounts/ci_bot/tokens")
+            .json(&json!({"name": "empty", "scopes": []}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        // Only service accounts get tokens
+        app.post_mgmt("/api/mgmt/service-accounts/alice/tokens")
+            .json(&json!({"name": "mine", "scopes": ["read"]}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        let accounts = app
+            .get_mgmt("/api/mgmt/service-accounts")
+            .await
+            .json::<ApiResponse<Vec<ServiceAccountResponse>>>()
+            .data;
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "CI");
+        assert_eq!(accounts[0].tokens[0].name, "nightly");
+
+        // No password to log in with
+        app.server
+            .post("/api/login")
+            .json(&json!({"user": "ci_bot", "password": ""}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        app.delete_mgmt("/api/mgmt/service-accounts/ci_bot")
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        app.server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&token.token)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_left_out_of_user_listings() {
+        let app = builder().build().await;
+        create_account(&app).await;
+
+        // Every user has `registered_at`
+        let users = app
+            .get_mgmt("/api/mgmt/users?key=registered_at")
+            .await
+            .json::<ApiResponse<Vec<UserMetadataResponse>>>()
+            .data;
+        let names: Vec<&str> = users.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(names, vec!["alice"]);
+        let users = app
+            .get_mgmt("/api/mgmt/users?key=registered_at&service_accounts=true")
+            .await
+            .json::<ApiResponse<Vec<UserMetadataResponse>>>()
+            .data;
+        let names: Vec<&str> = users.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(names, vec!["alice", "ci_bot"]);
+    }
+
+    #[tokio::test]
+    async fn test_not_rate_limited() {
+        let app = builder()
+            .config(|config| config.rate_limits = parse_rules("GET /api/v1/*: 1/min/ip").unwrap())
+            .build()
+            .await;
+        create_account(&app).await;
+        let token = issue(&app, "nightly", &["read"]).await;
+
+        for _ in 0..3 {
+            app.server
+                .get("/api/v1/tickets/1")
+                .authorization_bearer(&token.token)
+                .await
+                .assert_status_ok();
+        }
+        app.get_as("alice", "/api/v1/tickets/1").await.assert_status_ok();
+        app.get_as("alice", "/api/v1/tickets/1")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+}
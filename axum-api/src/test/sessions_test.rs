@@ -0,0 +1,360 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+
+    use crate::{create_app, create_mock_shared_state, schema::*};
+
+    async fn register_and_login(server: &TestServer, user: &str, agents: &[&str]) -> Vec<String> {
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: user.to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let mut tokens = Vec::new();
+        for agent in agents {
+            let response = server
+                .post("/api/login")
+                .add_header("User-Agent", *agent)
+                .json(&LoginRequest {
+                    user: user.to_string(),
+                    password: "securepassword123".to_string(),
+                    remember_me: false,
+                })
+                .await;
+            response.assert_status_ok();
+            tokens.push(response.json::<ApiResponse<LoginResponse>>().data.token);
+        }
+        tokens
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_marks_current() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "sessionuser", &["laptop", "phone"]).await;
+
+        let response = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[1])
+            .await;
+        response.assert_status_ok();
+
+        let sessions = response
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        assert_eq!(sessions.len(), 2);
+        let current: Vec<_> = sessions.iter().filter(|s| s.current).collect();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].user_agent.as_deref(), Some("phone"));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session_logs_out_device() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "revokeuser", &["laptop", "phone"]).await;
+
+        let sessions = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[0])
+            .await
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        let phone = sessions.iter().find(|s| !s.current).unwrap();
+
+        server
+            .delete(&format!("/api/v1/me/sessions/{}", phone.id))
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        // The revoked device is no longer authorized, the current one still is
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[1])
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_keeps_current_session() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens =
+            register_and_login(&server, "revokealluser", &["laptop", "phone", "tablet"]).await;
+
+        let response = server
+            .post("/api/v1/me/sessions/revoke-all")
+            .authorization_bearer(&tokens[2])
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<ApiResponse<RevokedSessions>>().data.revoked,
+            2
+        );
+
+        for token in &tokens[..2] {
+            server
+                .get("/api/v1/me/sessions")
+                .authorization_bearer(token)
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+
+        let sessions = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[2])
+            .await
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].current);
+    }
+
+    #[tokio::test]
+    async fn test_remember_me_lengthens_session() {
+        let state = create_mock_shared_state().unwrap();
+        let config = state.config.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "rememberuser", &["laptop"]).await;
+        let remembered = server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: "rememberuser".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: true,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data;
+
+        let sessions = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&remembered.token)
+            .await
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        let current = sessions.iter().find(|s| s.current).unwrap();
+        let other = sessions.iter().find(|s| !s.current).unwrap();
+        let current_lifetime = (current.expires_at - current.created_at).num_seconds() as usize;
+        let other_lifetime = (other.expires_at - other.created_at).num_seconds() as usize;
+        assert!(current_lifetime + 1 >= config.remember_me_lifetime);
+        assert!(other_lifetime <= config.refresh_token_lifetime);
+
+        let claims = crate::middleware::auth::Auth::new(config.jwt_secret.as_bytes())
+            .decode_token(&tokens[0])
+            .unwrap();
+        assert!(claims.iat > 0 && claims.nbf == claims.iat);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_follows_session() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        register_and_login(&server, "refreshuser", &[]).await;
+        let login = server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: "refreshuser".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data;
+
+        // The refresh token is not an access token
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&login.refresh_token)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/api/refresh")
+            .json(&RefreshRequest {
+                refresh_token: login.refresh_token.clone(),
+            })
+            .await;
+        response.assert_status_ok();
+        let refreshed = response.json::<ApiResponse<LoginResponse>>().data;
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&refreshed.token)
+            .await
+            .assert_status_ok();
+
+        // Revoking the session also kills its refresh token
+        let sessions = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&refreshed.token)
+            .await
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        server
+            .delete(&format!("/api/v1/me/sessions/{}", sessions[0].id))
+            .authorization_bearer(&refreshed.token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .post("/api/refresh")
+            .json(&RefreshRequest {
+                refresh_token: login.refresh_token,
+            })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_logout_all_invalidates_every_token() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "logoutalluser", &["laptop", "phone"]).await;
+        let refresh_token = server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: "logoutalluser".to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data
+            .refresh_token;
+
+        server
+            .post("/api/v1/me/logout-all")
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        for token in &tokens {
+            server
+                .get("/api/v1/me/sessions")
+                .authorization_bearer(token)
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+        server
+            .post("/api/refresh")
+            .json(&RefreshRequest { refresh_token })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        // Fresh logins work again
+        let tokens = login_token(&server, "logoutalluser").await;
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_mgmt_logout_all() {
+        let state = create_mock_shared_state().unwrap();
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "mgmtlogoutuser", &["laptop"]).await;
+
+        server
+            .post("/api/mgmt/users/mgmtlogoutuser/logout-all")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/api/mgmt/users/nosuchuser/logout-all")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cached_validation_honours_revocation() {
+        let mut state = create_mock_shared_state().unwrap();
+        Arc::get_mut(&mut state.config).unwrap().auth_cache_ttl = 60;
+        let state = Arc::new(state);
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+
+        let tokens = register_and_login(&server, "cacheduser", &["laptop", "phone", "tablet"]).await;
+        let me = |token: &String| server.get("/api/v1/me/sessions").authorization_bearer(token);
+        for token in &tokens {
+            me(token).await.assert_status_ok();
+        }
+
+        // Removed behind the app's back: the session is trusted until its entry expires
+        let tablet = state.auth.decode_token(&tokens[2]).unwrap();
+        state.db.sessions().delete_session(&tablet.sid).await.unwrap();
+        me(&tokens[2]).await.assert_status_ok();
+
+        // Revoked through the app: rejected at once
+        let sessions = me(&tokens[0]).await.json::<ApiResponse<ListResponse<SessionInfo>>>().data.items;
+        let phone = sessions.iter().find(|s| s.user_agent.as_deref() == Some("phone")).unwrap();
+        server
+            .delete(&format!("/api/v1/me/sessions/{}", phone.id))
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        me(&tokens[1]).await.assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .post("/api/v1/me/logout-all")
+            .authorization_bearer(&tokens[0])
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        me(&tokens[0]).await.assert_status(StatusCode::UNAUTHORIZED);
+        me(&tokens[2]).await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    async fn login_token(server: &TestServer, user: &str) -> String {
+        server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: user.to_string(),
+                password: "securepassword123".to_string(),
+                remember_me: false,
+            })
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data
+            .token
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::{
+        models::{Permissions, Ticket, TicketShare},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// Alice can change ticket 1, bob can only read it. Ticket 2 is in another project.
+    async fn setup() -> TestApp {
+        let mut project = sample_project(&["bob"]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        let other = sample_project(&["carol"]);
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .ticket(Ticket {
+                project: Some(project.id.to_string()),
+                ..sample_ticket(1, "Printer on fire")
+            })
+            .ticket(Ticket {
+                project: Some(other.id.to_string()),
+                ..sample_ticket(2, "Secret")
+            })
+            .project(project)
+            .project(other)
+            .build()
+            .await
+    }
+
+    async fn share(app: &TestApp, body: serde_json::Value) -> TicketShareResponse {
+        let response = app.post_as("alice", "/api/v1/tickets/1/share").json(&body).await;
+        response.assert_status(StatusCode::CREATED);
+        response.json::<ApiResponse<TicketShareResponse>>().data
+    }
+
+    #[tokio::test]
+    async fn test_shared_ticket_is_readable_without_login() {
+        let app = setup().await;
+        let link = share(&app, json!({})).await;
+        assert_eq!(link.url, format!("/api/public/tickets/shared?token={}", link.token));
+        let hours = (link.expires_at - Utc::now()).num_minutes() as f64 / 60.0;
+        assert!((71.9..=72.0).contains(&hours), "expires in {} hours", hours);
+
+        let response = app.server.get(&link.url).await;
+        response.assert_status_ok();
+        let ticket = response.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!((ticket.id, ticket.title.as_str()), (1, "Printer on fire"));
+
+        let shares = app
+            .get_as("alice", "/api/v1/tickets/1/shares")
+            .await
+            .json::<ApiResponse<Vec<TicketShare>>>()
+            .data;
+        assert_eq!(shares.len(), 1);
+        assert_eq!((shares[0].id.as_str(), shares[0].created_by.as_str()), (link.id.as_str(), "alice"));
+    }
+
+    #[tokio::test]
+    async fn test_share_needs_modify() {
+        let app = setup().await;
+        app.post_as("bob", "/api/v1/tickets/1/share")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.get_as("bob", "/api/v1/tickets/1/shares").await.assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("alice", "/api/v1/tickets/2/share")
+            .json(&json!({}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        for hours in [0, 24 * 31] {
+            app.post_as("alice", "/api/v1/tickets/1/share")
+                .json(&json!({ "expires_in_hours": hours }))
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revoked_share_stops_working() {
+        let app = setup().await;
+        let link = share(&app, json!({ "expires_in_hours": 1 })).await;
+        let kept = share(&app, json!({})).await;
+
+        app.delete_as("bob", &format!("/api/v1/tickets/1/shares/{}", link.id))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.delete_as("alice", &format!("/api/v1/tickets/1/shares/{}", link.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        app.delete_as("alice", &format!("/api/v1/tickets/1/shares/{}", link.id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        app.server.get(&link.url).await.assert_status(StatusCode::UNAUTHORIZED);
+        app.server.get(&kept.url).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_forged_and_expired_links_are_refused() {
+        let app = setup().await;
+        let link = share(&app, json!({})).await;
+        for token in ["guess", &format!("{}.{}", link.id, "0".repeat(64)), &format!("other.{}", link.token)] {
+            app.server
+                .get(&format!("/api/public/tickets/shared?token={}", token))
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+
+        // A share past its expiry, before the database purged it
+        let (token, _) = app
+            .state
+            .controller
+            .share
+            .create(1, "alice", Duration::seconds(-1), app.state.config.jwt_secret.as_bytes())
+            .await
+            .unwrap();
+        app.server
+            .get(&format!("/api/public/tickets/shared?token={}", token))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use axum::http::StatusCode;
+
+    use crate::{
+        schema::*,
+        startup::{self, Startup, StartupPhase},
+        test::app::TestApp,
+    };
+
+    async fn phase(app: &TestApp, expected: StatusCode) -> String {
+        let response = app.server.get("/health/ready").await;
+        response.assert_status(expected);
+        response.json::<ApiResponse<HealthStatus>>().data.status
+    }
+
+    #[tokio::test]
+    async fn test_ready_once_started() {
+        let startup = Arc::new(Startup::default());
+        let shared = startup.clone();
+        let app = TestApp::builder().state(move |s| s.startup = shared).build().await;
+
+        assert_eq!(phase(&app, StatusCode::SERVICE_UNAVAILABLE).await, "starting");
+        // Liveness doesn't wait
+        app.server.get("/health").await.assert_status_ok();
+
+        startup.ready();
+        assert_eq!(phase(&app, StatusCode::OK).await, "ready");
+        // Never goes back
+        startup.failed();
+        assert_eq!(startup.phase(), StartupPhase::Ready);
+
+        let failed = Startup::default();
+        failed.failed();
+        failed.ready();
+        assert_eq!(failed.phase(), StartupPhase::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_retries_until_the_limit() {
+        let attempts = AtomicUsize::new(0);
+        let result = startup::wait_for("Database", Duration::from_secs(5), || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err("connection refused"),
+                n => Ok(n),
+            }
+        })
+        .await;
+        assert_eq!(result, Ok(2));
+
+        // Without a limit it is tried once
+        let attempts = AtomicUsize::new(0);
+        let result: Result<(), _> = startup::wait_for("Database", Duration::ZERO, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err("connection refused")
+        })
+        .await;
+        assert_eq!(result, Err("connection refused"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
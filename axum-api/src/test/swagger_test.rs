@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+
+    use crate::{config::SwaggerAccess, create_app, create_mock_shared_state};
+
+    fn server_with_access(access: SwaggerAccess) -> (TestServer, String) {
+        let mut state = create_mock_shared_state().unwrap();
+        let mut config = (*state.config).clone();
+        config.swagger_access = access;
+        let mgmt_token = config.management_token.clone();
+        state.config = Arc::new(config);
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        (server, mgmt_token)
+    }
+
+    #[tokio::test]
+    async fn test_api_docs_require_mgmt_token() {
+        let (server, mgmt_token) = server_with_access(SwaggerAccess::Management);
+
+        server
+            .get("/api-docs/openapi.json")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .get("/api-docs/openapi.json")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_api_docs_public_and_disabled() {
+        let (server, _) = server_with_access(SwaggerAccess::Public);
+        server.get("/api-docs/openapi.json").await.assert_status_ok();
+
+        let (server, mgmt_token) = server_with_access(SwaggerAccess::Disabled);
+        server
+            .get("/api-docs/openapi.json")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::{
+        models::{
+            AccessControlList, AccessControlStore, AclInheritance, Comment, CustomFieldDefinition, CustomFieldType,
+            Permissions, Severity, Ticket, TicketGroup,
+        },
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    fn grant(permissions: Permissions, principal: &str) -> AccessControlList {
+        AccessControlList {
+            permissions,
+            principals: vec![principal.to_string()],
+        }
+    }
+
+    /// alice and carol can change tickets of `web`, alice can file them in `ops` too,
+    /// carol only reads it and bob only reads `web`.
+    async fn setup() -> (TestApp, String, String) {
+        let mut web = sample_project(&["bob"]);
+        web.acl.list.push(grant(Permissions::WRITE, "alice"));
+        web.acl.list.push(grant(Permissions::WRITE, "carol"));
+        web.custom_fields.push(CustomFieldDefinition {
+            name: "browser".to_string(),
+            kind: CustomFieldType::Text,
+            required: false,
+            options: vec![],
+        });
+        let mut ops = sample_project(&["carol"]);
+        ops.acl.list.push(grant(Permissions::WRITE, "alice"));
+        ops.severities = vec![Severity::new(1, "sev1"), Severity::new(2, "sev2")];
+        ops.tickets.push(TicketGroup {
+            prefix: "OPS".to_string(),
+            acl: AccessControlStore {
+                list: vec![grant(Permissions::READ, "dave")],
+                deny: vec![],
+                last_mod_date: Utc::now(),
+            },
+            inheritance: AclInheritance::Extend,
+        });
+        let (web_id, ops_id) = (web.id.to_string(), ops.id.to_string());
+
+        let mut ticket = Ticket {
+            project: Some(web_id.clone()),
+            mentioned: vec!["alice".to_string(), "bob".to_string(), "dave".to_string()],
+            milestone: Some("web-1.0".to_string()),
+            ..sample_ticket(1, "Cache fills the disk")
+        };
+        ticket.custom_fields.insert("browser".to_string(), json!("firefox"));
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .user(UserFixture::new("dave"))
+            .project(web)
+            .project(ops)
+            .ticket(ticket)
+            .ticket(Ticket {
+                project: Some(ops_id.clone()),
+                ..sample_ticket(2, "Already in ops")
+            })
+            .build()
+            .await;
+        (app, web_id, ops_id)
+    }
+
+    #[tokio::test]
+    async fn test_move_to_another_project() {
+        let (app, web, ops) = setup().await;
+
+        let moved = app
+            .post_as("alice", "/api/v1/tickets/1/move")
+            .json(&json!({"status": "open", "position": 0, "project": ops, "ticket_group": "OPS"}))
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(moved.id, 1);
+        assert_eq!(moved.project.as_deref(), Some(ops.as_str()));
+        assert_eq!(moved.ticket_group.as_deref(), Some("OPS"));
+        assert_eq!(moved.severity_label, "sev2");
+        // What belongs to the old project stays there
+        assert!(moved.custom_fields.is_empty());
+        assert_eq!(moved.milestone, None);
+        // bob can't see the ticket any more, dave can through the ticket group
+        assert_eq!(moved.mentioned, vec!["alice", "dave"]);
+
+        let board = app
+            .get_as("alice", &format!("/api/v1/projects/{}/board", ops))
+            .await
+            .json::<ApiResponse<BoardResponse>>()
+            .data;
+        let open: Vec<i64> = board.columns[0].tickets.iter().map(|t| t.id).collect();
+        assert_eq!(open, vec![1, 2]);
+
+        let comments = app
+            .get_as("alice", "/api/v1/tickets/1/comments")
+            .await
+            .json::<ApiResponse<Vec<Comment>>>()
+            .data;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author, "alice");
+        assert_eq!(
+            comments[0].body,
+            format!("Moved from project {} to ticket group OPS of project {}", web, ops)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_needs_modify_and_create() {
+        let (app, _, ops) = setup().await;
+        let to_ops = json!({"status": "open", "position": 0, "project": ops});
+
+        // bob reads the ticket only, carol can't file tickets in ops
+        app.post_as("bob", "/api/v1/tickets/1/move")
+            .json(&to_ops)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.post_as("carol", "/api/v1/tickets/1/move")
+            .json(&to_ops)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        // Nor can she move it back out
+        app.post_as("carol", "/api/v1/tickets/2/move")
+            .json(&json!({"status": "open", "position": 0, "ticket_group": "OPS"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let ticket = app.state.db.tickets().get_ticket("1").await.unwrap();
+        assert_ne!(ticket.project.as_deref(), Some(ops.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_move_destination_must_exist() {
+        let (app, web, ops) = setup().await;
+
+        for destination in [
+            json!({"status": "open", "position": 0, "project": ops, "ticket_group": "DEV"}),
+            json!({"status": "open", "position": 0, "project": "nowhere"}),
+            json!({"status": "open", "position": 0, "project": web}),
+        ] {
+            app.post_as("alice", "/api/v1/tickets/1/move")
+                .json(&destination)
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+        // A board move leaves the ticket where it is and records nothing
+        app.post_as("alice", "/api/v1/tickets/1/move")
+            .json(&json!({"status": "in_progress", "position": 0}))
+            .await
+            .assert_status_ok();
+        assert!(app.state.db.comments().list_comments(1).await.unwrap().is_empty());
+    }
+}
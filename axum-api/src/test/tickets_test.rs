@@ -0,0 +1,421 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::NaiveDate;
+    use serde_json::{Value, json};
+
+    use axum_test::TestServer;
+    use std::sync::Arc;
+
+    use crate::{
+        db::{
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+            versioned,
+        },
+        models::{Permissions, Project, Severity, Ticket, TicketStatus},
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn setup() -> (TestServer, String) {
+        let app = TestApp::builder()
+            .user(UserFixture::new("ticketuser"))
+            .group("support", &["ticketuser"])
+            .ticket(sample_ticket(1, "Login page broken"))
+            .ticket(sample_ticket(2, "Typo in footer"))
+            .build()
+            .await;
+        let token = app.token("ticketuser").to_string();
+        (app.server, token)
+    }
+
+    #[tokio::test]
+    async fn test_get_ticket_full() {
+        let (server, token) = setup().await;
+
+        let ticket = server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(ticket.title, "Login page broken");
+        assert_eq!(ticket.severity, 2);
+        assert_eq!(ticket.severity_label, "major");
+    }
+
+    #[tokio::test]
+    async fn test_fields_selection() {
+        let (server, token) = setup().await;
+
+        let ticket = server
+            .get("/api/v1/tickets/2")
+            .add_query_param("fields", "title,severity_label")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<Value>>()
+            .data;
+        assert_eq!(
+            ticket,
+            serde_json::json!({"title": "Typo in footer", "severity_label": "major"})
+        );
+
+        let tickets = server
+            .get("/api/v2/tickets")
+            .add_query_param("fields", "id,assigned_to")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<ListResponse<Value>>>()
+            .data
+            .items;
+        assert_eq!(tickets.len(), 2);
+        for ticket in tickets {
+            let keys: Vec<_> = ticket.as_object().unwrap().keys().cloned().collect();
+            assert_eq!(keys.len(), 2);
+            assert_eq!(ticket["assigned_to"], "support");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_field_is_rejected() {
+        let (server, token) = setup().await;
+
+        server
+            .get("/api/v1/tickets")
+            .add_query_param("fields", "title,password_hash")
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .get("/api/v1/tickets/404")
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_sorted_listing() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("ticketuser"))
+            .group("support", &["ticketuser"])
+            .ticket(Ticket {
+                status: TicketStatus::Resolved,
+                due_date: NaiveDate::from_ymd_opt(2026, 5, 1),
+                ..sample_ticket(1, "Login page broken")
+            })
+            .ticket(Ticket {
+                severity: Severity::new(1, "critical"),
+                due_date: NaiveDate::from_ymd_opt(2026, 4, 1),
+                ..sample_ticket(2, "Typo in footer")
+            })
+            .ticket(Ticket {
+                status: TicketStatus::InProgress,
+                severity: Severity::new(4, "trivial"),
+                ..sample_ticket(3, "Slow search")
+            })
+            .build()
+            .await;
+        let ids = |query: &'static str| {
+            let request = app.get_as("ticketuser", &format!("/api/v1/tickets?{}", query));
+            async move {
+                let tickets = request.await.json::<ApiResponse<ListResponse<Value>>>().data.items;
+                tickets.iter().map(|t| t["id"].as_i64().unwrap()).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(ids("sort=severity").await, vec![2, 1, 3]);
+        assert_eq!(ids("sort=severity&order=desc").await, vec![3, 1, 2]);
+        assert_eq!(ids("sort=status").await, vec![2, 3, 1]);
+        assert_eq!(ids("sort=title&fields=id,title").await, vec![1, 3, 2]);
+        // Without a due date last, either way
+        assert_eq!(ids("sort=due_date").await, vec![2, 1, 3]);
+        assert_eq!(ids("sort=due_date&order=desc").await, vec![1, 2, 3]);
+
+        for query in ["sort=description", "sort=title&fields=id", "sort=id&order=sideways"] {
+            app.get_as("ticketuser", &format!("/api/v1/tickets?{}", query))
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let (server, token) = setup().await;
+
+        let response = server
+            .get("/api/v1/tickets/1")
+            .authorization_bearer(&token)
+            .await;
+        response.assert_status_ok();
+        let etag = response.header("ETag");
+        let last_modified = response.header("Last-Modified");
+
+        server
+            .get("/api/v1/tickets/1")
+            .add_header("If-None-Match", etag.clone())
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::NOT_MODIFIED);
+        server
+            .get("/api/v1/tickets/1")
+            .add_header("If-Modified-Since", last_modified)
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::NOT_MODIFIED);
+
+        // A different representation has a different tag
+        server
+            .get("/api/v1/tickets/1")
+            .add_query_param("fields", "title")
+            .add_header("If-None-Match", etag)
+            .authorization_bearer(&token)
+            .await
+            .assert_status_ok();
+        server
+            .get("/api/v1/tickets/1")
+            .add_header("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT")
+            .authorization_bearer(&token)
+            .await
+            .assert_status_ok();
+
+        let response = server
+            .get("/api/v1/tickets")
+            .authorization_bearer(&token)
+            .await;
+        server
+            .get("/api/v1/tickets")
+            .add_header("If-None-Match", response.header("ETag"))
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_create() {
+        let (server, token) = setup().await;
+        let request = CreateTicketRequest {
+            title: "Crash on save".to_string(),
+            severity: 1,
+            severity_label: "critical".to_string(),
+            description: String::new(),
+            assigned_to: "support".to_string(),
+            mentioned: vec![],
+            project: None,
+            custom_fields: Default::default(),
+            due_date: None,
+            ticket_group: None,
+            draft: None,
+        };
+
+        let first = server
+            .post("/api/v1/tickets")
+            .add_header("Idempotency-Key", "create-crash-1")
+            .authorization_bearer(&token)
+            .json(&request)
+            .await;
+        first.assert_status(StatusCode::CREATED);
+        let created = first.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!(created.id, 3);
+        assert_eq!(created.created_by, "ticketuser");
+
+        // A retry replays the first response instead of creating a duplicate
+        let retry = server
+            .post("/api/v1/tickets")
+            .add_header("Idempotency-Key", "create-crash-1")
+            .authorization_bearer(&token)
+            .json(&request)
+            .await;
+        retry.assert_status(StatusCode::CREATED);
+        assert_eq!(retry.header("Idempotent-Replayed"), "true");
+        assert_eq!(retry.json::<ApiResponse<TicketResponse>>().data.id, 3);
+
+        // Same key with a different body is rejected
+        server
+            .post("/api/v1/tickets")
+            .add_header("Idempotency-Key", "create-crash-1")
+            .authorization_bearer(&token)
+            .json(&CreateTicketRequest {
+                title: "Another crash".to_string(),
+                ..request
+            })
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let tickets = server
+            .get("/api/v1/tickets")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<ListResponse<TicketResponse>>>()
+            .data
+            .items;
+        assert_eq!(tickets.len(), 3);
+
+        // Without a key every request creates a ticket
+        let created = server
+            .post("/api/v1/tickets")
+            .authorization_bearer(&token)
+            .json(&CreateTicketRequest {
+                title: "Crash on load".to_string(),
+                severity: 1,
+                severity_label: "critical".to_string(),
+                description: String::new(),
+                assigned_to: "support".to_string(),
+                mentioned: vec![],
+                project: None,
+                custom_fields: Default::default(),
+                due_date: None,
+                ticket_group: None,
+                draft: None,
+            })
+            .await
+            .json::<ApiResponse<TicketResponse>>()
+            .data;
+        assert_eq!(created.id, 4);
+    }
+
+    async fn create(server: &TestServer, token: &str, body: Value) -> axum_test::TestResponse {
+        server
+            .post("/api/v1/tickets")
+            .authorization_bearer(token)
+            .json(&body)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_severity_validation() {
+        let (server, token) = setup().await;
+
+        // The label comes from the scale when left out
+        let response = create(
+            &server,
+            &token,
+            json!({"title": "Slow search", "severity": 3, "assigned_to": "support"}),
+        )
+        .await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(
+            response
+                .json::<ApiResponse<TicketResponse>>()
+                .data
+                .severity_label,
+            "minor"
+        );
+
+        let response = create(
+            &server,
+            &token,
+            json!({"title": "Slow search", "severity": 3, "severity_label": "Minor", "assigned_to": "support"}),
+        )
+        .await;
+        assert_eq!(
+            response
+                .json::<ApiResponse<TicketResponse>>()
+                .data
+                .severity_label,
+            "minor"
+        );
+
+        create(
+            &server,
+            &token,
+            json!({"title": "Slow search", "severity": 9, "assigned_to": "support"}),
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+        create(
+            &server,
+            &token,
+            json!({"title": "Slow search", "severity": 1, "severity_label": "minor", "assigned_to": "support"}),
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_project_severity_scale() {
+        let mut project = Project {
+            severities: vec![Severity::new(1, "blocker"), Severity::new(2, "annoyance")],
+            ..sample_project(&[])
+        };
+        project.acl.set_permissions("ticketuser", Permissions::WRITE);
+        let id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("ticketuser"))
+            .project(project)
+            .build()
+            .await;
+        let token = app.token("ticketuser").to_string();
+
+        let scale = app
+            .get_as("ticketuser", &format!("/api/v1/projects/{}/severities", id))
+            .await
+            .json::<ApiResponse<Vec<Severity>>>()
+            .data;
+        assert_eq!(
+            scale,
+            vec![Severity::new(1, "blocker"), Severity::new(2, "annoyance")]
+        );
+
+        let ticket = |severity: u8| json!({"title": "Flaky test", "severity": severity, "assigned_to": "support", "project": id});
+        let response = create(&app.server, &token, ticket(2)).await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(
+            response
+                .json::<ApiResponse<TicketResponse>>()
+                .data
+                .severity_label,
+            "annoyance"
+        );
+        create(&app.server, &token, ticket(3))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Unknown projects have no scale
+        let unknown = json!({"title": "Flaky test", "severity": 1, "assigned_to": "support", "project": "nope"});
+        create(&app.server, &token, unknown)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_legacy_severity_pair() {
+        let mut stored = serde_json::to_value(sample_ticket(1, "Old")).unwrap();
+        assert_eq!(stored["severity"], json!({"level": 2, "label": "major"}));
+
+        stored["severity"] = json!([3, "minor"]);
+        let ticket: Ticket = versioned::from_stored(stored).unwrap();
+        assert_eq!(ticket.severity, Severity::new(3, "minor"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_creates_get_distinct_ids() {
+        // Slow enough for both creations to read the tickets before either is stored
+        let slow = ChaosConfig {
+            latency: std::time::Duration::from_millis(5),
+            ..ChaosConfig::default()
+        };
+        let db = Arc::new(ChaosDatabase::with_seed(Arc::new(InMemoryDatabase::new()), slow, 1));
+        let app = TestApp::builder()
+            .database(db)
+            .user(UserFixture::new("ticketuser"))
+            .group("support", &["ticketuser"])
+            .ticket(sample_ticket(1, "Login page broken"))
+            .build()
+            .await;
+        let create = |title: &str| {
+            app.post_as("ticketuser", "/api/v1/tickets")
+                .json(&json!({"title": title, "severity": 2, "severity_label": "major"}))
+        };
+
+        let (first, second) = tokio::join!(create("First"), create("Second"));
+        first.assert_status(StatusCode::CREATED);
+        second.assert_status(StatusCode::CREATED);
+        let mut ids = [
+            first.json::<ApiResponse<TicketResponse>>().data.id,
+            second.json::<ApiResponse<TicketResponse>>().data.id,
+        ];
+        ids.sort();
+        assert_eq!(ids, [2, 3]);
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use crate::{
+        models::Ticket,
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_ticket},
+    };
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .ticket(Ticket {
+                assigned_to: "alice".to_string(),
+                due_date: NaiveDate::from_ymd_opt(2026, 11, 20),
+                ..sample_ticket(1, "Renew certificate")
+            })
+            .build()
+            .await
+    }
+
+    async fn set_timezone(app: &TestApp, timezone: serde_json::Value) -> TimezoneResponse {
+        let response = app
+            .put_as("alice", "/api/v1/me/timezone")
+            .json(&json!({ "timezone": timezone }))
+            .await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<TimezoneResponse>>().data
+    }
+
+    #[tokio::test]
+    async fn test_timezone_is_stored_by_iana_name() {
+        let app = setup().await;
+        let unset = app.get_as("alice", "/api/v1/me/timezone").await.json::<ApiResponse<TimezoneResponse>>().data;
+        assert_eq!(unset.timezone, None);
+
+        let set = set_timezone(&app, json!(" Europe/Kyiv ")).await;
+        assert_eq!(set.timezone.as_deref(), Some("Europe/Kyiv"));
+        let user = app.state.db.users().get_user("alice").await.unwrap();
+        assert_eq!(user.personal.timezone.as_deref(), Some("Europe/Kyiv"));
+
+        for timezone in ["Mars/Olympus", "UTC+2", ""] {
+            app.put_as("alice", "/api/v1/me/timezone")
+                .json(&json!({ "timezone": timezone }))
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(set_timezone(&app, json!(null)).await.timezone, None);
+    }
+
+    #[tokio::test]
+    async fn test_calendar_reminds_in_the_users_timezone() {
+        let app = setup().await;
+        let token = app
+            .post_as("alice", "/api/v1/me/calendar-token")
+            .await
+            .json::<ApiResponse<CalendarTokenResponse>>()
+            .data;
+
+        let feed = app.server.get(&token.url).await.text();
+        assert!(feed.contains("\r\nX-WR-TIMEZONE:UTC\r\n"));
+        assert!(feed.contains("\r\nTRIGGER;VALUE=DATE-TIME:20261120T090000Z\r\n"));
+
+        // Kyiv is at UTC+2 in November
+        set_timezone(&app, json!("Europe/Kyiv")).await;
+        let feed = app.server.get(&token.url).await.text();
+        assert!(feed.contains("\r\nX-WR-TIMEZONE:Europe/Kyiv\r\n"));
+        assert!(feed.contains("\r\nTRIGGER;VALUE=DATE-TIME:20261120T070000Z\r\n"));
+        // The event itself stays on the due date
+        assert!(feed.contains("DTSTART;VALUE=DATE:20261120\r\nDTEND;VALUE=DATE:20261121\r\n"));
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use chrono::{TimeDelta, Utc};
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn trash(app: &TestApp) -> Vec<DeletedEntity> {
+        let response = app.get_mgmt("/api/mgmt/trash").await;
+        response.assert_status_ok();
+        response.json::<ApiResponse<Vec<DeletedEntity>>>().data
+    }
+
+    #[tokio::test]
+    async fn test_deleted_entities_can_be_restored() {
+        let project = sample_project(&["alice"]);
+        let project_id = project.id.to_string();
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project)
+            .ticket(sample_ticket(7, "Printer on fire"))
+            .build()
+            .await;
+        let db = &app.state.db;
+
+        db.users().delete_user("bob", false).await.unwrap();
+        db.tickets().delete_ticket("7", false).await.unwrap();
+        db.projects().delete_project(&project_id, false).await.unwrap();
+        assert!(db.users().get_user("bob").await.is_err());
+        assert!(db.tickets().get_ticket("7").await.is_err());
+
+        let deleted = trash(&app).await;
+        let kinds: Vec<_> = deleted.iter().map(|e| (e.kind, e.id.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [(DeletedKind::User, "bob"), (DeletedKind::Ticket, "7"), (DeletedKind::Project, project_id.as_str())]
+        );
+        assert_eq!(deleted[0].purge_at - deleted[0].deleted_at, TimeDelta::days(30));
+
+        app.post_mgmt("/api/mgmt/trash/user/bob/restore")
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(db.users().get_user("bob").await.unwrap().username, "bob");
+        app.post_mgmt("/api/mgmt/trash/user/bob/restore")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        app.delete_mgmt("/api/mgmt/trash/ticket/7")
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        app.post_mgmt("/api/mgmt/trash/ticket/7/restore")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(trash(&app).await.len(), 1);
+
+        app.get_as("alice", "/api/mgmt/trash")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entities_are_purged() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .ticket(sample_ticket(1, "Printer on fire"))
+            .config(|c| c.soft_delete_retention = 0)
+            .build()
+            .await;
+        let db = &app.state.db;
+
+        db.tickets().delete_ticket("1", false).await.unwrap();
+        app.post_mgmt("/api/mgmt/trash/ticket/1/restore")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        let trash_controller = &app.state.controller.trash;
+        let purged = trash_controller.purge_expired(TimeDelta::days(1), Utc::now()).await.unwrap();
+        assert!(purged.is_empty());
+        let purged = trash_controller.purge_expired(TimeDelta::zero(), Utc::now()).await.unwrap();
+        assert_eq!(purged.len(), 1);
+        assert_eq!((purged[0].kind, purged[0].id.as_str()), (DeletedKind::Ticket, "1"));
+        assert!(trash(&app).await.is_empty());
+
+        // The id of a purged ticket is free again
+        db.tickets().create_ticket(sample_ticket(1, "Printer on fire again")).await.unwrap();
+    }
+}
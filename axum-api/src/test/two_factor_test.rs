@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+    use totp_rs::TOTP;
+
+    use crate::{
+        controllers::two_factor_controller::MAX_CHALLENGE_FAILURES,
+        create_app, create_mock_shared_state,
+        middleware::auth::now_secs,
+        schema::*,
+        test::app::TestApp,
+        utils::encryption::EncryptionKey,
+    };
+
+    const PASSWORD: &str = "securepassword123";
+
+    /// The code of the next time step: the current one went to confirm the enrollment.
+    fn next_code(totp: &TOTP) -> String {
+        totp.generate(now_secs() as u64 + 30)
+    }
+
+    async fn answer(server: &TestServer, challenge: &TwoFactorChallenge, code: &str) -> axum_test::TestResponse {
+        server
+            .post("/api/login/2fa")
+            .json(&TwoFactorLoginRequest {
+                challenge_token: challenge.challenge_token.clone(),
+                code: code.to_string(),
+                remember_me: false,
+            })
+            .await
+    }
+
+    async fn challenge(server: &TestServer, user: &str) -> TwoFactorChallenge {
+        login(server, user).await.json::<ApiResponse<TwoFactorChallenge>>().data
+    }
+
+    async fn login(server: &TestServer, user: &str) -> axum_test::TestResponse {
+        server
+            .post("/api/login")
+            .json(&LoginRequest {
+                user: user.to_string(),
+                password: PASSWORD.to_string(),
+                remember_me: false,
+            })
+            .await
+    }
+
+    /// Registers a user and enables 2FA, returns the authenticator and recovery codes.
+    async fn user_with_two_factor(server: &TestServer, user: &str) -> (TOTP, Vec<String>) {
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: user.to_string(),
+                password: PASSWORD.to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let token = login(server, user)
+            .await
+            .json::<ApiResponse<LoginResponse>>()
+            .data
+            .token;
+
+        let enrollment = server
+            .post("/api/v1/me/2fa/enroll")
+            .authorization_bearer(&token)
+            .await
+            .json::<ApiResponse<TwoFactorEnrollResponse>>()
+            .data;
+        let totp = TOTP::from_url(&enrollment.otpauth_uri).unwrap();
+
+        server
+            .post("/api/v1/me/2fa/confirm")
+            .authorization_bearer(&token)
+            .json(&TwoFactorCodeRequest {
+                code: totp.generate_current().unwrap(),
+            })
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        (totp, enrollment.recovery_codes)
+    }
+
+    #[tokio::test]
+    async fn test_login_requires_second_step() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let (totp, _) = user_with_two_factor(&server, "twofactoruser").await;
+
+        let response = login(&server, "twofactoruser").await;
+        response.assert_status(StatusCode::ACCEPTED);
+        let challenge = response.json::<ApiResponse<TwoFactorChallenge>>().data;
+
+        // The challenge token is not an access token
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&challenge.challenge_token)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .post("/api/login/2fa")
+            .json(&TwoFactorLoginRequest {
+                challenge_token: challenge.challenge_token.clone(),
+                code: "000000".to_string(),
+                remember_me: false,
+            })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/api/login/2fa")
+            .json(&TwoFactorLoginRequest {
+                challenge_token: challenge.challenge_token,
+                code: next_code(&totp),
+                remember_me: false,
+            })
+            .await;
+        response.assert_status_ok();
+        let token = response.json::<ApiResponse<LoginResponse>>().data.token;
+
+        server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&token)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_recovery_code_is_single_use() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let (_, recovery_codes) = user_with_two_factor(&server, "recoveryuser").await;
+
+        for expected in [StatusCode::OK, StatusCode::UNAUTHORIZED] {
+            let challenge = login(&server, "recoveryuser")
+                .await
+                .json::<ApiResponse<TwoFactorChallenge>>()
+                .data;
+            server
+                .post("/api/login/2fa")
+                .json(&TwoFactorLoginRequest {
+                    challenge_token: challenge.challenge_token,
+                    code: recovery_codes[0].clone(),
+                    remember_me: false,
+                })
+                .await
+                .assert_status(expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_totp_codes_are_single_use() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let (totp, _) = user_with_two_factor(&server, "replayuser").await;
+
+        // Spent: the confirmation's code, then the one of the login, and those before it
+        let code = next_code(&totp);
+        let current = totp.generate_current().unwrap();
+        answer(&server, &challenge(&server, "replayuser").await, &current)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        answer(&server, &challenge(&server, "replayuser").await, &code).await.assert_status_ok();
+        for code in [code, current] {
+            answer(&server, &challenge(&server, "replayuser").await, &code)
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_is_spent_after_wrong_codes() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let (totp, _) = user_with_two_factor(&server, "guessuser").await;
+
+        let guessed = challenge(&server, "guessuser").await;
+        for _ in 0..MAX_CHALLENGE_FAILURES {
+            answer(&server, &guessed, "000000").await.assert_status(StatusCode::UNAUTHORIZED);
+        }
+        answer(&server, &guessed, &next_code(&totp))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        // A new challenge takes the password again
+        answer(&server, &challenge(&server, "guessuser").await, &next_code(&totp))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_secret_is_encrypted_at_rest() {
+        let key = EncryptionKey::parse(&format!("v1:{}", "01".repeat(32))).unwrap();
+        let app = TestApp::builder().config(move |c| c.encryption_keys = vec![key]).build().await;
+        let (totp, _) = user_with_two_factor(&app.server, "sealeduser").await;
+
+        let user = app.state.db.users().get_user("sealeduser").await.unwrap();
+        assert!(user.two_factor.unwrap().secret.starts_with("enc:v1:"));
+        answer(&app.server, &challenge(&app.server, "sealeduser").await, &next_code(&totp))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_mgmt_reset_disables_two_factor() {
+        let state = create_mock_shared_state().unwrap();
+        let mgmt_token = state.config.management_token.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        user_with_two_factor(&server, "resetuser").await;
+
+        server
+            .delete("/api/mgmt/users/resetuser/2fa")
+            .authorization_bearer("not-the-mgmt-token")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .delete("/api/mgmt/users/resetuser/2fa")
+            .authorization_bearer(&mgmt_token)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        login(&server, "resetuser").await.assert_status_ok();
+    }
+}
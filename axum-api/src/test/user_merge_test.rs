@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::StatusCode;
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::{
+        db::{
+            DatabaseInterface,
+            chaos::{ChaosConfig, ChaosDatabase},
+            inmemory::InMemoryDatabase,
+        },
+        models::{AccessControlList, Comment, Permissions, Project, Ticket},
+        schema::*,
+        test::app::{TestApp, TestAppBuilder, UserFixture, sample_project, sample_ticket},
+    };
+
+    /// Alice registered twice, as alice and alice2. Alice2 is in both groups, can
+    /// change and owns the project, and filed, was assigned and commented ticket 1.
+    fn project() -> Project {
+        let mut project = sample_project(&["bob"]);
+        project.acl.list.push(AccessControlList {
+            permissions: Permissions::WRITE,
+            principals: vec!["alice2".to_string(), "alice".to_string()],
+        });
+        project.owner = Some("alice2".to_string());
+        project
+    }
+
+    fn setup(builder: TestAppBuilder, project: &Project) -> TestAppBuilder {
+        builder
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("alice2"))
+            .user(UserFixture::new("bob"))
+            .group("support", &["alice2", "bob"])
+            .group("ops", &["alice", "alice2"])
+            .ticket(Ticket {
+                created_by: "alice2".to_string(),
+                assigned_to: "alice2".to_string(),
+                mentioned: vec!["alice".to_string(), "alice2".to_string()],
+                project: Some(project.id.to_string()),
+                ..sample_ticket(1, "Printer on fire")
+            })
+            .ticket(Ticket {
+                created_by: "bob".to_string(),
+                ..sample_ticket(2, "Someone else's")
+            })
+            .project(project.clone())
+    }
+
+    async fn comment(app: &TestApp, author: &str) -> Comment {
+        let comment = Comment {
+            id: uuid::Uuid::now_v7().to_string(),
+            ticket: 1,
+            author: author.to_string(),
+            body: "On it".to_string(),
+            created_at: Utc::now(),
+            message_id: None,
+        };
+        app.state.db.comments().create_comment(comment.clone()).await.unwrap();
+        comment
+    }
+
+    fn merge(app: &TestApp, source: &str, target: &str) -> axum_test::TestRequest {
+        app.post_mgmt("/api/mgmt/users/merge").json(&json!({ "source": source, "target": target }))
+    }
+
+    #[tokio::test]
+    async fn test_merge_repoints_everything() {
+        let project = project();
+        let app = setup(TestApp::builder(), &project).build().await;
+        comment(&app, "alice2").await;
+        comment(&app, "bob").await;
+
+        let response = merge(&app, "alice2", "alice").await;
+        response.assert_status_ok();
+        let merged = response.json::<ApiResponse<MergeUsersResponse>>().data;
+        assert_eq!(
+            (merged.tickets, merged.comments, merged.groups, merged.projects),
+            (1, 1, 2, 1)
+        );
+
+        let db = &app.state.db;
+        let ticket = db.tickets().get_ticket("1").await.unwrap();
+        assert_eq!((ticket.created_by.as_str(), ticket.assigned_to.as_str()), ("alice", "alice"));
+        assert_eq!(ticket.mentioned, vec!["alice"]);
+        assert_eq!(db.tickets().get_ticket("2").await.unwrap().created_by, "bob");
+        let authors: Vec<String> = db.comments().list_comments(1).await.unwrap().into_iter().map(|c| c.author).collect();
+        assert_eq!(authors, vec!["alice", "bob"]);
+        assert_eq!(db.groups().get_group("support").await.unwrap().principals, vec!["alice", "bob"]);
+        assert_eq!(db.groups().get_group("ops").await.unwrap().principals, vec!["alice"]);
+        let project = db.projects().get_project(&project.id.to_string()).await.unwrap();
+        assert_eq!(project.owner.as_deref(), Some("alice"));
+        assert_eq!(project.acl.list[1].principals, vec!["alice"]);
+
+        // The duplicate is gone, its tokens with it
+        assert!(db.users().get_user("alice2").await.unwrap().deactivated);
+        app.get_as("alice2", "/api/v1/tickets/1").await.assert_status(StatusCode::UNAUTHORIZED);
+        app.get_as("alice", "/api/v1/tickets/1").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_merge_refuses_bad_pairs() {
+        let project = project();
+        let app = setup(TestApp::builder(), &project).build().await;
+        merge(&app, "alice", "alice").await.assert_status(StatusCode::BAD_REQUEST);
+        merge(&app, "alice2", "nobody").await.assert_status(StatusCode::NOT_FOUND);
+        merge(&app, "nobody", "alice").await.assert_status(StatusCode::NOT_FOUND);
+
+        merge(&app, "alice2", "alice").await.assert_status_ok();
+        // Nothing is merged into a deactivated user
+        merge(&app, "bob", "alice2").await.assert_status(StatusCode::BAD_REQUEST);
+        assert!(!app.state.db.users().get_user("bob").await.unwrap().deactivated);
+
+        app.post_as("alice", "/api/mgmt/users/merge")
+            .json(&json!({ "source": "bob", "target": "alice" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_failed_merge_changes_nothing() {
+        let db = Arc::new(ChaosDatabase::new(Arc::new(InMemoryDatabase::new()), ChaosConfig::default()));
+        let project = project();
+        let app = setup(TestApp::builder().database(db.clone()), &project).build().await;
+        db.set_config(ChaosConfig {
+            conflict_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+
+        merge(&app, "alice2", "alice").await.assert_status(StatusCode::CONFLICT);
+        db.set_config(ChaosConfig::default());
+        assert!(!db.users().get_user("alice2").await.unwrap().deactivated);
+        assert_eq!(db.tickets().get_ticket("1").await.unwrap().created_by, "alice2");
+        app.get_as("alice2", "/api/v1/tickets/1").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_crossed_merges_run_one_at_a_time() {
+        let db = Arc::new(ChaosDatabase::new(Arc::new(InMemoryDatabase::new()), ChaosConfig::default()));
+        let project = project();
+        let app = setup(TestApp::builder().database(db.clone()), &project).build().await;
+        // Slow enough for both merges to check their target before either writes
+        db.set_config(ChaosConfig {
+            latency: Duration::from_millis(5),
+            ..ChaosConfig::default()
+        });
+
+        let (forth, back) = tokio::join!(merge(&app, "alice2", "alice"), merge(&app, "alice", "alice2"));
+        let mut statuses = [forth.status_code(), back.status_code()];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST]);
+        db.set_config(ChaosConfig::default());
+        let deactivated = [
+            db.users().get_user("alice").await.unwrap().deactivated,
+            db.users().get_user("alice2").await.unwrap().deactivated,
+        ];
+        assert_eq!(deactivated.iter().filter(|d| **d).count(), 1);
+    }
+}
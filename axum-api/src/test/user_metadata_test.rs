@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        schema::*,
+        test::app::{TestApp, UserFixture},
+        validation::metadata::{MAX_KEYS, MAX_VALUE_BYTES},
+    };
+
+    async fn setup() -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("carol"))
+            .build()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_set_and_remove_metadata() {
+        let app = setup().await;
+
+        let set = app
+            .put_mgmt("/api/mgmt/users/alice/metadata/Beta")
+            .json(&json!({"value": "2026-spring"}))
+            .await
+            .json::<ApiResponse<UserMetadataResponse>>()
+            .data;
+        assert_eq!(set.metadata.get("beta").map(String::as_str), Some("2026-spring"));
+
+        // Users read theirs, the rest is for operators
+        let mine = app
+            .get_as("alice", "/api/v1/me/metadata")
+            .await
+            .json::<ApiResponse<UserMetadataResponse>>()
+            .data;
+        assert_eq!(mine.metadata, set.metadata);
+        app.put_as("alice", "/api/mgmt/users/alice/metadata/beta")
+            .json(&json!({"value": "no"}))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        app.delete_mgmt("/api/mgmt/users/alice/metadata/beta")
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        app.delete_mgmt("/api/mgmt/users/alice/metadata/beta")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        app.put_mgmt("/api/mgmt/users/nobody/metadata/beta")
+            .json(&json!({"value": "yes"}))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_limits() {
+        let app = setup().await;
+
+        app.put_mgmt("/api/mgmt/users/bob/metadata/2fa")
+            .json(&json!({"value": "yes"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        app.put_mgmt("/api/mgmt/users/bob/metadata/note")
+            .json(&json!({"value": "x".repeat(MAX_VALUE_BYTES + 1)}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // registered_at is there already
+        for n in 1..MAX_KEYS {
+            app.put_mgmt(&format!("/api/mgmt/users/bob/metadata/key{}", n))
+                .json(&json!({"value": "v"}))
+                .await
+                .assert_status_ok();
+        }
+        app.put_mgmt("/api/mgmt/users/bob/metadata/one-more")
+            .json(&json!({"value": "v"}))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        // Replacing a value is fine
+        app.put_mgmt("/api/mgmt/users/bob/metadata/key1")
+            .json(&json!({"value": "w"}))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_find_users_by_metadata() {
+        let app = setup().await;
+        for (user, value) in [("carol", "ldap"), ("alice", "ldap"), ("bob", "csv")] {
+            app.put_mgmt(&format!("/api/mgmt/users/{}/metadata/import-source", user))
+                .json(&json!({"value": value}))
+                .await
+                .assert_status_ok();
+        }
+
+        let found = |query: &'static str| {
+            let app = &app;
+            async move {
+                app.get_mgmt(&format!("/api/mgmt/users?{}", query))
+                    .await
+                    .json::<ApiResponse<Vec<UserMetadataResponse>>>()
+                    .data
+                    .into_iter()
+                    .map(|u| u.username)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(found("key=import-source&value=ldap").await, vec!["alice", "carol"]);
+        assert_eq!(found("key=Import-Source").await, vec!["alice", "bob", "carol"]);
+        assert!(found("key=beta").await.is_empty());
+        app.get_mgmt("/api/mgmt/users").await.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
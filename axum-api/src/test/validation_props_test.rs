@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::validation::{
+        email::validate_email_address,
+        fields::validate_fields,
+        force_lowercase, force_uppercase, limit_length, limit_min_length,
+        mentions::{mention_spans, parse_mentions},
+        naming::{validate_slug, validate_username},
+    };
+
+    /// Any text, or text close to what passes, so that both outcomes are exercised.
+    fn text() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "[a-zA-Z0-9_@. -]{0,30}", "[a-zA-Z][a-zA-Z0-9_]{1,26}", "\\PC{0,40}"]
+    }
+
+    fn email() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "[ a-zA-Z0-9.]{0,12}@[a-zA-Z0-9.]{0,12}", "\\PC{1,8}@\\PC{1,8}\\.\\PC{1,4}"]
+    }
+
+    fn mentions_text() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "([a-zé_.@(), -]|@\\w{1,6}|\\PC){0,20}"]
+    }
+
+    proptest! {
+        #[test]
+        fn transformers_are_idempotent(s in text()) {
+            let (lower, upper) = (force_lowercase(), force_uppercase());
+            prop_assert_eq!(lower(&lower(&s)), lower(&s));
+            prop_assert_eq!(upper(&upper(&s)), upper(&s));
+        }
+
+        #[test]
+        fn lengths_count_characters(s in text(), n in 0usize..40) {
+            let length = s.chars().count();
+            prop_assert_eq!(limit_length(n)(&s).is_ok(), length <= n);
+            prop_assert_eq!(limit_min_length(n)(&s).is_ok(), length >= n);
+        }
+
+        #[test]
+        fn usernames_normalize_once(s in text()) {
+            if let Ok(username) = validate_username(&s) {
+                prop_assert_eq!(validate_username(&username), Ok(username.clone()));
+                prop_assert!((2..=25).contains(&username.len()));
+                prop_assert!(username.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'));
+            }
+        }
+
+        #[test]
+        fn slugs_normalize_once(s in text()) {
+            if let Ok(slug) = validate_slug(&s) {
+                prop_assert_eq!(validate_slug(&slug), Ok(slug.clone()));
+                prop_assert!(!slug.starts_with('-') && !slug.ends_with('-'));
+            }
+        }
+
+        #[test]
+        fn emails_normalize_once(s in email()) {
+            if let Ok(email) = validate_email_address(&s, &[]) {
+                prop_assert_eq!(validate_email_address(&email, &[]), Ok(email.clone()));
+                let domain = email.rsplit('@').next().unwrap().to_string();
+                prop_assert_eq!(validate_email_address(&s, &[domain]), Ok(email.clone()));
+            }
+        }
+
+        #[test]
+        fn mentions_are_found_again(s in mentions_text()) {
+            let mentions = parse_mentions(&s);
+            for span in mention_spans(&s) {
+                prop_assert!(s[span.clone()].starts_with('@'));
+                prop_assert!(mentions.iter().any(|m| *m == s[span.start + 1..span.end]));
+            }
+            let written: Vec<String> = mentions.iter().map(|m| format!("@{}", m)).collect();
+            prop_assert_eq!(parse_mentions(&written.join(" ")), mentions);
+        }
+
+        #[test]
+        fn field_selections_are_stable(s in "[a-z_, ]{0,40}") {
+            let allowed = ["title", "severity", "assigned_to", "a", "b_"];
+            if let Ok(fields) = validate_fields(&s, &allowed) {
+                prop_assert_eq!(validate_fields(&fields.join(","), &allowed), Ok(fields.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+
+    use axum_test::TestServer;
+    use utoipa::openapi::{
+        Deprecated, HttpMethod, OpenApiBuilder, PathItem, PathsBuilder, path::OperationBuilder,
+    };
+
+    use crate::{
+        api::versions::{v1_doc, v2_doc},
+        create_app, create_mock_shared_state,
+        schema::*,
+    };
+
+    async fn login_twice(server: &TestServer, user: &str) -> Vec<String> {
+        server
+            .post("/api/register")
+            .json(&RegisterRequest {
+                user: user.to_string(),
+                password: "securepassword123".to_string(),
+                email: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let mut tokens = Vec::new();
+        for _ in 0..2 {
+            tokens.push(
+                server
+                    .post("/api/login")
+                    .json(&LoginRequest {
+                        user: user.to_string(),
+                        password: "securepassword123".to_string(),
+                        remember_me: false,
+                    })
+                    .await
+                    .json::<ApiResponse<LoginResponse>>()
+                    .data
+                    .token,
+            );
+        }
+        tokens
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_v1_route_headers() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = login_twice(&server, "deprecationuser").await;
+
+        let response = server
+            .post("/api/v1/me/sessions/revoke-all")
+            .authorization_bearer(&tokens[1])
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header("Deprecation"), "@1792108800");
+        assert_eq!(response.header("Sunset"), "Fri, 16 Apr 2027 00:00:00 GMT");
+        assert_eq!(
+            response.header("Link"),
+            "</api/v2/me/sessions>; rel=\"successor-version\""
+        );
+
+        // Routes that are not deprecated carry no headers
+        let response = server
+            .get("/api/v1/me/sessions")
+            .authorization_bearer(&tokens[1])
+            .await;
+        response.assert_status_ok();
+        assert!(response.maybe_header("Deprecation").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_v2_reuses_v1_handlers() {
+        let state = create_mock_shared_state().unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let tokens = login_twice(&server, "v2user").await;
+
+        let sessions = server
+            .get("/api/v2/me/sessions")
+            .authorization_bearer(&tokens[1])
+            .await
+            .json::<ApiResponse<ListResponse<SessionInfo>>>()
+            .data
+            .items;
+        assert_eq!(sessions.len(), 2);
+
+        let response = server
+            .delete("/api/v2/me/sessions")
+            .authorization_bearer(&tokens[1])
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<ApiResponse<RevokedSessions>>().data.revoked,
+            1
+        );
+
+        // Only DELETE /me/sessions/{id} matches the path in v2
+        server
+            .post("/api/v2/me/sessions/revoke-all")
+            .authorization_bearer(&tokens[1])
+            .await
+            .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_versioned_openapi_documents() {
+        let item = |method| PathItem::new(method, OperationBuilder::new().build());
+        let api = OpenApiBuilder::new()
+            .paths(
+                PathsBuilder::new()
+                    .path("/api/login", item(HttpMethod::Post))
+                    .path("/api/v1/me/sessions", item(HttpMethod::Get))
+                    .path("/api/v1/me/sessions/revoke-all", item(HttpMethod::Post))
+                    .path("/api/v2/me/sessions", item(HttpMethod::Delete)),
+            )
+            .build();
+
+        let v1 = v1_doc(&api);
+        assert!(v1.paths.paths.contains_key("/api/login"));
+        assert!(!v1.paths.paths.contains_key("/api/v2/me/sessions"));
+        let revoke_all = &v1.paths.paths["/api/v1/me/sessions/revoke-all"];
+        assert!(matches!(
+            revoke_all.post.as_ref().unwrap().deprecated,
+            Some(Deprecated::True)
+        ));
+
+        let v2 = v2_doc(&api);
+        assert!(v2.paths.paths.contains_key("/api/login"));
+        assert!(!v2.paths.paths.keys().any(|p| p.starts_with("/api/v1")));
+        assert!(
+            !v2.paths
+                .paths
+                .contains_key("/api/v2/me/sessions/revoke-all")
+        );
+        let sessions = &v2.paths.paths["/api/v2/me/sessions"];
+        assert!(sessions.get.is_some() && sessions.delete.is_some());
+    }
+}
@@ -0,0 +1,525 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::http::StatusCode;
+    use axum_test::{TestWebSocket, WsMessage};
+
+    use crate::{
+        api::v1::ws::protocol::{ClientMessage, ServerMessage},
+        models::{
+            AccessControlStore, AclInheritance, NotificationKind, Permissions, TicketEventKind, TicketGroup,
+            TicketStatus,
+        },
+        schema::*,
+        test::app::{TestApp, UserFixture, sample_project, sample_ticket},
+    };
+
+    async fn app(configure: impl FnOnce(&mut crate::config::AppConfig) + 'static) -> TestApp {
+        TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .config(configure)
+            .websockets()
+            .build()
+            .await
+    }
+
+    /// Reads past pings until the next text message.
+    async fn receive_text(socket: &mut TestWebSocket) -> String {
+        loop {
+            match socket.receive_message().await {
+                WsMessage::Text(text) => return text.to_string(),
+                WsMessage::Ping(_) => continue,
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+    }
+
+    async fn send(socket: &mut TestWebSocket, message: ClientMessage) -> ServerMessage {
+        socket.send_text(serde_json::to_string(&message).unwrap()).await;
+        receive(socket).await
+    }
+
+    async fn receive(socket: &mut TestWebSocket) -> ServerMessage {
+        serde_json::from_str(&receive_text(socket).await).unwrap()
+    }
+
+    async fn send_auth(socket: &mut TestWebSocket, token: &str) -> ServerMessage {
+        let auth = ClientMessage::Auth {
+            token: token.to_string(),
+        };
+        send(socket, auth).await
+    }
+
+    /// Reads past pings until the server closes, returning the close reason.
+    async fn close_reason(socket: &mut TestWebSocket) -> String {
+        loop {
+            match socket.receive_message().await {
+                WsMessage::Close(frame) => return frame.map(|f| f.reason.to_string()).unwrap_or_default(),
+                WsMessage::Ping(_) => continue,
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit_per_user() {
+        let app = app(|config| config.ws_max_connections_per_user = 1).await;
+
+        let first = app.ws_as("alice", "/api/v1/ws").await;
+        app.server
+            .get_websocket("/api/v1/ws")
+            .authorization_bearer(app.token("alice"))
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        first.close().await;
+        for _ in 0..50 {
+            if app.state.ws_connections.user_count("alice") == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let mut second = app.ws_as("alice", "/api/v1/ws").await;
+        second.send_text("again").await;
+        second.assert_receive_text("alice said: again").await;
+
+        let stats = app
+            .get_mgmt("/api/mgmt/stats")
+            .await
+            .json::<ApiResponse<AdminStatsResponse>>()
+            .data;
+        assert_eq!(stats.ws_connections, 1);
+        assert_eq!((stats.ws_totals.opened, stats.ws_totals.rejected), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let app = app(|config| {
+            config.ws_ping_interval = 1;
+            config.ws_idle_timeout = 1;
+        })
+        .await;
+
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        assert_eq!(close_reason(&mut socket).await, "Idle timeout");
+    }
+
+    #[tokio::test]
+    async fn test_missed_pongs_disconnect() {
+        let app = app(|config| {
+            config.ws_ping_interval = 1;
+            config.ws_idle_timeout = 60;
+        })
+        .await;
+
+        // Pongs are only sent while the client reads or writes
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        assert_eq!(close_reason(&mut socket).await, "Heartbeat timeout");
+        assert_eq!(app.state.ws_connections.totals().timed_out, 1);
+    }
+
+    #[tokio::test]
+    async fn test_active_socket_stays_open() {
+        let app = app(|config| {
+            config.ws_ping_interval = 1;
+            config.ws_idle_timeout = 60;
+        })
+        .await;
+
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        for i in 0..6 {
+            socket.send_text(format!("message {}", i)).await;
+            loop {
+                match socket.receive_message().await {
+                    WsMessage::Ping(_) => continue,
+                    WsMessage::Text(text) => {
+                        assert_eq!(text.as_str(), format!("alice said: message {}", i));
+                        break;
+                    }
+                    other => panic!("Unexpected message {:?}", other),
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(600)).await;
+        }
+        assert_eq!(app.state.ws_connections.user_count("alice"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_socket_closes_when_token_expires() {
+        let app = app(|config| config.access_token_lifetime = 1).await;
+
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        assert_eq!(close_reason(&mut socket).await, "Token expired");
+    }
+
+    #[tokio::test]
+    async fn test_token_refresh_keeps_socket_open() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .websockets()
+            .build()
+            .await;
+        let claims = app.state.auth.decode_token(app.token("alice")).unwrap();
+        let token = |lifetime| {
+            app.state
+                .auth
+                .create_token("alice", &claims.sid, claims.generation, lifetime)
+                .unwrap()
+        };
+        let (short, _) = token(2);
+        let (fresh, expires_at) = token(60);
+
+        let mut socket = app
+            .server
+            .get_websocket("/api/v1/ws")
+            .authorization_bearer(short)
+            .await
+            .into_websocket()
+            .await;
+        assert_eq!(
+            send_auth(&mut socket, app.token("bob")).await,
+            ServerMessage::AuthError {
+                message: "Token belongs to another user".to_string()
+            }
+        );
+        assert!(matches!(
+            send_auth(&mut socket, "garbage").await,
+            ServerMessage::AuthError { .. }
+        ));
+        assert_eq!(
+            send_auth(&mut socket, &fresh).await,
+            ServerMessage::AuthOk { expires_at }
+        );
+
+        tokio::time::sleep(Duration::from_millis(3000)).await;
+        socket.send_text("still here").await;
+        assert_eq!(receive_text(&mut socket).await, "alice said: still here");
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_closes_socket() {
+        let app = app(|config| config.ws_revalidate_interval = 1).await;
+
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        app.post_mgmt("/api/mgmt/users/alice/logout-all")
+            .await
+            .assert_status_success();
+        assert_eq!(close_reason(&mut socket).await, "Session revoked");
+    }
+
+    #[tokio::test]
+    async fn test_deactivated_user_socket_closes() {
+        let app = app(|config| config.ws_revalidate_interval = 1).await;
+
+        let mut socket = app.ws_as("alice", "/api/v1/ws").await;
+        let mut alice = app.state.db.users().get_user("alice").await.unwrap();
+        alice.deactivated = true;
+        app.state.db.users().update_user("alice", alice).await.unwrap();
+        assert_eq!(close_reason(&mut socket).await, "Session revoked");
+    }
+
+    #[tokio::test]
+    async fn test_presence() {
+        let project = sample_project(&["alice", "bob"]);
+        let channel = format!("project:{}", project.id);
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .user(UserFixture::new("mallory"))
+            .project(project.clone())
+            .websockets()
+            .build()
+            .await;
+
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        let subscribe = ClientMessage::Subscribe {
+            channel: channel.clone(),
+        };
+        assert_eq!(
+            send(&mut alice, subscribe.clone()).await,
+            ServerMessage::Subscribed {
+                channel: channel.clone(),
+                online: vec!["alice".to_string()]
+            }
+        );
+
+        let mut mallory = app.ws_as("mallory", "/api/v1/ws").await;
+        assert!(matches!(
+            send(&mut mallory, subscribe).await,
+            ServerMessage::Error { .. }
+        ));
+        let bob = app.ws_as("bob", "/api/v1/ws").await;
+        assert_eq!(
+            receive(&mut alice).await,
+            ServerMessage::Presence {
+                channel: channel.clone(),
+                user: "bob".to_string(),
+                online: true
+            }
+        );
+
+        let path = format!("/api/v1/projects/{}/online", project.id);
+        let online = app
+            .get_as("alice", &path)
+            .await
+            .json::<ApiResponse<ProjectOnlineResponse>>()
+            .data;
+        assert_eq!(online.users, vec!["alice", "bob"]);
+        app.get_as("mallory", &path)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        bob.close().await;
+        assert_eq!(
+            receive(&mut alice).await,
+            ServerMessage::Presence {
+                channel,
+                user: "bob".to_string(),
+                online: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ticket_changes_reach_project_channel() {
+        let mut project = sample_project(&[]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        let channel = format!("project:{}", project.id);
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .project(project.clone())
+            .websockets()
+            .build()
+            .await;
+
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        send(&mut alice, ClientMessage::Subscribe { channel: channel.clone() }).await;
+
+        app.post_as("alice", "/api/v1/tickets")
+            .json(&serde_json::json!({ "title": "Refunds are late", "severity": 3, "project": project.id }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let ServerMessage::TicketUpdated { channel: target, ticket, kind, actor, .. } = receive(&mut alice).await else {
+            panic!("Expected a ticket update");
+        };
+        assert_eq!((target.as_str(), ticket, kind, actor.as_str()), (channel.as_str(), 1, TicketEventKind::Created, "alice"));
+
+        app.post_as("alice", "/api/v1/tickets/1/comments")
+            .json(&serde_json::json!({ "body": "Seen on staging too" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let ServerMessage::CommentAdded { channel: target, comment } = receive(&mut alice).await else {
+            panic!("Expected a comment");
+        };
+        assert_eq!(target, channel);
+        assert_eq!((comment.ticket, comment.body.as_str()), (1, "Seen on staging too"));
+    }
+
+    #[tokio::test]
+    async fn test_ticket_changes_follow_ticket_groups() {
+        let mut project = sample_project(&["bob"]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        let mut ops = AccessControlStore::default();
+        ops.set_permissions("alice", Permissions::ROOT);
+        project.tickets.push(TicketGroup {
+            prefix: "OPS".to_string(),
+            acl: ops,
+            inheritance: AclInheritance::Narrow,
+        });
+        let channel = format!("project:{}", project.id);
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project.clone())
+            .websockets()
+            .build()
+            .await;
+
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        send(&mut alice, ClientMessage::Subscribe { channel: channel.clone() }).await;
+        let mut bob = app.ws_as("bob", "/api/v1/ws").await;
+        send(&mut bob, ClientMessage::Subscribe { channel: channel.clone() }).await;
+        receive(&mut alice).await; // bob's presence
+
+        // bob reads the project, but not the OPS group
+        for group in [Some("OPS"), None] {
+            app.post_as("alice", "/api/v1/tickets")
+                .json(&serde_json::json!({ "title": "Disk full", "severity": 3, "project": project.id, "ticket_group": group }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        for (socket, expected) in [(&mut alice, 1), (&mut bob, 2)] {
+            let ServerMessage::TicketUpdated { ticket, .. } = receive(socket).await else {
+                panic!("Expected a ticket update");
+            };
+            assert_eq!(ticket, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_requires_a_token() {
+        let app = app(|_| {}).await;
+
+        app.server
+            .get_websocket("/api/v1/ws")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        app.server
+            .get_websocket("/api/v1/ws")
+            .authorization_bearer("garbage")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        assert_eq!(app.state.ws_connections.totals().opened, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_channels() {
+        let project = sample_project(&["alice"]);
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .project(project.clone())
+            .websockets()
+            .build()
+            .await;
+        let subscribe = |channel: String| ClientMessage::Subscribe { channel };
+
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        for channel in ["general".to_string(), format!("project:{}", uuid::Uuid::now_v7())] {
+            assert!(matches!(send(&mut alice, subscribe(channel)).await, ServerMessage::Error { .. }));
+        }
+        // The socket stays usable
+        let channel = format!("project:{}", project.id);
+        assert!(matches!(send(&mut alice, subscribe(channel)).await, ServerMessage::Subscribed { .. }));
+        assert_eq!(app.state.ws_connections.channels(), vec![format!("project:{}", project.id)]);
+    }
+
+    #[tokio::test]
+    async fn test_ticket_moves_reach_subscribers() {
+        let mut project = sample_project(&["bob"]);
+        project.acl.set_permissions("alice", Permissions::WRITE);
+        let channel = format!("project:{}", project.id);
+        let mut ticket = sample_ticket(1, "Refunds are late");
+        ticket.project = Some(project.id.to_string());
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .project(project.clone())
+            .ticket(ticket)
+            .websockets()
+            .build()
+            .await;
+        let move_to = |status: &str| {
+            app.post_as("alice", "/api/v1/tickets/1/move")
+                .json(&serde_json::json!({ "status": status, "position": 0 }))
+        };
+        let subscribe = ClientMessage::Subscribe { channel: channel.clone() };
+
+        // Authenticated again before subscribing, as a client refreshing its token does
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        let claims = app.state.auth.decode_token(app.token("alice")).unwrap();
+        let (fresh, expires_at) = app
+            .state
+            .auth
+            .create_token("alice", &claims.sid, claims.generation, 60)
+            .unwrap();
+        assert_eq!(send_auth(&mut alice, &fresh).await, ServerMessage::AuthOk { expires_at });
+        assert_eq!(
+            send(&mut alice, subscribe.clone()).await,
+            ServerMessage::Subscribed {
+                channel: channel.clone(),
+                online: vec!["alice".to_string()]
+            }
+        );
+        let mut bob = app.ws_as("bob", "/api/v1/ws").await;
+        assert!(matches!(send(&mut bob, subscribe.clone()).await, ServerMessage::Subscribed { .. }));
+        assert!(matches!(receive(&mut alice).await, ServerMessage::Presence { online: true, .. }));
+
+        move_to("in_progress").await.assert_status_ok();
+        for socket in [&mut alice, &mut bob] {
+            let ServerMessage::TicketUpdated { channel: target, ticket, kind, actor, .. } = receive(socket).await else {
+                panic!("Expected a ticket update");
+            };
+            assert_eq!(
+                (target.as_str(), ticket, kind, actor.as_str()),
+                (channel.as_str(), 1, TicketEventKind::Moved, "alice")
+            );
+        }
+        // What the event announced is what REST returns
+        let ticket = app.get_as("bob", "/api/v1/tickets/1").await.json::<ApiResponse<TicketResponse>>().data;
+        assert_eq!(ticket.status, TicketStatus::InProgress);
+
+        // Only subscribed sockets hear of the next one
+        assert_eq!(
+            send(&mut alice, ClientMessage::Unsubscribe { channel: channel.clone() }).await,
+            ServerMessage::Unsubscribed { channel: channel.clone() }
+        );
+        move_to("resolved").await.assert_status_ok();
+        assert!(matches!(receive(&mut bob).await, ServerMessage::TicketUpdated { ticket: 1, .. }));
+        assert!(matches!(send(&mut alice, subscribe).await, ServerMessage::Subscribed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_direct_messages() {
+        let app = TestApp::builder()
+            .user(UserFixture::new("alice"))
+            .user(UserFixture::new("bob"))
+            .websockets()
+            .build()
+            .await;
+        let dm = |to: &str, body: &str| ClientMessage::Dm {
+            to: to.to_string(),
+            body: body.to_string(),
+        };
+
+        let mut alice = app.ws_as("alice", "/api/v1/ws").await;
+        let mut bob = app.ws_as("bob", "/api/v1/ws").await;
+        let mut bob_again = app.ws_as("bob", "/api/v1/ws").await;
+        assert_eq!(
+            send(&mut alice, dm("bob", "ticket 7?")).await,
+            ServerMessage::DmSent {
+                to: "bob".to_string(),
+                delivered: true
+            }
+        );
+        for socket in [&mut bob, &mut bob_again] {
+            match receive(socket).await {
+                ServerMessage::Dm { from, body, .. } => {
+                    assert_eq!((from.as_str(), body.as_str()), ("alice", "ticket 7?"))
+                }
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+        assert!(matches!(
+            send(&mut alice, dm("nobody", "hello")).await,
+            ServerMessage::Error { .. }
+        ));
+        assert!(matches!(
+            send(&mut alice, dm("bob", &"x".repeat(4001))).await,
+            ServerMessage::Error { .. }
+        ));
+
+        bob.close().await;
+        bob_again.close().await;
+        for _ in 0..50 {
+            if app.state.ws_connections.user_count("bob") == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            send(&mut alice, dm("bob", "see you tomorrow")).await,
+            ServerMessage::DmSent {
+                to: "bob".to_string(),
+                delivered: false
+            }
+        );
+        let stored = app.state.db.notifications().list_notifications("bob", &Default::default()).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].kind, NotificationKind::DirectMessage);
+        assert_eq!(stored[0].payload["from"], "alice");
+        assert_eq!(stored[0].payload["body"], "see you tomorrow");
+    }
+}
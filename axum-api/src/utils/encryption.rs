@@ -0,0 +1,158 @@
+//! Application-level encryption of single values, so that selected fields are
+//! stored encrypted whichever backend holds them.
+//!
+//! Values are sealed with AES-256-GCM under the current key of a key ring, as
+//! `enc:<key id>:<hex of nonce and ciphertext>`. The field a value belongs to is
+//! authenticated along with it: a value copied into another field doesn't decrypt.
+//! Older keys stay in the ring to read what they sealed until it is re-encrypted.
+
+use std::fmt;
+
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng, Payload},
+};
+
+use crate::error::AppError;
+
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+
+/// A named AES-256 key. Only the id shows up in logs.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    pub id: String,
+    key: [u8; 32],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+impl EncryptionKey {
+    /// Parses `id:key`, the key being 32 bytes in hex.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (id, hex) = value
+            .split_once(':')
+            .ok_or_else(|| "Encryption keys are given as id:key".to_string())?;
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid encryption key id '{}'", id));
+        }
+        let key = from_hex(hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("Encryption key {} must be 64 hex characters", id))?;
+        Ok(Self { id: id.to_string(), key })
+    }
+}
+
+/// Seals values under the first key of the ring, opens them with any of its keys.
+pub struct FieldCipher {
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl FieldCipher {
+    /// `None` without keys. The first key is the current one.
+    pub fn new(keys: &[EncryptionKey]) -> Option<Self> {
+        if keys.is_empty() {
+            return None;
+        }
+        Some(Self {
+            keys: keys.iter().map(|k| (k.id.clone(), Aes256Gcm::new(&k.key.into()))).collect(),
+        })
+    }
+
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(PREFIX)
+    }
+
+    /// Whether the value is sealed under the current key.
+    pub fn is_current(&self, value: &str) -> bool {
+        value
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .is_some_and(|(id, _)| id == self.keys[0].0)
+    }
+
+    pub fn encrypt(&self, field: &str, value: &str) -> Result<String, AppError> {
+        let (id, cipher) = &self.keys[0];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = cipher
+            .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: field.as_bytes() })
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Encryption of {} failed", field)))?;
+        Ok(format!("{}{}:{}{}", PREFIX, id, to_hex(&nonce), to_hex(&sealed)))
+    }
+
+    /// Opens a sealed value. Values that aren't, written before the field was
+    /// encrypted, are returned as they are.
+    pub fn decrypt(&self, field: &str, value: &str) -> Result<String, AppError> {
+        let Some(rest) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let failed = |reason: &str| AppError::Internal(anyhow::anyhow!("{} can't be decrypted: {}", field, reason));
+        let (id, hex) = rest.split_once(':').ok_or_else(|| failed("malformed value"))?;
+        let (_, cipher) = self
+            .keys
+            .iter()
+            .find(|(key, _)| key == id)
+            .ok_or_else(|| failed(&format!("unknown key {}", id)))?;
+        let bytes = from_hex(hex).filter(|b| b.len() > NONCE_LEN).ok_or_else(|| failed("malformed value"))?;
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let plain = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: field.as_bytes() })
+            .map_err(|_| failed("authentication failed"))?;
+        String::from_utf8(plain).map_err(|_| failed("not UTF-8"))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str, byte: u8) -> EncryptionKey {
+        EncryptionKey::parse(&format!("{}:{}", id, to_hex(&[byte; 32]))).unwrap()
+    }
+
+    #[test]
+    fn round_trips_and_rotates() {
+        let old = FieldCipher::new(&[key("v1", 1)]).unwrap();
+        let sealed = old.encrypt("ssn", "078-05-1120").unwrap();
+        assert!(sealed.starts_with("enc:v1:"));
+        assert_ne!(sealed, old.encrypt("ssn", "078-05-1120").unwrap());
+        assert_eq!(old.decrypt("ssn", &sealed).unwrap(), "078-05-1120");
+        // Bound to its field
+        assert!(old.decrypt("phone", &sealed).is_err());
+
+        let rotated = FieldCipher::new(&[key("v2", 2), key("v1", 1)]).unwrap();
+        assert!(!rotated.is_current(&sealed));
+        assert_eq!(rotated.decrypt("ssn", &sealed).unwrap(), "078-05-1120");
+        assert!(rotated.is_current(&rotated.encrypt("ssn", "x").unwrap()));
+        assert!(FieldCipher::new(&[key("v2", 2)]).unwrap().decrypt("ssn", &sealed).is_err());
+
+        assert_eq!(old.decrypt("ssn", "plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert!(EncryptionKey::parse("v1").is_err());
+        assert!(EncryptionKey::parse("v1:abcd").is_err());
+        assert!(EncryptionKey::parse(&format!(":{}", "00".repeat(32))).is_err());
+        let key = EncryptionKey::parse(&format!("v1:{}", "ab".repeat(32))).unwrap();
+        assert_eq!(format!("{:?}", key), "EncryptionKey { id: \"v1\", .. }");
+    }
+}
@@ -0,0 +1,145 @@
+//! Minimal iCalendar (RFC 5545) writer for feeds of all-day events.
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+
+// Content lines longer than this many octets are folded
+const LINE_LIMIT: usize = 75;
+
+/// An all-day event.
+pub struct Event {
+    pub uid: String, // stable across feed refreshes, so calendars update the event in place
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: String,
+    pub reminder: Option<DateTime<Utc>>, // when calendars alert of the event
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line into lines of at most 75 octets, continuation lines start
+/// with a space. Lines end with CRLF.
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// The calendar with the events, stamped with `now`. Calendar apps show it in the
+/// timezone of its owner.
+pub fn calendar(name: &str, timezone: Tz, events: &[Event], now: DateTime<Utc>) -> String {
+    let stamp = timestamp(now);
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//startemplates//tracker//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+        format!("X-WR-TIMEZONE:{}", timezone.name()),
+    ];
+    for event in events {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", event.uid),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", (event.date + TimeDelta::days(1)).format("%Y%m%d")),
+            format!("SUMMARY:{}", escape(&event.summary)),
+            format!("DESCRIPTION:{}", escape(&event.description)),
+            "TRANSP:TRANSPARENT".to_string(),
+        ]);
+        if let Some(reminder) = event.reminder {
+            lines.extend([
+                "BEGIN:VALARM".to_string(),
+                "ACTION:DISPLAY".to_string(),
+                format!("DESCRIPTION:{}", escape(&event.summary)),
+                format!("TRIGGER;VALUE=DATE-TIME:{}", timestamp(reminder)),
+                "END:VALARM".to_string(),
+            ]);
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in &lines {
+        fold(line, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let mut out = String::new();
+        fold(&format!("SUMMARY:{}", "é".repeat(40)), &mut out);
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= LINE_LIMIT));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines.concat().replace(" é", "é"), format!("SUMMARY:{}", "é".repeat(40)));
+    }
+
+    #[test]
+    fn all_day_events() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T08:30:00Z").unwrap().to_utc();
+        let event = Event {
+            uid: "ticket-7@example".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+            summary: "Due: Fix login".to_string(),
+            description: String::new(),
+            reminder: None,
+        };
+        let ics = calendar("Deadlines", Tz::UTC, &[event], now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20260301T083000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20260331\r\nDTEND;VALUE=DATE:20260401\r\n"));
+        assert!(ics.contains("\r\nX-WR-TIMEZONE:UTC\r\n"));
+        assert!(!ics.contains("VALARM"));
+    }
+
+    #[test]
+    fn reminders_are_alarms() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T08:30:00Z").unwrap().to_utc();
+        let event = Event {
+            uid: "ticket-7@example".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+            summary: "Due: Fix login".to_string(),
+            description: String::new(),
+            reminder: Some(DateTime::parse_from_rfc3339("2026-03-31T06:00:00Z").unwrap().to_utc()),
+        };
+        let ics = calendar("Deadlines", Tz::Europe__Kyiv, &[event], now);
+        assert!(ics.contains("\r\nX-WR-TIMEZONE:Europe/Kyiv\r\n"));
+        assert!(ics.contains(
+            "\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nDESCRIPTION:Due: Fix login\r\n\
+             TRIGGER;VALUE=DATE-TIME:20260331T060000Z\r\nEND:VALARM\r\nEND:VEVENT\r\n"
+        ));
+    }
+}
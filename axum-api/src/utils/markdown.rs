@@ -0,0 +1,287 @@
+//! Markdown of ticket descriptions and comments, rendered to HTML safe to show as is.
+//!
+//! Rendering follows CommonMark with tables and strikethrough. Raw HTML in the
+//! markdown is kept only as far as the sanitizer allows: no scripts, styles, event
+//! handlers or `javascript:` links. Mentions (`@name`) and ticket references (`#12`,
+//! or `OPS-12` for a ticket of the `OPS` group) become links when they resolve,
+//! except in code and in links already.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::LazyLock,
+};
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd, TextMergeStream, html};
+
+use crate::validation::mentions::mention_spans;
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::default();
+    builder.add_allowed_classes("a", ["mention", "ticket-ref"]);
+    builder
+});
+
+/// What the markdown refers to, for the caller to resolve before rendering.
+#[derive(Debug, Default, PartialEq)]
+pub struct References {
+    pub mentions: Vec<String>,
+    pub tickets: Vec<i64>,
+}
+
+/// The references `render` links: principals that exist, and tickets that do with
+/// their ticket group.
+#[derive(Debug, Default)]
+pub struct Autolinks {
+    pub principals: HashSet<String>,
+    pub tickets: HashMap<i64, Option<String>>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Reference<'a> {
+    Mention(&'a str),
+    Ticket { id: i64, prefix: Option<&'a str> },
+}
+
+impl Reference<'_> {
+    /// Where the reference links, if it resolves.
+    fn link(&self, links: &Autolinks) -> Option<(String, &'static str)> {
+        match *self {
+            Reference::Mention(name) if links.principals.contains(name) => {
+                Some((format!("/api/v1/search?q={}", name), "mention"))
+            }
+            Reference::Ticket { id, prefix } => match (links.tickets.get(&id), prefix) {
+                (Some(_), None) => Some((format!("/api/v1/tickets/{}", id), "ticket-ref")),
+                (Some(Some(group)), Some(prefix)) if group == prefix => {
+                    Some((format!("/api/v1/tickets/{}", id), "ticket-ref"))
+                }
+                _ => None,
+            },
+            Reference::Mention(_) => None,
+        }
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `#12` and `PREFIX-12` references in text, starting where a word would.
+fn ticket_spans(text: &str) -> Vec<(Range<usize>, Reference<'_>)> {
+    let mut spans = Vec::new();
+    let mut previous: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        let starts_word = previous.is_none_or(|p| !is_word(p));
+        previous = Some(c);
+        if !starts_word {
+            continue;
+        }
+        let rest = &text[i..];
+        let (prefix, digits_at) = if c == '#' {
+            (None, 1)
+        } else if c.is_ascii_alphabetic() {
+            let len = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+            if !rest[len..].starts_with('-') {
+                continue;
+            }
+            (Some(&rest[..len]), len + 1)
+        } else {
+            continue;
+        };
+        let digits = rest[digits_at..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - digits_at);
+        let end = digits_at + digits;
+        if digits == 0 || rest[end..].chars().next().is_some_and(is_word) {
+            continue;
+        }
+        if let Ok(id) = rest[digits_at..end].parse() {
+            spans.push((i..i + end, Reference::Ticket { id, prefix }));
+        }
+    }
+    spans
+}
+
+/// Mentions and ticket references in text, in order. A ticket reference inside a
+/// mention is part of the mention.
+fn references(text: &str) -> Vec<(Range<usize>, Reference<'_>)> {
+    let mut spans: Vec<(Range<usize>, Reference<'_>)> = mention_spans(text)
+        .into_iter()
+        .map(|span| (span.clone(), Reference::Mention(&text[span.start + 1..span.end])))
+        .collect();
+    let mentions: Vec<Range<usize>> = spans.iter().map(|(span, _)| span.clone()).collect();
+    spans.extend(
+        ticket_spans(text)
+            .into_iter()
+            .filter(|(span, _)| !mentions.iter().any(|m| m.start < span.end && span.start < m.end)),
+    );
+    spans.sort_by_key(|(span, _)| span.start);
+    spans
+}
+
+fn parser(markdown: &str) -> TextMergeStream<'_, Parser<'_>> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    TextMergeStream::new(Parser::new_ext(markdown, options))
+}
+
+/// Tracks whether text is outside code, links and images, where references link.
+/// Anchors written as raw HTML count as links.
+#[derive(Default)]
+struct Linkable {
+    depth: usize,
+}
+
+impl Linkable {
+    /// Takes the event into account, returns whether it is where references link.
+    fn enter(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::Link { .. } | Tag::Image { .. }) => self.depth += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::Link | TagEnd::Image) => self.depth -= 1,
+            Event::InlineHtml(html) if is_tag(html, "<a") => self.depth += 1,
+            Event::InlineHtml(html) if is_tag(html, "</a") => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        self.depth == 0
+    }
+}
+
+/// Whether the HTML is a tag starting with `open`, such as `<a` for `<a href="...">`.
+fn is_tag(html: &str, open: &str) -> bool {
+    html.get(..open.len()).is_some_and(|start| start.eq_ignore_ascii_case(open))
+        && html[open.len()..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+}
+
+/// The mentioned names and referenced ticket ids, each once, outside code and links.
+pub fn scan(markdown: &str) -> References {
+    let mut found = References::default();
+    let mut linkable = Linkable::default();
+    for event in parser(markdown) {
+        let in_scope = linkable.enter(&event);
+        let Event::Text(text) = event else {
+            continue;
+        };
+        if !in_scope {
+            continue;
+        }
+        for (_, reference) in references(&text) {
+            match reference {
+                Reference::Mention(name) if !found.mentions.iter().any(|m| m == name) => {
+                    found.mentions.push(name.to_string())
+                }
+                Reference::Ticket { id, .. } if !found.tickets.contains(&id) => found.tickets.push(id),
+                _ => {}
+            }
+        }
+    }
+    found
+}
+
+/// Renders markdown to sanitized HTML, linking the references that resolve.
+pub fn render(markdown: &str, links: &Autolinks) -> String {
+    let mut events: Vec<Event> = Vec::new();
+    let mut linkable = Linkable::default();
+    for event in parser(markdown) {
+        let in_scope = linkable.enter(&event);
+        match event {
+            Event::Text(text) if in_scope => push_linked(&text, links, &mut events),
+            event => events.push(event),
+        }
+    }
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events.into_iter());
+    sanitize(&rendered)
+}
+
+/// Pushes text as text events, its resolving references as links.
+fn push_linked(text: &str, links: &Autolinks, events: &mut Vec<Event>) {
+    let mut at = 0;
+    for (span, reference) in references(text) {
+        let Some((url, class)) = reference.link(links) else {
+            continue;
+        };
+        if at < span.start {
+            events.push(Event::Text(text[at..span.start].to_string().into()));
+        }
+        // Link events have no class, so the anchor is raw HTML
+        events.push(Event::InlineHtml(format!("<a class=\"{}\" href=\"{}\">", class, url).into()));
+        events.push(Event::Text(text[span.clone()].to_string().into()));
+        events.push(Event::InlineHtml("</a>".into()));
+        at = span.end;
+    }
+    if at < text.len() {
+        events.push(Event::Text(text[at..].to_string().into()));
+    }
+}
+
+/// Strips what isn't safe to show from HTML: scripts, styles, event handlers,
+/// unknown tags and links with schemes other than the usual ones.
+pub fn sanitize(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> Autolinks {
+        Autolinks {
+            principals: HashSet::from(["alice".to_string()]),
+            tickets: HashMap::from([(12, Some("OPS".to_string())), (7, None)]),
+        }
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let html = render("# Title\n\n**bold** ~~gone~~\n\n| a |\n|---|\n| b |", &Autolinks::default());
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<strong>bold</strong> <del>gone</del>"));
+        assert!(html.contains("<td>b</td>"));
+    }
+
+    #[test]
+    fn strips_dangerous_html() {
+        let html = render(
+            "<script>alert(1)</script>\n\n<img src=x onerror=alert(1)> [x](javascript:alert(1)) <b>ok</b>",
+            &Autolinks::default(),
+        );
+        assert!(!html.contains("<script") && !html.contains("alert"));
+        assert!(html.contains("<img src=\"x\">"));
+        assert!(html.contains("<a rel=\"noopener noreferrer\">x</a>"));
+        assert!(html.contains("<b>ok</b>"));
+    }
+
+    #[test]
+    fn links_references_that_resolve() {
+        let html = render("@alice see #12, OPS-12 and #7; not @bob, #13, WEB-12 or OPS-7", &links());
+        assert_eq!(
+            html,
+            "<p><a class=\"mention\" href=\"/api/v1/search?q=alice\" rel=\"noopener noreferrer\">@alice</a> see \
+             <a class=\"ticket-ref\" href=\"/api/v1/tickets/12\" rel=\"noopener noreferrer\">#12</a>, \
+             <a class=\"ticket-ref\" href=\"/api/v1/tickets/12\" rel=\"noopener noreferrer\">OPS-12</a> and \
+             <a class=\"ticket-ref\" href=\"/api/v1/tickets/7\" rel=\"noopener noreferrer\">#7</a>; \
+             not @bob, #13, WEB-12 or OPS-7</p>\n"
+        );
+    }
+
+    #[test]
+    fn leaves_code_and_links_alone() {
+        let html = render(
+            "`@alice` [#12](https://example.com) <A href=\"https://example.com\">#7</a>\n\n```\nOPS-12\n```",
+            &links(),
+        );
+        assert!(!html.contains("ticket-ref"));
+        assert!(!html.contains("mention"));
+    }
+
+    #[test]
+    fn scans_references() {
+        let found = scan("@alice #12 mail bob@example.com, OPS-12, abc#3 @alice `#4` X-5y");
+        assert_eq!(
+            found,
+            References {
+                mentions: vec!["alice".to_string()],
+                tickets: vec![12],
+            }
+        );
+    }
+}
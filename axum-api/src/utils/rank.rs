@@ -0,0 +1,89 @@
+//! Lexicographic ranks ordering tickets within a board column. A rank can always be
+//! made between two others, so moving a ticket only rewrites that ticket.
+//!
+//! Ranks are base-36 strings (`0-9a-z`) compared bytewise. They never end with `0`,
+//! which leaves room ahead of any of them.
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+fn digit(c: u8) -> usize {
+    DIGITS.iter().position(|d| *d == c).unwrap_or(0)
+}
+
+/// A rank sorting after `before` and ahead of `after`, an open end when `None`.
+/// `before` must sort ahead of `after`.
+pub fn between(before: Option<&str>, after: Option<&str>) -> String {
+    let mut rank = Vec::new();
+    midpoint(before.unwrap_or_default().as_bytes(), after.map(str::as_bytes), &mut rank);
+    String::from_utf8(rank).expect("ranks are ASCII")
+}
+
+fn midpoint(a: &[u8], b: Option<&[u8]>, out: &mut Vec<u8>) {
+    if let Some(b) = b {
+        // Shared leading digits are kept, `a` being padded with zeros
+        let shared = b
+            .iter()
+            .enumerate()
+            .take_while(|(i, d)| a.get(*i).copied().unwrap_or(b'0') == **d)
+            .count();
+        if shared > 0 {
+            out.extend_from_slice(&b[..shared]);
+            return midpoint(a.get(shared..).unwrap_or_default(), Some(&b[shared..]), out);
+        }
+    }
+    let low = a.first().map_or(0, |d| digit(*d));
+    let high = b.and_then(|b| b.first()).map_or(DIGITS.len(), |d| digit(*d));
+    if high - low > 1 {
+        out.push(DIGITS[(low + high) / 2]);
+        return;
+    }
+    match b {
+        // The first digit of `after` alone sorts ahead of it
+        Some(b) if b.len() > 1 => out.push(b[0]),
+        _ => {
+            out.push(DIGITS[low]);
+            midpoint(a.get(1..).unwrap_or_default(), None, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_between(before: Option<&str>, after: Option<&str>) -> String {
+        let rank = between(before, after);
+        assert!(before.is_none_or(|b| b < rank.as_str()), "{:?} < {}", before, rank);
+        assert!(after.is_none_or(|a| rank.as_str() < a), "{} < {:?}", rank, after);
+        assert!(!rank.ends_with('0'));
+        rank
+    }
+
+    #[test]
+    fn ranks_between_neighbours() {
+        assert_eq!(assert_between(None, None), "i");
+        assert_between(Some("i"), None);
+        assert_between(None, Some("i"));
+        assert_between(Some("a"), Some("b"));
+        assert_between(Some("a"), Some("a1"));
+        assert_between(Some("az"), Some("b"));
+        assert_between(Some("z"), None);
+        assert_between(None, Some("01"));
+        assert_between(Some("0001"), Some("0002"));
+    }
+
+    #[test]
+    fn repeated_inserts_stay_ordered() {
+        // Always dropping a ticket right below the first one
+        let first = between(None, None);
+        let mut next = between(Some(&first), None);
+        for _ in 0..200 {
+            next = assert_between(Some(&first), Some(&next));
+        }
+        // And always at the end
+        let mut last = first;
+        for _ in 0..200 {
+            last = assert_between(Some(&last), None);
+        }
+    }
+}
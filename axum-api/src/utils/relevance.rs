@@ -0,0 +1,48 @@
+//! Scoring of texts against the words of a search, for the in-memory database's
+//! search. `words` also splits queries for the embedded search index, which tokenizes
+//! its documents the same way.
+
+/// Lowercased words of a text, split on anything but letters and digits.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// How well weighted fields match the terms: each term adds the weight of the best
+/// field having it as a word, or half of it when it only starts one. `None` when a
+/// term is in no field.
+pub fn score(terms: &[String], fields: &[(&str, f64)]) -> Option<f64> {
+    let fields: Vec<(Vec<String>, f64)> = fields.iter().map(|(text, weight)| (words(text), *weight)).collect();
+    terms.iter().try_fold(0.0, |total, term| {
+        let best = fields
+            .iter()
+            .filter_map(|(words, weight)| {
+                if words.iter().any(|w| w == term) {
+                    Some(*weight)
+                } else if words.iter().any(|w| w.starts_with(term.as_str())) {
+                    Some(weight / 2.0)
+                } else {
+                    None
+                }
+            })
+            .reduce(f64::max)?;
+        Some(total + best)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_every_term() {
+        let terms = words("Printer JAM");
+        assert_eq!(terms, ["printer", "jam"]);
+        assert_eq!(score(&terms, &[("Printer jam", 3.0)]), Some(6.0));
+        assert_eq!(score(&terms, &[("Printers", 3.0), ("jam", 1.0)]), Some(2.5));
+        assert_eq!(score(&terms, &[("Printer", 3.0)]), None);
+        assert_eq!(score(&[], &[("Printer", 3.0)]), Some(0.0));
+    }
+}
@@ -0,0 +1,47 @@
+//! Word overlap between short texts such as ticket titles, used to spot duplicates.
+
+use std::collections::BTreeSet;
+
+// Too common in ticket titles to tell two apart
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "not", "when", "does", "doesn", "can", "cannot", "from", "after", "into", "on",
+    "in", "of", "to", "is", "a", "an", "it",
+];
+
+/// Lowercase words of the text, without punctuation, stop words and one-letter words.
+pub fn tokens(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() > 1 && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Jaccard index of the two texts' words, from 0 (nothing shared) to 1 (same words).
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (tokens(a), tokens(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_words() {
+        let words: Vec<String> = tokens("Login fails on Safari: 500 from /api/login!").into_iter().collect();
+        assert_eq!(words, ["500", "api", "fails", "login", "safari"]);
+        assert!(tokens("a - the").is_empty());
+    }
+
+    #[test]
+    fn scores_overlap() {
+        assert_eq!(similarity("Login fails on Safari", "safari: login FAILS"), 1.0);
+        assert_eq!(similarity("Login fails", "Export is slow"), 0.0);
+        assert_eq!(similarity("Login fails on Safari", "Login fails on Firefox"), 0.5);
+        assert_eq!(similarity("", "the"), 0.0);
+    }
+}
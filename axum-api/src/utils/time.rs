@@ -0,0 +1,110 @@
+//! Timestamps and the users' timezones.
+//!
+//! Instants are stored, accepted and returned in UTC as RFC 3339. Dates without a
+//! time, such as due dates, are days of whoever set them: when a due date ends or a
+//! reminder goes off depends on the user's timezone, UTC unless they set one.
+
+use chrono::{DateTime, Days, LocalResult, NaiveDate, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::{error::AppError, models::User};
+
+/// Formats an instant the way JSON responses carry them, e.g. `2026-03-01T08:30:00Z`.
+pub fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses an IANA timezone name, e.g. `Europe/Kyiv`.
+pub fn parse_timezone(name: &str) -> Result<Tz, AppError> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        AppError::Validation(format!("Unknown timezone '{}', expected an IANA name such as Europe/Kyiv", name))
+    })
+}
+
+/// The user's timezone, UTC if they have none or it is no longer known.
+pub fn user_timezone(user: &User) -> Tz {
+    user.personal
+        .timezone
+        .as_deref()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// The date it is at `now` in the timezone.
+pub fn today(tz: Tz, now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&tz).date_naive()
+}
+
+/// The instant the wall clock of the timezone shows `time` on `date`. A time skipped
+/// by a DST change is taken as the first one after it, a repeated one as the earlier.
+pub fn local_instant(date: NaiveDate, time: NaiveTime, tz: Tz) -> DateTime<Utc> {
+    let mut local = date.and_time(time);
+    loop {
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => return at.to_utc(),
+            // Gaps are at most a few hours, a minute at a time gets out of them
+            LocalResult::None => local += chrono::TimeDelta::minutes(1),
+        }
+    }
+}
+
+/// The instant a due date ends in the timezone, the start of the next day there.
+pub fn due_at(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    local_instant(date + Days::new(1), NaiveTime::MIN, tz)
+}
+
+/// Whether a due date is over at `now` in the timezone.
+pub fn is_overdue(date: NaiveDate, tz: Tz, now: DateTime<Utc>) -> bool {
+    due_at(date, tz) <= now
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(at: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(at).unwrap().to_utc()
+    }
+
+    #[test]
+    fn formats_as_utc() {
+        assert_eq!(rfc3339(utc("2026-03-01T10:30:00+02:00")), "2026-03-01T08:30:00Z");
+        assert_eq!(
+            rfc3339(utc("2026-03-01T08:30:00.25Z")),
+            serde_json::to_value(utc("2026-03-01T08:30:00.25Z")).unwrap()
+        );
+    }
+
+    #[test]
+    fn parses_iana_names() {
+        assert_eq!(parse_timezone("Europe/Kyiv").unwrap(), Tz::Europe__Kyiv);
+        assert!(matches!(parse_timezone("Mars/Olympus"), Err(AppError::Validation(_))));
+        assert!(matches!(parse_timezone("+02:00"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn due_dates_end_at_local_midnight() {
+        let kyiv = Tz::Europe__Kyiv;
+        assert_eq!(due_at(date(2026, 1, 15), Tz::UTC), utc("2026-01-16T00:00:00Z"));
+        assert_eq!(due_at(date(2026, 1, 15), kyiv), utc("2026-01-15T22:00:00Z"));
+        assert_eq!(due_at(date(2026, 7, 15), kyiv), utc("2026-07-15T21:00:00Z"));
+
+        let now = utc("2026-01-15T23:00:00Z");
+        assert_eq!(today(kyiv, now), date(2026, 1, 16));
+        assert!(is_overdue(date(2026, 1, 15), kyiv, now));
+        assert!(!is_overdue(date(2026, 1, 15), Tz::UTC, now));
+    }
+
+    #[test]
+    fn skips_dst_gaps() {
+        // Clocks in Kyiv go from 03:00 to 04:00 on the last Sunday of March
+        let at = local_instant(date(2026, 3, 29), NaiveTime::from_hms_opt(3, 30, 0).unwrap(), Tz::Europe__Kyiv);
+        assert_eq!(at, utc("2026-03-29T01:00:00Z"));
+        let at = local_instant(date(2026, 10, 25), NaiveTime::from_hms_opt(3, 30, 0).unwrap(), Tz::Europe__Kyiv);
+        assert_eq!(at, utc("2026-10-25T00:30:00Z"));
+    }
+}
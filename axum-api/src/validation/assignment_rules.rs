@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::{
+    models::{AssignmentCondition, AssignmentRule, CustomFieldType, Project},
+    validation::custom_fields::value_matches,
+};
+
+/// Checks a project's assignment rules: names are unique and conditions refer to
+/// levels of the project's severity scale and to its custom fields. Whether the
+/// principals assigned exist is up to the caller.
+pub fn validate_rules(project: &Project, rules: &[AssignmentRule]) -> Result<(), String> {
+    let scale = project.severity_scale();
+    let mut names = HashSet::new();
+    for rule in rules {
+        let name = rule.name.trim();
+        if name.is_empty() {
+            return Err("Rule names can't be empty".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("Rule '{}' is defined twice", name));
+        }
+        match &rule.when {
+            AssignmentCondition::Any => {}
+            AssignmentCondition::Severity { levels } => {
+                if levels.is_empty() {
+                    return Err(format!("Rule '{}' matches no severity", name));
+                }
+                if let Some(level) = levels.iter().find(|l| !scale.iter().any(|s| s.level == **l)) {
+                    return Err(format!("Rule '{}': severity {} is not on the project's scale", name, level));
+                }
+            }
+            AssignmentCondition::Label { field, value } => {
+                let Some(definition) = project.custom_fields.iter().find(|d| &d.name == field) else {
+                    return Err(format!("Rule '{}': unknown field '{}'", name, field));
+                };
+                if definition.kind == CustomFieldType::Select && !definition.options.contains(value) {
+                    return Err(format!("Rule '{}': '{}' is not an option of '{}'", name, value, field));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a ticket with this severity level and custom field values meets the condition.
+pub fn condition_matches(condition: &AssignmentCondition, severity: u8, fields: &HashMap<String, Value>) -> bool {
+    match condition {
+        AssignmentCondition::Any => true,
+        AssignmentCondition::Severity { levels } => levels.contains(&severity),
+        AssignmentCondition::Label { field, value } => fields.get(field).is_some_and(|v| value_matches(v, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        models::{AssignmentTarget, CustomFieldDefinition},
+        test::app::sample_project,
+    };
+
+    fn rule(name: &str, when: AssignmentCondition) -> AssignmentRule {
+        AssignmentRule {
+            name: name.to_string(),
+            when,
+            assign: AssignmentTarget::Principal {
+                principal: "alice".to_string(),
+            },
+        }
+    }
+
+    fn label(field: &str, value: &str) -> AssignmentCondition {
+        AssignmentCondition::Label {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn checks_rules() {
+        let mut project = sample_project(&[]);
+        project.custom_fields.push(CustomFieldDefinition {
+            name: "component".to_string(),
+            kind: CustomFieldType::Select,
+            required: false,
+            options: vec!["ui".to_string(), "db".to_string()],
+        });
+        let valid = [
+            rule("critical", AssignmentCondition::Severity { levels: vec![1, 2] }),
+            rule("ui", label("component", "ui")),
+            rule("rest", AssignmentCondition::Any),
+        ];
+        assert!(validate_rules(&project, &valid).is_ok());
+
+        assert!(validate_rules(&project, &[rule(" ", AssignmentCondition::Any)]).is_err());
+        let twice = [rule("a", AssignmentCondition::Any), rule("a ", AssignmentCondition::Any)];
+        assert!(validate_rules(&project, &twice).is_err());
+        let off_scale = rule("a", AssignmentCondition::Severity { levels: vec![9] });
+        assert!(validate_rules(&project, &[off_scale]).is_err());
+        let none = rule("a", AssignmentCondition::Severity { levels: vec![] });
+        assert!(validate_rules(&project, &[none]).is_err());
+        assert!(validate_rules(&project, &[rule("a", label("team", "ui"))]).is_err());
+        assert!(validate_rules(&project, &[rule("a", label("component", "api"))]).is_err());
+    }
+
+    #[test]
+    fn matches_conditions() {
+        let fields: HashMap<String, Value> = serde_json::from_value(json!({"component": "ui", "version": 2})).unwrap();
+        assert!(condition_matches(&AssignmentCondition::Any, 4, &fields));
+        assert!(condition_matches(&AssignmentCondition::Severity { levels: vec![1, 2] }, 2, &fields));
+        assert!(!condition_matches(&AssignmentCondition::Severity { levels: vec![1] }, 2, &fields));
+        assert!(condition_matches(&label("component", "ui"), 3, &fields));
+        assert!(condition_matches(&label("version", "2"), 3, &fields));
+        assert!(!condition_matches(&label("component", "db"), 3, &fields));
+        assert!(!condition_matches(&label("team", "ui"), 3, &fields));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::models::{CustomFieldDefinition, CustomFieldType};
+
+const MAX_NAME_LENGTH: usize = 40;
+
+/// Checks a project's field definitions: names are unique lowercase identifiers,
+/// `select` fields list their options and other fields have none.
+pub fn validate_definitions(definitions: &[CustomFieldDefinition]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for definition in definitions {
+        let name = &definition.name;
+        let valid_name = !name.is_empty()
+            && name.len() <= MAX_NAME_LENGTH
+            && name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "Field name '{}' must be up to {} lowercase letters, digits or '_', starting with a letter",
+                name, MAX_NAME_LENGTH
+            ));
+        }
+        if !names.insert(name) {
+            return Err(format!("Field '{}' is defined twice", name));
+        }
+        match definition.kind {
+            CustomFieldType::Select if definition.options.is_empty() => {
+                return Err(format!("Select field '{}' needs options", name));
+            }
+            CustomFieldType::Select => {}
+            _ if !definition.options.is_empty() => {
+                return Err(format!("Only select fields have options, '{}' is not one", name));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks ticket values against the definitions: every field is defined, has a value
+/// of its type and required ones are present. `null` counts as absent.
+pub fn validate_values(
+    definitions: &[CustomFieldDefinition],
+    values: &HashMap<String, Value>,
+) -> Result<(), String> {
+    for (name, value) in values {
+        let Some(definition) = definitions.iter().find(|d| &d.name == name) else {
+            return Err(format!("Unknown field '{}'", name));
+        };
+        let valid = match (definition.kind, value) {
+            (_, Value::Null) => true,
+            (CustomFieldType::Text, Value::String(_)) => true,
+            (CustomFieldType::Number, Value::Number(_)) => true,
+            (CustomFieldType::Boolean, Value::Bool(_)) => true,
+            (CustomFieldType::Date, Value::String(s)) => NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+            (CustomFieldType::Select, Value::String(s)) => definition.options.contains(s),
+            _ => false,
+        };
+        if !valid {
+            let expected = match definition.kind {
+                CustomFieldType::Text => "a string".to_string(),
+                CustomFieldType::Number => "a number".to_string(),
+                CustomFieldType::Boolean => "true or false".to_string(),
+                CustomFieldType::Date => "a YYYY-MM-DD date".to_string(),
+                CustomFieldType::Select => format!("one of {}", definition.options.join(", ")),
+            };
+            return Err(format!("Field '{}' must be {}", name, expected));
+        }
+    }
+    for definition in definitions.iter().filter(|d| d.required) {
+        if values.get(&definition.name).is_none_or(Value::is_null) {
+            return Err(format!("Field '{}' is required", definition.name));
+        }
+    }
+    Ok(())
+}
+
+/// Parses a `?custom=name:value,name:value` filter.
+pub fn parse_filter(raw: &str) -> Result<Vec<(String, String)>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once(':') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!("Expected 'name:value', got '{}'", pair)),
+        })
+        .collect()
+}
+
+/// Whether a stored value equals one given as text in a query, like `?custom=version:2`.
+pub fn value_matches(value: &Value, expected: &str) -> bool {
+    match value {
+        Value::String(s) => s == expected,
+        Value::Number(_) | Value::Bool(_) => serde_json::from_str::<Value>(expected).is_ok_and(|v| &v == value),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str, kind: CustomFieldType, required: bool, options: &[&str]) -> CustomFieldDefinition {
+        CustomFieldDefinition {
+            name: name.to_string(),
+            kind,
+            required,
+            options: options.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    fn definitions() -> Vec<CustomFieldDefinition> {
+        vec![
+            field("environment", CustomFieldType::Select, true, &["staging", "production"]),
+            field("customer", CustomFieldType::Text, false, &[]),
+            field("due", CustomFieldType::Date, false, &[]),
+        ]
+    }
+
+    fn values(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn checks_definitions() {
+        assert!(validate_definitions(&definitions()).is_ok());
+        assert!(validate_definitions(&[field("Env", CustomFieldType::Text, false, &[])]).is_err());
+        assert!(validate_definitions(&[field("env", CustomFieldType::Select, false, &[])]).is_err());
+        assert!(validate_definitions(&[field("env", CustomFieldType::Text, false, &["a"])]).is_err());
+        let twice = [field("env", CustomFieldType::Text, false, &[]), field("env", CustomFieldType::Number, false, &[])];
+        assert!(validate_definitions(&twice).is_err());
+    }
+
+    #[test]
+    fn checks_values() {
+        let defs = definitions();
+        assert!(validate_values(&defs, &values(json!({"environment": "staging", "due": "2026-01-31"}))).is_ok());
+        assert!(validate_values(&defs, &values(json!({"environment": "qa"}))).is_err());
+        assert!(validate_values(&defs, &values(json!({"environment": "staging", "due": "31.01.2026"}))).is_err());
+        assert!(validate_values(&defs, &values(json!({"environment": "staging", "customer": 7}))).is_err());
+        assert!(validate_values(&defs, &values(json!({"environment": "staging", "team": "a"}))).is_err());
+        // Required
+        assert!(validate_values(&defs, &values(json!({"customer": "acme"}))).is_err());
+        assert!(validate_values(&defs, &values(json!({"environment": null}))).is_err());
+    }
+
+    #[test]
+    fn parses_filters() {
+        let r = parse_filter("environment:staging, version : 2,").unwrap();
+        assert_eq!(r, vec![("environment".into(), "staging".into()), ("version".into(), "2".into())]);
+        assert!(parse_filter("environment").is_err());
+        assert!(parse_filter(":staging").is_err());
+    }
+
+    #[test]
+    fn matches_query_values() {
+        assert!(value_matches(&json!("acme"), "acme"));
+        assert!(value_matches(&json!(2), "2"));
+        assert!(value_matches(&json!(true), "true"));
+        assert!(!value_matches(&json!(null), "null"));
+    }
+}
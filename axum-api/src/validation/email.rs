@@ -0,0 +1,40 @@
+use crate::validation::*;
+
+/// Validates and lowercases an email, optionally restricting it to a set of domains.
+pub fn validate_email_address(email: &str, allowed_domains: &[String]) -> Result<String, String> {
+    let lowercased = force_lowercase()(email.trim());
+    let validators: Vec<ValidatorFn> = vec![limit_length(254), validate_email()];
+    run_validators(&lowercased, &validators)?;
+
+    if !allowed_domains.is_empty() {
+        let domain = lowercased.rsplit('@').next().unwrap_or_default();
+        if !allowed_domains.iter().any(|d| d == domain) {
+            return Err(format!("Email domain '{}' is not allowed", domain));
+        }
+    }
+    Ok(lowercased)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_email_any_domain() {
+        let r = validate_email_address(" John@Example.COM ", &[]).unwrap();
+        assert_eq!(r, "john@example.com");
+    }
+
+    #[test]
+    fn invalid_email() {
+        assert!(validate_email_address("john.example.com", &[]).is_err());
+    }
+
+    #[test]
+    fn domain_allowlist() {
+        let allowed = vec!["example.com".to_string()];
+        assert!(validate_email_address("john@example.com", &allowed).is_ok());
+        assert!(validate_email_address("john@evil.com", &allowed).is_err());
+        assert!(validate_email_address("john@sub.example.com", &allowed).is_err());
+    }
+}
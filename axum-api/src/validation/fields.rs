@@ -0,0 +1,36 @@
+/// Parses a `fields=a,b,c` selection, rejecting names outside `allowed`.
+/// Duplicates are dropped, order is kept.
+pub fn validate_fields(raw: &str, allowed: &[&str]) -> Result<Vec<String>, String> {
+    let mut fields: Vec<String> = Vec::new();
+    for field in raw.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !allowed.contains(&field) {
+            return Err(format!("Unknown field '{}'", field));
+        }
+        if !fields.iter().any(|f| f == field) {
+            fields.push(field.to_string());
+        }
+    }
+    if fields.is_empty() {
+        return Err("At least one field must be selected".to_string());
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: &[&str] = &["title", "severity", "assigned_to"];
+
+    #[test]
+    fn selects_known_fields() {
+        let r = validate_fields(" title,severity ,title,", ALLOWED).unwrap();
+        assert_eq!(r, vec!["title", "severity"]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(validate_fields("title,_key", ALLOWED).is_err());
+        assert!(validate_fields(",,", ALLOWED).is_err());
+    }
+}
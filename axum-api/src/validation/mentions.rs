@@ -0,0 +1,76 @@
+use std::ops::Range;
+
+/// Extracts `@name` mentions from free text, in order of first appearance.
+/// A mention starts at an `@` that does not follow a word character (so email
+/// addresses are skipped) and runs over letters, digits, `_`, `-` and `.`;
+/// trailing punctuation is not part of it.
+pub fn parse_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for span in mention_spans(text) {
+        let name = &text[span.start + 1..span.end];
+        if !mentions.iter().any(|m| m == name) {
+            mentions.push(name.to_string());
+        }
+    }
+    mentions
+}
+
+/// Where each mention of `parse_mentions` is in the text, `@` included, repeated
+/// mentions every time.
+pub fn mention_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let after_word = previous.is_some_and(|p| p.is_alphanumeric() || p == '_');
+        previous = Some(c);
+        if c != '@' || after_word {
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while let Some(&(j, n)) = chars.peek() {
+            if !(n.is_alphanumeric() || matches!(n, '_' | '-' | '.')) {
+                break;
+            }
+            end = j + n.len_utf8();
+            previous = Some(n);
+            chars.next();
+        }
+        let name = text[start..end].trim_end_matches(['.', '-']);
+        if !name.is_empty() {
+            spans.push(i..start + name.len());
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_mentions() {
+        let r = parse_mentions("@alice please check with @qa-team and @bob.");
+        assert_eq!(r, vec!["alice", "qa-team", "bob"]);
+    }
+
+    #[test]
+    fn skips_emails_and_bare_at() {
+        let r = parse_mentions("mail bob@example.com or ping @ anyone, @@carol");
+        assert_eq!(r, vec!["carol"]);
+    }
+
+    #[test]
+    fn deduplicates() {
+        let r = parse_mentions("(@alice) @alice, @alice!");
+        assert_eq!(r, vec!["alice"]);
+    }
+
+    #[test]
+    fn spans_every_mention() {
+        let text = "(@alice) @alice, @qa-team.";
+        let spans: Vec<&str> = mention_spans(text).into_iter().map(|s| &text[s]).collect();
+        assert_eq!(spans, vec!["@alice", "@alice", "@qa-team"]);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::validation::*;
+
+pub const MAX_KEY_LENGTH: usize = 64;
+pub const MAX_VALUE_BYTES: usize = 1024;
+pub const MAX_KEYS: usize = 50; // per user
+
+/// Metadata keys are lowercased, start with a letter and hold letters, digits, `_`,
+/// `-` and `.`, so they can be matched in queries as typed.
+pub fn validate_key(key: &str) -> Result<String, String> {
+    let lowercased = force_lowercase()(key.trim());
+    let validators: Vec<ValidatorFn> = vec![
+        limit_length(MAX_KEY_LENGTH),
+        limit_min_length(1),
+        allow_only_alphanumerics_and_specials(Some("_-.")),
+        not_start_with_digit(),
+    ];
+    run_validators(&lowercased, &validators).map_err(|e| format!("Metadata key '{}': {}", key, e))?;
+    Ok(lowercased)
+}
+
+pub fn validate_value(key: &str, value: &str) -> Result<(), String> {
+    if value.len() > MAX_VALUE_BYTES {
+        return Err(format!(
+            "Metadata value of '{}' is {} bytes, maximum is {}",
+            key,
+            value.len(),
+            MAX_VALUE_BYTES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_lowercased() {
+        assert_eq!(validate_key(" Beta.Program ").unwrap(), "beta.program");
+        assert_eq!(validate_key("import-source_2").unwrap(), "import-source_2");
+    }
+
+    #[test]
+    fn rejects_bad_keys() {
+        assert!(validate_key("").is_err());
+        assert!(validate_key("2fa").is_err());
+        assert!(validate_key("a b").is_err());
+        assert!(validate_key(&"k".repeat(MAX_KEY_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn values_are_limited_in_bytes() {
+        assert!(validate_value("note", &"a".repeat(MAX_VALUE_BYTES)).is_ok());
+        // 2 bytes each
+        assert!(validate_value("note", &"é".repeat(MAX_VALUE_BYTES / 2 + 1)).is_err());
+    }
+}
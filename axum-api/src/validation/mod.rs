@@ -1,3 +1,9 @@
+pub mod assignment_rules;
+pub mod custom_fields;
+pub mod email;
+pub mod fields;
+pub mod mentions;
+pub mod metadata;
 pub mod naming;
 
 use std::collections::HashSet;
@@ -12,6 +12,21 @@ pub fn validate_username(username: &str) -> Result<String, String> {
     Ok(lowercased)
 }
 
+/// Slugs name things in URLs, e.g. a project's public portal.
+pub fn validate_slug(slug: &str) -> Result<String, String> {
+    let lowercased = force_lowercase()(slug.trim());
+    let validators: Vec<ValidatorFn> = vec![
+            limit_length(40),
+            limit_min_length(3),
+            allow_only_alphanumerics_and_specials(Some("-")),
+        ];
+    run_validators(&lowercased, &validators)?;
+    if lowercased.starts_with('-') || lowercased.ends_with('-') {
+        return Err("Slug cannot start or end with '-'.".to_string());
+    }
+    Ok(lowercased)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -26,17 +41,17 @@ mod tests {
     fn too_long() {
         // 26 chars
         let name = "abcdefghijklmnopqrstuvwxyzz";
-        let err = validate_username(name).unwrap_err();
+        validate_username(name).unwrap_err();
     }
 
     #[test]
     fn invalid_characters() {
-        let err = validate_username("john*doe").unwrap_err();
+        validate_username("john*doe").unwrap_err();
     }
 
     #[test]
     fn starts_with_digit() {
-        let err = validate_username("1abc").unwrap_err();
+        validate_username("1abc").unwrap_err();
     }
 
     #[test]
@@ -44,4 +59,12 @@ mod tests {
         let r = validate_username("abcXYZ").unwrap();
         assert_eq!(r, "abcxyz");
     }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(validate_slug(" Acme-Support ").unwrap(), "acme-support");
+        assert!(validate_slug("ab").is_err());
+        assert!(validate_slug("acme support").is_err());
+        assert!(validate_slug("-acme").is_err());
+    }
 }